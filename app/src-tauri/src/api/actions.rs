@@ -0,0 +1,263 @@
+use lazy_static::lazy_static;
+use serde_json::json;
+
+use crate::error::LogicError;
+use crate::etcd;
+use crate::transport::action::{ActionCapability, ActionDef, ActionInfo, ActionParam, ActionParamType};
+use crate::utils::value_plugin;
+
+use ActionCapability::{Root, Session, Write};
+use ActionParamType::{Boolean, Dir, File, Key, Number};
+
+lazy_static! {
+    static ref ACTIONS: Vec<ActionDef> = builtin_actions();
+}
+
+fn param(name: &str, param_type: ActionParamType) -> ActionParam {
+    ActionParam {
+        name: String::from(name),
+        param_type,
+        required: true,
+    }
+}
+
+fn optional(name: &str, param_type: ActionParamType) -> ActionParam {
+    ActionParam {
+        required: false,
+        ..param(name, param_type)
+    }
+}
+
+fn action(
+    id: &str,
+    title: &str,
+    category: &str,
+    command: &str,
+    params: Vec<ActionParam>,
+    capabilities: Vec<ActionCapability>,
+) -> ActionDef {
+    ActionDef {
+        id: String::from(id),
+        title: String::from(title),
+        category: String::from(category),
+        command: String::from(command),
+        params,
+        capabilities,
+        args: None,
+    }
+}
+
+/// 内置操作，参数名为命令参数的驼峰命名，`session` 参数由调用方根据当前连接传入，不在此列出
+fn builtin_actions() -> Vec<ActionDef> {
+    vec![
+        action("kv.get", "Get Key", "kv", "kv_get", vec![param("key", Key)], vec![Session]),
+        action(
+            "kv.history",
+            "Show Key History",
+            "kv",
+            "kv_get_history_versions",
+            vec![param("key", Key), param("start", Number), param("end", Number)],
+            vec![Session],
+        ),
+        action("kv.delete", "Delete Keys", "kv", "kv_delete", vec![param("keys", ActionParamType::Json)], vec![Session, Write]),
+        action(
+            "kv.attachLease",
+            "Attach Prefix to New Lease",
+            "kv",
+            "kv_attach_prefix_lease",
+            vec![param("prefix", Key), param("ttl", Number)],
+            vec![Session, Write],
+        ),
+        action(
+            "kv.putFromFile",
+            "Upload File to Key",
+            "kv",
+            "kv_put_from_file",
+            vec![param("key", Key), param("filepath", File), param("compress", Boolean), optional("ttl", Number)],
+            vec![Session, Write],
+        ),
+        action(
+            "kv.saveToFile",
+            "Save Key to File",
+            "kv",
+            "kv_save_to_file",
+            vec![param("key", Key), param("filepath", File), param("decompress", Boolean)],
+            vec![Session],
+        ),
+        action(
+            "kv.syncPull",
+            "Pull Prefix to Directory",
+            "kv",
+            "kv_sync_pull",
+            vec![param("prefix", Key), param("dir", Dir), param("force", Boolean)],
+            vec![Session],
+        ),
+        action("kv.syncPush", "Push Directory to Etcd", "kv", "kv_sync_push", vec![param("dir", Dir)], vec![Session, Write]),
+        action(
+            "kv.importJson",
+            "Import JSON File",
+            "kv",
+            "kv_import_json",
+            vec![param("filepath", File), optional("prefix", Key)],
+            vec![Session, Write],
+        ),
+        action(
+            "kv.exportHistory",
+            "Export Prefix History",
+            "kv",
+            "kv_export_history",
+            vec![param("prefix", Key), param("filepath", File), param("compress", Boolean)],
+            vec![Session],
+        ),
+        action(
+            "kv.setReadRevision",
+            "Read at Revision",
+            "kv",
+            "set_read_revision",
+            vec![optional("revision", Number)],
+            vec![Session],
+        ),
+        action("lease.list", "List Leases", "lease", "leases", vec![], vec![Session]),
+        action(
+            "lease.grant",
+            "Grant Lease",
+            "lease",
+            "lease_grant",
+            vec![param("ttl", Number), optional("lease", ActionParamType::String)],
+            vec![Session, Write],
+        ),
+        action(
+            "lease.revoke",
+            "Revoke Lease",
+            "lease",
+            "lease_revoke",
+            vec![param("lease", ActionParamType::String)],
+            vec![Session, Write],
+        ),
+        action("cluster.info", "Show Cluster", "cluster", "get_cluster", vec![], vec![Session]),
+        action(
+            "cluster.report",
+            "Generate Cluster Report",
+            "cluster",
+            "generate_cluster_report",
+            vec![param("filepath", File), optional("format", ActionParamType::String)],
+            vec![Session],
+        ),
+        action(
+            "maintenance.defragment",
+            "Defragment",
+            "maintenance",
+            "maintenance_defragment",
+            vec![optional("timeoutSeconds", Number)],
+            vec![Session, Write, Root],
+        ),
+        action(
+            "maintenance.snapshot",
+            "Create Snapshot",
+            "maintenance",
+            "maintenance_create_snapshot_task",
+            vec![param("filepath", File)],
+            vec![Session, Root],
+        ),
+        action("auth.users", "List Users", "auth", "user_list", vec![], vec![Session, Root]),
+        action("auth.roles", "List Roles", "auth", "role_list", vec![], vec![Session, Root]),
+        action("auth.identity", "Show Session Identity", "auth", "get_session_identity", vec![], vec![Session]),
+        action("auth.enable", "Enable Authentication", "auth", "auth_enable", vec![], vec![Session, Write, Root]),
+        action("auth.disable", "Disable Authentication", "auth", "auth_disable", vec![], vec![Session, Write, Root]),
+        action("connection.disconnect", "Disconnect", "connection", "disconnect", vec![], vec![Session]),
+        action("connection.export", "Export Connections", "connection", "export_connection", vec![param("filepath", File)], vec![]),
+        action("connection.import", "Import Connections", "connection", "import_connection", vec![param("filepath", File)], vec![]),
+        action(
+            "workspace.export",
+            "Export Workspace",
+            "settings",
+            "export_workspace",
+            vec![param("filepath", File), optional("sharePassword", ActionParamType::String)],
+            vec![],
+        ),
+        action(
+            "workspace.import",
+            "Import Workspace",
+            "settings",
+            "import_workspace",
+            vec![
+                param("filepath", File),
+                optional("sharePassword", ActionParamType::String),
+                param("overwrite", Boolean),
+                param("importSettings", Boolean),
+            ],
+            vec![],
+        ),
+        action("settings.reset", "Reset Settings", "settings", "reset_settings", vec![], vec![]),
+        action("settings.usageStats", "Show Usage Statistics", "settings", "get_usage_stats", vec![], vec![]),
+        action("plugin.reload", "Reload Plugins", "plugin", "plugin_reload", vec![], vec![]),
+    ]
+}
+
+/// 已加载插件提供的命令，通过 `plugin_run_command` 执行
+fn plugin_actions() -> Vec<ActionDef> {
+    value_plugin::list()
+        .into_iter()
+        .filter(|p| p.enabled && p.loaded)
+        .flat_map(|p| {
+            let write = p.allow_write;
+            p.commands.into_iter().map(move |c| ActionDef {
+                id: format!("plugin.{}.{}", p.name, c.name),
+                title: c.title,
+                category: String::from("plugin"),
+                command: String::from("plugin_run_command"),
+                params: vec![optional("args", ActionParamType::Json)],
+                capabilities: if write { vec![Session, Write] } else { vec![Session] },
+                args: Some(json!({"plugin": p.name, "command": c.name})),
+            })
+        })
+        .collect()
+}
+
+/// 检查操作所需的能力，返回不可用的原因
+fn check(action: &ActionDef, session: Option<i32>, root: Option<bool>) -> Option<String> {
+    for capability in &action.capabilities {
+        let reason = match capability {
+            Session if session.map(|s| etcd::get_connector_optional(&s).is_none()).unwrap_or(true) => {
+                "An open connection is required"
+            }
+            Write if session.map(|s| etcd::check_writable(&s).is_err()).unwrap_or(false) => "The connection is read-only",
+            Root if root == Some(false) => "Only root users can perform this operation",
+            _ => continue,
+        };
+        return Some(String::from(reason));
+    }
+    None
+}
+
+/// 列出所有可调用的操作，传入连接时根据连接状态标记是否可用
+#[tauri::command]
+pub async fn list_actions(session: Option<i32>) -> Result<Vec<ActionInfo>, LogicError> {
+    //  未开启认证时视为root，查询失败视为非root
+    let root = match session {
+        Some(session) => {
+            let user = etcd::get_connection_config(&session).and_then(|c| c.user.as_ref().map(|u| u.username.clone()));
+            match (user, etcd::get_connector_optional(&session)) {
+                (Some(user), Some(mut connector)) => Some(connector.user_is_root(&user).await.unwrap_or(false)),
+                (None, Some(_)) => Some(true),
+                _ => None,
+            }
+        }
+        None => None,
+    };
+
+    let result = ACTIONS
+        .iter()
+        .cloned()
+        .chain(plugin_actions())
+        .map(|action| {
+            let unavailable_reason = check(&action, session, root);
+            ActionInfo {
+                available: unavailable_reason.is_none(),
+                unavailable_reason,
+                action,
+            }
+        })
+        .collect();
+    Ok(result)
+}
@@ -1,24 +1,62 @@
+use std::collections::BTreeMap;
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Write};
 use std::path::Path;
+use std::time::Duration;
 use std::{fs, io, vec};
 
 use base64::prelude::BASE64_STANDARD;
 use base64::Engine;
 use log::{debug, info, warn};
 use tauri::Window;
+use uuid::Uuid;
 
 use crate::error::LogicError;
 use crate::etcd;
 use crate::etcd::etcd_connector::EtcdConnector;
 use crate::etcd::key_monitor::KeyMonitor;
-use crate::transport::connection::{Connection, ConnectionInfo, KeyMonitorConfig, SessionData};
-use crate::utils::{aes_util, file_util, md5};
+use crate::etcd::{credential_prompt, poll_scheduler, session_lock, shared_annotation};
+use crate::etcd::unix_proxy::UnixSocketProxy;
+use crate::ssh::ssh_algorithm;
+use crate::ssh::ssh_config::SshConfigFile;
+use crate::ssh::ssh_tunnel::SshTunnel;
+use crate::transport::connection::{
+    CachedState, Connection, ConnectionInfo, ConnectionTlsInfo, EndpointAddress, ExternalConnection, ExternalConnectionSource, ExternalImportResult, KeyAnnotation,
+    KeyBookmarks, KeyMonitorConfig, NotificationRule, ServerCertificate, SessionData, SharedAnnotationWriteResult, SharedAnnotations,
+    SshAlgorithms, TunnelDiagnostics,
+};
+use crate::transport::settings::{validate_poll_intervals, PollTask};
+use crate::utils::{aes_util, cert_util, conn_import, conn_share, file_util, fuzzy, md5, state_cache, template};
 
 use super::settings::get_settings;
 
+/// 连接继承的最大层数
+const MAX_EXTENDS_DEPTH: usize = 8;
+
+/// 沿 `extends` 逐级合并基础连接配置，基础配置不存在或存在循环继承时返回错误
+pub async fn resolve_connection(mut connection: Connection) -> Result<Connection, LogicError> {
+    let mut visited: Vec<String> = vec![];
+    while let Some(base_name) = connection.extends.clone() {
+        if visited.contains(&base_name) || visited.len() >= MAX_EXTENDS_DEPTH {
+            return Err(LogicError::IllegalArgument(format!(
+                "Circular or too deep connection inheritance: {}",
+                base_name
+            )));
+        }
+        let base = get_connection(base_name.clone()).await?.ok_or_else(|| {
+            LogicError::IllegalArgument(format!("Base connection does not exist: {}", base_name))
+        })?;
+        connection.inherit(&base.connection);
+        visited.push(base_name);
+    }
+    Ok(connection)
+}
+
 #[tauri::command]
-pub async fn connect_test(connection: Connection) -> Result<(), LogicError> {
+pub async fn connect_test(connection: Connection, window: Window) -> Result<(), LogicError> {
+    session_lock::ensure_unlocked()?;
+    let mut connection = resolve_connection(connection).await?;
+    credential_prompt::fill_credentials(&window, "", &mut connection).await?;
     let connector = EtcdConnector::new(connection).await?;
     connector.test_connection().await?;
     Ok(())
@@ -31,6 +69,12 @@ pub async fn connect(name: String, connection: Connection, window: Window) -> Re
     Ok(session)
 }
 
+/// 回复连接时的凭据请求，`value` 为空表示取消输入
+#[tauri::command]
+pub fn respond_credential_request(id: String, value: Option<String>) -> Result<(), LogicError> {
+    credential_prompt::respond(&id, value)
+}
+
 #[tauri::command]
 pub async fn disconnect(session: i32) -> Result<(), LogicError> {
     etcd::remove_connector(&session).await;
@@ -38,6 +82,105 @@ pub async fn disconnect(session: i32) -> Result<(), LogicError> {
     Ok(())
 }
 
+/// 根据成员列表重新同步连接使用的地址，返回除配置的地址外正在使用的成员地址
+#[tauri::command]
+pub async fn sync_session_endpoints(session: i32) -> Result<Vec<String>, LogicError> {
+    let mut connector = etcd::get_connector(&session)?;
+    connector.sync_member_endpoints().await
+}
+
+/// `~/.ssh/config` 中的主机别名，用于连接配置时选择
+#[tauri::command]
+pub fn list_ssh_config_hosts() -> Result<Vec<String>, LogicError> {
+    Ok(SshConfigFile::load()?.hosts())
+}
+
+/// 可配置的SSH密钥交换、加密及MAC算法
+#[tauri::command]
+pub fn get_ssh_algorithms() -> SshAlgorithms {
+    ssh_algorithm::supported()
+}
+
+/// 会话使用的SSH隧道的传输统计及诊断提示，未使用SSH时为空
+#[tauri::command]
+pub fn get_tunnel_diagnostics(session: i32) -> Result<Vec<TunnelDiagnostics>, LogicError> {
+    Ok(etcd::get_connector(&session)?.tunnel_diagnostics())
+}
+
+/// 解析当前连接配置的TLS证书信息，未配置TLS时返回 None
+#[tauri::command]
+pub fn get_connection_tls_info(session: i32) -> Result<Option<ConnectionTlsInfo>, LogicError> {
+    let config = etcd::get_connection_config(&session).ok_or(LogicError::ConnectionLose)?;
+    if let Some(tls) = &config.tls {
+        Ok(Some(cert_util::parse_tls_info(tls)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// 获取服务端证书信息，不校验证书是否可信，用于自签名证书的首次信任
+#[tauri::command]
+pub async fn fetch_server_certificate(connection: Connection) -> Result<ServerCertificate, LogicError> {
+    let connection = resolve_connection(connection).await?;
+    let settings = get_settings().await?;
+    let endpoint = connection.endpoint()?;
+    let server_name = connection
+        .tls
+        .as_ref()
+        .and_then(|tls| tls.domain.clone())
+        .unwrap_or_else(|| match &endpoint {
+            EndpointAddress::Tcp { host, .. } => host.clone(),
+            EndpointAddress::Unix(_) => String::from("localhost"),
+        });
+
+    //  隧道和转发在读取证书期间需要保持
+    let mut _unix_proxy = None;
+    let (host, port, _ssh) = if let Some(ssh) = connection.ssh {
+        let tunnel = SshTunnel::new(ssh, endpoint).await?;
+        (String::from("127.0.0.1"), tunnel.get_proxy_port(), Some(tunnel))
+    } else {
+        match endpoint {
+            EndpointAddress::Tcp { host, port } => (host, port, None),
+            EndpointAddress::Unix(path) => {
+                let proxy = UnixSocketProxy::new(&path).await?;
+                let port = proxy.get_proxy_port();
+                _unix_proxy = Some(proxy);
+                (String::from("127.0.0.1"), port, None)
+            }
+        }
+    };
+
+    let der = cert_util::fetch_server_certificate(
+        &host,
+        port,
+        server_name,
+        Duration::from_secs(settings.connect_timeout_seconds),
+    ).await?;
+
+    Ok(ServerCertificate {
+        info: cert_util::parse_der_certificate(&der)?,
+        pem: cert_util::der_to_pem(&der),
+    })
+}
+
+/// 信任服务端证书：固定证书指纹并将证书加入CA列表，返回更新后的连接配置
+#[tauri::command]
+pub async fn trust_server_certificate(name: String, certificate: ServerCertificate) -> Result<Connection, LogicError> {
+    let mut info = get_connection(name).await?
+        .ok_or(LogicError::ResourceNotExist("Connection does not exist"))?;
+    let tls = info.connection.tls.as_mut().ok_or(LogicError::ArgumentError)?;
+
+    tls.pinned_fingerprint = Some(certificate.info.fingerprint_sha256);
+    let pem = certificate.pem.into_bytes();
+    if !tls.cert.contains(&pem) {
+        tls.cert.push(pem);
+    }
+
+    let connection = info.connection.clone();
+    save_connection_info(info).await?;
+    Ok(connection)
+}
+
 pub fn restore_connections(old_key: &[u8], new_key: &[u8]) -> io::Result<()> {
     let dir = file_util::get_conn_config_dir_path();
     if dir.exists() {
@@ -66,17 +209,47 @@ pub fn restore_connections(old_key: &[u8], new_key: &[u8]) -> io::Result<()> {
     Ok(())
 }
 
-/// 保存连接信息，继承其他配置项
+/// 保存连接信息，继承其他配置项。`template` 为空时保持原有的模板标记
 #[tauri::command]
-pub async fn save_connection(name: String, connection: Connection) -> Result<(), LogicError> {
+pub async fn save_connection(name: String, connection: Connection, template: Option<bool>) -> Result<(), LogicError> {
+    if connection.extends.as_ref() == Some(&name) {
+        return Err(LogicError::IllegalArgument(String::from("A connection can not extend itself")));
+    }
+    //  提前解析以校验继承关系
+    let mut check = connection.clone();
+    if let Some(base_name) = check.extends.take() {
+        let base = get_connection(base_name.clone()).await?.ok_or_else(|| {
+            LogicError::IllegalArgument(format!("Base connection does not exist: {}", base_name))
+        })?;
+        if base.connection.extends.as_ref() == Some(&name) {
+            return Err(LogicError::IllegalArgument(format!("Circular connection inheritance: {}", base_name)));
+        }
+        check.inherit(&base.connection);
+        check = resolve_connection(check).await?;
+    }
+    //  模板可以只包含部分配置，不校验地址
+    if template != Some(true) && !check.host.is_empty() {
+        check.endpoint()?;
+    }
+
     let mut dir = file_util::get_conn_config_dir_path();
     let key = get_settings().await?.connection_conf_encrypt_key;
+    let mut connection = connection;
+    connection.strip_prompted_credentials();
 
     let mut connection_info = ConnectionInfo {
         name,
         connection,
         key_collection: vec![],
         key_monitor_list: vec![],
+        notification_rules: vec![],
+        template: template.unwrap_or(false),
+        annotations: vec![],
+        report_schedule: None,
+        maintenance_schedules: vec![],
+        variables: BTreeMap::new(),
+        poll_interval_seconds: BTreeMap::new(),
+        tracked_prefixes: vec![],
     };
     let file_name = md5(&connection_info.name);
     dir.push(file_name);
@@ -90,6 +263,16 @@ pub async fn save_connection(name: String, connection: Connection) -> Result<(),
             if let Ok(info) = serde_json::from_slice::<ConnectionInfo>(data.as_slice()) {
                 connection_info.key_collection = info.key_collection;
                 connection_info.key_monitor_list = info.key_monitor_list;
+                connection_info.notification_rules = info.notification_rules;
+                connection_info.annotations = info.annotations;
+                connection_info.report_schedule = info.report_schedule;
+                connection_info.maintenance_schedules = info.maintenance_schedules;
+                connection_info.variables = info.variables;
+                connection_info.poll_interval_seconds = info.poll_interval_seconds;
+                connection_info.tracked_prefixes = info.tracked_prefixes;
+                if template.is_none() {
+                    connection_info.template = info.template;
+                }
             }
         }
 
@@ -107,7 +290,8 @@ pub async fn save_connection(name: String, connection: Connection) -> Result<(),
 }
 
 //  保存完整的连接info数据
-pub async fn save_connection_info(info: ConnectionInfo) -> Result<(), LogicError> {
+pub async fn save_connection_info(mut info: ConnectionInfo) -> Result<(), LogicError> {
+    info.connection.strip_prompted_credentials();
     let mut dir = file_util::get_conn_config_dir_path();
     let key = get_settings().await?.connection_conf_encrypt_key;
 
@@ -139,6 +323,7 @@ pub fn remove_connection(name: String) -> Result<(), LogicError> {
     if dir.exists() {
         fs::remove_file(dir)?;
     }
+    state_cache::clear(Some(&name))?;
     Ok(())
 }
 
@@ -169,6 +354,7 @@ pub async fn get_connection(name: String) -> Result<Option<ConnectionInfo>, Logi
 
 #[tauri::command]
 pub async fn get_connection_list() -> Result<Vec<ConnectionInfo>, LogicError> {
+    session_lock::ensure_unlocked()?;
     let dir = file_util::get_conn_config_dir_path();
 
     let mut result = Vec::new();
@@ -250,6 +436,61 @@ pub async fn import_connection(filepath: String) -> Result<(), LogicError> {
     Ok(())
 }
 
+/// 解析其他etcd客户端（etcd-manager、etcdkeeper、Kstone）的配置文件，返回可导入的连接供用户确认
+#[tauri::command]
+pub async fn preview_external_connections(
+    source: ExternalConnectionSource,
+    filepath: String,
+) -> Result<Vec<ExternalConnection>, LogicError> {
+    let content = fs::read_to_string(&filepath)?;
+    conn_import::parse(source, &content).map_err(LogicError::IllegalArgument)
+}
+
+/// 导入其他etcd客户端的连接配置，`names` 为空时导入全部。已存在同名连接时，`overwrite` 为false则跳过
+#[tauri::command]
+pub async fn import_external_connections(
+    source: ExternalConnectionSource,
+    filepath: String,
+    names: Option<Vec<String>>,
+    overwrite: bool,
+) -> Result<ExternalImportResult, LogicError> {
+    let content = fs::read_to_string(&filepath)?;
+    let list = conn_import::parse(source, &content).map_err(LogicError::IllegalArgument)?;
+
+    let mut result = ExternalImportResult::default();
+    for external in list {
+        if let Some(names) = &names {
+            if !names.contains(&external.name) {
+                continue;
+            }
+        }
+        if !overwrite && get_connection(external.name.clone()).await?.is_some() {
+            result.skipped.push(external.name);
+            continue;
+        }
+        save_connection(external.name.clone(), external.connection, None).await?;
+        result.imported.push(external.name);
+    }
+    info!("Imported {} connections from {:?}", result.imported.len(), source);
+    Ok(result)
+}
+
+/// 将已保存的连接编码为分享字符串（可生成二维码），不包含密码、私钥及证书内容
+#[tauri::command]
+pub async fn encode_connection_share(name: String) -> Result<String, LogicError> {
+    let info = get_connection(name.clone())
+        .await?
+        .ok_or(LogicError::ResourceNotExist("The connection does not exist"))?;
+    let connection = resolve_connection(info.connection).await?;
+    conn_share::encode(&name, &connection).map_err(LogicError::MsgError)
+}
+
+/// 解析分享字符串，返回的连接需要用户补充凭据后保存
+#[tauri::command]
+pub fn decode_connection_share(content: String) -> Result<ExternalConnection, LogicError> {
+    conn_share::decode(&content).map_err(LogicError::IllegalArgument)
+}
+
 #[tauri::command]
 pub async fn update_key_collection(
     session: i32,
@@ -263,6 +504,218 @@ pub async fn update_key_collection(
     Ok(())
 }
 
+/// 连接未保存时返回空列表
+#[tauri::command]
+pub fn list_key_annotations(session: i32) -> Vec<KeyAnnotation> {
+    etcd::get_connection_info_optional(&session)
+        .map(|info| info.annotations.clone())
+        .unwrap_or_default()
+}
+
+/// 新增或更新注释，`key` 和 `prefix` 相同的注释视为同一条
+#[tauri::command]
+pub async fn set_key_annotation(session: i32, mut annotation: KeyAnnotation) -> Result<(), LogicError> {
+    if annotation.key.is_empty() {
+        return Err(LogicError::IllegalArgument(String::from("The key of annotation can not be empty")));
+    }
+    annotation.tags.retain(|t| !t.trim().is_empty());
+    annotation.updated_at = etcd::now_timestamp() as u64;
+
+    let result = etcd::get_connection_info_optional(&session);
+    if let Some(mut info) = result {
+        match info
+            .annotations
+            .iter_mut()
+            .find(|a| a.key == annotation.key && a.prefix == annotation.prefix)
+        {
+            Some(a) => *a = annotation,
+            None => info.annotations.push(annotation),
+        }
+        save_connection_info(info.value().clone()).await?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remove_key_annotation(session: i32, key: String, prefix: bool) -> Result<(), LogicError> {
+    let result = etcd::get_connection_info_optional(&session);
+    if let Some(mut info) = result {
+        info.annotations.retain(|a| a.key != key || a.prefix != prefix);
+        save_connection_info(info.value().clone()).await?;
+    }
+    Ok(())
+}
+
+/// 按key、注释内容和标签模糊搜索注释，匹配度高的在前
+#[tauri::command]
+pub fn search_key_annotations(session: i32, query: String) -> Vec<KeyAnnotation> {
+    let annotations = list_key_annotations(session);
+    let query = query.trim();
+    if query.is_empty() {
+        return annotations;
+    }
+    let mut matched: Vec<(i64, KeyAnnotation)> = annotations
+        .into_iter()
+        .filter_map(|a| {
+            let score = std::iter::once(a.key.as_str())
+                .chain(std::iter::once(a.note.as_str()))
+                .chain(a.tags.iter().map(|t| t.as_str()))
+                .filter_map(|text| fuzzy::fuzzy_match(query, text).map(|(score, _)| score))
+                .max()?;
+            Some((score, a))
+        })
+        .collect();
+    matched.sort_by(|a, b| b.0.cmp(&a.0));
+    matched.into_iter().map(|(_, a)| a).collect()
+}
+
+/// 导出收藏夹及注释
+#[tauri::command]
+pub fn export_key_bookmarks(session: i32) -> KeyBookmarks {
+    etcd::get_connection_info_optional(&session)
+        .map(|info| KeyBookmarks {
+            key_collection: info.key_collection.clone(),
+            annotations: info.annotations.clone(),
+        })
+        .unwrap_or_default()
+}
+
+/// 导入收藏夹及注释，与已有内容合并，`overwrite` 为true时覆盖相同key的注释，返回合并后的结果
+#[tauri::command]
+pub async fn import_key_bookmarks(session: i32, bookmarks: KeyBookmarks, overwrite: bool) -> Result<KeyBookmarks, LogicError> {
+    let result = etcd::get_connection_info_optional(&session);
+    let Some(mut info) = result else {
+        return Err(LogicError::IllegalArgument(String::from("The connection is not saved")));
+    };
+    for key in bookmarks.key_collection {
+        if !info.key_collection.contains(&key) {
+            info.key_collection.push(key);
+        }
+    }
+    for annotation in bookmarks.annotations {
+        match info
+            .annotations
+            .iter_mut()
+            .find(|a| a.key == annotation.key && a.prefix == annotation.prefix)
+        {
+            Some(a) if overwrite => *a = annotation,
+            Some(_) => {}
+            None => info.annotations.push(annotation),
+        }
+    }
+    save_connection_info(info.value().clone()).await?;
+    Ok(KeyBookmarks {
+        key_collection: info.key_collection.clone(),
+        annotations: info.annotations.clone(),
+    })
+}
+
+#[tauri::command]
+pub fn get_connection_variables(session: i32) -> BTreeMap<String, String> {
+    etcd::get_connection_info_optional(&session)
+        .map(|info| info.variables.clone())
+        .unwrap_or_default()
+}
+
+/// 保存连接的模板变量，值中的 `${NAME}` 在保存或预览时替换为变量值
+#[tauri::command]
+pub async fn set_connection_variables(session: i32, variables: BTreeMap<String, String>) -> Result<(), LogicError> {
+    if let Some(name) = variables.keys().find(|name| !template::is_valid_name(name)) {
+        return Err(LogicError::IllegalArgument(format!("Invalid variable name: {}", name)));
+    }
+    let result = etcd::get_connection_info_optional(&session);
+    let Some(mut info) = result else {
+        return Err(LogicError::IllegalArgument(String::from("The connection is not saved")));
+    };
+    info.variables = variables;
+    save_connection_info(info.value().clone()).await?;
+    Ok(())
+}
+
+/// 获取连接当前生效的定时任务间隔秒数，已计入窗口失去焦点时的放慢
+#[tauri::command]
+pub async fn get_poll_intervals(session: i32) -> Result<BTreeMap<PollTask, u64>, LogicError> {
+    Ok(poll_scheduler::intervals_of(session).await)
+}
+
+/// 保存连接的定时任务间隔，覆盖设置中的配置，在任务的下一个周期生效
+#[tauri::command]
+pub async fn set_connection_poll_intervals(session: i32, intervals: BTreeMap<PollTask, u64>) -> Result<(), LogicError> {
+    validate_poll_intervals(&intervals).map_err(LogicError::IllegalArgument)?;
+    let result = etcd::get_connection_info_optional(&session);
+    let Some(mut info) = result else {
+        return Err(LogicError::IllegalArgument(String::from("The connection is not saved")));
+    };
+    info.poll_interval_seconds = intervals;
+    save_connection_info(info.value().clone()).await?;
+    Ok(())
+}
+
+/// 获取连接最近一次读取到的key列表、集群状态和查看过的值，连接不可用时 `stale` 为true
+#[tauri::command]
+pub async fn get_cached_state(name: String) -> Result<Option<CachedState>, LogicError> {
+    state_cache::get(&name).await
+}
+
+/// 删除连接的离线缓存，未指定连接时删除所有缓存，返回删除的缓存数
+#[tauri::command]
+pub fn clear_cached_state(name: Option<String>) -> Result<usize, LogicError> {
+    state_cache::clear(name.as_deref())
+}
+
+/// 读取保存在etcd中的共享注释和收藏夹，需要在设置中开启共享注释
+#[tauri::command]
+pub async fn list_shared_annotations(session: i32) -> Result<SharedAnnotations, LogicError> {
+    let root = shared_annotation::root(&get_settings().await?)?;
+    let mut connector = etcd::get_connector(&session)?;
+    shared_annotation::list(&mut connector, &root).await
+}
+
+/// 写入共享注释，`expected_mod_revision` 为读取时的修改版本，新建时为0
+#[tauri::command]
+pub async fn set_shared_annotation(
+    session: i32,
+    mut annotation: KeyAnnotation,
+    expected_mod_revision: i64,
+) -> Result<SharedAnnotationWriteResult, LogicError> {
+    if annotation.key.is_empty() {
+        return Err(LogicError::IllegalArgument(String::from("The key of annotation can not be empty")));
+    }
+    let root = shared_annotation::root(&get_settings().await?)?;
+    etcd::check_writable(&session)?;
+    annotation.tags.retain(|t| !t.trim().is_empty());
+    annotation.updated_at = etcd::now_timestamp() as u64;
+    let mut connector = etcd::get_connector(&session)?;
+    shared_annotation::put(&mut connector, &root, annotation, expected_mod_revision).await
+}
+
+/// 删除共享注释，已被其他人修改时返回false
+#[tauri::command]
+pub async fn remove_shared_annotation(
+    session: i32,
+    key: String,
+    prefix: bool,
+    expected_mod_revision: i64,
+) -> Result<bool, LogicError> {
+    let root = shared_annotation::root(&get_settings().await?)?;
+    etcd::check_writable(&session)?;
+    let mut connector = etcd::get_connector(&session)?;
+    shared_annotation::remove(&mut connector, &root, &key, prefix, expected_mod_revision).await
+}
+
+/// 向共享收藏夹添加或移除key，返回修改后的收藏夹
+#[tauri::command]
+pub async fn update_shared_bookmarks(
+    session: i32,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Vec<String>, LogicError> {
+    let root = shared_annotation::root(&get_settings().await?)?;
+    etcd::check_writable(&session)?;
+    let mut connector = etcd::get_connector(&session)?;
+    shared_annotation::update_bookmarks(&mut connector, &root, add, remove).await
+}
+
 #[tauri::command]
 pub async fn set_key_monitor(
     session: i32,
@@ -308,4 +761,90 @@ pub async fn remove_key_monitor(
     let lock = lock_ref.value().clone();
     KeyMonitor::remove_config(lock, &key).await;
     Ok(())
-}
\ No newline at end of file
+}
+
+/// 更新连接的通知规则，已保存的连接会同时保存到配置中
+async fn update_notification_rules(
+    session: i32,
+    window: Window,
+    update: impl FnOnce(&mut Vec<NotificationRule>) -> Result<(), LogicError>,
+) -> Result<Vec<NotificationRule>, LogicError> {
+    let mut rules = etcd::get_notification_rules(&session);
+    update(&mut rules)?;
+
+    let info = etcd::get_connection_info_optional(&session).map(|mut info| {
+        info.notification_rules = rules.clone();
+        info.value().clone()
+    });
+    if let Some(info) = info {
+        save_connection_info(info).await?;
+    }
+
+    etcd::set_notification_rules(session, rules.clone(), window).await?;
+    Ok(rules)
+}
+
+fn find_notification_rule<'a>(rules: &'a mut [NotificationRule], id: &str) -> Result<&'a mut NotificationRule, LogicError> {
+    rules
+        .iter_mut()
+        .find(|r| r.id == id)
+        .ok_or(LogicError::ResourceNotExist("Notification rule does not exist"))
+}
+
+#[tauri::command]
+pub fn list_notification_rules(session: i32) -> Vec<NotificationRule> {
+    etcd::get_notification_rules(&session)
+}
+
+/// 新增或修改通知规则，`id` 为空时新增
+#[tauri::command]
+pub async fn set_notification_rule(
+    session: i32,
+    mut rule: NotificationRule,
+    window: Window,
+) -> Result<Vec<NotificationRule>, LogicError> {
+    if rule.key_pattern.is_empty() || rule.event_types.is_empty() {
+        return Err(LogicError::IllegalArgument(String::from("Key pattern and event types are required")));
+    }
+    update_notification_rules(session, window, move |rules| {
+        if rule.id.is_empty() {
+            rule.id = Uuid::new_v4().to_string();
+            rules.push(rule);
+        } else {
+            let id = rule.id.clone();
+            *find_notification_rule(rules, &id)? = rule;
+        }
+        Ok(())
+    }).await
+}
+
+#[tauri::command]
+pub async fn remove_notification_rule(session: i32, id: String, window: Window) -> Result<Vec<NotificationRule>, LogicError> {
+    update_notification_rules(session, window, |rules| {
+        rules.retain(|r| r.id != id);
+        Ok(())
+    }).await
+}
+
+/// 静音或取消静音通知规则
+#[tauri::command]
+pub async fn mute_notification_rule(session: i32, id: String, muted: bool, window: Window) -> Result<Vec<NotificationRule>, LogicError> {
+    update_notification_rules(session, window, |rules| {
+        find_notification_rule(rules, &id)?.muted = muted;
+        Ok(())
+    }).await
+}
+
+/// 暂停通知规则一段时间，`minutes` 为0时取消暂停
+#[tauri::command]
+pub async fn snooze_notification_rule(session: i32, id: String, minutes: u64, window: Window) -> Result<Vec<NotificationRule>, LogicError> {
+    update_notification_rules(session, window, |rules| {
+        find_notification_rule(rules, &id)?.snooze_until = if minutes == 0 {
+            None
+        } else {
+            Some(etcd::now_timestamp() as u64 + minutes * 60 * 1000)
+        };
+        Ok(())
+    }).await
+}
+
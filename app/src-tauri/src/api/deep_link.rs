@@ -0,0 +1,110 @@
+use lazy_static::lazy_static;
+use log::{info, warn};
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+use crate::api::connection::get_connection;
+use crate::api::windows::open_main_window0;
+use crate::error::LogicError;
+use crate::etcd;
+use crate::transport::connection::DeepLinkEvent;
+use crate::utils::deep_link::DeepLink;
+
+lazy_static! {
+    //  最近一次未被前端获取的深度链接处理结果
+    static ref PENDING_DEEP_LINK: Mutex<Option<DeepLinkEvent>> = Mutex::new(None);
+}
+
+/// 处理 `etcd-workbench://` 链接：显示主窗口，打开已保存的连接（已打开则复用），
+/// 然后通过 `deep_link` 事件通知主窗口调用 `take_deep_link` 获取结果并定位到key
+pub fn handle_deep_link(app_handle: AppHandle, url: String) {
+    info!("Received deep link: {}", url);
+    tauri::async_runtime::spawn(async move {
+        open_main_window0(&app_handle);
+        match DeepLink::parse(&url) {
+            Ok(link) => dispatch(&app_handle, link).await,
+            Err(e) => warn!("Invalid deep link: {}", e),
+        }
+    });
+}
+
+async fn dispatch(app_handle: &AppHandle, link: DeepLink) {
+    let event = open_deep_link(app_handle, link).await;
+    *PENDING_DEEP_LINK.lock().await = Some(event);
+    if let Err(e) = app_handle.emit_to("main", "deep_link", ()) {
+        warn!("Failed to emit deep link event: {e}");
+    }
+}
+
+async fn open_deep_link(app_handle: &AppHandle, link: DeepLink) -> DeepLinkEvent {
+    let mut event = DeepLinkEvent {
+        connection: link.connection.clone(),
+        key: link.key,
+        revision: link.revision,
+        session: None,
+        session_data: None,
+        error: None,
+    };
+
+    if let Some(session) = etcd::find_window_session("main", &link.connection) {
+        event.session = Some(session);
+        return event;
+    }
+
+    let result = async {
+        let info = get_connection(link.connection.clone())
+            .await?
+            .ok_or(LogicError::ResourceNotExist("Connection does not exist"))?;
+        let window = app_handle
+            .get_window("main")
+            .ok_or(LogicError::ResourceNotExist("Main window does not exist"))?;
+        etcd::new_connector(info.name, info.connection, window).await
+    }
+    .await;
+
+    match result {
+        Ok(session_data) => {
+            event.session = Some(session_data.id);
+            event.session_data = Some(session_data);
+        }
+        Err(e) => event.error = Some(e),
+    }
+    event
+}
+
+/// 获取尚未处理的深度链接，只能获取一次。前端启动时也需调用一次，用于应用通过链接冷启动的场景
+#[tauri::command]
+pub async fn take_deep_link() -> Option<DeepLinkEvent> {
+    PENDING_DEEP_LINK.lock().await.take()
+}
+
+/// 在应用内打开链接，如从事故文档中复制的链接，处理方式与从系统打开相同
+#[tauri::command]
+pub async fn resolve_deep_link(app_handle: AppHandle, url: String) -> Result<(), LogicError> {
+    let link = DeepLink::parse(&url).map_err(LogicError::IllegalArgument)?;
+    open_main_window0(&app_handle);
+    dispatch(&app_handle, link).await;
+    Ok(())
+}
+
+/// 生成指向key在指定版本的链接，`revision` 为空时使用key当前的修改版本。只有已保存的连接可以生成
+#[tauri::command]
+pub async fn generate_key_link(session: i32, key: String, revision: Option<i64>) -> Result<String, LogicError> {
+    let connection = etcd::get_connection_info_optional(&session)
+        .map(|info| info.name.clone())
+        .ok_or_else(|| LogicError::IllegalArgument(String::from("The connection is not saved")))?;
+    let revision = {
+        let mut connector = etcd::get_connector(&session)?;
+        match revision {
+            //  确认该版本未被压缩且key存在
+            Some(revision) => connector.kv_get_by_version(key.clone(), revision).await?.mod_revision,
+            None => connector.kv_get(key.clone()).await?.mod_revision,
+        }
+    };
+    let link = DeepLink {
+        connection,
+        key: Some(key),
+        revision: Some(revision),
+    };
+    Ok(link.to_url())
+}
@@ -0,0 +1,117 @@
+use std::collections::VecDeque;
+
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager, Window};
+
+use crate::error::LogicError;
+use crate::etcd::now_timestamp;
+
+/// 每个事件流保留的最近事件数
+const MAX_REPLAY_EVENTS: usize = 200;
+
+/// 事件流，同一流内的事件共享递增的序号
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all="camelCase")]
+pub enum EventStream {
+    /// key监控、通知规则、前缀变更等监听事件
+    Watch,
+    /// 快照、lease续约等任务进度
+    Progress,
+    /// 健康检查等指标
+    Metrics,
+}
+
+/// 推送给前端的事件，原始事件内容展开在顶层，保持与直接 `emit` 时相同的字段
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct BusEvent {
+    pub bus_stream: EventStream,
+    pub bus_event: String,
+    pub bus_seq: u64,
+    pub bus_time: u64,
+    #[serde(flatten)]
+    pub payload: Value,
+}
+
+#[derive(Default)]
+struct StreamBuffer {
+    seq: u64,
+    events: VecDeque<BusEvent>,
+}
+
+lazy_static! {
+    //  以 (窗口label, 事件流) 为索引
+    static ref EVENT_BUFFERS: DashMap<(String, EventStream), StreamBuffer> = DashMap::new();
+}
+
+fn record<S: Serialize>(label: &str, stream: EventStream, event: &str, payload: S) -> Option<BusEvent> {
+    let payload = match serde_json::to_value(payload) {
+        Ok(Value::Object(map)) => Value::Object(map),
+        Ok(other) => json!({ "payload": other }),
+        Err(e) => {
+            warn!("Failed to serialize event {}: {}", event, e);
+            return None;
+        }
+    };
+    let mut buffer = EVENT_BUFFERS.entry((String::from(label), stream)).or_default();
+    buffer.seq += 1;
+    let bus_event = BusEvent {
+        bus_stream: stream,
+        bus_event: String::from(event),
+        bus_seq: buffer.seq,
+        bus_time: now_timestamp() as u64,
+        payload,
+    };
+    if buffer.events.len() >= MAX_REPLAY_EVENTS {
+        buffer.events.pop_front();
+    }
+    buffer.events.push_back(bus_event.clone());
+    Some(bus_event)
+}
+
+/// 向窗口推送事件，分配序号并保存到重放缓冲区
+pub fn publish<S: Serialize>(window: &Window, stream: EventStream, event: &str, payload: S) {
+    if let Some(bus_event) = record(window.label(), stream, event, payload) {
+        if let Err(e) = window.emit(event, bus_event) {
+            warn!("Failed to emit event {}: {e}", event);
+        }
+    }
+}
+
+/// 按窗口label推送事件，窗口不存在时只保存到缓冲区
+pub fn publish_to<S: Serialize>(app: &AppHandle, label: &str, stream: EventStream, event: &str, payload: S) {
+    if let Some(bus_event) = record(label, stream, event, payload) {
+        if let Some(window) = app.get_window(label) {
+            if let Err(e) = window.emit(event, bus_event) {
+                warn!("Failed to emit event {}: {e}", event);
+            }
+        }
+    }
+}
+
+/// 窗口关闭后清理其缓冲区
+pub fn clear_window(label: &str) {
+    EVENT_BUFFERS.retain(|key, _| key.0 != label);
+}
+
+/// 重放序号大于 `after_seq` 的事件，用于视图重新挂载后补齐期间错过的事件。
+/// 若 `after_seq` 早于缓冲区中最早的事件，返回的第一个事件序号将不连续，前端需自行全量刷新
+#[tauri::command]
+pub fn replay_events(window: Window, stream: EventStream, after_seq: u64) -> Result<Vec<BusEvent>, LogicError> {
+    let events = EVENT_BUFFERS
+        .get(&(String::from(window.label()), stream))
+        .map(|buffer| {
+            buffer
+                .events
+                .iter()
+                .filter(|e| e.bus_seq > after_seq)
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(events)
+}
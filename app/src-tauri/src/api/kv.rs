@@ -1,39 +1,173 @@
+use std::path::Path;
 use std::str::FromStr;
 use log::warn;
+use tauri::Window;
 use crate::error::LogicError;
 use crate::etcd;
-use crate::transport::kv::{SearchResult, SerializableKeyValue};
+use crate::etcd::{audit_stream, console, content_hint, delete_preview, dir_sync, dry_run, garbage, key_index, key_tail, kv_macro, mirror, prefetcher, prefix_counter};
+use crate::api::connection::save_connection_info;
+use crate::api::quick_open;
+use crate::api::task_center::{self, TaskKind};
+use crate::api::settings::get_settings;
+use crate::utils::{file_util, format_convert, kv_import, state_cache, template, text_diff, trash, value_transfer};
+use crate::transport::connection::KeySeparatorInfo;
+use crate::transport::dry_run::Mutation;
+use crate::transport::kv::{
+    AuditStreamConfig, BatchPutResult, ConsoleResult, ContentHint, DeletePrefixResult, DeletePreview, DirSyncResult, EditLockResult, FileDiffResult, FormatLanguage, GarbageCleanupResult, GarbageItem, GarbageOptions, GarbageReport, HistoryArchive, HistoryExportResult, HotKeyReport, KeyTailConfig, KeyValuePair, KvMacro, MacroOp, MacroRunResult, LeaseAttachResult, MirrorConfig, MirrorStatus, PrefixMapping, KeyCompletion, KeyStreamBatch, KeyValuePage, KeyspaceBounds, PrefixChangeCounter, PrefetchResult, PrefixKeyCount, PrefixKeys, RevisionTimeSample,
+    SearchResult, SerializableKeyValue, TrashEntry, TrashRestoreResult, ValueCacheStats,
+};
 
+/// `timeout_seconds` 为本次读取指定超时时间，覆盖连接默认的请求超时时间，用于key数量极多的集群
 #[tauri::command]
-pub async fn kv_get_all_keys(session: i32) -> Result<Vec<SerializableKeyValue>, LogicError> {
-    let mut connector = etcd::get_connector(&session)?;
-    let keys = connector.kv_get_all_keys().await?;
+pub async fn kv_get_all_keys(session: i32, timeout_seconds: Option<u64>) -> Result<Vec<SerializableKeyValue>, LogicError> {
+    let deadline = etcd::call_deadline(timeout_seconds)?;
+    if let Some(index) = etcd::get_key_index(&session) {
+        let mut keys = key_index::to_serializable_kvs(index.read().unwrap().keys());
+        content_hint::hide_hint_keys(session, &mut keys, |kv| kv.key.as_bytes());
+        return Ok(keys);
+    }
+    let mut keys = {
+        let mut connector = etcd::get_connector(&session)?;
+        connector.with_deadline(deadline).kv_get_all_keys().await?
+    };
+    content_hint::hide_hint_keys(session, &mut keys, |kv| kv.key.as_bytes());
+    state_cache::cache_keys(session, &keys).await;
     Ok(keys)
 }
 
+/// `timeout_seconds` 为本次读取指定超时时间，覆盖连接默认的请求超时时间
 #[tauri::command]
-pub async fn kv_get_all_keys_paging(session: i32, cursor_key: String, limit: i64) -> Result<Vec<SerializableKeyValue>, LogicError> {
+pub async fn kv_get_all_keys_paging(
+    session: i32,
+    cursor_key: String,
+    limit: i64,
+    timeout_seconds: Option<u64>,
+) -> Result<Vec<SerializableKeyValue>, LogicError> {
+    let deadline = etcd::call_deadline(timeout_seconds)?;
+    if let Some(index) = etcd::get_key_index(&session) {
+        let index = index.read().unwrap();
+        let keys = index.keys_after(cursor_key.as_bytes(), limit.max(0) as usize);
+        return Ok(key_index::to_serializable_kvs(keys));
+    }
     let mut connector = etcd::get_connector(&session)?;
-    let keys = connector.kv_get_all_keys_paging(cursor_key, limit).await?;
+    let keys = connector.with_deadline(deadline).kv_get_all_keys_paging(cursor_key, limit).await?;
+    //  不隐藏提示key：调用方以返回的最后一个key翻页，整页都是提示key时隐藏后会被当作没有更多数据。
+    //  界面使用 `kv_get_all_keys_paging_at`，通过 `cursor` 翻页
     Ok(keys)
 }
 
+/// 分页获取所有key，所有分页可固定在同一版本读取。
+///
+/// 首次请求 `revision` 传空，后续分页传入首次结果中的 `revision`
 #[tauri::command]
-pub async fn kv_get(session: i32, key: String) -> Result<SerializableKeyValue, LogicError> {
-    let mut connector = etcd::get_connector(&session)?;
-    let mut kv = connector.kv_get(key).await?;
-    if kv.lease.ne("0") {
-        let lease_id = i64::from_str(kv.lease.as_str()).unwrap();
-        let info = connector.lease_get_simple_info(lease_id).await?;
-        kv.lease_info = Some(info)
+pub async fn kv_get_all_keys_paging_at(session: i32, cursor_key: String, limit: i64, revision: Option<i64>) -> Result<KeyValuePage, LogicError> {
+    if let Some(index) = etcd::get_key_index(&session) {
+        let index = index.read().unwrap();
+        //  索引只保存最新版本，指定了其他版本时仍从etcd读取
+        if revision.is_none() || revision == Some(index.revision()) {
+            let limit = limit.max(0) as usize;
+            let keys = index.keys_after(cursor_key.as_bytes(), limit + 1);
+            let more = keys.len() > limit;
+            let mut kvs = key_index::to_serializable_kvs(keys.into_iter().take(limit).collect());
+            let cursor = kvs.last().map(|kv| kv.key.clone());
+            content_hint::hide_hint_keys(session, &mut kvs, |kv| kv.key.as_bytes());
+            return Ok(KeyValuePage {
+                revision: index.revision(),
+                more,
+                kvs,
+                cursor,
+            });
+        }
     }
+    let mut page = {
+        let mut connector = etcd::get_connector(&session)?;
+        connector.kv_get_all_keys_paging_at(cursor_key, limit, revision).await?
+    };
+    content_hint::hide_hint_keys(session, &mut page.kvs, |kv| kv.key.as_bytes());
+    Ok(page)
+}
+
+#[tauri::command]
+pub async fn kv_get(session: i32, key: String) -> Result<SerializableKeyValue, LogicError> {
+    let mut kv = {
+        let mut connector = etcd::get_connector(&session)?;
+        let mut kv = connector.kv_get(key.clone()).await?;
+        if kv.lease.ne("0") {
+            let lease_id = i64::from_str(kv.lease.as_str()).unwrap();
+            let info = connector.lease_get_simple_info(lease_id).await?;
+            kv.lease_info = Some(info)
+        }
+        kv
+    };
+    quick_open::record_recent_key(session, &key).await;
+    state_cache::cache_value(session, &kv).await;
+    value_transfer::offload(&mut kv);
     Ok(kv)
 }
 
+/// 流式读取时每批的key数量
+const KEY_STREAM_BATCH_SIZE: i64 = 1000;
+
+/// 分批读取所有key，通过 `kv_keys_batch` 事件按顺序推送，界面可在首批数据到达后立即渲染。
+/// 所有批次固定在首批的版本读取，最后一批的 `done` 为true
+#[tauri::command]
+pub async fn kv_stream_all_keys(session: i32, stream_id: String, batch_size: Option<i64>, window: Window) -> Result<(), LogicError> {
+    etcd::get_connector(&session)?;
+    let batch_size = batch_size.unwrap_or(KEY_STREAM_BATCH_SIZE).max(1);
+
+    tokio::spawn(async move {
+        let mut cursor = String::new();
+        let mut revision = None;
+        let mut seq = 0;
+        let mut total = 0;
+        loop {
+            let mut batch = KeyStreamBatch {
+                stream_id: stream_id.clone(),
+                seq,
+                revision: revision.unwrap_or(0),
+                keys: vec![],
+                done: true,
+                total,
+                error_msg: None,
+            };
+            match kv_get_all_keys_paging_at(session, cursor.clone(), batch_size, revision).await {
+                Ok(page) => {
+                    let pinned = *revision.get_or_insert(page.revision);
+                    total += page.kvs.len();
+                    batch.revision = pinned;
+                    batch.total = total;
+                    match page.cursor {
+                        Some(last) if page.more => {
+                            cursor = last;
+                            batch.done = false;
+                        }
+                        _ => {}
+                    }
+                    batch.keys = page.kvs;
+                }
+                Err(e) => batch.error_msg = Some(format!("{:?}", e)),
+            }
+
+            let done = batch.done;
+            //  批次数据量大，直接推送而不进入事件重放缓冲区
+            if let Err(e) = window.emit("kv_keys_batch", batch) {
+                warn!("Failed to emit key batch: {e}");
+                break;
+            }
+            if done {
+                break;
+            }
+            seq += 1;
+        }
+    });
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn kv_get_by_version(session: i32, key: String, version: i64) -> Result<SerializableKeyValue, LogicError> {
     let mut connector = etcd::get_connector(&session)?;
-    let kv = connector.kv_get_by_version(key, version).await?;
+    let mut kv = connector.kv_get_by_version(key, version).await?;
+    value_transfer::offload(&mut kv);
     Ok(kv)
 }
 
@@ -44,39 +178,1017 @@ pub async fn kv_get_history_versions(session: i32, key: String, start: i64, end:
     Ok(versions)
 }
 
+/// 获取键空间的版本范围，用于历史版本和对比功能判断哪些版本可以读取
 #[tauri::command]
-pub async fn kv_get_with_prefix(session: i32, prefix: String) -> Result<SearchResult, LogicError> {
+pub async fn get_keyspace_bounds(session: i32) -> Result<KeyspaceBounds, LogicError> {
     let mut connector = etcd::get_connector(&session)?;
-    let result = connector.kv_get_with_prefix(prefix).await?;
+    let bounds = connector.get_keyspace_bounds().await?;
+    Ok(bounds)
+}
+
+#[tauri::command]
+pub async fn kv_get_with_prefix(session: i32, prefix: String, revision: Option<i64>) -> Result<SearchResult, LogicError> {
+    if let Some(index) = etcd::get_key_index(&session) {
+        let index = index.read().unwrap();
+        if revision.is_none() || revision == Some(index.revision()) {
+            let (keys, binary): (Vec<_>, Vec<_>) = index
+                .keys_with_prefix(prefix.as_bytes(), 50)
+                .into_iter()
+                .partition(|(key, _)| std::str::from_utf8(key).is_ok());
+            let mut results = key_index::to_serializable_kvs(keys);
+            content_hint::hide_hint_keys(session, &mut results, |kv| kv.key.as_bytes());
+            return Ok(SearchResult {
+                count: index.count_prefix(prefix.as_bytes()),
+                results,
+                revision: index.revision(),
+                binary_skipped: binary.len(),
+            });
+        }
+    }
+    let mut result = {
+        let mut connector = etcd::get_connector(&session)?;
+        connector.kv_get_with_prefix(prefix, revision).await?
+    };
+    content_hint::hide_hint_keys(session, &mut result.results, |kv| kv.key.as_bytes());
+    Ok(result)
+}
+
+/// 获取前缀下的key列表（不含value），用于逐层展开key树，已预取的前缀直接从内存返回
+#[tauri::command]
+pub async fn kv_list_prefix_keys(session: i32, prefix: String) -> Result<PrefixKeys, LogicError> {
+    let mut result = prefetcher::list_prefix_keys(session, prefix).await?;
+    content_hint::hide_hint_keys(session, &mut result.keys, |kv| kv.key.as_bytes());
     Ok(result)
 }
 
+/// 展开树节点后调用，并发预取下一层各子目录的key列表，使后续展开无需等待网络请求
+#[tauri::command]
+pub async fn kv_prefetch_children(session: i32, prefix: String) -> Result<PrefetchResult, LogicError> {
+    let delimiter = etcd::get_key_separator(session).await?;
+    prefetcher::prefetch_children(session, prefix, delimiter).await
+}
+
+/// 获取连接构建key树使用的分隔符，`Mixed` 模式下返回各候选分隔符的评估结果
+#[tauri::command]
+pub async fn kv_resolve_separator(session: i32, refresh: Option<bool>) -> Result<KeySeparatorInfo, LogicError> {
+    etcd::resolve_key_separator(session, refresh.unwrap_or(false)).await
+}
+
+/// 无索引时用于统计路径片段的key数量上限
+const AUTOCOMPLETE_SCAN_LIMIT: i64 = 2000;
+
+/// key路径自动补全，返回输入前缀之后最可能的下一个路径片段
 #[tauri::command]
-pub async fn kv_put(session: i32, key: String, value: Vec<u8>, ttl: Option<i64>) -> Result<(), LogicError> {
+pub async fn kv_autocomplete(session: i32, prefix: String, limit: usize) -> Result<Vec<KeyCompletion>, LogicError> {
+    let delimiter = etcd::get_key_separator(session).await?;
+
+    let segments = if let Some(index) = etcd::get_key_index(&session) {
+        let index = index.read().unwrap();
+        index.next_segments(prefix.as_bytes(), delimiter.as_bytes(), limit)
+    } else {
+        let mut connector = etcd::get_connector(&session)?;
+        let keys: Vec<Vec<u8>> = connector
+            .kv_get_keys_with_prefix(prefix.clone(), AUTOCOMPLETE_SCAN_LIMIT)
+            .await?
+            .into_iter()
+            .map(|kv| kv.key.into_bytes())
+            .collect();
+        key_index::next_segments_of_keys(&keys, prefix.as_bytes(), delimiter.as_bytes(), limit)
+    };
+
+    let completions = segments
+        .into_iter()
+        .map(|(path, key_count)| {
+            let path = String::from_utf8_lossy(&path).to_string();
+            KeyCompletion {
+                is_dir: path.ends_with(delimiter.as_str()),
+                path,
+                key_count,
+            }
+        })
+        .collect();
+    Ok(completions)
+}
+
+#[tauri::command]
+pub async fn kv_put(
+    session: i32,
+    key: String,
+    value: Vec<u8>,
+    ttl: Option<i64>,
+    render_variables: Option<bool>,
+    dry_run: Option<bool>,
+) -> Result<Mutation<()>, LogicError> {
+    let recorded = kv_macro::is_recording(session).then(|| (key.clone(), value.clone()));
+    let value = if render_variables.unwrap_or(false) {
+        let text = String::from_utf8(value)
+            .map_err(|_| LogicError::IllegalArgument(String::from("Only text values can use variables")))?;
+        render_template(&session, &text)?.into_bytes()
+    } else {
+        value
+    };
+    if dry_run::enabled(dry_run) {
+        let lease = ttl.filter(|ttl| *ttl > 0).map(|ttl| format!("<new lease, ttl={}>", ttl));
+        let plan = dry_run::plan_put(session, "kv_put", key, value.len(), lease).await?;
+        return Ok(Mutation::Plan(plan));
+    }
+    etcd::check_writable(&session)?;
     let mut connector = etcd::get_connector(&session)?;
     connector.kv_put(
         key,
         value,
         ttl,
     ).await?;
+    if let Some((key, value)) = recorded {
+        kv_macro::record(session, kv_macro::put_op(key, value, ttl, render_variables.unwrap_or(false)));
+    }
 
-    Ok(())
+    Ok(Mutation::Done(()))
 }
 
 #[tauri::command]
-pub async fn kv_put_with_lease(session: i32, key: String, value: Vec<u8>, lease: String) -> Result<(), LogicError> {
-    let mut connector = etcd::get_connector(&session)?;
+pub async fn kv_put_with_lease(
+    session: i32,
+    key: String,
+    value: Vec<u8>,
+    lease: String,
+    dry_run: Option<bool>,
+) -> Result<Mutation<()>, LogicError> {
     let lease = i64::from_str(&lease).map_err(|e| {
         warn!("ttl parse error: {e}");
         LogicError::ArgumentError
     })?;
+    if dry_run::enabled(dry_run) {
+        let plan = dry_run::plan_put(session, "kv_put_with_lease", key, value.len(), Some(lease.to_string())).await?;
+        return Ok(Mutation::Plan(plan));
+    }
+    etcd::check_writable(&session)?;
+    let recorded = kv_macro::is_recording(session).then(|| (key.clone(), value.clone()));
+    let mut connector = etcd::get_connector(&session)?;
     connector.kv_put_with_lease(key, value, lease).await?;
+    //  lease只在当前集群有效，录制时不包含lease
+    if let Some((key, value)) = recorded {
+        kv_macro::record(session, kv_macro::put_op(key, value, None, false));
+    }
+    Ok(Mutation::Done(()))
+}
+
+fn render_template(session: &i32, text: &str) -> Result<String, LogicError> {
+    let variables = etcd::get_connection_info_optional(session)
+        .map(|info| info.variables.clone())
+        .unwrap_or_default();
+    template::render(text, &variables)
+        .map_err(|missing| LogicError::IllegalArgument(format!("Undefined variables: {}", missing.join(", "))))
+}
+
+/// 预览值模板替换连接变量后的内容，引用了未定义的变量时返回错误
+#[tauri::command]
+pub fn kv_render_template(session: i32, value: String) -> Result<String, LogicError> {
+    render_template(&session, &value)
+}
+
+/// 将值中出现的连接变量值替换为 `${NAME}`，用于比较不同环境中的同一份配置
+#[tauri::command]
+pub fn kv_templatize_value(session: i32, value: String) -> Result<String, LogicError> {
+    let variables = etcd::get_connection_info_optional(&session)
+        .map(|info| info.variables.clone())
+        .unwrap_or_default();
+    Ok(template::templatize(&value, &variables))
+}
+
+#[tauri::command]
+pub async fn kv_delete(session: i32, keys: Vec<String>, dry_run: Option<bool>) -> Result<Mutation<usize>, LogicError> {
+    if dry_run::enabled(dry_run) {
+        return Ok(Mutation::Plan(dry_run::plan_delete(session, "kv_delete", &keys).await?));
+    }
+    etcd::check_writable(&session)?;
+    let settings = get_settings().await?;
+    let recorded = kv_macro::is_recording(session).then(|| keys.clone());
+    let mut connector = etcd::get_connector(&session)?;
+    if settings.trash_retention_days == 0 {
+        let size = connector.kv_delete(keys.clone()).await?;
+        drop(connector);
+        content_hint::remove_hints(session, &keys, false).await;
+        if let Some(keys) = recorded {
+            kv_macro::record(session, MacroOp::Delete { keys });
+        }
+        return Ok(Mutation::Done(size));
+    }
+    let (size, deleted) = connector.kv_delete_with_prev(keys.clone()).await?;
+    if let Some(keys) = recorded {
+        kv_macro::record(session, MacroOp::Delete { keys });
+    }
+    drop(connector);
+    content_hint::remove_hints(session, &keys, false).await;
+    let scope = etcd::cluster_scope(&session)?;
+    trash::add(&settings, &scope, etcd::get_connection_name(&session), deleted);
+    Ok(Mutation::Done(size))
+}
+
+/// 重命名key，保留值和绑定的lease，目标key已存在时不执行并返回false
+#[tauri::command]
+pub async fn kv_rename(session: i32, from: String, to: String, dry_run: Option<bool>) -> Result<Mutation<bool>, LogicError> {
+    if dry_run::enabled(dry_run) {
+        return Ok(Mutation::Plan(dry_run::plan_rename(session, "kv_rename", from, to).await?));
+    }
+    etcd::check_writable(&session)?;
+    let renamed = {
+        let mut connector = etcd::get_connector(&session)?;
+        connector.kv_rename(from.clone(), to.clone()).await?
+    };
+    if renamed {
+        content_hint::move_hint(session, &from, &to).await;
+        kv_macro::record(session, MacroOp::Rename { from, to });
+    }
+    Ok(Mutation::Done(renamed))
+}
+
+/// 开始录制连接上的写入、删除和重命名操作
+#[tauri::command]
+pub fn macro_start_recording(session: i32) -> Result<(), LogicError> {
+    kv_macro::start_recording(session)
+}
+
+/// 停止录制并返回录制的操作，通过 `macro_save` 保存
+#[tauri::command]
+pub fn macro_stop_recording(session: i32) -> Result<Vec<MacroOp>, LogicError> {
+    Ok(kv_macro::stop_recording(session))
+}
+
+#[tauri::command]
+pub fn macro_is_recording(session: i32) -> Result<bool, LogicError> {
+    Ok(kv_macro::is_recording(session))
+}
+
+#[tauri::command]
+pub fn macro_list() -> Result<Vec<KvMacro>, LogicError> {
+    Ok(kv_macro::list())
+}
+
+/// 保存宏，同名的宏会被覆盖
+#[tauri::command]
+pub fn macro_save(definition: KvMacro) -> Result<(), LogicError> {
+    kv_macro::save(definition)
+}
+
+#[tauri::command]
+pub fn macro_delete(name: String) -> Result<bool, LogicError> {
+    kv_macro::delete(&name)
+}
+
+/// 在目标连接上执行宏，`dry_run` 为true时只返回变更计划。遇到失败时停止，已执行的操作不会回滚
+#[tauri::command]
+pub async fn run_macro(session: i32, name: String, dry_run: Option<bool>) -> Result<Mutation<MacroRunResult>, LogicError> {
+    let definition = kv_macro::get(&name)?;
+    if dry_run::enabled(dry_run) {
+        return Ok(Mutation::Plan(kv_macro::plan(session, &definition).await?));
+    }
+    etcd::check_writable(&session)?;
+    Ok(Mutation::Done(kv_macro::run(session, &definition).await))
+}
+
+/// 预览删除前缀将影响的key数量、大小及绑定的lease，返回的token用于确认删除
+#[tauri::command]
+pub async fn kv_delete_prefix_preview(session: i32, prefix: String) -> Result<DeletePreview, LogicError> {
+    delete_preview::preview(session, prefix).await
+}
+
+/// 删除预览过的前缀，预览后前缀下的数据发生变化时不删除。试运行时不使用token
+#[tauri::command]
+pub async fn kv_delete_prefix(session: i32, token: String, dry_run: Option<bool>) -> Result<Mutation<DeletePrefixResult>, LogicError> {
+    if dry_run::enabled(dry_run) {
+        return Ok(Mutation::Plan(delete_preview::plan(session, "kv_delete_prefix", &token)?));
+    }
+    etcd::check_writable(&session)?;
+    Ok(Mutation::Done(delete_preview::delete_prefix(session, token).await?))
+}
+
+/// 列出回收站中的key，`session` 不为空时只列出该连接所在集群的记录
+#[tauri::command]
+pub async fn kv_list_trash(session: Option<i32>) -> Result<Vec<TrashEntry>, LogicError> {
+    let settings = get_settings().await?;
+    let scope = match session {
+        Some(session) => Some(etcd::cluster_scope(&session)?),
+        None => None,
+    };
+    Ok(trash::list(&settings, scope.as_deref()))
+}
+
+/// 从回收站恢复key，`overwrite` 为false时跳过已存在的key，恢复成功的记录从回收站移除
+#[tauri::command]
+pub async fn kv_restore_trash(
+    session: i32,
+    ids: Vec<String>,
+    overwrite: bool,
+    dry_run: Option<bool>,
+) -> Result<Mutation<TrashRestoreResult>, LogicError> {
+    let scope = etcd::cluster_scope(&session)?;
+    let entries = trash::get(&ids);
+    let encrypt_key = get_settings().await?.connection_conf_encrypt_key;
+    if dry_run::enabled(dry_run) {
+        let entries = entries
+            .into_iter()
+            .filter(|stored| stored.entry.scope == scope)
+            .map(|stored| Ok((stored.raw_key(), stored.entry.key.clone(), stored.value(&encrypt_key)?.len())))
+            .collect::<Result<Vec<_>, LogicError>>()?;
+        return Ok(Mutation::Plan(dry_run::plan_restore(session, "kv_restore_trash", entries, overwrite).await?));
+    }
+    etcd::check_writable(&session)?;
+    let mut result = TrashRestoreResult::default();
+    {
+        let mut connector = etcd::get_connector(&session)?;
+        for stored in entries {
+            if stored.entry.scope != scope {
+                result.skipped.push(stored.entry.key);
+                continue;
+            }
+            //  无法解密的值不恢复，保留在回收站中
+            let Ok(value) = stored.value(&encrypt_key) else {
+                result.skipped.push(stored.entry.key);
+                continue;
+            };
+            let key = stored.raw_key();
+            if connector.kv_put_raw(key, value, !overwrite).await? {
+                result.restored.push(stored.entry.id);
+            } else {
+                result.skipped.push(stored.entry.key);
+            }
+        }
+    }
+    trash::remove(&result.restored);
+    Ok(Mutation::Done(result))
+}
+
+/// 永久删除回收站中的记录，`ids` 为空时清空回收站，返回删除的数量
+#[tauri::command]
+pub async fn kv_purge_trash(ids: Option<Vec<String>>) -> Result<usize, LogicError> {
+    Ok(trash::purge(ids.as_deref()))
+}
+
+/// 授权一个新的lease，并将前缀下的所有key绑定到该lease，到期后这些key会被自动删除
+#[tauri::command]
+pub async fn kv_attach_prefix_lease(
+    session: i32,
+    prefix: String,
+    ttl: i64,
+    dry_run: Option<bool>,
+) -> Result<Mutation<LeaseAttachResult>, LogicError> {
+    if ttl <= 0 {
+        return Err(LogicError::IllegalArgument(String::from("The ttl must be greater than 0")));
+    }
+    if dry_run::enabled(dry_run) {
+        let plan = dry_run::plan_attach_prefix_lease(session, "kv_attach_prefix_lease", prefix, ttl).await?;
+        return Ok(Mutation::Plan(plan));
+    }
+    etcd::check_writable(&session)?;
+    let mut connector = etcd::get_connector(&session)?;
+    let lease = connector.lease_grant(ttl, None).await?;
+    //  没有任何key绑定成功时撤销lease，避免遗留无用的lease
+    let mut result = match connector.kv_attach_lease_prefix(prefix, lease).await {
+        Ok(result) if result.attached > 0 => result,
+        Ok(mut result) => {
+            connector.lease_revoke(lease).await?;
+            result.lease = 0;
+            result
+        }
+        Err(e) => {
+            if let Err(revoke_error) = connector.lease_revoke(lease).await {
+                warn!("Failed to revoke lease {} after attach failed: {:?}", lease, revoke_error);
+            }
+            return Err(e.into());
+        }
+    };
+    result.ttl = ttl;
+    Ok(Mutation::Done(result))
+}
+
+/// 比较后删除，只有当key当前的值或修改版本与期望一致时才删除，返回是否删除成功
+#[tauri::command]
+pub async fn kv_delete_if(
+    session: i32,
+    key: String,
+    value: Option<Vec<u8>>,
+    mod_revision: Option<i64>,
+    dry_run: Option<bool>,
+) -> Result<Mutation<bool>, LogicError> {
+    if value.is_none() && mod_revision.is_none() {
+        return Err(LogicError::ArgumentError);
+    }
+    if dry_run::enabled(dry_run) {
+        let plan = dry_run::plan_delete_if(session, "kv_delete_if", key, value.as_deref(), mod_revision).await?;
+        return Ok(Mutation::Plan(plan));
+    }
+    etcd::check_writable(&session)?;
+    let mut connector = etcd::get_connector(&session)?;
+    let deleted = connector.kv_delete_if(key, value, mod_revision).await?;
+    Ok(Mutation::Done(deleted))
+}
+
+/// 订阅前缀的变化统计，变化通过 `prefix_changes` 事件推送
+#[tauri::command]
+pub async fn subscribe_prefix_changes(session: i32, prefix: String, window: Window) -> Result<(), LogicError> {
+    etcd::subscribe_prefix_changes(session, prefix, window).await
+}
+
+/// 订阅前缀下key的变更事件，合并和限流后通过 `watch_events` 事件批量推送
+#[tauri::command]
+pub async fn subscribe_prefix_events(session: i32, prefix: String, window: Window) -> Result<(), LogicError> {
+    etcd::subscribe_prefix_events(session, prefix, window).await
+}
+
+#[tauri::command]
+pub fn unsubscribe_prefix_events(session: i32, prefix: String) -> Result<(), LogicError> {
+    etcd::unsubscribe_prefix_events(session, prefix);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn unsubscribe_prefix_changes(session: i32, prefix: String) -> Result<(), LogicError> {
+    etcd::unsubscribe_prefix_changes(session, prefix);
+    Ok(())
+}
+
+/// 标记前缀已查看，清零变化统计
+#[tauri::command]
+pub fn mark_prefix_viewed(session: i32, prefix: String) -> Result<Option<PrefixChangeCounter>, LogicError> {
+    Ok(etcd::reset_prefix_changes(session, prefix))
+}
+
+#[tauri::command]
+pub fn get_prefix_changes(session: i32) -> Result<Vec<PrefixChangeCounter>, LogicError> {
+    Ok(etcd::get_prefix_changes(session))
+}
+
+/// 开始统计前缀下的key数量，之后根据监听事件实时更新，不需要重新扫描
+#[tauri::command]
+pub async fn track_prefix_count(session: i32, prefix: String) -> Result<(), LogicError> {
+    etcd::get_connector(&session)?;
+    save_tracked_prefixes(session, |prefixes| {
+        if !prefixes.contains(&prefix) {
+            prefixes.push(prefix.clone());
+        }
+    })
+    .await?;
+    prefix_counter::track(session, prefix);
     Ok(())
 }
 
 #[tauri::command]
-pub async fn kv_delete(session: i32, keys: Vec<String>) -> Result<usize, LogicError> {
+pub async fn untrack_prefix_count(session: i32, prefix: String) -> Result<(), LogicError> {
+    save_tracked_prefixes(session, |prefixes| prefixes.retain(|p| *p != prefix)).await?;
+    prefix_counter::untrack(session, prefix);
+    Ok(())
+}
+
+/// 已保存的连接同时将统计的前缀保存到配置中，下次连接时自动统计
+async fn save_tracked_prefixes(session: i32, update: impl FnOnce(&mut Vec<String>)) -> Result<(), LogicError> {
+    let info = etcd::get_connection_info_optional(&session).map(|mut info| {
+        update(&mut info.tracked_prefixes);
+        info.value().clone()
+    });
+    if let Some(info) = info {
+        save_connection_info(info).await?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_prefix_counts(session: i32) -> Result<Vec<PrefixKeyCount>, LogicError> {
+    Ok(prefix_counter::list(session))
+}
+
+/// 在JSON和YAML之间转换值，用于以另一种格式编辑后再转换回原格式保存。
+/// 转换会丢失信息（注释、锚点等）或结果无法还原时返回错误
+#[tauri::command]
+pub fn convert_value_format(content: String, from: FormatLanguage, to: FormatLanguage) -> Result<String, LogicError> {
+    format_convert::convert(&content, &from, &to).map_err(LogicError::IllegalArgument)
+}
+
+/// 读取key的内容类型提示，连接未开启内容类型提示时返回空
+#[tauri::command]
+pub async fn get_content_hint(session: i32, key: String) -> Result<Option<ContentHint>, LogicError> {
+    let Some(config) = content_hint::config_of(session) else {
+        return Ok(None);
+    };
+    let mut connector = etcd::get_connector(&session)?;
+    content_hint::get(&mut connector, &config, &key).await
+}
+
+/// 记录用户为key选择的内容类型，`hint` 为空时删除记录。连接未开启内容类型提示时不写入并返回false
+#[tauri::command]
+pub async fn set_content_hint(session: i32, key: String, hint: Option<ContentHint>) -> Result<bool, LogicError> {
+    let Some(config) = content_hint::config_of(session) else {
+        return Ok(false);
+    };
+    etcd::check_writable(&session)?;
+    let mut connector = etcd::get_connector(&session)?;
+    content_hint::set(&mut connector, &config, &key, hint).await?;
+    Ok(true)
+}
+
+/// 设置历史版本读取模式，设置后浏览器中的所有读取都基于该版本，传空时恢复读取最新数据
+#[tauri::command]
+pub async fn set_read_revision(session: i32, revision: Option<i64>) -> Result<(), LogicError> {
+    let mut connector = etcd::get_connector(&session)?;
+    if let Some(rev) = revision {
+        let bounds = connector.get_keyspace_bounds().await?;
+        if rev < bounds.oldest_revision || rev > bounds.revision {
+            return Err(LogicError::IllegalArgument(format!(
+                "Revision {} is out of range [{}, {}]",
+                rev, bounds.oldest_revision, bounds.revision
+            )));
+        }
+    }
+    connector.set_read_revision(revision);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_read_revision(session: i32) -> Result<Option<i64>, LogicError> {
+    let connector = etcd::get_connector(&session)?;
+    Ok(connector.get_read_revision())
+}
+
+/// 获取时间点附近的版本，数据来自连接期间的观察记录，只能覆盖连接建立之后的时间
+#[tauri::command]
+pub fn list_revisions_near_time(session: i32, timestamp: u64, limit: usize) -> Result<Vec<RevisionTimeSample>, LogicError> {
+    Ok(etcd::list_revisions_near_time(session, timestamp, limit))
+}
+
+/// 执行 etcdctl 风格的控制台命令，支持 get、put、del。
+///
+/// 写操作需要先以 `confirmed = false` 调用获取操作描述，用户确认后再以 `confirmed = true` 执行
+#[tauri::command]
+pub async fn kv_console(session: i32, command: String, confirmed: bool) -> Result<ConsoleResult, LogicError> {
+    let command = console::parse(&command).map_err(LogicError::IllegalArgument)?;
+    if command.is_write() {
+        etcd::check_writable(&session)?;
+    }
+    let mut connector = etcd::get_connector(&session)?;
+    console::execute(&mut connector, command, confirmed).await
+}
+
+/// 导出加密前缀的密钥（base64），用于在团队成员之间共享
+#[tauri::command]
+pub async fn kv_encryption_export_key(session: i32, prefix: String) -> Result<String, LogicError> {
+    etcd::session_lock::ensure_unlocked()?;
+    let mut connector = etcd::get_connector(&session)?;
+    connector.export_encryption_key(&prefix)
+}
+
+/// 导入加密前缀的密钥（base64）并保存到系统钥匙串
+#[tauri::command]
+pub async fn kv_encryption_import_key(session: i32, prefix: String, key: String) -> Result<(), LogicError> {
+    let mut connector = etcd::get_connector(&session)?;
+    connector.import_encryption_key(&prefix, &key)
+}
+
+/// 文件作为值写入时的大小上限，etcd默认的单个请求大小上限为1.5MiB
+const MAX_FILE_VALUE_SIZE: usize = 1536 * 1024;
+
+/// 将本地文件的内容写入为key的值，`compress` 为true时使用gzip压缩后写入。返回写入的值大小
+#[tauri::command]
+pub async fn kv_put_from_file(
+    session: i32,
+    key: String,
+    filepath: String,
+    compress: bool,
+    ttl: Option<i64>,
+    dry_run: Option<bool>,
+) -> Result<Mutation<usize>, LogicError> {
+    let path = Path::new(&filepath);
+    if !path.exists() {
+        return Err(LogicError::ResourceNotExist("File not exists"));
+    }
+    let value = file_util::read_file_chunked(path, compress, MAX_FILE_VALUE_SIZE)
+        .await?
+        .ok_or_else(|| LogicError::IllegalArgument(format!(
+            "The file is too large, the value size limit is {} bytes",
+            MAX_FILE_VALUE_SIZE
+        )))?;
+    let size = value.len();
+    if dry_run::enabled(dry_run) {
+        let lease = ttl.filter(|ttl| *ttl > 0).map(|ttl| format!("<new lease, ttl={}>", ttl));
+        let plan = dry_run::plan_put(session, "kv_put_from_file", key, size, lease).await?;
+        return Ok(Mutation::Plan(plan));
+    }
+    etcd::check_writable(&session)?;
+
+    let mut connector = etcd::get_connector(&session)?;
+    connector.kv_put(key, value, ttl).await?;
+    Ok(Mutation::Done(size))
+}
+
+/// 将key的值保存到本地文件，`decompress` 为true且值为gzip格式时解压后保存。返回写入的字节数
+#[tauri::command]
+pub async fn kv_save_to_file(session: i32, key: String, filepath: String, decompress: bool) -> Result<u64, LogicError> {
+    let kv = {
+        let mut connector = etcd::get_connector(&session)?;
+        connector.kv_get(key).await?
+    };
+    if let Some(e) = kv.decrypt_error {
+        return Err(LogicError::MsgError(e));
+    }
+    let written = file_util::write_file_chunked(Path::new(&filepath), &kv.value, decompress).await?;
+    Ok(written)
+}
+
+/// 对比key的值与本地文件的内容，用于以git中的文件为准管理配置
+#[tauri::command]
+pub async fn kv_diff_file(session: i32, key: String, filepath: String) -> Result<FileDiffResult, LogicError> {
+    let path = Path::new(&filepath);
+    if !path.exists() {
+        return Err(LogicError::ResourceNotExist("File not exists"));
+    }
+    let file_content = file_util::read_file_chunked(path, false, MAX_FILE_VALUE_SIZE)
+        .await?
+        .ok_or_else(|| LogicError::IllegalArgument(format!(
+            "The file is too large, the value size limit is {} bytes",
+            MAX_FILE_VALUE_SIZE
+        )))?;
+    let kv = {
+        let mut connector = etcd::get_connector(&session)?;
+        match connector.kv_get(key).await {
+            Ok(kv) => Some(kv),
+            Err(LogicError::ResourceNotExist(_)) => None,
+            Err(e) => return Err(e),
+        }
+    };
+    if let Some(e) = kv.as_ref().and_then(|kv| kv.decrypt_error.clone()) {
+        return Err(LogicError::MsgError(e));
+    }
+    let (key_exists, mod_revision, value) = match kv {
+        Some(kv) => (true, kv.mod_revision, kv.value),
+        None => (false, 0, vec![]),
+    };
+
+    let identical = key_exists && value == file_content;
+    let binary = text_diff::is_binary(&value) || text_diff::is_binary(&file_content);
+    let lines = if binary {
+        vec![]
+    } else {
+        text_diff::diff_bytes(&value, &file_content)
+    };
+    let added = lines.iter().filter(|l| l.kind == text_diff::DiffKind::Insert).count();
+    let removed = lines.iter().filter(|l| l.kind == text_diff::DiffKind::Delete).count();
+    Ok(FileDiffResult {
+        key_exists,
+        mod_revision,
+        identical,
+        binary,
+        added,
+        removed,
+        lines,
+    })
+}
+
+/// 将本地文件的内容写回key，仅当key的修改版本仍为对比时的 `mod_revision` 时写入，
+/// 返回false表示key在对比之后已被修改
+#[tauri::command]
+pub async fn kv_apply_file(session: i32, key: String, filepath: String, mod_revision: i64) -> Result<bool, LogicError> {
+    etcd::check_writable(&session)?;
+    let path = Path::new(&filepath);
+    if !path.exists() {
+        return Err(LogicError::ResourceNotExist("File not exists"));
+    }
+    let value = file_util::read_file_chunked(path, false, MAX_FILE_VALUE_SIZE)
+        .await?
+        .ok_or_else(|| LogicError::IllegalArgument(format!(
+            "The file is too large, the value size limit is {} bytes",
+            MAX_FILE_VALUE_SIZE
+        )))?;
+    let mut connector = etcd::get_connector(&session)?;
+    Ok(connector.kv_put_if_mod_revision(key, value, mod_revision).await?.is_some())
+}
+
+/// 将前缀下的key拉取到本地目录，每个key一个文件，便于使用git查看和提交修改
+#[tauri::command]
+pub async fn kv_sync_pull(
+    session: i32,
+    prefix: String,
+    dir: String,
+    force: bool,
+    dry_run: Option<bool>,
+) -> Result<Mutation<DirSyncResult>, LogicError> {
+    dir_sync::pull(session, prefix, Path::new(&dir), force, dry_run::enabled(dry_run)).await
+}
+
+/// 将本地目录中修改的文件推送回etcd，上次拉取后在etcd中被修改过的key记为冲突
+#[tauri::command]
+pub async fn kv_sync_push(
+    session: i32,
+    prefix: String,
+    dir: String,
+    dry_run: Option<bool>,
+) -> Result<Mutation<DirSyncResult>, LogicError> {
+    dir_sync::push(session, prefix, Path::new(&dir), dry_run::enabled(dry_run)).await
+}
+
+/// 将前缀下所有仍可读取的历史修改（压缩版本之后）导出为归档文件，用于审计。
+/// `compress` 为true时使用gzip压缩
+#[tauri::command]
+pub async fn kv_export_history(session: i32, prefix: String, filepath: String, compress: bool) -> Result<HistoryExportResult, LogicError> {
+    let task = task_center::register(TaskKind::Export, file_name(&filepath), Some(session), Some(filepath.clone()));
+    let result = export_history(session, prefix, &filepath, compress).await;
+    task.complete(&result);
+    result
+}
+
+async fn export_history(session: i32, prefix: String, filepath: &str, compress: bool) -> Result<HistoryExportResult, LogicError> {
+    let (bounds, mut records, reached) = {
+        let mut connector = etcd::get_connector(&session)?;
+        let bounds = connector.get_keyspace_bounds().await?;
+        let (records, reached) = connector
+            .kv_history_records(prefix.clone(), bounds.oldest_revision, bounds.revision)
+            .await?;
+        (bounds, records, reached)
+    };
+    content_hint::hide_hint_keys(session, &mut records, |r| r.key.as_bytes());
+    let keys = records
+        .iter()
+        .map(|r| r.key.as_str())
+        .collect::<std::collections::HashSet<&str>>()
+        .len();
+    let archive = HistoryArchive {
+        prefix,
+        exported_at: etcd::now_timestamp() as u64,
+        compact_revision: bounds.compact_revision,
+        start_revision: bounds.oldest_revision,
+        end_revision: reached.min(bounds.revision),
+        truncated: reached < bounds.revision,
+        records,
+    };
+    let content = serde_json::to_vec_pretty(&archive)?;
+    let content = if compress {
+        file_util::gzip(&content)?
+    } else {
+        content
+    };
+    let bytes = file_util::write_file_chunked(Path::new(filepath), &content, false).await?;
+    Ok(HistoryExportResult {
+        records: archive.records.len(),
+        keys,
+        start_revision: archive.start_revision,
+        end_revision: archive.end_revision,
+        truncated: archive.truncated,
+        bytes,
+    })
+}
+
+/// 开启或关闭会话的键值读缓存，开启后重复读取同一个key将直接从内存返回
+#[tauri::command]
+pub async fn kv_set_value_cache(session: i32, enabled: bool) -> Result<(), LogicError> {
+    let mut connector = etcd::get_connector(&session)?;
+    connector.set_value_cache_enabled(enabled).await?;
+    Ok(())
+}
+
+/// 获取键值读缓存的统计信息，未开启时返回空
+#[tauri::command]
+pub async fn kv_get_value_cache_stats(session: i32) -> Result<Option<ValueCacheStats>, LogicError> {
+    let connector = etcd::get_connector(&session)?;
+    Ok(connector.value_cache_stats())
+}
+
+/// 开始编辑key前获取编辑锁，其他会话或workbench用户正在编辑时返回其信息用于提示
+#[tauri::command]
+pub async fn kv_acquire_edit_lock(session: i32, key: String) -> Result<EditLockResult, LogicError> {
+    etcd::acquire_edit_lock(session, key).await
+}
+
+#[tauri::command]
+pub async fn kv_release_edit_lock(session: i32, key: String) -> Result<(), LogicError> {
+    etcd::release_edit_lock(session, key).await
+}
+
+/// 批量导入的公共流程，按服务端事务限制拆分后写入
+fn file_name(filepath: &str) -> String {
+    Path::new(filepath)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| String::from(filepath))
+}
+
+/// 批量写入并登记为后台任务，可在任务中心查看进度或取消
+pub(crate) async fn import_kvs(
+    session: i32,
+    kvs: Vec<(String, Vec<u8>)>,
+    kind: TaskKind,
+    name: impl Into<String>,
+) -> Result<BatchPutResult, LogicError> {
+    etcd::check_writable(&session)?;
+    let task = task_center::register(kind, name, Some(session), None);
+    let result = {
+        let mut connector = etcd::get_connector(&session)?;
+        connector.kv_put_batch(kvs, Some(&task)).await.map_err(LogicError::from)
+    };
+    match &result {
+        Ok(BatchPutResult { error_msg: Some(e), .. }) if !task.is_cancelled() => task.fail(e.clone()),
+        _ => task.complete(&result),
+    }
+    result
+}
+
+/// 试运行时返回批量写入的变更计划，否则登记为后台任务写入
+async fn import_or_plan(
+    session: i32,
+    kvs: Vec<(String, Vec<u8>)>,
+    command: &str,
+    name: impl Into<String>,
+    dry_run: Option<bool>,
+) -> Result<Mutation<BatchPutResult>, LogicError> {
+    if dry_run::enabled(dry_run) {
+        return Ok(Mutation::Plan(dry_run::plan_put_batch(session, command, &kvs).await?));
+    }
+    Ok(Mutation::Done(import_kvs(session, kvs, TaskKind::Import, name).await?))
+}
+
+#[tauri::command]
+pub async fn kv_put_batch(session: i32, kvs: Vec<KeyValuePair>, dry_run: Option<bool>) -> Result<Mutation<BatchPutResult>, LogicError> {
+    let kvs = kvs.into_iter().map(|kv| (kv.key, kv.value)).collect();
+    import_or_plan(session, kvs, "kv_put_batch", "Batch put", dry_run).await
+}
+
+/// 从JSON文件导入键值对，支持 `{"key": "value"}` 对象或 `[{"key": "", "value": ""}]` 数组，
+/// `prefix` 不为空时添加到每个key之前
+#[tauri::command]
+pub async fn kv_import_json(
+    session: i32,
+    filepath: String,
+    prefix: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<Mutation<BatchPutResult>, LogicError> {
+    let content = tokio::fs::read_to_string(&filepath).await?;
+    let json: serde_json::Value = serde_json::from_str(&content)?;
+    let prefix = prefix.unwrap_or_default();
+    let to_bytes = |v: &serde_json::Value| match v {
+        serde_json::Value::String(s) => s.clone().into_bytes(),
+        other => other.to_string().into_bytes(),
+    };
+    let kvs: Vec<(String, Vec<u8>)> = match &json {
+        serde_json::Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| (format!("{}{}", prefix, k), to_bytes(v)))
+            .collect(),
+        serde_json::Value::Array(arr) => arr
+            .iter()
+            .filter_map(|item| {
+                let key = item.get("key")?.as_str()?;
+                let value = item.get("value").map(to_bytes).unwrap_or_default();
+                Some((format!("{}{}", prefix, key), value))
+            })
+            .collect(),
+        _ => return Err(LogicError::IllegalArgument(String::from("Unsupported import file format"))),
+    };
+    import_or_plan(session, kvs, "kv_import_json", file_name(&filepath), dry_run).await
+}
+
+/// 从 Consul（`consul kv export`）或 ZooKeeper 导出文件导入键值对，`format` 为 `consul` 或 `zookeeper`，
+/// key按 `mappings` 转换前缀后批量写入
+#[tauri::command]
+pub async fn kv_import_external(
+    session: i32,
+    filepath: String,
+    format: String,
+    mappings: Vec<PrefixMapping>,
+    dry_run: Option<bool>,
+) -> Result<Mutation<BatchPutResult>, LogicError> {
+    let content = tokio::fs::read_to_string(&filepath).await?;
+    let kvs = match format.as_str() {
+        "consul" => kv_import::parse_consul_export(&content),
+        "zookeeper" => kv_import::parse_zookeeper_dump(&content),
+        _ => Err(format!("Unsupported import format: {}", format)),
+    }
+    .map_err(LogicError::IllegalArgument)?;
+    let kvs = kvs
+        .into_iter()
+        .map(|(key, value)| (kv_import::map_key(&key, &mappings), value))
+        .collect();
+    import_or_plan(session, kvs, "kv_import_external", file_name(&filepath), dry_run).await
+}
+
+/// 将源连接中以 `prefix` 开头的键值对复制到目标连接，`target_prefix` 替换原前缀
+#[tauri::command]
+pub async fn kv_transfer(
+    session: i32,
+    target_session: i32,
+    prefix: String,
+    target_prefix: Option<String>,
+) -> Result<BatchPutResult, LogicError> {
+    let mut kvs = {
+        let mut connector = etcd::get_connector(&session)?;
+        connector.kv_range(prefix.clone(), true, false, 0, None).await?
+    };
+    content_hint::hide_hint_keys(session, &mut kvs, |kv| kv.key.as_bytes());
+    if let Some(kv) = kvs.iter().find(|kv| kv.decrypt_error.is_some()) {
+        return Err(LogicError::MsgError(format!("Unable to decrypt value of key: {}", kv.key)));
+    }
+    let target_prefix = target_prefix.unwrap_or_else(|| prefix.clone());
+    let kvs = kvs
+        .into_iter()
+        .map(|kv| {
+            let key = kv.key.strip_prefix(prefix.as_str()).unwrap_or(&kv.key);
+            (format!("{}{}", target_prefix, key), kv.value)
+        })
+        .collect();
+    import_kvs(target_session, kvs, TaskKind::Transfer, format!("{} -> {}", prefix, target_prefix)).await
+}
+
+/// 在指定时长内统计整个键空间中修改最频繁的key和前缀，结果通过 `hot_key_report` 事件推送。
+/// `depth` 为统计前缀时截取的路径层级
+#[tauri::command]
+pub async fn kv_start_hot_key_sampling(
+    session: i32,
+    duration_seconds: u64,
+    depth: usize,
+    top: usize,
+    window: Window,
+) -> Result<(), LogicError> {
+    let splitter = etcd::get_key_separator(session).await?;
+    etcd::start_hot_key_sampling(session, duration_seconds, splitter, depth, top, window).await
+}
+
+#[tauri::command]
+pub fn kv_stop_hot_key_sampling(session: i32) -> Result<(), LogicError> {
+    etcd::stop_hot_key_sampling(session);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn kv_get_hot_key_report(session: i32) -> Result<Option<HotKeyReport>, LogicError> {
+    Ok(etcd::get_hot_key_report(session))
+}
+
+/// 开始将源连接中的前缀持续镜像到目标连接
+#[tauri::command]
+pub fn mirror_start(config: MirrorConfig, window: Window) -> Result<MirrorStatus, LogicError> {
+    mirror::start_mirror(config, window)
+}
+
+#[tauri::command]
+pub fn mirror_pause(id: i32, window: Window) -> Result<(), LogicError> {
+    mirror::pause_mirror(id, window)
+}
+
+#[tauri::command]
+pub fn mirror_resume(id: i32, window: Window) -> Result<(), LogicError> {
+    mirror::resume_mirror(id, window)
+}
+
+#[tauri::command]
+pub fn mirror_stop(id: i32) -> Result<(), LogicError> {
+    mirror::stop_mirror(id);
+    Ok(())
+}
+
+/// 启动审计任务，持续将整个keyspace的变更写入按天分割的压缩文件，返回任务ID，通过 `cancel_task` 停止
+#[tauri::command]
+pub fn kv_start_audit_stream(config: AuditStreamConfig) -> Result<i32, LogicError> {
+    audit_stream::start(config)
+}
+
+/// 启动追加任务，持续将key的变更追加到本地文件，返回任务ID，通过 `cancel_task` 停止
+#[tauri::command]
+pub fn kv_start_key_tail(config: KeyTailConfig) -> Result<i32, LogicError> {
+    key_tail::start(config)
+}
+
+#[tauri::command]
+pub fn mirror_list() -> Result<Vec<MirrorStatus>, LogicError> {
+    Ok(mirror::list_mirrors())
+}
+
+/// 分析前缀下可能已废弃的数据：长时间未修改、值为空以及遗留的锁或选举key
+#[tauri::command]
+pub async fn kv_analyze_garbage(session: i32, options: GarbageOptions) -> Result<GarbageReport, LogicError> {
+    let (revision, kvs) = {
+        let mut connector = etcd::get_connector(&session)?;
+        connector.kv_get_prefix_values(options.prefix.clone()).await?
+    };
+    let timeline = etcd::get_revision_timeline(session);
+    Ok(garbage::analyze(&kvs, revision, &timeline, etcd::now_timestamp() as u64, &options))
+}
+
+/// 删除用户在分析报告中选中的key，分析后被修改过的key不会删除
+#[tauri::command]
+pub async fn kv_cleanup_garbage(
+    session: i32,
+    items: Vec<GarbageItem>,
+    dry_run: Option<bool>,
+) -> Result<Mutation<GarbageCleanupResult>, LogicError> {
+    if dry_run::enabled(dry_run) {
+        let items: Vec<(String, i64)> = items.into_iter().map(|item| (item.key, item.mod_revision)).collect();
+        return Ok(Mutation::Plan(dry_run::plan_delete_unchanged(session, "kv_cleanup_garbage", &items).await?));
+    }
+    etcd::check_writable(&session)?;
     let mut connector = etcd::get_connector(&session)?;
-    let size = connector.kv_delete(keys).await?;
-    Ok(size)
-}
\ No newline at end of file
+    let mut result = GarbageCleanupResult::default();
+    for item in items {
+        if connector.kv_delete_if(item.key.clone(), None, Some(item.mod_revision)).await? {
+            result.deleted.push(item.key);
+        } else {
+            result.conflicts.push(item.key);
+        }
+    }
+    Ok(Mutation::Done(result))
+}
@@ -1,10 +1,13 @@
 use std::str::FromStr;
 
 use log::warn;
+use tauri::Window;
 
 use crate::error::LogicError;
 use crate::etcd;
-use crate::transport::kv::SerializableLeaseInfo;
+use crate::etcd::dry_run;
+use crate::transport::dry_run::{Mutation, PlannedOp};
+use crate::transport::kv::{LeaseKeepAliveState, SerializableLeaseInfo};
 
 #[tauri::command]
 pub async fn leases(session: i32) -> Result<Vec<String>, LogicError> {
@@ -25,9 +28,12 @@ pub async fn lease_get(session: i32, lease: String) -> Result<SerializableLeaseI
 }
 
 #[tauri::command]
-pub async fn lease_grant(session: i32, ttl: i64, lease: Option<String>) -> Result<String, LogicError> {
-    let mut connector = etcd::get_connector(&session)?;
-
+pub async fn lease_grant(
+    session: i32,
+    ttl: i64,
+    lease: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<Mutation<String>, LogicError> {
     let lease = if let Some(s) = lease {
         Some(i64::from_str(&s).map_err(|e| {
             warn!("lease parse error: {e}");
@@ -36,18 +42,58 @@ pub async fn lease_grant(session: i32, ttl: i64, lease: Option<String>) -> Resul
     } else {
         None
     };
+    if dry_run::enabled(dry_run) {
+        let target = lease.map(|id| id.to_string()).unwrap_or_else(|| String::from("<new lease>"));
+        let op = PlannedOp {
+            lease: Some(format!("ttl={}", ttl)),
+            ..PlannedOp::new("leaseGrant", target)
+        };
+        return Ok(Mutation::Plan(dry_run::plan("lease_grant", vec![op])));
+    }
 
+    etcd::check_writable(&session)?;
+    let mut connector = etcd::get_connector(&session)?;
     let lease_id = connector.lease_grant(ttl, lease).await?;
-    Ok(lease_id.to_string())
+    Ok(Mutation::Done(lease_id.to_string()))
 }
 
 #[tauri::command]
-pub async fn lease_revoke(session: i32, lease: String) -> Result<(), LogicError> {
-    let mut connector = etcd::get_connector(&session)?;
+pub async fn lease_revoke(session: i32, lease: String, dry_run: Option<bool>) -> Result<Mutation<()>, LogicError> {
     let lease = i64::from_str(&lease).map_err(|e| {
         warn!("lease parse error: {e}");
         LogicError::ArgumentError
     })?;
+    if dry_run::enabled(dry_run) {
+        return Ok(Mutation::Plan(dry_run::plan_lease_revoke(session, "lease_revoke", lease).await?));
+    }
+    etcd::check_writable(&session)?;
+    let mut connector = etcd::get_connector(&session)?;
     connector.lease_revoke(lease).await?;
+    Ok(Mutation::Done(()))
+}
+
+/// 由 workbench 代为续约lease，续约状态通过 `lease_keep_alive` 事件推送
+#[tauri::command]
+pub async fn lease_keep_alive_start(session: i32, lease: String, window: Window) -> Result<(), LogicError> {
+    let lease = i64::from_str(&lease).map_err(|e| {
+        warn!("lease parse error: {e}");
+        LogicError::ArgumentError
+    })?;
+    etcd::check_writable(&session)?;
+    etcd::start_lease_keep_alive(session, lease, window).await
+}
+
+#[tauri::command]
+pub fn lease_keep_alive_stop(session: i32, lease: String) -> Result<(), LogicError> {
+    let lease = i64::from_str(&lease).map_err(|e| {
+        warn!("lease parse error: {e}");
+        LogicError::ArgumentError
+    })?;
+    etcd::stop_lease_keep_alive(session, lease);
     Ok(())
-}
\ No newline at end of file
+}
+
+#[tauri::command]
+pub fn lease_keep_alive_list(session: i32) -> Result<Vec<LeaseKeepAliveState>, LogicError> {
+    Ok(etcd::list_lease_keep_alive(session))
+}
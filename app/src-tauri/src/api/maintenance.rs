@@ -1,142 +1,476 @@
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicI32, Ordering};
 
-use dashmap::DashMap;
-use lazy_static::lazy_static;
-use tauri::Manager;
-use tokio::sync::{mpsc, oneshot};
+use log::info;
+use tauri::{Manager, Window};
+use tokio::select;
+use uuid::Uuid;
+use crate::api::event_bus::{self, EventStream};
+use crate::api::task_center::{self, TaskInfo, TaskKind, TaskState};
 use crate::error::LogicError;
 use crate::etcd;
-use crate::etcd::etcd_connector::SnapshotTask;
-use crate::transport::maintenance::{SerializableCluster, SnapshotInfo, SnapshotState, SnapshotStateEvent};
+use crate::etcd::{clock_drift, cluster_status, dry_run, key_index, maintenance_scheduler, operation_queue, raft_lag, report_scheduler, snapshot_transfer};
+use crate::transport::maintenance::{
+    ClockDriftReport, EndpointCapabilities, EndpointLatency, HealthState, MaintenanceAction, MaintenanceOperation, MaintenanceQueueEvent, MaintenanceRun,
+    MaintenanceSchedule, QueuedOperation, RaftLagReport, SerializableCluster, ServerFeature, SnapshotInfo, SnapshotState, SnapshotStateEvent,
+};
+use crate::api::connection::save_connection_info;
+use crate::transport::dry_run::{Mutation, PlannedOp};
+use crate::transport::report::{
+    AuthReport, ClusterReport, KeyspaceReport, MemberStatusReport, PrefixCount, ReportDeliveryResult, ReportSchedule,
+};
+use crate::utils::cron::CronSchedule;
+use crate::utils::report_util::{self, ReportFormat};
+use crate::utils::state_cache;
 
-#[allow(unused)]
-static SNAPSHOT_TASK_ID_COUNTER: AtomicI32 = AtomicI32::new(1);
-lazy_static! {
-    static ref SNAPSHOT_TASK_POOL:DashMap<i32, SnapshotTask> = DashMap::with_capacity(1);
-}
 
 #[tauri::command]
 pub async fn get_cluster(session: i32) -> Result<SerializableCluster, LogicError> {
-    let mut connector = etcd::get_connector(&session)?;
-    let cluster = connector.cluster_get().await?;
+    let mut cluster = {
+        let mut connector = etcd::get_connector(&session)?;
+        connector.cluster_get().await?
+    };
+    cluster_status::fill_member_status(session, &mut cluster).await;
+    state_cache::cache_cluster(session, &cluster).await;
     Ok(cluster)
 }
 
+/// 获取最近一次健康检查的结果，尚未完成检查时返回 None
+#[tauri::command]
+pub fn get_health_state(session: i32) -> Result<Option<HealthState>, LogicError> {
+    Ok(etcd::get_health_state(&session))
+}
 
+/// 开始定时采样会话连接及每个成员的请求延迟
 #[tauri::command]
-pub async fn maintenance_defragment(session: i32) -> Result<(), LogicError> {
-    let mut connector = etcd::get_connector(&session)?;
-    connector.maintenance_defragment().await?;
+pub async fn start_latency_sampler(session: i32) -> Result<(), LogicError> {
+    etcd::start_latency_sampler(session)
+}
+
+#[tauri::command]
+pub fn stop_latency_sampler(session: i32) -> Result<(), LogicError> {
+    etcd::stop_latency_sampler(session);
     Ok(())
 }
 
+#[tauri::command]
+pub fn get_latency_samples(session: i32) -> Result<Vec<EndpointLatency>, LogicError> {
+    Ok(etcd::get_latency_samples(session))
+}
+
+/// 获取连接端点支持的功能，用于在界面上禁用不可用的操作
+#[tauri::command]
+pub fn get_endpoint_capabilities(session: i32) -> Result<EndpointCapabilities, LogicError> {
+    etcd::get_capabilities(&session)
+}
+
+/// 添加集群成员，`learner` 为true时以 learner 身份加入，返回新成员的ID
+#[tauri::command]
+pub async fn cluster_add_member(
+    session: i32,
+    urls: Vec<String>,
+    learner: bool,
+    dry_run: Option<bool>,
+) -> Result<Mutation<Option<String>>, LogicError> {
+    if learner {
+        etcd::check_feature(&session, ServerFeature::Learner)?;
+    }
+    if dry_run::enabled(dry_run) {
+        let op = if learner { "memberAddLearner" } else { "memberAdd" };
+        return Ok(Mutation::Plan(dry_run::plan("cluster_add_member", vec![PlannedOp::new(op, urls.join(","))])));
+    }
+    etcd::check_writable(&session)?;
+    let mut connector = etcd::get_connector(&session)?;
+    if learner {
+        Ok(Mutation::Done(Some(connector.cluster_add_learner(urls).await?)))
+    } else {
+        connector.cluster_add_member(urls).await?;
+        Ok(Mutation::Done(None))
+    }
+}
+
+/// 将 learner 成员提升为投票成员
+#[tauri::command]
+pub async fn cluster_promote_member(session: i32, id: String, dry_run: Option<bool>) -> Result<Mutation<()>, LogicError> {
+    etcd::check_feature(&session, ServerFeature::Learner)?;
+    if dry_run::enabled(dry_run) {
+        return Ok(Mutation::Plan(dry_run::plan("cluster_promote_member", vec![PlannedOp::new("memberPromote", id)])));
+    }
+    etcd::check_writable(&session)?;
+    let mut connector = etcd::get_connector(&session)?;
+    connector.cluster_promote_member(id).await?;
+    Ok(Mutation::Done(()))
+}
+
+/// 数据量大的成员整理碎片耗时较长，可通过 `timeout_seconds` 指定本次操作的超时时间。
+/// 同一连接有其他运维操作时排队等待，排队位置通过 `maintenance_queue` 事件推送
+#[tauri::command]
+pub async fn maintenance_defragment(
+    window: Window,
+    session: i32,
+    timeout_seconds: Option<u64>,
+    dry_run: Option<bool>,
+) -> Result<Mutation<()>, LogicError> {
+    etcd::check_maintenance_supported(&session)?;
+    if dry_run::enabled(dry_run) {
+        let endpoint = etcd::get_connection_info_optional(&session)
+            .map(|info| format!("{}:{}", info.connection.host, info.connection.port))
+            .unwrap_or_default();
+        return Ok(Mutation::Plan(dry_run::plan("maintenance_defragment", vec![PlannedOp::new("defragment", endpoint)])));
+    }
+    etcd::check_writable(&session)?;
+    let deadline = etcd::call_deadline(timeout_seconds)?;
+    let _permit = operation_queue::acquire(session, MaintenanceOperation::Defragment, |ahead| {
+        let event = MaintenanceQueueEvent {
+            session,
+            operation: MaintenanceOperation::Defragment,
+            ahead,
+        };
+        event_bus::publish(&window, EventStream::Progress, "maintenance_queue", event);
+    })
+    .await?;
+    let mut connector = etcd::get_connector(&session)?;
+    connector.with_deadline(deadline).maintenance_defragment().await?;
+    Ok(Mutation::Done(()))
+}
+
+/// 连接的运维操作队列，第一个为正在执行的操作
+#[tauri::command]
+pub fn list_maintenance_queue(session: i32) -> Vec<QueuedOperation> {
+    operation_queue::list(session)
+}
+
+/// 创建快照任务，进度通过任务中心的 `task_state` 事件推送，同时保留 `snapshot_state` 事件
 #[tauri::command]
 pub async fn maintenance_create_snapshot_task(
     app: tauri::AppHandle,
     session: i32,
     filepath: String,
 ) -> Result<SnapshotInfo, LogicError> {
-    let mut connector = etcd::get_connector(&session)?;
-    let id = SNAPSHOT_TASK_ID_COUNTER.fetch_add(1, Ordering::SeqCst);
+    etcd::check_maintenance_supported(&session)?;
+    etcd::get_connector(&session)?;
+
+    let file_path = PathBuf::from(filepath);
+    let file_name = if let Some(name) = file_path.file_name() {
+        String::from_utf8_lossy(name.as_encoded_bytes()).to_string()
+    } else {
+        String::from("Snapshot Task")
+    };
 
-    let task_id = id.clone();
-    let (watch_sender, mut receiver) = mpsc::channel::<(u64, u64, Option<String>)>(128);
+    let mut handle = task_center::register(
+        TaskKind::Snapshot,
+        file_name,
+        Some(session),
+        Some(file_path.to_string_lossy().to_string()),
+    );
+    let task_id = handle.id();
+    let window_label = etcd::get_session_window(&session);
 
     tokio::spawn(async move {
-
-        while let Some(state) = receiver.recv().await {
-            let task = SNAPSHOT_TASK_POOL.get_mut(&task_id);
-            if let Some(mut t) = task {
-                if let Some(err_msg) = &state.2 {
-                    t.state.error_msg = Some(err_msg.clone());
-                } else {
-                    t.state.received += state.0;
-                    t.state.remain = state.1;
-                }
-
-                let state = t.state.clone();
-
-                app.emit_all("snapshot_state", SnapshotStateEvent {
-                    id: task_id,
-                    state: state,
-                }).unwrap();
+        //  只推送给发起快照的连接所在窗口
+        let report = |state: &SnapshotState| {
+            let event = SnapshotStateEvent {
+                id: task_id,
+                state: state.clone(),
+            };
+            match &window_label {
+                Some(label) => event_bus::publish_to(&app, label, EventStream::Progress, "snapshot_state", event),
+                None => app.emit_all("snapshot_state", event).unwrap(),
             }
+        };
+        let mut state = SnapshotState::default();
+        //  与同一连接的其他运维操作互斥，排队期间可以停止任务
+        let queued = select! {
+            result = operation_queue::acquire(session, MaintenanceOperation::Snapshot, |ahead| {
+                handle.message(format!("Queued, {} operations ahead", ahead));
+            }) => result,
+            _ = handle.cancelled() => return,
+        };
+        let result = async {
+            let _permit = queued?;
+            let stream = {
+                let mut connector = etcd::get_connector(&session)?;
+                connector.maintenance_snapshot_stream().await?
+            };
+            snapshot_transfer::download(session, stream, &file_path, &mut handle, &mut state, &report).await
         }
-    });
-    
-    let file_path = PathBuf::from(filepath);
-    let folder = if let Some(path) = file_path.parent() {
-        path.to_string_lossy().to_string()
-    } else {
-        #[cfg(windows)]
-        {
-            String::from("C:\\")
+        .await;
+        match result {
+            //  任务被停止时句柄释放后记为已取消
+            Ok(()) if !handle.is_cancelled() => handle.succeed(),
+            Ok(()) => {}
+            Err(e) => {
+                let err_msg = task_center::error_message(&e);
+                state.error_msg = Some(err_msg.clone());
+                report(&state);
+                handle.fail(err_msg);
+            }
         }
+    });
+
+    Ok(task_center::get_task(task_id)
+        .map(to_snapshot_info)
+        .ok_or(LogicError::ResourceNotExist("The snapshot task does not exist"))?)
+}
+
+fn to_snapshot_info(task: TaskInfo) -> SnapshotInfo {
+    let target = PathBuf::from(task.target.unwrap_or_default());
+    let folder = match target.parent() {
+        Some(path) => path.to_string_lossy().to_string(),
+        None => {
+            #[cfg(windows)]
+            {
+                String::from("C:\\")
+            }
 
-        #[cfg(target_family = "unix")]
-        {
-            String::from("/")
+            #[cfg(target_family = "unix")]
+            {
+                String::from("/")
+            }
         }
     };
-
-    let file_name = if let Some(name) = file_path.file_name() {
-        String::from_utf8_lossy(name.as_encoded_bytes()).to_string()
-    } else {
-        String::from("Snapshot Task")
+    let error_msg = match task.state {
+        TaskState::Cancelled => Some(String::from("Stopped")),
+        _ => task.error_msg,
     };
+    SnapshotInfo {
+        name: task.name,
+        folder,
+        id: task.id,
+        state: SnapshotState {
+            received: task.processed,
+            remain: task.total.saturating_sub(task.processed),
+            total: (task.total > 0).then_some(task.total),
+            error_msg,
+            ..Default::default()
+        },
+    }
+}
 
-    let (stop_sender, stop_receiver) = oneshot::channel();
+#[tauri::command]
+pub fn maintenance_stop_snapshot_task(task_id: i32) -> Result<(), LogicError> {
+    task_center::cancel(task_id);
+    Ok(())
+}
 
-    let info = SnapshotInfo {
-        id: task_id,
-        name: file_name.clone(),
-        folder: folder.clone(),
-        state: SnapshotState::default()
-    };
+#[tauri::command]
+pub fn maintenance_remove_snapshot_task(task_id: i32) -> Result<(), LogicError> {
+    task_center::remove(task_id)
+}
 
-    let task = SnapshotTask {
-        name: file_name,
-        folder,
-        state: SnapshotState::default(),
-        stop_notifier: Some(stop_sender),
-    };
-    SNAPSHOT_TASK_POOL.insert(id, task);
+#[tauri::command]
+pub fn maintenance_list_snapshot_task() -> Result<Vec<SnapshotInfo>, LogicError> {
+    Ok(task_center::list(Some(TaskKind::Snapshot))
+        .into_iter()
+        .map(to_snapshot_info)
+        .collect())
+}
+
+/// 报告中一级路径统计最多扫描的key数量
+const REPORT_KEY_SAMPLE_LIMIT: i64 = 100000;
+
+/// 生成集群报告并写入文件，格式由 `format`（html、markdown）或文件后缀决定
+#[tauri::command]
+pub async fn generate_cluster_report(
+    session: i32,
+    filepath: String,
+    format: Option<String>,
+) -> Result<ClusterReport, LogicError> {
+    let report = collect_cluster_report(session).await?;
+    let content = report_util::render(&report, ReportFormat::parse(format.as_deref(), &filepath));
+    tokio::fs::write(&filepath, content).await?;
+    info!("Generated cluster report: {}", filepath);
+    Ok(report)
+}
 
-    connector.maintenance_snapshot(file_path, watch_sender, stop_receiver).await?;
-    
-    Ok(info)
+/// 连接未保存或未配置定时报告时返回空
+#[tauri::command]
+pub fn get_report_schedule(session: i32) -> Option<ReportSchedule> {
+    etcd::get_connection_info_optional(&session).and_then(|info| info.report_schedule.clone())
 }
 
+/// 保存连接的定时报告配置，为空时删除配置。只有已保存的连接可以配置
 #[tauri::command]
-pub fn maintenance_stop_snapshot_task(task_id: i32) -> Result<(), LogicError> {
-    let task = SNAPSHOT_TASK_POOL.get_mut(&task_id);
-    if let Some(mut t) = task {
-        let stop_notifier = t.stop_notifier.take();
-        if let Some(sender) = stop_notifier {
-            sender.send(()).unwrap();
+pub async fn set_report_schedule(session: i32, schedule: Option<ReportSchedule>) -> Result<(), LogicError> {
+    if let Some(schedule) = &schedule {
+        schedule.validate().map_err(LogicError::IllegalArgument)?;
+    }
+    let info = etcd::get_connection_info_optional(&session).map(|info| info.value().clone());
+    let Some(mut info) = info else {
+        return Err(LogicError::IllegalArgument(String::from("The connection is not saved")));
+    };
+    let last_run = info.report_schedule.as_ref().map(|s| s.last_run).unwrap_or(0);
+    info.report_schedule = schedule.map(|mut s| {
+        s.last_run = last_run;
+        s
+    });
+    //  同一连接在多个窗口中打开时，所有会话使用相同的配置
+    for id in etcd::list_named_sessions(&info.name) {
+        if let Some(mut other) = etcd::get_connection_info_optional(&id) {
+            other.report_schedule = info.report_schedule.clone();
         }
-        t.state.error_msg = Some(String::from("Stopped"));
     }
+    save_connection_info(info).await?;
     Ok(())
 }
 
+/// 立即生成报告并推送，用于验证定时报告的配置
 #[tauri::command]
-pub fn maintenance_remove_snapshot_task(task_id: i32) -> Result<(), LogicError> {
-    SNAPSHOT_TASK_POOL.remove(&task_id);
-    Ok(())
+pub async fn send_cluster_report(session: i32) -> Result<ReportDeliveryResult, LogicError> {
+    report_scheduler::run(session).await
 }
 
+/// 连接的定时运维计划，附带下次执行时间
 #[tauri::command]
-pub fn maintenance_list_snapshot_task() -> Result<Vec<SnapshotInfo>, LogicError> {
-    let mut list = Vec::new();
-    for entry in SNAPSHOT_TASK_POOL.iter() {
-        list.push(SnapshotInfo {
-            name: entry.value().name.clone(),
-            folder: entry.value().folder.clone(),
-            id: entry.key().clone(),
-            state: entry.state.clone(),
-        });
+pub fn list_maintenance_schedules(session: i32) -> Vec<MaintenanceSchedule> {
+    let mut schedules = etcd::get_connection_info_optional(&session)
+        .map(|info| info.maintenance_schedules.clone())
+        .unwrap_or_default();
+    let now = chrono::Local::now();
+    for schedule in schedules.iter_mut().filter(|s| s.enabled) {
+        schedule.next_run = CronSchedule::parse(&schedule.cron)
+            .ok()
+            .and_then(|cron| cron.next_after(&now))
+            .map(|time| time.timestamp_millis() as u64);
+    }
+    schedules
+}
+
+/// 新增或更新运维计划，`id` 为空时新增，返回保存后的计划
+#[tauri::command]
+pub async fn set_maintenance_schedule(session: i32, mut schedule: MaintenanceSchedule) -> Result<MaintenanceSchedule, LogicError> {
+    CronSchedule::parse(&schedule.cron).map_err(LogicError::IllegalArgument)?;
+    if let MaintenanceAction::Compact { retain_revisions, retain_hours } = &schedule.action {
+        if retain_revisions.is_none() && retain_hours.is_none() {
+            return Err(LogicError::IllegalArgument(String::from("Retain revisions or hours is required")));
+        }
     }
-    Ok(list)
-}
\ No newline at end of file
+    etcd::check_writable(&session)?;
+    etcd::check_maintenance_supported(&session)?;
+    let result = etcd::get_connection_info_optional(&session);
+    let Some(mut info) = result else {
+        return Err(LogicError::IllegalArgument(String::from("The connection is not saved")));
+    };
+    if schedule.id.is_empty() {
+        schedule.id = Uuid::new_v4().simple().to_string();
+    }
+    schedule.next_run = None;
+    match info.maintenance_schedules.iter_mut().find(|s| s.id == schedule.id) {
+        Some(s) => *s = schedule.clone(),
+        None => info.maintenance_schedules.push(schedule.clone()),
+    }
+    save_connection_info(info.value().clone()).await?;
+    Ok(schedule)
+}
+
+#[tauri::command]
+pub async fn remove_maintenance_schedule(session: i32, id: String) -> Result<(), LogicError> {
+    let result = etcd::get_connection_info_optional(&session);
+    if let Some(mut info) = result {
+        info.maintenance_schedules.retain(|s| s.id != id);
+        save_connection_info(info.value().clone()).await?;
+    }
+    Ok(())
+}
+
+/// 立即执行运维计划，不影响定时执行
+#[tauri::command]
+pub async fn run_maintenance_schedule(session: i32, id: String) -> Result<MaintenanceRun, LogicError> {
+    etcd::check_writable(&session)?;
+    etcd::check_maintenance_supported(&session)?;
+    let schedule = etcd::get_connection_info_optional(&session)
+        .and_then(|info| info.maintenance_schedules.iter().find(|s| s.id == id).cloned())
+        .ok_or(LogicError::ResourceNotExist("The maintenance schedule does not exist"))?;
+    maintenance_scheduler::run_schedule(session, schedule).await
+}
+
+/// 最近的运维计划执行记录，新的在前
+#[tauri::command]
+pub fn list_maintenance_runs(session: i32) -> Vec<MaintenanceRun> {
+    maintenance_scheduler::list_runs(session)
+}
+
+/// 诊断成员间的时钟漂移及leader频繁切换的风险，结果中的警告用于界面展示提示
+#[tauri::command]
+pub async fn diagnose_clock_drift(session: i32) -> Result<ClockDriftReport, LogicError> {
+    clock_drift::diagnose(session).await
+}
+
+/// 采样各follower落后leader的已应用日志条数，返回结果包含本会话的历史采样
+#[tauri::command]
+pub async fn get_raft_lag(session: i32) -> Result<RaftLagReport, LogicError> {
+    raft_lag::sample(session).await
+}
+
+pub async fn collect_cluster_report(session: i32) -> Result<ClusterReport, LogicError> {
+    let delimiter = etcd::get_key_separator(session).await?;
+    let key_index = etcd::get_key_index(&session);
+    let mut connector = etcd::get_connector(&session)?;
+
+    let mut cluster = connector.cluster_get().await?;
+    let (alarms, _) = connector.health_check().await?;
+    let bounds = connector.get_keyspace_bounds().await?;
+    //  `kv_count` 只统计 `/` 之后的key，报告需要整个键空间的数量
+    let (total_keys, _) = connector.kv_count_all().await?;
+
+    let (top_prefixes, sampled) = if let Some(index) = key_index {
+        let index = index.read().unwrap();
+        (index.next_segments(&[], delimiter.as_bytes(), 50), false)
+    } else {
+        let keys: Vec<Vec<u8>> = connector
+            .kv_get_keys_with_prefix(vec![], REPORT_KEY_SAMPLE_LIMIT)
+            .await?
+            .into_iter()
+            .map(|kv| kv.key.into_bytes())
+            .collect();
+        let sampled = (keys.len() as i64) < total_keys;
+        (key_index::next_segments_of_keys(&keys, &[], delimiter.as_bytes(), 50), sampled)
+    };
+
+    let auth = match connector.user_list().await {
+        Ok(users) => AuthReport {
+            users,
+            roles: connector.role_list().await.unwrap_or_default(),
+            error_msg: None,
+        },
+        Err(e) => AuthReport {
+            users: vec![],
+            roles: vec![],
+            error_msg: Some(e.to_string()),
+        },
+    };
+    drop(connector);
+
+    cluster_status::fill_member_status(session, &mut cluster).await;
+    let member_status = cluster
+        .members
+        .iter()
+        .map(|member| MemberStatusReport {
+            id: member.id.clone(),
+            name: member.name.clone(),
+            endpoint: member.client_uri.first().cloned(),
+            status: member.status.clone(),
+            error_msg: member.error_msg.clone(),
+        })
+        .collect();
+
+    Ok(ClusterReport {
+        generate_time: etcd::now_timestamp() as u64,
+        cluster,
+        member_status,
+        alarms,
+        keyspace: KeyspaceReport {
+            revision: bounds.revision,
+            compact_revision: bounds.compact_revision,
+            total_keys,
+            top_prefixes: top_prefixes
+                .into_iter()
+                .map(|(prefix, count)| PrefixCount {
+                    prefix: String::from_utf8_lossy(&prefix).to_string(),
+                    count,
+                })
+                .collect(),
+            sampled,
+        },
+        auth,
+    })
+}
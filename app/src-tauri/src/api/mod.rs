@@ -6,4 +6,13 @@ pub mod settings;
 pub mod lease;
 pub mod role;
 pub mod windows;
+pub mod updater;
+pub mod deep_link;
+pub mod quick_open;
+pub mod event_bus;
+pub mod sandbox;
+pub mod task_center;
+pub mod plugin;
+pub mod shutdown;
 
+pub mod actions;
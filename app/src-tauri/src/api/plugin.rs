@@ -0,0 +1,44 @@
+use std::path::Path;
+
+use crate::error::LogicError;
+use crate::transport::plugin::{PluginCommandResult, PluginGrant, PluginInfo};
+use crate::utils::{plugin_host, value_plugin};
+
+#[tauri::command]
+pub fn plugin_list() -> Result<Vec<PluginInfo>, LogicError> {
+    Ok(value_plugin::list())
+}
+
+/// 重新扫描插件目录并加载插件
+#[tauri::command]
+pub async fn plugin_reload() -> Result<Vec<PluginInfo>, LogicError> {
+    Ok(value_plugin::load_all())
+}
+
+/// 从包含 `plugin.json` 的目录安装插件，安装前会校验接口版本。
+/// `grant` 为用户授予的key访问权限，未授予时插件命令不能访问任何key
+#[tauri::command]
+pub async fn plugin_install(dir: String, grant: Option<PluginGrant>) -> Result<PluginInfo, LogicError> {
+    value_plugin::install(Path::new(&dir), grant.unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn plugin_remove(name: String) -> Result<(), LogicError> {
+    value_plugin::remove(&name)
+}
+
+#[tauri::command]
+pub async fn plugin_set_enabled(name: String, enabled: bool) -> Result<Vec<PluginInfo>, LogicError> {
+    value_plugin::set_enabled(&name, enabled)
+}
+
+/// 执行插件提供的自定义命令，插件只能访问用户授予的前缀下的key，执行结果以面板的形式返回
+#[tauri::command]
+pub async fn plugin_run_command(
+    session: i32,
+    plugin: String,
+    command: String,
+    args: Option<serde_json::Value>,
+) -> Result<PluginCommandResult, LogicError> {
+    plugin_host::execute(session, plugin, command, args.unwrap_or_default()).await
+}
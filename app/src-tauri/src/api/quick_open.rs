@@ -0,0 +1,115 @@
+use std::collections::VecDeque;
+
+use lazy_static::lazy_static;
+use log::warn;
+use tokio::sync::Mutex;
+
+use crate::api::connection::get_connection_list;
+use crate::error::LogicError;
+use crate::etcd;
+use crate::transport::connection::{QuickOpenItem, RecentKey};
+use crate::utils::file_util;
+use crate::utils::fuzzy::fuzzy_match;
+
+/// 最多保存的最近访问key数量
+const MAX_RECENT_KEYS: usize = 200;
+
+lazy_static! {
+    static ref RECENT_KEYS: Mutex<Option<VecDeque<RecentKey>>> = Mutex::new(None);
+}
+
+async fn load_recent_keys() -> VecDeque<RecentKey> {
+    let path = file_util::get_recent_keys_file_path();
+    match tokio::fs::read(&path).await {
+        Ok(content) => serde_json::from_slice(&content).unwrap_or_else(|e| {
+            warn!("Failed to parse recent keys file: {e}");
+            VecDeque::new()
+        }),
+        Err(_) => VecDeque::new(),
+    }
+}
+
+/// 记录最近访问的key，只记录已保存的连接
+pub async fn record_recent_key(session: i32, key: &str) {
+    let Some(connection) = etcd::get_connection_info_optional(&session).map(|info| info.name.clone()) else {
+        return;
+    };
+
+    let mut lock = RECENT_KEYS.lock().await;
+    if lock.is_none() {
+        *lock = Some(load_recent_keys().await);
+    }
+    let recent_keys = lock.as_mut().unwrap();
+    recent_keys.retain(|r| !(r.connection == connection && r.key == key));
+    recent_keys.push_front(RecentKey {
+        connection,
+        key: String::from(key),
+        time: etcd::now_timestamp() as u64,
+    });
+    recent_keys.truncate(MAX_RECENT_KEYS);
+
+    let result = match serde_json::to_vec(recent_keys) {
+        Ok(content) => tokio::fs::write(file_util::get_recent_keys_file_path(), content)
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        warn!("Failed to save recent keys: {e}");
+    }
+}
+
+/// 获取最近访问的key
+#[tauri::command]
+pub async fn get_recent_keys() -> Vec<RecentKey> {
+    let mut lock = RECENT_KEYS.lock().await;
+    if lock.is_none() {
+        *lock = Some(load_recent_keys().await);
+    }
+    lock.as_ref().unwrap().iter().cloned().collect()
+}
+
+/// 快速打开搜索，在已保存的连接、收藏的key以及最近访问的key中模糊匹配。
+///
+/// 查询为空时按最近访问顺序返回
+#[tauri::command]
+pub async fn quick_open_search(query: String, limit: usize) -> Result<Vec<QuickOpenItem>, LogicError> {
+    let connections = get_connection_list().await?;
+    let recent_keys = get_recent_keys().await;
+
+    let mut items = Vec::new();
+    let mut push = |kind: &str, connection: &str, key: Option<&str>, order: usize| {
+        let text = key.unwrap_or(connection);
+        if let Some((score, matched)) = fuzzy_match(&query, text) {
+            items.push((
+                order,
+                QuickOpenItem {
+                    kind: String::from(kind),
+                    connection: String::from(connection),
+                    key: key.map(String::from),
+                    score,
+                    matched,
+                },
+            ));
+        }
+    };
+
+    for (i, recent) in recent_keys.iter().enumerate() {
+        push("recentKey", &recent.connection, Some(&recent.key), i);
+    }
+    let offset = recent_keys.len();
+    for (i, info) in connections.iter().enumerate() {
+        push("connection", &info.name, None, offset + i);
+        for key in &info.key_collection {
+            //  已在最近访问中的收藏不重复展示
+            if recent_keys.iter().any(|r| r.connection == info.name && &r.key == key) {
+                continue;
+            }
+            push("bookmark", &info.name, Some(key), offset + i);
+        }
+    }
+
+    //  分数相同时最近访问的优先
+    items.sort_by(|a, b| b.1.score.cmp(&a.1.score).then(a.0.cmp(&b.0)));
+    Ok(items.into_iter().take(limit).map(|(_, item)| item).collect())
+}
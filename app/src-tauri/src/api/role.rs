@@ -1,6 +1,13 @@
+use std::collections::HashMap;
+use std::fs;
+
 use crate::error::LogicError;
 use crate::etcd;
-use crate::transport::user::SerializablePermission;
+use crate::etcd::dry_run;
+use crate::transport::dry_run::{Mutation, PlannedOp};
+use crate::transport::user::{
+    AuthApplyResult, AuthDefinition, AuthExport, AuthImportResult, AuthPlanStep, AuthSimulation, OperationPrecheck, PrecheckOperation, SerializablePermission,
+};
 
 #[tauri::command]
 pub async fn role_list(session: i32) -> Result<Vec<String>, LogicError> {
@@ -10,17 +17,25 @@ pub async fn role_list(session: i32) -> Result<Vec<String>, LogicError> {
 }
 
 #[tauri::command]
-pub async fn role_add(session: i32, role: String) -> Result<(), LogicError> {
+pub async fn role_add(session: i32, role: String, dry_run: Option<bool>) -> Result<Mutation<()>, LogicError> {
+    if dry_run::enabled(dry_run) {
+        return Ok(Mutation::Plan(dry_run::plan("role_add", vec![PlannedOp::new("roleAdd", role.as_str())])));
+    }
+    etcd::check_writable(&session)?;
     let mut connector = etcd::get_connector(&session)?;
     connector.role_add(role).await?;
-    Ok(())
+    Ok(Mutation::Done(()))
 }
 
 #[tauri::command]
-pub async fn role_delete(session: i32, role: String) -> Result<(), LogicError> {
+pub async fn role_delete(session: i32, role: String, dry_run: Option<bool>) -> Result<Mutation<()>, LogicError> {
+    if dry_run::enabled(dry_run) {
+        return Ok(Mutation::Plan(dry_run::plan("role_delete", vec![PlannedOp::new("roleDelete", role.as_str())])));
+    }
+    etcd::check_writable(&session)?;
     let mut connector = etcd::get_connector(&session)?;
     connector.role_delete(role).await?;
-    Ok(())
+    Ok(Mutation::Done(()))
 }
 
 #[tauri::command]
@@ -31,15 +46,154 @@ pub async fn role_get_permissions(session: i32, role: String) -> Result<Vec<Seri
 }
 
 #[tauri::command]
-pub async fn role_grant_permission(session: i32, role: String, permission: SerializablePermission) -> Result<(), LogicError> {
+pub async fn role_grant_permission(session: i32, role: String, permission: SerializablePermission, dry_run: Option<bool>) -> Result<Mutation<()>, LogicError> {
+    if dry_run::enabled(dry_run) {
+        return Ok(Mutation::Plan(dry_run::plan("role_grant_permission", vec![PlannedOp::new("roleGrantPermission", dry_run::permission_target(&role, &permission))])));
+    }
+    etcd::check_writable(&session)?;
     let mut connector = etcd::get_connector(&session)?;
     connector.role_grant_permission(role, permission).await?;
-    Ok(())
+    Ok(Mutation::Done(()))
 }
 
 #[tauri::command]
-pub async fn role_revoke_permission(session: i32, role: String, permission: SerializablePermission) -> Result<(), LogicError> {
+pub async fn role_revoke_permission(session: i32, role: String, permission: SerializablePermission, dry_run: Option<bool>) -> Result<Mutation<()>, LogicError> {
+    if dry_run::enabled(dry_run) {
+        return Ok(Mutation::Plan(dry_run::plan("role_revoke_permission", vec![PlannedOp::new("roleRevokePermission", dry_run::permission_target(&role, &permission))])));
+    }
+    etcd::check_writable(&session)?;
     let mut connector = etcd::get_connector(&session)?;
     connector.role_revoke_permission(role, permission).await?;
+    Ok(Mutation::Done(()))
+}
+
+/// 对比权限定义（JSON 或 YAML）与集群当前状态，返回变更计划但不执行
+#[tauri::command]
+pub async fn auth_plan(session: i32, definition: String) -> Result<Vec<AuthPlanStep>, LogicError> {
+    let definition = AuthDefinition::parse(&definition).map_err(LogicError::IllegalArgument)?;
+    let mut connector = etcd::get_connector(&session)?;
+    let steps = connector.auth_plan(&definition).await?;
+    Ok(steps)
+}
+
+/// 按顺序执行预览过的变更计划 `steps`，失败时回滚已执行的步骤。
+/// 执行前按权限定义重新对比集群状态，与预览的计划不一致时说明集群已被修改，拒绝执行
+#[tauri::command]
+pub async fn auth_apply(
+    session: i32,
+    definition: String,
+    steps: Vec<AuthPlanStep>,
+    dry_run: Option<bool>,
+) -> Result<Mutation<AuthApplyResult>, LogicError> {
+    let definition = AuthDefinition::parse(&definition).map_err(LogicError::IllegalArgument)?;
+    let mut connector = etcd::get_connector(&session)?;
+    let current = connector.auth_plan(&definition).await?;
+    let unchanged = current.len() == steps.len() && current.iter().zip(&steps).all(|(a, b)| a.same_as(b));
+    if !unchanged {
+        return Err(LogicError::IllegalArgument(String::from(
+            "The cluster has changed since the plan was previewed, please preview the plan again",
+        )));
+    }
+    if dry_run::enabled(dry_run) {
+        return Ok(Mutation::Plan(dry_run::plan("auth_apply", dry_run::auth_step_ops(&steps))));
+    }
+    etcd::check_writable(&session)?;
+    Ok(Mutation::Done(connector.auth_apply(steps).await))
+}
+
+/// 将集群的用户、角色及权限导出为YAML文件，密码以占位符代替
+#[tauri::command]
+pub async fn auth_export(session: i32, filepath: String) -> Result<(), LogicError> {
+    let export = {
+        let mut connector = etcd::get_connector(&session)?;
+        connector.auth_export().await?
+    };
+    let content = serde_yaml::to_string(&export).map_err(|e| LogicError::MsgError(e.to_string()))?;
+    fs::write(filepath, content)?;
     Ok(())
-}
\ No newline at end of file
+}
+
+/// 导入权限配置文件，已存在的资源不会重复创建。`passwords` 为新建用户的密码，文件中为占位符的新用户必须提供
+#[tauri::command]
+pub async fn auth_import(
+    session: i32,
+    filepath: String,
+    passwords: HashMap<String, String>,
+    dry_run: Option<bool>,
+) -> Result<Mutation<AuthImportResult>, LogicError> {
+    let content = fs::read_to_string(filepath)?;
+    let export = AuthExport::parse(&content).map_err(LogicError::IllegalArgument)?;
+    if dry_run::enabled(dry_run) {
+        let (new_users, steps) = {
+            let mut connector = etcd::get_connector(&session)?;
+            connector.auth_import_plan(&export, &passwords).await?
+        };
+        let mut ops: Vec<PlannedOp> = new_users.iter().map(|user| PlannedOp::new("userAdd", user.as_str())).collect();
+        ops.extend(dry_run::auth_step_ops(&steps));
+        return Ok(Mutation::Plan(dry_run::plan("auth_import", ops)));
+    }
+    etcd::check_writable(&session)?;
+    let mut connector = etcd::get_connector(&session)?;
+    Ok(Mutation::Done(connector.auth_import(&export, &passwords).await?))
+}
+
+/// 模拟用户对key或前缀的读写权限，并给出授予权限的角色。
+/// 传入权限定义（JSON 或 YAML）时按定义应用后的状态计算，用于应用前验证
+#[tauri::command]
+pub async fn auth_simulate(
+    session: i32,
+    user: String,
+    key: String,
+    prefix: bool,
+    definition: Option<String>,
+) -> Result<AuthSimulation, LogicError> {
+    let definition = match definition.filter(|d| !d.trim().is_empty()) {
+        Some(d) => Some(AuthDefinition::parse(&d).map_err(LogicError::IllegalArgument)?),
+        None => None,
+    };
+    let mut connector = etcd::get_connector(&session)?;
+    let simulation = connector.auth_simulate(&user, &key, prefix, definition.as_ref()).await?;
+    Ok(simulation)
+}
+
+/// 预检当前连接的用户能否执行操作，供前端禁用没有权限的按钮。
+/// 维护和权限管理操作通过用户信息判断是否为root用户，key的读写通过不产生修改的请求探测
+#[tauri::command]
+pub async fn precheck_operation(
+    session: i32,
+    operation: PrecheckOperation,
+    key: Option<String>,
+    prefix: Option<bool>,
+) -> Result<OperationPrecheck, LogicError> {
+    let key = key.unwrap_or_default();
+    let prefix = prefix.unwrap_or(false);
+    let (read_only, user) = {
+        let config = etcd::get_connection_config(&session).ok_or(LogicError::ConnectionLose)?;
+        (config.read_only, config.user.as_ref().map(|u| u.username.clone()))
+    };
+
+    if read_only && operation.is_write() {
+        return Ok(OperationPrecheck::deny(operation, key, prefix, "readOnly", "The connection is read-only"));
+    }
+    let Some(user) = user else {
+        return Ok(OperationPrecheck::allow(operation, key, prefix, "noAuth"));
+    };
+
+    let mut connector = etcd::get_connector(&session)?;
+    if operation.root_only() {
+        //  非root用户可能无权查询用户信息，查询失败也视为非root用户
+        let root = connector.user_is_root(&user).await.unwrap_or(false);
+        return Ok(if root {
+            OperationPrecheck::allow(operation, key, prefix, "root")
+        } else {
+            OperationPrecheck::deny(operation, key, prefix, "root", "Only root users can perform this operation")
+        });
+    }
+
+    let allowed = connector.probe_permission(key.clone(), prefix, operation.is_write()).await?;
+    Ok(if allowed {
+        OperationPrecheck::allow(operation, key, prefix, "probe")
+    } else {
+        OperationPrecheck::deny(operation, key, prefix, "probe", "Permission denied")
+    })
+}
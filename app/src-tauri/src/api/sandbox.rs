@@ -0,0 +1,25 @@
+use crate::error::LogicError;
+use crate::etcd::sandbox;
+use crate::transport::sandbox::{SandboxOptions, SandboxStatus};
+
+/// 启动本地单节点沙箱集群，未指定etcd程序时自动下载
+#[tauri::command]
+pub async fn sandbox_start(options: Option<SandboxOptions>) -> Result<SandboxStatus, LogicError> {
+    sandbox::start(options.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn sandbox_stop() -> Result<(), LogicError> {
+    sandbox::stop().await
+}
+
+#[tauri::command]
+pub async fn sandbox_status() -> Result<SandboxStatus, LogicError> {
+    Ok(sandbox::status().await)
+}
+
+/// 停止沙箱并删除其数据
+#[tauri::command]
+pub async fn sandbox_reset() -> Result<(), LogicError> {
+    sandbox::reset().await
+}
@@ -3,14 +3,19 @@ use std::io::Read;
 use std::fs;
 
 use lazy_static::lazy_static;
-use log::debug;
-use tauri::AppHandle;
+use log::{debug, info, warn};
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
 use tokio::sync::RwLock;
 
-use crate::api::connection::restore_connections;
+use crate::api::connection::{get_connection, get_connection_list, restore_connections, save_connection_info};
 use crate::error::LogicError;
-use crate::transport::settings::{GlobalStoreConfig, SettingConfig};
-use crate::utils::{aes_util, file_util};
+use crate::etcd::{alert_dispatcher, session_lock};
+use crate::transport::maintenance::{Alert, AlertType};
+use crate::transport::settings::{
+    AlertWebhook, GlobalStoreConfig, SettingConfig, UsageStats, WorkspaceBundle, WorkspaceImportResult, validate_download_dir,
+};
+use crate::utils::{aes_util, file_util, trash, usage_stats, workspace};
 
 lazy_static! {
     static ref SETTING_CONFIG: RwLock<Option<SettingConfig>> = RwLock::new(None);
@@ -90,17 +95,31 @@ pub async fn get_global_store() -> Result<GlobalStoreConfig, LogicError> {
 }
 
 #[tauri::command]
-pub async fn save_settings(setting_config: SettingConfig) -> Result<(), LogicError> {
+pub async fn save_settings(app: AppHandle, mut setting_config: SettingConfig) -> Result<(), LogicError> {
+    let old = get_settings().await?;
+    //  主密码只能通过 `set_master_password` 修改
+    setting_config.master_password_hash = old.master_password_hash;
+    setting_config.validate().map_err(LogicError::IllegalArgument)?;
+    if setting_config.download_dir != old.download_dir {
+        validate_download_dir(&setting_config.download_dir).map_err(LogicError::IllegalArgument)?;
+    }
+
     let new_key = &setting_config.connection_conf_encrypt_key;
     if new_key.as_bytes().len() != aes_util::LENGTH_16 {
         return Err(LogicError::ArgumentError);
     }
 
-    let old_key = get_settings().await?.connection_conf_encrypt_key;
+    let old_key = old.connection_conf_encrypt_key;
     if old_key.ne(new_key) {
         restore_connections(old_key.as_bytes(), new_key.as_bytes())?;
+        trash::reencrypt(old_key.as_bytes(), new_key.as_bytes());
     }
 
+    write_settings(&app, setting_config).await
+}
+
+/// 写入设置文件并通知所有窗口
+async fn write_settings(app: &AppHandle, setting_config: SettingConfig) -> Result<(), LogicError> {
     let path = file_util::get_setting_file_path();
     let s = serde_json::to_string(&setting_config)?;
     if !path.exists() {
@@ -109,14 +128,208 @@ pub async fn save_settings(setting_config: SettingConfig) -> Result<(), LogicErr
     fs::write(path, s)?;
     {
         let mut write_lock = SETTING_CONFIG.write().await;
-        *write_lock = Some(setting_config);
+        *write_lock = Some(setting_config.clone());
     }
+    usage_stats::set_enabled(setting_config.telemetry_enabled);
 
     debug!("Save settings");
+    if let Err(e) = app.emit_all("settings_changed", setting_config) {
+        warn!("Failed to emit settings changed event: {e}");
+    }
+
+    Ok(())
+}
+
+/// 设置、修改或清除主密码，已设置主密码时需要提供当前密码。清除主密码时同时关闭会话锁定
+#[tauri::command]
+pub async fn set_master_password(
+    app: AppHandle,
+    current_password: Option<String>,
+    new_password: Option<String>,
+) -> Result<(), LogicError> {
+    session_lock::ensure_unlocked()?;
+    let mut settings = get_settings().await?;
+    if let Some(hash) = &settings.master_password_hash {
+        let current = current_password.unwrap_or_default();
+        if !session_lock::verify_master_password(&current, hash) {
+            return Err(LogicError::IllegalArgument(String::from("Incorrect master password")));
+        }
+    }
+    let new_password = new_password.filter(|p| !p.is_empty());
+    match &new_password {
+        Some(password) => {
+            if password.chars().count() < session_lock::MIN_PASSWORD_LENGTH {
+                return Err(LogicError::IllegalArgument(format!(
+                    "Master password must be at least {} characters",
+                    session_lock::MIN_PASSWORD_LENGTH
+                )));
+            }
+            settings.master_password_hash = Some(session_lock::hash_master_password(password));
+        }
+        None => {
+            settings.master_password_hash = None;
+            settings.lock_idle_minutes = 0;
+        }
+    }
+    write_settings(&app, settings).await?;
+    match &new_password {
+        Some(password) => session_lock::prepare_seal_key(password),
+        None => session_lock::clear_seal_key(),
+    }
+    info!("Master password updated");
+    Ok(())
+}
+
+/// 立即锁定会话
+#[tauri::command]
+pub async fn lock_session(app: AppHandle) -> Result<(), LogicError> {
+    session_lock::lock(&app).await
+}
+
+/// 验证主密码并解锁会话
+#[tauri::command]
+pub async fn unlock_session(app: AppHandle, password: String) -> Result<(), LogicError> {
+    session_lock::unlock(&app, &password).await
+}
+
+#[tauri::command]
+pub fn is_session_locked() -> bool {
+    session_lock::is_locked()
+}
+
+/// 前端在用户操作时调用，刷新会话的最后活跃时间
+#[tauri::command]
+pub fn touch_session_activity() {
+    session_lock::touch();
+}
+
+/// 修改单个设置项，`key` 为设置项的驼峰命名，如 `kvLimitPerPage`
+#[tauri::command]
+pub async fn set_setting(app: AppHandle, key: String, value: Value) -> Result<SettingConfig, LogicError> {
+    let settings = get_settings().await?;
+    let mut json = serde_json::to_value(settings)?;
+    match json.as_object_mut() {
+        Some(map) if map.contains_key(&key) => {
+            map.insert(key, value);
+        }
+        _ => {
+            return Err(LogicError::IllegalArgument(format!("Unknown setting: {}", key)));
+        }
+    }
 
+    let new_settings = serde_json::from_value::<SettingConfig>(json)?;
+    save_settings(app, new_settings.clone()).await?;
+    Ok(new_settings)
+}
+
+/// 恢复默认设置，为避免已保存的连接需要重新加密，连接存储加密密钥保持不变
+#[tauri::command]
+pub async fn reset_settings(app: AppHandle) -> Result<SettingConfig, LogicError> {
+    let old = get_settings().await?;
+    let mut settings = SettingConfig::default();
+    settings.connection_conf_encrypt_key = old.connection_conf_encrypt_key;
+
+    save_settings(app, settings.clone()).await?;
+    Ok(settings)
+}
+
+/// 导出所有连接（含收藏夹和模板）与设置到一个文件，用于团队共享。
+/// 密码、私钥等敏感信息不会以明文导出，设置了共享密码时使用该密码加密后一并导出
+#[tauri::command]
+pub async fn export_workspace(filepath: String, share_password: Option<String>) -> Result<(), LogicError> {
+    let mut connections = get_connection_list().await?;
+    connections.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut settings = get_settings().await?;
+    let secrets = workspace::strip(&mut connections, &mut settings, share_password.as_deref())
+        .map_err(LogicError::MsgError)?;
+
+    let bundle = WorkspaceBundle {
+        version: workspace::WORKSPACE_VERSION,
+        exported_at: crate::etcd::now_timestamp() as i64,
+        connections,
+        settings,
+        secrets,
+    };
+    fs::write(&filepath, serde_json::to_vec_pretty(&bundle)?)?;
+    info!("Exported {} connections to workspace file", bundle.connections.len());
     Ok(())
 }
 
+/// 导入工作区文件。已存在同名连接时，`overwrite` 为false则跳过；
+/// 提供共享密码时恢复敏感信息，否则导入的连接需要重新填写密码和私钥
+#[tauri::command]
+pub async fn import_workspace(
+    app: AppHandle,
+    filepath: String,
+    share_password: Option<String>,
+    overwrite: bool,
+    import_settings: bool,
+) -> Result<WorkspaceImportResult, LogicError> {
+    let content = fs::read(&filepath)?;
+    let mut bundle = serde_json::from_slice::<WorkspaceBundle>(&content)
+        .map_err(|e| LogicError::IllegalArgument(format!("Invalid workspace file: {e}")))?;
+    if bundle.version > workspace::WORKSPACE_VERSION {
+        return Err(LogicError::IllegalArgument(format!(
+            "Unsupported workspace version {}, please upgrade the application",
+            bundle.version
+        )));
+    }
+
+    let mut result = WorkspaceImportResult::default();
+    if let (Some(secrets), Some(password)) = (&bundle.secrets, share_password.as_deref().filter(|p| !p.is_empty())) {
+        workspace::restore(&mut bundle.connections, &mut bundle.settings, secrets, password)
+            .map_err(LogicError::IllegalArgument)?;
+        result.secrets_restored = true;
+    }
+
+    for info in bundle.connections {
+        if !overwrite && get_connection(info.name.clone()).await?.is_some() {
+            result.skipped.push(info.name);
+            continue;
+        }
+        result.imported.push(info.name.clone());
+        save_connection_info(info).await?;
+    }
+
+    if import_settings {
+        let mut settings = bundle.settings;
+        let local = get_settings().await?;
+        settings.connection_conf_encrypt_key = local.connection_conf_encrypt_key;
+        settings.lock_idle_minutes = local.lock_idle_minutes;
+        save_settings(app, settings).await?;
+        result.settings_imported = true;
+    }
+    info!("Imported {} connections from workspace file", result.imported.len());
+    Ok(result)
+}
+
+/// 获取本地使用统计：各功能的调用次数、错误类型和错误率，数据只保存在本机
+#[tauri::command]
+pub fn get_usage_stats() -> UsageStats {
+    usage_stats::get()
+}
+
+#[tauri::command]
+pub fn reset_usage_stats() {
+    usage_stats::reset();
+}
+
+/// 向webhook发送一条测试告警，用于检查配置是否正确
+#[tauri::command]
+pub async fn test_alert_webhook(webhook: AlertWebhook) -> Result<(), LogicError> {
+    let alert = Alert {
+        alert_type: AlertType::Unreachable,
+        session: 0,
+        connection: String::from("Test"),
+        subject: String::new(),
+        message: String::from("This is a test alert from Etcd Workbench"),
+        time: crate::etcd::now_timestamp() as u64,
+    };
+    alert_dispatcher::send_with_retry(&webhook, &alert)
+        .await
+        .map_err(LogicError::MsgError)
+}
+
 #[tauri::command]
 pub async fn save_global_store(store: GlobalStoreConfig) -> Result<(), LogicError> {
     let path = file_util::get_global_store_file_path();
@@ -0,0 +1,66 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use log::{info, warn};
+
+use crate::api::task_center;
+use crate::etcd;
+use crate::ssh::session_pool;
+use crate::utils::usage_stats;
+
+/// 退出时等待任务结束及断开连接的最长时间，超时后直接退出
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+const TASK_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// 断开连接后保存统计数据及结束沙箱进程的最长等待时间
+const SAVE_TIMEOUT: Duration = Duration::from_secs(2);
+
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// 应用退出前依次取消后台任务（监听、审计等，审计任务会写入结束记录）、断开所有etcd会话、
+/// 断开SSH会话，最后保存统计数据并结束沙箱进程。只执行一次
+pub async fn shutdown() {
+    if SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    info!("Shutting down");
+
+    let drain = async {
+        let cancelled = task_center::cancel_all();
+        if cancelled > 0 {
+            info!("Waiting for {} tasks to stop", cancelled);
+        }
+        while task_center::running_count() > 0 {
+            tokio::time::sleep(TASK_POLL_INTERVAL).await;
+        }
+        etcd::remove_all_connectors().await;
+        session_pool::disconnect_all().await;
+    };
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, drain).await.is_err() {
+        warn!("Shutdown did not finish in {:?}, exit anyway", SHUTDOWN_TIMEOUT);
+    }
+
+    etcd::sandbox::shutdown();
+    usage_stats::flush();
+    info!("Shutdown finished");
+}
+
+/// 在 `RunEvent::Exit` 回调中执行退出流程。
+///
+/// 回调运行在主线程上，主线程此时处于 `#[tokio::main]` 的 `block_on` 中，不能再次阻塞执行异步任务。
+/// 退出流程交给运行时的工作线程执行，主线程最多等待 [`SHUTDOWN_TIMEOUT`] 加上保存数据的时间
+pub fn shutdown_blocking() {
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        warn!("No runtime to shutdown with, only save data");
+        etcd::sandbox::shutdown();
+        usage_stats::flush();
+        return;
+    };
+    let (sender, receiver) = std::sync::mpsc::channel();
+    runtime.spawn(async move {
+        shutdown().await;
+        let _ = sender.send(());
+    });
+    if receiver.recv_timeout(SHUTDOWN_TIMEOUT + SAVE_TIMEOUT).is_err() {
+        warn!("Shutdown did not finish in time, exit anyway");
+    }
+}
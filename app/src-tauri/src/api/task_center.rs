@@ -0,0 +1,302 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::RwLock;
+
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tokio::sync::watch;
+
+use crate::api::event_bus::{self, EventStream};
+use crate::error::LogicError;
+use crate::etcd::{self, now_timestamp};
+
+/// 最多保留的已结束任务数
+const MAX_FINISHED_TASKS: usize = 100;
+
+static TASK_ID_COUNTER: AtomicI32 = AtomicI32::new(1);
+
+/// 后台任务类型
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all="camelCase")]
+pub enum TaskKind {
+    Snapshot,
+    Export,
+    Import,
+    Transfer,
+    Benchmark,
+    Audit,
+    Tail,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all="camelCase")]
+pub enum TaskState {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+/// 后台任务的状态，通过 `task_state` 事件推送
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct TaskInfo {
+    pub id: i32,
+    pub kind: TaskKind,
+    pub name: String,
+    pub session: Option<i32>,
+    /// 任务输出的文件或目标位置
+    pub target: Option<String>,
+    pub state: TaskState,
+    /// 已处理的数量，单位由任务决定（字节、key数量等）
+    pub processed: u64,
+    /// 总数量，未知时为0
+    pub total: u64,
+    /// 进度百分比，总数量未知时为空
+    pub percent: Option<f64>,
+    pub message: Option<String>,
+    pub error_msg: Option<String>,
+    /// 创建时间（毫秒时间戳）
+    pub created_at: u64,
+    pub finished_at: Option<u64>,
+}
+
+lazy_static! {
+    static ref APP_HANDLE: RwLock<Option<AppHandle>> = RwLock::new(None);
+    static ref TASKS: DashMap<i32, TaskInfo> = DashMap::new();
+    static ref TASK_CANCELLERS: DashMap<i32, watch::Sender<bool>> = DashMap::new();
+    //  已结束任务的id，按结束顺序排列
+    static ref FINISHED_TASKS: std::sync::Mutex<VecDeque<i32>> = std::sync::Mutex::new(VecDeque::new());
+}
+
+pub fn init(app: AppHandle) {
+    *APP_HANDLE.write().unwrap() = Some(app);
+}
+
+/// 推送任务状态，属于某个连接的任务只推送给该连接所在窗口
+fn publish(info: &TaskInfo) {
+    let Some(app) = APP_HANDLE.read().unwrap().clone() else {
+        return;
+    };
+    match info.session.and_then(|s| etcd::get_session_window(&s)) {
+        Some(label) => event_bus::publish_to(&app, &label, EventStream::Progress, "task_state", info),
+        None => {
+            for label in app.windows().keys() {
+                event_bus::publish_to(&app, label, EventStream::Progress, "task_state", info);
+            }
+        }
+    }
+}
+
+pub fn error_message(e: &LogicError) -> String {
+    match e {
+        LogicError::MsgError(msg) | LogicError::IllegalArgument(msg) => msg.clone(),
+        LogicError::EtcdClientError(e) => e.to_string(),
+        LogicError::IoError(e) => e.to_string(),
+        LogicError::ResourceNotExist(msg) => String::from(*msg),
+        other => format!("{:?}", other),
+    }
+}
+
+fn update(id: i32, f: impl FnOnce(&mut TaskInfo)) -> Option<TaskInfo> {
+    let info = {
+        let mut info = TASKS.get_mut(&id)?;
+        f(&mut info);
+        info.clone()
+    };
+    publish(&info);
+    Some(info)
+}
+
+/// 后台任务的句柄，任务通过它上报进度并检查是否被取消。
+/// 句柄释放时任务仍未结束则视为已取消
+pub struct TaskHandle {
+    id: i32,
+    cancel_receiver: watch::Receiver<bool>,
+    finished: bool,
+}
+
+impl TaskHandle {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn progress(&self, processed: u64, total: u64) {
+        update(self.id, |info| {
+            info.processed = processed;
+            info.total = total;
+            info.percent = if total > 0 {
+                Some((processed as f64 / total as f64 * 100.0).min(100.0))
+            } else {
+                None
+            };
+        });
+    }
+
+    pub fn message(&self, message: impl Into<String>) {
+        let message = message.into();
+        update(self.id, |info| info.message = Some(message));
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancel_receiver.borrow()
+    }
+
+    /// 等待任务被取消
+    pub async fn cancelled(&self) {
+        let mut receiver = self.cancel_receiver.clone();
+        let _ = receiver.wait_for(|cancelled| *cancelled).await;
+    }
+
+    pub fn succeed(mut self) {
+        self.finish(TaskState::Succeeded, None);
+    }
+
+    pub fn fail(mut self, error_msg: impl Into<String>) {
+        self.finish(TaskState::Failed, Some(error_msg.into()));
+    }
+
+    /// 根据执行结果结束任务
+    pub fn complete<T>(self, result: &Result<T, LogicError>) {
+        match result {
+            Ok(_) if self.is_cancelled() => drop(self),
+            Ok(_) => self.succeed(),
+            Err(e) => self.fail(error_message(e)),
+        }
+    }
+
+    fn finish(&mut self, state: TaskState, error_msg: Option<String>) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        TASK_CANCELLERS.remove(&self.id);
+        //  任务记录已被移除时不再记录
+        let updated = update(self.id, |info| {
+            info.state = state;
+            info.error_msg = error_msg;
+            info.finished_at = Some(now_timestamp() as u64);
+            if state == TaskState::Succeeded && info.total > 0 {
+                info.processed = info.total;
+                info.percent = Some(100.0);
+            }
+        });
+        debug!("Task {} finished: {:?}", self.id, state);
+        if updated.is_none() {
+            return;
+        }
+
+        let mut finished = FINISHED_TASKS.lock().unwrap();
+        finished.push_back(self.id);
+        while finished.len() > MAX_FINISHED_TASKS {
+            if let Some(id) = finished.pop_front() {
+                TASKS.remove(&id);
+            }
+        }
+    }
+}
+
+impl Drop for TaskHandle {
+    fn drop(&mut self) {
+        self.finish(TaskState::Cancelled, None);
+    }
+}
+
+/// 登记一个新的后台任务
+pub fn register(kind: TaskKind, name: impl Into<String>, session: Option<i32>, target: Option<String>) -> TaskHandle {
+    let id = TASK_ID_COUNTER.fetch_add(1, Ordering::SeqCst);
+    let (cancel_sender, cancel_receiver) = watch::channel(false);
+    let info = TaskInfo {
+        id,
+        kind,
+        name: name.into(),
+        session,
+        target,
+        state: TaskState::Running,
+        processed: 0,
+        total: 0,
+        percent: None,
+        message: None,
+        error_msg: None,
+        created_at: now_timestamp() as u64,
+        finished_at: None,
+    };
+    //  先登记再推送，收到事件后立即查询也能查到该任务
+    TASKS.insert(id, info.clone());
+    TASK_CANCELLERS.insert(id, cancel_sender);
+    publish(&info);
+    TaskHandle {
+        id,
+        cancel_receiver,
+        finished: false,
+    }
+}
+
+pub fn get_task(id: i32) -> Option<TaskInfo> {
+    TASKS.get(&id).map(|t| t.value().clone())
+}
+
+/// 请求取消任务，任务在下一个检查点停止
+pub fn cancel(id: i32) -> bool {
+    match TASK_CANCELLERS.get(&id) {
+        Some(sender) => sender.send(true).is_ok(),
+        None => false,
+    }
+}
+
+/// 取消所有运行中的任务，返回请求取消的数量
+pub fn cancel_all() -> usize {
+    TASK_CANCELLERS.iter().filter(|sender| sender.send(true).is_ok()).count()
+}
+
+/// 运行中的任务数量
+pub fn running_count() -> usize {
+    TASK_CANCELLERS.len()
+}
+
+/// 移除任务记录，任务仍在运行时先请求取消
+pub fn remove(id: i32) -> Result<(), LogicError> {
+    cancel(id);
+    TASKS.remove(&id);
+    FINISHED_TASKS.lock().unwrap().retain(|t| *t != id);
+    Ok(())
+}
+
+pub fn list(kind: Option<TaskKind>) -> Vec<TaskInfo> {
+    let mut list: Vec<TaskInfo> = TASKS
+        .iter()
+        .filter(|t| kind.map(|k| t.kind == k).unwrap_or(true))
+        .map(|t| t.value().clone())
+        .collect();
+    list.sort_by_key(|t| t.id);
+    list
+}
+
+/// 列出所有后台任务，包括最近结束的任务
+#[tauri::command]
+pub fn list_tasks(kind: Option<TaskKind>) -> Result<Vec<TaskInfo>, LogicError> {
+    Ok(list(kind))
+}
+
+#[tauri::command]
+pub fn cancel_task(id: i32) -> Result<bool, LogicError> {
+    Ok(cancel(id))
+}
+
+#[tauri::command]
+pub fn remove_task(id: i32) -> Result<(), LogicError> {
+    remove(id)
+}
+
+/// 清除所有已结束的任务记录
+#[tauri::command]
+pub fn clear_finished_tasks() -> Result<(), LogicError> {
+    let ids: Vec<i32> = FINISHED_TASKS.lock().unwrap().drain(..).collect();
+    for id in ids {
+        TASKS.remove(&id);
+    }
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use log::{debug, info, warn};
+use tauri::updater::{UpdateBuilder, UpdateResponse};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::api::settings::get_settings;
+use crate::error::LogicError;
+use crate::transport::updater::UpdateCheckResult;
+
+pub const UPDATE_CHANNEL_STABLE: &'static str = "stable";
+pub const UPDATE_CHANNEL_BETA: &'static str = "beta";
+
+/// beta渠道的更新清单，stable渠道使用 tauri.conf.json 中配置的地址
+const BETA_UPDATE_ENDPOINT: &'static str = "https://tzfun.github.io/etcd-workbench/etcd-workbench-update-beta.json";
+
+/// 后台自动检查更新的间隔
+const AUTO_CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+fn update_builder<R: Runtime>(app: &AppHandle<R>, channel: &str) -> UpdateBuilder<R> {
+    let builder = app.updater();
+    if channel == UPDATE_CHANNEL_BETA {
+        builder.endpoints(&[String::from(BETA_UPDATE_ENDPOINT)])
+    } else {
+        builder
+    }
+}
+
+fn to_check_result<R: Runtime>(response: &UpdateResponse<R>, channel: String) -> UpdateCheckResult {
+    UpdateCheckResult {
+        available: response.is_update_available(),
+        channel,
+        current_version: response.current_version().to_string(),
+        latest_version: String::from(response.latest_version()),
+        notes: response.body().cloned(),
+        date: response.date().map(|d| d.unix_timestamp() * 1000),
+    }
+}
+
+async fn check<R: Runtime>(app: &AppHandle<R>) -> Result<(UpdateResponse<R>, String), LogicError> {
+    let channel = get_settings().await?.update_channel;
+    let response = update_builder(app, &channel).check().await.map_err(|e| {
+        warn!("Failed to check update: {e}");
+        LogicError::MsgError(e.to_string())
+    })?;
+    Ok((response, channel))
+}
+
+/// 手动检查更新
+#[tauri::command]
+pub async fn check_update(app: AppHandle) -> Result<UpdateCheckResult, LogicError> {
+    let (response, channel) = check(&app).await?;
+    Ok(to_check_result(&response, channel))
+}
+
+/// 下载并安装更新，签名由 tauri 更新器使用配置的公钥校验。
+///
+/// `restart` 为 false 时只完成安装，新版本在下次启动时生效。
+/// 返回 false 表示当前已是最新版本。
+#[tauri::command]
+pub async fn install_update(app: AppHandle, restart: bool) -> Result<bool, LogicError> {
+    let (response, _) = check(&app).await?;
+    if !response.is_update_available() {
+        return Ok(false);
+    }
+
+    info!("Installing update: {}", response.latest_version());
+    response.download_and_install().await.map_err(|e| {
+        warn!("Failed to install update: {e}");
+        LogicError::MsgError(e.to_string())
+    })?;
+    info!("Update installed");
+
+    if restart {
+        app.restart();
+    }
+    Ok(true)
+}
+
+/// 启动后台更新检查，开启自动更新时发现新版本会推送 `update_available` 事件
+pub fn start_update_checker(app: AppHandle) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(AUTO_CHECK_INTERVAL).await;
+
+            let auto_update = get_settings().await.map(|s| s.auto_update).unwrap_or(false);
+            if !auto_update {
+                continue;
+            }
+
+            match check(&app).await {
+                Ok((response, channel)) => {
+                    if response.is_update_available() {
+                        debug!("Found new version: {}", response.latest_version());
+                        let _ = app.emit_all("update_available", to_check_result(&response, channel));
+                    }
+                }
+                Err(e) => {
+                    debug!("Background update check failed: {:?}", e);
+                }
+            }
+        }
+    });
+}
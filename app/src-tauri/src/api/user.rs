@@ -1,6 +1,8 @@
 use crate::error::LogicError;
 use crate::etcd;
-use crate::transport::user::SerializableUser;
+use crate::etcd::dry_run;
+use crate::transport::dry_run::{Mutation, PlannedOp};
+use crate::transport::user::{AuthBootstrapConfig, AuthBootstrapStep, SerializableUser, SessionIdentity};
 
 #[tauri::command]
 pub async fn user_list(session: i32) -> Result<Vec<SerializableUser>, LogicError> {
@@ -10,50 +12,92 @@ pub async fn user_list(session: i32) -> Result<Vec<SerializableUser>, LogicError
 }
 
 #[tauri::command]
-pub async fn user_add(session: i32, user: String, password: String) -> Result<(), LogicError> {
+pub async fn user_add(session: i32, user: String, password: String, dry_run: Option<bool>) -> Result<Mutation<()>, LogicError> {
+    if dry_run::enabled(dry_run) {
+        return Ok(Mutation::Plan(dry_run::plan("user_add", vec![PlannedOp::new("userAdd", user.as_str())])));
+    }
+    etcd::check_writable(&session)?;
     let mut connector = etcd::get_connector(&session)?;
     connector.user_add(user, password).await?;
-    Ok(())
+    Ok(Mutation::Done(()))
 }
 
 #[tauri::command]
-pub async fn user_delete(session: i32, user: String) -> Result<(), LogicError> {
+pub async fn user_delete(session: i32, user: String, dry_run: Option<bool>) -> Result<Mutation<()>, LogicError> {
+    if dry_run::enabled(dry_run) {
+        return Ok(Mutation::Plan(dry_run::plan("user_delete", vec![PlannedOp::new("userDelete", user.as_str())])));
+    }
+    etcd::check_writable(&session)?;
     let mut connector = etcd::get_connector(&session)?;
     connector.user_delete(user).await?;
-    Ok(())
+    Ok(Mutation::Done(()))
 }
 
 #[tauri::command]
-pub async fn user_change_password(session: i32, user: String, new_password: String) -> Result<(), LogicError> {
+pub async fn user_change_password(session: i32, user: String, new_password: String, dry_run: Option<bool>) -> Result<Mutation<()>, LogicError> {
+    if dry_run::enabled(dry_run) {
+        return Ok(Mutation::Plan(dry_run::plan("user_change_password", vec![PlannedOp::new("userChangePassword", user.as_str())])));
+    }
+    etcd::check_writable(&session)?;
     let mut connector = etcd::get_connector(&session)?;
     connector.user_change_password(user, new_password).await?;
-    Ok(())
+    Ok(Mutation::Done(()))
 }
 
 #[tauri::command]
-pub async fn user_grant_role(session: i32, user: String, role: String) -> Result<(), LogicError> {
+pub async fn user_grant_role(session: i32, user: String, role: String, dry_run: Option<bool>) -> Result<Mutation<()>, LogicError> {
+    if dry_run::enabled(dry_run) {
+        return Ok(Mutation::Plan(dry_run::plan("user_grant_role", vec![PlannedOp::new("userGrantRole", format!("{} -> {}", user, role))])));
+    }
+    etcd::check_writable(&session)?;
     let mut connector = etcd::get_connector(&session)?;
     connector.user_grant_role(user, role).await?;
-    Ok(())
+    Ok(Mutation::Done(()))
 }
 
 #[tauri::command]
-pub async fn user_revoke_role(session: i32, user: String, role: String) -> Result<(), LogicError> {
+pub async fn user_revoke_role(session: i32, user: String, role: String, dry_run: Option<bool>) -> Result<Mutation<()>, LogicError> {
+    if dry_run::enabled(dry_run) {
+        return Ok(Mutation::Plan(dry_run::plan("user_revoke_role", vec![PlannedOp::new("userRevokeRole", format!("{} -> {}", user, role))])));
+    }
+    etcd::check_writable(&session)?;
     let mut connector = etcd::get_connector(&session)?;
     connector.user_revoke_role(user, role).await?;
-    Ok(())
+    Ok(Mutation::Done(()))
 }
 
 #[tauri::command]
-pub async fn auth_enable(session: i32) -> Result<(), LogicError> {
+pub async fn auth_enable(session: i32, dry_run: Option<bool>) -> Result<Mutation<()>, LogicError> {
+    if dry_run::enabled(dry_run) {
+        return Ok(Mutation::Plan(dry_run::plan("auth_enable", vec![PlannedOp::new("authEnable", "auth")])));
+    }
+    etcd::check_writable(&session)?;
     let mut connector = etcd::get_connector(&session)?;
     connector.auth_enable().await?;
-    Ok(())
+    Ok(Mutation::Done(()))
 }
 
 #[tauri::command]
-pub async fn auth_disable(session: i32) -> Result<(), LogicError> {
+pub async fn auth_disable(session: i32, dry_run: Option<bool>) -> Result<Mutation<()>, LogicError> {
+    if dry_run::enabled(dry_run) {
+        return Ok(Mutation::Plan(dry_run::plan("auth_disable", vec![PlannedOp::new("authDisable", "auth")])));
+    }
+    etcd::check_writable(&session)?;
     let mut connector = etcd::get_connector(&session)?;
     connector.auth_disable().await?;
-    Ok(())
-}
\ No newline at end of file
+    Ok(Mutation::Done(()))
+}
+
+/// 一键初始化权限配置，开启权限验证后当前连接需要使用root用户重新连接
+#[tauri::command]
+pub async fn auth_bootstrap(session: i32, config: AuthBootstrapConfig) -> Result<Vec<AuthBootstrapStep>, LogicError> {
+    etcd::check_writable(&session)?;
+    let mut connector = etcd::get_connector(&session)?;
+    let steps = connector.auth_bootstrap(config).await?;
+    Ok(steps)
+}
+/// 获取连接使用的用户、角色、认证状态及token信息
+#[tauri::command]
+pub async fn get_session_identity(session: i32) -> Result<SessionIdentity, LogicError> {
+    etcd::get_session_identity(session).await
+}
@@ -1,12 +1,38 @@
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::atomic::{AtomicU32, Ordering};
 
-use log::error;
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::{error, info, warn};
 use tauri::api::path::download_dir;
-use tauri::{Manager, WindowBuilder};
+use tauri::{
+    AppHandle, CustomMenuItem, Manager, SystemTray, SystemTrayEvent, SystemTrayMenu,
+    SystemTrayMenuItem, SystemTraySubmenu, Window, WindowBuilder,
+};
 use tauri::utils::config::WindowConfig;
+use tokio::sync::RwLock;
 
+use crate::api::connection::get_connection_list;
+use crate::api::settings::get_settings;
 use crate::error::LogicError;
+use crate::etcd;
+
+/// 独立连接窗口的label前缀，窗口关闭时会断开其中的所有连接
+pub const CONNECTION_WINDOW_LABEL_PREFIX: &str = "connection-";
+
+static CONNECTION_WINDOW_COUNTER: AtomicU32 = AtomicU32::new(1);
+
+const TRAY_SHOW_MAIN: &str = "show_main";
+const TRAY_QUIT: &str = "quit";
+const TRAY_CONNECT_PREFIX: &str = "connect:";
+const TRAY_DISCONNECT_PREFIX: &str = "disconnect:";
+
+lazy_static! {
+    //  新窗口label -> 窗口加载完成后需要打开的连接名
+    static ref PENDING_WINDOW_CONNECTIONS: DashMap<String, String> = DashMap::new();
+    static ref TRAY_APP_HANDLE: RwLock<Option<AppHandle>> = RwLock::new(None);
+}
 
 #[tauri::command]
 pub fn client_error(info: String, err: String) {
@@ -64,8 +90,67 @@ pub async fn open_setting_window(app_handle: tauri::AppHandle, window: tauri::Wi
     }
 }
 
+/// 在新的系统窗口中打开连接，新窗口拥有独立的会话，可与主窗口并排对比两个集群。
+///
+/// 返回新窗口的label，新窗口加载后通过 `take_window_connection` 获取需要打开的连接名
+#[tauri::command]
+pub fn open_connection_window(app_handle: tauri::AppHandle, name: String) -> Result<String, LogicError> {
+    let label = format!(
+        "{}{}",
+        CONNECTION_WINDOW_LABEL_PREFIX,
+        CONNECTION_WINDOW_COUNTER.fetch_add(1, Ordering::SeqCst)
+    );
+    let config = WindowConfig {
+        label: label.clone(),
+        title: format!("Etcd Workbench - {}", name),
+        width: 1400f64,
+        height: 1000f64,
+        center: true,
+        decorations: false,
+        transparent: true,
+        ..<_>::default()
+    };
+    PENDING_WINDOW_CONNECTIONS.insert(label.clone(), name);
+
+    let window = WindowBuilder::from_config(&app_handle, config)
+        .build()
+        .map_err(|e| {
+            PENDING_WINDOW_CONNECTIONS.remove(&label);
+            LogicError::MsgError(e.to_string())
+        })?;
+    #[cfg(target_os = "windows")]
+    {
+        window_shadows::set_shadow(&window, true).unwrap();
+    }
+    window.show().unwrap();
+    info!("Opened connection window: {}", label);
+    Ok(label)
+}
+
+/// 获取当前窗口需要打开的连接名，只能获取一次
 #[tauri::command]
-pub fn exit_app() {
+pub fn take_window_connection(window: Window) -> Option<String> {
+    PENDING_WINDOW_CONNECTIONS.remove(window.label()).map(|(_, name)| name)
+}
+
+/// 窗口销毁后释放其中的连接
+pub fn on_window_destroyed(label: &str) {
+    super::event_bus::clear_window(label);
+    crate::etcd::poll_scheduler::clear_window(label);
+    crate::etcd::credential_prompt::clear_window(label);
+    if !label.starts_with(CONNECTION_WINDOW_LABEL_PREFIX) {
+        return;
+    }
+    PENDING_WINDOW_CONNECTIONS.remove(label);
+    let label = String::from(label);
+    tauri::async_runtime::spawn(async move {
+        crate::etcd::remove_window_connectors(&label).await;
+    });
+}
+
+#[tauri::command]
+pub async fn exit_app() {
+    crate::api::shutdown::shutdown().await;
     std::process::exit(0);
 }
 
@@ -103,8 +188,16 @@ pub fn open_folder(path: String, select_file: Option<String>) -> Result<(), Logi
 }
 
 
+/// 获取下载目录，优先使用设置中配置的目录
 #[tauri::command]
-pub fn get_download_path() -> Option<String> {
+pub async fn get_download_path() -> Option<String> {
+    if let Ok(settings) = get_settings().await {
+        if let Some(dir) = settings.download_dir {
+            if !dir.is_empty() {
+                return Some(dir);
+            }
+        }
+    }
     download_dir().map(|path| path.to_string_lossy().to_string())
 }
 
@@ -128,4 +221,129 @@ pub fn create_configured_window(app_handle: &tauri::AppHandle, name: &'static st
             break;
         }
     }
-}
\ No newline at end of file
+}
+
+/// 创建系统托盘，菜单内容在应用启动后由 [`refresh_tray`] 填充
+pub fn create_system_tray() -> SystemTray {
+    SystemTray::new().with_menu(build_tray_menu(&[], &[]))
+}
+
+/// 保存应用句柄并初始化托盘菜单
+pub fn init_tray(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        *TRAY_APP_HANDLE.write().await = Some(app_handle);
+        refresh_tray().await;
+    });
+}
+
+/// 根据已保存的连接以及已打开连接的健康状态刷新托盘菜单和提示
+pub async fn refresh_tray() {
+    let Some(app_handle) = TRAY_APP_HANDLE.read().await.clone() else {
+        return;
+    };
+    let saved: Vec<String> = get_connection_list()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|info| info.name)
+        .collect();
+    let sessions = etcd::list_session_health();
+
+    let tooltip = if sessions.is_empty() {
+        String::from("Etcd Workbench")
+    } else {
+        let healthy = sessions.iter().filter(|s| s.2 == Some(true)).count();
+        format!("Etcd Workbench - {}/{} healthy", healthy, sessions.len())
+    };
+
+    let tray = app_handle.tray_handle();
+    if let Err(e) = tray.set_menu(build_tray_menu(&saved, &sessions)) {
+        warn!("Failed to update tray menu: {e}");
+    }
+    if let Err(e) = tray.set_tooltip(&tooltip) {
+        warn!("Failed to update tray tooltip: {e}");
+    }
+}
+
+fn build_tray_menu(saved: &[String], sessions: &[(i32, String, Option<bool>)]) -> SystemTrayMenu {
+    let mut menu = SystemTrayMenu::new()
+        .add_item(CustomMenuItem::new(TRAY_SHOW_MAIN, "Show Etcd Workbench"))
+        .add_native_item(SystemTrayMenuItem::Separator);
+
+    if sessions.is_empty() {
+        menu = menu.add_item(CustomMenuItem::new("no_session", "No active connections").disabled());
+    }
+    for (id, name, healthy) in sessions {
+        let state = match healthy {
+            Some(true) => "Healthy",
+            Some(false) => "Unhealthy",
+            None => "Checking",
+        };
+        let submenu = SystemTrayMenu::new()
+            .add_item(CustomMenuItem::new(format!("state:{}", id), state).disabled())
+            .add_item(CustomMenuItem::new(format!("{}{}", TRAY_DISCONNECT_PREFIX, id), "Disconnect"));
+        menu = menu.add_submenu(SystemTraySubmenu::new(format!("{} ({})", name, state), submenu));
+    }
+
+    if !saved.is_empty() {
+        let mut connect_menu = SystemTrayMenu::new();
+        for name in saved {
+            connect_menu = connect_menu.add_item(CustomMenuItem::new(format!("{}{}", TRAY_CONNECT_PREFIX, name), name));
+        }
+        menu = menu
+            .add_native_item(SystemTrayMenuItem::Separator)
+            .add_submenu(SystemTraySubmenu::new("Connect", connect_menu));
+    }
+
+    menu.add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new(TRAY_QUIT, "Quit"))
+}
+
+fn show_main_window(app_handle: &AppHandle) {
+    open_main_window0(app_handle);
+    if let Some(main) = app_handle.get_window("main") {
+        if main.is_minimized().unwrap_or(false) {
+            let _ = main.unminimize();
+        }
+        let _ = main.set_focus();
+    }
+}
+
+/// 处理托盘事件：点击托盘图标恢复主窗口，菜单项用于打开或断开连接。
+///
+/// 打开连接需要由主窗口完成（可能需要输入密码），因此通过 `tray_connect` 事件通知主窗口
+pub fn tray_menu_handle(app_handle: &AppHandle, event: SystemTrayEvent) {
+    match event {
+        SystemTrayEvent::LeftClick { .. } | SystemTrayEvent::DoubleClick { .. } => {
+            show_main_window(app_handle);
+        }
+        SystemTrayEvent::MenuItemClick { id, .. } => {
+            if id == TRAY_SHOW_MAIN {
+                show_main_window(app_handle);
+            } else if id == TRAY_QUIT {
+                show_main_window(app_handle);
+                app_handle.emit_all("confirm_exit", ()).unwrap();
+            } else if let Some(name) = id.strip_prefix(TRAY_CONNECT_PREFIX) {
+                show_main_window(app_handle);
+                if let Err(e) = app_handle.emit_to("main", "tray_connect", name) {
+                    warn!("Failed to emit tray connect event: {e}");
+                }
+            } else if let Some(session) = id.strip_prefix(TRAY_DISCONNECT_PREFIX) {
+                let Ok(session) = session.parse::<i32>() else {
+                    return;
+                };
+                let app_handle = app_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    let label = etcd::get_session_window(&session);
+                    etcd::remove_connector(&session).await;
+                    info!("Disconnected from tray: {}", session);
+                    if let Some(label) = label {
+                        let _ = app_handle.emit_to(&label, "session_disconnected", session);
+                    }
+                });
+            }
+        }
+        _ => {}
+    }
+}
+
@@ -0,0 +1,63 @@
+use serde::Serialize;
+use tonic::Code;
+
+/// etcd错误面向用户的分类，序列化后的值保持稳定，前端据此显示本地化的提示
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCategory {
+    /// 用户名密码错误或token失效
+    AuthFailed,
+    /// 用户没有操作权限
+    PermissionDenied,
+    /// 成员没有leader或leader发生切换
+    NotLeader,
+    /// 集群不可达或请求超时
+    Unavailable,
+    /// 请求的版本已被压缩
+    Compacted,
+    /// 请求或响应超过消息大小限制
+    TooLarge,
+    /// 后端数据库超出配额
+    QuotaExceeded,
+}
+
+/// 根据gRPC状态码确定分类，非gRPC错误的 `code` 传0。
+/// 只有etcd对不同错误使用同一状态码时（如压缩与未来版本、没有leader与请求超时）再按错误信息区分，
+/// 无法识别时返回None
+pub fn categorize(code: i32, msg: &str) -> Option<ErrorCategory> {
+    let msg = msg.to_lowercase();
+    let category = match Code::from_i32(code) {
+        Code::Unauthenticated => ErrorCategory::AuthFailed,
+        Code::PermissionDenied => ErrorCategory::PermissionDenied,
+        Code::DeadlineExceeded => ErrorCategory::Unavailable,
+        Code::Unavailable if is_leader_error(&msg) => ErrorCategory::NotLeader,
+        Code::Unavailable => ErrorCategory::Unavailable,
+        Code::FailedPrecondition if is_leader_error(&msg) => ErrorCategory::NotLeader,
+        Code::FailedPrecondition if msg.contains("user name is empty") => ErrorCategory::AuthFailed,
+        Code::InvalidArgument if msg.contains("authentication failed") => ErrorCategory::AuthFailed,
+        Code::InvalidArgument if msg.contains("request is too large") => ErrorCategory::TooLarge,
+        Code::ResourceExhausted if msg.contains("received message larger than max") => ErrorCategory::TooLarge,
+        //  请求过多同为 ResourceExhausted
+        Code::ResourceExhausted if msg.contains("database space exceeded") || msg.contains("nospace") => {
+            ErrorCategory::QuotaExceeded
+        }
+        Code::OutOfRange if msg.contains("required revision has been compacted") => ErrorCategory::Compacted,
+        //  客户端收发的消息超过大小限制
+        Code::OutOfRange if msg.contains("message length too large") => ErrorCategory::TooLarge,
+        //  非gRPC错误没有状态码，只能按错误信息识别
+        Code::Ok if is_transport_error(&msg) => ErrorCategory::Unavailable,
+        _ => return None,
+    };
+    Some(category)
+}
+
+fn is_leader_error(msg: &str) -> bool {
+    msg.contains("no leader") || msg.contains("not leader") || msg.contains("leader changed")
+}
+
+fn is_transport_error(msg: &str) -> bool {
+    msg.contains("transport error")
+        || msg.contains("connection refused")
+        || msg.contains("timed out")
+        || msg.contains("unavailable")
+}
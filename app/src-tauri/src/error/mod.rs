@@ -8,6 +8,12 @@ use tokio::sync::oneshot;
 
 use crate::utils::aes_util::AesError;
 
+pub mod category;
+pub mod remediation;
+
+use category::ErrorCategory;
+use remediation::Remediation;
+
 #[derive(Debug, Serialize, Deserialize)]
 enum ErrorType {
     /// 身份认证失效，需要重新连接
@@ -26,6 +32,16 @@ enum ErrorType {
     ResourceNotExist,
     /// 权限被拒绝
     PermissionDenied,
+    /// 服务端证书不受信任
+    CertificateUntrusted,
+    /// 需要输入密码
+    PassphraseRequired,
+    /// 只读连接不允许写操作
+    ReadOnly,
+    /// 服务端版本过低，不支持该功能
+    UnsupportedByServer,
+    /// 会话已锁定，需要输入主密码解锁
+    SessionLocked,
 }
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all="camelCase")]
@@ -34,11 +50,37 @@ pub struct ErrorPayload<'a> {
     err_msg: &'a str,
 }
 
+/// etcd服务端返回的错误信息，可识别的常见错误附带修复建议
+#[derive(Debug, Serialize)]
+#[serde(rename_all="camelCase")]
+struct EtcdErrorPayload<'a> {
+    err_type: ErrorType,
+    err_msg: &'a str,
+    /// 面向用户的错误分类
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<ErrorCategory>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remediation: Option<Remediation>,
+}
+
+/// 服务端版本不支持时的错误信息，附带所需的最低版本
+#[derive(Debug, Serialize)]
+#[serde(rename_all="camelCase")]
+struct UnsupportedPayload<'a> {
+    err_type: ErrorType,
+    err_msg: &'a str,
+    feature: &'a str,
+    min_version: &'a str,
+    server_version: &'a str,
+}
+
 #[derive(Debug)]
 pub enum LogicError<> {
     MsgError(String),
     ConnectionLose,
     ArgumentError,
+    /// 参数不合法，值为错误描述
+    IllegalArgument(String),
     ResourceNotExist(&'static str),
     EtcdClientError(etcd_client::Error),
     SshError(russh::Error),
@@ -47,7 +89,21 @@ pub enum LogicError<> {
     SerdeError(serde_json::Error),
     AesError(AesError),
     ChannelRcvError(oneshot::error::RecvError),
-    StringConvertError(FromUtf8Error)
+    StringConvertError(FromUtf8Error),
+    /// 服务端证书指纹与固定的指纹不一致，值为当前服务端证书指纹
+    CertificateFingerprintMismatch(String),
+    /// 私钥或证书包已加密，但未提供密码
+    PassphraseRequired,
+    /// 连接为只读模式
+    ReadOnly,
+    /// 会话因长时间未操作已锁定
+    SessionLocked,
+    /// 服务端版本不支持该功能
+    UnsupportedByServer {
+        feature: &'static str,
+        min_version: &'static str,
+        server_version: String,
+    },
 }
 
 impl Serialize for LogicError {
@@ -70,6 +126,8 @@ impl Serialize for LogicError {
                         let code = status.code();
                         let msg = status.message();
                         let code = code as i32;
+                        let remediation = remediation::remediation_for(code, msg);
+                        let category = category::categorize(code, msg);
 
                         let msg = if msg.starts_with("etcdserver:") {
                             msg.replace("etcdserver:", "")
@@ -79,22 +137,19 @@ impl Serialize for LogicError {
 
                         let msg = msg.as_str();
 
-                        if code == 16 { //  Unauthenticated
-                            ErrorPayload {
-                                err_type: ErrorType::Unauthenticated,
-                                err_msg: msg,
-                            }.serialize(serializer)
+                        let err_type = if code == 16 { //  Unauthenticated
+                            ErrorType::Unauthenticated
                         } else if code == 7 {   //  PermissionDenied
-                            ErrorPayload {
-                                err_type: ErrorType::PermissionDenied,
-                                err_msg: msg,
-                            }.serialize(serializer)
+                            ErrorType::PermissionDenied
                         } else {
-                            ErrorPayload {
-                                err_type: ErrorType::EtcdClientError,
-                                err_msg: msg,
-                            }.serialize(serializer)
-                        }
+                            ErrorType::EtcdClientError
+                        };
+                        EtcdErrorPayload {
+                            err_type,
+                            err_msg: msg,
+                            category,
+                            remediation,
+                        }.serialize(serializer)
                     }
                     etcd_client::Error::InvalidArgs(msg) => {
                         ErrorPayload {
@@ -104,9 +159,11 @@ impl Serialize for LogicError {
                     }
                     _ => {
                         let msg = e.to_string();
-                        ErrorPayload {
+                        EtcdErrorPayload {
                             err_type: ErrorType::EtcdClientError,
                             err_msg: msg.as_str(),
+                            category: category::categorize(0, &msg),
+                            remediation: remediation::remediation_for(0, &msg),
                         }.serialize(serializer)
                     }
                 }
@@ -177,12 +234,56 @@ impl Serialize for LogicError {
                     err_msg: "invalid argument",
                 }.serialize(serializer)
             }
+            LogicError::IllegalArgument(msg) => {
+                ErrorPayload {
+                    err_type: ErrorType::ArgumentError,
+                    err_msg: msg.as_str(),
+                }.serialize(serializer)
+            }
             LogicError::ResourceNotExist(e) => {
                 ErrorPayload {
                     err_type: ErrorType::ResourceNotExist,
                     err_msg: e,
                 }.serialize(serializer)
             }
+            LogicError::PassphraseRequired => {
+                ErrorPayload {
+                    err_type: ErrorType::PassphraseRequired,
+                    err_msg: "passphrase required",
+                }.serialize(serializer)
+            }
+            LogicError::ReadOnly => {
+                ErrorPayload {
+                    err_type: ErrorType::ReadOnly,
+                    err_msg: "The connection is read-only",
+                }.serialize(serializer)
+            }
+            LogicError::SessionLocked => {
+                ErrorPayload {
+                    err_type: ErrorType::SessionLocked,
+                    err_msg: "The session is locked, enter the master password to unlock",
+                }.serialize(serializer)
+            }
+            LogicError::UnsupportedByServer { feature, min_version, server_version } => {
+                let msg = format!(
+                    "{} requires etcd server {} or later, current version: {}",
+                    feature, min_version, server_version
+                );
+                UnsupportedPayload {
+                    err_type: ErrorType::UnsupportedByServer,
+                    err_msg: msg.as_str(),
+                    feature,
+                    min_version,
+                    server_version: server_version.as_str(),
+                }.serialize(serializer)
+            }
+            LogicError::CertificateFingerprintMismatch(fingerprint) => {
+                let msg = format!("The server certificate has changed, current fingerprint: {}", fingerprint);
+                ErrorPayload {
+                    err_type: ErrorType::CertificateUntrusted,
+                    err_msg: msg.as_str(),
+                }.serialize(serializer)
+            }
         }
     }
 }
@@ -0,0 +1,95 @@
+use serde::Serialize;
+use tonic::Code;
+
+use super::category::{categorize, ErrorCategory};
+
+/// 前端可以直接执行的修复操作
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all="camelCase")]
+pub enum RemediationAction {
+    /// 压缩历史版本
+    Compact,
+    /// 碎片整理
+    Defragment,
+    /// 解除告警
+    DisarmAlarm,
+    /// 以最新版本重新读取
+    UseLatestRevision,
+    /// 创建新的lease
+    CreateLease,
+    /// 开启身份认证
+    EnableAuth,
+    /// 管理用户和角色权限
+    ManagePermission,
+    /// 重新连接
+    Reconnect,
+    /// 修改连接配置
+    EditConnection,
+}
+
+/// 常见错误的修复建议，`code` 供前端识别错误类别
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all="camelCase")]
+pub struct Remediation {
+    pub code: &'static str,
+    pub hint: &'static str,
+    pub actions: Vec<RemediationAction>,
+}
+
+/// 根据错误分类给出修复建议，不属于任何分类的错误按状态码识别lease不存在和未开启认证，无法识别时返回None
+pub fn remediation_for(code: i32, msg: &str) -> Option<Remediation> {
+    let lower = msg.to_lowercase();
+    let (code, hint, actions) = match categorize(code, msg) {
+        Some(ErrorCategory::QuotaExceeded) => (
+            "NOSPACE",
+            "The backend database exceeds its quota, compact and defragment it, then disarm the NOSPACE alarm",
+            vec![RemediationAction::Compact, RemediationAction::Defragment, RemediationAction::DisarmAlarm],
+        ),
+        Some(ErrorCategory::Compacted) => (
+            "COMPACTED",
+            "The requested revision has been compacted, read with the latest revision instead",
+            vec![RemediationAction::UseLatestRevision],
+        ),
+        //  客户端的收发限制与服务端的请求大小限制同属一类，按错误信息区分
+        Some(ErrorCategory::TooLarge) if lower.contains("decoded message length too large") => (
+            "RESPONSE_TOO_LARGE",
+            "The response exceeds the max receive message size of the connection, increase it in the connection settings or read fewer keys at once",
+            vec![RemediationAction::EditConnection],
+        ),
+        Some(ErrorCategory::TooLarge) if lower.contains("encoded message length too large") => (
+            "REQUEST_TOO_LARGE",
+            "The request exceeds the max send message size of the connection, increase it in the connection settings",
+            vec![RemediationAction::EditConnection],
+        ),
+        Some(ErrorCategory::TooLarge) => (
+            "SERVER_REQUEST_TOO_LARGE",
+            "The request exceeds the --max-request-bytes limit of the server, write smaller values or fewer keys at once",
+            vec![],
+        ),
+        Some(ErrorCategory::PermissionDenied) => (
+            "PERMISSION_DENIED",
+            "The user has no permission for this operation, grant it to one of the user's roles",
+            vec![RemediationAction::ManagePermission],
+        ),
+        Some(ErrorCategory::AuthFailed) if Code::from_i32(code) == Code::Unauthenticated => (
+            "UNAUTHENTICATED",
+            "The auth token is invalid or expired, reconnect to the cluster",
+            vec![RemediationAction::Reconnect],
+        ),
+        Some(_) => return None,
+        None => match Code::from_i32(code) {
+            Code::NotFound if lower.contains("requested lease not found") => (
+                "LEASE_NOT_FOUND",
+                "The lease has expired or been revoked, create a new lease",
+                vec![RemediationAction::CreateLease],
+            ),
+            Code::FailedPrecondition if lower.contains("authentication is not enabled") => (
+                "AUTH_NOT_ENABLED",
+                "Authentication is not enabled on the cluster",
+                vec![RemediationAction::EnableAuth],
+            ),
+            _ => return None,
+        },
+    };
+    Some(Remediation { code, hint, actions })
+}
@@ -0,0 +1,130 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use log::{debug, warn};
+use serde_json::json;
+use sha2::Sha256;
+
+use crate::api::settings::get_settings;
+use crate::transport::maintenance::{Alert, AlertType};
+use crate::transport::settings::AlertWebhook;
+
+/// 单个webhook的最大发送次数
+const MAX_ATTEMPTS: u32 = 3;
+/// 首次重试的等待时间，之后每次翻倍
+const RETRY_BACKOFF: Duration = Duration::from_secs(1);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub const SIGNATURE_HEADER: &str = "X-Etcd-Workbench-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Etcd-Workbench-Timestamp";
+
+/// 将告警推送到设置中所有已启用且订阅了该告警类型的webhook，发送在后台进行
+pub async fn dispatch(alerts: Vec<Alert>) {
+    if alerts.is_empty() {
+        return;
+    }
+    let webhooks = match get_settings().await {
+        Ok(settings) => settings.alert_webhooks,
+        Err(e) => {
+            warn!("Failed to read alert webhooks: {:?}", e);
+            return;
+        }
+    };
+
+    for webhook in webhooks.into_iter().filter(|w| w.enabled) {
+        let alerts: Vec<Alert> = alerts
+            .iter()
+            .filter(|a| subscribed(&webhook, a.alert_type))
+            .cloned()
+            .collect();
+        if alerts.is_empty() {
+            continue;
+        }
+        tokio::spawn(async move {
+            for alert in alerts {
+                if let Err(e) = send_with_retry(&webhook, &alert).await {
+                    warn!("Failed to send alert to webhook {}: {}", webhook.name, e);
+                }
+            }
+        });
+    }
+}
+
+fn subscribed(webhook: &AlertWebhook, alert_type: AlertType) -> bool {
+    webhook.alert_types.is_empty() || webhook.alert_types.contains(&alert_type)
+}
+
+/// 发送告警，失败时按指数退避重试
+pub async fn send_with_retry(webhook: &AlertWebhook, alert: &Alert) -> Result<(), String> {
+    let mut backoff = RETRY_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match send(webhook, alert).await {
+            Ok(()) => {
+                debug!("Alert sent to webhook {}: {:?}", webhook.name, alert.alert_type);
+                return Ok(());
+            }
+            Err(e) if attempt >= MAX_ATTEMPTS => return Err(e),
+            Err(e) => {
+                debug!("Send alert to webhook {} failed (attempt {}): {}", webhook.name, attempt, e);
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+async fn send(webhook: &AlertWebhook, alert: &Alert) -> Result<(), String> {
+    let body = build_body(&webhook.kind, alert)?;
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let mut request = client
+        .post(&webhook.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json");
+    if let Some(secret) = webhook.secret.as_ref().filter(|s| !s.is_empty()) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+            .to_string();
+        request = request
+            .header(TIMESTAMP_HEADER, &timestamp)
+            .header(SIGNATURE_HEADER, format!("sha256={}", sign(secret, &timestamp, &body)));
+    }
+
+    let response = request.body(body).send().await.map_err(|e| e.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("Webhook responded with status {}", response.status()))
+    }
+}
+
+/// 按webhook类型构造请求体，Slack和Teams使用 `text` 字段，通用类型发送完整的告警JSON
+fn build_body(kind: &str, alert: &Alert) -> Result<String, String> {
+    let body = match kind {
+        "slack" | "teams" => json!({
+            "text": format!("[Etcd Workbench] {}: {}", alert.connection, alert.message)
+        }),
+        _ => serde_json::to_value(alert).map_err(|e| e.to_string())?,
+    };
+    Ok(body.to_string())
+}
+
+/// 签名内容为 `timestamp.body`，使用 HMAC-SHA256 并以小写十六进制表示
+pub fn sign(secret: &str, timestamp: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
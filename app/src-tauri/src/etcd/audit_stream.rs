@@ -0,0 +1,280 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use chrono::Local;
+use etcd_client::{Event, EventType};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::{debug, info, warn};
+use serde::Serialize;
+use tokio::select;
+
+use crate::api::task_center::{self, TaskHandle, TaskKind};
+use crate::error::LogicError;
+use crate::transport::kv::AuditStreamConfig;
+
+use super::etcd_connector::EtcdConnector;
+use super::{cluster_scope, get_connection_config, now_timestamp, subscribe_reconnected, wait_connector, wait_reconnected};
+
+/// 重新建立监听的最短等待时间，连续失败时翻倍
+const MIN_RETRY_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+/// 审计文件中的一行记录
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AuditRecord {
+    /// 开始审计，之后的事件从 `revision` 开始
+    #[serde(rename_all = "camelCase")]
+    Start { time: u64, revision: i64 },
+    #[serde(rename_all = "camelCase")]
+    Event {
+        time: u64,
+        revision: i64,
+        /// put 或 delete
+        op: &'static str,
+        key: String,
+        /// key不是UTF-8时的原始内容（base64）
+        #[serde(skip_serializing_if = "Option::is_none")]
+        raw_key: Option<String>,
+        create_revision: i64,
+        version: i64,
+        lease: i64,
+        /// 值的base64，未开启记录值或删除事件时为空
+        #[serde(skip_serializing_if = "Option::is_none")]
+        value: Option<String>,
+    },
+    /// 监听中断，之后从 `revision` 继续，未丢失事件
+    #[serde(rename_all = "camelCase")]
+    Resume { time: u64, revision: i64, reason: String },
+    /// 审计任务停止
+    #[serde(rename_all = "camelCase")]
+    Stop { time: u64, reason: String },
+    /// `from_revision` 到 `to_revision` 之间的事件已被压缩或因连接不可用而无法记录
+    #[serde(rename_all = "camelCase")]
+    Gap {
+        time: u64,
+        from_revision: i64,
+        to_revision: i64,
+        reason: String,
+    },
+}
+
+impl AuditRecord {
+    fn from_event(connector: &EtcdConnector, event: &Event, include_values: bool, time: u64) -> Option<Self> {
+        let kv = event.kv()?;
+        let key = connector.strip_namespace(kv.key().to_vec());
+        let (key, raw_key) = match String::from_utf8(key) {
+            Ok(key) => (key, None),
+            Err(e) => {
+                let raw = BASE64_STANDARD.encode(e.as_bytes());
+                (String::from_utf8_lossy(e.as_bytes()).to_string(), Some(raw))
+            }
+        };
+        let (op, value) = match event.event_type() {
+            EventType::Put => ("put", include_values.then(|| BASE64_STANDARD.encode(kv.value()))),
+            EventType::Delete => ("delete", None),
+        };
+        Some(AuditRecord::Event {
+            time,
+            revision: kv.mod_revision(),
+            op,
+            key,
+            raw_key,
+            create_revision: kv.create_revision(),
+            version: kv.version(),
+            lease: kv.lease(),
+            value,
+        })
+    }
+}
+
+/// 按天分割的gzip压缩NDJSON文件。每次写入追加一个独立的gzip成员，
+/// 进程异常退出时已写入的内容仍然完整，`zcat` 等工具可直接读取多成员文件
+pub struct AuditWriter {
+    dir: PathBuf,
+    file_prefix: String,
+}
+
+impl AuditWriter {
+    pub fn new(dir: &Path, scope: &str) -> Self {
+        let scope: String = scope
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+            .collect();
+        AuditWriter {
+            dir: dir.to_path_buf(),
+            file_prefix: format!("audit-{}", scope),
+        }
+    }
+
+    pub fn file_path(&self, date: &str) -> PathBuf {
+        self.dir.join(format!("{}-{}.ndjson.gz", self.file_prefix, date))
+    }
+
+    pub fn write(&self, records: &[AuditRecord]) -> Result<(), LogicError> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let path = self.file_path(&Local::now().format("%Y-%m-%d").to_string());
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        for record in records {
+            serde_json::to_writer(&mut encoder, record)?;
+            encoder.write_all(b"\n")?;
+        }
+        encoder.finish()?;
+        Ok(())
+    }
+}
+
+/// 启动审计任务，持续监听整个keyspace并将事件写入 `dir` 目录，返回任务ID，通过任务中心取消
+pub fn start(config: AuditStreamConfig) -> Result<i32, LogicError> {
+    get_connection_config(&config.session).ok_or(LogicError::ConnectionLose)?;
+    let dir = PathBuf::from(&config.dir);
+    fs::create_dir_all(&dir)?;
+    let scope = cluster_scope(&config.session)?;
+    let writer = AuditWriter::new(&dir, &scope);
+
+    let mut handle = task_center::register(
+        TaskKind::Audit,
+        format!("Audit {}", scope),
+        Some(config.session),
+        Some(config.dir.clone()),
+    );
+    let id = handle.id();
+    tokio::spawn(async move {
+        info!("Audit stream started: {}", id);
+        let result = run(&config, &writer, &mut handle).await;
+        let reason = match &result {
+            Ok(_) => String::from("Cancelled"),
+            Err(e) => {
+                warn!("Audit stream {} failed: {:?}", id, e);
+                format!("{:?}", e)
+            }
+        };
+        if let Err(e) = writer.write(&[AuditRecord::Stop {
+            time: now_timestamp() as u64,
+            reason,
+        }]) {
+            warn!("Failed to write audit file: {:?}", e);
+        }
+        handle.complete(&result);
+        debug!("Audit stream stopped: {}", id);
+    });
+    Ok(id)
+}
+
+async fn run(config: &AuditStreamConfig, writer: &AuditWriter, handle: &mut TaskHandle) -> Result<(), LogicError> {
+    let session = config.session;
+    let mut next_revision = {
+        let mut connector = wait_connector(&session).await?;
+        connector.get_keyspace_bounds().await?.revision + 1
+    };
+    writer.write(&[AuditRecord::Start {
+        time: now_timestamp() as u64,
+        revision: next_revision,
+    }])?;
+
+    let mut events = 0u64;
+    let mut reconnected = subscribe_reconnected();
+    let mut backoff = MIN_RETRY_BACKOFF;
+    loop {
+        //  连接不可用时与监听失败一样稍后重试，不结束审计
+        let watch = match wait_connector(&session).await {
+            Ok(mut connector) => connector.kv_watch_prefix_from("", next_revision).await.map_err(|e| e.to_string()),
+            Err(e) => Err(format!("{:?}", e)),
+        };
+        let reason = match watch {
+            Ok((mut watcher, mut stream)) => {
+                let reason = loop {
+                    let message = select! {
+                        message = stream.message() => message,
+                        _ = wait_reconnected(&mut reconnected, session) => {
+                            backoff = Duration::ZERO;
+                            break String::from("Session reconnected");
+                        }
+                        _ = handle.cancelled() => {
+                            let _ = watcher.cancel().await;
+                            return Ok(());
+                        }
+                    };
+                    let response = match message {
+                        Ok(Some(response)) => response,
+                        Ok(None) => break String::from("Watch stream closed"),
+                        Err(e) => break e.to_string(),
+                    };
+                    backoff = MIN_RETRY_BACKOFF;
+                    let time = now_timestamp() as u64;
+                    if response.compact_revision() > 0 {
+                        let compacted = response.compact_revision();
+                        writer.write(&[AuditRecord::Gap {
+                            time,
+                            from_revision: next_revision,
+                            to_revision: compacted - 1,
+                            reason: format!("Revision has been compacted to {}", compacted),
+                        }])?;
+                        next_revision = compacted;
+                        break String::from("Compacted");
+                    }
+                    if response.canceled() {
+                        break format!("Watch canceled: {}", response.cancel_reason());
+                    }
+
+                    let records: Vec<AuditRecord> = match wait_connector(&session).await {
+                        Ok(connector) => response
+                            .events()
+                            .iter()
+                            .filter_map(|e| AuditRecord::from_event(&connector, e, config.include_values, time))
+                            .collect(),
+                        //  无法解析本批事件，记录缺口后从之后的版本继续
+                        Err(e) => {
+                            let last = response
+                                .events()
+                                .iter()
+                                .filter_map(|e| e.kv().map(|kv| kv.mod_revision()))
+                                .max()
+                                .unwrap_or(next_revision - 1);
+                            writer.write(&[AuditRecord::Gap {
+                                time,
+                                from_revision: next_revision,
+                                to_revision: last,
+                                reason: format!("Connection unavailable: {:?}", e),
+                            }])?;
+                            next_revision = next_revision.max(last + 1);
+                            break format!("{:?}", e);
+                        }
+                    };
+                    for record in &records {
+                        if let AuditRecord::Event { revision, .. } = record {
+                            next_revision = next_revision.max(*revision + 1);
+                        }
+                    }
+                    writer.write(&records)?;
+                    events += records.len() as u64;
+                    handle.progress(events, 0);
+                    handle.message(format!("Revision {}", next_revision - 1));
+                };
+                let _ = watcher.cancel().await;
+                reason
+            }
+            Err(reason) => reason,
+        };
+
+        debug!("Audit stream of {} interrupted: {}", session, reason);
+        writer.write(&[AuditRecord::Resume {
+            time: now_timestamp() as u64,
+            revision: next_revision,
+            reason,
+        }])?;
+        select! {
+            _ = tokio::time::sleep(backoff) => {},
+            _ = handle.cancelled() => return Ok(()),
+        }
+        backoff = (backoff * 2).clamp(MIN_RETRY_BACKOFF, MAX_RETRY_BACKOFF);
+    }
+}
@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use etcd_client::{EventType, WatchStream, Watcher};
+use log::{debug, info, warn};
+use tauri::Window;
+use tokio::select;
+use tokio::sync::oneshot;
+use tokio::time::{interval, MissedTickBehavior};
+
+use crate::api::event_bus::{self, EventStream};
+use crate::transport::kv::PrefixChangeCounter;
+
+use super::{now_timestamp, CONNECTION_CHANGE_COUNTERS};
+
+/// 前缀变化订阅，通过监听统计前缀下新增、修改和删除的key数量，
+/// 统计结果变化时通过 `prefix_changes` 事件推送给窗口，用于在树节点上显示未读变化。
+/// 每个刷新周期最多推送一次，避免高频变更的前缀产生大量事件
+pub struct ChangeSubscription {
+    session_id: i32,
+    prefix: String,
+    stop_notifier: Option<oneshot::Sender<()>>,
+}
+
+impl ChangeSubscription {
+    pub fn start(
+        session_id: i32,
+        prefix: String,
+        mut watcher: Watcher,
+        mut stream: WatchStream,
+        window: Window,
+        flush_interval: Duration,
+    ) -> Self {
+        let (stop_sender, mut stop_receiver) = oneshot::channel::<()>();
+
+        CONNECTION_CHANGE_COUNTERS.insert(
+            (session_id, prefix.clone()),
+            PrefixChangeCounter {
+                session: session_id,
+                prefix: prefix.clone(),
+                added: 0,
+                modified: 0,
+                deleted: 0,
+                since: now_timestamp() as u64,
+            },
+        );
+
+        let counter_key = (session_id, prefix.clone());
+        tokio::spawn(async move {
+            info!("Prefix change subscription started: {}, {}", counter_key.0, counter_key.1);
+            let mut timer = interval(flush_interval);
+            timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            let mut dirty = false;
+            loop {
+                let message = select! {
+                    message = stream.message() => message,
+                    _ = timer.tick(), if dirty => {
+                        dirty = false;
+                        let Some(counter) = CONNECTION_CHANGE_COUNTERS.get(&counter_key).map(|c| c.clone()) else {
+                            break;
+                        };
+                        event_bus::publish(&window, EventStream::Watch, "prefix_changes", counter);
+                        continue;
+                    },
+                    _ = &mut stop_receiver => break,
+                };
+
+                let response = match message {
+                    Ok(Some(response)) => response,
+                    Ok(None) => break,
+                    Err(e) => {
+                        warn!("Prefix change watch error: {e}");
+                        break;
+                    }
+                };
+
+                if response.events().is_empty() {
+                    continue;
+                }
+
+                match CONNECTION_CHANGE_COUNTERS.get_mut(&counter_key) {
+                    Some(mut counter) => {
+                        for event in response.events() {
+                            match event.event_type() {
+                                EventType::Put => {
+                                    if event.kv().map(|kv| kv.version() == 1).unwrap_or(false) {
+                                        counter.added += 1;
+                                    } else {
+                                        counter.modified += 1;
+                                    }
+                                }
+                                EventType::Delete => counter.deleted += 1,
+                            }
+                        }
+                    }
+                    None => break,
+                }
+                dirty = true;
+            }
+            let _ = watcher.cancel().await;
+            debug!("Prefix change subscription stopped: {}, {}", counter_key.0, counter_key.1);
+        });
+
+        ChangeSubscription {
+            session_id,
+            prefix,
+            stop_notifier: Some(stop_sender),
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(sender) = self.stop_notifier.take() {
+            let _ = sender.send(());
+        }
+        debug!("Stop prefix change subscription: {}, {}", self.session_id, self.prefix);
+    }
+}
+
+impl Drop for ChangeSubscription {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
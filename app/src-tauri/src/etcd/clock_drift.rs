@@ -0,0 +1,210 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use chrono::DateTime;
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use tokio::task::JoinSet;
+
+use crate::error::LogicError;
+use crate::transport::connection::ConnectionTls;
+use crate::transport::maintenance::{ClockDriftReport, DriftRisk, MemberClock};
+
+use crate::utils::cert_util;
+
+use super::{cluster_status, get_connection_config, get_connector, now_timestamp};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// etcd 在成员间时钟偏差超过1秒时输出警告
+const DRIFT_WARN_MILLIS: i64 = 1000;
+const DRIFT_HIGH_MILLIS: i64 = 5000;
+/// 一小时内任期变化超过此值认为leader频繁切换
+const TERM_CHANGES_WARN_PER_HOUR: f64 = 3.0;
+/// 每个连接保留的任期观察记录数
+const MAX_TERM_SAMPLES: usize = 1000;
+
+lazy_static! {
+    /// 各连接观察到的 (时间, 最大任期)
+    static ref TERM_HISTORY: DashMap<i32, VecDeque<(u64, u64)>> = DashMap::new();
+}
+
+/// 使用连接的TLS配置创建请求成员的客户端
+fn build_client(tls: Option<&ConnectionTls>) -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder().timeout(REQUEST_TIMEOUT);
+    if let Some(tls) = tls {
+        for cert in &tls.cert {
+            let cert = reqwest::Certificate::from_pem(cert).map_err(|e| e.to_string())?;
+            builder = builder.add_root_certificate(cert);
+        }
+        //  配置了证书域名时成员地址与证书中的域名不一致，仍然校验证书链
+        if tls.domain.is_some() {
+            builder = builder.danger_accept_invalid_hostnames(true);
+        }
+        if let Some(identity) = &tls.identity {
+            let (cert, key) = cert_util::resolve_identity(identity).map_err(|e| format!("{:?}", e))?;
+            let identity = reqwest::Identity::from_pkcs8_pem(&cert, &key).map_err(|e| e.to_string())?;
+            builder = builder.identity(identity);
+        }
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// 请求成员的 `/health` 接口，根据响应的 `Date` 头估算成员时钟偏差，返回偏差及其误差范围
+async fn measure_offset(client: reqwest::Client, client_uri: String) -> Result<(i64, u64), String> {
+    let url = format!("{}/health", client_uri.trim_end_matches('/'));
+    let sent_at = now_timestamp() as i64;
+    let start = Instant::now();
+    let response = client.get(&url).send().await.map_err(|e| e.to_string())?;
+    let rtt = start.elapsed().as_millis() as i64;
+    let date = response
+        .headers()
+        .get(reqwest::header::DATE)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| String::from("No Date header in response"))?;
+    let server_time = DateTime::parse_from_rfc2822(date)
+        .map_err(|e| format!("Invalid Date header {}: {}", date, e))?
+        .timestamp_millis();
+    //  Date头精确到秒，取该秒的中点
+    let offset = server_time + 500 - (sent_at + rtt / 2);
+    Ok((offset, (rtt / 2 + 500) as u64))
+}
+
+/// 记录本次观察到的任期，返回观察期间的任期变化次数和观察时长
+fn record_term(session: i32, time: u64, term: u64) -> (u64, u64) {
+    let mut history = TERM_HISTORY.entry(session).or_default();
+    history.push_back((time, term));
+    while history.len() > MAX_TERM_SAMPLES {
+        history.pop_front();
+    }
+    let changes = history
+        .iter()
+        .zip(history.iter().skip(1))
+        .filter(|(a, b)| b.1 > a.1)
+        .map(|(a, b)| b.1 - a.1)
+        .sum();
+    let observed = history.front().map(|(first, _)| time - first).unwrap_or(0);
+    (changes, observed)
+}
+
+pub fn remove_session(session: i32) {
+    TERM_HISTORY.remove(&session);
+}
+
+/// 根据时钟偏差和任期变化评估风险，返回风险等级和警告信息
+pub fn assess(members: &[MemberClock], term_changes: u64, observed_millis: u64) -> (Option<i64>, DriftRisk, Vec<String>) {
+    let mut warnings = Vec::new();
+    let mut risk = DriftRisk::Low;
+
+    let offsets: Vec<(i64, i64)> = members
+        .iter()
+        .filter_map(|m| m.offset_millis.map(|o| (o, m.uncertainty_millis.unwrap_or(0) as i64)))
+        .collect();
+    let max_drift = if offsets.len() >= 2 {
+        let max = offsets.iter().map(|(o, _)| *o).max().unwrap();
+        let min = offsets.iter().map(|(o, _)| *o).min().unwrap();
+        Some(max - min)
+    } else {
+        None
+    };
+    //  扣除测量误差后仍能确定的最小偏差，避免Date头的秒级精度导致误报
+    let certain_drift = offsets
+        .iter()
+        .flat_map(|a| offsets.iter().map(move |b| a.0 - b.0 - a.1 - b.1))
+        .max()
+        .unwrap_or(0);
+    if max_drift.is_some() && certain_drift > DRIFT_WARN_MILLIS {
+        warnings.push(format!("Clock drift between members is at least {} ms", certain_drift));
+        risk = if certain_drift > DRIFT_HIGH_MILLIS { DriftRisk::High } else { DriftRisk::Medium };
+    }
+
+    let terms: Vec<u64> = members.iter().filter_map(|m| m.raft_term).collect();
+    if let (Some(min), Some(max)) = (terms.iter().min(), terms.iter().max()) {
+        if min != max {
+            warnings.push(format!("Members report different raft terms ({} - {})", min, max));
+            risk = risk.max(DriftRisk::Medium);
+        }
+    }
+    if observed_millis > 0 && term_changes > 0 {
+        let per_hour = term_changes as f64 * 3_600_000.0 / observed_millis as f64;
+        if per_hour >= TERM_CHANGES_WARN_PER_HOUR {
+            warnings.push(format!(
+                "Leader changed {} times in the last {} minutes",
+                term_changes,
+                observed_millis / 60_000
+            ));
+            risk = DriftRisk::High;
+        }
+    }
+    (max_drift, risk, warnings)
+}
+
+/// 诊断各成员之间的时钟漂移及leader切换风险。通过SSH隧道连接时无法直接访问成员，只检查任期
+pub async fn diagnose(session: i32) -> Result<ClockDriftReport, LogicError> {
+    let (via_ssh, tls) = {
+        let connection = get_connection_config(&session).ok_or(LogicError::ConnectionLose)?;
+        (connection.ssh.is_some(), connection.tls.clone())
+    };
+    let mut cluster = {
+        let mut connector = get_connector(&session)?;
+        connector.cluster_get().await?
+    };
+    cluster_status::fill_member_status(session, &mut cluster).await;
+
+    let mut members: Vec<MemberClock> = cluster
+        .members
+        .iter()
+        .map(|m| MemberClock {
+            id: m.id.clone(),
+            name: m.name.clone(),
+            endpoint: m.client_uri.first().cloned(),
+            offset_millis: None,
+            uncertainty_millis: None,
+            raft_term: m.status.as_ref().and_then(|s| s.raft_term.parse().ok()),
+            leader: m.status.as_ref().map(|s| s.leader.clone()),
+            error_msg: m.error_msg.clone(),
+        })
+        .collect();
+
+    if via_ssh {
+        for member in members.iter_mut().filter(|m| m.error_msg.is_none()) {
+            member.error_msg = Some(String::from("Clock can not be measured through SSH tunnel"));
+        }
+    } else {
+        let client = build_client(tls.as_ref()).map_err(LogicError::MsgError)?;
+        let mut tasks = JoinSet::new();
+        for (i, member) in members.iter().enumerate() {
+            if let Some(endpoint) = member.endpoint.clone() {
+                let client = client.clone();
+                tasks.spawn(async move { (i, measure_offset(client, endpoint).await) });
+            }
+        }
+        while let Some(joined) = tasks.join_next().await {
+            let Ok((i, result)) = joined else {
+                continue;
+            };
+            match result {
+                Ok((offset, uncertainty)) => {
+                    members[i].offset_millis = Some(offset);
+                    members[i].uncertainty_millis = Some(uncertainty);
+                }
+                Err(e) => members[i].error_msg = Some(e),
+            }
+        }
+    }
+
+    let check_time = now_timestamp() as u64;
+    let (term_changes, observed_millis) = match members.iter().filter_map(|m| m.raft_term).max() {
+        Some(term) => record_term(session, check_time, term),
+        None => (0, 0),
+    };
+    let (max_drift_millis, risk, warnings) = assess(&members, term_changes, observed_millis);
+    Ok(ClockDriftReport {
+        check_time,
+        members,
+        max_drift_millis,
+        term_changes,
+        observed_millis,
+        risk,
+        warnings,
+    })
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use log::debug;
+use tokio::task::JoinSet;
+
+use crate::transport::maintenance::{SerializableCluster, SerializableClusterStatus};
+
+use super::etcd_connector::EtcdConnector;
+use super::get_member_connection;
+
+/// 读取单个成员状态的超时时间，包含建立连接的时间
+const MEMBER_STATUS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 使用当前连接的认证、TLS和SSH配置连接到指定成员，读取该成员的状态
+pub async fn member_status(session: i32, client_uri: &str) -> Result<SerializableClusterStatus, String> {
+    let connection = get_member_connection(&session, client_uri)?;
+    let request = async {
+        let mut connector = EtcdConnector::new(connection)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+        connector.endpoint_status().await.map_err(|e| e.to_string())
+    };
+    match tokio::time::timeout(MEMBER_STATUS_TIMEOUT, request).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("Timed out after {} seconds", MEMBER_STATUS_TIMEOUT.as_secs())),
+    }
+}
+
+/// 并发读取各成员的状态，每个成员单独计算超时，失败的成员记录错误信息而不影响其他成员。
+///
+/// 当前连接的成员直接使用已读取的端点状态
+pub async fn fill_member_status(session: i32, cluster: &mut SerializableCluster) {
+    let mut tasks = JoinSet::new();
+    for (i, member) in cluster.members.iter_mut().enumerate() {
+        if member.id == cluster.member_id {
+            if let Some(status) = &cluster.status {
+                member.status = Some(status.clone());
+                continue;
+            }
+        }
+        match member.client_uri.first().cloned() {
+            Some(uri) => {
+                tasks.spawn(async move { (i, member_status(session, &uri).await) });
+            }
+            None => member.error_msg = Some(String::from("No client url")),
+        }
+    }
+
+    while let Some(joined) = tasks.join_next().await {
+        let Ok((i, result)) = joined else {
+            continue;
+        };
+        let member = &mut cluster.members[i];
+        match result {
+            Ok(status) => member.status = Some(status),
+            Err(e) => {
+                debug!("Failed to get status of member {}: {}", member.name, e);
+                member.error_msg = Some(e);
+            }
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use crate::error::LogicError;
+use crate::etcd::etcd_connector::EtcdConnector;
+use crate::transport::kv::ConsoleResult;
+
+/// 未指定 `--limit` 时最多返回的键值对数量
+const DEFAULT_GET_LIMIT: i64 = 100;
+
+/// 控制台支持的命令，语法为 etcdctl 的子集
+#[derive(Debug, PartialEq, Eq)]
+pub enum ConsoleCommand {
+    /// get <key> [--prefix] [--keys-only] [--limit N] [--rev N]
+    Get {
+        key: String,
+        prefix: bool,
+        keys_only: bool,
+        limit: i64,
+        revision: Option<i64>,
+    },
+    /// put <key> <value> [--lease N]
+    Put {
+        key: String,
+        value: String,
+        lease: Option<i64>,
+    },
+    /// del <key> [--prefix]
+    Del { key: String, prefix: bool },
+}
+
+impl ConsoleCommand {
+    pub fn is_write(&self) -> bool {
+        !matches!(self, ConsoleCommand::Get { .. })
+    }
+}
+
+/// 按空白拆分命令行，支持单引号、双引号以及反斜杠转义
+fn tokenize(line: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) => {
+                if c == q {
+                    quote = None;
+                } else if c == '\\' && q == '"' {
+                    current.push(chars.next().ok_or("Unexpected end after '\\'")?);
+                } else {
+                    current.push(c);
+                }
+            }
+            None => {
+                if c.is_whitespace() {
+                    if in_token {
+                        tokens.push(std::mem::take(&mut current));
+                        in_token = false;
+                    }
+                    continue;
+                }
+                in_token = true;
+                match c {
+                    '"' | '\'' => quote = Some(c),
+                    '\\' => current.push(chars.next().ok_or("Unexpected end after '\\'")?),
+                    _ => current.push(c),
+                }
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(String::from("Unclosed quote"));
+    }
+    if in_token {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+fn parse_number(flag: &str, value: Option<String>) -> Result<i64, String> {
+    let value = value.ok_or_else(|| format!("Missing value for {}", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("Invalid number for {}: {}", flag, value))
+}
+
+/// 解析控制台命令
+pub fn parse(line: &str) -> Result<ConsoleCommand, String> {
+    let mut tokens = tokenize(line)?.into_iter();
+    let name = tokens.next().ok_or("Empty command")?;
+
+    let mut args = Vec::new();
+    let mut prefix = false;
+    let mut keys_only = false;
+    let mut limit = None;
+    let mut revision = None;
+    let mut lease = None;
+
+    while let Some(token) = tokens.next() {
+        //  支持 --flag=value 写法
+        let (flag, inline_value) = match token.split_once('=') {
+            Some((f, v)) if token.starts_with("--") => (f.to_string(), Some(v.to_string())),
+            _ => (token.clone(), None),
+        };
+        match flag.as_str() {
+            "--prefix" => prefix = true,
+            "--keys-only" => keys_only = true,
+            "--limit" => limit = Some(parse_number("--limit", inline_value.or_else(|| tokens.next()))?),
+            "--rev" => revision = Some(parse_number("--rev", inline_value.or_else(|| tokens.next()))?),
+            "--lease" => lease = Some(parse_number("--lease", inline_value.or_else(|| tokens.next()))?),
+            "--" => args.extend(tokens.by_ref()),
+            f if f.starts_with("--") => return Err(format!("Unknown flag: {}", f)),
+            _ => args.push(token),
+        }
+    }
+
+    let mut args = args.into_iter();
+    let command = match name.as_str() {
+        "get" => ConsoleCommand::Get {
+            key: args.next().ok_or("Usage: get <key> [--prefix] [--keys-only] [--limit N] [--rev N]")?,
+            prefix,
+            keys_only,
+            limit: limit.unwrap_or(DEFAULT_GET_LIMIT),
+            revision,
+        },
+        "put" => ConsoleCommand::Put {
+            key: args.next().ok_or("Usage: put <key> <value> [--lease N]")?,
+            value: args.next().ok_or("Usage: put <key> <value> [--lease N]")?,
+            lease,
+        },
+        "del" | "delete" => {
+            let key = args.next().ok_or("Usage: del <key> [--prefix]")?;
+            if prefix && key.is_empty() {
+                return Err(String::from("Deleting with an empty prefix is not allowed"));
+            }
+            ConsoleCommand::Del { key, prefix }
+        }
+        _ => return Err(format!("Unsupported command: {}, available commands: get, put, del", name)),
+    };
+    if args.next().is_some() {
+        return Err(String::from("Too many arguments"));
+    }
+    Ok(command)
+}
+
+/// 执行控制台命令。写操作在未确认时不会执行，只返回将要进行的操作描述
+pub async fn execute(
+    connector: &mut EtcdConnector,
+    command: ConsoleCommand,
+    confirmed: bool,
+) -> Result<ConsoleResult, LogicError> {
+    if command.is_write() && !confirmed {
+        let output = match &command {
+            ConsoleCommand::Put { key, .. } => format!("Put key: {}", key),
+            ConsoleCommand::Del { key, prefix: true } => {
+                let count = connector.kv_get_with_prefix(key.clone(), None).await?.count;
+                format!("Delete {} key(s) with prefix: {}", count, key)
+            }
+            ConsoleCommand::Del { key, .. } => format!("Delete key: {}", key),
+            ConsoleCommand::Get { .. } => unreachable!(),
+        };
+        return Ok(ConsoleResult {
+            output,
+            confirm_required: true,
+        });
+    }
+
+    let output = match command {
+        ConsoleCommand::Get { key, prefix, keys_only, limit, revision } => {
+            let kvs = connector.kv_range(key, prefix, keys_only, limit, revision).await?;
+            let mut lines = Vec::with_capacity(kvs.len() * 2);
+            for kv in kvs {
+                lines.push(kv.key);
+                if !keys_only {
+                    lines.push(String::from_utf8_lossy(&kv.value).to_string());
+                }
+            }
+            lines.join("\n")
+        }
+        ConsoleCommand::Put { key, value, lease } => {
+            match lease {
+                Some(lease) => connector.kv_put_with_lease(key, value, lease).await?,
+                None => connector.kv_put(key, value, None).await?,
+            }
+            String::from("OK")
+        }
+        ConsoleCommand::Del { key, prefix } => {
+            connector.kv_delete_range(key, prefix).await?.to_string()
+        }
+    };
+    Ok(ConsoleResult {
+        output,
+        confirm_required: false,
+    })
+}
@@ -0,0 +1,161 @@
+use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+use base64::Engine;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::error::LogicError;
+use crate::transport::connection::{ContentHintConfig, ContentHintStorage};
+use crate::transport::kv::ContentHint;
+
+use super::etcd_connector::EtcdConnector;
+use super::{get_connection_config, get_connector};
+
+/// 当前写入的数据格式版本，读取时忽略更高版本的记录
+const SCHEMA_VERSION: u32 = 1;
+pub const SIBLING_SUFFIX: &str = ".__meta";
+pub const DEFAULT_PREFIX: &str = "/.etcd-workbench/content-hints/";
+
+/// 内容类型提示在etcd中的存储格式
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HintDoc {
+    schema: u32,
+    #[serde(flatten)]
+    hint: ContentHint,
+}
+
+/// key对应的提示存储位置，`Prefix` 模式下key经过base64编码
+pub fn hint_key(config: &ContentHintConfig, key: &str) -> String {
+    match config.storage {
+        ContentHintStorage::Sibling => format!("{}{}", key, SIBLING_SUFFIX),
+        ContentHintStorage::Prefix => {
+            let mut prefix = config
+                .prefix
+                .clone()
+                .filter(|p| !p.is_empty())
+                .unwrap_or_else(|| String::from(DEFAULT_PREFIX));
+            if !prefix.ends_with('/') {
+                prefix.push('/');
+            }
+            format!("{}{}", prefix, BASE64_URL_SAFE_NO_PAD.encode(key))
+        }
+    }
+}
+
+/// 是否为提示本身的存储key，这类key不再记录提示
+pub fn is_hint_key(config: &ContentHintConfig, key: &str) -> bool {
+    match config.storage {
+        ContentHintStorage::Sibling => key.ends_with(SIBLING_SUFFIX),
+        ContentHintStorage::Prefix => key.starts_with(&hint_key(config, "")),
+    }
+}
+
+/// 原始key是否为提示本身的存储key
+pub fn is_hint_key_raw(config: &ContentHintConfig, key: &[u8]) -> bool {
+    std::str::from_utf8(key).is_ok_and(|key| is_hint_key(config, key))
+}
+
+/// 会话所在连接开启内容类型提示时的配置
+pub fn config_of(session: i32) -> Option<ContentHintConfig> {
+    get_connection_config(&session).and_then(|c| c.content_hints.clone())
+}
+
+/// 开启内容类型提示时，从列表、导出和镜像的数据中移除提示本身的存储key
+pub fn hide_hint_keys<T>(session: i32, items: &mut Vec<T>, key_of: impl Fn(&T) -> &[u8]) {
+    if let Some(config) = config_of(session) {
+        items.retain(|item| !is_hint_key_raw(&config, key_of(item)));
+    }
+}
+
+/// 删除key后一并删除其提示。`Sibling` 模式下按前缀删除时提示已一并删除，`prefix_deleted` 为true时跳过。
+/// 提示只是辅助信息，删除失败时只记录日志
+pub async fn remove_hints(session: i32, keys: &[String], prefix_deleted: bool) {
+    let Some(config) = config_of(session) else {
+        return;
+    };
+    if prefix_deleted && config.storage == ContentHintStorage::Sibling {
+        return;
+    }
+    let hint_keys: Vec<String> = keys
+        .iter()
+        .filter(|key| !is_hint_key(&config, key))
+        .map(|key| hint_key(&config, key))
+        .collect();
+    if hint_keys.is_empty() {
+        return;
+    }
+    let result = async {
+        let mut connector = get_connector(&session)?;
+        connector.kv_delete(hint_keys).await?;
+        Ok::<(), LogicError>(())
+    };
+    if let Err(e) = result.await {
+        warn!("Failed to remove content hints of deleted keys: {:?}", e);
+    }
+}
+
+/// 重命名key后将提示移动到新key，失败时只记录日志
+pub async fn move_hint(session: i32, from: &str, to: &str) {
+    let Some(config) = config_of(session) else {
+        return;
+    };
+    if is_hint_key(&config, from) || is_hint_key(&config, to) {
+        return;
+    }
+    let result = async {
+        let mut connector = get_connector(&session)?;
+        if let Some(hint) = get(&mut connector, &config, from).await? {
+            set(&mut connector, &config, to, Some(hint)).await?;
+            set(&mut connector, &config, from, None).await?;
+        }
+        Ok::<(), LogicError>(())
+    };
+    if let Err(e) = result.await {
+        warn!("Failed to move content hint from {} to {}: {:?}", from, to, e);
+    }
+}
+
+pub async fn get(connector: &mut EtcdConnector, config: &ContentHintConfig, key: &str) -> Result<Option<ContentHint>, LogicError> {
+    if is_hint_key(config, key) {
+        return Ok(None);
+    }
+    let storage_key = hint_key(config, key);
+    let response = connector.kv_get_request(storage_key.clone(), None).await?;
+    let Some(kv) = response.kvs().first() else {
+        return Ok(None);
+    };
+    match serde_json::from_slice::<HintDoc>(kv.value()) {
+        Ok(doc) if doc.schema <= SCHEMA_VERSION => Ok(Some(doc.hint)),
+        Ok(_) => Ok(None),
+        Err(e) => {
+            warn!("Skipped broken content hint {}: {e}", storage_key);
+            Ok(None)
+        }
+    }
+}
+
+/// 写入提示，`hint` 为空时删除
+pub async fn set(
+    connector: &mut EtcdConnector,
+    config: &ContentHintConfig,
+    key: &str,
+    hint: Option<ContentHint>,
+) -> Result<(), LogicError> {
+    if is_hint_key(config, key) {
+        return Err(LogicError::IllegalArgument(format!("{} is a content hint key", key)));
+    }
+    let storage_key = hint_key(config, key);
+    match hint {
+        Some(hint) => {
+            let doc = HintDoc {
+                schema: SCHEMA_VERSION,
+                hint,
+            };
+            connector.kv_put(storage_key, serde_json::to_vec(&doc)?, None).await?;
+        }
+        None => {
+            connector.kv_delete(vec![storage_key]).await?;
+        }
+    }
+    Ok(())
+}
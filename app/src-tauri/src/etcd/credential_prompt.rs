@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::debug;
+use tauri::Window;
+use tokio::sync::oneshot;
+use tokio::time::timeout;
+use uuid::Uuid;
+
+use crate::error::LogicError;
+use crate::transport::connection::{Connection, CredentialKind, CredentialRequest, SshIdentity};
+
+/// 等待用户输入凭据的最长时间
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(300);
+
+struct PendingRequest {
+    window: String,
+    sender: oneshot::Sender<Option<String>>,
+}
+
+lazy_static! {
+    /// 以请求ID为索引，等待前端回复的凭据请求
+    static ref PENDING_REQUESTS: DashMap<String, PendingRequest> = DashMap::new();
+}
+
+/// 向窗口发送 `credential_request` 事件并等待用户输入，取消或超时返回错误
+async fn request(window: &Window, connection: &str, kind: CredentialKind, target: String) -> Result<String, LogicError> {
+    let id = Uuid::new_v4().simple().to_string();
+    let (sender, receiver) = oneshot::channel();
+    PENDING_REQUESTS.insert(
+        id.clone(),
+        PendingRequest {
+            window: String::from(window.label()),
+            sender,
+        },
+    );
+    let request = CredentialRequest {
+        id: id.clone(),
+        connection: String::from(connection),
+        kind,
+        target,
+    };
+    if let Err(e) = window.emit("credential_request", request) {
+        PENDING_REQUESTS.remove(&id);
+        return Err(LogicError::MsgError(format!("Failed to request credential: {e}")));
+    }
+
+    let result = timeout(PROMPT_TIMEOUT, receiver).await;
+    PENDING_REQUESTS.remove(&id);
+    match result {
+        Ok(Ok(Some(value))) => Ok(value),
+        Ok(_) => Err(LogicError::MsgError(String::from("Credential input was cancelled"))),
+        Err(_) => Err(LogicError::MsgError(String::from("Timed out waiting for credential input"))),
+    }
+}
+
+/// 回复凭据请求，`value` 为空表示用户取消输入
+pub fn respond(id: &str, value: Option<String>) -> Result<(), LogicError> {
+    let (_, pending) = PENDING_REQUESTS
+        .remove(id)
+        .ok_or(LogicError::ResourceNotExist("The credential request does not exist or has expired"))?;
+    let _ = pending.sender.send(value);
+    Ok(())
+}
+
+/// 窗口关闭时取消其中等待输入的请求
+pub fn clear_window(label: &str) {
+    PENDING_REQUESTS.retain(|id, pending| {
+        let keep = pending.window != label;
+        if !keep {
+            debug!("Cancel credential request {} of closed window {}", id, label);
+        }
+        keep
+    });
+}
+
+/// 依次请求连接配置为提示输入且尚未填写的凭据，凭据只保存在本次连接的内存中
+pub async fn fill_credentials(window: &Window, name: &str, connection: &mut Connection) -> Result<(), LogicError> {
+    for kind in connection.prompt_credentials.clone() {
+        match kind {
+            CredentialKind::EtcdPassword => {
+                if let Some(user) = connection.user.as_mut().filter(|u| u.password.is_empty()) {
+                    user.password = request(window, name, kind, user.username.clone()).await?;
+                }
+            }
+            CredentialKind::SshPassword => {
+                let Some(ssh) = connection.ssh.as_mut() else {
+                    continue;
+                };
+                let identity = ssh.identity.get_or_insert(SshIdentity {
+                    password: None,
+                    key: None,
+                });
+                if identity.key.is_none() && identity.password.as_deref().unwrap_or_default().is_empty() {
+                    let target = format!("{}@{}:{}", ssh.user, ssh.host, ssh.port);
+                    identity.password = Some(request(window, name, kind, target).await?);
+                }
+            }
+            CredentialKind::SshPassphrase => {
+                let Some(ssh) = connection.ssh.as_mut() else {
+                    continue;
+                };
+                let target = format!("{}@{}:{}", ssh.user, ssh.host, ssh.port);
+                if let Some(key) = ssh.identity.as_mut().and_then(|i| i.key.as_mut()) {
+                    if key.passphrase.as_deref().unwrap_or_default().is_empty() {
+                        key.passphrase = Some(request(window, name, kind, target).await?);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
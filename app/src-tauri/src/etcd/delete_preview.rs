@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use dashmap::DashMap;
+use etcd_client::GetOptions;
+use lazy_static::lazy_static;
+use log::info;
+use uuid::Uuid;
+
+use crate::api::settings::get_settings;
+use crate::error::LogicError;
+use crate::transport::dry_run::{MutationPlan, PlannedOp};
+use crate::transport::kv::{DeletePrefixResult, DeletePreview, DeletePreviewLease};
+use crate::utils::trash;
+
+use super::{cluster_scope, content_hint, dry_run, get_connection_name, get_connector, now_timestamp};
+
+/// 预览token的有效期
+const TOKEN_TTL_MILLIS: u64 = 5 * 60 * 1000;
+/// 预览中展示的key数量
+const SAMPLE_KEYS: usize = 50;
+/// 预览最多读取的key数量，超过时只有总数是准确的，字节数和lease只统计读取到的部分
+const MAX_SCAN_KEYS: i64 = 10000;
+
+struct PendingDelete {
+    session: i32,
+    prefix: String,
+    revision: i64,
+    count: usize,
+    expire_at: u64,
+}
+
+lazy_static! {
+    static ref PENDING_DELETES: DashMap<String, PendingDelete> = DashMap::new();
+}
+
+pub fn remove_session(session: i32) {
+    PENDING_DELETES.retain(|_, pending| pending.session != session);
+}
+
+/// 统计 (key, value长度, lease) 列表，lease按绑定的key数量降序
+pub fn summarize(kvs: &[(String, usize, i64)]) -> (u64, Vec<DeletePreviewLease>, Vec<String>) {
+    let total_bytes = kvs.iter().map(|(key, size, _)| (key.len() + size) as u64).sum();
+    let mut leases: HashMap<i64, usize> = HashMap::new();
+    for (_, _, lease) in kvs.iter().filter(|(_, _, lease)| *lease != 0) {
+        *leases.entry(*lease).or_default() += 1;
+    }
+    let mut leases: Vec<(i64, usize)> = leases.into_iter().collect();
+    leases.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let leases = leases
+        .into_iter()
+        .map(|(lease, keys)| DeletePreviewLease {
+            lease: lease.to_string(),
+            keys,
+        })
+        .collect();
+    let sample_keys = kvs.iter().take(SAMPLE_KEYS).map(|(key, _, _)| key.clone()).collect();
+    (total_bytes, leases, sample_keys)
+}
+
+/// 在固定版本读取前缀下将被删除的数据，最多读取 [`MAX_SCAN_KEYS`] 个，返回的token在有效期内可用于确认删除
+pub async fn preview(session: i32, prefix: String) -> Result<DeletePreview, LogicError> {
+    if prefix.is_empty() {
+        return Err(LogicError::IllegalArgument(String::from(
+            "Deleting with an empty prefix is not allowed",
+        )));
+    }
+    let response = {
+        let mut connector = get_connector(&session)?;
+        connector
+            .kv_get_request(prefix.clone(), Some(GetOptions::new().with_prefix().with_limit(MAX_SCAN_KEYS)))
+            .await?
+    };
+    let revision = response.header().map(|h| h.revision()).unwrap_or(0);
+    //  指定limit时count仍是范围内的总数
+    let count = response.count() as usize;
+    let truncated = response.more();
+    let kvs: Vec<(String, usize, i64)> = {
+        let connector = get_connector(&session)?;
+        response
+            .kvs()
+            .iter()
+            .map(|kv| {
+                let key = connector.strip_namespace(kv.key().to_vec());
+                (String::from_utf8_lossy(&key).to_string(), kv.value().len(), kv.lease())
+            })
+            .collect()
+    };
+    let (total_bytes, leases, sample_keys) = summarize(&kvs);
+
+    let now = now_timestamp() as u64;
+    PENDING_DELETES.retain(|_, pending| pending.expire_at > now);
+    let token = Uuid::new_v4().simple().to_string();
+    let expire_at = now + TOKEN_TTL_MILLIS;
+    PENDING_DELETES.insert(
+        token.clone(),
+        PendingDelete {
+            session,
+            prefix: prefix.clone(),
+            revision,
+            count,
+            expire_at,
+        },
+    );
+    Ok(DeletePreview {
+        token,
+        prefix,
+        revision,
+        count,
+        truncated,
+        total_bytes,
+        leases,
+        sample_keys,
+        expire_at,
+    })
+}
+
+/// 预览过的删除的变更计划，不使用token，确认删除时仍需要该token
+pub fn plan(session: i32, command: &str, token: &str) -> Result<MutationPlan, LogicError> {
+    let pending = PENDING_DELETES
+        .get(token)
+        .filter(|pending| pending.session == session && pending.expire_at > now_timestamp() as u64)
+        .ok_or(LogicError::ResourceNotExist("The delete preview does not exist or has expired"))?;
+    let ops = vec![PlannedOp {
+        affected: Some(pending.count),
+        ..PlannedOp::new("deletePrefix", pending.prefix.as_str())
+    }];
+    Ok(MutationPlan {
+        //  按服务端的事务限制分批删除，每批比较前缀下的key在预览后未被修改
+        txn: true,
+        compares: vec![format!("mod_revision({:?}*) <= {}", pending.prefix, pending.revision)],
+        revision: pending.revision,
+        ops,
+        ..dry_run::plan(command, vec![])
+    })
+}
+
+/// 执行预览过的删除。预览之后前缀下有key被创建或修改时不删除并返回错误，需要重新预览；
+/// 分批删除过程中有key被修改时停止删除，已删除的key仍放入回收站
+pub async fn delete_prefix(session: i32, token: String) -> Result<DeletePrefixResult, LogicError> {
+    let (_, pending) = PENDING_DELETES
+        .remove(&token)
+        .ok_or(LogicError::ResourceNotExist("The delete preview does not exist or has been used"))?;
+    if pending.session != session {
+        return Err(LogicError::IllegalArgument(String::from(
+            "The delete preview belongs to another connection",
+        )));
+    }
+    if pending.expire_at <= now_timestamp() as u64 {
+        return Err(LogicError::IllegalArgument(String::from(
+            "The delete preview has expired, please preview again",
+        )));
+    }
+
+    let settings = get_settings().await?;
+    let result = {
+        let mut connector = get_connector(&session)?;
+        connector
+            .kv_delete_prefix_at(pending.prefix.clone(), pending.revision)
+            .await?
+    };
+    let Some((revision, deleted, complete)) = result else {
+        return Err(LogicError::IllegalArgument(format!(
+            "Keys with prefix {} have changed since revision {}, please preview again",
+            pending.prefix, pending.revision
+        )));
+    };
+    info!("Deleted {} key(s) with prefix {} of {}", deleted.len(), pending.prefix, session);
+    let deleted_keys: Vec<String> = deleted
+        .iter()
+        .filter_map(|(key, _, _)| String::from_utf8(key.clone()).ok())
+        .collect();
+    content_hint::remove_hints(session, &deleted_keys, true).await;
+
+    let count = deleted.len();
+    let scope = cluster_scope(&session)?;
+    trash::add(&settings, &scope, get_connection_name(&session), deleted);
+    if !complete {
+        return Err(LogicError::IllegalArgument(format!(
+            "Deleted {} key(s) with prefix {}, the remaining keys changed since revision {}, please preview again",
+            count, pending.prefix, pending.revision
+        )));
+    }
+    Ok(DeletePrefixResult {
+        deleted: count,
+        revision,
+    })
+}
@@ -0,0 +1,423 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use etcd_client::GetOptions;
+use serde::{Deserialize, Serialize};
+
+use crate::error::LogicError;
+use crate::transport::dry_run::{Mutation, MutationPlan, PlannedOp};
+use crate::transport::kv::DirSyncResult;
+use crate::utils;
+
+use super::{content_hint, dry_run, get_connector};
+
+/// 目录中记录同步状态的文件，推送时以其中的修改版本进行CAS校验
+pub const MANIFEST_FILE: &'static str = ".etcd-workbench-sync.json";
+/// key同时是其他key的父路径时，其值保存在该目录下的此文件中
+const SELF_FILE: &'static str = "%self";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+struct SyncEntry {
+    key: String,
+    mod_revision: i64,
+    md5: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+struct SyncManifest {
+    prefix: String,
+    /// 以相对路径（`/` 分隔）为索引
+    entries: BTreeMap<String, SyncEntry>,
+}
+
+impl SyncManifest {
+    fn load(dir: &Path) -> Result<Option<Self>, LogicError> {
+        let path = dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let manifest: SyncManifest = serde_json::from_slice(&fs::read(path)?)?;
+        manifest.validate()?;
+        Ok(Some(manifest))
+    }
+
+    /// 同步记录文件可能被修改，每条记录的路径必须位于目录内，key必须是该路径对应的前缀下的key
+    fn validate(&self) -> Result<(), LogicError> {
+        for (relative_path, entry) in &self.entries {
+            if !is_safe_relative_path(relative_path) {
+                return Err(LogicError::IllegalArgument(format!(
+                    "Invalid path in {MANIFEST_FILE}: {relative_path}"
+                )));
+            }
+            if !entry.key.starts_with(&self.prefix)
+                || entry.key[self.prefix.len()..] != path_to_key(relative_path)
+            {
+                return Err(LogicError::IllegalArgument(format!(
+                    "The key {} in {MANIFEST_FILE} does not match {relative_path} under prefix {}",
+                    entry.key, self.prefix
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn save(&self, dir: &Path) -> Result<(), LogicError> {
+        fs::write(dir.join(MANIFEST_FILE), serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// 编码路径片段，转义文件系统不允许的字符，空片段编码为 `%`
+fn encode_segment(segment: &str) -> String {
+    match segment {
+        "" => return String::from("%"),
+        "." => return String::from("%2E"),
+        ".." => return String::from("%2E%2E"),
+        _ => {}
+    }
+    let mut encoded = String::with_capacity(segment.len());
+    for c in segment.chars() {
+        if matches!(c, '%' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control() {
+            let mut buf = [0u8; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+                encoded.push_str(&format!("%{:02X}", b));
+            }
+        } else {
+            encoded.push(c);
+        }
+    }
+    encoded
+}
+
+fn decode_segment(segment: &str) -> String {
+    if segment == "%" {
+        return String::new();
+    }
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && i + 2 < bytes.len()
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit()
+        {
+            decoded.push(u8::from_str_radix(&segment[i + 1..i + 3], 16).unwrap());
+            i += 3;
+            continue;
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// 将去除前缀后的key转换为相对路径，`is_parent` 表示该key同时是其他key的父路径
+pub fn key_to_path(relative_key: &str, is_parent: bool) -> String {
+    let mut segments: Vec<String> = relative_key.split('/').map(encode_segment).collect();
+    if is_parent {
+        segments.push(String::from(SELF_FILE));
+    }
+    segments.join("/")
+}
+
+/// 将相对路径还原为去除前缀后的key
+pub fn path_to_key(relative_path: &str) -> String {
+    let mut segments: Vec<&str> = relative_path.split('/').collect();
+    if segments.len() > 1 && segments.last() == Some(&SELF_FILE) {
+        segments.pop();
+    }
+    segments.into_iter().map(decode_segment).collect::<Vec<String>>().join("/")
+}
+
+/// 递归列出目录下的文件，返回 `/` 分隔的相对路径，跳过同步记录文件和以 `.` 开头的目录（如 `.git`）
+fn list_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> Result<(), LogicError> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        if path.is_dir() {
+            if !name.starts_with('.') {
+                list_files(root, &path, files)?;
+            }
+        } else if name != MANIFEST_FILE {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            let relative: Vec<String> = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect();
+            files.push(relative.join("/"));
+        }
+    }
+    Ok(())
+}
+
+/// 由 [`key_to_path`] 生成的相对路径，片段非空且不包含 `.`、`..` 以及被转义的字符
+pub fn is_safe_relative_path(relative_path: &str) -> bool {
+    relative_path
+        .split('/')
+        .all(|segment| !segment.is_empty() && segment != "." && segment != ".." && !segment.contains(['\\', ':']))
+}
+
+/// 解析符号链接后确认路径仍位于目录内
+fn ensure_within(dir: &Path, path: &Path) -> Result<(), LogicError> {
+    let root = dir.canonicalize()?;
+    if path.canonicalize()?.starts_with(&root) {
+        Ok(())
+    } else {
+        Err(LogicError::IllegalArgument(format!(
+            "The path is outside the sync directory: {}",
+            path.display()
+        )))
+    }
+}
+
+fn file_path(dir: &Path, relative_path: &str) -> PathBuf {
+    let mut path = dir.to_path_buf();
+    for segment in relative_path.split('/') {
+        path.push(segment);
+    }
+    path
+}
+
+fn file_md5(path: &Path) -> Option<String> {
+    fs::read(path).ok().map(utils::md5)
+}
+
+/// 将前缀下的key拉取到目录中，每个key一个文件。
+///
+/// 本地文件在上次同步后被修改、且key也被修改时视为冲突并跳过，`force` 为true时以etcd为准覆盖。
+/// `dry_run` 为true时只返回将写入和删除的文件，不修改目录
+pub async fn pull(session: i32, prefix: String, dir: &Path, force: bool, dry_run: bool) -> Result<Mutation<DirSyncResult>, LogicError> {
+    if !dry_run {
+        fs::create_dir_all(dir)?;
+    }
+    let mut manifest = match SyncManifest::load(dir)? {
+        Some(m) if m.prefix != prefix => {
+            return Err(LogicError::IllegalArgument(format!(
+                "The directory is synced with another prefix: {}",
+                m.prefix
+            )));
+        }
+        Some(m) => m,
+        None => SyncManifest {
+            prefix: prefix.clone(),
+            entries: BTreeMap::new(),
+        },
+    };
+
+    let (revision, mut kvs) = {
+        let mut connector = get_connector(&session)?;
+        connector.kv_get_prefix_values(prefix.clone()).await?
+    };
+    content_hint::hide_hint_keys(session, &mut kvs, |kv| kv.key.as_bytes());
+
+    let mut ops = Vec::new();
+    let relative_keys: Vec<&str> = kvs.iter().map(|kv| &kv.key[prefix.len().min(kv.key.len())..]).collect();
+    let parents: HashSet<&str> = relative_keys
+        .iter()
+        .flat_map(|key| key.match_indices('/').map(move |(i, _)| &key[..i]))
+        .collect();
+
+    let mut result = DirSyncResult::default();
+    let mut seen = HashSet::new();
+    for (kv, relative_key) in kvs.iter().zip(relative_keys.iter()) {
+        if kv.decrypt_error.is_some() {
+            result.conflicts.push(kv.key.clone());
+            continue;
+        }
+        let relative_path = key_to_path(relative_key, parents.contains(relative_key));
+        seen.insert(relative_path.clone());
+        let path = file_path(dir, &relative_path);
+        let local_md5 = file_md5(&path);
+        let remote_md5 = utils::md5(&kv.value);
+
+        if let Some(entry) = manifest.entries.get(&relative_path) {
+            let locally_modified = local_md5.as_ref() != Some(&entry.md5);
+            if entry.mod_revision == kv.mod_revision {
+                //  etcd未变化，本地修改等待推送
+                result.unchanged += 1;
+                continue;
+            }
+            if locally_modified && local_md5.as_ref() != Some(&remote_md5) && !force {
+                result.conflicts.push(kv.key.clone());
+                continue;
+            }
+        } else if local_md5.is_some() && local_md5.as_ref() != Some(&remote_md5) && !force {
+            result.conflicts.push(kv.key.clone());
+            continue;
+        }
+
+        if local_md5.as_ref() != Some(&remote_md5) && dry_run {
+            ops.push(PlannedOp {
+                exists: Some(local_md5.is_some()),
+                mod_revision: Some(kv.mod_revision),
+                value_size: Some(kv.value.len()),
+                ..PlannedOp::new("writeFile", relative_path.clone())
+            });
+        } else if local_md5.as_ref() != Some(&remote_md5) {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+                ensure_within(dir, parent)?;
+            }
+            if path.exists() {
+                ensure_within(dir, &path)?;
+            }
+            fs::write(&path, &kv.value)?;
+            result.written.push(relative_path.clone());
+        } else {
+            result.unchanged += 1;
+        }
+        manifest.entries.insert(
+            relative_path,
+            SyncEntry {
+                key: kv.key.clone(),
+                mod_revision: kv.mod_revision,
+                md5: remote_md5,
+            },
+        );
+    }
+
+    //  etcd中已删除的key，本地未修改时删除文件
+    let removed: Vec<String> = manifest
+        .entries
+        .keys()
+        .filter(|path| !seen.contains(*path))
+        .cloned()
+        .collect();
+    for relative_path in removed {
+        let entry = manifest.entries.get(&relative_path).unwrap().clone();
+        let path = file_path(dir, &relative_path);
+        let local_md5 = file_md5(&path);
+        if local_md5.is_some() && local_md5.as_ref() != Some(&entry.md5) && !force {
+            result.conflicts.push(entry.key);
+            continue;
+        }
+        if dry_run {
+            ops.push(PlannedOp {
+                exists: Some(local_md5.is_some()),
+                ..PlannedOp::new("removeFile", relative_path.clone())
+            });
+        } else if path.exists() {
+            ensure_within(dir, &path)?;
+            fs::remove_file(&path)?;
+        }
+        manifest.entries.remove(&relative_path);
+        result.deleted.push(relative_path);
+    }
+
+    if dry_run {
+        return Ok(Mutation::Plan(MutationPlan {
+            revision,
+            ops,
+            ..dry_run::plan("kv_sync_pull", vec![])
+        }));
+    }
+    manifest.save(dir)?;
+    Ok(Mutation::Done(result))
+}
+
+/// 将目录中修改过的文件推送到etcd，以上次同步时的修改版本进行CAS校验，
+/// 校验失败的key记为冲突，需要先拉取。本地删除的文件对应的key也会被删除。
+/// 目录必须是从 `prefix` 拉取的，只会写入和删除该前缀下的key。
+/// `dry_run` 为true时读取key的当前状态，返回将执行的写入和删除及其比较条件
+pub async fn push(session: i32, prefix: String, dir: &Path, dry_run: bool) -> Result<Mutation<DirSyncResult>, LogicError> {
+    if !dry_run {
+        super::check_writable(&session)?;
+    }
+    let mut manifest = SyncManifest::load(dir)?
+        .ok_or(LogicError::ResourceNotExist("The directory has not been pulled yet"))?;
+    if manifest.prefix != prefix {
+        return Err(LogicError::IllegalArgument(format!(
+            "The directory is synced with another prefix: {}",
+            manifest.prefix
+        )));
+    }
+
+    let mut files = Vec::new();
+    list_files(dir, dir, &mut files)?;
+
+    let mut result = DirSyncResult::default();
+    let mut plan = dry_run::plan("kv_sync_push", vec![]);
+    let mut connector = get_connector(&session)?;
+    for relative_path in &files {
+        let path = file_path(dir, relative_path);
+        ensure_within(dir, &path)?;
+        let content = fs::read(path)?;
+        let md5 = utils::md5(&content);
+        let (key, expected) = match manifest.entries.get(relative_path) {
+            Some(entry) if entry.md5 == md5 => {
+                result.unchanged += 1;
+                continue;
+            }
+            Some(entry) => (entry.key.clone(), entry.mod_revision),
+            None => (format!("{}{}", manifest.prefix, path_to_key(relative_path)), 0),
+        };
+        if dry_run {
+            let response = connector
+                .kv_get_request(key.clone(), Some(GetOptions::new().with_keys_only()))
+                .await?;
+            plan.revision = plan.revision.max(response.header().map(|h| h.revision()).unwrap_or(0));
+            let kv = response.kvs().first();
+            plan.compares.push(format!("mod_revision({:?}) == {}", key, expected));
+            plan.ops.push(PlannedOp {
+                exists: Some(kv.is_some()),
+                mod_revision: kv.map(|kv| kv.mod_revision()),
+                value_size: Some(content.len()),
+                ..PlannedOp::new("put", key)
+            });
+            continue;
+        }
+        match connector.kv_put_if_mod_revision(key.clone(), content, expected).await? {
+            Some(revision) => {
+                manifest.entries.insert(
+                    relative_path.clone(),
+                    SyncEntry {
+                        key: key.clone(),
+                        mod_revision: revision,
+                        md5,
+                    },
+                );
+                result.written.push(key);
+            }
+            None => result.conflicts.push(key),
+        }
+    }
+
+    let files: HashSet<&String> = files.iter().collect();
+    let removed: Vec<String> = manifest
+        .entries
+        .keys()
+        .filter(|path| !files.contains(path))
+        .cloned()
+        .collect();
+    for relative_path in removed {
+        let entry = manifest.entries.get(&relative_path).unwrap().clone();
+        if dry_run {
+            plan.compares.push(format!("mod_revision({:?}) == {}", entry.key, entry.mod_revision));
+            plan.ops.push(PlannedOp {
+                mod_revision: Some(entry.mod_revision),
+                ..PlannedOp::new("delete", entry.key)
+            });
+            continue;
+        }
+        if connector
+            .kv_delete_if(entry.key.clone(), None, Some(entry.mod_revision))
+            .await?
+        {
+            manifest.entries.remove(&relative_path);
+            result.deleted.push(entry.key);
+        } else {
+            result.conflicts.push(entry.key);
+        }
+    }
+    drop(connector);
+
+    if dry_run {
+        return Ok(Mutation::Plan(plan));
+    }
+    manifest.save(dir)?;
+    Ok(Mutation::Done(result))
+}
@@ -0,0 +1,254 @@
+use etcd_client::GetOptions;
+
+use crate::error::LogicError;
+use crate::transport::dry_run::{MutationPlan, PlannedOp};
+use crate::transport::user::{AuthPlanStep, SerializablePermission};
+
+use super::get_connector;
+
+/// 命令是否只生成变更计划而不执行
+pub fn enabled(dry_run: Option<bool>) -> bool {
+    dry_run.unwrap_or(false)
+}
+
+/// 不需要读取集群状态的变更计划，如用户和角色的修改
+pub fn plan(command: &str, ops: Vec<PlannedOp>) -> MutationPlan {
+    MutationPlan {
+        command: String::from(command),
+        ops,
+        ..Default::default()
+    }
+}
+
+/// 读取key的当前状态，生成对每个key的操作
+async fn key_ops(session: i32, op: &str, keys: &[String]) -> Result<(i64, Vec<PlannedOp>), LogicError> {
+    let mut connector = get_connector(&session)?;
+    let mut revision = 0;
+    let mut ops = Vec::with_capacity(keys.len());
+    for key in keys {
+        let response = connector
+            .kv_get_request(key.clone(), Some(GetOptions::new().with_keys_only()))
+            .await?;
+        revision = revision.max(response.header().map(|h| h.revision()).unwrap_or(0));
+        let kv = response.kvs().first();
+        ops.push(PlannedOp {
+            exists: Some(kv.is_some()),
+            mod_revision: kv.map(|kv| kv.mod_revision()),
+            ..PlannedOp::new(op, key.clone())
+        });
+    }
+    Ok((revision, ops))
+}
+
+pub async fn plan_put(
+    session: i32,
+    command: &str,
+    key: String,
+    value_size: usize,
+    lease: Option<String>,
+) -> Result<MutationPlan, LogicError> {
+    let (revision, mut ops) = key_ops(session, "put", &[key]).await?;
+    for op in ops.iter_mut() {
+        op.value_size = Some(value_size);
+        op.lease = lease.clone();
+    }
+    Ok(MutationPlan {
+        revision,
+        ops,
+        ..plan(command, vec![])
+    })
+}
+
+/// 批量写入按服务端的事务限制分批提交，每批在一个事务中执行
+pub async fn plan_put_batch(
+    session: i32,
+    command: &str,
+    kvs: &[(String, Vec<u8>)],
+) -> Result<MutationPlan, LogicError> {
+    let keys: Vec<String> = kvs.iter().map(|(key, _)| key.clone()).collect();
+    let (revision, mut ops) = key_ops(session, "put", &keys).await?;
+    for (op, (_, value)) in ops.iter_mut().zip(kvs) {
+        op.value_size = Some(value.len());
+    }
+    Ok(MutationPlan {
+        txn: true,
+        revision,
+        ops,
+        ..plan(command, vec![])
+    })
+}
+
+pub async fn plan_delete(session: i32, command: &str, keys: &[String]) -> Result<MutationPlan, LogicError> {
+    let (revision, ops) = key_ops(session, "delete", keys).await?;
+    Ok(MutationPlan {
+        revision,
+        ops,
+        ..plan(command, vec![])
+    })
+}
+
+/// 比较后删除的计划，在一个事务中先比较值或修改版本再删除
+pub async fn plan_delete_if(
+    session: i32,
+    command: &str,
+    key: String,
+    value: Option<&[u8]>,
+    mod_revision: Option<i64>,
+) -> Result<MutationPlan, LogicError> {
+    let mut compares = Vec::with_capacity(2);
+    if let Some(value) = value {
+        compares.push(format!("value({:?}) == <{} bytes>", key, value.len()));
+    }
+    if let Some(mod_revision) = mod_revision {
+        compares.push(format!("mod_revision({:?}) == {}", key, mod_revision));
+    }
+    let mut result = plan_delete(session, command, &[key]).await?;
+    result.txn = true;
+    result.compares = compares;
+    Ok(result)
+}
+
+/// 重命名在一个事务中写入新key并删除旧key，新key必须不存在
+pub async fn plan_rename(session: i32, command: &str, from: String, to: String) -> Result<MutationPlan, LogicError> {
+    let (_, mut ops) = key_ops(session, "delete", &[from.clone()]).await?;
+    let (revision, put) = key_ops(session, "put", &[to.clone()]).await?;
+    let compares = vec![
+        match ops[0].mod_revision {
+            Some(mod_revision) => format!("mod_revision({:?}) == {}", from, mod_revision),
+            None => format!("exists({:?})", from),
+        },
+        format!("create_revision({:?}) == 0", to),
+    ];
+    ops.extend(put);
+    Ok(MutationPlan {
+        txn: true,
+        compares,
+        revision,
+        ops,
+        ..plan(command, vec![])
+    })
+}
+
+/// 按分析时的修改版本逐个比较后删除，修改过的key不会删除
+pub async fn plan_delete_unchanged(
+    session: i32,
+    command: &str,
+    items: &[(String, i64)],
+) -> Result<MutationPlan, LogicError> {
+    let keys: Vec<String> = items.iter().map(|(key, _)| key.clone()).collect();
+    let mut result = plan_delete(session, command, &keys).await?;
+    result.compares = items
+        .iter()
+        .map(|(key, mod_revision)| format!("mod_revision({:?}) == {}", key, mod_revision))
+        .collect();
+    Ok(result)
+}
+
+/// 恢复回收站中的key，`overwrite` 为false时只写入不存在的key。`entries` 为 (原始key, 展示的key, 值大小)
+pub async fn plan_restore(
+    session: i32,
+    command: &str,
+    entries: Vec<(Vec<u8>, String, usize)>,
+    overwrite: bool,
+) -> Result<MutationPlan, LogicError> {
+    let mut connector = get_connector(&session)?;
+    let mut revision = 0;
+    let mut ops = Vec::with_capacity(entries.len());
+    let mut compares = Vec::new();
+    for (raw_key, key, value_size) in entries {
+        let response = connector
+            .kv_get_request(raw_key, Some(GetOptions::new().with_keys_only()))
+            .await?;
+        revision = revision.max(response.header().map(|h| h.revision()).unwrap_or(0));
+        let kv = response.kvs().first();
+        if !overwrite {
+            compares.push(format!("create_revision({:?}) == 0", key));
+        }
+        ops.push(PlannedOp {
+            exists: Some(kv.is_some()),
+            mod_revision: kv.map(|kv| kv.mod_revision()),
+            value_size: Some(value_size),
+            ..PlannedOp::new("put", key)
+        });
+    }
+    Ok(MutationPlan {
+        compares,
+        revision,
+        ops,
+        ..plan(command, vec![])
+    })
+}
+
+/// 删除lease会同时删除绑定的所有key
+pub async fn plan_lease_revoke(session: i32, command: &str, lease: i64) -> Result<MutationPlan, LogicError> {
+    let info = {
+        let mut connector = get_connector(&session)?;
+        connector.lease_get(lease).await?
+    };
+    let mut ops = vec![PlannedOp {
+        affected: Some(info.keys.len()),
+        ..PlannedOp::new("leaseRevoke", lease.to_string())
+    }];
+    ops.extend(info.keys.into_iter().map(|key| PlannedOp {
+        exists: Some(true),
+        lease: Some(lease.to_string()),
+        ..PlannedOp::new("delete", key)
+    }));
+    Ok(plan(command, ops))
+}
+
+/// 授权新的lease并绑定前缀下的所有key
+pub async fn plan_attach_prefix_lease(
+    session: i32,
+    command: &str,
+    prefix: String,
+    ttl: i64,
+) -> Result<MutationPlan, LogicError> {
+    let response = {
+        let mut connector = get_connector(&session)?;
+        connector
+            .kv_get_request(prefix.clone(), Some(GetOptions::new().with_prefix().with_count_only()))
+            .await?
+    };
+    let ops = vec![
+        PlannedOp::new("leaseGrant", format!("ttl={}", ttl)),
+        PlannedOp {
+            affected: Some(response.count() as usize),
+            ..PlannedOp::new("attachLease", prefix)
+        },
+    ];
+    Ok(MutationPlan {
+        revision: response.header().map(|h| h.revision()).unwrap_or(0),
+        //  按服务端的事务限制分批提交，每批比较各key的修改版本
+        txn: true,
+        compares: vec![String::from("mod_revision(key) == <read revision> for each key")],
+        ops,
+        ..plan(command, vec![])
+    })
+}
+
+/// 权限操作的目标，如 `role:/app/*`
+pub fn permission_target(role: &str, permission: &SerializablePermission) -> String {
+    if permission.all_keys {
+        format!("{}:*", role)
+    } else if permission.prefix {
+        format!("{}:{}*", role, permission.key)
+    } else {
+        format!("{}:{}", role, permission.key)
+    }
+}
+
+/// 权限变更计划的步骤，按执行顺序排列
+pub fn auth_step_ops(steps: &[AuthPlanStep]) -> Vec<PlannedOp> {
+    steps
+        .iter()
+        .map(|step| {
+            let target = match (&step.permission, &step.user) {
+                (Some(permission), _) => permission_target(&step.role, permission),
+                (None, Some(user)) => format!("{} -> {}", user, step.role),
+                (None, None) => step.role.clone(),
+            };
+            PlannedOp::new(&step.action, target)
+        })
+        .collect()
+}
@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use etcd_client::{LeaseKeepAliveStream, LeaseKeeper};
+use log::{debug, warn};
+use tokio::select;
+use tokio::sync::oneshot;
+
+use crate::transport::kv::EditLockInfo;
+
+/// 共享编辑锁在etcd中的前缀，位于连接的命名空间内
+pub const EDIT_LOCK_PREFIX: &str = "/.etcd-workbench/edit-lock";
+/// 共享编辑锁的lease有效期，workbench异常退出后锁会在该时间后自动释放
+pub const EDIT_LOCK_TTL: i64 = 30;
+
+/// 当前用户的身份标识，格式为 `user@host`
+pub fn local_identity() -> String {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| String::from("unknown"));
+    let host = std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .unwrap_or_else(|_| String::from("localhost"));
+    format!("{}@{}", user, host)
+}
+
+pub fn lock_key(key: &str) -> String {
+    format!("{}{}", EDIT_LOCK_PREFIX, key)
+}
+
+/// 已持有的编辑锁。开启共享锁时会持有etcd中的lease并在后台续约
+pub struct EditLock {
+    pub info: EditLockInfo,
+    pub lease: Option<i64>,
+    stop_notifier: Option<oneshot::Sender<()>>,
+}
+
+impl EditLock {
+    pub fn local(info: EditLockInfo) -> Self {
+        EditLock {
+            info,
+            lease: None,
+            stop_notifier: None,
+        }
+    }
+
+    pub fn shared(
+        info: EditLockInfo,
+        lease: i64,
+        mut keeper: LeaseKeeper,
+        mut stream: LeaseKeepAliveStream,
+    ) -> Self {
+        let (stop_sender, mut stop_receiver) = oneshot::channel::<()>();
+        let interval = Duration::from_secs(EDIT_LOCK_TTL as u64 / 3);
+
+        tokio::spawn(async move {
+            loop {
+                select! {
+                    _ = tokio::time::sleep(interval) => {},
+                    _ = &mut stop_receiver => break,
+                }
+                let result = async {
+                    keeper.keep_alive().await?;
+                    stream.message().await
+                };
+                let response = select! {
+                    r = result => r,
+                    _ = &mut stop_receiver => break,
+                };
+                match response {
+                    Ok(Some(response)) if response.ttl() > 0 => {}
+                    Ok(_) => {
+                        warn!("Edit lock lease expired: {}", lease);
+                        break;
+                    }
+                    Err(e) => {
+                        warn!("Edit lock keep alive error: {}, {e}", lease);
+                        break;
+                    }
+                }
+            }
+            debug!("Edit lock keep alive stopped: {}", lease);
+        });
+
+        EditLock {
+            info,
+            lease: Some(lease),
+            stop_notifier: Some(stop_sender),
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(sender) = self.stop_notifier.take() {
+            let _ = sender.send(());
+        }
+    }
+}
+
+impl Drop for EditLock {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
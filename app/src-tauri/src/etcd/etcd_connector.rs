@@ -3,58 +3,141 @@ use std::any::Any;
 use std::collections::HashMap;
 use std::ffi::{OsStr, OsString};
 use std::future::Future;
-use std::path::PathBuf;
+use std::ops::{Deref, DerefMut};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::time::Duration;
-use std::{fs, u8};
 
 use crate::api::settings::get_settings;
+use crate::api::task_center::TaskHandle;
 use crate::error::LogicError;
-use crate::etcd::wrapped_etcd_client::WrappedEtcdClient;
+use crate::etcd::prefetcher;
+use crate::etcd::retry::RetryPolicy;
+use crate::etcd::txn_batch::{self, TxnLimits};
+use crate::etcd::unix_proxy::UnixSocketProxy;
+use crate::etcd::value_cache::ValueCache;
+use crate::etcd::value_crypto::ValueCrypto;
+use crate::etcd::wrapped_etcd_client::{self, ReauthListener, WrappedEtcdClient};
 use crate::ssh::ssh_tunnel::SshTunnel;
-use crate::transport::connection::{Connection, ConnectionUser};
+use crate::transport::connection::{Connection, ConnectionUser, EndpointAddress, TunnelDiagnostics};
 use crate::transport::kv::{
-    SearchResult, SerializableKeyValue, SerializableLeaseInfo, SerializableLeaseSimpleInfo
+    BatchPutResult, HistoryRecord, KeyValuePage, LeaseAttachResult, KeyspaceBounds, SearchResult, SerializableKeyValue, SerializableLeaseInfo,
+    SerializableLeaseSimpleInfo, ValueCacheStats,
 };
 use crate::transport::maintenance::{
-    SerializableCluster, SerializableClusterMember, SerializableClusterStatus, SnapshotInfo,
-    SnapshotState,
+    EndpointCapabilities, SerializableAlarm, SerializableCluster, SerializableClusterMember, SerializableClusterStatus,
+    SnapshotInfo,
 };
-use crate::transport::user::{SerializablePermission, SerializableUser};
-use crate::utils::k8s_formatter;
+use crate::transport::user::{
+    AuthApplyResult, AuthBootstrapConfig, AuthBootstrapRole, AuthBootstrapStep, AuthBootstrapUser, AuthDefinition, AuthExport,
+    AuthImportResult, AuthPlanStep, AuthSimulation, AuthTokenType, SerializablePermission, SerializableUser, SessionIdentity,
+    PASSWORD_PLACEHOLDER,
+};
+use crate::utils::{cert_util, conn_import, k8s_formatter, value_plugin};
+use base64::prelude::{BASE64_STANDARD, BASE64_URL_SAFE_NO_PAD};
+use base64::Engine;
 use etcd_client::{
-    AlarmAction, AlarmType, Certificate, Client, ConnectOptions, Error, GetOptions, GetResponse,
-    Identity, LeaseGrantOptions, LeaseTimeToLiveOptions, PutOptions, RoleRevokePermissionOptions,
-    SortOrder, SortTarget, TlsOptions,
+    AlarmAction, AlarmType, Certificate, Client, CompactionOptions, Compare, CompareOp, ConnectOptions, DeleteOptions, Error, EventType,
+    GetOptions, GetResponse, Identity, KeyValue, LeaseGrantOptions, MemberAddOptions, LeaseKeepAliveStream, LeaseKeeper, LeaseTimeToLiveOptions, PutOptions,
+    RoleRevokePermissionOptions, SnapshotStreaming, SortOrder, SortTarget, TlsOptions, Txn, TxnOp, TxnOpResponse, WatchOptions,
+    WatchStream, Watcher,
 };
-use log::{debug, error, info, warn};
+use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
-use tokio::fs::{File, OpenOptions};
-use tokio::io::AsyncWriteExt;
-use tokio::select;
-use tokio::sync::{mpsc, oneshot};
-use tokio::task::JoinHandle;
+use tokio::sync::Semaphore;
+use tokio::task::{JoinHandle, JoinSet};
+
+/// 单次操作可指定的最长超时时间（秒）
+pub const MAX_CALL_DEADLINE_SECONDS: u64 = 3600;
+/// 按前缀删除时每个事务删除的key数量，每批被删除的旧值在一个响应中返回
+const DELETE_BATCH_KEYS: i64 = 500;
+/// 重放历史时没有新事件多久后请求监听进度
+const HISTORY_IDLE_TIMEOUT: Duration = Duration::from_secs(3);
+/// 重放历史时连续请求监听进度的最大次数
+const HISTORY_MAX_PROGRESS_REQUESTS: u32 = 5;
 
 pub struct EtcdConnector {
     namespace: Option<String>,
     client: WrappedEtcdClient,
     ssh: Option<SshTunnel>,
+    /// 连接unix socket地址时的本地转发
+    unix_proxy: Option<UnixSocketProxy>,
+    /// 配置的地址，同步成员地址时跳过
+    seed_address: String,
+    /// 客户端添加地址时使用的协议，添加和移除成员地址时需要使用相同的完整地址
+    endpoint_scheme: &'static str,
+    /// 根据成员列表添加的地址
+    member_endpoints: Vec<MemberEndpoint>,
+    /// 历史版本读取模式，设置后所有范围读取都读取该版本的数据
+    read_revision: Option<i64>,
+    /// 客户端加密，未配置加密前缀时为空
+    value_crypto: Option<ValueCrypto>,
+    /// 键值读缓存，默认关闭
+    value_cache: Option<ValueCache>,
+    /// 服务端事务限制，用于拆分批量写入
+    txn_limits: TxnLimits,
+    /// 所属会话，写入时使该会话的预取结果失效。临时创建的连接为空
+    session: Option<i32>,
+}
+
+/// 根据成员列表添加到客户端的地址
+struct MemberEndpoint {
+    /// 成员的地址
+    address: String,
+    /// 客户端实际连接的完整地址，使用SSH隧道时为本地转发端口
+    endpoint: String,
+    tunnel: Option<SshTunnel>,
+}
+
+/// 从成员的客户端地址中解析需要添加的地址，去除重复、unix socket地址和已配置的地址
+pub fn member_endpoint_addresses(client_urls: &[&str], seed_address: &str) -> Vec<EndpointAddress> {
+    let mut addresses: Vec<EndpointAddress> = vec![];
+    for url in client_urls {
+        let Some(endpoint) = conn_import::parse_endpoint(url) else {
+            continue;
+        };
+        let Ok(address @ EndpointAddress::Tcp { .. }) = EndpointAddress::parse(&endpoint.host, endpoint.port) else {
+            continue;
+        };
+        if address.to_string() != seed_address && !addresses.contains(&address) {
+            addresses.push(address);
+        }
+    }
+    addresses
 }
 
 impl EtcdConnector {
     pub async fn new(connection: Connection) -> Result<Self, LogicError> {
         let settings = get_settings().await?;
+        let endpoint = connection.endpoint()?;
+        let seed_address = endpoint.to_string();
+        let sync_endpoints = connection.sync_endpoints;
+        let endpoint_scheme = if connection.tls.is_some() { "https" } else { "http" };
 
         let mut option = ConnectOptions::new()
             .with_keep_alive_while_idle(true)
             .with_tcp_keepalive(Duration::from_secs(5))
             .with_connect_timeout(Duration::from_secs(settings.connect_timeout_seconds))
-            .with_timeout(Duration::from_secs(settings.request_timeout_seconds));
+            //  请求超时由 WrappedEtcdClient 控制，这里只设置上限，使单次操作可以指定更长的超时时间
+            .with_timeout(Duration::from_secs(MAX_CALL_DEADLINE_SECONDS));
 
-        if let Some(user) = connection.user.clone() {
-            option = option.with_user(user.username, user.password)
+        if let Some(user) = &connection.user {
+            option = option.with_user(user.username.clone(), user.password.clone())
         };
 
+        let pinned_fingerprint = connection
+            .tls
+            .as_ref()
+            .and_then(|tls| tls.pinned_fingerprint.clone());
+        let tls_server_name = connection
+            .tls
+            .as_ref()
+            .and_then(|tls| tls.domain.clone())
+            .unwrap_or_else(|| match &endpoint {
+                EndpointAddress::Tcp { host, .. } => host.clone(),
+                EndpointAddress::Unix(_) => String::from("localhost"),
+            });
+
         if let Some(tls) = connection.tls {
             let mut tls_option = TlsOptions::new();
 
@@ -67,34 +150,147 @@ impl EtcdConnector {
             };
 
             if let Some(identity) = tls.identity {
-                tls_option = tls_option.identity(Identity::from_pem(identity.cert, identity.key))
+                let (cert, key) = cert_util::resolve_identity(&identity)?;
+                tls_option = tls_option.identity(Identity::from_pem(cert, key))
             };
 
             option = option.with_tls(tls_option)
         };
-        let mut host = connection.host;
-        let mut port = connection.port;
+        let txn_limits = TxnLimits::from_connection(&connection);
+        let max_recv_message_bytes = connection.max_recv_message_bytes;
+        let max_send_message_bytes = connection.max_send_message_bytes;
+        let value_crypto = ValueCrypto::new(
+            &connection.host,
+            connection.port,
+            &connection.namespace,
+            &connection.encrypted_prefixes,
+        );
         let namespace = connection.namespace.clone();
 
-        let ssh = if let Some(ssh) = connection.ssh {
-            let ssh_context =
-                SshTunnel::new(ssh, Box::leak(host.clone().into_boxed_str()), port).await?;
-            port = ssh_context.get_proxy_port();
-            host.clear();
-            host.push_str("127.0.0.1");
-            Some(ssh_context)
+        let mut unix_proxy = None;
+        let (host, port, ssh) = if let Some(ssh) = connection.ssh {
+            let ssh_context = SshTunnel::new(ssh, endpoint).await?;
+            (String::from("127.0.0.1"), ssh_context.get_proxy_port(), Some(ssh_context))
         } else {
-            None
+            match endpoint {
+                EndpointAddress::Tcp { host, port } => (host, port, None),
+                EndpointAddress::Unix(path) => {
+                    let proxy = UnixSocketProxy::new(&path).await?;
+                    let port = proxy.get_proxy_port();
+                    unix_proxy = Some(proxy);
+                    (String::from("127.0.0.1"), port, None)
+                }
+            }
         };
 
-        let address = format!("{}:{}", host, port);
+        if let Some(expected) = pinned_fingerprint {
+            let der = cert_util::fetch_server_certificate(
+                &host,
+                port,
+                tls_server_name,
+                Duration::from_secs(settings.connect_timeout_seconds),
+            )
+            .await?;
+            let actual = cert_util::fingerprint_sha256(&der);
+            if !actual.eq_ignore_ascii_case(&expected) {
+                warn!("Server certificate fingerprint mismatch, expected {}, actual {}", expected, actual);
+                return Err(LogicError::CertificateFingerprintMismatch(actual));
+            }
+        }
+
+        let retry = RetryPolicy::new(settings.retry_max_attempts, settings.retry_base_delay_millis);
+        let address = EndpointAddress::Tcp { host, port }.to_string();
         info!("Connect to etcd server: {}", address);
-        let client = Client::connect([address], Some(option)).await?;
-        Ok(EtcdConnector {
+        //  `with_connect_timeout` 只限制TCP连接，TLS握手和认证同样受连接超时限制
+        let connect_timeout = Duration::from_secs(settings.connect_timeout_seconds);
+        let client = wrapped_etcd_client::deadline(connect_timeout, Client::connect([address], Some(option))).await?;
+        let mut connector = EtcdConnector {
             namespace,
-            client: WrappedEtcdClient::new(client, connection.user),
+            client: WrappedEtcdClient::new(client, connection.user)
+                .with_retry(retry)
+                .with_timeout(Duration::from_secs(settings.request_timeout_seconds))
+                .with_message_limits(max_recv_message_bytes, max_send_message_bytes),
             ssh,
-        })
+            unix_proxy,
+            seed_address,
+            endpoint_scheme,
+            member_endpoints: vec![],
+            read_revision: None,
+            value_crypto,
+            value_cache: None,
+            txn_limits,
+            session: None,
+        };
+        if sync_endpoints {
+            //  同步失败不影响使用配置的地址连接
+            if let Err(e) = connector.sync_member_endpoints().await {
+                warn!("Failed to sync member endpoints of {}: {:?}", connector.seed_address, e);
+            }
+        }
+        Ok(connector)
+    }
+
+    /// 根据成员列表同步客户端使用的地址，使请求在集群所有成员间负载均衡，
+    /// 使用SSH隧道时在同一个SSH会话上为每个成员建立转发。返回当前额外使用的成员地址
+    pub async fn sync_member_endpoints(&mut self) -> Result<Vec<String>, LogicError> {
+        let response = self.client.member_list().await?;
+        let client_urls: Vec<&str> = response
+            .members()
+            .iter()
+            .flat_map(|member| member.client_urls().iter().map(String::as_str))
+            .collect();
+        let addresses = member_endpoint_addresses(&client_urls, &self.seed_address);
+
+        let (kept, removed): (Vec<MemberEndpoint>, Vec<MemberEndpoint>) = std::mem::take(&mut self.member_endpoints)
+            .into_iter()
+            .partition(|member| addresses.iter().any(|address| address.to_string() == member.address));
+        self.member_endpoints = kept;
+        for member in removed {
+            info!("Remove endpoint of removed member: {}", member.address);
+            if let Err(e) = self.client.get_inner().remove_endpoint(&member.endpoint).await {
+                warn!("Failed to remove endpoint {}: {}", member.endpoint, e);
+            }
+        }
+
+        for address in addresses {
+            let display = address.to_string();
+            if self.member_endpoints.iter().any(|member| member.address == display) {
+                continue;
+            }
+            let (endpoint, tunnel) = match &self.ssh {
+                Some(ssh) => match ssh.forward(address).await {
+                    Ok(tunnel) => (
+                        format!("{}://127.0.0.1:{}", self.endpoint_scheme, tunnel.get_proxy_port()),
+                        Some(tunnel),
+                    ),
+                    Err(e) => {
+                        warn!("Failed to open ssh forward to member {}: {:?}", display, e);
+                        continue;
+                    }
+                },
+                None => (format!("{}://{}", self.endpoint_scheme, display), None),
+            };
+            if let Err(e) = self.client.get_inner().add_endpoint(&endpoint).await {
+                warn!("Failed to add endpoint {}: {}", display, e);
+                continue;
+            }
+            info!("Add endpoint of member: {}", display);
+            self.member_endpoints.push(MemberEndpoint {
+                address: display,
+                endpoint,
+                tunnel,
+            });
+        }
+        Ok(self.member_endpoints.iter().map(|member| member.address.clone()).collect())
+    }
+
+    /// 使用中的SSH隧道的传输统计，包括为其他成员建立的转发
+    pub fn tunnel_diagnostics(&self) -> Vec<TunnelDiagnostics> {
+        self.ssh
+            .iter()
+            .chain(self.member_endpoints.iter().filter_map(|member| member.tunnel.as_ref()))
+            .map(|tunnel| tunnel.diagnostics())
+            .collect()
     }
 
     pub fn has_namespace(&self) -> bool {
@@ -109,14 +305,40 @@ impl EtcdConnector {
         &self.namespace.as_ref().unwrap()
     }
 
+    /// 为接下来的操作指定超时时间，返回的作用域结束后恢复连接默认的超时时间
+    pub fn with_deadline(&mut self, deadline: Option<Duration>) -> DeadlineScope<'_> {
+        self.client.set_call_deadline(deadline);
+        DeadlineScope { connector: self }
+    }
+
+    pub fn set_reauth_listener(&mut self, listener: ReauthListener) {
+        self.client.set_reauth_listener(listener);
+    }
+
+    pub fn get_read_revision(&self) -> Option<i64> {
+        self.read_revision
+    }
+
+    pub fn set_read_revision(&mut self, revision: Option<i64>) {
+        self.read_revision = revision;
+    }
+
+    /// 处于历史版本读取模式时为读取选项指定版本
+    fn with_read_revision(&self, option: GetOptions) -> GetOptions {
+        match self.read_revision {
+            Some(rev) => option.with_revision(rev),
+            None => option,
+        }
+    }
+
     pub async fn test_connection(&self) -> Result<(), Error> {
         let key = self.prefix_namespace("/");
-        let response = self
-            .client
-            .get_inner()
-            .kv_client()
-            .get(key, Some(GetOptions::new().with_keys_only()))
-            .await?;
+        let mut kv_client = self.client.get_inner().kv_client();
+        let response = wrapped_etcd_client::deadline(
+            self.client.timeout(),
+            kv_client.get(key, Some(GetOptions::new().with_keys_only())),
+        )
+        .await?;
         debug!("test connection, kv length: {}", response.kvs().len());
         Ok(())
     }
@@ -124,7 +346,7 @@ impl EtcdConnector {
     /// 获取所有key，不包含value
     pub async fn kv_get_all_keys(&mut self) -> Result<Vec<SerializableKeyValue>, Error> {
         let root_path = self.root_key();
-        let get_options = GetOptions::new().with_prefix().with_keys_only();
+        let get_options = self.with_read_revision(GetOptions::new().with_prefix().with_keys_only());
         self.kv_get_by_option(root_path, Some(get_options)).await
     }
 
@@ -134,18 +356,36 @@ impl EtcdConnector {
         cursor_key: impl Into<Vec<u8>>,
         limit: i64,
     ) -> Result<Vec<SerializableKeyValue>, Error> {
+        let page = self.kv_get_all_keys_paging_at(cursor_key, limit, None).await?;
+        Ok(page.kvs)
+    }
+
+    /// 分页获取所有key，不包含value。
+    ///
+    /// `revision` 为空时读取最新数据，并在结果中返回本次读取的版本号；
+    /// 后续分页传入该版本号即可让所有分页读取同一版本的数据，避免读取过程中集群写入导致结果不一致。
+    pub async fn kv_get_all_keys_paging_at(
+        &mut self,
+        cursor_key: impl Into<Vec<u8>>,
+        limit: i64,
+        revision: Option<i64>,
+    ) -> Result<KeyValuePage, Error> {
         let mut cursor: Vec<u8> = cursor_key.into();
         cursor.push(0);
 
         let key = self.prefix_namespace(cursor);
         let end_key = self.prefix_namespace_to_range_end(vec![0]);
 
-        let get_options = GetOptions::new()
+        let mut get_options = GetOptions::new()
             .with_keys_only()
             .with_range(end_key)
             .with_limit(limit)
             .with_sort(SortTarget::Key, SortOrder::Ascend);
-        self.kv_get_by_option(key, Some(get_options)).await
+        let revision = revision.or(self.read_revision);
+        if let Some(rev) = revision {
+            get_options = get_options.with_revision(rev);
+        }
+        self.kv_get_page_by_option(key, Some(get_options), revision).await
     }
 
     async fn kv_get_by_option(
@@ -154,8 +394,25 @@ impl EtcdConnector {
         option: Option<GetOptions>,
     ) -> Result<Vec<SerializableKeyValue>, Error> {
         let mut response = self.client.kv_get_request(key, option).await?;
+        Ok(self.convert_kvs(response.take_kvs()))
+    }
+
+    /// 读取一页数据，同时返回读取的版本号。指定了 `revision` 时返回该版本，否则为响应头中的版本号
+    async fn kv_get_page_by_option(
+        &mut self,
+        key: Vec<u8>,
+        option: Option<GetOptions>,
+        revision: Option<i64>,
+    ) -> Result<KeyValuePage, Error> {
+        let mut response = self.client.kv_get_request(key, option).await?;
+        let revision = revision.unwrap_or_else(|| response.header().map(|h| h.revision()).unwrap_or(0));
+        let more = response.more();
+        let kvs = self.convert_kvs(response.take_kvs());
+        let cursor = kvs.last().map(|kv| kv.key.clone());
+        Ok(KeyValuePage { revision, more, kvs, cursor })
+    }
 
-        let kvs = response.take_kvs();
+    fn convert_kvs(&self, kvs: Vec<KeyValue>) -> Vec<SerializableKeyValue> {
         let mut arr = Vec::with_capacity(kvs.len());
         for kv in kvs {
             let mut s_kv = SerializableKeyValue::from(kv);
@@ -164,7 +421,7 @@ impl EtcdConnector {
             }
             arr.push(s_kv);
         }
-        Ok(arr)
+        arr
     }
 
     /// 请求Key-Value
@@ -183,9 +440,61 @@ impl EtcdConnector {
         key: impl Into<Vec<u8>>,
     ) -> Result<SerializableKeyValue, LogicError> {
         let path = self.prefix_namespace(key);
-        let kv = self.kv_get_by_option(path, None).await?;
+        let cached = self
+            .value_cache
+            .as_ref()
+            .filter(|_| self.read_revision.is_none())
+            .map(|cache| cache.get(&path));
+        match cached {
+            Some(Some(kv)) => Ok(kv),
+            Some(None) => {
+                let mut response = self.client.kv_get_request(path.clone(), None).await?;
+                let revision = response.header().map(|h| h.revision()).unwrap_or(0);
+                let kvs = self.convert_kvs(response.take_kvs());
+                let kv = self.find_first_kv(kvs).await?;
+                if let Some(cache) = &self.value_cache {
+                    cache.put(path, revision, kv.clone());
+                }
+                Ok(kv)
+            }
+            None => {
+                let option = self.read_revision.map(|rev| GetOptions::new().with_revision(rev));
+                let kv = self.kv_get_by_option(path, option).await?;
+                self.find_first_kv(kv).await
+            }
+        }
+    }
+
+    /// 开启或关闭键值读缓存，开启后通过 watch 失效发生变化的key
+    pub async fn set_value_cache_enabled(&mut self, enabled: bool) -> Result<(), Error> {
+        if !enabled {
+            self.value_cache = None;
+            return Ok(());
+        }
+        let usable = self.value_cache.as_ref().map(|c| c.stats().enabled).unwrap_or(false);
+        if !usable {
+            let (watcher, stream) = self.kv_watch_prefix("").await?;
+            self.value_cache = Some(ValueCache::start(watcher, stream));
+        }
+        Ok(())
+    }
+
+    pub fn value_cache_stats(&self) -> Option<ValueCacheStats> {
+        self.value_cache.as_ref().map(|c| c.stats())
+    }
 
-        self.find_first_kv(kv)
+    pub fn set_session(&mut self, session: i32) {
+        self.session = Some(session);
+    }
+
+    /// 本连接写入的key立即失效，避免 watch 事件到达前读到旧值
+    fn invalidate_cache(&self, path: &[u8]) {
+        if let Some(cache) = &self.value_cache {
+            cache.invalidate(path);
+        }
+        if let Some(session) = self.session {
+            prefetcher::invalidate(session, &self.strip_namespace(path.to_vec()));
+        }
     }
 
     /// 根据历史版本获取键值对详情
@@ -199,40 +508,201 @@ impl EtcdConnector {
             .kv_get_by_option(path, Some(GetOptions::new().with_revision(version)))
             .await?;
 
-        self.find_first_kv(kv)
+        self.find_first_kv(kv).await
     }
 
-    /// 根据前缀搜索键
+    /// 根据前缀搜索键，`revision` 不为空时在指定版本中搜索
     pub async fn kv_get_with_prefix(
         &mut self,
         prefix: impl Into<Vec<u8>>,
+        revision: Option<i64>,
     ) -> Result<SearchResult, LogicError> {
         let key = self.prefix_namespace(prefix);
-        let option = GetOptions::new()
+        let mut option = GetOptions::new()
         .with_prefix()
         .with_limit(50)
         .with_keys_only();
+        if let Some(rev) = revision.or(self.read_revision) {
+            option = option.with_revision(rev);
+        }
 
         let mut response = self.client.kv_get_request(key, Some(option)).await?;
-        
-        let kvs = response.take_kvs();
-        let mut arr = Vec::with_capacity(kvs.len());
-        for kv in kvs {
-            let mut s_kv = SerializableKeyValue::from(kv);
-            if let Some(namespace) = &self.namespace {
-                s_kv.remove_prefix(namespace);
-            }
-            arr.push(s_kv);
-        }
+        let revision = response.header().map(|h| h.revision()).unwrap_or(0);
+        //  非UTF-8的key无法在界面中再次打开，不放入结果但计数返回
+        let (kvs, binary): (Vec<_>, Vec<_>) = response
+            .take_kvs()
+            .into_iter()
+            .partition(|kv| std::str::from_utf8(kv.key()).is_ok());
+        let arr = self.convert_kvs(kvs);
 
         Ok(SearchResult{
             count: response.count() as usize,
             results: arr,
+            revision,
+            binary_skipped: binary.len(),
         })
     }
 
-    fn find_first_kv(
+    /// 获取以 `prefix` 开头的key，不包含value，最多 `limit` 个
+    pub async fn kv_get_keys_with_prefix(
+        &mut self,
+        prefix: impl Into<Vec<u8>>,
+        limit: i64,
+    ) -> Result<Vec<SerializableKeyValue>, Error> {
+        let key = self.prefix_namespace(prefix);
+        let option = GetOptions::new()
+            .with_prefix()
+            .with_keys_only()
+            .with_limit(limit)
+            .with_sort(SortTarget::Key, SortOrder::Ascend);
+        let option = self.with_read_revision(option);
+        self.kv_get_by_option(key, Some(option)).await
+    }
+
+    /// 并发读取多个前缀下的key（不含value），同时进行的请求不超过 `parallelism` 个，结果顺序与 `prefixes` 一致
+    pub async fn kv_get_keys_with_prefixes(
         &self,
+        prefixes: &[String],
+        limit: i64,
+        parallelism: usize,
+    ) -> Vec<Result<KeyValuePage, Error>> {
+        let semaphore = Arc::new(Semaphore::new(parallelism.max(1)));
+        let mut tasks = JoinSet::new();
+        for (i, prefix) in prefixes.iter().enumerate() {
+            let key = self.prefix_namespace(prefix.as_str());
+            let option = self.with_read_revision(
+                GetOptions::new()
+                    .with_prefix()
+                    .with_keys_only()
+                    .with_limit(limit)
+                    .with_sort(SortTarget::Key, SortOrder::Ascend),
+            );
+            let mut client = self.client.clone();
+            let semaphore = Arc::clone(&semaphore);
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                (i, client.kv_get_request(key, Some(option)).await)
+            });
+        }
+
+        let mut responses: Vec<Option<Result<GetResponse, Error>>> = prefixes.iter().map(|_| None).collect();
+        while let Some(joined) = tasks.join_next().await {
+            if let Ok((i, response)) = joined {
+                responses[i] = Some(response);
+            }
+        }
+        responses
+            .into_iter()
+            .map(|response| match response {
+                Some(Ok(mut response)) => {
+                    let kvs = self.convert_kvs(response.take_kvs());
+                    Ok(KeyValuePage {
+                        revision: response.header().map(|h| h.revision()).unwrap_or(0),
+                        more: response.more(),
+                        cursor: kvs.last().map(|kv| kv.key.clone()),
+                        kvs,
+                    })
+                }
+                Some(Err(e)) => Err(e),
+                None => Err(Error::InvalidArgs(String::from("The request was aborted"))),
+            })
+            .collect()
+    }
+
+    /// 按条件读取键值对
+    pub async fn kv_range(
+        &mut self,
+        key: impl Into<Vec<u8>>,
+        prefix: bool,
+        keys_only: bool,
+        limit: i64,
+        revision: Option<i64>,
+    ) -> Result<Vec<SerializableKeyValue>, Error> {
+        let key = self.prefix_namespace(key);
+        let mut option = GetOptions::new()
+            .with_limit(limit)
+            .with_sort(SortTarget::Key, SortOrder::Ascend);
+        if prefix {
+            option = option.with_prefix();
+        }
+        if keys_only {
+            option = option.with_keys_only();
+        }
+        option = match revision {
+            Some(rev) => option.with_revision(rev),
+            None => self.with_read_revision(option),
+        };
+        let mut kvs = self.kv_get_by_option(key, Some(option)).await?;
+        for kv in kvs.iter_mut() {
+            self.decrypt_kv(kv);
+        }
+        Ok(kvs)
+    }
+
+    /// 解密客户端加密的值，失败时保留原始值并记录失败原因
+    fn decrypt_kv(&mut self, kv: &mut SerializableKeyValue) {
+        if !ValueCrypto::is_encrypted(&kv.value) {
+            return;
+        }
+        kv.encrypted = true;
+        let result = match self.value_crypto.as_mut() {
+            Some(crypto) => crypto.decrypt(kv.key.as_bytes(), &kv.value),
+            None => Err(String::from("Encryption is not configured for this connection")),
+        };
+        match result {
+            Ok(Some(value)) => kv.value = value,
+            Ok(None) => {}
+            Err(e) => kv.decrypt_error = Some(e),
+        }
+    }
+
+    /// 如果key属于加密前缀，加密值
+    fn encrypt_value(&mut self, key: &[u8], value: Vec<u8>) -> Result<Vec<u8>, Error> {
+        match self.value_crypto.as_mut() {
+            Some(crypto) => crypto.encrypt(key, value).map_err(Error::InvalidArgs),
+            None => Ok(value),
+        }
+    }
+
+    /// 清除内存中缓存的值加密密钥
+    pub fn clear_encryption_keys(&mut self) {
+        if let Some(crypto) = self.value_crypto.as_mut() {
+            crypto.clear_keys();
+        }
+    }
+
+    /// 会话锁定时清除连接使用的认证凭据和值加密密钥
+    pub fn clear_credentials(&mut self) {
+        self.client.clear_credentials();
+        self.clear_encryption_keys();
+    }
+
+    /// 解锁后恢复认证用户并重新认证
+    pub async fn restore_credentials(&mut self, user: Option<ConnectionUser>) -> Result<(), LogicError> {
+        self.client.restore_credentials(user).await?;
+        Ok(())
+    }
+
+    /// 导出加密前缀的密钥，不存在时生成新密钥
+    pub fn export_encryption_key(&mut self, prefix: &str) -> Result<String, LogicError> {
+        let crypto = self
+            .value_crypto
+            .as_mut()
+            .ok_or_else(|| LogicError::IllegalArgument(String::from("Encryption is not configured for this connection")))?;
+        crypto.export_key(prefix).map_err(LogicError::IllegalArgument)
+    }
+
+    /// 导入加密前缀的密钥
+    pub fn import_encryption_key(&mut self, prefix: &str, key: &str) -> Result<(), LogicError> {
+        let crypto = self
+            .value_crypto
+            .as_mut()
+            .ok_or_else(|| LogicError::IllegalArgument(String::from("Encryption is not configured for this connection")))?;
+        crypto.import_key(prefix, key).map_err(LogicError::IllegalArgument)
+    }
+
+    async fn find_first_kv(
+        &mut self,
         kv: Vec<SerializableKeyValue>,
     ) -> Result<SerializableKeyValue, LogicError> {
         if kv.is_empty() {
@@ -245,8 +715,12 @@ impl EtcdConnector {
             if let Some(namespace) = &self.namespace {
                 s_kv.remove_prefix(namespace);
             }
+            self.decrypt_kv(&mut s_kv);
 
-            s_kv.formatted_value = k8s_formatter::try_format_proto(&full_key, &s_kv.value);
+            s_kv.formatted_value = match k8s_formatter::try_format_proto(&full_key, &s_kv.value) {
+                Some(formatted) => Some(formatted),
+                None => value_plugin::try_format(full_key, s_kv.value.clone()).await,
+            };
             Ok(s_kv)
         }
     }
@@ -256,11 +730,34 @@ impl EtcdConnector {
         let key = self.prefix_namespace("/");
         let response = self
             .client
-            .kv_get_request(key, Some(GetOptions::new().with_count_only()))
+            .kv_get_request(key, Some(self.with_read_revision(GetOptions::new().with_count_only())))
             .await?;
         Ok(response.count())
     }
 
+    /// 获取当前命名空间下所有Key的数量，同时返回统计时的版本
+    pub async fn kv_count_all(&mut self) -> Result<(i64, i64), Error> {
+        let key = self.prefix_namespace(vec![0]);
+        let end_key = self.prefix_namespace_to_range_end(vec![0]);
+        let response = self
+            .client
+            .kv_get_request(key, Some(GetOptions::new().with_range(end_key).with_count_only()))
+            .await?;
+        let revision = response.header().map(|h| h.revision()).unwrap_or(0);
+        Ok((response.count(), revision))
+    }
+
+    /// 获取前缀下Key的数量，同时返回统计时的版本
+    pub async fn kv_count_prefix(&mut self, prefix: impl Into<Vec<u8>>) -> Result<(i64, i64), Error> {
+        let key = self.prefix_namespace(prefix);
+        let response = self
+            .client
+            .kv_get_request(key, Some(GetOptions::new().with_prefix().with_count_only()))
+            .await?;
+        let revision = response.header().map(|h| h.revision()).unwrap_or(0);
+        Ok((response.count(), revision))
+    }
+
     /// 更新键值对
     pub async fn kv_put(
         &mut self,
@@ -269,6 +766,8 @@ impl EtcdConnector {
         ttl: Option<i64>,
     ) -> Result<(), Error> {
         let mut lease_id = 0;
+        let key = key.into();
+        let value = self.encrypt_value(&key, value.into())?;
         let final_key = self.prefix_namespace(key);
         if let Some(ttl_param) = ttl {
             let response = self.client.lease_grant(ttl_param, None).await?;
@@ -289,46 +788,762 @@ impl EtcdConnector {
             Some(PutOptions::new().with_lease(lease_id))
         };
 
+        self.invalidate_cache(&final_key);
+        self.client
+            .kv_put_request(final_key, value, option)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 将Key绑定到lease中
+    pub async fn kv_put_with_lease(
+        &mut self,
+        key: impl Into<Vec<u8>>,
+        value: impl Into<Vec<u8>>,
+        lease: i64,
+    ) -> Result<(), Error> {
+        let key = key.into();
+        let value = self.encrypt_value(&key, value.into())?;
+        let final_key = self.prefix_namespace(key);
+        self.invalidate_cache(&final_key);
         self.client
-            .kv_put_request(final_key, value.into(), option)
+            .kv_put_request(
+                final_key,
+                value,
+                Some(PutOptions::new().with_lease(lease)),
+            )
             .await?;
 
-        Ok(())
+        Ok(())
+    }
+
+    /// 删除键值对
+    pub async fn kv_delete(&mut self, keys: Vec<impl Into<Vec<u8>>>) -> Result<usize, Error> {
+        let mut success = 0usize;
+        for key in keys {
+            let path = self.prefix_namespace(key);
+            self.invalidate_cache(&path);
+            let result = self
+                .client
+                .kv_delete_request(path, None)
+                .await;
+            if result.is_ok() {
+                success += 1;
+            }
+        }
+
+        Ok(success)
+    }
+
+    /// 删除key并返回删除成功的数量及被删除的键值对，键值对为去除命名空间后的原始内容（不解密），用于放入回收站
+    pub async fn kv_delete_with_prev(
+        &mut self,
+        keys: Vec<impl Into<Vec<u8>>>,
+    ) -> Result<(usize, Vec<(Vec<u8>, Vec<u8>, i64)>), Error> {
+        let mut success = 0usize;
+        let mut deleted = Vec::with_capacity(keys.len());
+        for key in keys {
+            let path = self.prefix_namespace(key);
+            self.invalidate_cache(&path);
+            let result = self
+                .client
+                .kv_delete_request(path, Some(DeleteOptions::new().with_prev_key()))
+                .await;
+            if let Ok(mut response) = result {
+                success += 1;
+                for kv in response.take_prev_kvs() {
+                    deleted.push((self.strip_namespace(kv.key().to_vec()), kv.value().to_vec(), kv.lease()));
+                }
+            }
+        }
+
+        Ok((success, deleted))
+    }
+
+    /// 批量写入键值对，按服务端的事务限制拆分为多个事务依次提交。
+    /// 服务端仍返回超出限制的错误时（实际限制小于配置），将该批次对半拆分后重试
+    pub async fn kv_put_batch(
+        &mut self,
+        kvs: Vec<(String, Vec<u8>)>,
+        task: Option<&TaskHandle>,
+    ) -> Result<BatchPutResult, Error> {
+        let mut encrypted = Vec::with_capacity(kvs.len());
+        for (key, value) in kvs {
+            let value = self.encrypt_value(key.as_bytes(), value)?;
+            encrypted.push((key.into_bytes(), value));
+        }
+        self.kv_put_raw_batch(encrypted, task).await
+    }
+
+    /// 批量写入原始值，不经过客户端加密，用于镜像等需要保持存储内容一致的场景。
+    /// 传入 `task` 时每批写入后上报进度，任务被取消时停止写入剩余批次
+    pub async fn kv_put_raw_batch(
+        &mut self,
+        kvs: Vec<(Vec<u8>, Vec<u8>)>,
+        task: Option<&TaskHandle>,
+    ) -> Result<BatchPutResult, Error> {
+        let mut result = BatchPutResult {
+            total: kvs.len(),
+            ..Default::default()
+        };
+        let mut prepared = Vec::with_capacity(kvs.len());
+        for (key, value) in kvs {
+            let final_key = self.prefix_namespace(key);
+            self.invalidate_cache(&final_key);
+            prepared.push((final_key, value));
+        }
+
+        let ranges = self.txn_limits.split(&prepared).map_err(|key| {
+            Error::InvalidArgs(format!(
+                "The size of key {} exceeds the max request bytes {}",
+                String::from_utf8_lossy(&key),
+                self.txn_limits.max_request_bytes
+            ))
+        })?;
+        let mut queue = std::collections::VecDeque::from(ranges);
+        while let Some(range) = queue.pop_front() {
+            let ops: Vec<TxnOp> = prepared[range.clone()]
+                .iter()
+                .map(|(key, value)| TxnOp::put(key.clone(), value.clone(), None))
+                .collect();
+            match self.client.txn(Txn::new().and_then(ops)).await {
+                Ok(_) => {
+                    result.written += range.len();
+                    result.batches += 1;
+                    if let Some(task) = task {
+                        task.progress(result.written as u64, result.total as u64);
+                        if task.is_cancelled() {
+                            result.error_msg = Some(String::from("Cancelled"));
+                            break;
+                        }
+                    }
+                }
+                Err(e) if txn_batch::is_limit_exceeded(&e) && range.len() > 1 => {
+                    let mid = range.start + range.len() / 2;
+                    debug!("Txn exceeds server limits, split batch {:?} at {}", range, mid);
+                    queue.push_front(mid..range.end);
+                    queue.push_front(range.start..mid);
+                }
+                Err(e) => {
+                    result.error_msg = Some(e.to_string());
+                    break;
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// 将前缀下所有key绑定到 `lease`，保留原始值。按事务限制分批重写，每个key以读取时的修改版本做CAS校验，
+    /// 校验失败的批次逐个key重试，期间被修改的key记为冲突。
+    ///
+    /// 开始写入后的失败记录在 `error_msg` 中，返回错误时没有任何key被修改
+    pub async fn kv_attach_lease_prefix(
+        &mut self,
+        prefix: impl Into<Vec<u8>>,
+        lease: i64,
+    ) -> Result<LeaseAttachResult, Error> {
+        let key = self.prefix_namespace(prefix);
+        let mut response = self
+            .client
+            .kv_get_request(key, Some(GetOptions::new().with_prefix()))
+            .await?;
+        let kvs: Vec<(Vec<u8>, Vec<u8>, i64)> = response
+            .take_kvs()
+            .into_iter()
+            .map(|kv| {
+                let mod_revision = kv.mod_revision();
+                let (key, value) = kv.into_key_value();
+                (key, value, mod_revision)
+            })
+            .collect();
+
+        let mut result = LeaseAttachResult {
+            lease,
+            total: kvs.len(),
+            ..Default::default()
+        };
+        let sizes: Vec<(Vec<u8>, Vec<u8>)> = kvs.iter().map(|(k, v, _)| (k.clone(), v.clone())).collect();
+        let ranges = self.txn_limits.split(&sizes).map_err(|key| {
+            Error::InvalidArgs(format!(
+                "The size of key {} exceeds the max request bytes {}",
+                String::from_utf8_lossy(&key),
+                self.txn_limits.max_request_bytes
+            ))
+        })?;
+
+        let put_option = || Some(PutOptions::new().with_lease(lease));
+        let mut queue = std::collections::VecDeque::from(ranges);
+        while let Some(range) = queue.pop_front() {
+            let batch = &kvs[range.clone()];
+            let compares: Vec<Compare> = batch
+                .iter()
+                .map(|(key, _, revision)| Compare::mod_revision(key.clone(), CompareOp::Equal, *revision))
+                .collect();
+            let ops: Vec<TxnOp> = batch
+                .iter()
+                .map(|(key, value, _)| TxnOp::put(key.clone(), value.clone(), put_option()))
+                .collect();
+            for (key, _, _) in batch {
+                self.invalidate_cache(key);
+            }
+            match self.client.txn(Txn::new().when(compares).and_then(ops)).await {
+                Ok(response) if response.succeeded() => {
+                    result.attached += range.len();
+                    result.batches += 1;
+                }
+                Ok(_) => {
+                    //  批次中存在已被修改的key，逐个重试以找出冲突
+                    for (key, value, revision) in batch {
+                        let txn = Txn::new()
+                            .when(vec![Compare::mod_revision(key.clone(), CompareOp::Equal, *revision)])
+                            .and_then(vec![TxnOp::put(key.clone(), value.clone(), put_option())]);
+                        match self.client.txn(txn).await {
+                            Ok(response) if response.succeeded() => result.attached += 1,
+                            Ok(_) => {
+                                let key = self.strip_namespace(key.clone());
+                                result.conflicts.push(String::from_utf8_lossy(&key).to_string());
+                            }
+                            Err(e) => {
+                                result.error_msg = Some(e.to_string());
+                                break;
+                            }
+                        }
+                        result.batches += 1;
+                    }
+                    if result.error_msg.is_some() {
+                        break;
+                    }
+                }
+                Err(e) if txn_batch::is_limit_exceeded(&e) && range.len() > 1 => {
+                    let mid = range.start + range.len() / 2;
+                    debug!("Txn exceeds server limits, split batch {:?} at {}", range, mid);
+                    queue.push_front(mid..range.end);
+                    queue.push_front(range.start..mid);
+                }
+                Err(e) => {
+                    result.error_msg = Some(e.to_string());
+                    break;
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// 写入原始值，`only_absent` 为true时仅在key不存在时写入，返回是否写入
+    pub async fn kv_put_raw(
+        &mut self,
+        key: impl Into<Vec<u8>>,
+        value: impl Into<Vec<u8>>,
+        only_absent: bool,
+    ) -> Result<bool, Error> {
+        let final_key = self.prefix_namespace(key);
+        self.invalidate_cache(&final_key);
+        if !only_absent {
+            self.client.kv_put_request(final_key, value.into(), None).await?;
+            return Ok(true);
+        }
+        let txn = Txn::new()
+            .when(vec![Compare::create_revision(final_key.clone(), CompareOp::Equal, 0)])
+            .and_then(vec![TxnOp::put(final_key, value, None)]);
+        Ok(self.client.txn(txn).await?.succeeded())
+    }
+
+    /// key的修改版本等于 `expected_mod_revision` 时写入原始内容，key不存在时修改版本为0。
+    /// 返回写入后的版本，key已被修改时返回 None
+    pub async fn kv_put_raw_if_unchanged(
+        &mut self,
+        key: impl Into<Vec<u8>>,
+        value: impl Into<Vec<u8>>,
+        expected_mod_revision: i64,
+    ) -> Result<Option<i64>, Error> {
+        let final_key = self.prefix_namespace(key);
+        self.invalidate_cache(&final_key);
+        let txn = Txn::new()
+            .when(vec![Compare::mod_revision(final_key.clone(), CompareOp::Equal, expected_mod_revision)])
+            .and_then(vec![TxnOp::put(final_key, value, None)]);
+        let response = self.client.txn(txn).await?;
+        if !response.succeeded() {
+            return Ok(None);
+        }
+        Ok(Some(response.header().map(|h| h.revision()).unwrap_or(0)))
+    }
+
+    /// key的修改版本等于 `expected_mod_revision` 时删除，返回是否已删除
+    pub async fn kv_delete_raw_if_unchanged(
+        &mut self,
+        key: impl Into<Vec<u8>>,
+        expected_mod_revision: i64,
+    ) -> Result<bool, Error> {
+        let final_key = self.prefix_namespace(key);
+        self.invalidate_cache(&final_key);
+        let txn = Txn::new()
+            .when(vec![Compare::mod_revision(final_key.clone(), CompareOp::Equal, expected_mod_revision)])
+            .and_then(vec![TxnOp::delete(final_key, None)]);
+        Ok(self.client.txn(txn).await?.succeeded())
+    }
+
+    /// 读取前缀下所有键值对的原始内容（不解密），返回读取的版本以及去除命名空间后的键值对
+    pub async fn kv_range_raw(&mut self, prefix: impl Into<Vec<u8>>) -> Result<(i64, Vec<(Vec<u8>, Vec<u8>)>), Error> {
+        let key = self.prefix_namespace(prefix);
+        let mut response = self
+            .client
+            .kv_get_request(key, Some(GetOptions::new().with_prefix()))
+            .await?;
+        let revision = response.header().map(|h| h.revision()).unwrap_or(0);
+        let kvs = response
+            .take_kvs()
+            .into_iter()
+            .map(|kv| {
+                let (key, value) = kv.into_key_value();
+                (self.strip_namespace(key), value)
+            })
+            .collect();
+        Ok((revision, kvs))
+    }
+
+    /// 读取前缀下所有的键值对并解密，返回读取时的版本
+    pub async fn kv_get_prefix_values(
+        &mut self,
+        prefix: impl Into<Vec<u8>>,
+    ) -> Result<(i64, Vec<SerializableKeyValue>), Error> {
+        let key = self.prefix_namespace(prefix);
+        let mut response = self
+            .client
+            .kv_get_request(key, Some(GetOptions::new().with_prefix()))
+            .await?;
+        let revision = response.header().map(|h| h.revision()).unwrap_or(0);
+        let mut kvs = self.convert_kvs(response.take_kvs());
+        for kv in kvs.iter_mut() {
+            self.decrypt_kv(kv);
+        }
+        Ok((revision, kvs))
+    }
+
+    /// 通过从 `start_revision` 开始监听重放前缀下的历史修改，获取到 `end_revision` 为止的所有记录，
+    /// 包括已删除的key。值为存储的原始内容，不进行解密。
+    ///
+    /// 前缀下的修改可能早于 `end_revision` 结束，一段时间内没有新事件时请求监听进度，进度追上 `end_revision` 时结束。
+    /// 多次请求仍未追上或监听被关闭时提前结束，返回已确认读取到的版本，小于 `end_revision` 时记录不完整
+    pub async fn kv_history_records(
+        &mut self,
+        prefix: impl Into<Vec<u8>>,
+        start_revision: i64,
+        end_revision: i64,
+    ) -> Result<(Vec<HistoryRecord>, i64), Error> {
+        let (mut watcher, mut stream) = self.kv_watch_prefix_from(prefix, start_revision).await?;
+        let mut records = Vec::new();
+        let mut reached = start_revision - 1;
+        let mut idle = 0;
+        loop {
+            let message = match tokio::time::timeout(HISTORY_IDLE_TIMEOUT, stream.message()).await {
+                Ok(message) => message?,
+                Err(_) => {
+                    idle += 1;
+                    if idle > HISTORY_MAX_PROGRESS_REQUESTS {
+                        warn!("History replay stopped at revision {}, expected {}", reached, end_revision);
+                        break;
+                    }
+                    watcher.request_progress().await?;
+                    continue;
+                }
+            };
+            idle = 0;
+            let Some(response) = message else {
+                break;
+            };
+            if response.compact_revision() > 0 {
+                let _ = watcher.cancel().await;
+                return Err(Error::InvalidArgs(format!(
+                    "Revision {} has been compacted, compact revision: {}",
+                    start_revision,
+                    response.compact_revision()
+                )));
+            }
+            //  创建监听之外没有事件的响应为进度通知，响应头中的版本之前的修改都已推送
+            if response.events().is_empty() && !response.created() {
+                if let Some(header) = response.header() {
+                    reached = reached.max(header.revision().min(end_revision));
+                }
+            }
+            let mut done = reached >= end_revision;
+            for event in response.events() {
+                let Some(kv) = event.kv() else {
+                    continue;
+                };
+                if kv.mod_revision() > end_revision {
+                    reached = end_revision;
+                    done = true;
+                    break;
+                }
+                let is_put = event.event_type() == EventType::Put;
+                records.push(HistoryRecord {
+                    key: String::from_utf8_lossy(&self.strip_namespace(kv.key().to_vec())).to_string(),
+                    event_type: String::from(if is_put { "put" } else { "delete" }),
+                    mod_revision: kv.mod_revision(),
+                    create_revision: kv.create_revision(),
+                    version: kv.version(),
+                    lease: kv.lease(),
+                    value: if is_put { BASE64_STANDARD.encode(kv.value()) } else { String::new() },
+                });
+                reached = reached.max(kv.mod_revision());
+                if kv.mod_revision() >= end_revision {
+                    done = true;
+                }
+            }
+            if done {
+                break;
+            }
+        }
+        let _ = watcher.cancel().await;
+        Ok((records, reached))
+    }
+
+    /// 去除key中的命名空间前缀
+    pub fn strip_namespace(&self, key: Vec<u8>) -> Vec<u8> {
+        match &self.namespace {
+            Some(namespace) if !namespace.is_empty() && key.starts_with(namespace.as_bytes()) => {
+                key[namespace.len()..].to_vec()
+            }
+            _ => key,
+        }
+    }
+
+    /// 从指定版本开始监听前缀
+    pub async fn kv_watch_prefix_from(
+        &mut self,
+        prefix: impl Into<Vec<u8>>,
+        start_revision: i64,
+    ) -> Result<(Watcher, WatchStream), Error> {
+        let key = self.prefix_namespace(prefix);
+        self.client
+            .watch(
+                key,
+                Some(WatchOptions::new().with_prefix().with_start_revision(start_revision)),
+            )
+            .await
+    }
+
+    /// 从指定版本开始监听单个key
+    pub async fn kv_watch_key_from(
+        &mut self,
+        key: impl Into<Vec<u8>>,
+        start_revision: i64,
+    ) -> Result<(Watcher, WatchStream), Error> {
+        let key = self.prefix_namespace(key);
+        self.client
+            .watch(key, Some(WatchOptions::new().with_start_revision(start_revision)))
+            .await
+    }
+
+    /// key不存在时写入并绑定lease，已存在时返回现有的值
+    pub async fn kv_put_if_absent(
+        &mut self,
+        key: impl Into<Vec<u8>>,
+        value: impl Into<Vec<u8>>,
+        lease: i64,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let final_key = self.prefix_namespace(key);
+        let txn = Txn::new()
+            .when(vec![Compare::create_revision(final_key.clone(), CompareOp::Equal, 0)])
+            .and_then(vec![TxnOp::put(
+                final_key.clone(),
+                value,
+                Some(PutOptions::new().with_lease(lease)),
+            )])
+            .or_else(vec![TxnOp::get(final_key.clone(), None)]);
+        self.invalidate_cache(&final_key);
+        let response = self.client.txn(txn).await?;
+        if response.succeeded() {
+            return Ok(None);
+        }
+        for op in response.op_responses() {
+            if let TxnOpResponse::Get(get) = op {
+                if let Some(kv) = get.kvs().first() {
+                    return Ok(Some(Vec::from(kv.value())));
+                }
+            }
+        }
+        //  读取时锁已被释放，视为未获取到但无持有者信息
+        Ok(Some(vec![]))
+    }
+
+    /// 仅当key的修改版本等于 `expected_mod_revision` 时写入，为0表示key不存在时才写入。
+    /// 写入已存在的key时保留其绑定的lease，写入成功时返回新的修改版本
+    pub async fn kv_put_if_mod_revision(
+        &mut self,
+        key: impl Into<Vec<u8>>,
+        value: impl Into<Vec<u8>>,
+        expected_mod_revision: i64,
+    ) -> Result<Option<i64>, Error> {
+        let key = key.into();
+        let value = self.encrypt_value(&key, value.into())?;
+        let final_key = self.prefix_namespace(key);
+        let option = if expected_mod_revision > 0 {
+            Some(PutOptions::new().with_ignore_lease())
+        } else {
+            None
+        };
+        let txn = Txn::new()
+            .when(vec![Compare::mod_revision(final_key.clone(), CompareOp::Equal, expected_mod_revision)])
+            .and_then(vec![TxnOp::put(final_key.clone(), value, option)]);
+        self.invalidate_cache(&final_key);
+        let response = self.client.txn(txn).await?;
+        if response.succeeded() {
+            Ok(Some(response.header().map(|h| h.revision()).unwrap_or(0)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// 删除键值对，`prefix` 为 true 时删除以 `key` 开头的所有键值对，返回删除的数量
+    pub async fn kv_delete_range(
+        &mut self,
+        key: impl Into<Vec<u8>>,
+        prefix: bool,
+    ) -> Result<i64, Error> {
+        let key = self.prefix_namespace(key);
+        let option = if prefix {
+            Some(DeleteOptions::new().with_prefix())
+        } else {
+            None
+        };
+        if prefix {
+            if let Some(session) = self.session {
+                prefetcher::invalidate_prefix(session, &self.strip_namespace(key.clone()));
+            }
+        } else {
+            self.invalidate_cache(&key);
+        }
+        let response = self.client.kv_delete_request(key, option).await?;
+        Ok(response.deleted())
+    }
+
+    /// 删除前缀下的所有键值对，仅当前缀下没有key在 `revision` 之后被创建或修改时才删除。
+    ///
+    /// 按 [`DELETE_BATCH_KEYS`] 分批在事务中删除，避免一次返回所有旧值超出响应大小限制。
+    /// 第一批比较不通过时返回空，否则返回最后删除时的版本、被删除的键值对（去除命名空间）
+    /// 以及是否全部删除，中途有key被修改时停止删除剩余的key
+    pub async fn kv_delete_prefix_at(
+        &mut self,
+        prefix: impl Into<Vec<u8>>,
+        revision: i64,
+    ) -> Result<Option<(i64, Vec<(Vec<u8>, Vec<u8>, i64)>, bool)>, Error> {
+        let key = self.prefix_namespace(prefix);
+        let mut deleted = vec![];
+        let mut last_revision = 0;
+        loop {
+            let option = GetOptions::new()
+                .with_prefix()
+                .with_keys_only()
+                .with_limit(DELETE_BATCH_KEYS)
+                .with_sort(SortTarget::Key, SortOrder::Ascend);
+            let page = self.client.kv_get_request(key.clone(), Some(option)).await?;
+            let Some(last) = page.kvs().last() else {
+                if deleted.is_empty() {
+                    last_revision = page.header().map(|h| h.revision()).unwrap_or(0);
+                }
+                break;
+            };
+            let mut batch_end = last.key().to_vec();
+            batch_end.push(0);
+
+            let txn = Txn::new()
+                .when(vec![Compare::mod_revision(key.clone(), CompareOp::Less, revision + 1).with_prefix()])
+                .and_then(vec![TxnOp::delete(
+                    key.clone(),
+                    Some(DeleteOptions::new().with_range(batch_end).with_prev_key()),
+                )]);
+            let response = self.client.txn(txn).await?;
+            if !response.succeeded() {
+                if deleted.is_empty() {
+                    return Ok(None);
+                }
+                return Ok(Some((last_revision, deleted, false)));
+            }
+            last_revision = response.header().map(|h| h.revision()).unwrap_or(0);
+            for op in response.op_responses() {
+                if let TxnOpResponse::Delete(delete) = op {
+                    for kv in delete.prev_kvs() {
+                        self.invalidate_cache(kv.key());
+                        deleted.push((self.strip_namespace(kv.key().to_vec()), kv.value().to_vec(), kv.lease()));
+                    }
+                }
+            }
+            if !page.more() {
+                break;
+            }
+        }
+        Ok(Some((last_revision, deleted, true)))
+    }
+
+    /// 重命名key，在同一个事务中写入新key并删除旧key，保留值和绑定的lease。
+    ///
+    /// 旧key不存在、读取后被修改或新key已存在时不执行，返回 false
+    pub async fn kv_rename(&mut self, from: impl Into<Vec<u8>>, to: impl Into<Vec<u8>>) -> Result<bool, Error> {
+        let from = from.into();
+        let to = to.into();
+        let from_key = self.prefix_namespace(from.clone());
+        let to_key = self.prefix_namespace(to.clone());
+        let response = self.client.kv_get_request(from_key.clone(), None).await?;
+        let Some(kv) = response.kvs().first() else {
+            return Ok(false);
+        };
+        let mod_revision = kv.mod_revision();
+        let lease = kv.lease();
+        let mut value = kv.value().to_vec();
+        //  加密前缀的密钥与key相关，需要解密后按新key重新加密
+        if ValueCrypto::is_encrypted(&value) {
+            if let Some(crypto) = self.value_crypto.as_mut() {
+                if let Some(plain) = crypto.decrypt(&from, &value).map_err(Error::InvalidArgs)? {
+                    value = plain;
+                }
+            }
+        }
+        let value = self.encrypt_value(&to, value)?;
+        let option = if lease == 0 {
+            None
+        } else {
+            Some(PutOptions::new().with_lease(lease))
+        };
+
+        self.invalidate_cache(&from_key);
+        self.invalidate_cache(&to_key);
+        let txn = Txn::new()
+            .when(vec![
+                Compare::mod_revision(from_key.clone(), CompareOp::Equal, mod_revision),
+                Compare::create_revision(to_key.clone(), CompareOp::Equal, 0),
+            ])
+            .and_then(vec![TxnOp::put(to_key, value, option), TxnOp::delete(from_key, None)]);
+        Ok(self.client.txn(txn).await?.succeeded())
+    }
+
+    /// 在事务中比较后删除键值对，只有当key当前的值或修改版本与期望一致时才会删除。
+    ///
+    /// 返回是否删除成功，比较不通过时返回 false
+    pub async fn kv_delete_if(
+        &mut self,
+        key: impl Into<Vec<u8>>,
+        expected_value: Option<Vec<u8>>,
+        expected_mod_revision: Option<i64>,
+    ) -> Result<bool, Error> {
+        let final_key = self.prefix_namespace(key);
+        let mut compares = Vec::with_capacity(2);
+        if let Some(value) = expected_value {
+            compares.push(Compare::value(final_key.clone(), CompareOp::Equal, value));
+        }
+        if let Some(revision) = expected_mod_revision {
+            compares.push(Compare::mod_revision(final_key.clone(), CompareOp::Equal, revision));
+        }
+        if compares.is_empty() {
+            return Err(Error::InvalidArgs(String::from(
+                "value or mod revision is required",
+            )));
+        }
+
+        self.invalidate_cache(&final_key);
+        let txn = Txn::new()
+            .when(compares)
+            .and_then(vec![TxnOp::delete(final_key, None)]);
+        let response = self.client.txn(txn).await?;
+        Ok(response.succeeded())
     }
 
-    /// 将Key绑定到lease中
-    pub async fn kv_put_with_lease(
+    /// 以不产生修改的请求探测当前用户对key或前缀的权限，返回false表示权限被拒绝。
+    ///
+    /// 读权限使用只统计数量的查询；写权限使用条件永不成立的事务，etcd会校验事务中所有操作的权限，
+    /// 但事务条件本身需要读权限，因此只有写权限的用户会被判断为无权限
+    pub async fn probe_permission(
         &mut self,
         key: impl Into<Vec<u8>>,
-        value: impl Into<Vec<u8>>,
-        lease: i64,
-    ) -> Result<(), Error> {
+        prefix: bool,
+        write: bool,
+    ) -> Result<bool, Error> {
         let final_key = self.prefix_namespace(key);
-        self.client
-            .kv_put_request(
-                final_key,
-                value.into(),
-                Some(PutOptions::new().with_lease(lease)),
-            )
-            .await?;
+        let result = if write {
+            let op = if prefix {
+                TxnOp::delete(final_key.clone(), Some(DeleteOptions::new().with_prefix()))
+            } else {
+                TxnOp::put(final_key.clone(), vec![], None)
+            };
+            //  key的版本不会小于0，事务条件永不成立
+            let txn = Txn::new()
+                .when(vec![Compare::version(final_key, CompareOp::Less, 0)])
+                .and_then(vec![op]);
+            self.client.txn(txn).await.map(|_| ())
+        } else {
+            let mut option = GetOptions::new().with_count_only();
+            if prefix {
+                option = option.with_prefix();
+            }
+            self.client.kv_get_request(final_key, Some(option)).await.map(|_| ())
+        };
+        match result {
+            Ok(_) => Ok(true),
+            //  7: PermissionDenied
+            Err(Error::GRpcStatus(status)) if status.code() as i32 == 7 => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
 
-        Ok(())
+    /// 监听当前命名空间下所有key的变化，从 `start_revision` 开始接收事件
+    pub async fn kv_watch_all(
+        &mut self,
+        start_revision: i64,
+    ) -> Result<(Watcher, WatchStream), Error> {
+        let key = self.prefix_namespace(vec![0]);
+        let end_key = self.prefix_namespace_to_range_end(vec![0]);
+        let option = WatchOptions::new()
+            .with_range(end_key)
+            .with_start_revision(start_revision);
+        self.client.watch(key, Some(option)).await
     }
 
-    /// 删除键值对
-    pub async fn kv_delete(&mut self, keys: Vec<impl Into<Vec<u8>>>) -> Result<usize, Error> {
-        let mut success = 0usize;
-        for key in keys {
-            let result = self
-                .client
-                .kv_delete_request(self.prefix_namespace(key), None)
-                .await;
-            if result.is_ok() {
-                success += 1;
+    /// 监听以 `prefix` 开头的key的变化
+    pub async fn kv_watch_prefix(
+        &mut self,
+        prefix: impl Into<Vec<u8>>,
+    ) -> Result<(Watcher, WatchStream), Error> {
+        let key = self.prefix_namespace(prefix);
+        self.client
+            .watch(key, Some(WatchOptions::new().with_prefix()))
+            .await
+    }
+
+    /// 获取当前键空间的版本范围：当前版本、压缩版本以及最早可读取的版本。
+    ///
+    /// etcd 没有直接返回压缩版本的接口，这里通过二分查找最早可以读取的版本得出
+    pub async fn get_keyspace_bounds(&mut self) -> Result<KeyspaceBounds, Error> {
+        let probe_key = self.prefix_namespace("/");
+        let response = self
+            .client
+            .kv_get_request(probe_key.clone(), Some(GetOptions::new().with_count_only()))
+            .await?;
+        let revision = response.header().map(|h| h.revision()).unwrap_or(0);
+
+        let mut low = 1;
+        let mut high = revision;
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let option = GetOptions::new().with_count_only().with_revision(mid);
+            match self.client.kv_get_request(probe_key.clone(), Some(option)).await {
+                Ok(_) => high = mid,
+                Err(e) => {
+                    if is_compacted_error(&e) {
+                        low = mid + 1;
+                    } else {
+                        return Err(e);
+                    }
+                }
             }
         }
 
-        Ok(success)
+        let oldest_revision = low.min(revision);
+        Ok(KeyspaceBounds {
+            revision,
+            compact_revision: if oldest_revision > 1 { oldest_revision } else { 0 },
+            oldest_revision,
+        })
     }
 
     /// 获取某一个key的历史版本，如果中间某个版本以及被删除或压缩，将终止搜索
@@ -422,6 +1637,14 @@ impl EtcdConnector {
         }
     }
 
+    /// 创建lease续约通道，通过 `LeaseKeeper` 发送续约请求，在返回的流中接收续约结果
+    pub async fn lease_keep_alive(
+        &mut self,
+        lease: i64,
+    ) -> Result<(LeaseKeeper, LeaseKeepAliveStream), Error> {
+        self.client.lease_keep_alive(lease).await
+    }
+
     /// 获取所有lease id
     pub async fn leases(&mut self) -> Result<Vec<String>, Error> {
         let response = self.client.leases().await?;
@@ -481,6 +1704,10 @@ impl EtcdConnector {
     /// 回收lease
     pub async fn lease_revoke(&mut self, lease: i64) -> Result<(), Error> {
         self.client.lease_revoke(lease).await?;
+        //  绑定该lease的key随之删除，无法确定是哪些key
+        if let Some(session) = self.session {
+            prefetcher::clear(session);
+        }
         Ok(())
     }
 
@@ -490,8 +1717,10 @@ impl EtcdConnector {
         let mut auth_client = self.client.get_inner().auth_client();
         let users = response.users();
         let mut result_users = Vec::with_capacity(users.len());
+        //  直接使用内部客户端查询，需要自行设置超时
+        let timeout = self.client.timeout();
         for user in response.users() {
-            let response = auth_client.user_get(user).await?;
+            let response = wrapped_etcd_client::deadline(timeout, auth_client.user_get(user)).await?;
             result_users.push(SerializableUser {
                 user: user.clone(),
                 roles: Vec::from(response.roles()),
@@ -549,6 +1778,45 @@ impl EtcdConnector {
         Ok(false)
     }
 
+    /// 获取连接使用的用户、角色及token信息，token只在首次查询或重新认证后申请，不影响当前使用的token
+    pub async fn session_identity(&mut self) -> Result<SessionIdentity, Error> {
+        let mut identity = SessionIdentity {
+            user: None,
+            roles: vec![],
+            root: true,
+            auth_enabled: false,
+            token_type: None,
+            token_expires_at: None,
+            reauth_count: 0,
+            last_reauth_time: None,
+        };
+        let Some(user) = self.client.get_user().map(|u| u.username.clone()) else {
+            return Ok(identity);
+        };
+
+        match self.client.session_token().await {
+            Ok(Some(token)) => {
+                let (token_type, expires_at) = parse_auth_token(&token);
+                identity.auth_enabled = true;
+                identity.token_type = Some(token_type);
+                identity.token_expires_at = expires_at;
+            }
+            Ok(None) => {}
+            Err(e) if e.to_string().contains("authentication is not enabled") => {}
+            Err(e) => return Err(e),
+        }
+
+        //  未开启认证时用户可能不存在
+        identity.roles = match self.client.user_get(&user).await {
+            Ok(response) => response.roles().to_vec(),
+            Err(e) if identity.auth_enabled => return Err(e),
+            Err(_) => vec![],
+        };
+        identity.root = user == "root" || identity.roles.iter().any(|r| r == "root");
+        identity.user = Some(user);
+        Ok(identity)
+    }
+
     /// 开启权限验证功能，此功能调用后可能会导致connector无法使用
     pub async fn auth_enable(&mut self) -> Result<(), Error> {
         self.client.auth_enable().await?;
@@ -578,7 +1846,7 @@ impl EtcdConnector {
 
         for permission in permissions {
             let key_bytes = permission.key();
-            let key = String::from_utf8(Vec::from(key_bytes)).unwrap();
+            let key = String::from_utf8_lossy(key_bytes).to_string();
             let perm_type = permission.get_type();
             let range_end = permission.range_end();
 
@@ -647,12 +1915,403 @@ impl EtcdConnector {
         Ok(())
     }
 
-    /// 获取集群的详情信息，包含集群数据、成员、报警、状态等信息
-    pub async fn cluster_get(&mut self) -> Result<SerializableCluster, Error> {
-        let mut response = self.client.member_list().await?;
-        let status = self.client.status().await?;
+    /// 按顺序初始化权限配置：角色 -> 角色权限 -> root用户 -> 其他用户 -> 开启权限验证。
+    /// 已存在的角色、权限、用户会被跳过，因此可以重复执行。
+    pub async fn auth_bootstrap(
+        &mut self,
+        config: AuthBootstrapConfig,
+    ) -> Result<Vec<AuthBootstrapStep>, Error> {
+        let mut steps = Vec::new();
+
+        let exist_roles = Vec::from(self.client.role_list().await?.roles());
+        for role_config in config.roles {
+            let role = role_config.role;
+            //  root 角色由 etcd 内置，不需要创建
+            if role == "root" {
+                steps.push(AuthBootstrapStep::new("addRole", role, true));
+                continue;
+            }
+            let role_exist = exist_roles.contains(&role);
+            if !role_exist {
+                self.role_add(role.clone()).await?;
+            }
+            steps.push(AuthBootstrapStep::new("addRole", role.clone(), role_exist));
+
+            let exist_permissions = if role_exist {
+                self.role_get_permissions(role.clone()).await?
+            } else {
+                vec![]
+            };
+            for permission in role_config.permissions {
+                let target = format!("{}:{}", role, permission.key);
+                let granted = exist_permissions.iter().any(|p| p.same_as(&permission));
+                if !granted {
+                    self.role_grant_permission(role.clone(), permission).await?;
+                }
+                steps.push(AuthBootstrapStep::new("grantPermission", target, granted));
+            }
+        }
+
+        let exist_users = Vec::from(self.client.user_list().await?.users());
+        let root = String::from("root");
+        let root_exist = exist_users.contains(&root);
+        if !root_exist {
+            self.user_add(root.clone(), config.root_password).await?;
+        }
+        steps.push(AuthBootstrapStep::new("addUser", root.clone(), root_exist));
+
+        let root_has_role = root_exist && self.user_is_root_role_granted(&root).await?;
+        if !root_has_role {
+            self.user_grant_role(root.clone(), root.clone()).await?;
+        }
+        steps.push(AuthBootstrapStep::new("grantRole", "root:root", root_has_role));
+
+        for user_config in config.users {
+            let user = user_config.user;
+            //  root 用户已在上面处理
+            if user == root {
+                steps.push(AuthBootstrapStep::new("addUser", user, true));
+                continue;
+            }
+            let user_exist = exist_users.contains(&user);
+            let granted_roles = if user_exist {
+                Vec::from(self.client.user_get(&user).await?.roles())
+            } else {
+                self.user_add(user.clone(), user_config.password).await?;
+                vec![]
+            };
+            steps.push(AuthBootstrapStep::new("addUser", user.clone(), user_exist));
+
+            for role in user_config.roles {
+                let target = format!("{}:{}", user, role);
+                let granted = granted_roles.contains(&role);
+                if !granted {
+                    self.user_grant_role(user.clone(), role).await?;
+                }
+                steps.push(AuthBootstrapStep::new("grantRole", target, granted));
+            }
+        }
+
+        if config.enable_auth {
+            let enabled = self.auth_is_enabled(&root, &config.root_password).await?;
+            if !enabled {
+                self.auth_enable().await?;
+            }
+            steps.push(AuthBootstrapStep::new("enableAuth", "", enabled));
+        }
+
+        Ok(steps)
+    }
+
+    /// 使用指定用户申请token判断是否已开启权限验证，未开启时服务端返回 `authentication is not enabled`
+    async fn auth_is_enabled(&mut self, user: &str, password: &str) -> Result<bool, Error> {
+        let mut auth_client = self.client.get_inner().auth_client();
+        let result = wrapped_etcd_client::deadline(
+            self.client.timeout(),
+            auth_client.authenticate(String::from(user), String::from(password)),
+        )
+        .await;
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) if e.to_string().contains("authentication is not enabled") => Ok(false),
+            //  开启权限验证后密码错误等认证失败同样说明已开启
+            Err(Error::GRpcStatus(status)) if status.code() == tonic::Code::InvalidArgument => Ok(true),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 判断用户是否已直接授权root角色
+    async fn user_is_root_role_granted(&mut self, user: &String) -> Result<bool, Error> {
+        let response = self.client.user_get(user).await?;
+        Ok(response.roles().iter().any(|r| r == "root"))
+    }
+
+    /// 模拟用户对key或前缀是否有读写权限。传入权限定义时按定义应用后的状态计算，
+    /// 定义中未包含的用户和角色使用集群当前的配置
+    pub async fn auth_simulate(
+        &mut self,
+        user: &str,
+        key: &str,
+        prefix: bool,
+        definition: Option<&AuthDefinition>,
+    ) -> Result<AuthSimulation, Error> {
+        let defined_user = definition.and_then(|d| d.users.iter().find(|u| u.user == user));
+        let user_roles = match defined_user {
+            Some(u) => u.roles.clone(),
+            None if user == "root" => vec![String::from("root")],
+            None => Vec::from(self.client.user_get(&String::from(user)).await?.roles()),
+        };
+
+        let mut roles = Vec::with_capacity(user_roles.len());
+        for role in user_roles {
+            let defined_role = definition.and_then(|d| d.roles.iter().find(|r| r.role == role));
+            let permissions = match (definition, defined_role) {
+                (_, Some(r)) => r.permissions.clone(),
+                (_, None) if role == "root" => vec![],
+                //  开启 prune 时定义外的角色会被删除
+                (Some(d), None) if d.prune => vec![],
+                _ => self.role_get_permissions(role.clone()).await?,
+            };
+            roles.push((role, permissions));
+        }
+        Ok(AuthSimulation::resolve(user, key, prefix, roles))
+    }
+
+    /// 对比期望的权限定义与集群当前的权限状态，生成按顺序执行的变更计划：
+    /// 新增角色 -> 授权权限 -> 回收权限 -> 用户授权角色 -> 用户回收角色 -> 删除多余角色。
+    /// root 角色不会被修改，root 用户的 root 角色也不会被回收
+    pub async fn auth_plan(&mut self, definition: &AuthDefinition) -> Result<Vec<AuthPlanStep>, Error> {
+        let exist_roles = Vec::from(self.client.role_list().await?.roles());
+        let exist_users = self.user_list().await?;
+
+        let mut add_roles = Vec::new();
+        let mut grant_permissions = Vec::new();
+        let mut revoke_permissions = Vec::new();
+        //  root角色不比较权限，声明了该角色或有用户需要授予时才创建
+        let root_required = definition.roles.iter().any(|r| r.role == "root")
+            || definition.users.iter().any(|u| u.roles.iter().any(|r| r == "root"));
+        if root_required && !exist_roles.iter().any(|r| r == "root") {
+            add_roles.push(AuthPlanStep::role("addRole", "root"));
+        }
+        for role_config in &definition.roles {
+            let role = &role_config.role;
+            if role == "root" {
+                continue;
+            }
+            let exist_permissions = if exist_roles.contains(role) {
+                self.role_get_permissions(role.clone()).await?
+            } else {
+                add_roles.push(AuthPlanStep::role("addRole", role));
+                vec![]
+            };
+
+            for permission in &role_config.permissions {
+                if !exist_permissions.iter().any(|p| p.same_as(permission)) {
+                    grant_permissions.push(AuthPlanStep::permission("grantPermission", role, permission.clone()));
+                }
+            }
+            for permission in exist_permissions {
+                if !role_config.permissions.iter().any(|p| p.same_as(&permission)) {
+                    revoke_permissions.push(AuthPlanStep::permission("revokePermission", role, permission));
+                }
+            }
+        }
+
+        let mut grant_roles = Vec::new();
+        let mut revoke_roles = Vec::new();
+        for user_config in &definition.users {
+            let user = &user_config.user;
+            let exist_user = exist_users.iter().find(|u| &u.user == user).ok_or_else(|| {
+                Error::InvalidArgs(format!("User does not exist: {}", user))
+            })?;
+            for role in &user_config.roles {
+                if !exist_user.roles.contains(role) {
+                    grant_roles.push(AuthPlanStep::user("grantRole", user, role));
+                }
+            }
+            for role in &exist_user.roles {
+                if user == "root" && role == "root" {
+                    continue;
+                }
+                if !user_config.roles.contains(role) {
+                    revoke_roles.push(AuthPlanStep::user("revokeRole", user, role));
+                }
+            }
+        }
+
+        let mut delete_roles = Vec::new();
+        if definition.prune {
+            for role in &exist_roles {
+                if role == "root" || definition.roles.iter().any(|r| &r.role == role) {
+                    continue;
+                }
+                //  先解除用户授权并回收权限，以便失败时可以完整回滚
+                for user in &exist_users {
+                    let declared = definition.users.iter().any(|u| u.user == user.user);
+                    if !declared && user.roles.contains(role) {
+                        delete_roles.push(AuthPlanStep::user("revokeRole", &user.user, role));
+                    }
+                }
+                for permission in self.role_get_permissions(role.clone()).await? {
+                    delete_roles.push(AuthPlanStep::permission("revokePermission", role, permission));
+                }
+                delete_roles.push(AuthPlanStep::role("deleteRole", role));
+            }
+        }
+
+        let mut steps = add_roles;
+        steps.append(&mut grant_permissions);
+        steps.append(&mut revoke_permissions);
+        steps.append(&mut grant_roles);
+        steps.append(&mut revoke_roles);
+        steps.append(&mut delete_roles);
+        Ok(steps)
+    }
+
+    /// 按顺序执行权限变更计划，任一步骤失败时按相反顺序回滚已执行的步骤
+    pub async fn auth_apply(&mut self, steps: Vec<AuthPlanStep>) -> AuthApplyResult {
+        let mut applied = 0;
+        let mut error_msg = None;
+        for step in &steps {
+            if let Err(e) = self.auth_apply_step(step.clone()).await {
+                warn!("Failed to apply auth step {:?}: {e}", step);
+                error_msg = Some(e.to_string());
+                break;
+            }
+            applied += 1;
+        }
+
+        let mut rolled_back = false;
+        if error_msg.is_some() {
+            rolled_back = true;
+            for step in steps[..applied].iter().rev() {
+                if let Err(e) = self.auth_apply_step(step.inverse()).await {
+                    warn!("Failed to rollback auth step {:?}: {e}", step);
+                    rolled_back = false;
+                }
+            }
+        }
+
+        AuthApplyResult {
+            steps,
+            applied,
+            rolled_back,
+            error_msg,
+        }
+    }
+
+    async fn auth_apply_step(&mut self, step: AuthPlanStep) -> Result<(), Error> {
+        match (step.action.as_str(), step.user, step.permission) {
+            ("addRole", _, _) => self.role_add(step.role).await,
+            ("deleteRole", _, _) => self.role_delete(step.role).await,
+            ("grantPermission", _, Some(permission)) => {
+                self.role_grant_permission(step.role, permission).await
+            }
+            ("revokePermission", _, Some(permission)) => {
+                self.role_revoke_permission(step.role, permission).await
+            }
+            ("grantRole", Some(user), _) => self.user_grant_role(user, step.role).await,
+            ("revokeRole", Some(user), _) => self.user_revoke_role(user, step.role).await,
+            (action, _, _) => Err(Error::InvalidArgs(format!("Invalid auth step: {}", action))),
+        }
+    }
+
+    /// 导出集群的角色、权限及用户，用户密码使用占位符代替
+    pub async fn auth_export(&mut self) -> Result<AuthExport, Error> {
+        let mut roles = Vec::new();
+        for role in self.role_list().await? {
+            //  root角色拥有全部权限，只导出角色本身，导入时按需创建
+            let permissions = if role == "root" {
+                vec![]
+            } else {
+                self.role_get_permissions(role.clone()).await?
+            };
+            roles.push(AuthBootstrapRole { role, permissions });
+        }
+        let users = self
+            .user_list()
+            .await?
+            .into_iter()
+            .map(|u| AuthBootstrapUser {
+                user: u.user,
+                password: String::from(PASSWORD_PLACEHOLDER),
+                roles: u.roles,
+            })
+            .collect();
+        Ok(AuthExport { roles, users })
+    }
+
+    /// 导入权限配置：先创建不存在的用户，再按权限定义补齐角色、权限及用户授权，可重复执行。
+    ///
+    /// 任一新用户缺少密码时不做任何修改；权限变更失败并回滚后删除本次新建的用户
+    pub async fn auth_import(
+        &mut self,
+        export: &AuthExport,
+        passwords: &HashMap<String, String>,
+    ) -> Result<AuthImportResult, Error> {
+        let new_users = self.auth_import_new_users(export, passwords).await?;
+
+        let mut created_users = Vec::with_capacity(new_users.len());
+        for (user, password) in new_users {
+            self.user_add(user.clone(), password).await?;
+            created_users.push(user);
+        }
+
+        let steps = self.auth_plan(&export.to_definition()).await?;
+        let apply = self.auth_apply(steps).await;
+        if apply.rolled_back {
+            for user in &created_users {
+                if let Err(e) = self.user_delete(user.clone()).await {
+                    warn!("Failed to rollback created user {}: {e}", user);
+                }
+            }
+        }
+        Ok(AuthImportResult { created_users, apply })
+    }
 
-        let cluster_status = SerializableClusterStatus {
+    /// 导入时需要新建的用户及其密码，文件中为占位符的新用户必须在 `passwords` 中提供
+    async fn auth_import_new_users(
+        &mut self,
+        export: &AuthExport,
+        passwords: &HashMap<String, String>,
+    ) -> Result<Vec<(String, String)>, Error> {
+        let exist_users = Vec::from(self.client.user_list().await?.users());
+        let mut new_users = Vec::new();
+        let mut missing = Vec::new();
+        for user in export.users.iter().filter(|u| !exist_users.contains(&u.user)) {
+            match export.password_of(&user.user, passwords) {
+                Some(password) => new_users.push((user.user.clone(), password)),
+                None => missing.push(user.user.clone()),
+            }
+        }
+        if !missing.is_empty() {
+            return Err(Error::InvalidArgs(format!(
+                "Password is required for new users: {}",
+                missing.join(", ")
+            )));
+        }
+        Ok(new_users)
+    }
+
+    /// 导入权限配置的变更计划，不执行修改。返回需要新建的用户，以及新建后执行的权限变更步骤
+    pub async fn auth_import_plan(
+        &mut self,
+        export: &AuthExport,
+        passwords: &HashMap<String, String>,
+    ) -> Result<(Vec<String>, Vec<AuthPlanStep>), Error> {
+        let new_users: Vec<String> = self
+            .auth_import_new_users(export, passwords)
+            .await?
+            .into_iter()
+            .map(|(user, _)| user)
+            .collect();
+        //  新用户尚不存在，不参与对比，其角色全部需要授予
+        let mut definition = export.to_definition();
+        definition.users.retain(|u| !new_users.contains(&u.user));
+        let mut steps = self.auth_plan(&definition).await?;
+        for user in export.users.iter().filter(|u| new_users.contains(&u.user)) {
+            for role in &user.roles {
+                steps.push(AuthPlanStep::user("grantRole", &user.user, role));
+            }
+        }
+        Ok((new_users, steps))
+    }
+
+    /// 向当前连接的节点发送一个最小的串行化读请求，返回往返耗时
+    pub async fn ping(&mut self) -> Result<Duration, Error> {
+        let option = GetOptions::new()
+            .with_serializable()
+            .with_count_only()
+            .with_limit(1);
+        let start = std::time::Instant::now();
+        self.client.kv_get_request(vec![0], Some(option)).await?;
+        Ok(start.elapsed())
+    }
+
+    /// 获取当前连接节点的状态
+    pub async fn endpoint_status(&mut self) -> Result<SerializableClusterStatus, Error> {
+        let status = self.client.status().await?;
+        Ok(SerializableClusterStatus {
             version: String::from(status.version()),
             db_size_allocated: status.db_size(),
             db_size_used: status.raft_used_db_size(),
@@ -661,17 +2320,35 @@ impl EtcdConnector {
             raft_term: status.raft_term().to_string(),
             raft_applied_index: status.raft_applied_index().to_string(),
             errors: Vec::from(status.errors()),
+        })
+    }
+
+    /// 获取集群的详情信息，包含集群数据、成员、报警、状态等信息。
+    ///
+    /// 只有成员列表读取失败时返回错误，端点状态和报警读取失败时返回已读取的部分并附带错误信息
+    pub async fn cluster_get(&mut self) -> Result<SerializableCluster, Error> {
+        let mut response = self.client.member_list().await?;
+        let (cluster_status, status_error) = match self.endpoint_status().await {
+            Ok(status) => (Some(status), None),
+            Err(e) => {
+                warn!("Failed to get endpoint status: {}", e);
+                (None, Some(e.to_string()))
+            }
         };
-        let alarm_response = self
-            .client
-            .alarm(AlarmAction::Get, AlarmType::None, None)
-            .await?;
-        let alarms = alarm_response.alarms();
 
-        let mut alarms_map = HashMap::with_capacity(alarms.len());
-        for alarm in alarms {
-            alarms_map.insert(alarm.member_id(), alarm.alarm());
-        }
+        let mut alarms_map = HashMap::new();
+        let alarm_error = match self.client.alarm(AlarmAction::Get, AlarmType::None, None).await {
+            Ok(alarm_response) => {
+                for alarm in alarm_response.alarms() {
+                    alarms_map.insert(alarm.member_id(), alarm.alarm());
+                }
+                None
+            }
+            Err(e) => {
+                warn!("Failed to get alarms: {}", e);
+                Some(e.to_string())
+            }
+        };
 
         let pb_members = response.members();
         let mut members = Vec::with_capacity(pb_members.len());
@@ -687,6 +2364,8 @@ impl EtcdConnector {
                 alarm_type: *alarms_map
                     .get(&member.id())
                     .unwrap_or_else(|| &AlarmType::None) as i32,
+                status: None,
+                error_msg: None,
             })
         }
 
@@ -698,15 +2377,94 @@ impl EtcdConnector {
             revision: header.revision(),
             members,
             status: cluster_status,
+            status_error,
+            alarm_error,
         })
     }
 
+    /// 探测连接的端点是否为 gRPC proxy 并得出可用的功能。
+    ///
+    /// proxy 会将请求转发到任意一个后端成员，且不会附加自身的标识，这里根据响应中的成员信息推断：
+    /// 配置了 `--resolver-prefix` 的 proxy 返回的成员列表没有ID和集群ID；
+    /// 其余情况下 Status 请求的应答成员通常不是成员列表返回的成员，或多次请求由不同成员应答
+    pub async fn endpoint_capabilities(&mut self) -> Result<EndpointCapabilities, Error> {
+        let version = String::from(self.client.status().await?.version());
+        let members = self.client.member_list().await?;
+        let cluster_id = members.header().map(|h| h.cluster_id()).unwrap_or(0);
+        let member_ids: Vec<u64> = members.members().iter().map(|m| m.id()).collect();
+
+        let mut capabilities = EndpointCapabilities::default().with_server_version(&version);
+        if cluster_id == 0 || member_ids.iter().any(|id| *id == 0) {
+            capabilities.grpc_proxy = true;
+            capabilities.member_management = false;
+            capabilities.proxy_reason = Some(String::from("Member list is served by the proxy resolver"));
+        } else {
+            let mut responders = Vec::with_capacity(2);
+            for _ in 0..2 {
+                let status = self.client.status().await?;
+                if let Some(header) = status.header() {
+                    responders.push(header.member_id());
+                }
+            }
+            if responders.iter().any(|id| !member_ids.contains(id)) {
+                capabilities.grpc_proxy = true;
+                capabilities.proxy_reason = Some(String::from("Status is answered by an unknown member"));
+            } else if responders.windows(2).any(|w| w[0] != w[1]) {
+                capabilities.grpc_proxy = true;
+                capabilities.proxy_reason = Some(String::from("Status is answered by different members"));
+            }
+        }
+        if capabilities.grpc_proxy {
+            capabilities.maintenance = false;
+        }
+        Ok(capabilities)
+    }
+
+    /// 检查集群是否可用，返回当前的报警列表
+    pub async fn health_check(&mut self) -> Result<(Vec<SerializableAlarm>, i64), Error> {
+        let status = self.client.status().await?;
+        let revision = status.header().map(|h| h.revision()).unwrap_or(0);
+        let response = self
+            .client
+            .alarm(AlarmAction::Get, AlarmType::None, None)
+            .await?;
+        let alarms = response
+            .alarms()
+            .iter()
+            .map(|alarm| SerializableAlarm {
+                member_id: alarm.member_id().to_string(),
+                alarm_type: alarm.alarm() as i32,
+            })
+            .collect();
+        Ok((alarms, revision))
+    }
+
     /// 集群添加新成员节点
     pub async fn cluster_add_member(&mut self, urls: impl Into<Vec<String>>) -> Result<(), Error> {
         self.client.member_add(urls.into(), None).await?;
         Ok(())
     }
 
+    /// 集群添加 learner 成员节点，返回新成员的ID
+    pub async fn cluster_add_learner(&mut self, urls: impl Into<Vec<String>>) -> Result<String, Error> {
+        let response = self
+            .client
+            .member_add(urls.into(), Some(MemberAddOptions::new().with_is_learner()))
+            .await?;
+        Ok(response.member().map(|m| m.id().to_string()).unwrap_or_default())
+    }
+
+    /// 将 learner 成员提升为投票成员
+    pub async fn cluster_promote_member(&mut self, id: String) -> Result<(), Error> {
+        match id.parse::<u64>() {
+            Ok(id) => {
+                self.client.member_promote(id).await?;
+                Ok(())
+            }
+            Err(e) => Err(Error::InvalidArgs(e.to_string())),
+        }
+    }
+
     /// 集群移除成员节点
     pub async fn cluster_remove_member(&mut self, id: String) -> Result<(), Error> {
         match id.parse::<u64>() {
@@ -739,81 +2497,45 @@ impl EtcdConnector {
         Ok(())
     }
 
-    /// 保存数据快照
-    pub async fn maintenance_snapshot(
-        &mut self,
-        file_path: PathBuf,
-        watch_sender: mpsc::Sender<(u64, u64, Option<String>)>,
-        stop_receiver: oneshot::Receiver<()>,
-    ) -> Result<(), LogicError> {
-        let mut stream = self.client.snapshot().await?;
-        if let Some(parent) = file_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        if file_path.exists() {
-            fs::remove_file(&file_path)?;
-        }
+    /// 压缩 `revision` 之前的历史版本，`physical` 为true时等待压缩在后端完成后返回
+    pub async fn maintenance_compact(&mut self, revision: i64, physical: bool) -> Result<(), Error> {
+        let options = if physical {
+            Some(CompactionOptions::new().with_physical())
+        } else {
+            None
+        };
+        self.client.compact(revision, options).await?;
+        Ok(())
+    }
 
-        File::create(&file_path).await?;
-        let mut file = OpenOptions::new()
-            .write(true)
-            .append(true)
-            .open(file_path)
-            .await?;
+    /// 请求数据快照，返回快照数据流
+    pub async fn maintenance_snapshot_stream(&mut self) -> Result<SnapshotStreaming, LogicError> {
+        Ok(self.client.snapshot().await?)
+    }
+}
 
-        let watch_task = async move {
-            loop {
-                let slip_result = stream.message().await;
-                match slip_result {
-                    Ok(slip) => {
-                        if let Some(response) = slip {
-                            let blob = response.blob();
-                            let remain = response.remaining_bytes();
-
-                            let received = blob.len() as u64;
-
-                            file.write_all(blob).await;
-
-                            debug!("snapshot [remain] {}-{}", received, remain);
-                            watch_sender
-                                .send((received, remain, None))
-                                .await
-                                .unwrap_or_else(|e| error!("watch send error[remain]: {e}"));
-                            if remain == 0 {
-                                break;
-                            }
-                        } else {
-                            watch_sender
-                                .send((0, 0, None))
-                                .await
-                                .unwrap_or_else(|e| error!("watch send error[finish]: {e}"));
-                            break;
-                        }
-                    }
-                    Err(e) => {
-                        watch_sender
-                            .send((0, 0, Some(e.to_string())))
-                            .await
-                            .unwrap_or_else(|e| error!("watch send error[failed]: {e}"));
-                        debug!("snapshot [failed]: {e}");
-                        break;
-                    }
-                }
-            }
-        };
+/// 指定了超时时间的连接，离开作用域时恢复默认超时时间
+pub struct DeadlineScope<'a> {
+    connector: &'a mut EtcdConnector,
+}
 
-        let task = tokio::spawn(async {
-            select! {
-                _ = watch_task => {
-                    info!("Snapshot task finished")
-                },
-                _ = stop_receiver => {
-                    info!("Snapshot task stopped")
-                }
-            }
-        });
+impl Deref for DeadlineScope<'_> {
+    type Target = EtcdConnector;
 
-        Ok(())
+    fn deref(&self) -> &Self::Target {
+        self.connector
+    }
+}
+
+impl DerefMut for DeadlineScope<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.connector
+    }
+}
+
+impl Drop for DeadlineScope<'_> {
+    fn drop(&mut self) {
+        self.connector.client.set_call_deadline(None);
     }
 }
 
@@ -825,9 +2547,27 @@ fn key_next(key: &mut Vec<u8>) {
         key[len - 1] += 1
     }
 }
-pub struct SnapshotTask {
-    pub name: String,
-    pub folder: String,
-    pub state: SnapshotState,
-    pub stop_notifier: Option<oneshot::Sender<()>>,
+/// 判断是否为请求的版本已被压缩的错误
+fn is_compacted_error(e: &Error) -> bool {
+    if let Error::GRpcStatus(s) = e {
+        //  11: OutOfRange
+        s.code() as i32 == 11 && s.message().contains("compacted")
+    } else {
+        false
+    }
+}
+
+/// 解析认证token，JWT token由三段base64url编码组成，simple token格式为 `随机字符串.序号`
+pub fn parse_auth_token(token: &str) -> (AuthTokenType, Option<u64>) {
+    let parts: Vec<&str> = token.split('.').collect();
+    if parts.len() != 3 {
+        return (AuthTokenType::Simple, None);
+    }
+    let expires_at = BASE64_URL_SAFE_NO_PAD
+        .decode(parts[1].trim_end_matches('='))
+        .ok()
+        .and_then(|payload| serde_json::from_slice::<serde_json::Value>(&payload).ok())
+        .and_then(|claims| claims.get("exp").and_then(|exp| exp.as_u64()))
+        .map(|exp| exp * 1000);
+    (AuthTokenType::Jwt, expires_at)
 }
@@ -0,0 +1,90 @@
+use crate::transport::kv::{GarbageItem, GarbageKind, GarbageOptions, GarbageReport, RevisionTimeSample, SerializableKeyValue};
+
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+/// etcd并发库以十六进制的lease id作为锁和选举key的最后一段，较短的十六进制片段多为普通key
+const MIN_LEASE_HEX_LEN: usize = 12;
+const MAX_LEASE_HEX_LEN: usize = 16;
+
+/// 估算key至少已多久未修改（毫秒）。
+///
+/// 以修改版本之后第一次观察到的版本时间计算，版本时间记录只覆盖连接建立之后，
+/// 修改版本晚于所有记录时返回None
+pub fn min_unmodified_millis(mod_revision: i64, timeline: &[RevisionTimeSample], now: u64) -> Option<u64> {
+    let pos = timeline.partition_point(|s| s.revision < mod_revision);
+    timeline.get(pos).map(|s| now.saturating_sub(s.time))
+}
+
+/// 锁和选举常用的路径段，只在这些前缀下识别遗留的锁，避免以十六进制结尾的普通key被误判
+const LOCK_SEGMENTS: [&str; 7] = ["lock", "locks", "mutex", "mutexes", "election", "elections", "leader"];
+
+/// 判断是否为遗留的锁或选举key：位于锁或选举前缀下，形如 `/<prefix>/<lease-hex>`，
+/// 最后一段是十六进制的lease id，但key没有绑定该lease
+pub fn is_orphan_lock(key: &str, lease: i64) -> bool {
+    let Some((parent, last)) = key.rsplit_once('/') else {
+        return false;
+    };
+    if !parent.split('/').any(|segment| LOCK_SEGMENTS.contains(&segment.to_lowercase().as_str())) {
+        return false;
+    }
+    if last.len() < MIN_LEASE_HEX_LEN || last.len() > MAX_LEASE_HEX_LEN {
+        return false;
+    }
+    match u64::from_str_radix(last, 16) {
+        Ok(id) => lease == 0 || id as i64 != lease,
+        Err(_) => false,
+    }
+}
+
+/// 根据启发式规则找出可能已废弃的key：长时间未修改、值为空、遗留的锁或选举key
+pub fn analyze(
+    kvs: &[SerializableKeyValue],
+    revision: i64,
+    timeline: &[RevisionTimeSample],
+    now: u64,
+    options: &GarbageOptions,
+) -> GarbageReport {
+    let mut items = Vec::new();
+    for kv in kvs {
+        let lease = kv.lease.parse::<i64>().unwrap_or(0);
+        let unmodified_millis = min_unmodified_millis(kv.mod_revision, timeline, now);
+        let mut item = GarbageItem {
+            key: kv.key.clone(),
+            kind: GarbageKind::Stale,
+            mod_revision: kv.mod_revision,
+            lease,
+            unmodified_millis,
+        };
+
+        if is_orphan_lock(&kv.key, lease) {
+            item.kind = GarbageKind::OrphanLock;
+            items.push(item);
+            continue;
+        }
+        if kv.decrypt_error.is_none() && kv.value.is_empty() {
+            item.kind = GarbageKind::EmptyValue;
+            items.push(item);
+            continue;
+        }
+
+        let stale_by_time = match (options.stale_days, unmodified_millis) {
+            (Some(days), Some(millis)) => days > 0 && millis >= days * DAY_MILLIS,
+            _ => false,
+        };
+        let stale_by_revision = match options.stale_revisions {
+            Some(gap) => gap > 0 && revision - kv.mod_revision >= gap,
+            None => false,
+        };
+        //  有租约的key会自动过期，不视为长期未修改
+        if lease == 0 && (stale_by_time || stale_by_revision) {
+            items.push(item);
+        }
+    }
+
+    GarbageReport {
+        prefix: options.prefix.clone(),
+        revision,
+        scanned: kvs.len(),
+        timeline_start: timeline.first().map(|s| s.time),
+        items,
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use log::{debug, error, info};
+use tauri::Window;
+use tokio::select;
+use tokio::sync::oneshot;
+
+use crate::api::event_bus::{self, EventStream};
+use crate::api::settings::get_settings;
+use crate::api::windows;
+use crate::etcd::etcd_connector::EtcdConnector;
+use crate::transport::maintenance::{AlertType, HealthState};
+use crate::transport::settings::PollTask;
+use crate::utils::{cert_util, state_cache};
+
+use super::{alert_dispatcher, poll_scheduler, get_connection_config, get_connection_name, now_timestamp, record_revision_sample, CONNECTION_HEALTH_STATE};
+
+/// 连接健康检查任务，定时检查集群可用性、报警以及TLS证书有效期，
+/// 检查结果通过 `health_state` 事件推送给窗口，新出现的告警会推送到配置的webhook
+pub struct HealthMonitor {
+    session_id: i32,
+    stop_notifier: Option<oneshot::Sender<()>>,
+}
+
+impl HealthMonitor {
+    pub async fn start(session_id: i32, window: Window) -> Self {
+        let (stop_sender, mut stop_receiver) = oneshot::channel::<()>();
+
+        let warn_days = get_settings().await.unwrap_or_default().tls_cert_expire_warn_days;
+
+        tokio::spawn(async move {
+            let mut connector: Option<EtcdConnector> = None;
+            //  当前仍在持续的告警，同一告警只在首次出现时推送。
+            //  按类型、会话和告警对象去重，告警内容（如剩余天数、错误原因）变化不会重复推送
+            let mut firing: HashSet<(AlertType, i32, String)> = HashSet::new();
+            //  首次检查在连接建立后稍等片刻执行，之后的间隔由调度器按配置和窗口焦点计算
+            let mut delay = Duration::from_secs(3);
+            info!("Health monitor started: {}", session_id);
+
+            loop {
+                select! {
+                    _ = tokio::time::sleep(delay) => {},
+                    _ = &mut stop_receiver => {
+                        break;
+                    }
+                }
+
+                let state = Self::check(session_id, &mut connector, warn_days).await;
+                if !state.healthy {
+                    //  连接异常时丢弃旧连接，下次检查时重建
+                    connector = None;
+                }
+                let connection = get_connection_name(&session_id).unwrap_or_default();
+                let alerts = state.alerts(&connection);
+                let current: HashSet<(AlertType, i32, String)> = alerts
+                    .iter()
+                    .map(|a| (a.alert_type, a.session, a.subject.clone()))
+                    .collect();
+                let new_alerts = alerts
+                    .into_iter()
+                    .filter(|a| !firing.contains(&(a.alert_type, a.session, a.subject.clone())))
+                    .collect();
+                firing = current;
+                alert_dispatcher::dispatch(new_alerts).await;
+
+                event_bus::publish(&window, EventStream::Metrics, "health_state", &state);
+                state_cache::cache_health(session_id, &state).await;
+                let healthy = state.healthy;
+                let changed = CONNECTION_HEALTH_STATE
+                    .insert(session_id, state)
+                    .map(|s| s.healthy != healthy)
+                    .unwrap_or(true);
+                if changed {
+                    windows::refresh_tray().await;
+                }
+                delay = poll_scheduler::interval_of(session_id, PollTask::HealthCheck).await;
+            }
+            info!("Health monitor stopped: {}", session_id);
+        });
+
+        HealthMonitor {
+            session_id,
+            stop_notifier: Some(stop_sender),
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(sender) = self.stop_notifier.take() {
+            let _ = sender.send(());
+        }
+        debug!("Stop health monitor: {}", self.session_id);
+    }
+
+    async fn check(
+        session_id: i32,
+        connector: &mut Option<EtcdConnector>,
+        warn_days: i64,
+    ) -> HealthState {
+        let mut state = HealthState {
+            session: session_id,
+            healthy: false,
+            check_time: now_timestamp() as u64,
+            error_msg: None,
+            alarms: vec![],
+            cert_warnings: vec![],
+        };
+
+        let connection = match get_connection_config(&session_id) {
+            Some(config) => config.value().clone(),
+            None => {
+                state.error_msg = Some(String::from("connection lose"));
+                return state;
+            }
+        };
+
+        if let Some(tls) = &connection.tls {
+            state.cert_warnings = cert_util::find_expiring_certificates(tls, warn_days);
+        }
+
+        if connector.is_none() {
+            match EtcdConnector::new(connection).await {
+                Ok(c) => *connector = Some(c),
+                Err(e) => {
+                    error!("Health monitor failed to init connector: {:?}", e);
+                    state.error_msg = Some(String::from("Unable to connect to etcd server"));
+                    return state;
+                }
+            }
+        }
+
+        match connector.as_mut().unwrap().health_check().await {
+            Ok((alarms, revision)) => {
+                state.healthy = true;
+                state.alarms = alarms;
+                record_revision_sample(session_id, state.check_time, revision);
+            }
+            Err(e) => {
+                state.error_msg = Some(e.to_string());
+            }
+        }
+        state
+    }
+}
+
+impl Drop for HealthMonitor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
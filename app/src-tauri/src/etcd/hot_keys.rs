@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use etcd_client::{EventType, WatchStream, Watcher};
+use log::{debug, info, warn};
+use tauri::Window;
+use tokio::select;
+use tokio::sync::oneshot;
+use tokio::time::Instant;
+
+use crate::api::event_bus::{self, EventStream};
+use crate::error::LogicError;
+use crate::transport::kv::{HotKeyReport, HotKeyStat};
+use crate::transport::settings::PollTask;
+
+use super::{now_timestamp, poll_scheduler, subscribe_reconnected, wait_connector, wait_reconnected, CONNECTION_HOT_KEY_REPORTS};
+
+#[derive(Default, Clone, Copy)]
+struct Counter {
+    puts: u64,
+    deletes: u64,
+}
+
+/// 截取key的前 `depth` 级路径作为前缀，开头的分隔符不计入层级。
+/// 层级不足时返回 None
+pub fn key_prefix<'a>(key: &'a str, splitter: &str, depth: usize) -> Option<&'a str> {
+    if depth == 0 || splitter.is_empty() {
+        return None;
+    }
+    let start = if key.starts_with(splitter) { splitter.len() } else { 0 };
+    key[start..]
+        .match_indices(splitter)
+        .nth(depth - 1)
+        .map(|(i, _)| &key[..start + i + splitter.len()])
+}
+
+/// 会话重新连接后在新连接上重新监听整个键空间
+async fn rewatch(session_id: i32) -> Result<(Watcher, WatchStream), LogicError> {
+    let mut connector = wait_connector(&session_id).await?;
+    Ok(connector.kv_watch_prefix(vec![]).await?)
+}
+
+/// 热点key分析，在指定时长内监听整个键空间，统计每个key和前缀的修改频率。
+/// etcd 的监听不支持只返回key，收到事件后只保留key，不保存value
+pub struct HotKeySampler {
+    session_id: i32,
+    stop_notifier: Option<oneshot::Sender<()>>,
+}
+
+impl HotKeySampler {
+    pub fn start(
+        session_id: i32,
+        namespace: Option<String>,
+        duration: Duration,
+        splitter: String,
+        depth: usize,
+        top: usize,
+        mut watcher: Watcher,
+        mut stream: WatchStream,
+        window: Window,
+    ) -> Self {
+        let (stop_sender, mut stop_receiver) = oneshot::channel::<()>();
+        let since = now_timestamp() as u64;
+
+        tokio::spawn(async move {
+            info!("Hot key sampling started: {}", session_id);
+            let started = Instant::now();
+            let deadline = tokio::time::sleep(duration);
+            tokio::pin!(deadline);
+            //  分析过程中推送中间结果，间隔由调度器计算
+            let next_report = tokio::time::sleep(Duration::ZERO);
+            tokio::pin!(next_report);
+
+            let mut keys: HashMap<String, Counter> = HashMap::new();
+            let mut prefixes: HashMap<String, Counter> = HashMap::new();
+            let mut total = 0u64;
+            //  会话重新连接后旧连接上的监听不再可用，在新连接上重新监听并继续统计
+            let mut reconnected = subscribe_reconnected();
+            let mut broken = false;
+            let report = |keys: &HashMap<String, Counter>, prefixes: &HashMap<String, Counter>, total: u64, finished: bool| {
+                let elapsed = started.elapsed();
+                HotKeyReport {
+                    session: session_id,
+                    since,
+                    duration_seconds: duration.as_secs(),
+                    elapsed_millis: elapsed.as_millis() as u64,
+                    total_events: total,
+                    finished,
+                    keys: rank(keys, elapsed, top),
+                    prefixes: rank(prefixes, elapsed, top),
+                }
+            };
+
+            loop {
+                let message = select! {
+                    message = stream.message(), if !broken => message,
+                    _ = wait_reconnected(&mut reconnected, session_id) => {
+                        match rewatch(session_id).await {
+                            Ok((new_watcher, new_stream)) => {
+                                let _ = watcher.cancel().await;
+                                watcher = new_watcher;
+                                stream = new_stream;
+                                broken = false;
+                                debug!("Hot key sampling resumed after reconnect: {}", session_id);
+                            }
+                            //  等待下一次重新连接后再尝试
+                            Err(e) => {
+                                warn!("Failed to resume hot key sampling: {:?}", e);
+                                broken = true;
+                            }
+                        }
+                        continue;
+                    },
+                    _ = &mut next_report => {
+                        publish(&window, report(&keys, &prefixes, total, false));
+                        let delay = poll_scheduler::interval_of(session_id, PollTask::HotKeyReport).await;
+                        next_report.as_mut().reset(Instant::now() + delay);
+                        continue;
+                    },
+                    _ = &mut deadline => break,
+                    _ = &mut stop_receiver => break,
+                };
+                let response = match message {
+                    Ok(Some(response)) => response,
+                    Ok(None) => {
+                        broken = true;
+                        continue;
+                    }
+                    Err(e) => {
+                        warn!("Hot key sampling watch error: {e}");
+                        broken = true;
+                        continue;
+                    }
+                };
+                for event in response.events() {
+                    let Some(kv) = event.kv() else {
+                        continue;
+                    };
+                    let mut key = String::from_utf8_lossy(kv.key()).to_string();
+                    if let Some(namespace) = &namespace {
+                        if let Some(stripped) = key.strip_prefix(namespace.as_str()) {
+                            key = String::from(stripped);
+                        }
+                    }
+                    let is_put = event.event_type() == EventType::Put;
+                    if let Some(prefix) = key_prefix(&key, &splitter, depth) {
+                        increase(prefixes.entry(String::from(prefix)).or_default(), is_put);
+                    }
+                    increase(keys.entry(key).or_default(), is_put);
+                    total += 1;
+                }
+            }
+            let _ = watcher.cancel().await;
+            publish(&window, report(&keys, &prefixes, total, true));
+            debug!("Hot key sampling stopped: {}", session_id);
+        });
+
+        HotKeySampler {
+            session_id,
+            stop_notifier: Some(stop_sender),
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(sender) = self.stop_notifier.take() {
+            let _ = sender.send(());
+        }
+        debug!("Stop hot key sampling: {}", self.session_id);
+    }
+}
+
+impl Drop for HotKeySampler {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn increase(counter: &mut Counter, is_put: bool) {
+    if is_put {
+        counter.puts += 1;
+    } else {
+        counter.deletes += 1;
+    }
+}
+
+/// 按修改次数降序取前 `top` 项
+fn rank(counters: &HashMap<String, Counter>, elapsed: Duration, top: usize) -> Vec<HotKeyStat> {
+    let seconds = elapsed.as_secs_f64().max(1.0);
+    let mut stats: Vec<HotKeyStat> = counters
+        .iter()
+        .map(|(key, c)| HotKeyStat {
+            key: key.clone(),
+            puts: c.puts,
+            deletes: c.deletes,
+            rate: (c.puts + c.deletes) as f64 / seconds,
+        })
+        .collect();
+    stats.sort_by(|a, b| (b.puts + b.deletes).cmp(&(a.puts + a.deletes)).then_with(|| a.key.cmp(&b.key)));
+    stats.truncate(top);
+    stats
+}
+
+/// 保存最新的报告并通过 `hot_key_report` 事件推送
+fn publish(window: &Window, report: HotKeyReport) {
+    CONNECTION_HOT_KEY_REPORTS.insert(report.session, report.clone());
+    event_bus::publish(window, EventStream::Metrics, "hot_key_report", report);
+}
@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use log::{debug, info, warn};
+use tauri::{Invoke, Runtime, Window};
+use tokio::select;
+use tokio::sync::oneshot;
+use tokio::time::{interval, MissedTickBehavior};
+
+use crate::transport::connection::SessionIdleClosed;
+
+use super::{now_timestamp, remove_connector, touch_session, CONNECTION_LAST_ACTIVE};
+
+/// 空闲检查间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 包装命令处理函数，前端调用带有会话参数的命令时刷新该会话的最后活跃时间
+pub fn track_activity<R: Runtime>(
+    handler: impl Fn(Invoke<R>) + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) + Send + Sync + 'static {
+    move |invoke| {
+        if let Some(session) = invoke.message.payload().get("session").and_then(|v| v.as_i64()) {
+            touch_session(&(session as i32));
+        }
+        handler(invoke)
+    }
+}
+
+/// 连接空闲检查任务，超过空闲时间没有任何操作时自动断开连接并释放SSH隧道等资源，
+/// 断开后通过 `session_idle_closed` 事件通知窗口
+pub struct IdleMonitor {
+    session_id: i32,
+    stop_notifier: Option<oneshot::Sender<()>>,
+}
+
+impl IdleMonitor {
+    pub fn start(session_id: i32, idle_timeout_minutes: u64, window: Window) -> Self {
+        let (stop_sender, mut stop_receiver) = oneshot::channel::<()>();
+        let timeout = idle_timeout_minutes as u128 * 60 * 1000;
+
+        tokio::spawn(async move {
+            let mut timer = interval(CHECK_INTERVAL);
+            timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            info!("Idle monitor started: {}, timeout {} minutes", session_id, idle_timeout_minutes);
+
+            loop {
+                select! {
+                    _ = timer.tick() => {},
+                    _ = &mut stop_receiver => {
+                        debug!("Idle monitor stopped: {}", session_id);
+                        return;
+                    }
+                }
+
+                let last_active = match CONNECTION_LAST_ACTIVE.get(&session_id) {
+                    Some(t) => *t.value(),
+                    None => return,
+                };
+
+                if now_timestamp().saturating_sub(last_active) < timeout {
+                    continue;
+                }
+
+                info!("Connection {} is idle for {} minutes, disconnect it", session_id, idle_timeout_minutes);
+                remove_connector(&session_id).await;
+
+                let event = SessionIdleClosed {
+                    session: session_id,
+                    last_active_time: last_active as u64,
+                    idle_timeout_minutes,
+                };
+                if let Err(e) = window.emit("session_idle_closed", event) {
+                    warn!("Failed to emit idle closed event: {e}");
+                }
+                return;
+            }
+        });
+
+        IdleMonitor {
+            session_id,
+            stop_notifier: Some(stop_sender),
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(sender) = self.stop_notifier.take() {
+            let _ = sender.send(());
+        }
+        debug!("Stop idle monitor: {}", self.session_id);
+    }
+}
+
+impl Drop for IdleMonitor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,518 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use etcd_client::EventType;
+use log::{debug, info, warn};
+use tokio::select;
+use tokio::sync::oneshot;
+
+use crate::api::settings::get_settings;
+use crate::etcd::etcd_connector::EtcdConnector;
+use crate::transport::kv::SerializableKeyValue;
+
+use super::{get_connection_config, now_timestamp, record_revision_sample, CONNECTION_KEY_INDEX};
+
+/// 初始化扫描时每页读取的key数量
+const SCAN_PAGE_SIZE: i64 = 5000;
+/// 监听断开后重建索引的等待时间
+const REBUILD_DELAY: Duration = Duration::from_secs(10);
+
+/// key的元数据，不包含value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyMeta {
+    pub create_revision: i64,
+    pub mod_revision: i64,
+    pub version: i64,
+    pub lease: i64,
+}
+
+/// 基数树节点，子节点按路径首字节有序存放，单一子节点的路径会被压缩
+#[derive(Debug, Default)]
+struct Node {
+    path: Vec<u8>,
+    meta: Option<KeyMeta>,
+    children: Vec<Node>,
+    /// 子树中key的数量（包含自身）
+    count: usize,
+}
+
+impl Node {
+    fn leaf(path: &[u8], meta: KeyMeta) -> Self {
+        Node {
+            path: path.to_vec(),
+            meta: Some(meta),
+            children: vec![],
+            count: 1,
+        }
+    }
+
+    fn child_index(&self, first: u8) -> Result<usize, usize> {
+        self.children.binary_search_by(|c| c.path[0].cmp(&first))
+    }
+
+    /// 插入key，`key` 为去除本节点路径后的剩余部分，新增key时返回 true
+    fn insert(&mut self, key: &[u8], meta: KeyMeta) -> bool {
+        if key.is_empty() {
+            let added = self.meta.is_none();
+            self.meta = Some(meta);
+            if added {
+                self.count += 1;
+            }
+            return added;
+        }
+
+        let added = match self.child_index(key[0]) {
+            Ok(i) => {
+                let child = &mut self.children[i];
+                let common = common_prefix_len(&child.path, key);
+                if common < child.path.len() {
+                    child.split(common);
+                }
+                child.insert(&key[common..], meta)
+            }
+            Err(i) => {
+                self.children.insert(i, Node::leaf(key, meta));
+                true
+            }
+        };
+        if added {
+            self.count += 1;
+        }
+        added
+    }
+
+    /// 在 `at` 处拆分本节点路径，后半部分下沉为唯一子节点
+    fn split(&mut self, at: usize) {
+        let child = Node {
+            path: self.path.split_off(at),
+            meta: self.meta.take(),
+            children: std::mem::take(&mut self.children),
+            count: self.count,
+        };
+        self.children = vec![child];
+    }
+
+    /// 删除key，`key` 为去除本节点路径后的剩余部分，删除成功时返回 true
+    fn remove(&mut self, key: &[u8]) -> bool {
+        if key.is_empty() {
+            if self.meta.take().is_some() {
+                self.count -= 1;
+                return true;
+            }
+            return false;
+        }
+
+        let i = match self.child_index(key[0]) {
+            Ok(i) => i,
+            Err(_) => return false,
+        };
+        let child = &mut self.children[i];
+        if !key.starts_with(&child.path) {
+            return false;
+        }
+        let path_len = child.path.len();
+        if !child.remove(&key[path_len..]) {
+            return false;
+        }
+        if child.count == 0 {
+            self.children.remove(i);
+        } else {
+            child.merge();
+        }
+        self.count -= 1;
+        true
+    }
+
+    /// 没有自身key且只有一个子节点时，与子节点合并
+    fn merge(&mut self) {
+        if self.meta.is_none() && self.children.len() == 1 {
+            let child = self.children.pop().unwrap();
+            self.path.extend_from_slice(&child.path);
+            self.meta = child.meta;
+            self.children = child.children;
+        }
+    }
+
+    /// 按字典序收集子树中的key，`key` 为本节点的完整路径
+    fn collect(&self, key: &mut Vec<u8>, limit: usize, out: &mut Vec<(Vec<u8>, KeyMeta)>) {
+        if out.len() >= limit {
+            return;
+        }
+        if let Some(meta) = self.meta {
+            out.push((key.clone(), meta));
+        }
+        for child in &self.children {
+            if out.len() >= limit {
+                return;
+            }
+            key.extend_from_slice(&child.path);
+            child.collect(key, limit, out);
+            key.truncate(key.len() - child.path.len());
+        }
+    }
+
+    /// 按字典序收集子树中大于 `cursor` 的key
+    fn collect_after(
+        &self,
+        key: &mut Vec<u8>,
+        cursor: &[u8],
+        limit: usize,
+        out: &mut Vec<(Vec<u8>, KeyMeta)>,
+    ) {
+        if out.len() >= limit {
+            return;
+        }
+        if !cursor.starts_with(key) {
+            //  整个子树都大于或都小于游标
+            if key.as_slice() > cursor {
+                self.collect(key, limit, out);
+            }
+            return;
+        }
+        for child in &self.children {
+            if out.len() >= limit {
+                return;
+            }
+            key.extend_from_slice(&child.path);
+            child.collect_after(key, cursor, limit, out);
+            key.truncate(key.len() - child.path.len());
+        }
+    }
+
+    /// 统计子树中key在 `start` 之后的下一个路径片段，`key` 为本节点的完整路径
+    fn collect_segments(
+        &self,
+        key: &mut Vec<u8>,
+        start: usize,
+        delimiter: &[u8],
+        out: &mut HashMap<Vec<u8>, usize>,
+    ) {
+        if let Some(end) = segment_end(key, start, delimiter) {
+            //  子树中的key共享同一个片段
+            *out.entry(key[..end].to_vec()).or_insert(0) += self.count;
+            return;
+        }
+        if self.meta.is_some() && key.len() > start {
+            *out.entry(key.clone()).or_insert(0) += 1;
+        }
+        for child in &self.children {
+            key.extend_from_slice(&child.path);
+            child.collect_segments(key, start, delimiter, out);
+            key.truncate(key.len() - child.path.len());
+        }
+    }
+}
+
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count()
+}
+
+/// `key` 在 `start` 之后第一个分隔符的结束位置（包含分隔符）
+fn segment_end(key: &[u8], start: usize, delimiter: &[u8]) -> Option<usize> {
+    if delimiter.is_empty() || key.len() <= start {
+        return None;
+    }
+    key[start..]
+        .windows(delimiter.len())
+        .position(|w| w == delimiter)
+        .map(|pos| start + pos + delimiter.len())
+}
+
+/// 统计key列表在 `prefix` 之后的下一个路径片段，按key数量从多到少排序
+pub fn next_segments_of_keys(
+    keys: &[Vec<u8>],
+    prefix: &[u8],
+    delimiter: &[u8],
+    limit: usize,
+) -> Vec<(Vec<u8>, usize)> {
+    let mut segments = HashMap::new();
+    for key in keys {
+        if !key.starts_with(prefix) || key.len() == prefix.len() {
+            continue;
+        }
+        let end = segment_end(key, prefix.len(), delimiter).unwrap_or(key.len());
+        *segments.entry(key[..end].to_vec()).or_insert(0) += 1;
+    }
+    sort_segments(segments, limit)
+}
+
+fn sort_segments(segments: HashMap<Vec<u8>, usize>, limit: usize) -> Vec<(Vec<u8>, usize)> {
+    let mut list: Vec<(Vec<u8>, usize)> = segments.into_iter().collect();
+    list.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    list.truncate(limit);
+    list
+}
+
+/// 连接的内存key索引，只保存key及其元数据，通过初始扫描建立并由监听事件增量更新
+#[derive(Debug, Default)]
+pub struct KeyIndex {
+    root: Node,
+    /// 索引数据对应的版本
+    revision: i64,
+}
+
+impl KeyIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.root.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.count == 0
+    }
+
+    pub fn revision(&self) -> i64 {
+        self.revision
+    }
+
+    pub fn set_revision(&mut self, revision: i64) {
+        self.revision = revision;
+    }
+
+    pub fn insert(&mut self, key: &[u8], meta: KeyMeta) -> bool {
+        self.root.insert(key, meta)
+    }
+
+    pub fn remove(&mut self, key: &[u8]) -> bool {
+        self.root.remove(key)
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<KeyMeta> {
+        let mut node = &self.root;
+        let mut rest = key;
+        loop {
+            if rest.is_empty() {
+                return node.meta;
+            }
+            let i = node.child_index(rest[0]).ok()?;
+            node = &node.children[i];
+            if !rest.starts_with(&node.path) {
+                return None;
+            }
+            rest = &rest[node.path.len()..];
+        }
+    }
+
+    /// 找到包含所有以 `prefix` 开头的key的子树，返回该子树节点及其完整路径
+    fn find_prefix(&self, prefix: &[u8]) -> Option<(&Node, Vec<u8>)> {
+        let mut node = &self.root;
+        let mut key = Vec::with_capacity(prefix.len());
+        let mut rest = prefix;
+        loop {
+            if rest.is_empty() {
+                return Some((node, key));
+            }
+            let i = node.child_index(rest[0]).ok()?;
+            let child = &node.children[i];
+            let common = common_prefix_len(&child.path, rest);
+            key.extend_from_slice(&child.path);
+            if common == rest.len() {
+                return Some((child, key));
+            }
+            if common < child.path.len() {
+                return None;
+            }
+            rest = &rest[common..];
+            node = child;
+        }
+    }
+
+    /// 以 `prefix` 开头的key数量
+    pub fn count_prefix(&self, prefix: &[u8]) -> usize {
+        self.find_prefix(prefix).map(|(node, _)| node.count).unwrap_or(0)
+    }
+
+    /// 按字典序获取以 `prefix` 开头的key，最多 `limit` 个
+    pub fn keys_with_prefix(&self, prefix: &[u8], limit: usize) -> Vec<(Vec<u8>, KeyMeta)> {
+        let mut out = Vec::new();
+        if let Some((node, mut key)) = self.find_prefix(prefix) {
+            node.collect(&mut key, limit, &mut out);
+        }
+        out
+    }
+
+    /// 按字典序获取大于 `cursor` 的key，最多 `limit` 个，用于分页
+    pub fn keys_after(&self, cursor: &[u8], limit: usize) -> Vec<(Vec<u8>, KeyMeta)> {
+        let mut out = Vec::new();
+        self.root.collect_after(&mut Vec::new(), cursor, limit, &mut out);
+        out
+    }
+
+    /// 获取 `prefix` 之后的下一个路径片段及片段下的key数量，按key数量从多到少排序
+    pub fn next_segments(&self, prefix: &[u8], delimiter: &[u8], limit: usize) -> Vec<(Vec<u8>, usize)> {
+        let mut segments = HashMap::new();
+        if let Some((node, mut key)) = self.find_prefix(prefix) {
+            node.collect_segments(&mut key, prefix.len(), delimiter, &mut segments);
+        }
+        sort_segments(segments, limit)
+    }
+
+    /// 所有key
+    pub fn keys(&self) -> Vec<(Vec<u8>, KeyMeta)> {
+        self.keys_with_prefix(&[], usize::MAX)
+    }
+}
+
+/// 将索引中的key转换为不包含value的键值对
+pub fn to_serializable_kvs(keys: Vec<(Vec<u8>, KeyMeta)>) -> Vec<SerializableKeyValue> {
+    keys.into_iter()
+        .map(|(key, meta)| SerializableKeyValue {
+            key: String::from_utf8_lossy(&key).to_string(),
+            create_revision: meta.create_revision,
+            mod_revision: meta.mod_revision,
+            version: meta.version,
+            value: vec![],
+            lease: meta.lease.to_string(),
+            lease_info: None,
+            formatted_value: None,
+            encrypted: false,
+            decrypt_error: None,
+            value_handle: None,
+        })
+        .collect()
+}
+
+/// 索引维护任务，连接的key数量达到设置的阈值时建立索引，并通过监听保持索引为最新状态
+pub struct KeyIndexer {
+    session_id: i32,
+    stop_notifier: Option<oneshot::Sender<()>>,
+}
+
+impl KeyIndexer {
+    pub fn start(session_id: i32) -> Self {
+        let (stop_sender, mut stop_receiver) = oneshot::channel::<()>();
+
+        tokio::spawn(async move {
+            loop {
+                select! {
+                    result = Self::run(session_id) => {
+                        CONNECTION_KEY_INDEX.remove(&session_id);
+                        match result {
+                            Ok(false) => break,
+                            Ok(true) => info!("Key index watch closed, rebuild it later: {}", session_id),
+                            Err(e) => warn!("Key index error, rebuild it later: {}, {e}", session_id),
+                        }
+                    }
+                    _ = &mut stop_receiver => {
+                        CONNECTION_KEY_INDEX.remove(&session_id);
+                        break;
+                    }
+                }
+
+                select! {
+                    _ = tokio::time::sleep(REBUILD_DELAY) => {},
+                    _ = &mut stop_receiver => break,
+                }
+            }
+            debug!("Key indexer stopped: {}", session_id);
+        });
+
+        KeyIndexer {
+            session_id,
+            stop_notifier: Some(stop_sender),
+        }
+    }
+
+    /// 建立索引并持续监听更新，不需要建立索引时返回 false，监听中断需要重建时返回 true
+    async fn run(session_id: i32) -> Result<bool, String> {
+        let settings = get_settings().await.unwrap_or_default();
+        if !settings.kv_index_enabled {
+            return Ok(false);
+        }
+
+        let connection = match get_connection_config(&session_id) {
+            Some(config) => config.value().clone(),
+            None => return Ok(false),
+        };
+        let namespace = connection.namespace.clone().unwrap_or_default().into_bytes();
+
+        let mut connector = EtcdConnector::new(connection)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+        let (count, revision) = connector.kv_count_all().await.map_err(|e| e.to_string())?;
+        if count < settings.kv_index_min_keys {
+            debug!("Skip key index for {}, key count: {}", session_id, count);
+            return Ok(false);
+        }
+
+        //  所有分页固定在统计数量时的版本读取，再从下一个版本开始监听
+        let mut index = KeyIndex::new();
+        let mut cursor = String::new();
+        loop {
+            let page = connector
+                .kv_get_all_keys_paging_at(cursor.clone(), SCAN_PAGE_SIZE, Some(revision))
+                .await
+                .map_err(|e| e.to_string())?;
+            for kv in &page.kvs {
+                index.insert(kv.key.as_bytes(), KeyMeta {
+                    create_revision: kv.create_revision,
+                    mod_revision: kv.mod_revision,
+                    version: kv.version,
+                    lease: kv.lease.parse().unwrap_or(0),
+                });
+            }
+            match page.kvs.last() {
+                Some(last) if page.more => cursor = last.key.clone(),
+                _ => break,
+            }
+        }
+        index.set_revision(revision);
+        info!("Key index built for {}, {} keys at revision {}", session_id, index.len(), revision);
+
+        let index = Arc::new(RwLock::new(index));
+        CONNECTION_KEY_INDEX.insert(session_id, Arc::clone(&index));
+
+        let (_watcher, mut stream) = connector
+            .kv_watch_all(revision + 1)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        while let Some(response) = stream.message().await.map_err(|e| e.to_string())? {
+            if response.canceled() || response.compact_revision() > 0 {
+                return Ok(true);
+            }
+            let mut guard = index.write().unwrap();
+            for event in response.events() {
+                let Some(kv) = event.kv() else {
+                    continue;
+                };
+                let key = kv.key().strip_prefix(namespace.as_slice()).unwrap_or(kv.key());
+                match event.event_type() {
+                    EventType::Put => {
+                        guard.insert(key, KeyMeta {
+                            create_revision: kv.create_revision(),
+                            mod_revision: kv.mod_revision(),
+                            version: kv.version(),
+                            lease: kv.lease(),
+                        });
+                    }
+                    EventType::Delete => {
+                        guard.remove(key);
+                    }
+                }
+            }
+            if let Some(header) = response.header() {
+                guard.set_revision(header.revision());
+                record_revision_sample(session_id, now_timestamp() as u64, header.revision());
+            }
+        }
+        Ok(true)
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(sender) = self.stop_notifier.take() {
+            let _ = sender.send(());
+        }
+        debug!("Stop key indexer: {}", self.session_id);
+    }
+}
+
+impl Drop for KeyIndexer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -1,3 +1,4 @@
+use crate::api::event_bus::{self, EventStream};
 use crate::etcd::etcd_connector::EtcdConnector;
 use crate::transport::connection::KeyMonitorConfig;
 use crate::transport::kv::FormattedValue;
@@ -269,7 +270,7 @@ impl MonitorTask {
                 .body(event.key.clone())
                 .show();
         }
-        window.emit("key_monitor", event);
+        event_bus::publish(window, EventStream::Watch, "key_monitor", event);
     }
 }
 
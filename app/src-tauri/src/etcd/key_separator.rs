@@ -0,0 +1,61 @@
+use std::collections::HashSet;
+
+use crate::transport::connection::SeparatorScore;
+
+/// `Mixed` 模式未配置候选分隔符时使用的默认候选
+pub const DEFAULT_CANDIDATES: [&'static str; 5] = ["/", ".", ":", "::", "_"];
+/// 计算层数时超过此值不再加分，避免过细的分隔符得分过高
+const MAX_SCORED_DEPTH: f64 = 5.0;
+
+/// 评估分隔符对key列表产生的层级结构。
+///
+/// 包含分隔符的key越多、层数越多得分越高；目录数量相对key数量越多（如按uuid中的 `-` 分隔，每个key各成一个目录）得分越低
+pub fn score(keys: &[String], separator: &str) -> SeparatorScore {
+    let mut result = SeparatorScore {
+        separator: String::from(separator),
+        coverage: 0.0,
+        avg_depth: 0.0,
+        max_depth: 0,
+        dirs: 0,
+        score: 0.0,
+    };
+    if keys.is_empty() || separator.is_empty() {
+        return result;
+    }
+
+    let mut dirs = HashSet::new();
+    let mut covered = 0usize;
+    let mut depth_sum = 0usize;
+    for key in keys {
+        let depth = key.split(separator).filter(|s| !s.is_empty()).count().max(1);
+        if depth > 1 {
+            covered += 1;
+        }
+        depth_sum += depth;
+        result.max_depth = result.max_depth.max(depth);
+        for (pos, _) in key.match_indices(separator) {
+            if pos > 0 {
+                dirs.insert(&key[..pos]);
+            }
+        }
+    }
+
+    let total = keys.len() as f64;
+    result.coverage = covered as f64 / total;
+    result.avg_depth = depth_sum as f64 / total;
+    result.dirs = dirs.len();
+    let dir_ratio = result.dirs as f64 / total;
+    result.score = result.coverage * (result.avg_depth - 1.0).min(MAX_SCORED_DEPTH) / (1.0 + dir_ratio);
+    result
+}
+
+/// 评估所有候选分隔符，按得分从高到低排序
+pub fn detect(keys: &[String], candidates: &[String]) -> Vec<SeparatorScore> {
+    let mut scores: Vec<SeparatorScore> = if candidates.is_empty() {
+        DEFAULT_CANDIDATES.iter().map(|c| score(keys, c)).collect()
+    } else {
+        candidates.iter().map(|c| score(keys, c)).collect()
+    };
+    scores.sort_by(|a, b| b.score.total_cmp(&a.score));
+    scores
+}
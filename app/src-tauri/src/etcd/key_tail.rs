@@ -0,0 +1,271 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use etcd_client::{EventType, KeyValue};
+use log::{debug, info, warn};
+use serde::Serialize;
+use tokio::select;
+
+use crate::api::task_center::{self, TaskHandle, TaskKind};
+use crate::error::LogicError;
+use crate::transport::kv::{KeyTailConfig, KeyTailFormat};
+
+use super::{get_connection_config, now_timestamp, subscribe_reconnected, wait_connector, wait_reconnected};
+
+const DEFAULT_MAX_FILE_SIZE_MB: u64 = 10;
+const DEFAULT_MAX_FILES: usize = 5;
+/// 重新建立监听的最短等待时间，连续失败时翻倍
+const MIN_RETRY_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+/// JSON格式下的一行记录
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TailRecord {
+    pub time: u64,
+    pub revision: i64,
+    /// put 或 delete
+    pub op: &'static str,
+    pub key: String,
+    pub version: i64,
+    pub lease: i64,
+    /// UTF-8的值，删除时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// 值不是UTF-8时的原始内容（base64）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw_value: Option<String>,
+}
+
+impl TailRecord {
+    fn new(key: &str, kv: &KeyValue, deleted: bool) -> Self {
+        let (value, raw_value) = if deleted {
+            (None, None)
+        } else {
+            match std::str::from_utf8(kv.value()) {
+                Ok(value) => (Some(value.to_string()), None),
+                Err(_) => (None, Some(BASE64_STANDARD.encode(kv.value()))),
+            }
+        };
+        TailRecord {
+            time: now_timestamp() as u64,
+            revision: kv.mod_revision(),
+            op: if deleted { "delete" } else { "put" },
+            key: key.to_string(),
+            version: kv.version(),
+            lease: kv.lease(),
+            value,
+            raw_value,
+        }
+    }
+}
+
+/// 追加写入文件，超过大小限制时轮转为 `file.1`、`file.2` ...，最旧的文件被删除
+pub struct TailWriter {
+    path: PathBuf,
+    format: KeyTailFormat,
+    max_size: u64,
+    max_files: usize,
+}
+
+impl TailWriter {
+    pub fn new(path: &Path, format: KeyTailFormat, max_size: u64, max_files: usize) -> Self {
+        TailWriter {
+            path: path.to_path_buf(),
+            format,
+            max_size,
+            max_files,
+        }
+    }
+
+    pub fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&self) -> Result<(), LogicError> {
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+            return Ok(());
+        }
+        let oldest = self.rotated_path(self.max_files);
+        if oldest.exists() {
+            fs::remove_file(oldest)?;
+        }
+        for i in (1..self.max_files).rev() {
+            let from = self.rotated_path(i);
+            if from.exists() {
+                fs::rename(from, self.rotated_path(i + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+        Ok(())
+    }
+
+    /// 按格式写入一条记录，值格式下删除事件不写入
+    pub fn write(&self, record: &TailRecord) -> Result<(), LogicError> {
+        let mut line = match self.format {
+            KeyTailFormat::Value => match (&record.value, &record.raw_value) {
+                (Some(value), _) => value.clone().into_bytes(),
+                (None, Some(raw)) => raw.clone().into_bytes(),
+                (None, None) => return Ok(()),
+            },
+            KeyTailFormat::Json => serde_json::to_vec(record)?,
+        };
+        line.push(b'\n');
+
+        if self.max_size > 0 {
+            let size = fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+            if size > 0 && size + line.len() as u64 > self.max_size {
+                self.rotate()?;
+            }
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        file.write_all(&line)?;
+        Ok(())
+    }
+}
+
+/// 启动追加任务，先写入key的当前值，之后每次变更都追加到文件，返回任务ID，通过任务中心取消
+pub fn start(config: KeyTailConfig) -> Result<i32, LogicError> {
+    get_connection_config(&config.session).ok_or(LogicError::ConnectionLose)?;
+    if config.key.is_empty() {
+        return Err(LogicError::IllegalArgument(String::from("Key is required")));
+    }
+    let path = PathBuf::from(&config.path);
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let writer = TailWriter::new(
+        &path,
+        config.format,
+        config.max_file_size_mb.unwrap_or(DEFAULT_MAX_FILE_SIZE_MB) * 1024 * 1024,
+        config.max_files.unwrap_or(DEFAULT_MAX_FILES),
+    );
+
+    let mut handle = task_center::register(
+        TaskKind::Tail,
+        format!("Tail {}", config.key),
+        Some(config.session),
+        Some(config.path.clone()),
+    );
+    let id = handle.id();
+    tokio::spawn(async move {
+        info!("Key tail started: {}, {}", id, config.key);
+        let result = run(&config, &writer, &mut handle).await;
+        if let Err(e) = &result {
+            warn!("Key tail {} failed: {:?}", id, e);
+        }
+        handle.complete(&result);
+        debug!("Key tail stopped: {}", id);
+    });
+    Ok(id)
+}
+
+/// 读取key的当前值，版本不早于 `min_revision` 时写入，返回读取时的集群版本
+async fn write_current(
+    session: i32,
+    key: &str,
+    min_revision: i64,
+    writer: &TailWriter,
+) -> Result<(i64, bool), LogicError> {
+    let response = {
+        let mut connector = wait_connector(&session).await?;
+        connector.kv_get_request(key, None).await?
+    };
+    let revision = response.header().map(|h| h.revision()).unwrap_or(0);
+    let mut written = false;
+    if let Some(kv) = response.kvs().first() {
+        if kv.mod_revision() >= min_revision {
+            writer.write(&TailRecord::new(key, kv, false))?;
+            written = true;
+        }
+    }
+    Ok((revision, written))
+}
+
+async fn run(config: &KeyTailConfig, writer: &TailWriter, handle: &mut TaskHandle) -> Result<(), LogicError> {
+    let session = config.session;
+    let key = config.key.as_str();
+    let (revision, written) = write_current(session, key, 0, writer).await?;
+    let mut next_revision = revision + 1;
+    let mut count = written as u64;
+    handle.progress(count, 0);
+
+    let mut reconnected = subscribe_reconnected();
+    let mut backoff = MIN_RETRY_BACKOFF;
+    loop {
+        let watch = {
+            let mut connector = wait_connector(&session).await?;
+            connector.kv_watch_key_from(key, next_revision).await
+        };
+        let reason = match watch {
+            Ok((mut watcher, mut stream)) => {
+                let reason = loop {
+                    let message = select! {
+                        message = stream.message() => message,
+                        _ = wait_reconnected(&mut reconnected, session) => {
+                            backoff = Duration::ZERO;
+                            break String::from("Session reconnected");
+                        }
+                        _ = handle.cancelled() => {
+                            let _ = watcher.cancel().await;
+                            return Ok(());
+                        }
+                    };
+                    let response = match message {
+                        Ok(Some(response)) => response,
+                        Ok(None) => break String::from("Watch stream closed"),
+                        Err(e) => break e.to_string(),
+                    };
+                    backoff = MIN_RETRY_BACKOFF;
+                    if response.compact_revision() > 0 {
+                        //  中间的修改已无法读取，只补写当前值
+                        //  写入失败时保持原版本，重新监听后再次补写
+                        match write_current(session, key, next_revision, writer).await {
+                            Ok((revision, written)) => {
+                                count += written as u64;
+                                next_revision = revision + 1;
+                            }
+                            Err(LogicError::ConnectionLose) => return Err(LogicError::ConnectionLose),
+                            Err(e) => warn!("Key tail of {} failed to write current value: {:?}", key, e),
+                        }
+                        break format!("Revision has been compacted to {}", response.compact_revision());
+                    }
+                    if response.canceled() {
+                        break format!("Watch canceled: {}", response.cancel_reason());
+                    }
+
+                    for event in response.events() {
+                        let Some(kv) = event.kv() else {
+                            continue;
+                        };
+                        let deleted = event.event_type() == EventType::Delete;
+                        match writer.write(&TailRecord::new(key, kv, deleted)) {
+                            Ok(()) => count += 1,
+                            Err(e) => warn!("Key tail of {} skipped revision {}: {:?}", key, kv.mod_revision(), e),
+                        }
+                        next_revision = next_revision.max(kv.mod_revision() + 1);
+                    }
+                    handle.progress(count, 0);
+                    handle.message(format!("Revision {}", next_revision - 1));
+                };
+                let _ = watcher.cancel().await;
+                reason
+            }
+            Err(e) => e.to_string(),
+        };
+
+        debug!("Key tail of {} interrupted: {}", key, reason);
+        select! {
+            _ = tokio::time::sleep(backoff) => {},
+            _ = handle.cancelled() => return Ok(()),
+        }
+        backoff = (backoff * 2).clamp(MIN_RETRY_BACKOFF, MAX_RETRY_BACKOFF);
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::{info, warn};
+
+use crate::api::settings::get_settings;
+use crate::api::task_center;
+use crate::error::LogicError;
+use crate::transport::dry_run::MutationPlan;
+use crate::transport::kv::{KvMacro, MacroOp, MacroRunResult};
+use crate::utils::{file_util, template, trash};
+
+use super::{cluster_scope, dry_run, get_connection_config, get_connection_info_optional, get_connection_name, get_connector, now_timestamp};
+
+lazy_static! {
+    /// 正在录制的连接及已录制的操作
+    static ref RECORDINGS: DashMap<i32, Vec<MacroOp>> = DashMap::new();
+}
+
+pub fn remove_session(session: i32) {
+    RECORDINGS.remove(&session);
+}
+
+/// 开始录制，已在录制时清空之前录制的操作
+pub fn start_recording(session: i32) -> Result<(), LogicError> {
+    get_connection_config(&session).ok_or(LogicError::ConnectionLose)?;
+    RECORDINGS.insert(session, Vec::new());
+    Ok(())
+}
+
+pub fn is_recording(session: i32) -> bool {
+    RECORDINGS.contains_key(&session)
+}
+
+/// 停止录制并返回录制的操作
+pub fn stop_recording(session: i32) -> Vec<MacroOp> {
+    RECORDINGS.remove(&session).map(|(_, ops)| ops).unwrap_or_default()
+}
+
+/// 连接正在录制时追加一个已成功执行的操作
+pub fn record(session: i32, op: MacroOp) {
+    if let Some(mut ops) = RECORDINGS.get_mut(&session) {
+        ops.push(op);
+    }
+}
+
+pub fn put_op(key: String, value: Vec<u8>, ttl: Option<i64>, render_variables: bool) -> MacroOp {
+    let (value, raw_value) = match String::from_utf8(value) {
+        Ok(value) => (Some(value), None),
+        Err(e) => (None, Some(BASE64_STANDARD.encode(e.into_bytes()))),
+    };
+    MacroOp::Put {
+        key,
+        value,
+        raw_value,
+        ttl,
+        render_variables,
+    }
+}
+
+/// 读取put操作写入的值，需要时使用连接变量渲染
+pub fn op_value(
+    value: &Option<String>,
+    raw_value: &Option<String>,
+    render_variables: bool,
+    variables: &BTreeMap<String, String>,
+) -> Result<Vec<u8>, String> {
+    match (value, raw_value) {
+        (Some(value), _) if render_variables => template::render(value, variables)
+            .map(String::into_bytes)
+            .map_err(|missing| format!("Undefined variables: {}", missing.join(", "))),
+        (Some(value), _) => Ok(value.clone().into_bytes()),
+        (None, Some(raw)) => BASE64_STANDARD.decode(raw).map_err(|e| format!("Invalid raw value: {e}")),
+        (None, None) => Ok(vec![]),
+    }
+}
+
+fn load() -> BTreeMap<String, KvMacro> {
+    let Ok(content) = fs::read_to_string(file_util::get_macros_file_path()) else {
+        return BTreeMap::new();
+    };
+    serde_json::from_str(&content).unwrap_or_else(|e| {
+        warn!("Failed to read macros: {e}");
+        BTreeMap::new()
+    })
+}
+
+fn store(macros: &BTreeMap<String, KvMacro>) -> Result<(), LogicError> {
+    fs::write(file_util::get_macros_file_path(), serde_json::to_vec_pretty(macros)?)?;
+    Ok(())
+}
+
+pub fn list() -> Vec<KvMacro> {
+    load().into_values().collect()
+}
+
+pub fn get(name: &str) -> Result<KvMacro, LogicError> {
+    load().remove(name).ok_or(LogicError::ResourceNotExist("Macro does not exist"))
+}
+
+/// 保存宏，同名的宏会被覆盖
+pub fn save(mut kv_macro: KvMacro) -> Result<(), LogicError> {
+    kv_macro.name = kv_macro.name.trim().to_string();
+    if kv_macro.name.is_empty() {
+        return Err(LogicError::IllegalArgument(String::from("Macro name is required")));
+    }
+    if kv_macro.ops.is_empty() {
+        return Err(LogicError::IllegalArgument(String::from("Macro has no operations")));
+    }
+    for (i, op) in kv_macro.ops.iter().enumerate() {
+        if let MacroOp::Put { raw_value: Some(raw), .. } = op {
+            BASE64_STANDARD
+                .decode(raw)
+                .map_err(|e| LogicError::IllegalArgument(format!("Invalid raw value of operation {}: {e}", i)))?;
+        }
+    }
+    if kv_macro.created_at == 0 {
+        kv_macro.created_at = now_timestamp() as u64;
+    }
+    let mut macros = load();
+    macros.insert(kv_macro.name.clone(), kv_macro);
+    store(&macros)
+}
+
+pub fn delete(name: &str) -> Result<bool, LogicError> {
+    let mut macros = load();
+    let removed = macros.remove(name).is_some();
+    if removed {
+        store(&macros)?;
+    }
+    Ok(removed)
+}
+
+fn variables(session: i32) -> BTreeMap<String, String> {
+    get_connection_info_optional(&session)
+        .map(|info| info.variables.clone())
+        .unwrap_or_default()
+}
+
+/// 生成宏在目标连接上执行的变更计划，各操作依次执行，不在同一个事务中
+pub async fn plan(session: i32, kv_macro: &KvMacro) -> Result<MutationPlan, LogicError> {
+    let variables = variables(session);
+    let mut result = dry_run::plan("run_macro", vec![]);
+    for op in &kv_macro.ops {
+        let plan = match op {
+            MacroOp::Put {
+                key,
+                value,
+                raw_value,
+                ttl,
+                render_variables,
+            } => {
+                let value = op_value(value, raw_value, *render_variables, &variables).map_err(LogicError::IllegalArgument)?;
+                let lease = ttl.filter(|ttl| *ttl > 0).map(|ttl| format!("<new lease, ttl={}>", ttl));
+                dry_run::plan_put(session, "kv_put", key.clone(), value.len(), lease).await?
+            }
+            MacroOp::Delete { keys } => dry_run::plan_delete(session, "kv_delete", keys).await?,
+            MacroOp::Rename { from, to } => dry_run::plan_rename(session, "kv_rename", from.clone(), to.clone()).await?,
+        };
+        result.revision = result.revision.max(plan.revision);
+        result.compares.extend(plan.compares);
+        result.ops.extend(plan.ops);
+    }
+    Ok(result)
+}
+
+async fn run_op(session: i32, op: &MacroOp, variables: &BTreeMap<String, String>) -> Result<(), LogicError> {
+    match op {
+        MacroOp::Put {
+            key,
+            value,
+            raw_value,
+            ttl,
+            render_variables,
+        } => {
+            let value = op_value(value, raw_value, *render_variables, variables).map_err(LogicError::IllegalArgument)?;
+            let mut connector = get_connector(&session)?;
+            connector.kv_put(key.clone(), value, *ttl).await?;
+        }
+        MacroOp::Delete { keys } => {
+            let settings = get_settings().await?;
+            let deleted = {
+                let mut connector = get_connector(&session)?;
+                if settings.trash_retention_days == 0 {
+                    connector.kv_delete(keys.clone()).await?;
+                    return Ok(());
+                }
+                connector.kv_delete_with_prev(keys.clone()).await?.1
+            };
+            let scope = cluster_scope(&session)?;
+            trash::add(&settings, &scope, get_connection_name(&session), deleted);
+        }
+        MacroOp::Rename { from, to } => {
+            let mut connector = get_connector(&session)?;
+            if !connector.kv_rename(from.clone(), to.clone()).await? {
+                return Err(LogicError::IllegalArgument(format!(
+                    "Cannot rename {} to {}: the source does not exist or the target already exists",
+                    from, to
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 在目标连接上依次执行宏中的操作，遇到失败时停止，已执行的操作不会回滚
+pub async fn run(session: i32, kv_macro: &KvMacro) -> MacroRunResult {
+    let variables = variables(session);
+    let mut result = MacroRunResult::default();
+    for (i, op) in kv_macro.ops.iter().enumerate() {
+        if let Err(e) = run_op(session, op, &variables).await {
+            warn!("Macro {} failed at operation {} on {}: {:?}", kv_macro.name, i, session, e);
+            result.failed_index = Some(i);
+            result.error = Some(task_center::error_message(&e));
+            return result;
+        }
+        result.executed += 1;
+    }
+    info!("Macro {} executed {} operation(s) on {}", kv_macro.name, result.executed, session);
+    result
+}
@@ -0,0 +1,168 @@
+use std::time::Duration;
+
+use log::{debug, info, warn};
+use tokio::select;
+use tokio::sync::oneshot;
+
+use crate::etcd::etcd_connector::EtcdConnector;
+use crate::transport::connection::Connection;
+use crate::transport::maintenance::{EndpointLatency, LatencySample};
+use crate::transport::settings::PollTask;
+
+use super::{get_connection_config, get_member_connection, now_timestamp, poll_scheduler, CONNECTION_LATENCY_SAMPLES};
+
+/// 每个节点最多保留的采样数
+const MAX_SAMPLES: usize = 120;
+
+struct SampleTarget {
+    latency: EndpointLatency,
+    connection: Option<Connection>,
+    connector: Option<EtcdConnector>,
+}
+
+impl SampleTarget {
+    async fn sample(&mut self) {
+        if self.connector.is_none() {
+            if let Some(connection) = self.connection.clone() {
+                match EtcdConnector::new(connection).await {
+                    Ok(c) => self.connector = Some(c),
+                    Err(e) => {
+                        self.push(None, Some(format!("{:?}", e)));
+                        return;
+                    }
+                }
+            }
+        }
+
+        let result = match self.connector.as_mut() {
+            Some(connector) => connector.ping().await,
+            None => return,
+        };
+        match result {
+            Ok(rtt) => self.push(Some(rtt.as_secs_f64() * 1000.0), None),
+            Err(e) => {
+                //  请求失败时重建连接
+                self.connector = None;
+                self.push(None, Some(e.to_string()));
+            }
+        }
+    }
+
+    fn push(&mut self, rtt_ms: Option<f64>, error_msg: Option<String>) {
+        let samples = &mut self.latency.samples;
+        samples.push(LatencySample {
+            time: now_timestamp() as u64,
+            rtt_ms,
+            error_msg,
+        });
+        if samples.len() > MAX_SAMPLES {
+            samples.remove(0);
+        }
+    }
+}
+
+/// 延迟采样任务，定时向会话连接及每个成员节点发送最小的串行化读请求并记录往返耗时，
+/// 用于判断哪个成员或隧道较慢
+pub struct LatencySampler {
+    session_id: i32,
+    stop_notifier: Option<oneshot::Sender<()>>,
+}
+
+impl LatencySampler {
+    pub fn start(session_id: i32) -> Self {
+        let (stop_sender, mut stop_receiver) = oneshot::channel::<()>();
+
+        tokio::spawn(async move {
+            let mut targets = match Self::init_targets(session_id).await {
+                Ok(targets) => targets,
+                Err(e) => {
+                    warn!("Failed to init latency sampler: {}, {}", session_id, e);
+                    return;
+                }
+            };
+            info!("Latency sampler started: {}, {} endpoints", session_id, targets.len());
+
+            let mut delay = Duration::ZERO;
+            loop {
+                select! {
+                    _ = tokio::time::sleep(delay) => {},
+                    _ = &mut stop_receiver => break,
+                }
+
+                for target in targets.iter_mut() {
+                    target.sample().await;
+                }
+                let latency = targets.iter().map(|t| t.latency.clone()).collect();
+                CONNECTION_LATENCY_SAMPLES.insert(session_id, latency);
+                delay = poll_scheduler::interval_of(session_id, PollTask::LatencySample).await;
+            }
+            CONNECTION_LATENCY_SAMPLES.remove(&session_id);
+            debug!("Latency sampler stopped: {}", session_id);
+        });
+
+        LatencySampler {
+            session_id,
+            stop_notifier: Some(stop_sender),
+        }
+    }
+
+    async fn init_targets(session_id: i32) -> Result<Vec<SampleTarget>, String> {
+        let connection = get_connection_config(&session_id)
+            .map(|c| c.value().clone())
+            .ok_or_else(|| String::from("Connection lose"))?;
+        let endpoint = connection.endpoint().map_err(|e| format!("{:?}", e))?.to_string();
+
+        let mut connector = EtcdConnector::new(connection.clone())
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+        let cluster = connector.cluster_get().await.map_err(|e| e.to_string())?;
+
+        let mut targets = vec![SampleTarget {
+            latency: EndpointLatency {
+                member_id: None,
+                name: String::from("Session"),
+                endpoint,
+                samples: vec![],
+            },
+            connection: Some(connection),
+            connector: Some(connector),
+        }];
+
+        for member in cluster.members {
+            let Some(uri) = member.client_uri.first() else {
+                continue;
+            };
+            let connection = match get_member_connection(&session_id, uri) {
+                Ok(c) => Some(c),
+                Err(e) => {
+                    warn!("Skip latency sampling for member {}: {}", member.name, e);
+                    continue;
+                }
+            };
+            targets.push(SampleTarget {
+                latency: EndpointLatency {
+                    member_id: Some(member.id),
+                    name: member.name,
+                    endpoint: uri.clone(),
+                    samples: vec![],
+                },
+                connection,
+                connector: None,
+            });
+        }
+        Ok(targets)
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(sender) = self.stop_notifier.take() {
+            let _ = sender.send(());
+        }
+        debug!("Stop latency sampler: {}", self.session_id);
+    }
+}
+
+impl Drop for LatencySampler {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
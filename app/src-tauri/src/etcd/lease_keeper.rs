@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use etcd_client::{LeaseKeepAliveStream, LeaseKeeper};
+use log::{debug, info, warn};
+use tauri::Window;
+use tokio::select;
+use tokio::sync::oneshot;
+
+use crate::api::event_bus::{self, EventStream};
+use crate::transport::kv::LeaseKeepAliveState;
+
+use super::{now_timestamp, wait_connector, CONNECTION_LEASE_KEEP_ALIVE_STATE};
+
+/// 续约间隔的下限
+const MIN_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(1);
+/// 续约失败后重试的最短等待时间，连续失败时翻倍，不会超过lease剩余时间
+const MIN_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(10);
+
+/// 由 workbench 代为续约lease的后台任务，用于调试已退出的服务。
+/// 每次续约后通过 `lease_keep_alive` 事件推送剩余时间，用于显示倒计时
+pub struct LeaseKeepAliveTask {
+    session_id: i32,
+    lease: i64,
+    stop_notifier: Option<oneshot::Sender<()>>,
+}
+
+impl LeaseKeepAliveTask {
+    pub fn start(
+        session_id: i32,
+        lease: i64,
+        mut keeper: LeaseKeeper,
+        mut stream: LeaseKeepAliveStream,
+        window: Window,
+    ) -> Self {
+        let (stop_sender, mut stop_receiver) = oneshot::channel::<()>();
+
+        tokio::spawn(async move {
+            let key = (session_id, lease);
+            let mut state = LeaseKeepAliveState {
+                session: session_id,
+                lease: lease.to_string(),
+                ttl: 0,
+                keep_alive_time: 0,
+                next_keep_alive_time: 0,
+                stopped: false,
+                error_msg: None,
+            };
+            info!("Lease keep alive started: {}, {}", session_id, lease);
+
+            let mut backoff = MIN_RETRY_BACKOFF;
+            'keep_alive: loop {
+                let result = async {
+                    keeper.keep_alive().await?;
+                    stream.message().await
+                };
+                let response = select! {
+                    r = result => r,
+                    _ = &mut stop_receiver => break,
+                };
+
+                let now = now_timestamp() as u64;
+                let mut error = match response {
+                    Ok(Some(response)) if response.ttl() > 0 => {
+                        //  在剩余时间的三分之一处续约
+                        let interval = Duration::from_secs(response.ttl() as u64 / 3)
+                            .max(MIN_KEEP_ALIVE_INTERVAL);
+                        state.ttl = response.ttl();
+                        state.keep_alive_time = now;
+                        state.next_keep_alive_time = now + interval.as_millis() as u64;
+                        state.error_msg = None;
+                        backoff = MIN_RETRY_BACKOFF;
+                        CONNECTION_LEASE_KEEP_ALIVE_STATE.insert(key, state.clone());
+                        Self::emit(&window, &state);
+
+                        select! {
+                            _ = tokio::time::sleep(interval) => {},
+                            _ = &mut stop_receiver => break,
+                        }
+                        continue;
+                    }
+                    Ok(Some(_)) => {
+                        state.error_msg = Some(String::from("The lease has expired"));
+                        break;
+                    }
+                    Ok(None) => String::from("Keep alive stream closed"),
+                    Err(e) => e.to_string(),
+                };
+
+                //  续约失败时在lease到期前不断重试，网络短暂中断不会导致lease过期
+                loop {
+                    let now = now_timestamp() as u64;
+                    let expire_time = state.keep_alive_time + state.ttl as u64 * 1000;
+                    if now >= expire_time {
+                        state.error_msg = Some(error);
+                        break 'keep_alive;
+                    }
+                    warn!("Lease keep alive error, retry in {:?}: {}, {}", backoff, lease, error);
+                    state.error_msg = Some(error.clone());
+                    CONNECTION_LEASE_KEEP_ALIVE_STATE.insert(key, state.clone());
+                    Self::emit(&window, &state);
+
+                    let delay = backoff.min(Duration::from_millis(expire_time - now));
+                    select! {
+                        _ = tokio::time::sleep(delay) => {},
+                        _ = &mut stop_receiver => break 'keep_alive,
+                    }
+                    backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+
+                    let reopened = match wait_connector(&session_id).await {
+                        Ok(mut connector) => connector.lease_keep_alive(lease).await,
+                        Err(_) => {
+                            state.error_msg = Some(String::from("Connection closed"));
+                            break 'keep_alive;
+                        }
+                    };
+                    match reopened {
+                        Ok((new_keeper, new_stream)) => {
+                            keeper = new_keeper;
+                            stream = new_stream;
+                            continue 'keep_alive;
+                        }
+                        Err(e) => error = e.to_string(),
+                    }
+                }
+            }
+
+            state.stopped = true;
+            state.ttl = 0;
+            Self::emit(&window, &state);
+            //  未被手动移除时保留最终状态，便于界面展示停止原因
+            CONNECTION_LEASE_KEEP_ALIVE_STATE.alter(&key, |_, _| state);
+            debug!("Lease keep alive stopped: {}, {}", session_id, lease);
+        });
+
+        LeaseKeepAliveTask {
+            session_id,
+            lease,
+            stop_notifier: Some(stop_sender),
+        }
+    }
+
+    fn emit(window: &Window, state: &LeaseKeepAliveState) {
+        event_bus::publish(window, EventStream::Progress, "lease_keep_alive", state);
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(sender) = self.stop_notifier.take() {
+            let _ = sender.send(());
+        }
+        debug!("Stop lease keep alive: {}, {}", self.session_id, self.lease);
+    }
+}
+
+impl Drop for LeaseKeepAliveTask {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
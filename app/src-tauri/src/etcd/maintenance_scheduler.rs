@@ -0,0 +1,232 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use chrono::Local;
+use dashmap::{DashMap, DashSet};
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use tokio::time::{interval, MissedTickBehavior};
+
+use crate::error::LogicError;
+use crate::transport::maintenance::{
+    Alert, AlertType, MaintenanceAction, MaintenanceOperation, MaintenanceRun, MaintenanceSchedule,
+};
+use crate::utils::cron::CronSchedule;
+
+use super::etcd_connector::{EtcdConnector, MAX_CALL_DEADLINE_SECONDS};
+use super::{
+    alert_dispatcher, check_writable, get_connection_info_optional, get_connection_name, get_connector,
+    get_member_connection, list_revisions_near_time, list_session_windows, now_timestamp, operation_queue, session_lock,
+};
+
+/// 检查间隔，小于一分钟以免错过执行时间，同一分钟内只执行一次
+const CHECK_INTERVAL: Duration = Duration::from_secs(20);
+/// 每个连接保留的执行记录数
+const MAX_RUN_HISTORY: usize = 50;
+
+lazy_static! {
+    static ref RUN_HISTORY: DashMap<i32, VecDeque<MaintenanceRun>> = DashMap::new();
+    /// 各计划最后一次触发的分钟数，避免同一分钟内重复执行
+    static ref LAST_FIRED: DashMap<(i32, String), i64> = DashMap::new();
+    static ref RUNNING: DashSet<(i32, String)> = DashSet::new();
+}
+
+/// 定时检查所有已打开连接的运维计划，到达执行时间时在后台执行
+pub fn start() {
+    tokio::spawn(async move {
+        let mut timer = interval(CHECK_INTERVAL);
+        timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            timer.tick().await;
+            let now = Local::now();
+            let minute = now.timestamp() / 60;
+            for (session, _) in list_session_windows() {
+                let schedules = get_connection_info_optional(&session)
+                    .map(|info| info.maintenance_schedules.clone())
+                    .unwrap_or_default();
+                for schedule in schedules.into_iter().filter(|s| s.enabled) {
+                    let Ok(cron) = CronSchedule::parse(&schedule.cron) else {
+                        continue;
+                    };
+                    if !cron.matches(&now) {
+                        continue;
+                    }
+                    let key = (session, schedule.id.clone());
+                    if LAST_FIRED.insert(key, minute) == Some(minute) {
+                        continue;
+                    }
+                    tokio::spawn(async move {
+                        //  锁定期间无法使用连接，解锁后再执行
+                        session_lock::wait_unlocked().await;
+                        if let Err(e) = run_schedule(session, schedule).await {
+                            debug!("Maintenance schedule of {} skipped: {:?}", session, e);
+                        }
+                    });
+                }
+            }
+        }
+    });
+}
+
+/// 执行运维计划并记录结果，失败时发送告警。只读连接或同一计划正在执行时返回错误
+pub async fn run_schedule(session: i32, schedule: MaintenanceSchedule) -> Result<MaintenanceRun, LogicError> {
+    check_writable(&session)?;
+    let key = (session, schedule.id.clone());
+    if !RUNNING.insert(key.clone()) {
+        return Err(LogicError::IllegalArgument(format!("Schedule {} is still running", schedule.name)));
+    }
+    let start_time = now_timestamp() as u64;
+    info!("Maintenance schedule {} of {} started", schedule.name, session);
+    let operation = match &schedule.action {
+        MaintenanceAction::Compact { .. } => MaintenanceOperation::Compact,
+        MaintenanceAction::Defragment { .. } => MaintenanceOperation::Defragment,
+    };
+    let result = match operation_queue::acquire(session, operation, |_| {}).await {
+        Ok(_permit) => match &schedule.action {
+            MaintenanceAction::Compact {
+                retain_revisions,
+                retain_hours,
+            } => compact(session, *retain_revisions, *retain_hours).await,
+            MaintenanceAction::Defragment { include_leader } => defragment(session, *include_leader).await,
+        },
+        Err(e) => Err(e),
+    };
+    RUNNING.remove(&key);
+
+    let run = MaintenanceRun {
+        schedule_id: schedule.id.clone(),
+        name: schedule.name.clone(),
+        start_time,
+        end_time: now_timestamp() as u64,
+        success: result.is_ok(),
+        message: match &result {
+            Ok(message) => message.clone(),
+            Err(e) => error_message(e),
+        },
+    };
+    if !run.success {
+        warn!("Maintenance schedule {} of {} failed: {}", schedule.name, session, run.message);
+        alert_dispatcher::dispatch(vec![Alert {
+            alert_type: AlertType::MaintenanceFailed,
+            session,
+            connection: get_connection_name(&session).unwrap_or_default(),
+            subject: schedule.id.clone(),
+            message: format!("Maintenance '{}' failed: {}", schedule.name, run.message),
+            time: run.end_time,
+        }])
+        .await;
+    }
+
+    let mut history = RUN_HISTORY.entry(session).or_default();
+    history.push_back(run.clone());
+    while history.len() > MAX_RUN_HISTORY {
+        history.pop_front();
+    }
+    Ok(run)
+}
+
+fn error_message(e: &LogicError) -> String {
+    match e {
+        LogicError::MsgError(msg) | LogicError::IllegalArgument(msg) => msg.clone(),
+        LogicError::EtcdClientError(e) => e.to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// 最近的执行记录，新的在前
+pub fn list_runs(session: i32) -> Vec<MaintenanceRun> {
+    RUN_HISTORY
+        .get(&session)
+        .map(|history| history.iter().rev().cloned().collect())
+        .unwrap_or_default()
+}
+
+pub fn remove_session(session: i32) {
+    RUN_HISTORY.remove(&session);
+    LAST_FIRED.retain(|(s, _), _| *s != session);
+}
+
+/// 计算压缩版本，保留时长依赖连接建立后记录的版本时间。
+/// 记录不足时无法确定该时间点的版本，返回 `Err` 说明跳过的原因
+fn compact_revision(
+    session: i32,
+    revision: i64,
+    retain_revisions: Option<i64>,
+    retain_hours: Option<u64>,
+) -> Result<i64, String> {
+    let mut targets = Vec::new();
+    if let Some(n) = retain_revisions {
+        targets.push(revision - n.max(1));
+    }
+    if let Some(hours) = retain_hours {
+        let time = (now_timestamp() as u64).saturating_sub(hours * 3600 * 1000);
+        let sample = list_revisions_near_time(session, time, 1)
+            .into_iter()
+            .filter(|s| s.time <= time)
+            .last()
+            .ok_or_else(|| {
+                format!(
+                    "Skipped: no revision was observed {} hours ago in this session, keep the connection open longer",
+                    hours
+                )
+            })?;
+        targets.push(sample.revision);
+    }
+    targets
+        .into_iter()
+        .min()
+        .ok_or_else(|| String::from("Skipped: retain revisions or hours is required"))
+}
+
+async fn compact(session: i32, retain_revisions: Option<i64>, retain_hours: Option<u64>) -> Result<String, LogicError> {
+    let mut connector = get_connector(&session)?;
+    let bounds = connector.get_keyspace_bounds().await?;
+    //  无法确定压缩版本时跳过本次执行，不视为失败
+    let target = match compact_revision(session, bounds.revision, retain_revisions, retain_hours) {
+        Ok(target) => target,
+        Err(reason) => return Ok(reason),
+    };
+    if target <= bounds.compact_revision {
+        return Ok(format!("Already compacted to revision {}", bounds.compact_revision));
+    }
+    connector
+        .with_deadline(Some(Duration::from_secs(MAX_CALL_DEADLINE_SECONDS)))
+        .maintenance_compact(target, false)
+        .await?;
+    Ok(format!("Compacted to revision {}", target))
+}
+
+/// 依次整理各成员，任一成员失败时停止，避免多个成员同时不可用
+async fn defragment(session: i32, include_leader: bool) -> Result<String, LogicError> {
+    let cluster = {
+        let mut connector = get_connector(&session)?;
+        connector.cluster_get().await?
+    };
+    let leader = cluster
+        .status
+        .as_ref()
+        .map(|s| s.leader.clone())
+        .ok_or_else(|| LogicError::MsgError(String::from("Unable to determine the leader")))?;
+
+    let mut members: Vec<_> = cluster.members.iter().filter(|m| m.id != leader).collect();
+    if include_leader {
+        members.extend(cluster.members.iter().filter(|m| m.id == leader));
+    }
+    let mut done = Vec::new();
+    for member in members {
+        let uri = member
+            .client_uri
+            .first()
+            .ok_or_else(|| LogicError::MsgError(format!("Member {} has no client url", member.name)))?;
+        let connection = get_member_connection(&session, uri).map_err(LogicError::MsgError)?;
+        let mut connector = EtcdConnector::new(connection).await?;
+        connector
+            .with_deadline(Some(Duration::from_secs(MAX_CALL_DEADLINE_SECONDS)))
+            .maintenance_defragment()
+            .await
+            .map_err(|e| LogicError::MsgError(format!("Defragment {} failed after {:?}: {}", member.name, done, e)))?;
+        debug!("Defragmented member {} of {}", member.name, session);
+        done.push(member.name.clone());
+    }
+    Ok(format!("Defragmented {}", done.join(", ")))
+}
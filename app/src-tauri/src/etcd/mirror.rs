@@ -0,0 +1,387 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+use dashmap::DashMap;
+use etcd_client::{EventType, KeyValue};
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use tauri::Window;
+use tokio::select;
+use tokio::sync::oneshot;
+
+use crate::api::event_bus::{self, EventStream};
+use crate::error::LogicError;
+use crate::transport::kv::{MirrorConfig, MirrorConflictPolicy, MirrorState, MirrorStatus};
+
+use super::etcd_connector::EtcdConnector;
+use super::{check_writable, content_hint, get_connection_config, now_timestamp, wait_connector};
+
+static MIRROR_ID_COUNTER: AtomicI32 = AtomicI32::new(1);
+
+lazy_static! {
+    static ref MIRROR_TASKS: DashMap<i32, MirrorTask> = DashMap::new();
+    static ref MIRROR_STATUS: DashMap<i32, MirrorStatus> = DashMap::new();
+    /// `DestinationWins` 策略下镜像写入目标key后的修改版本，目标key的版本与记录不一致时说明已在目标连接被修改
+    static ref SYNCED_REVISIONS: DashMap<i32, HashMap<Vec<u8>, i64>> = DashMap::new();
+}
+
+/// 单向镜像任务，类似 `etcdctl make-mirror`：首先全量复制源前缀下的数据，
+/// 之后从全量读取的版本开始监听源前缀，将变更近实时地应用到目标连接。
+/// 值按原始内容复制，不经过客户端加密和解密
+struct MirrorTask {
+    id: i32,
+    stop_notifier: Option<oneshot::Sender<()>>,
+}
+
+impl MirrorTask {
+    /// `start_revision` 为空时先进行全量同步
+    fn start(id: i32, config: MirrorConfig, start_revision: Option<i64>, window: Window) -> Self {
+        let (stop_sender, stop_receiver) = oneshot::channel::<()>();
+
+        tokio::spawn(async move {
+            info!("Mirror started: {}", id);
+            if let Err(e) = Self::run(id, &config, start_revision, stop_receiver, &window).await {
+                warn!("Mirror {} failed: {:?}", id, e);
+                let msg = match e {
+                    LogicError::EtcdClientError(e) => e.to_string(),
+                    LogicError::MsgError(msg) | LogicError::IllegalArgument(msg) => msg,
+                    other => format!("{:?}", other),
+                };
+                update_status(id, &window, |status| {
+                    status.state = MirrorState::Error;
+                    status.error_msg = Some(msg);
+                });
+            }
+            debug!("Mirror stopped: {}", id);
+        });
+
+        MirrorTask {
+            id,
+            stop_notifier: Some(stop_sender),
+        }
+    }
+
+    async fn run(
+        id: i32,
+        config: &MirrorConfig,
+        start_revision: Option<i64>,
+        mut stop_receiver: oneshot::Receiver<()>,
+        window: &Window,
+    ) -> Result<(), LogicError> {
+        let start_revision = match start_revision {
+            Some(revision) => revision,
+            None => Self::initial_sync(id, config, window).await? + 1,
+        };
+
+        let (mut watcher, mut stream) = {
+            let mut source = wait_connector(&config.source_session).await?;
+            source.kv_watch_prefix_from(config.prefix.clone(), start_revision).await?
+        };
+        update_status(id, window, |status| status.state = MirrorState::Running);
+
+        let result = loop {
+            let message = select! {
+                message = stream.message() => message,
+                _ = &mut stop_receiver => break Ok(()),
+            };
+            let response = match message {
+                Ok(Some(response)) => response,
+                Ok(None) => break Err(LogicError::MsgError(String::from("Source watch stream closed"))),
+                Err(e) => break Err(e.into()),
+            };
+            if response.compact_revision() > 0 {
+                break Err(LogicError::MsgError(format!(
+                    "Source revision has been compacted to {}, please restart the mirror",
+                    response.compact_revision()
+                )));
+            }
+
+            let received = now_timestamp();
+            let source_revision = response.header().map(|h| h.revision()).unwrap_or(0);
+            let hints = content_hint::config_of(config.source_session);
+            let events: Vec<(Vec<u8>, EventType, &KeyValue)> = {
+                let source = wait_connector(&config.source_session).await?;
+                response
+                    .events()
+                    .iter()
+                    .filter_map(|event| {
+                        let kv = event.kv()?;
+                        let key = source.strip_namespace(kv.key().to_vec());
+                        //  内容类型提示只属于源连接，不复制到目标
+                        if hints.as_ref().is_some_and(|hints| content_hint::is_hint_key_raw(hints, &key)) {
+                            return None;
+                        }
+                        Some((map_key(config, &key), event.event_type(), kv))
+                    })
+                    .collect()
+            };
+            let mut applied = 0;
+            let mut applied_revision = None;
+            //  每批事件获取一次目标连接，应用完成后释放，不在等待下一批事件时占用
+            {
+                let mut target = wait_connector(&config.target_session).await?;
+                for (target_key, event_type, kv) in events {
+                    match (event_type, &config.conflict_policy) {
+                        (EventType::Put, MirrorConflictPolicy::Overwrite) => {
+                            target.kv_put_raw(target_key, kv.value().to_vec(), false).await?;
+                        }
+                        (EventType::Delete, MirrorConflictPolicy::Overwrite) => {
+                            target.kv_delete(vec![target_key]).await?;
+                        }
+                        (EventType::Put, MirrorConflictPolicy::DestinationWins) => {
+                            put_unless_changed(id, &mut target, target_key, kv.value().to_vec()).await?;
+                        }
+                        (EventType::Delete, MirrorConflictPolicy::DestinationWins) => {
+                            let synced = take_synced_revision(id, &target_key);
+                            if let Some(revision) = synced {
+                                if !target.kv_delete_raw_if_unchanged(target_key, revision).await? {
+                                    debug!("Mirror {} skipped a key changed in the target", id);
+                                }
+                            }
+                        }
+                    }
+                    applied += 1;
+                    applied_revision = Some(kv.mod_revision());
+                }
+            }
+
+            let apply_millis = (now_timestamp() - received) as u64;
+            update_status(id, window, |status| {
+                status.applied_events += applied;
+                status.source_revision = status.source_revision.max(source_revision);
+                if let Some(revision) = applied_revision {
+                    status.applied_revision = revision;
+                }
+                status.lag_revisions = (status.source_revision - status.applied_revision).max(0);
+                status.apply_millis = apply_millis;
+            });
+        };
+        let _ = watcher.cancel().await;
+        result
+    }
+
+    /// 全量复制源前缀下的数据，返回读取时的版本
+    async fn initial_sync(id: i32, config: &MirrorConfig, window: &Window) -> Result<i64, LogicError> {
+        let (revision, mut kvs) = {
+            let mut source = wait_connector(&config.source_session).await?;
+            source.kv_range_raw(config.prefix.clone()).await?
+        };
+        content_hint::hide_hint_keys(config.source_session, &mut kvs, |(key, _)| key.as_slice());
+        let kvs: Vec<(Vec<u8>, Vec<u8>)> = kvs
+            .into_iter()
+            .map(|(key, value)| (map_key(config, &key), value))
+            .collect();
+        let total = kvs.len();
+
+        let mut target = wait_connector(&config.target_session).await?;
+        match config.conflict_policy {
+            MirrorConflictPolicy::Overwrite => {
+                let result = target.kv_put_raw_batch(kvs, None).await?;
+                if let Some(e) = result.error_msg {
+                    return Err(LogicError::MsgError(e));
+                }
+            }
+            MirrorConflictPolicy::DestinationWins => {
+                for (key, value) in kvs {
+                    put_unless_changed(id, &mut target, key, value).await?;
+                }
+            }
+        }
+        drop(target);
+
+        update_status(id, window, |status| {
+            status.synced_keys = total;
+            status.source_revision = revision;
+            status.applied_revision = revision;
+            status.lag_revisions = 0;
+        });
+        Ok(revision)
+    }
+
+    fn stop(&mut self) {
+        if let Some(sender) = self.stop_notifier.take() {
+            let _ = sender.send(());
+        }
+        debug!("Stop mirror: {}", self.id);
+    }
+}
+
+impl Drop for MirrorTask {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// 目标key不存在或自上次镜像写入后未被修改时写入，否则以目标连接为准跳过该key
+async fn put_unless_changed(
+    id: i32,
+    target: &mut EtcdConnector,
+    key: Vec<u8>,
+    value: Vec<u8>,
+) -> Result<(), LogicError> {
+    let expected = SYNCED_REVISIONS
+        .get(&id)
+        .and_then(|synced| synced.get(&key).copied())
+        .unwrap_or(0);
+    match target.kv_put_raw_if_unchanged(key.clone(), value, expected).await? {
+        Some(revision) => {
+            SYNCED_REVISIONS.entry(id).or_default().insert(key, revision);
+        }
+        None => {
+            take_synced_revision(id, &key);
+            debug!("Mirror {} skipped a key changed in the target", id);
+        }
+    }
+    Ok(())
+}
+
+fn take_synced_revision(id: i32, key: &[u8]) -> Option<i64> {
+    SYNCED_REVISIONS.get_mut(&id).and_then(|mut synced| synced.remove(key))
+}
+
+/// 将源key中的前缀替换为目标前缀
+fn map_key(config: &MirrorConfig, key: &[u8]) -> Vec<u8> {
+    let Some(target_prefix) = &config.target_prefix else {
+        return key.to_vec();
+    };
+    let suffix = key.strip_prefix(config.prefix.as_bytes()).unwrap_or(key);
+    let mut target = target_prefix.clone().into_bytes();
+    target.extend_from_slice(suffix);
+    target
+}
+
+/// 更新状态并通过 `mirror_state` 事件推送
+fn update_status(id: i32, window: &Window, f: impl FnOnce(&mut MirrorStatus)) {
+    let status = match MIRROR_STATUS.get_mut(&id) {
+        Some(mut status) => {
+            f(&mut status);
+            status.clone()
+        }
+        None => return,
+    };
+    event_bus::publish(window, EventStream::Progress, "mirror_state", status);
+}
+
+fn check_config(config: &MirrorConfig) -> Result<(), LogicError> {
+    get_connection_config(&config.source_session).ok_or(LogicError::ConnectionLose)?;
+    check_writable(&config.target_session)?;
+    if config.source_session == config.target_session {
+        let target = config.target_prefix.as_deref().unwrap_or(&config.prefix);
+        if target.starts_with(config.prefix.as_str()) || config.prefix.starts_with(target) {
+            return Err(LogicError::IllegalArgument(String::from(
+                "The target prefix overlaps with the source prefix in the same connection",
+            )));
+        }
+    }
+    Ok(())
+}
+
+pub fn start_mirror(config: MirrorConfig, window: Window) -> Result<MirrorStatus, LogicError> {
+    check_config(&config)?;
+    let id = MIRROR_ID_COUNTER.fetch_add(1, Ordering::SeqCst);
+    let status = MirrorStatus {
+        id,
+        config: config.clone(),
+        state: MirrorState::Syncing,
+        synced_keys: 0,
+        applied_events: 0,
+        source_revision: 0,
+        applied_revision: 0,
+        lag_revisions: 0,
+        apply_millis: 0,
+        error_msg: None,
+    };
+    MIRROR_STATUS.insert(id, status.clone());
+    MIRROR_TASKS.insert(id, MirrorTask::start(id, config, None, window));
+    Ok(status)
+}
+
+/// 暂停镜像，停止监听但保留已应用的版本，恢复时从该版本继续
+pub fn pause_mirror(id: i32, window: Window) -> Result<(), LogicError> {
+    if let Some((_, mut task)) = MIRROR_TASKS.remove(&id) {
+        task.stop();
+    }
+    if !MIRROR_STATUS.contains_key(&id) {
+        return Err(LogicError::ResourceNotExist("The mirror does not exist"));
+    }
+    update_status(id, &window, |status| status.state = MirrorState::Paused);
+    Ok(())
+}
+
+/// 从已应用的版本之后继续镜像，若全量同步未完成则重新同步
+pub fn resume_mirror(id: i32, window: Window) -> Result<(), LogicError> {
+    let (config, start_revision) = {
+        let status = MIRROR_STATUS
+            .get(&id)
+            .ok_or(LogicError::ResourceNotExist("The mirror does not exist"))?;
+        if status.state == MirrorState::Running || status.state == MirrorState::Syncing {
+            return Ok(());
+        }
+        let start_revision = if status.applied_revision > 0 {
+            Some(status.applied_revision + 1)
+        } else {
+            None
+        };
+        (status.config.clone(), start_revision)
+    };
+    check_config(&config)?;
+    update_status(id, &window, |status| {
+        status.state = if start_revision.is_some() {
+            MirrorState::Running
+        } else {
+            MirrorState::Syncing
+        };
+        status.error_msg = None;
+    });
+    MIRROR_TASKS.insert(id, MirrorTask::start(id, config, start_revision, window));
+    Ok(())
+}
+
+pub fn stop_mirror(id: i32) {
+    MIRROR_TASKS.remove(&id);
+    MIRROR_STATUS.remove(&id);
+    SYNCED_REVISIONS.remove(&id);
+}
+
+pub fn list_mirrors() -> Vec<MirrorStatus> {
+    let mut list: Vec<MirrorStatus> = MIRROR_STATUS.iter().map(|s| s.value().clone()).collect();
+    list.sort_by_key(|s| s.id);
+    list
+}
+
+/// 连接关闭时停止相关的镜像，保留状态用于展示
+pub fn stop_session_mirrors(session: i32) {
+    let ids: Vec<i32> = MIRROR_STATUS
+        .iter()
+        .filter(|s| s.config.source_session == session || s.config.target_session == session)
+        .map(|s| s.id)
+        .collect();
+    for id in ids {
+        MIRROR_TASKS.remove(&id);
+        if let Some(mut status) = MIRROR_STATUS.get_mut(&id) {
+            status.state = MirrorState::Stopped;
+            status.error_msg = Some(String::from("Connection closed"));
+        }
+    }
+}
+
+/// 连接重建后在新连接上重新启动相关的镜像，运行中的镜像从已应用的版本之后继续，因连接失效而出错的镜像同样恢复
+pub fn resume_session_mirrors(session: i32, window: Window) {
+    let ids: Vec<i32> = MIRROR_STATUS
+        .iter()
+        .filter(|s| s.config.source_session == session || s.config.target_session == session)
+        .filter(|s| matches!(s.state, MirrorState::Error | MirrorState::Running | MirrorState::Syncing))
+        .map(|s| s.id)
+        .collect();
+    for id in ids {
+        if let Some((_, mut task)) = MIRROR_TASKS.remove(&id) {
+            task.stop();
+        }
+        if let Some(mut status) = MIRROR_STATUS.get_mut(&id) {
+            status.state = MirrorState::Paused;
+        }
+        if let Err(e) = resume_mirror(id, window.clone()) {
+            warn!("Failed to resume mirror {}: {:?}", id, e);
+        }
+    }
+}
@@ -1,32 +1,133 @@
 #![allow(unused)]
-use std::sync::Arc;
+use std::collections::VecDeque;
+use std::sync::{Arc, RwLock};
 use std::sync::atomic::{AtomicI32, Ordering};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use dashmap::mapref::entry::Entry;
 use dashmap::mapref::one::{Ref, RefMut};
 use dashmap::DashMap;
 use etcd_client::Error;
 use lazy_static::lazy_static;
 use tauri::Window;
-use tokio::sync::Mutex;
-use crate::api::connection;
+use tokio::sync::{broadcast, Mutex};
+use crate::api::{connection, windows};
+use crate::api::event_bus::{self, EventStream};
 use crate::error::LogicError;
-use crate::etcd::etcd_connector::EtcdConnector;
+use crate::etcd::etcd_connector::{EtcdConnector, MAX_CALL_DEADLINE_SECONDS};
+use crate::etcd::change_counter::ChangeSubscription;
+use crate::etcd::watch_throttle::{PrefixEventSubscription, WatchThrottle};
+use crate::etcd::edit_lock::EditLock;
+use crate::etcd::health_monitor::HealthMonitor;
+use crate::etcd::hot_keys::HotKeySampler;
+use crate::etcd::idle_monitor::IdleMonitor;
+use crate::etcd::key_index::{KeyIndex, KeyIndexer};
+use crate::etcd::latency_sampler::LatencySampler;
+use crate::etcd::lease_keeper::LeaseKeepAliveTask;
 use crate::etcd::key_monitor::KeyMonitor;
-use crate::transport::connection::{Connection, ConnectionInfo, SessionData};
+use crate::etcd::key_separator;
+use crate::etcd::notifier::NotificationWatcher;
+use crate::etcd::prefetcher::PrefetchIndex;
+use crate::transport::connection::{Connection, ConnectionInfo, KeySeparatorInfo, KeyTreeMode, NotificationRule, SessionData};
+use crate::api::settings::get_settings;
+use crate::transport::kv::{EditLockInfo, EditLockResult, HotKeyReport, LeaseKeepAliveState, PrefixChangeCounter, RevisionTimeSample};
+use crate::transport::user::{ReauthEvent, SessionIdentity};
+use crate::transport::maintenance::{EndpointCapabilities, EndpointLatency, HealthState, ServerFeature};
 
 pub mod etcd_connector;
 mod wrapped_etcd_client;
+mod retry;
 mod test;
 pub mod key_monitor;
+pub mod health_monitor;
+pub mod idle_monitor;
+pub mod key_index;
+pub mod change_counter;
+pub mod console;
+pub mod latency_sampler;
+pub mod lease_keeper;
+pub mod notifier;
+pub mod alert_dispatcher;
+mod value_crypto;
+mod value_cache;
+pub mod edit_lock;
+pub mod txn_batch;
+pub mod mirror;
+pub mod sandbox;
+pub mod hot_keys;
+pub mod dir_sync;
+pub mod wake_monitor;
+pub mod garbage;
+pub mod prefetcher;
+pub mod key_separator;
+pub mod cluster_status;
+pub mod shared_annotation;
+pub mod report_scheduler;
+pub mod audit_stream;
+pub mod maintenance_scheduler;
+pub mod clock_drift;
+pub mod raft_lag;
+pub mod key_tail;
+pub mod delete_preview;
+pub mod dry_run;
+pub mod kv_macro;
+pub mod unix_proxy;
+pub mod watch_throttle;
+pub mod poll_scheduler;
+pub mod snapshot_transfer;
+pub mod credential_prompt;
+pub mod session_lock;
+pub mod operation_queue;
+pub mod prefix_counter;
+pub mod content_hint;
 
 static CONNECTION_ID_COUNTER: AtomicI32 = AtomicI32::new(1);
+/// 每个连接最多保存的版本时间记录数
+const MAX_REVISION_SAMPLES: usize = 10000;
+/// `Mixed` 模式检测分隔符时采样的key数量
+const SEPARATOR_SAMPLE_KEYS: i64 = 2000;
 
 lazy_static! {
     static ref CONNECTION_POOL:DashMap<i32, EtcdConnector> = DashMap::with_capacity(2);
     static ref CONNECTION_CONFIG:DashMap<i32, Connection> = DashMap::with_capacity(2);
     static ref CONNECTION_INFO_POOL: DashMap<i32, ConnectionInfo> = DashMap::new();
     static ref CONNECTION_KEY_MONITORS: DashMap<i32, Arc<Mutex<KeyMonitor>>> = DashMap::new();
+    static ref CONNECTION_HEALTH_MONITORS: DashMap<i32, HealthMonitor> = DashMap::new();
+    static ref CONNECTION_HEALTH_STATE: DashMap<i32, HealthState> = DashMap::new();
+    static ref CONNECTION_CAPABILITIES: DashMap<i32, EndpointCapabilities> = DashMap::new();
+    static ref CONNECTION_IDLE_MONITORS: DashMap<i32, IdleMonitor> = DashMap::new();
+    //  连接最后一次操作的时间
+    static ref CONNECTION_LAST_ACTIVE: DashMap<i32, u128> = DashMap::new();
+    static ref CONNECTION_KEY_INDEXERS: DashMap<i32, KeyIndexer> = DashMap::new();
+    //  已建立完成的内存key索引
+    static ref CONNECTION_KEY_INDEX: DashMap<i32, Arc<RwLock<KeyIndex>>> = DashMap::new();
+    //  展开树节点时预取的部分key索引
+    static ref CONNECTION_PREFETCH_INDEX: DashMap<i32, PrefetchIndex> = DashMap::new();
+    //  `Mixed` 模式检测出的分隔符
+    static ref CONNECTION_KEY_SEPARATOR: DashMap<i32, KeySeparatorInfo> = DashMap::new();
+    //  token失效后重新认证的次数及最后一次的时间
+    static ref CONNECTION_REAUTH: DashMap<i32, (u64, u64)> = DashMap::new();
+    static ref CONNECTION_CHANGE_SUBSCRIPTIONS: DashMap<(i32, String), ChangeSubscription> = DashMap::new();
+    static ref CONNECTION_CHANGE_COUNTERS: DashMap<(i32, String), PrefixChangeCounter> = DashMap::new();
+    static ref CONNECTION_EVENT_SUBSCRIPTIONS: DashMap<(i32, String), PrefixEventSubscription> = DashMap::new();
+    static ref CONNECTION_LATENCY_SAMPLERS: DashMap<i32, LatencySampler> = DashMap::new();
+    static ref CONNECTION_LATENCY_SAMPLES: DashMap<i32, Vec<EndpointLatency>> = DashMap::new();
+    static ref CONNECTION_HOT_KEY_SAMPLERS: DashMap<i32, HotKeySampler> = DashMap::new();
+    //  最近一次热点key分析的报告
+    static ref CONNECTION_HOT_KEY_REPORTS: DashMap<i32, HotKeyReport> = DashMap::new();
+    static ref CONNECTION_LEASE_KEEP_ALIVE_TASKS: DashMap<(i32, i64), LeaseKeepAliveTask> = DashMap::new();
+    static ref CONNECTION_LEASE_KEEP_ALIVE_STATE: DashMap<(i32, i64), LeaseKeepAliveState> = DashMap::new();
+    //  连接期间观察到的版本与时间的对应关系
+    static ref CONNECTION_REVISION_TIMELINE: DashMap<i32, VecDeque<RevisionTimeSample>> = DashMap::new();
+    //  连接所属窗口的label，事件只推送到该窗口
+    static ref CONNECTION_WINDOW: DashMap<i32, String> = DashMap::new();
+    static ref CONNECTION_NAME: DashMap<i32, String> = DashMap::new();
+    static ref CONNECTION_NOTIFICATION_RULES: DashMap<i32, Vec<NotificationRule>> = DashMap::new();
+    static ref CONNECTION_NOTIFIERS: DashMap<i32, NotificationWatcher> = DashMap::new();
+    //  会话重新连接的通知，长期运行的监听任务收到后在新连接上重新监听
+    static ref SESSION_RECONNECTED: broadcast::Sender<i32> = broadcast::channel(16).0;
+    //  应用内的编辑锁，以 (集群地址和命名空间, key) 为索引，同一集群的不同会话共享
+    static ref EDIT_LOCKS: DashMap<(String, String), EditLock> = DashMap::new();
 }
 
 fn gen_connection_id() -> i32 {
@@ -41,14 +142,22 @@ pub fn now_timestamp() -> u128 {
 }
 
 pub async fn new_connector(name: String, connection: Connection, window: Window) -> Result<SessionData, LogicError> {
+    session_lock::ensure_unlocked()?;
+    let mut connection = connection::resolve_connection(connection).await?;
+    credential_prompt::fill_credentials(&window, &name, &mut connection).await?;
     let user = if let Some(u) = &connection.user {
         Some(u.username.clone())
     } else {
         None
     };
     let namespace = connection.namespace.clone();
+    let idle_timeout_minutes = connection.idle_timeout_minutes.unwrap_or(0);
     let mut connector = EtcdConnector::new(connection.clone()).await?;
     connector.test_connection().await?;
+    let capabilities = connector.endpoint_capabilities().await.unwrap_or_else(|e| {
+        log::warn!("Failed to detect endpoint capabilities: {}", e);
+        EndpointCapabilities::default()
+    });
 
     let root = if let Some(u) = &user {
         connector.user_is_root(u).await?
@@ -57,9 +166,15 @@ pub async fn new_connector(name: String, connection: Connection, window: Window)
     };
 
     let connector_id = gen_connection_id();
+    connector.set_session(connector_id);
+    set_reauth_listener(connector_id, &mut connector, &window);
     CONNECTION_POOL.insert(connector_id, connector);
+    CONNECTION_LAST_ACTIVE.insert(connector_id, now_timestamp());
+    CONNECTION_CAPABILITIES.insert(connector_id, capabilities);
 
     CONNECTION_CONFIG.insert(connector_id, connection);
+    CONNECTION_WINDOW.insert(connector_id, String::from(window.label()));
+    CONNECTION_NAME.insert(connector_id, name.clone());
 
 
     let info_result = connection::get_connection(name).await?;
@@ -67,14 +182,38 @@ pub async fn new_connector(name: String, connection: Connection, window: Window)
     let mut connection_saved = false;
     let mut key_collection = None;
     let mut key_monitor_list = None;
+    let mut notification_rules = vec![];
+    let mut tracked_prefixes = vec![];
     if let Some(info) = info_result {
         key_collection = Some((&info.key_collection).clone());
         key_monitor_list = Some((&info.key_monitor_list).clone());
+        notification_rules = info.notification_rules.clone();
+        tracked_prefixes = info.tracked_prefixes.clone();
         connection_saved = true;
         
         CONNECTION_INFO_POOL.insert(connector_id, info);
     }
 
+    let health_monitor = HealthMonitor::start(connector_id, window.clone()).await;
+    CONNECTION_HEALTH_MONITORS.insert(connector_id, health_monitor);
+
+    CONNECTION_KEY_INDEXERS.insert(connector_id, KeyIndexer::start(connector_id));
+
+    if idle_timeout_minutes > 0 {
+        let idle_monitor = IdleMonitor::start(connector_id, idle_timeout_minutes, window.clone());
+        CONNECTION_IDLE_MONITORS.insert(connector_id, idle_monitor);
+    }
+
+    if !notification_rules.is_empty() {
+        if let Err(e) = set_notification_rules(connector_id, notification_rules, window.clone()).await {
+            log::warn!("Failed to start notification watcher: {}, {:?}", connector_id, e);
+        }
+    }
+
+    for prefix in tracked_prefixes {
+        prefix_counter::track(connector_id, prefix);
+    }
+
     let mut key_monitor = KeyMonitor::new(connector_id, window);
     let mut has_key_monitor = false;
     if let Some(monitor_list) = &key_monitor_list {
@@ -90,6 +229,7 @@ pub async fn new_connector(name: String, connection: Connection, window: Window)
         log::info!("Started key monitor when create: {}", connector_id);
     }
     CONNECTION_KEY_MONITORS.insert(connector_id, key_monitor_lock);
+    windows::refresh_tray().await;
 
     Ok(SessionData {
         id: connector_id,
@@ -102,32 +242,759 @@ pub async fn new_connector(name: String, connection: Connection, window: Window)
     })
 }
 
+/// 获取会话的连接，会话锁定时返回错误
 pub fn get_connector(id: &i32) -> Result<RefMut<'_, i32, EtcdConnector>, LogicError> {
+    session_lock::ensure_unlocked()?;
     get_connector_optional(id).ok_or(LogicError::ConnectionLose)
 }
 
+/// 后台任务获取会话的连接，会话锁定时等待解锁后再获取，只在会话已关闭时返回错误
+pub async fn wait_connector(id: &i32) -> Result<RefMut<'_, i32, EtcdConnector>, LogicError> {
+    loop {
+        session_lock::wait_unlocked().await;
+        if let Some(connector) = get_connector_optional(id) {
+            return Ok(connector);
+        }
+        if !CONNECTION_POOL.contains_key(id) {
+            return Err(LogicError::ConnectionLose);
+        }
+    }
+}
+
+/// token失效后客户端会自动重新认证，记录次数并通知界面
+fn set_reauth_listener(id: i32, connector: &mut EtcdConnector, window: &Window) {
+    let window = window.clone();
+    connector.set_reauth_listener(Arc::new(move |error_msg| {
+        let time = now_timestamp() as u64;
+        CONNECTION_REAUTH
+            .entry(id)
+            .and_modify(|(count, last)| {
+                *count += 1;
+                *last = time;
+            })
+            .or_insert((1, time));
+        let event = ReauthEvent {
+            session: id,
+            user: get_connection_config(&id).and_then(|c| c.user.as_ref().map(|u| u.username.clone())),
+            success: error_msg.is_none(),
+            time,
+            error_msg,
+        };
+        log::info!("Session {} re-authenticated, success: {}", id, event.success);
+        event_bus::publish(&window, EventStream::Watch, "session_reauth", event);
+    }));
+}
+
+/// 获取连接使用的用户身份、token信息及重新认证的记录
+pub async fn get_session_identity(id: i32) -> Result<SessionIdentity, LogicError> {
+    let mut identity = get_connector(&id)?.session_identity().await?;
+    if let Some(reauth) = CONNECTION_REAUTH.get(&id) {
+        identity.reauth_count = reauth.0;
+        identity.last_reauth_time = Some(reauth.1);
+    }
+    Ok(identity)
+}
+
+/// 将界面为单次操作指定的超时秒数转换为超时时间，未指定时使用连接默认的超时时间
+pub fn call_deadline(timeout_seconds: Option<u64>) -> Result<Option<Duration>, LogicError> {
+    match timeout_seconds {
+        None => Ok(None),
+        Some(seconds) if seconds == 0 || seconds > MAX_CALL_DEADLINE_SECONDS => Err(LogicError::IllegalArgument(
+            format!("Timeout must be between 1 and {} seconds", MAX_CALL_DEADLINE_SECONDS),
+        )),
+        Some(seconds) => Ok(Some(Duration::from_secs(seconds))),
+    }
+}
+
+/// 会话锁定时返回 None，锁定期间后台任务同样不能使用连接
 pub fn get_connector_optional(id: &i32) -> Option<RefMut<'_, i32, EtcdConnector>> {
+    if session_lock::is_locked() {
+        return None;
+    }
     CONNECTION_POOL.get_mut(id)
 }
 
+/// 刷新会话的最后活跃时间，只在前端调用命令时记录，后台任务使用连接不影响空闲断开
+pub fn touch_session(id: &i32) {
+    if let Some(mut last_active) = CONNECTION_LAST_ACTIVE.get_mut(id) {
+        *last_active = now_timestamp();
+    }
+}
+
 pub fn get_connection_config(id: &i32) -> Option<Ref<'_, i32, Connection>> {
     CONNECTION_CONFIG.get(id)
 }
 
+/// 基于会话的连接配置生成连接到指定成员的配置，沿用认证、TLS和SSH配置
+pub fn get_member_connection(id: &i32, client_uri: &str) -> Result<Connection, String> {
+    if session_lock::is_locked() {
+        return Err(String::from("The session is locked"));
+    }
+    let mut connection = get_connection_config(id)
+        .map(|c| c.value().clone())
+        .ok_or_else(|| String::from("Connection lose"))?;
+
+    let address = client_uri.split("://").last().unwrap_or(client_uri);
+    let (host, port) = address
+        .rsplit_once(':')
+        .ok_or_else(|| format!("Invalid client url: {}", client_uri))?;
+    connection.host = host.trim_start_matches('[').trim_end_matches(']').to_string();
+    connection.port = port
+        .trim_end_matches('/')
+        .parse()
+        .map_err(|_| format!("Invalid client url: {}", client_uri))?;
+    //  固定的证书指纹只对应原连接的节点
+    if let Some(tls) = connection.tls.as_mut() {
+        tls.pinned_fingerprint = None;
+    }
+    Ok(connection)
+}
+
+/// 检查连接是否允许写操作
+pub fn check_writable(id: &i32) -> Result<(), LogicError> {
+    match CONNECTION_CONFIG.get(id) {
+        Some(config) if config.read_only => Err(LogicError::ReadOnly),
+        Some(_) => Ok(()),
+        None => Err(LogicError::ConnectionLose),
+    }
+}
+
 pub fn get_connection_info_optional(id: &i32) -> Option<RefMut<'_, i32, ConnectionInfo>> {
     CONNECTION_INFO_POOL.get_mut(id)
 }
 
+pub fn get_health_state(id: &i32) -> Option<HealthState> {
+    CONNECTION_HEALTH_STATE.get(id).map(|s| s.value().clone())
+}
+
+pub fn get_capabilities(id: &i32) -> Result<EndpointCapabilities, LogicError> {
+    CONNECTION_CAPABILITIES
+        .get(id)
+        .map(|c| c.value().clone())
+        .ok_or(LogicError::ConnectionLose)
+}
+
+/// 检查连接时获取到的服务端版本是否支持该功能
+pub fn check_feature(id: &i32, feature: ServerFeature) -> Result<(), LogicError> {
+    let capabilities = get_capabilities(id)?;
+    if feature.supported_by(&capabilities.server_version) {
+        Ok(())
+    } else {
+        Err(LogicError::UnsupportedByServer {
+            feature: feature.name(),
+            min_version: feature.min_version(),
+            server_version: capabilities.server_version,
+        })
+    }
+}
+
+/// 通过 gRPC proxy 连接时运维操作会被转发到任意成员，直接拒绝而不是返回令人困惑的结果
+pub fn check_maintenance_supported(id: &i32) -> Result<(), LogicError> {
+    if get_capabilities(id)?.maintenance {
+        Ok(())
+    } else {
+        Err(LogicError::IllegalArgument(String::from(
+            "Maintenance operations are not supported through an etcd gRPC proxy, please connect to a member directly",
+        )))
+    }
+}
+
+/// 获取连接的内存key索引，未建立索引或处于历史版本读取模式时返回 None
+pub fn get_key_index(id: &i32) -> Option<Arc<RwLock<KeyIndex>>> {
+    let time_travel = CONNECTION_POOL
+        .get(id)
+        .map(|c| c.get_read_revision().is_some())
+        .unwrap_or(false);
+    if time_travel {
+        return None;
+    }
+    CONNECTION_KEY_INDEX.get(id).map(|i| Arc::clone(i.value()))
+}
+
+/// 获取连接构建key树使用的分隔符，未配置时使用设置中的分隔符。
+///
+/// `Mixed` 模式从索引或etcd中采样key评估各候选分隔符，结果在连接期间缓存，`refresh` 为 true 时重新检测
+pub async fn resolve_key_separator(id: i32, refresh: bool) -> Result<KeySeparatorInfo, LogicError> {
+    let config = get_connection_config(&id).and_then(|c| c.key_tree.clone());
+    let Some(config) = config else {
+        return Ok(KeySeparatorInfo {
+            mode: KeyTreeMode::Separator,
+            separator: get_settings().await?.kv_path_splitter,
+            scores: vec![],
+        });
+    };
+
+    match config.mode {
+        KeyTreeMode::Flat => Ok(KeySeparatorInfo {
+            mode: KeyTreeMode::Flat,
+            separator: String::new(),
+            scores: vec![],
+        }),
+        KeyTreeMode::Separator => {
+            let separator = match config.separator.filter(|s| !s.is_empty()) {
+                Some(separator) => separator,
+                None => get_settings().await?.kv_path_splitter,
+            };
+            Ok(KeySeparatorInfo {
+                mode: KeyTreeMode::Separator,
+                separator,
+                scores: vec![],
+            })
+        }
+        KeyTreeMode::Mixed => {
+            if !refresh {
+                if let Some(info) = CONNECTION_KEY_SEPARATOR.get(&id) {
+                    return Ok(info.clone());
+                }
+            }
+            let keys: Vec<String> = if let Some(index) = get_key_index(&id) {
+                let index = index.read().unwrap();
+                index
+                    .keys_with_prefix(&[], SEPARATOR_SAMPLE_KEYS as usize)
+                    .into_iter()
+                    .map(|(key, _)| String::from_utf8_lossy(&key).to_string())
+                    .collect()
+            } else {
+                let mut connector = get_connector(&id)?;
+                connector
+                    .kv_get_keys_with_prefix("", SEPARATOR_SAMPLE_KEYS)
+                    .await?
+                    .into_iter()
+                    .map(|kv| kv.key)
+                    .collect()
+            };
+            let scores = key_separator::detect(&keys, &config.candidates);
+            //  所有候选分隔符都无法分层时平铺展示
+            let separator = scores
+                .first()
+                .filter(|s| s.score > 0.0)
+                .map(|s| s.separator.clone())
+                .unwrap_or_default();
+            let info = KeySeparatorInfo {
+                mode: KeyTreeMode::Mixed,
+                separator,
+                scores,
+            };
+            CONNECTION_KEY_SEPARATOR.insert(id, info.clone());
+            Ok(info)
+        }
+    }
+}
+
+/// 获取连接构建key树使用的分隔符，为空表示平铺
+pub async fn get_key_separator(id: i32) -> Result<String, LogicError> {
+    Ok(resolve_key_separator(id, false).await?.separator)
+}
+
+/// 开始延迟采样，已在采样时不做处理
+pub fn start_latency_sampler(id: i32) -> Result<(), LogicError> {
+    if !CONNECTION_CONFIG.contains_key(&id) {
+        return Err(LogicError::ConnectionLose);
+    }
+    CONNECTION_LATENCY_SAMPLERS
+        .entry(id)
+        .or_insert_with(|| LatencySampler::start(id));
+    Ok(())
+}
+
+pub fn stop_latency_sampler(id: i32) {
+    if let Some((_, mut sampler)) = CONNECTION_LATENCY_SAMPLERS.remove(&id) {
+        sampler.stop();
+    }
+    CONNECTION_LATENCY_SAMPLES.remove(&id);
+}
+
+pub fn get_latency_samples(id: i32) -> Vec<EndpointLatency> {
+    CONNECTION_LATENCY_SAMPLES
+        .get(&id)
+        .map(|s| s.value().clone())
+        .unwrap_or_default()
+}
+
+/// 开始热点key分析，已在分析时重新开始
+pub async fn start_hot_key_sampling(
+    id: i32,
+    duration_seconds: u64,
+    splitter: String,
+    depth: usize,
+    top: usize,
+    window: Window,
+) -> Result<(), LogicError> {
+    if duration_seconds == 0 || duration_seconds > 3600 {
+        return Err(LogicError::IllegalArgument(String::from(
+            "Duration must be between 1 and 3600 seconds",
+        )));
+    }
+    stop_hot_key_sampling(id);
+    let (namespace, watcher, stream) = {
+        let mut connector = get_connector(&id)?;
+        let namespace = if connector.has_namespace() {
+            Some(connector.get_namespace_unchecked().clone())
+        } else {
+            None
+        };
+        let (watcher, stream) = connector.kv_watch_prefix(vec![]).await?;
+        (namespace, watcher, stream)
+    };
+    let sampler = HotKeySampler::start(
+        id,
+        namespace,
+        std::time::Duration::from_secs(duration_seconds),
+        splitter,
+        depth,
+        top.max(1),
+        watcher,
+        stream,
+        window,
+    );
+    CONNECTION_HOT_KEY_SAMPLERS.insert(id, sampler);
+    Ok(())
+}
+
+/// 提前结束热点key分析，保留已统计的报告
+pub fn stop_hot_key_sampling(id: i32) {
+    if let Some((_, mut sampler)) = CONNECTION_HOT_KEY_SAMPLERS.remove(&id) {
+        sampler.stop();
+    }
+}
+
+pub fn get_hot_key_report(id: i32) -> Option<HotKeyReport> {
+    CONNECTION_HOT_KEY_REPORTS.get(&id).map(|r| r.value().clone())
+}
+
+/// 开始代为续约lease，已在续约时重新开始
+pub async fn start_lease_keep_alive(id: i32, lease: i64, window: Window) -> Result<(), LogicError> {
+    stop_lease_keep_alive(id, lease);
+    let (keeper, stream) = {
+        let mut connector = get_connector(&id)?;
+        connector.lease_keep_alive(lease).await?
+    };
+    let task = LeaseKeepAliveTask::start(id, lease, keeper, stream, window);
+    CONNECTION_LEASE_KEEP_ALIVE_TASKS.insert((id, lease), task);
+    Ok(())
+}
+
+pub fn stop_lease_keep_alive(id: i32, lease: i64) {
+    if let Some((_, mut task)) = CONNECTION_LEASE_KEEP_ALIVE_TASKS.remove(&(id, lease)) {
+        task.stop();
+    }
+    CONNECTION_LEASE_KEEP_ALIVE_STATE.remove(&(id, lease));
+}
+
+pub fn list_lease_keep_alive(id: i32) -> Vec<LeaseKeepAliveState> {
+    CONNECTION_LEASE_KEEP_ALIVE_STATE
+        .iter()
+        .filter(|e| e.key().0 == id)
+        .map(|e| e.value().clone())
+        .collect()
+}
+
+/// 记录某一时间观察到的集群版本，版本未变化时只更新时间
+pub fn record_revision_sample(id: i32, time: u64, revision: i64) {
+    let mut timeline = CONNECTION_REVISION_TIMELINE.entry(id).or_default();
+    if let Some(last) = timeline.back_mut() {
+        if last.revision == revision {
+            last.time = time;
+            return;
+        }
+    }
+    timeline.push_back(RevisionTimeSample { time, revision });
+    if timeline.len() > MAX_REVISION_SAMPLES {
+        timeline.pop_front();
+    }
+}
+
+/// 获取时间点附近观察到的版本，前后各最多 `limit` 个，只能覆盖连接建立之后的时间
+pub fn list_revisions_near_time(id: i32, timestamp: u64, limit: usize) -> Vec<RevisionTimeSample> {
+    match CONNECTION_REVISION_TIMELINE.get(&id) {
+        Some(timeline) => {
+            let pos = timeline.partition_point(|s| s.time <= timestamp);
+            let start = pos.saturating_sub(limit);
+            let end = (pos + limit).min(timeline.len());
+            timeline.range(start..end).cloned().collect()
+        }
+        None => vec![],
+    }
+}
+
+/// 获取连接期间观察到的所有版本时间记录
+pub fn get_revision_timeline(id: i32) -> Vec<RevisionTimeSample> {
+    CONNECTION_REVISION_TIMELINE
+        .get(&id)
+        .map(|timeline| timeline.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// 订阅前缀的变化统计，已订阅时不做处理
+pub async fn subscribe_prefix_changes(id: i32, prefix: String, window: Window) -> Result<(), LogicError> {
+    let key = (id, prefix.clone());
+    if CONNECTION_CHANGE_SUBSCRIPTIONS.contains_key(&key) {
+        return Ok(());
+    }
+    let flush_interval = Duration::from_millis(get_settings().await?.watch_flush_interval_millis);
+    let (watcher, stream) = {
+        let mut connector = get_connector(&id)?;
+        connector.kv_watch_prefix(prefix.clone()).await?
+    };
+    let subscription = ChangeSubscription::start(id, prefix, watcher, stream, window, flush_interval);
+    CONNECTION_CHANGE_SUBSCRIPTIONS.insert(key, subscription);
+    Ok(())
+}
+
+/// 订阅前缀下key的变更事件，事件按设置的刷新间隔合并、限流后推送，已订阅时不做处理
+pub async fn subscribe_prefix_events(id: i32, prefix: String, window: Window) -> Result<(), LogicError> {
+    let key = (id, prefix.clone());
+    if CONNECTION_EVENT_SUBSCRIPTIONS.contains_key(&key) {
+        return Ok(());
+    }
+    let settings = get_settings().await?;
+    let throttle = WatchThrottle::new(
+        Duration::from_millis(settings.watch_flush_interval_millis),
+        settings.watch_max_events_per_second,
+    );
+    let namespace = get_connection_config(&id).and_then(|c| c.namespace.clone());
+    let (watcher, stream) = {
+        let mut connector = get_connector(&id)?;
+        connector.kv_watch_prefix(prefix.clone()).await?
+    };
+    let subscription = PrefixEventSubscription::start(id, prefix, namespace, watcher, stream, window, throttle);
+    CONNECTION_EVENT_SUBSCRIPTIONS.insert(key, subscription);
+    Ok(())
+}
+
+pub fn unsubscribe_prefix_events(id: i32, prefix: String) {
+    if let Some((_, mut subscription)) = CONNECTION_EVENT_SUBSCRIPTIONS.remove(&(id, prefix)) {
+        subscription.stop();
+    }
+}
+
+pub fn unsubscribe_prefix_changes(id: i32, prefix: String) {
+    let key = (id, prefix);
+    if let Some((_, mut subscription)) = CONNECTION_CHANGE_SUBSCRIPTIONS.remove(&key) {
+        subscription.stop();
+    }
+    CONNECTION_CHANGE_COUNTERS.remove(&key);
+}
+
+/// 用户查看前缀后清零统计
+pub fn reset_prefix_changes(id: i32, prefix: String) -> Option<PrefixChangeCounter> {
+    CONNECTION_CHANGE_COUNTERS.get_mut(&(id, prefix)).map(|mut counter| {
+        counter.added = 0;
+        counter.modified = 0;
+        counter.deleted = 0;
+        counter.since = now_timestamp() as u64;
+        counter.clone()
+    })
+}
+
+pub fn get_prefix_changes(id: i32) -> Vec<PrefixChangeCounter> {
+    CONNECTION_CHANGE_COUNTERS
+        .iter()
+        .filter(|e| e.key().0 == id)
+        .map(|e| e.value().clone())
+        .collect()
+}
+
+/// 更新连接的通知规则，有规则时启动监听任务，没有规则时停止
+pub async fn set_notification_rules(id: i32, rules: Vec<NotificationRule>, window: Window) -> Result<(), LogicError> {
+    if rules.is_empty() {
+        CONNECTION_NOTIFICATION_RULES.remove(&id);
+        CONNECTION_NOTIFIERS.remove(&id);
+        return Ok(());
+    }
+    CONNECTION_NOTIFICATION_RULES.insert(id, rules);
+    if CONNECTION_NOTIFIERS.contains_key(&id) {
+        return Ok(());
+    }
+
+    let namespace = get_connection_config(&id).and_then(|c| c.namespace.clone());
+    let (watcher, stream) = {
+        let mut connector = get_connector(&id)?;
+        connector.kv_watch_prefix("").await?
+    };
+    let notifier = NotificationWatcher::start(id, namespace, watcher, stream, window);
+    CONNECTION_NOTIFIERS.insert(id, notifier);
+    Ok(())
+}
+
+pub fn get_notification_rules(id: &i32) -> Vec<NotificationRule> {
+    CONNECTION_NOTIFICATION_RULES
+        .get(id)
+        .map(|rules| rules.value().clone())
+        .unwrap_or_default()
+}
+
+/// 连接的集群地址和命名空间，用于在不同会话间识别同一份数据
+pub fn cluster_scope(id: &i32) -> Result<String, LogicError> {
+    let config = get_connection_config(id).ok_or(LogicError::ConnectionLose)?;
+    Ok(format!(
+        "{}:{}{}",
+        config.host,
+        config.port,
+        config.namespace.as_deref().unwrap_or("")
+    ))
+}
+
+/// 获取key的编辑锁。同一集群中其他会话正在编辑时返回其持有者信息；
+/// 开启共享编辑锁后，还会在etcd中基于lease加锁，以便发现其他workbench用户
+pub async fn acquire_edit_lock(id: i32, key: String) -> Result<EditLockResult, LogicError> {
+    let scope = (cluster_scope(&id)?, key.clone());
+    if let Some(lock) = EDIT_LOCKS.get(&scope) {
+        let acquired = lock.info.session == id;
+        return Ok(EditLockResult {
+            acquired,
+            holder: if acquired { None } else { Some(lock.info.clone()) },
+        });
+    }
+
+    let info = EditLockInfo {
+        key: key.clone(),
+        owner: edit_lock::local_identity(),
+        etcd_user: get_connection_config(&id).and_then(|c| c.user.as_ref().map(|u| u.username.clone())),
+        session: id,
+        since: now_timestamp() as u64,
+        remote: false,
+    };
+
+    let shared = get_settings().await?.shared_edit_lock && check_writable(&id).is_ok();
+    let lock = if shared {
+        let mut connector = get_connector(&id)?;
+        let lease = connector.lease_grant(edit_lock::EDIT_LOCK_TTL, None).await?;
+        let value = serde_json::to_vec(&info)?;
+        if let Some(existing) = connector.kv_put_if_absent(edit_lock::lock_key(&key), value, lease).await? {
+            let _ = connector.lease_revoke(lease).await;
+            let holder = serde_json::from_slice::<EditLockInfo>(&existing).ok().map(|mut h| {
+                h.remote = true;
+                h
+            });
+            return Ok(EditLockResult { acquired: false, holder });
+        }
+        let (keeper, stream) = connector.lease_keep_alive(lease).await?;
+        EditLock::shared(info, lease, keeper, stream)
+    } else {
+        EditLock::local(info)
+    };
+
+    match EDIT_LOCKS.entry(scope) {
+        Entry::Occupied(e) => {
+            //  等待etcd期间被其他会话抢先获取
+            let holder = e.get().info.clone();
+            drop(e);
+            if let Some(lease) = lock.lease {
+                let mut connector = get_connector(&id)?;
+                let _ = connector.lease_revoke(lease).await;
+            }
+            Ok(EditLockResult { acquired: holder.session == id, holder: Some(holder) })
+        }
+        Entry::Vacant(e) => {
+            e.insert(lock);
+            Ok(EditLockResult { acquired: true, holder: None })
+        }
+    }
+}
+
+/// 释放会话持有的编辑锁
+pub async fn release_edit_lock(id: i32, key: String) -> Result<(), LogicError> {
+    let scope = (cluster_scope(&id)?, key);
+    let Some((_, mut lock)) = EDIT_LOCKS.remove_if(&scope, |_, lock| lock.info.session == id) else {
+        return Ok(());
+    };
+    lock.stop();
+    if let Some(lease) = lock.lease {
+        let mut connector = get_connector(&id)?;
+        connector.lease_revoke(lease).await?;
+    }
+    Ok(())
+}
+
 pub fn get_key_monitor(id: &i32) -> Ref<'_, i32, Arc<Mutex<KeyMonitor>>> {
     CONNECTION_KEY_MONITORS.get(id).unwrap()
 }
 
+/// 获取连接所属窗口的label
+pub fn get_session_window(id: &i32) -> Option<String> {
+    CONNECTION_WINDOW.get(id).map(|label| label.value().clone())
+}
+
+/// 获取所有已打开的连接及其健康状态，健康状态为空表示尚未完成检查
+pub fn list_session_health() -> Vec<(i32, String, Option<bool>)> {
+    let mut sessions: Vec<(i32, String, Option<bool>)> = CONNECTION_NAME
+        .iter()
+        .map(|e| {
+            let healthy = CONNECTION_HEALTH_STATE.get(e.key()).map(|s| s.healthy);
+            (*e.key(), e.value().clone(), healthy)
+        })
+        .collect();
+    sessions.sort_by_key(|s| s.0);
+    sessions
+}
+
+/// 获取会话对应的连接名
+pub fn get_connection_name(id: &i32) -> Option<String> {
+    CONNECTION_NAME.get(id).map(|n| n.value().clone())
+}
+
+/// 查找窗口中已打开的同名连接
+pub fn find_window_session(label: &str, name: &str) -> Option<i32> {
+    CONNECTION_NAME
+        .iter()
+        .filter(|e| e.value() == name)
+        .map(|e| *e.key())
+        .find(|id| CONNECTION_WINDOW.get(id).map(|l| l.value() == label).unwrap_or(false))
+}
+
+/// 获取同一个已保存连接打开的所有会话
+pub fn list_named_sessions(name: &str) -> Vec<i32> {
+    let mut sessions: Vec<i32> = CONNECTION_NAME
+        .iter()
+        .filter(|e| e.value() == name)
+        .map(|e| *e.key())
+        .collect();
+    sessions.sort();
+    sessions
+}
+
+/// 获取所有已打开的连接及其所属窗口的label
+pub fn list_session_windows() -> Vec<(i32, String)> {
+    let mut sessions: Vec<(i32, String)> = CONNECTION_WINDOW
+        .iter()
+        .map(|e| (*e.key(), e.value().clone()))
+        .collect();
+    sessions.sort_by_key(|s| s.0);
+    sessions
+}
+
+/// 重新建立连接的SSH隧道和etcd客户端，并重启或通知依赖旧客户端的监听任务。
+///
+/// 用于系统休眠唤醒后，原有的TCP连接和watch流大多已失效
+pub async fn reconnect_session(id: i32, window: Window) -> Result<(), LogicError> {
+    session_lock::ensure_unlocked()?;
+    let connection = get_connection_config(&id)
+        .ok_or(LogicError::ConnectionLose)?
+        .value()
+        .clone();
+    let (read_revision, value_cache_enabled) = {
+        let connector = get_connector(&id)?;
+        (connector.get_read_revision(), connector.value_cache_stats().is_some())
+    };
+
+    let mut connector = EtcdConnector::new(connection).await?;
+    connector.test_connection().await?;
+    connector.set_read_revision(read_revision);
+    connector.set_session(id);
+    set_reauth_listener(id, &mut connector, &window);
+    if value_cache_enabled {
+        connector.set_value_cache_enabled(true).await?;
+    }
+    //  旧连接在此释放，其SSH隧道随之关闭
+    CONNECTION_POOL.insert(id, connector);
+    CONNECTION_LAST_ACTIVE.insert(id, now_timestamp());
+    //  审计、key追踪和热点key分析任务收到通知后在新连接上重新监听，没有任务时发送失败可忽略
+    let _ = SESSION_RECONNECTED.send(id);
+
+    if let Some(mut indexer) = CONNECTION_KEY_INDEXERS.insert(id, KeyIndexer::start(id)) {
+        indexer.stop();
+    }
+
+    CONNECTION_NOTIFIERS.remove(&id);
+    let rules = get_notification_rules(&id);
+    if !rules.is_empty() {
+        set_notification_rules(id, rules, window.clone()).await?;
+    }
+
+    //  重新订阅前缀变化，保留已有的统计
+    let prefixes: Vec<String> = CONNECTION_CHANGE_SUBSCRIPTIONS
+        .iter()
+        .filter(|e| e.key().0 == id)
+        .map(|e| e.key().1.clone())
+        .collect();
+    for prefix in prefixes {
+        let key = (id, prefix.clone());
+        if let Some((_, mut subscription)) = CONNECTION_CHANGE_SUBSCRIPTIONS.remove(&key) {
+            subscription.stop();
+        }
+        let counter = CONNECTION_CHANGE_COUNTERS.get(&key).map(|c| c.value().clone());
+        subscribe_prefix_changes(id, prefix, window.clone()).await?;
+        if let Some(counter) = counter {
+            CONNECTION_CHANGE_COUNTERS.insert(key, counter);
+        }
+    }
+
+    let prefixes: Vec<String> = CONNECTION_EVENT_SUBSCRIPTIONS
+        .iter()
+        .filter(|e| e.key().0 == id)
+        .map(|e| e.key().1.clone())
+        .collect();
+    for prefix in prefixes {
+        unsubscribe_prefix_events(id, prefix.clone());
+        subscribe_prefix_events(id, prefix, window.clone()).await?;
+    }
+
+    let leases: Vec<i64> = CONNECTION_LEASE_KEEP_ALIVE_TASKS
+        .iter()
+        .filter(|e| e.key().0 == id)
+        .map(|e| e.key().1)
+        .collect();
+    for lease in leases {
+        if let Err(e) = start_lease_keep_alive(id, lease, window.clone()).await {
+            log::warn!("Failed to restart lease keep alive: {}, {}, {:?}", id, lease, e);
+        }
+    }
+
+    mirror::resume_session_mirrors(id, window);
+    log::info!("Session reconnected: {}", id);
+    Ok(())
+}
+
+/// 订阅会话重新连接的通知
+pub fn subscribe_reconnected() -> broadcast::Receiver<i32> {
+    SESSION_RECONNECTED.subscribe()
+}
+
+/// 等待指定会话重新连接，错过通知时同样视为已重新连接
+pub async fn wait_reconnected(receiver: &mut broadcast::Receiver<i32>, session: i32) {
+    loop {
+        match receiver.recv().await {
+            Ok(id) if id == session => return,
+            Ok(_) => continue,
+            Err(broadcast::error::RecvError::Lagged(_)) => return,
+            Err(broadcast::error::RecvError::Closed) => std::future::pending().await,
+        }
+    }
+}
+
+/// 关闭窗口中打开的所有连接
+pub async fn remove_window_connectors(label: &str) {
+    let ids: Vec<i32> = CONNECTION_WINDOW
+        .iter()
+        .filter(|e| e.value() == label)
+        .map(|e| *e.key())
+        .collect();
+    for id in ids {
+        remove_connector(&id).await;
+        log::info!("Removed connection of closed window {}: {}", label, id);
+    }
+}
+
+/// 断开所有会话，应用退出时使用
+pub async fn remove_all_connectors() {
+    let mut ids: Vec<i32> = CONNECTION_CONFIG.iter().map(|e| *e.key()).collect();
+    ids.extend(CONNECTION_POOL.iter().map(|e| *e.key()));
+    ids.sort();
+    ids.dedup();
+    for id in ids {
+        remove_connector(&id).await;
+    }
+}
+
 pub async fn remove_connector(id: &i32) {
     if let Some((_, connector)) = CONNECTION_POOL.remove(id) {
         drop(connector)
     }
 
     CONNECTION_CONFIG.remove(id);
+    CONNECTION_WINDOW.remove(id);
+    CONNECTION_NAME.remove(id);
 
     if let Some((_, info)) = CONNECTION_INFO_POOL.remove(id) {
         drop(info)
@@ -136,4 +1003,50 @@ pub async fn remove_connector(id: &i32) {
     if let Some((_, lock)) = CONNECTION_KEY_MONITORS.remove(id) {
         KeyMonitor::stop(lock).await;
     }
+
+    if let Some((_, mut monitor)) = CONNECTION_HEALTH_MONITORS.remove(id) {
+        monitor.stop();
+    }
+    CONNECTION_HEALTH_STATE.remove(id);
+    CONNECTION_CAPABILITIES.remove(id);
+
+    if let Some((_, mut monitor)) = CONNECTION_IDLE_MONITORS.remove(id) {
+        monitor.stop();
+    }
+    CONNECTION_LAST_ACTIVE.remove(id);
+
+    if let Some((_, mut indexer)) = CONNECTION_KEY_INDEXERS.remove(id) {
+        indexer.stop();
+    }
+    CONNECTION_KEY_INDEX.remove(id);
+    CONNECTION_PREFETCH_INDEX.remove(id);
+    CONNECTION_KEY_SEPARATOR.remove(id);
+    CONNECTION_REAUTH.remove(id);
+
+    CONNECTION_CHANGE_SUBSCRIPTIONS.retain(|key, _| key.0 != *id);
+    CONNECTION_CHANGE_COUNTERS.retain(|key, _| key.0 != *id);
+    CONNECTION_EVENT_SUBSCRIPTIONS.retain(|key, _| key.0 != *id);
+    CONNECTION_REVISION_TIMELINE.remove(id);
+
+    stop_latency_sampler(*id);
+    stop_hot_key_sampling(*id);
+    CONNECTION_HOT_KEY_REPORTS.remove(id);
+
+    CONNECTION_LEASE_KEEP_ALIVE_TASKS.retain(|key, _| key.0 != *id);
+    CONNECTION_LEASE_KEEP_ALIVE_STATE.retain(|key, _| key.0 != *id);
+
+    CONNECTION_NOTIFIERS.remove(id);
+    CONNECTION_NOTIFICATION_RULES.remove(id);
+    //  etcd中的共享锁会在lease过期后释放
+    EDIT_LOCKS.retain(|_, lock| lock.info.session != *id);
+    mirror::stop_session_mirrors(*id);
+    maintenance_scheduler::remove_session(*id);
+    operation_queue::remove_session(*id);
+    prefix_counter::remove_session(*id);
+    clock_drift::remove_session(*id);
+    raft_lag::remove_session(*id);
+    delete_preview::remove_session(*id);
+    kv_macro::remove_session(*id);
+
+    windows::refresh_tray().await;
 }
\ No newline at end of file
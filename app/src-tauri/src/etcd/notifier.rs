@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+use etcd_client::{EventType, WatchStream, Watcher};
+use log::{debug, info, warn};
+use tauri::api::notification::Notification;
+use tauri::Window;
+use tokio::select;
+use tokio::sync::oneshot;
+
+use crate::api::event_bus::{self, EventStream};
+use crate::error::LogicError;
+use crate::transport::connection::{NotificationRuleTriggered, WatchEventType};
+
+use super::{now_timestamp, subscribe_reconnected, wait_connector, wait_reconnected, CONNECTION_NOTIFICATION_RULES};
+
+/// 重新建立监听的最短等待时间，连续失败时翻倍
+const MIN_RETRY_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+/// 从 `next_revision` 开始重新监听整个键空间，尚未收到过任何响应时从当前版本开始
+async fn rewatch(session_id: i32, next_revision: i64) -> Result<(Watcher, WatchStream), LogicError> {
+    let mut connector = wait_connector(&session_id).await?;
+    let watch = if next_revision > 0 {
+        connector.kv_watch_prefix_from("", next_revision).await?
+    } else {
+        connector.kv_watch_prefix("").await?
+    };
+    Ok(watch)
+}
+
+/// 监听事件通知任务，监听整个键空间，事件匹配通知规则时发送系统通知，
+/// 即使窗口最小化也会通知，同时通过 `notification_rule_triggered` 事件推送给窗口
+pub struct NotificationWatcher {
+    session_id: i32,
+    stop_notifier: Option<oneshot::Sender<()>>,
+}
+
+impl NotificationWatcher {
+    pub fn start(
+        session_id: i32,
+        namespace: Option<String>,
+        mut watcher: Watcher,
+        mut stream: WatchStream,
+        window: Window,
+    ) -> Self {
+        let (stop_sender, mut stop_receiver) = oneshot::channel::<()>();
+
+        tokio::spawn(async move {
+            info!("Notification watcher started: {}", session_id);
+            //  下一个需要接收的版本，监听中断后从这里重新监听，避免漏掉中断期间的事件
+            let mut next_revision = 0i64;
+            let mut reconnected = subscribe_reconnected();
+            let mut backoff = MIN_RETRY_BACKOFF;
+            loop {
+                let reason = loop {
+                    let message = select! {
+                        message = stream.message() => message,
+                        _ = wait_reconnected(&mut reconnected, session_id) => {
+                            backoff = Duration::ZERO;
+                            break String::from("Session reconnected");
+                        }
+                        _ = &mut stop_receiver => {
+                            let _ = watcher.cancel().await;
+                            debug!("Notification watcher stopped: {}", session_id);
+                            return;
+                        }
+                    };
+
+                    let response = match message {
+                        Ok(Some(response)) => response,
+                        Ok(None) => break String::from("Watch stream closed"),
+                        Err(e) => break e.to_string(),
+                    };
+                    backoff = MIN_RETRY_BACKOFF;
+                    if response.compact_revision() > 0 {
+                        //  中间的事件已被压缩，从压缩版本继续监听
+                        next_revision = response.compact_revision();
+                        break format!("Revision has been compacted to {}", response.compact_revision());
+                    }
+                    if response.canceled() {
+                        break format!("Watch canceled: {}", response.cancel_reason());
+                    }
+                    if next_revision == 0 {
+                        if let Some(header) = response.header() {
+                            next_revision = header.revision() + 1;
+                        }
+                    }
+
+                    for event in response.events() {
+                        let Some(kv) = event.kv() else {
+                            continue;
+                        };
+                        next_revision = next_revision.max(kv.mod_revision() + 1);
+                        let mut key = String::from_utf8_lossy(kv.key()).to_string();
+                        if let Some(namespace) = &namespace {
+                            if let Some(k) = key.strip_prefix(namespace.as_str()) {
+                                key = String::from(k);
+                            }
+                        }
+                        let (event_type, value) = match event.event_type() {
+                            EventType::Put if kv.version() == 1 => (WatchEventType::Create, Some(kv.value())),
+                            EventType::Put => (WatchEventType::Modify, Some(kv.value())),
+                            EventType::Delete => (WatchEventType::Delete, None),
+                        };
+                        Self::on_event(session_id, &key, event_type, value, &window);
+                    }
+                };
+                let _ = watcher.cancel().await;
+                debug!("Notification watch of {} interrupted: {}", session_id, reason);
+
+                //  按退避时间重试，直到重新监听成功或任务停止
+                loop {
+                    select! {
+                        _ = tokio::time::sleep(backoff) => {},
+                        _ = &mut stop_receiver => {
+                            debug!("Notification watcher stopped: {}", session_id);
+                            return;
+                        }
+                    }
+                    backoff = (backoff * 2).clamp(MIN_RETRY_BACKOFF, MAX_RETRY_BACKOFF);
+                    match rewatch(session_id, next_revision).await {
+                        Ok((new_watcher, new_stream)) => {
+                            watcher = new_watcher;
+                            stream = new_stream;
+                            debug!("Notification watcher resumed from revision {}: {}", next_revision, session_id);
+                            break;
+                        }
+                        Err(LogicError::ConnectionLose) => {
+                            debug!("Notification watcher stopped, session closed: {}", session_id);
+                            return;
+                        }
+                        Err(e) => warn!("Failed to resume notification watcher: {:?}", e),
+                    }
+                }
+            }
+        });
+
+        NotificationWatcher {
+            session_id,
+            stop_notifier: Some(stop_sender),
+        }
+    }
+
+    fn on_event(
+        session_id: i32,
+        key: &str,
+        event_type: WatchEventType,
+        value: Option<&[u8]>,
+        window: &Window,
+    ) {
+        let now = now_timestamp() as u64;
+        let triggered: Vec<NotificationRuleTriggered> = match CONNECTION_NOTIFICATION_RULES.get(&session_id) {
+            Some(rules) => rules
+                .iter()
+                .filter(|rule| rule.is_active(now) && rule.matches(key, event_type, value))
+                .map(|rule| NotificationRuleTriggered {
+                    session: session_id,
+                    rule_id: rule.id.clone(),
+                    rule_name: rule.name.clone(),
+                    key: String::from(key),
+                    event_type,
+                    time: now,
+                })
+                .collect(),
+            None => return,
+        };
+
+        for event in triggered {
+            let title = if event.rule_name.is_empty() {
+                String::from("Watch rule triggered")
+            } else {
+                event.rule_name.clone()
+            };
+            let action = match event_type {
+                WatchEventType::Create => "Created",
+                WatchEventType::Modify => "Modified",
+                WatchEventType::Delete => "Deleted",
+            };
+            let _ = Notification::new("com.beifengtz.etcdworkbench")
+                .title(title)
+                .body(format!("{}: {}", action, key))
+                .show();
+            event_bus::publish(window, EventStream::Watch, "notification_rule_triggered", event);
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(sender) = self.stop_notifier.take() {
+            let _ = sender.send(());
+        }
+        debug!("Stop notification watcher: {}", self.session_id);
+    }
+}
+
+impl Drop for NotificationWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
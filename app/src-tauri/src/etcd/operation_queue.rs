@@ -0,0 +1,98 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::debug;
+use tokio::sync::Notify;
+
+use crate::error::LogicError;
+use crate::transport::maintenance::{MaintenanceOperation, QueuedOperation};
+
+use super::now_timestamp;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+lazy_static! {
+    /// 各会话的运维操作队列，按加入顺序依次执行
+    static ref QUEUES: DashMap<i32, VecDeque<QueuedOperation>> = DashMap::new();
+    /// 任一队列变化时唤醒等待的操作
+    static ref CHANGED: Notify = Notify::new();
+}
+
+/// 执行运维操作的许可，释放时从队列中移除并唤醒下一个操作
+#[derive(Debug)]
+pub struct OperationPermit {
+    session: i32,
+    id: u64,
+}
+
+impl Drop for OperationPermit {
+    fn drop(&mut self) {
+        remove(self.session, self.id);
+    }
+}
+
+fn remove(session: i32, id: u64) {
+    if let Some(mut queue) = QUEUES.get_mut(&session) {
+        queue.retain(|op| op.id != id);
+    }
+    QUEUES.remove_if(&session, |_, queue| queue.is_empty());
+    CHANGED.notify_waiters();
+}
+
+/// 排在前面的操作数量，已不在队列中时返回None
+fn position(session: i32, id: u64) -> Option<usize> {
+    QUEUES.get(&session)?.iter().position(|op| op.id == id)
+}
+
+/// 将操作加入会话的队列并等待前面的操作完成，排队位置变化时调用 `on_wait`。
+/// 返回的许可在操作完成前需要一直持有；等待期间会话断开时返回 `ConnectionLose`
+pub async fn acquire(
+    session: i32,
+    operation: MaintenanceOperation,
+    on_wait: impl Fn(usize),
+) -> Result<OperationPermit, LogicError> {
+    let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+    QUEUES.entry(session).or_default().push_back(QueuedOperation {
+        id,
+        operation,
+        enqueued_at: now_timestamp() as u64,
+    });
+    //  等待中被取消时随许可一起移出队列
+    let permit = OperationPermit { session, id };
+
+    let mut reported = None;
+    loop {
+        let notified = CHANGED.notified();
+        tokio::pin!(notified);
+        //  先登记再检查位置，避免错过检查之后的唤醒
+        notified.as_mut().enable();
+        match position(session, id) {
+            None => return Err(LogicError::ConnectionLose),
+            Some(0) => return Ok(permit),
+            Some(ahead) => {
+                if reported != Some(ahead) {
+                    debug!("{:?} of session {} queued, {} operations ahead", operation, session, ahead);
+                    on_wait(ahead);
+                    reported = Some(ahead);
+                }
+            }
+        }
+        notified.await;
+    }
+}
+
+/// 会话的运维队列，第一个为正在执行的操作
+pub fn list(session: i32) -> Vec<QueuedOperation> {
+    QUEUES
+        .get(&session)
+        .map(|queue| queue.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// 会话断开时清空队列，等待中的操作返回错误
+pub fn remove_session(session: i32) {
+    QUEUES.remove(&session);
+    CHANGED.notify_waiters();
+}
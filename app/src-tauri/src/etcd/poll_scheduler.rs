@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+
+use crate::api::settings::get_settings;
+use crate::transport::settings::{PollTask, SettingConfig};
+
+use super::{get_connection_info_optional, get_session_window};
+
+lazy_static! {
+    /// 窗口是否处于焦点，未记录的窗口视为处于焦点
+    static ref WINDOW_FOCUSED: DashMap<String, bool> = DashMap::new();
+}
+
+pub fn set_window_focused(label: &str, focused: bool) {
+    WINDOW_FOCUSED.insert(String::from(label), focused);
+}
+
+pub fn clear_window(label: &str) {
+    WINDOW_FOCUSED.remove(label);
+}
+
+fn is_session_focused(session: i32) -> bool {
+    get_session_window(&session)
+        .and_then(|label| WINDOW_FOCUSED.get(&label).map(|focused| *focused))
+        .unwrap_or(true)
+}
+
+/// 计算任务的间隔：连接的配置优先于设置，都未配置时使用默认值，窗口失去焦点且开启自适应时放慢
+pub fn resolve_interval(
+    task: PollTask,
+    settings: &SettingConfig,
+    overrides: Option<&BTreeMap<PollTask, u64>>,
+    focused: bool,
+) -> Duration {
+    let seconds = overrides
+        .and_then(|overrides| overrides.get(&task))
+        .or_else(|| settings.poll_interval_seconds.get(&task))
+        .copied()
+        .unwrap_or_else(|| task.default_seconds(settings))
+        .max(task.min_seconds());
+    if settings.adaptive_polling && !focused {
+        Duration::from_secs(seconds * settings.unfocused_poll_factor.max(1) as u64)
+    } else {
+        Duration::from_secs(seconds)
+    }
+}
+
+/// 连接的任务下一次执行前等待的时间，每次执行前重新计算，配置和焦点的变化在下一个周期生效
+pub async fn interval_of(session: i32, task: PollTask) -> Duration {
+    let settings = get_settings().await.unwrap_or_default();
+    let overrides = get_connection_info_optional(&session).map(|info| info.poll_interval_seconds.clone());
+    resolve_interval(task, &settings, overrides.as_ref(), is_session_focused(session))
+}
+
+/// 连接当前生效的所有任务间隔秒数
+pub async fn intervals_of(session: i32) -> BTreeMap<PollTask, u64> {
+    let mut intervals = BTreeMap::new();
+    for task in PollTask::ALL {
+        intervals.insert(task, interval_of(session, task).await.as_secs());
+    }
+    intervals
+}
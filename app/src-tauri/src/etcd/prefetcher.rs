@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use log::debug;
+
+use crate::error::LogicError;
+use crate::transport::kv::{KeyValuePage, PrefetchResult, PrefixKeys, SerializableKeyValue};
+
+use super::key_index::{self, KeyIndex, KeyMeta};
+use super::{get_connector, get_key_index, CONNECTION_PREFETCH_INDEX};
+
+/// 预取结果的有效期，过期后重新从etcd读取
+const PREFETCH_TTL: Duration = Duration::from_secs(30);
+/// 单个前缀最多缓存的key数量，超过时不缓存
+const MAX_PREFIX_KEYS: i64 = 2000;
+/// 单次展开最多预取的子目录数
+const MAX_PREFETCH_DIRS: usize = 32;
+/// 同时进行的预取请求数
+const PREFETCH_PARALLELISM: usize = 4;
+/// 预取索引的key数量上限，超出后清空
+const MAX_INDEX_KEYS: usize = 200_000;
+
+/// 预取的key索引，只包含已完整读取过的前缀，在有效期内可代替etcd查询
+#[derive(Default)]
+pub struct PrefetchIndex {
+    index: KeyIndex,
+    /// 已完整读取的前缀及读取时间
+    covered: HashMap<Vec<u8>, Instant>,
+}
+
+impl PrefetchIndex {
+    /// `prefix` 在有效期内被完整读取过（或位于完整读取过的前缀之下）
+    pub fn is_covered(&self, prefix: &[u8]) -> bool {
+        self.covered
+            .iter()
+            .any(|(p, time)| prefix.starts_with(p) && time.elapsed() < PREFETCH_TTL)
+    }
+
+    pub fn keys_with_prefix(&self, prefix: &[u8]) -> Vec<(Vec<u8>, KeyMeta)> {
+        self.index.keys_with_prefix(prefix, usize::MAX)
+    }
+
+    /// 保存前缀下的全部key，替换之前的结果
+    pub fn store(&mut self, prefix: &[u8], kvs: &[SerializableKeyValue]) {
+        self.covered.retain(|_, time| time.elapsed() < PREFETCH_TTL);
+        if self.covered.is_empty() || self.index.len() + kvs.len() > MAX_INDEX_KEYS {
+            self.index = KeyIndex::new();
+            self.covered.clear();
+        }
+        for (key, _) in self.index.keys_with_prefix(prefix, usize::MAX) {
+            self.index.remove(&key);
+        }
+        for kv in kvs {
+            self.index.insert(kv.key.as_bytes(), KeyMeta {
+                create_revision: kv.create_revision,
+                mod_revision: kv.mod_revision,
+                version: kv.version,
+                lease: kv.lease.parse().unwrap_or(0),
+            });
+        }
+        self.covered.insert(prefix.to_vec(), Instant::now());
+    }
+
+    /// key发生变化，包含它的前缀需要重新读取
+    pub fn invalidate(&mut self, key: &[u8]) {
+        self.covered.retain(|p, _| !key.starts_with(p));
+    }
+
+    /// 前缀下的key被删除，包含该前缀或位于其下的前缀都需要重新读取
+    pub fn invalidate_prefix(&mut self, prefix: &[u8]) {
+        self.covered.retain(|p, _| !prefix.starts_with(p) && !p.starts_with(prefix));
+    }
+}
+
+/// 会话的连接写入key时调用，其他客户端的修改在有效期过后可见
+pub fn invalidate(session: i32, key: &[u8]) {
+    if let Some(mut prefetch) = CONNECTION_PREFETCH_INDEX.get_mut(&session) {
+        prefetch.invalidate(key);
+    }
+}
+
+pub fn invalidate_prefix(session: i32, prefix: &[u8]) {
+    if let Some(mut prefetch) = CONNECTION_PREFETCH_INDEX.get_mut(&session) {
+        prefetch.invalidate_prefix(prefix);
+    }
+}
+
+/// 无法确定哪些key发生变化时清空预取结果
+pub fn clear(session: i32) {
+    CONNECTION_PREFETCH_INDEX.remove(&session);
+}
+
+fn cached_keys(session: i32, prefix: &str) -> Option<Vec<SerializableKeyValue>> {
+    let prefetch = CONNECTION_PREFETCH_INDEX.get(&session)?;
+    if !prefetch.is_covered(prefix.as_bytes()) {
+        return None;
+    }
+    Some(key_index::to_serializable_kvs(prefetch.keys_with_prefix(prefix.as_bytes())))
+}
+
+fn store_page(session: i32, prefix: &str, page: &KeyValuePage) -> bool {
+    if page.more {
+        return false;
+    }
+    CONNECTION_PREFETCH_INDEX
+        .entry(session)
+        .or_default()
+        .store(prefix.as_bytes(), &page.kvs);
+    true
+}
+
+/// 获取前缀下的key列表，优先从完整索引或预取索引返回
+pub async fn list_prefix_keys(session: i32, prefix: String) -> Result<PrefixKeys, LogicError> {
+    if let Some(index) = get_key_index(&session) {
+        let index = index.read().unwrap();
+        return Ok(PrefixKeys {
+            keys: key_index::to_serializable_kvs(index.keys_with_prefix(prefix.as_bytes(), MAX_PREFIX_KEYS as usize)),
+            complete: index.count_prefix(prefix.as_bytes()) <= MAX_PREFIX_KEYS as usize,
+            cached: true,
+        });
+    }
+    if let Some(keys) = cached_keys(session, &prefix) {
+        return Ok(PrefixKeys {
+            keys,
+            complete: true,
+            cached: true,
+        });
+    }
+
+    let page = {
+        let connector = get_connector(&session)?;
+        let history = connector.get_read_revision().is_some();
+        let page = connector
+            .kv_get_keys_with_prefixes(&[prefix.clone()], MAX_PREFIX_KEYS, 1)
+            .await
+            .remove(0)?;
+        //  历史版本读取的结果不缓存
+        if !history {
+            store_page(session, &prefix, &page);
+        }
+        page
+    };
+    Ok(PrefixKeys {
+        complete: !page.more,
+        keys: page.kvs,
+        cached: false,
+    })
+}
+
+/// 展开 `prefix` 时并发预取其下一层各子目录的key列表，之后展开子目录时可直接从内存返回
+pub async fn prefetch_children(session: i32, prefix: String, delimiter: String) -> Result<PrefetchResult, LogicError> {
+    if get_key_index(&session).is_some() {
+        return Ok(PrefetchResult {
+            from_index: true,
+            ..PrefetchResult::default()
+        });
+    }
+    if get_connector(&session)?.get_read_revision().is_some() {
+        return Ok(PrefetchResult::default());
+    }
+
+    let children = list_prefix_keys(session, prefix.clone()).await?;
+    let keys: Vec<Vec<u8>> = children.keys.into_iter().map(|kv| kv.key.into_bytes()).collect();
+    let dirs: Vec<String> = key_index::next_segments_of_keys(&keys, prefix.as_bytes(), delimiter.as_bytes(), usize::MAX)
+        .into_iter()
+        .map(|(path, _)| String::from_utf8_lossy(&path).to_string())
+        .filter(|path| !delimiter.is_empty() && path.ends_with(delimiter.as_str()))
+        .filter(|path| cached_keys(session, path).is_none())
+        .take(MAX_PREFETCH_DIRS)
+        .collect();
+    if dirs.is_empty() {
+        return Ok(PrefetchResult::default());
+    }
+
+    let pages = {
+        let connector = get_connector(&session)?;
+        connector
+            .kv_get_keys_with_prefixes(&dirs, MAX_PREFIX_KEYS, PREFETCH_PARALLELISM)
+            .await
+    };
+    let mut result = PrefetchResult::default();
+    for (dir, page) in dirs.into_iter().zip(pages) {
+        match page {
+            Ok(page) if store_page(session, &dir, &page) => result.prefixes.push(dir),
+            Ok(_) => result.skipped.push(dir),
+            Err(e) => {
+                debug!("Failed to prefetch {}: {e}", dir);
+                result.skipped.push(dir);
+            }
+        }
+    }
+    debug!("Prefetched {} prefixes under {} for {}", result.prefixes.len(), prefix, session);
+    Ok(result)
+}
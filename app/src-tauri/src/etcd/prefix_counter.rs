@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use dashmap::DashMap;
+use etcd_client::EventType;
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use tokio::task::JoinHandle;
+
+use crate::error::LogicError;
+use crate::transport::kv::PrefixKeyCount;
+
+use super::{now_timestamp, wait_connector};
+
+/// 监听中断后重新统计的间隔
+const RESEED_DELAY: Duration = Duration::from_secs(5);
+
+lazy_static! {
+    static ref PREFIX_COUNTS: DashMap<(i32, String), PrefixKeyCount> = DashMap::new();
+    static ref COUNT_TASKS: DashMap<(i32, String), JoinHandle<()>> = DashMap::new();
+}
+
+/// 开始统计前缀下的key数量，已在统计时不做处理
+pub fn track(session: i32, prefix: String) {
+    let key = (session, prefix.clone());
+    if COUNT_TASKS.contains_key(&key) {
+        return;
+    }
+    PREFIX_COUNTS.insert(
+        key.clone(),
+        PrefixKeyCount {
+            session,
+            prefix: prefix.clone(),
+            count: 0,
+            revision: 0,
+            updated_at: 0,
+            live: false,
+        },
+    );
+    COUNT_TASKS.insert(key, tokio::spawn(run(session, prefix)));
+}
+
+pub fn untrack(session: i32, prefix: String) {
+    let key = (session, prefix);
+    if let Some((_, task)) = COUNT_TASKS.remove(&key) {
+        task.abort();
+    }
+    PREFIX_COUNTS.remove(&key);
+}
+
+/// 会话统计中的所有前缀
+pub fn list(session: i32) -> Vec<PrefixKeyCount> {
+    let mut counts: Vec<PrefixKeyCount> = PREFIX_COUNTS
+        .iter()
+        .filter(|e| e.key().0 == session)
+        .map(|e| e.value().clone())
+        .collect();
+    counts.sort_by(|a, b| a.prefix.cmp(&b.prefix));
+    counts
+}
+
+pub fn remove_session(session: i32) {
+    COUNT_TASKS.retain(|key, task| {
+        if key.0 == session {
+            task.abort();
+            false
+        } else {
+            true
+        }
+    });
+    PREFIX_COUNTS.retain(|key, _| key.0 != session);
+}
+
+/// 监听中断（如重连、watch被压缩取消）后等待一段时间重新统计
+async fn run(session: i32, prefix: String) {
+    info!("Prefix key counter started: {}, {}", session, prefix);
+    loop {
+        match follow(session, &prefix).await {
+            Ok(()) => debug!("Prefix key counter watch ended: {}, {}", session, prefix),
+            Err(e) => warn!("Prefix key counter of {}, {} interrupted: {:?}", session, prefix, e),
+        }
+        if let Some(mut count) = PREFIX_COUNTS.get_mut(&(session, prefix.clone())) {
+            count.live = false;
+        }
+        tokio::time::sleep(RESEED_DELAY).await;
+    }
+}
+
+/// 统计一次前缀下的数量，然后从统计的下一个版本开始监听，根据创建和删除事件增减
+async fn follow(session: i32, prefix: &str) -> Result<(), LogicError> {
+    let key = (session, prefix.to_string());
+    let (_watcher, mut stream) = {
+        let mut connector = wait_connector(&session).await?;
+        let (count, revision) = connector.kv_count_prefix(prefix).await?;
+        let watch = connector.kv_watch_prefix_from(prefix, revision + 1).await?;
+
+        let Some(mut entry) = PREFIX_COUNTS.get_mut(&key) else {
+            return Ok(());
+        };
+        entry.count = count;
+        entry.revision = revision;
+        entry.updated_at = now_timestamp() as u64;
+        entry.live = true;
+        watch
+    };
+
+    while let Some(response) = stream.message().await? {
+        if response.canceled() {
+            return Err(LogicError::MsgError(format!("Watch canceled: {}", response.cancel_reason())));
+        }
+        let delta = count_delta(
+            response
+                .events()
+                .iter()
+                .map(|event| (event.event_type(), event.kv().map(|kv| kv.version()).unwrap_or(0))),
+        );
+        let Some(mut entry) = PREFIX_COUNTS.get_mut(&key) else {
+            return Ok(());
+        };
+        entry.count = (entry.count + delta).max(0);
+        if let Some(header) = response.header() {
+            entry.revision = header.revision();
+        }
+        entry.updated_at = now_timestamp() as u64;
+    }
+    Ok(())
+}
+
+/// 根据事件类型和key的版本计算数量变化。版本为1的写入表示新建key，删除事件只会在key存在时产生
+pub fn count_delta(events: impl Iterator<Item = (EventType, i64)>) -> i64 {
+    events
+        .map(|(event_type, version)| match event_type {
+            EventType::Put if version == 1 => 1,
+            EventType::Put => 0,
+            EventType::Delete => -1,
+        })
+        .sum()
+}
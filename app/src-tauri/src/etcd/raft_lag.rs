@@ -0,0 +1,137 @@
+use std::collections::VecDeque;
+
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+
+use crate::error::LogicError;
+use crate::transport::maintenance::{FollowerLag, RaftLagReport, RaftLagSample, SerializableCluster};
+
+use super::{cluster_status, get_connector, now_timestamp};
+
+/// 每个连接保留的采样数
+const MAX_LAG_SAMPLES: usize = 360;
+/// 落后的日志条数超过此值认为该次采样较慢。各成员状态并非同一时刻获取，写入频繁时少量落后属于正常现象
+const SLOW_LAG_ENTRIES: u64 = 500;
+/// 至少有这么多次采样才判断是否持续较慢
+const MIN_SAMPLES_FOR_SLOW: usize = 5;
+/// 较慢的采样占比达到此值认为成员持续较慢
+const SLOW_SAMPLE_RATIO: f64 = 0.8;
+
+lazy_static! {
+    static ref LAG_HISTORY: DashMap<i32, VecDeque<RaftLagSample>> = DashMap::new();
+}
+
+pub fn remove_session(session: i32) {
+    LAG_HISTORY.remove(&session);
+}
+
+/// 根据各成员状态计算follower落后leader的已应用日志条数，无法确定leader时返回空
+pub fn compute_sample(cluster: &SerializableCluster, time: u64) -> Option<RaftLagSample> {
+    let applied_index = |id: &str| {
+        cluster
+            .members
+            .iter()
+            .find(|m| m.id == id)
+            .and_then(|m| m.status.as_ref())
+            .and_then(|s| s.raft_applied_index.parse::<u64>().ok())
+    };
+    let leader = cluster
+        .members
+        .iter()
+        .filter_map(|m| m.status.as_ref())
+        .map(|s| s.leader.clone())
+        .find(|leader| applied_index(leader).is_some())?;
+    let leader_applied_index = applied_index(&leader)?;
+
+    let lags = cluster
+        .members
+        .iter()
+        .filter(|m| m.id != leader)
+        .filter_map(|m| applied_index(&m.id).map(|index| (m.id.clone(), leader_applied_index.saturating_sub(index))))
+        .collect();
+    Some(RaftLagSample {
+        time,
+        leader,
+        leader_applied_index,
+        lags,
+    })
+}
+
+/// 统计follower在历史采样中的平均、最大落后条数，以及是否持续较慢
+pub fn summarize(samples: &[RaftLagSample], id: &str) -> (Option<f64>, u64, bool) {
+    let lags: Vec<u64> = samples.iter().filter_map(|s| s.lags.get(id).copied()).collect();
+    if lags.is_empty() {
+        return (None, 0, false);
+    }
+    let avg = lags.iter().sum::<u64>() as f64 / lags.len() as f64;
+    let max = lags.iter().copied().max().unwrap_or(0);
+    let slow_count = lags.iter().filter(|lag| **lag > SLOW_LAG_ENTRIES).count();
+    let slow = lags.len() >= MIN_SAMPLES_FOR_SLOW && slow_count as f64 >= lags.len() as f64 * SLOW_SAMPLE_RATIO;
+    (Some(avg), max, slow)
+}
+
+/// 采样一次各成员的raft进度并与历史采样一起返回，界面定时调用以积累历史
+pub async fn sample(session: i32) -> Result<RaftLagReport, LogicError> {
+    let mut cluster = {
+        let mut connector = get_connector(&session)?;
+        connector.cluster_get().await?
+    };
+    cluster_status::fill_member_status(session, &mut cluster).await;
+
+    let check_time = now_timestamp() as u64;
+    let current = compute_sample(&cluster, check_time);
+    let samples: Vec<RaftLagSample> = {
+        let mut history = LAG_HISTORY.entry(session).or_default();
+        if let Some(sample) = current.clone() {
+            //  leader切换后各成员的进度不再可比，丢弃之前的采样
+            if matches!(history.back(), Some(last) if last.leader != sample.leader) {
+                history.clear();
+            }
+            history.push_back(sample);
+            while history.len() > MAX_LAG_SAMPLES {
+                history.pop_front();
+            }
+        }
+        history.iter().cloned().collect()
+    };
+
+    let leader = current.as_ref().map(|s| s.leader.clone());
+    let mut warnings = Vec::new();
+    let followers: Vec<FollowerLag> = cluster
+        .members
+        .iter()
+        .filter(|m| leader.as_ref() != Some(&m.id))
+        .map(|m| {
+            let (avg_lag, max_lag, slow) = summarize(&samples, &m.id);
+            if slow {
+                warnings.push(format!(
+                    "Member {} has been lagging behind the leader by about {:.0} entries",
+                    m.name,
+                    avg_lag.unwrap_or_default()
+                ));
+            }
+            FollowerLag {
+                id: m.id.clone(),
+                name: m.name.clone(),
+                applied_index: m.status.as_ref().and_then(|s| s.raft_applied_index.parse().ok()),
+                lag: current.as_ref().and_then(|s| s.lags.get(&m.id).copied()),
+                avg_lag,
+                max_lag,
+                slow,
+                error_msg: m.error_msg.clone(),
+            }
+        })
+        .collect();
+    if leader.is_none() {
+        warnings.push(String::from("Unable to determine the leader"));
+    }
+
+    Ok(RaftLagReport {
+        check_time,
+        leader,
+        leader_applied_index: current.map(|s| s.leader_applied_index),
+        followers,
+        samples,
+        warnings,
+    })
+}
@@ -0,0 +1,204 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Local, NaiveTime, TimeZone};
+use dashmap::DashSet;
+use lazy_static::lazy_static;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use log::{debug, info, warn};
+use serde_json::json;
+use tokio::time::{interval, MissedTickBehavior};
+
+use crate::api::connection::save_connection_info;
+use crate::api::maintenance::collect_cluster_report;
+use crate::error::LogicError;
+use crate::transport::report::{ReportDeliveryResult, ReportWebhook, SmtpConfig};
+use crate::utils::report_util::{self, ReportFormat};
+
+use super::alert_dispatcher::{sign, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use super::{get_connection_info_optional, get_connection_name, list_named_sessions, list_session_windows, now_timestamp};
+
+/// 检查是否到达报告时间的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+lazy_static! {
+    /// 正在生成报告的连接名，同一连接在多个窗口中打开时避免重复执行
+    static ref RUNNING: DashSet<String> = DashSet::new();
+}
+
+/// 今天的报告时间已过且今天尚未生成过报告。应用在报告时间之后才打开时会补发当天的报告
+pub fn is_due(time: &str, last_run: u64, now: DateTime<Local>) -> bool {
+    let Ok(time) = NaiveTime::parse_from_str(time, "%H:%M") else {
+        return false;
+    };
+    let Some(scheduled) = Local.from_local_datetime(&now.date_naive().and_time(time)).earliest() else {
+        return false;
+    };
+    now >= scheduled && (last_run as i64) < scheduled.timestamp_millis()
+}
+
+/// 定时检查所有已打开的连接，到达报告时间时在后台生成并推送报告。
+///
+/// 同一个已保存的连接打开多次时只由其中一个会话生成报告
+pub fn start() {
+    tokio::spawn(async move {
+        let mut timer = interval(CHECK_INTERVAL);
+        timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            timer.tick().await;
+            for (session, _) in list_session_windows() {
+                let Some(name) = get_connection_name(&session) else {
+                    continue;
+                };
+                //  只检查同名连接中最早打开的会话
+                if list_named_sessions(&name).first() != Some(&session) {
+                    continue;
+                }
+                let due = get_connection_info_optional(&session)
+                    .and_then(|info| info.report_schedule.clone())
+                    .map(|s| s.enabled && is_due(&s.time, s.last_run, Local::now()))
+                    .unwrap_or(false);
+                if !due || !RUNNING.insert(name.clone()) {
+                    continue;
+                }
+                tokio::spawn(async move {
+                    match run(session).await {
+                        Ok(result) if result.errors.is_empty() => info!("Scheduled report of {} delivered", name),
+                        Ok(result) => warn!("Scheduled report of {} partially failed: {:?}", name, result.errors),
+                        Err(e) => warn!("Failed to generate scheduled report of {}: {:?}", name, e),
+                    }
+                    RUNNING.remove(&name);
+                });
+            }
+        }
+    });
+}
+
+/// 立即生成报告并推送到连接配置的所有渠道，无论成功与否都会更新上次执行时间
+pub async fn run(session: i32) -> Result<ReportDeliveryResult, LogicError> {
+    let schedule = get_connection_info_optional(&session)
+        .and_then(|info| info.report_schedule.clone())
+        .ok_or(LogicError::IllegalArgument(String::from("Report schedule is not configured")))?;
+    let connection = get_connection_name(&session).unwrap_or_else(|| session.to_string());
+
+    let report = collect_cluster_report(session).await;
+    mark_run(session).await?;
+    let report = report?;
+
+    let format = ReportFormat::parse(Some(&schedule.format), "");
+    let content = report_util::render(&report, format);
+    let subject = format!(
+        "[Etcd Workbench] Cluster report of {} ({})",
+        connection,
+        Local::now().format("%Y-%m-%d")
+    );
+    let mut result = ReportDeliveryResult {
+        generate_time: report.generate_time,
+        ..ReportDeliveryResult::default()
+    };
+
+    if let Some(webhook) = &schedule.webhook {
+        match send_webhook(webhook, &connection, &schedule.format, report.generate_time, &content).await {
+            Ok(()) => result.delivered.push(String::from("webhook")),
+            Err(e) => result.errors.push(format!("webhook: {e}")),
+        }
+    }
+    if let Some(smtp) = &schedule.smtp {
+        match send_mail(smtp, subject, content, format == ReportFormat::Html).await {
+            Ok(()) => result.delivered.push(String::from("smtp")),
+            Err(e) => result.errors.push(format!("smtp: {e}")),
+        }
+    }
+    debug!("Report of {} delivered to {:?}", session, result.delivered);
+    Ok(result)
+}
+
+/// 更新上次执行时间，同一连接的其他会话也同步更新，避免各自重复生成
+async fn mark_run(session: i32) -> Result<(), LogicError> {
+    let Some(name) = get_connection_name(&session) else {
+        return Ok(());
+    };
+    let now = now_timestamp() as u64;
+    for id in list_named_sessions(&name) {
+        if let Some(mut info) = get_connection_info_optional(&id) {
+            if let Some(schedule) = info.report_schedule.as_mut() {
+                schedule.last_run = now;
+            }
+        }
+    }
+    let info = get_connection_info_optional(&session).map(|info| info.value().clone());
+    if let Some(info) = info {
+        save_connection_info(info).await?;
+    }
+    Ok(())
+}
+
+async fn send_webhook(
+    webhook: &ReportWebhook,
+    connection: &str,
+    format: &str,
+    generate_time: u64,
+    content: &str,
+) -> Result<(), String> {
+    let body = json!({
+        "connection": connection,
+        "generateTime": generate_time,
+        "format": format,
+        "content": content,
+    })
+    .to_string();
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let mut request = client
+        .post(&webhook.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json");
+    if let Some(secret) = webhook.secret.as_ref().filter(|s| !s.is_empty()) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+            .to_string();
+        request = request
+            .header(TIMESTAMP_HEADER, &timestamp)
+            .header(SIGNATURE_HEADER, format!("sha256={}", sign(secret, &timestamp, &body)));
+    }
+
+    let response = request.body(body).send().await.map_err(|e| e.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("Webhook responded with status {}", response.status()))
+    }
+}
+
+async fn send_mail(smtp: &SmtpConfig, subject: String, content: String, html: bool) -> Result<(), String> {
+    let from: Mailbox = smtp.from.parse().map_err(|e| format!("Invalid sender {}: {e}", smtp.from))?;
+    let mut builder = Message::builder().from(from).subject(subject);
+    for to in &smtp.to {
+        let to: Mailbox = to.parse().map_err(|e| format!("Invalid recipient {}: {e}", to))?;
+        builder = builder.to(to);
+    }
+    let content_type = if html { ContentType::TEXT_HTML } else { ContentType::TEXT_PLAIN };
+    let message = builder.header(content_type).body(content).map_err(|e| e.to_string())?;
+
+    let builder = match smtp.security.as_str() {
+        "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.host).map_err(|e| e.to_string())?,
+        "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.host),
+        _ => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host).map_err(|e| e.to_string())?,
+    };
+    let mut builder = builder.port(smtp.port).timeout(Some(REQUEST_TIMEOUT));
+    if let Some(username) = smtp.username.as_ref().filter(|u| !u.is_empty()) {
+        builder = builder.credentials(Credentials::new(
+            username.clone(),
+            smtp.password.clone().unwrap_or_default(),
+        ));
+    }
+    builder.build().send(message).await.map_err(|e| e.to_string())?;
+    Ok(())
+}
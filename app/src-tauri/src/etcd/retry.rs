@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use rand::Rng;
+
+/// 单次重试等待时间的上限
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// 幂等请求遇到短暂的 gRPC 故障（如 leader 选举期间）时的重试策略，
+/// 等待时间按指数增长并加入随机抖动，避免多个请求同时重试
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// 最大重试次数，为0时不重试
+    pub max_retries: u32,
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            base_delay: Duration::from_millis(200),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32, base_delay_millis: u64) -> Self {
+        RetryPolicy {
+            max_retries,
+            base_delay: Duration::from_millis(base_delay_millis),
+        }
+    }
+
+    /// 仅 Unavailable 和 DeadlineExceeded 视为短暂故障
+    pub fn is_transient(e: &etcd_client::Error) -> bool {
+        match e {
+            //  14: Unavailable, 4: DeadlineExceeded
+            etcd_client::Error::GRpcStatus(status) => matches!(status.code() as i32, 14 | 4),
+            etcd_client::Error::TransportError(_) => true,
+            _ => false,
+        }
+    }
+
+    /// 第 `attempt` 次失败后是否重试，返回需要等待的时间（full jitter）
+    pub fn next_delay(&self, e: &etcd_client::Error, attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_retries || !Self::is_transient(e) {
+            return None;
+        }
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(MAX_RETRY_DELAY);
+        let millis = ceiling.as_millis() as u64;
+        Some(Duration::from_millis(rand::thread_rng().gen_range(0..=millis)))
+    }
+}
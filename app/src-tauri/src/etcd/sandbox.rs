@@ -0,0 +1,377 @@
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use flate2::read::GzDecoder;
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use sha2::{Digest, Sha256};
+use tokio::process::{Child, Command};
+use uuid::Uuid;
+use tokio::sync::Mutex;
+
+use crate::error::LogicError;
+use crate::transport::sandbox::{SandboxOptions, SandboxStatus};
+use crate::utils::file_util;
+
+/// 未指定版本时自动下载的etcd版本
+const DEFAULT_ETCD_VERSION: &'static str = "3.5.17";
+const DOWNLOAD_BASE_URL: &'static str = "https://github.com/etcd-io/etcd/releases/download";
+/// 默认端口避开2379和2380，防止与本机已有的etcd冲突
+const DEFAULT_CLIENT_PORT: u16 = 23790;
+const DEFAULT_PEER_PORT: u16 = 23800;
+const SANDBOX_NAME: &'static str = "workbench-sandbox";
+/// 等待沙箱就绪的最长时间
+const READY_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[cfg(target_os = "windows")]
+const BINARY_NAME: &'static str = "etcd.exe";
+#[cfg(not(target_os = "windows"))]
+const BINARY_NAME: &'static str = "etcd";
+
+lazy_static! {
+    static ref SANDBOX: Mutex<Option<Sandbox>> = Mutex::new(None);
+}
+
+/// 由应用管理的本地单节点etcd集群，仅监听本机地址
+struct Sandbox {
+    child: Child,
+    endpoint: String,
+    version: Option<String>,
+    binary_path: PathBuf,
+}
+
+fn data_dir() -> PathBuf {
+    let mut path = file_util::get_sandbox_dir_path();
+    path.push("data");
+    path
+}
+
+fn bin_dir() -> PathBuf {
+    let mut path = file_util::get_sandbox_dir_path();
+    path.push("bin");
+    path
+}
+
+fn log_path() -> PathBuf {
+    let mut path = file_util::get_sandbox_dir_path();
+    path.push("etcd.log");
+    path
+}
+
+/// 校验用户输入的版本号为 `主版本.次版本.修订号[-预发布]` 格式，去除前缀 `v`
+pub fn validate_version(version: &str) -> Result<&str, LogicError> {
+    let version = version.trim().trim_start_matches('v');
+    let (core, pre) = match version.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (version, None),
+    };
+    let core_valid = core.split('.').count() == 3
+        && core.split('.').all(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+    let pre_valid = pre.map_or(true, |pre| {
+        pre.split('.').all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_alphanumeric()))
+    });
+    if core_valid && pre_valid {
+        Ok(version)
+    } else {
+        Err(LogicError::IllegalArgument(format!("Invalid etcd version: {version}")))
+    }
+}
+
+/// 当前平台的etcd发布包名称，Linux为tar.gz，其余平台为zip
+fn release_package(version: &str) -> Result<String, LogicError> {
+    let os = match std::env::consts::OS {
+        "linux" => "linux",
+        "macos" => "darwin",
+        "windows" => "windows",
+        other => return Err(LogicError::MsgError(format!("Unsupported platform: {other}"))),
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        other => return Err(LogicError::MsgError(format!("Unsupported architecture: {other}"))),
+    };
+    let ext = if os == "linux" { "tar.gz" } else { "zip" };
+    Ok(format!("etcd-v{version}-{os}-{arch}.{ext}"))
+}
+
+/// 从发布版本的 `SHA256SUMS` 中找到发布包的校验值
+pub fn find_checksum(sums: &str, package: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let sum = parts.next()?;
+        let name = parts.next()?.trim_start_matches('*');
+        (name == package).then(|| sum.to_ascii_lowercase())
+    })
+}
+
+async fn download(url: &str) -> Result<Vec<u8>, LogicError> {
+    let response = reqwest::get(url)
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| LogicError::MsgError(format!("Failed to download {url}: {e}")))?;
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| LogicError::MsgError(format!("Failed to download {url}: {e}")))?;
+    Ok(bytes.to_vec())
+}
+
+/// 从发布包中取出etcd程序写入 `target`
+fn extract_binary(package: &[u8], is_zip: bool, target: &Path) -> io::Result<()> {
+    let mut content = Vec::new();
+    if is_zip {
+        let mut archive = zip::ZipArchive::new(Cursor::new(package))?;
+        let name = archive
+            .file_names()
+            .find(|name| name.rsplit('/').next() == Some(BINARY_NAME))
+            .map(String::from)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "etcd binary not found in package"))?;
+        archive.by_name(&name)?.read_to_end(&mut content)?;
+    } else {
+        let mut archive = tar::Archive::new(GzDecoder::new(package));
+        let mut found = false;
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if entry.path()?.file_name().and_then(|n| n.to_str()) == Some(BINARY_NAME) {
+                entry.read_to_end(&mut content)?;
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "etcd binary not found in package"));
+        }
+    }
+
+    fs::create_dir_all(target.parent().unwrap())?;
+    fs::write(target, content)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(target, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
+
+/// 将发布包解压到临时目录后整体改名为版本目录，其他下载已完成时使用已有的版本目录
+fn install_binary(package: &[u8], is_zip: bool, version_dir: &Path) -> io::Result<()> {
+    let temp_dir = bin_dir().join(format!(".{}", Uuid::new_v4()));
+    let result = extract_binary(package, is_zip, &temp_dir.join(BINARY_NAME))
+        .and_then(|_| fs::rename(&temp_dir, version_dir));
+    if temp_dir.exists() {
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+    match result {
+        Err(_) if version_dir.join(BINARY_NAME).is_file() => Ok(()),
+        other => other,
+    }
+}
+
+/// 下载指定版本的etcd到沙箱目录并校验发布包的SHA-256，已下载时直接返回
+async fn download_binary(version: &str) -> Result<PathBuf, LogicError> {
+    let version = validate_version(version)?;
+    let version_dir = bin_dir().join(version);
+    let target = version_dir.join(BINARY_NAME);
+    if target.is_file() {
+        return Ok(target);
+    }
+
+    let package = release_package(version)?;
+    let sums = download(&format!("{DOWNLOAD_BASE_URL}/v{version}/SHA256SUMS")).await?;
+    let expected = find_checksum(&String::from_utf8_lossy(&sums), &package)
+        .ok_or_else(|| LogicError::MsgError(format!("No checksum published for {package}")))?;
+
+    let url = format!("{DOWNLOAD_BASE_URL}/v{version}/{package}");
+    info!("Downloading etcd sandbox binary: {url}");
+    let bytes = download(&url).await?;
+    let actual = Sha256::digest(&bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    if actual != expected {
+        return Err(LogicError::MsgError(format!(
+            "Checksum mismatch for {package}: expected {expected}, got {actual}"
+        )));
+    }
+
+    let is_zip = package.ends_with(".zip");
+    tokio::task::spawn_blocking(move || install_binary(&bytes, is_zip, &version_dir))
+        .await
+        .map_err(|e| LogicError::MsgError(e.to_string()))??;
+    Ok(target)
+}
+
+/// 执行 `etcd --version` 读取版本号
+async fn binary_version(binary: &Path) -> Option<String> {
+    let output = Command::new(binary).arg("--version").output().await.ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("etcd Version:"))
+        .map(|v| String::from(v.trim()))
+}
+
+async fn resolve_binary(options: &SandboxOptions) -> Result<PathBuf, LogicError> {
+    match &options.binary_path {
+        Some(path) if !path.is_empty() => {
+            let path = PathBuf::from(path);
+            if !path.is_file() {
+                return Err(LogicError::IllegalArgument(format!(
+                    "Etcd binary does not exist: {}",
+                    path.display()
+                )));
+            }
+            Ok(path)
+        }
+        _ => download_binary(options.version.as_deref().unwrap_or(DEFAULT_ETCD_VERSION)).await,
+    }
+}
+
+/// 日志文件末尾的内容，用于启动失败时展示原因
+fn log_tail() -> String {
+    let content = fs::read_to_string(log_path()).unwrap_or_default();
+    let lines: Vec<&str> = content.lines().collect();
+    lines[lines.len().saturating_sub(5)..].join("\n")
+}
+
+/// 等待沙箱可以响应请求，进程提前退出时返回错误
+async fn wait_ready(child: &mut Child, endpoint: &str) -> Result<(), LogicError> {
+    let deadline = tokio::time::Instant::now() + READY_TIMEOUT;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Err(LogicError::MsgError(format!(
+                "Etcd sandbox exited with {status}: {}",
+                log_tail()
+            )));
+        }
+        if let Ok(mut client) = etcd_client::Client::connect([endpoint], None).await {
+            if client.status().await.is_ok() {
+                return Ok(());
+            }
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(LogicError::MsgError(String::from("Timed out waiting for etcd sandbox to start")));
+        }
+        tokio::time::sleep(Duration::from_millis(300)).await;
+    }
+}
+
+fn to_status(sandbox: Option<&mut Sandbox>) -> SandboxStatus {
+    let mut status = SandboxStatus {
+        running: false,
+        pid: None,
+        endpoint: format!("127.0.0.1:{DEFAULT_CLIENT_PORT}"),
+        version: None,
+        binary_path: None,
+        data_dir: data_dir().display().to_string(),
+        log_path: log_path().display().to_string(),
+    };
+    if let Some(sandbox) = sandbox {
+        status.running = matches!(sandbox.child.try_wait(), Ok(None));
+        status.pid = sandbox.child.id();
+        status.endpoint = sandbox.endpoint.clone();
+        status.version = sandbox.version.clone();
+        status.binary_path = Some(sandbox.binary_path.display().to_string());
+    }
+    status
+}
+
+/// 运行中的沙箱状态，未运行时返回空
+async fn running_status() -> Option<SandboxStatus> {
+    let mut sandbox = SANDBOX.lock().await;
+    let running = sandbox.as_mut()?;
+    matches!(running.child.try_wait(), Ok(None)).then(|| to_status(Some(running)))
+}
+
+/// 启动沙箱集群，已在运行时直接返回当前状态
+pub async fn start(options: SandboxOptions) -> Result<SandboxStatus, LogicError> {
+    if let Some(status) = running_status().await {
+        return Ok(status);
+    }
+
+    //  下载期间不持有锁，避免阻塞状态查询和停止
+    let binary = resolve_binary(&options).await?;
+    let version = binary_version(&binary).await;
+
+    let mut sandbox = SANDBOX.lock().await;
+    if let Some(running) = sandbox.as_mut() {
+        if matches!(running.child.try_wait(), Ok(None)) {
+            return Ok(to_status(Some(running)));
+        }
+    }
+    *sandbox = None;
+    let client_port = options.client_port.unwrap_or(DEFAULT_CLIENT_PORT);
+    let peer_port = options.peer_port.unwrap_or(DEFAULT_PEER_PORT);
+    let client_url = format!("http://127.0.0.1:{client_port}");
+    let peer_url = format!("http://127.0.0.1:{peer_port}");
+
+    let data_dir = data_dir();
+    fs::create_dir_all(&data_dir)?;
+    let log = File::create(log_path())?;
+
+    info!("Starting etcd sandbox: {} {:?}", binary.display(), version);
+    let mut child = Command::new(&binary)
+        .arg("--name").arg(SANDBOX_NAME)
+        .arg("--data-dir").arg(&data_dir)
+        .arg("--listen-client-urls").arg(&client_url)
+        .arg("--advertise-client-urls").arg(&client_url)
+        .arg("--listen-peer-urls").arg(&peer_url)
+        .arg("--initial-advertise-peer-urls").arg(&peer_url)
+        .arg("--initial-cluster").arg(format!("{SANDBOX_NAME}={peer_url}"))
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let endpoint = format!("127.0.0.1:{client_port}");
+    if let Err(e) = wait_ready(&mut child, &endpoint).await {
+        let _ = child.kill().await;
+        return Err(e);
+    }
+    info!("Etcd sandbox is ready: {endpoint}");
+
+    let sandbox = sandbox.insert(Sandbox {
+        child,
+        endpoint,
+        version,
+        binary_path: binary,
+    });
+    Ok(to_status(Some(sandbox)))
+}
+
+pub async fn stop() -> Result<(), LogicError> {
+    if let Some(mut sandbox) = SANDBOX.lock().await.take() {
+        debug!("Stopping etcd sandbox");
+        sandbox.child.kill().await?;
+    }
+    Ok(())
+}
+
+pub async fn status() -> SandboxStatus {
+    let mut sandbox = SANDBOX.lock().await;
+    to_status(sandbox.as_mut())
+}
+
+/// 清空沙箱数据，需先停止运行
+pub async fn reset() -> Result<(), LogicError> {
+    stop().await?;
+    let data_dir = data_dir();
+    if data_dir.exists() {
+        fs::remove_dir_all(&data_dir)?;
+    }
+    Ok(())
+}
+
+/// 应用退出时结束沙箱进程
+pub fn shutdown() {
+    if let Ok(mut sandbox) = SANDBOX.try_lock() {
+        if let Some(sandbox) = sandbox.as_mut() {
+            if let Err(e) = sandbox.child.start_kill() {
+                warn!("Failed to stop etcd sandbox: {e}");
+            }
+        }
+    }
+}
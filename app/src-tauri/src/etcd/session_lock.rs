@@ -0,0 +1,301 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use dashmap::try_result::TryResult;
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use tauri::{AppHandle, Manager};
+use tokio::sync::{Mutex, Notify};
+use tokio::time::{interval, MissedTickBehavior};
+use zeroize::Zeroizing;
+
+use crate::api::settings::get_settings;
+use crate::error::LogicError;
+use crate::transport::connection::Connection;
+use crate::utils::aes_util;
+
+use super::{now_timestamp, CONNECTION_CONFIG, CONNECTION_POOL};
+
+/// 检查是否超时未操作的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+/// 主密码哈希的格式标识
+const HASH_SCHEME: &str = "pbkdf2-sha256";
+/// 等待使用中的连接释放以清除其凭据的间隔
+const BUSY_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+pub const MIN_PASSWORD_LENGTH: usize = 6;
+
+static LOCKED: AtomicBool = AtomicBool::new(false);
+/// 每次锁定、解锁时递增，等待连接释放的任务在状态变化后放弃
+static LOCK_EPOCH: AtomicU64 = AtomicU64::new(0);
+/// 最后一次用户操作的时间戳（毫秒）
+static LAST_ACTIVITY: AtomicU64 = AtomicU64::new(0);
+
+/// 锁定期间加密保存的会话连接配置
+struct SealedSecrets {
+    /// 派生密钥使用的盐
+    salt: Vec<u8>,
+    data: Vec<u8>,
+}
+
+/// 由主密码派生的加密密钥，只保存在内存中
+struct SealKey {
+    salt: Vec<u8>,
+    key: Zeroizing<Vec<u8>>,
+}
+
+lazy_static! {
+    static ref SEALED: Mutex<Option<SealedSecrets>> = Mutex::new(None);
+    /// 输入主密码（设置或解锁）后派生，下次锁定时使用后清除
+    static ref SEAL_KEY: std::sync::Mutex<Option<SealKey>> = std::sync::Mutex::new(None);
+    /// 解锁时通知等待中的后台任务
+    static ref UNLOCKED: Notify = Notify::new();
+}
+
+/// 生成主密码的加盐哈希：`pbkdf2-sha256$<salt>$<hash>`
+pub fn hash_master_password(password: &str) -> String {
+    let salt = aes_util::generate_key_256();
+    let hash = aes_util::derive_key_256(password, &salt);
+    format!("{}${}${}", HASH_SCHEME, BASE64_STANDARD.encode(salt), BASE64_STANDARD.encode(hash))
+}
+
+pub fn verify_master_password(password: &str, stored: &str) -> bool {
+    let mut parts = stored.split('$');
+    let (Some(HASH_SCHEME), Some(salt), Some(hash), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+        warn!("Unrecognized master password hash");
+        return false;
+    };
+    let (Ok(salt), Ok(hash)) = (BASE64_STANDARD.decode(salt), BASE64_STANDARD.decode(hash)) else {
+        return false;
+    };
+    let derived = aes_util::derive_key_256(password, &salt);
+    //  按固定时间比较，避免通过耗时推测哈希
+    derived.len() == hash.len() && derived.iter().zip(hash.iter()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+pub fn is_locked() -> bool {
+    LOCKED.load(Ordering::SeqCst)
+}
+
+/// 需要凭据的操作在执行前调用，会话锁定时返回错误
+pub fn ensure_unlocked() -> Result<(), LogicError> {
+    if is_locked() {
+        Err(LogicError::SessionLocked)
+    } else {
+        Ok(())
+    }
+}
+
+/// 后台任务在使用连接前调用，会话锁定时等待解锁
+pub async fn wait_unlocked() {
+    loop {
+        let unlocked = UNLOCKED.notified();
+        if !is_locked() {
+            return;
+        }
+        unlocked.await;
+    }
+}
+
+pub fn touch() {
+    if !is_locked() {
+        LAST_ACTIVITY.store(now_timestamp() as u64, Ordering::SeqCst);
+    }
+}
+
+/// 输入主密码后调用，派生下次锁定时加密连接配置的密钥
+pub fn prepare_seal_key(password: &str) {
+    let salt = aes_util::generate_key_256();
+    let key = Zeroizing::new(aes_util::derive_key_256(password, &salt));
+    *SEAL_KEY.lock().unwrap() = Some(SealKey { salt, key });
+}
+
+/// 取消主密码时清除派生的密钥
+pub fn clear_seal_key() {
+    SEAL_KEY.lock().unwrap().take();
+}
+
+/// 使用由主密码派生的密钥加密连接配置，密钥只使用一次
+fn seal(connections: &[(i32, Connection)]) -> Result<SealedSecrets, LogicError> {
+    let SealKey { salt, key } = SEAL_KEY
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| LogicError::MsgError(String::from("The master password has not been entered")))?;
+    let json = Zeroizing::new(serde_json::to_vec(connections)?);
+    let data = aes_util::encrypt_gcm_256(&key, &json)?;
+    Ok(SealedSecrets { salt, data })
+}
+
+/// 由解锁时输入的主密码重新派生密钥解密连接配置
+fn unseal(password: &str, sealed: &SealedSecrets) -> Result<Vec<(i32, Connection)>, LogicError> {
+    let key = Zeroizing::new(aes_util::derive_key_256(password, &sealed.salt));
+    let json = Zeroizing::new(aes_util::decrypt_gcm_256(&key, &sealed.data)?);
+    Ok(serde_json::from_slice(&json)?)
+}
+
+/// 加密保存会话的连接配置，然后清除内存中的密码、私钥、值加密密钥以及连接使用的认证凭据。
+/// 无法加密保存时直接清除，相关会话需要重新连接
+async fn seal_secrets() {
+    let connections: Vec<(i32, Connection)> = CONNECTION_CONFIG
+        .iter()
+        .map(|entry| (*entry.key(), entry.value().clone()))
+        .collect();
+
+    if !connections.is_empty() {
+        match seal(&connections) {
+            Ok(sealed) => *SEALED.lock().await = Some(sealed),
+            Err(e) => warn!("Failed to seal session secrets, they will be discarded: {:?}", e),
+        }
+    }
+
+    for mut entry in CONNECTION_CONFIG.iter_mut() {
+        entry.value_mut().clear_secrets();
+    }
+    //  锁定后新连接需要重新认证
+    crate::ssh::session_pool::clear();
+    let ids: Vec<i32> = CONNECTION_POOL.iter().map(|entry| *entry.key()).collect();
+    let epoch = LOCK_EPOCH.load(Ordering::SeqCst);
+    for id in ids {
+        match CONNECTION_POOL.try_get_mut(&id).try_unwrap() {
+            Some(mut connector) => connector.clear_credentials(),
+            //  锁定后无法再获取连接，等待正在执行的操作结束后清除
+            None => {
+                debug!("Connector {} is busy, clear its credentials after it is released", id);
+                tokio::spawn(async move {
+                    while LOCK_EPOCH.load(Ordering::SeqCst) == epoch {
+                        match CONNECTION_POOL.try_get_mut(&id) {
+                            TryResult::Present(mut connector) => {
+                                connector.clear_credentials();
+                                return;
+                            }
+                            TryResult::Absent => return,
+                            TryResult::Locked => tokio::time::sleep(BUSY_RETRY_INTERVAL).await,
+                        }
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// 恢复锁定前的连接配置并重新认证，锁定期间已断开的会话忽略
+async fn restore_secrets(password: &str) {
+    let Some(sealed) = SEALED.lock().await.take() else {
+        return;
+    };
+    let connections = match unseal(password, &sealed) {
+        Ok(connections) => connections,
+        Err(e) => {
+            warn!("Failed to restore session secrets, sessions need to reconnect: {:?}", e);
+            return;
+        }
+    };
+    let epoch = LOCK_EPOCH.load(Ordering::SeqCst);
+    for (id, connection) in connections {
+        let user = connection.user.clone();
+        match CONNECTION_CONFIG.get_mut(&id) {
+            Some(mut entry) => *entry = connection,
+            None => continue,
+        }
+        //  锁定时仍在使用的连接可能还未清除凭据，等待其释放后再恢复
+        tokio::spawn(async move {
+            while LOCK_EPOCH.load(Ordering::SeqCst) == epoch {
+                match CONNECTION_POOL.try_get_mut(&id) {
+                    TryResult::Present(mut connector) => {
+                        if let Err(e) = connector.restore_credentials(user).await {
+                            warn!("Failed to re-authenticate session {} after unlock: {}", id, e);
+                        }
+                        return;
+                    }
+                    TryResult::Absent => return,
+                    TryResult::Locked => tokio::time::sleep(BUSY_RETRY_INTERVAL).await,
+                }
+            }
+        });
+    }
+}
+
+/// 锁定会话，未设置主密码时无法锁定
+pub async fn lock(app: &AppHandle) -> Result<(), LogicError> {
+    if get_settings().await?.master_password_hash.is_none() {
+        return Err(LogicError::IllegalArgument(String::from("Master password is not set")));
+    }
+    if LOCKED.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+    LOCK_EPOCH.fetch_add(1, Ordering::SeqCst);
+    seal_secrets().await;
+    info!("Session locked");
+    if let Err(e) = app.emit_all("session_locked", ()) {
+        warn!("Failed to emit session locked event: {e}");
+    }
+    Ok(())
+}
+
+pub async fn unlock(app: &AppHandle, password: &str) -> Result<(), LogicError> {
+    if !is_locked() {
+        return Ok(());
+    }
+    let hash = get_settings()
+        .await?
+        .master_password_hash
+        .ok_or_else(|| LogicError::IllegalArgument(String::from("Master password is not set")))?;
+    if !verify_master_password(password, &hash) {
+        return Err(LogicError::IllegalArgument(String::from("Incorrect master password")));
+    }
+    LOCK_EPOCH.fetch_add(1, Ordering::SeqCst);
+    restore_secrets(password).await;
+    prepare_seal_key(password);
+    LOCKED.store(false, Ordering::SeqCst);
+    UNLOCKED.notify_waiters();
+    touch();
+    info!("Session unlocked");
+    if let Err(e) = app.emit_all("session_unlocked", ()) {
+        warn!("Failed to emit session unlocked event: {e}");
+    }
+    Ok(())
+}
+
+/// 定时检查，超过设置的分钟数未操作时锁定会话。
+/// 设置了主密码时启动后先锁定，输入主密码后才能派生锁定时加密连接配置的密钥
+pub fn start(app: AppHandle) {
+    touch();
+    tokio::spawn(async move {
+        match get_settings().await {
+            Ok(settings) if settings.master_password_hash.is_some() => {
+                if let Err(e) = lock(&app).await {
+                    warn!("Failed to lock session on startup: {:?}", e);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to read settings for session lock: {:?}", e),
+        }
+        let mut timer = interval(CHECK_INTERVAL);
+        timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            timer.tick().await;
+            if is_locked() {
+                continue;
+            }
+            let settings = match get_settings().await {
+                Ok(settings) => settings,
+                Err(e) => {
+                    warn!("Failed to read settings for session lock: {:?}", e);
+                    continue;
+                }
+            };
+            if settings.lock_idle_minutes == 0 || settings.master_password_hash.is_none() {
+                continue;
+            }
+            let idle = (now_timestamp() as u64).saturating_sub(LAST_ACTIVITY.load(Ordering::SeqCst));
+            if idle >= settings.lock_idle_minutes as u64 * 60_000 {
+                debug!("No activity for {} seconds, locking session", idle / 1000);
+                if let Err(e) = lock(&app).await {
+                    warn!("Failed to lock session: {:?}", e);
+                }
+            }
+        }
+    });
+}
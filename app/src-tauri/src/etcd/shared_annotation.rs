@@ -0,0 +1,203 @@
+use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+use base64::Engine;
+use etcd_client::GetOptions;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::error::LogicError;
+use crate::transport::connection::{KeyAnnotation, SharedAnnotation, SharedAnnotationWriteResult, SharedAnnotations};
+use crate::transport::settings::SettingConfig;
+
+use super::edit_lock;
+use super::etcd_connector::EtcdConnector;
+
+/// 当前写入的数据格式版本，记录在每条数据的 `schema` 字段中，读取时跳过更高版本的记录
+pub const SCHEMA_VERSION: u32 = 1;
+/// 并发修改收藏夹发生冲突时的最大重试次数
+const MAX_BOOKMARK_RETRIES: usize = 5;
+
+/// 单条注释在etcd中的存储格式
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AnnotationDoc {
+    schema: u32,
+    author: String,
+    annotation: KeyAnnotation,
+}
+
+/// 共享收藏夹在etcd中的存储格式
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct BookmarkDoc {
+    schema: u32,
+    keys: Vec<String>,
+}
+
+/// 未开启共享注释时返回错误，否则返回以 `/` 结尾的前缀
+pub fn root(settings: &SettingConfig) -> Result<String, LogicError> {
+    if !settings.shared_annotations {
+        return Err(LogicError::IllegalArgument(String::from("Shared annotations are not enabled")));
+    }
+    let mut root = settings.shared_annotation_prefix.clone();
+    if !root.ends_with('/') {
+        root.push('/');
+    }
+    Ok(root)
+}
+
+fn annotations_prefix(root: &str) -> String {
+    format!("{}annotations/", root)
+}
+
+/// 注释的存储key，key经过base64编码，作用于前缀的注释追加 `.prefix` 后缀
+pub fn annotation_key(root: &str, key: &str, prefix: bool) -> String {
+    let encoded = BASE64_URL_SAFE_NO_PAD.encode(key);
+    if prefix {
+        format!("{}{}.prefix", annotations_prefix(root), encoded)
+    } else {
+        format!("{}{}", annotations_prefix(root), encoded)
+    }
+}
+
+fn bookmarks_key(root: &str) -> String {
+    format!("{}bookmarks", root)
+}
+
+fn parse_annotation(value: &[u8], mod_revision: i64) -> Result<Option<SharedAnnotation>, serde_json::Error> {
+    let doc = serde_json::from_slice::<AnnotationDoc>(value)?;
+    if doc.schema > SCHEMA_VERSION {
+        return Ok(None);
+    }
+    Ok(Some(SharedAnnotation {
+        annotation: doc.annotation,
+        author: doc.author,
+        mod_revision,
+    }))
+}
+
+async fn get_annotation(connector: &mut EtcdConnector, storage_key: &str) -> Result<Option<SharedAnnotation>, LogicError> {
+    let response = connector.kv_get_request(storage_key, None).await?;
+    match response.kvs().first() {
+        Some(kv) => Ok(parse_annotation(kv.value(), kv.mod_revision())?),
+        None => Ok(None),
+    }
+}
+
+async fn get_bookmarks(connector: &mut EtcdConnector, root: &str) -> Result<(BookmarkDoc, i64), LogicError> {
+    let response = connector.kv_get_request(bookmarks_key(root), None).await?;
+    match response.kvs().first() {
+        Some(kv) => Ok((serde_json::from_slice(kv.value())?, kv.mod_revision())),
+        None => Ok((BookmarkDoc::default(), 0)),
+    }
+}
+
+/// 读取所有共享注释及收藏夹
+pub async fn list(connector: &mut EtcdConnector, root: &str) -> Result<SharedAnnotations, LogicError> {
+    let mut result = SharedAnnotations::default();
+    let mut response = connector
+        .kv_get_request(annotations_prefix(root), Some(GetOptions::new().with_prefix()))
+        .await?;
+    for kv in response.take_kvs() {
+        match parse_annotation(kv.value(), kv.mod_revision()) {
+            Ok(Some(annotation)) => result.annotations.push(annotation),
+            Ok(None) => result.unsupported += 1,
+            Err(e) => {
+                warn!("Skipped broken shared annotation {}: {e}", String::from_utf8_lossy(kv.key()));
+                result.unsupported += 1;
+            }
+        }
+    }
+
+    let (bookmarks, _) = get_bookmarks(connector, root).await?;
+    if bookmarks.schema > SCHEMA_VERSION {
+        result.unsupported += 1;
+    } else {
+        result.key_collection = bookmarks.keys;
+    }
+    Ok(result)
+}
+
+/// 写入注释，仅当etcd中的修改版本等于 `expected_mod_revision` 时成功，为0表示新建。
+///
+/// 冲突时不覆盖，返回etcd中的最新内容由用户决定如何合并
+pub async fn put(
+    connector: &mut EtcdConnector,
+    root: &str,
+    annotation: KeyAnnotation,
+    expected_mod_revision: i64,
+) -> Result<SharedAnnotationWriteResult, LogicError> {
+    let storage_key = annotation_key(root, &annotation.key, annotation.prefix);
+    let doc = AnnotationDoc {
+        schema: SCHEMA_VERSION,
+        author: edit_lock::local_identity(),
+        annotation,
+    };
+    let value = serde_json::to_vec(&doc)?;
+    match connector
+        .kv_put_if_mod_revision(storage_key.clone(), value, expected_mod_revision)
+        .await?
+    {
+        Some(mod_revision) => Ok(SharedAnnotationWriteResult {
+            success: true,
+            mod_revision,
+            current: None,
+        }),
+        None => {
+            let current = get_annotation(connector, &storage_key).await?;
+            Ok(SharedAnnotationWriteResult {
+                success: false,
+                mod_revision: current.as_ref().map(|c| c.mod_revision).unwrap_or(0),
+                current,
+            })
+        }
+    }
+}
+
+/// 删除注释，etcd中的修改版本与 `expected_mod_revision` 不一致时不删除并返回false
+pub async fn remove(
+    connector: &mut EtcdConnector,
+    root: &str,
+    key: &str,
+    prefix: bool,
+    expected_mod_revision: i64,
+) -> Result<bool, LogicError> {
+    let storage_key = annotation_key(root, key, prefix);
+    Ok(connector
+        .kv_delete_if(storage_key, None, Some(expected_mod_revision))
+        .await?)
+}
+
+/// 修改共享收藏夹，基于修改版本比较后写入，冲突时重新读取并合并，返回修改后的收藏夹
+pub async fn update_bookmarks(
+    connector: &mut EtcdConnector,
+    root: &str,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Vec<String>, LogicError> {
+    for _ in 0..MAX_BOOKMARK_RETRIES {
+        let (mut doc, mod_revision) = get_bookmarks(connector, root).await?;
+        if doc.schema > SCHEMA_VERSION {
+            return Err(LogicError::IllegalArgument(String::from(
+                "Shared bookmarks were written by a newer version of workbench",
+            )));
+        }
+        doc.schema = SCHEMA_VERSION;
+        doc.keys.retain(|k| !remove.contains(k));
+        for key in &add {
+            if !doc.keys.contains(key) {
+                doc.keys.push(key.clone());
+            }
+        }
+        let value = serde_json::to_vec(&doc)?;
+        if connector
+            .kv_put_if_mod_revision(bookmarks_key(root), value, mod_revision)
+            .await?
+            .is_some()
+        {
+            return Ok(doc.keys);
+        }
+    }
+    Err(LogicError::IllegalArgument(String::from(
+        "Shared bookmarks are being modified concurrently, please try again",
+    )))
+}
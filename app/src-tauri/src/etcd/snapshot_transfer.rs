@@ -0,0 +1,275 @@
+use std::collections::VecDeque;
+use std::io::SeekFrom;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use etcd_client::SnapshotStreaming;
+use log::{debug, info, warn};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::select;
+use tokio::time::Instant;
+
+use crate::api::task_center::TaskHandle;
+use crate::error::LogicError;
+use crate::transport::maintenance::SnapshotState;
+
+use super::get_connector;
+
+/// 传输中断后最多重新请求快照的次数
+const MAX_RESUMES: u32 = 3;
+/// 推送进度事件的最小间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+/// 计算瞬时速率的时间窗口
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(3);
+
+/// 下载速率统计，时间为开始下载后经过的时长
+#[derive(Default)]
+pub struct ThroughputMeter {
+    samples: VecDeque<(Duration, u64)>,
+    total: u64,
+}
+
+impl ThroughputMeter {
+    pub fn record(&mut self, at: Duration, bytes: u64) {
+        self.total += bytes;
+        self.samples.push_back((at, bytes));
+        while let Some((time, _)) = self.samples.front() {
+            if at.saturating_sub(*time) <= THROUGHPUT_WINDOW {
+                break;
+            }
+            self.samples.pop_front();
+        }
+    }
+
+    /// 最近一个时间窗口内的速率，刚开始下载时按已经过的时长计算
+    pub fn instant(&self, now: Duration) -> f64 {
+        let window = now.min(THROUGHPUT_WINDOW).as_secs_f64();
+        if window <= 0.0 {
+            return 0.0;
+        }
+        let bytes: u64 = self
+            .samples
+            .iter()
+            .filter(|(time, _)| now.saturating_sub(*time) <= THROUGHPUT_WINDOW)
+            .map(|(_, bytes)| bytes)
+            .sum();
+        bytes as f64 / window
+    }
+
+    pub fn average(&self, now: Duration) -> f64 {
+        let elapsed = now.as_secs_f64();
+        if elapsed <= 0.0 {
+            return 0.0;
+        }
+        self.total as f64 / elapsed
+    }
+}
+
+pub fn eta_seconds(remain: u64, throughput: f64) -> Option<u64> {
+    if throughput <= 0.0 {
+        return None;
+    }
+    Some((remain as f64 / throughput).ceil() as u64)
+}
+
+/// 写入快照文件。续传时新的快照流先与已写入的内容比对，一致的部分不重复写入，
+/// 从第一个不一致的位置开始覆盖，因此中断前后集群数据未变化时可以从中断处继续
+pub struct SnapshotWriter {
+    file: File,
+    /// 文件中已写入的字节数
+    written: u64,
+    /// 当前快照流已确认或写入到的位置
+    position: u64,
+}
+
+impl SnapshotWriter {
+    pub async fn create(path: &Path) -> Result<Self, LogicError> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .await?;
+        Ok(SnapshotWriter {
+            file,
+            written: 0,
+            position: 0,
+        })
+    }
+
+    /// 开始读取新的快照流
+    pub fn restart(&mut self) {
+        self.position = 0;
+    }
+
+    /// 当前快照流已确认或写入到的位置
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    pub async fn write(&mut self, mut blob: &[u8]) -> Result<(), LogicError> {
+        if self.position < self.written {
+            let overlap = blob.len().min((self.written - self.position) as usize);
+            let mut existing = vec![0u8; overlap];
+            self.file.seek(SeekFrom::Start(self.position)).await?;
+            self.file.read_exact(&mut existing).await?;
+            if existing == blob[..overlap] {
+                self.position += overlap as u64;
+                blob = &blob[overlap..];
+            } else {
+                debug!("Snapshot content changed at {}, discard the rest of previous download", self.position);
+                self.file.set_len(self.position).await?;
+                self.written = self.position;
+            }
+        }
+        if !blob.is_empty() {
+            self.file.seek(SeekFrom::Start(self.position)).await?;
+            self.file.write_all(blob).await?;
+            self.position += blob.len() as u64;
+            self.written = self.written.max(self.position);
+        }
+        Ok(())
+    }
+
+    pub async fn finish(&mut self) -> Result<(), LogicError> {
+        //  新的快照比中断前的短时，截掉多余的旧内容
+        self.file.set_len(self.position).await?;
+        self.file.flush().await?;
+        Ok(())
+    }
+}
+
+/// 下载过程中写入的临时文件，完成后重命名为目标文件
+pub fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+/// 读取快照流写入文件，定时通过 `report` 上报进度，流中断时重新请求快照续传。
+/// 下载过程中写入 `.part` 临时文件，完成后才重命名为目标文件，取消或失败时删除临时文件。
+/// 返回错误时 `state` 中保留最后的进度
+pub async fn download(
+    session: i32,
+    stream: SnapshotStreaming,
+    path: &Path,
+    handle: &mut TaskHandle,
+    state: &mut SnapshotState,
+    report: impl Fn(&SnapshotState),
+) -> Result<(), LogicError> {
+    let part = part_path(path);
+    let mut writer = SnapshotWriter::create(&part).await?;
+    let mut result = receive(session, stream, &mut writer, handle, state, &report).await;
+    if let Ok(true) = result {
+        result = writer.finish().await.map(|_| true);
+    }
+    drop(writer);
+    if let Ok(true) = result {
+        result = tokio::fs::rename(&part, path).await.map(|_| true).map_err(LogicError::from);
+    }
+
+    match result {
+        Ok(true) => {
+            state.remain = 0;
+            state.eta_seconds = Some(0);
+            handle.progress(state.received, state.received);
+            report(state);
+            info!("Snapshot of session {} saved: {} bytes, {} resumes", session, state.received, state.resumes);
+            Ok(())
+        }
+        other => {
+            if let Err(e) = tokio::fs::remove_file(&part).await {
+                warn!("Failed to remove incomplete snapshot file {}: {}", part.display(), e);
+            }
+            other.map(|_| ())
+        }
+    }
+}
+
+/// 读取快照流直到结束，任务被取消时返回 false
+async fn receive(
+    session: i32,
+    mut stream: SnapshotStreaming,
+    writer: &mut SnapshotWriter,
+    handle: &mut TaskHandle,
+    state: &mut SnapshotState,
+    report: &impl Fn(&SnapshotState),
+) -> Result<bool, LogicError> {
+    let mut meter = ThroughputMeter::default();
+    let started = Instant::now();
+    let mut last_report: Option<Instant> = None;
+
+    loop {
+        let message = select! {
+            message = stream.message() => message,
+            _ = handle.cancelled() => return Ok(false),
+        };
+        let interrupted = match message {
+            Ok(Some(response)) => {
+                let blob = response.blob();
+                let remain = response.remaining_bytes();
+                writer.write(blob).await?;
+
+                let now = started.elapsed();
+                meter.record(now, blob.len() as u64);
+                state.received = writer.position;
+                state.remain = remain;
+                state.total = Some(writer.position + remain);
+                state.throughput = meter.instant(now);
+                state.average_throughput = meter.average(now);
+                state.eta_seconds = eta_seconds(remain, state.throughput);
+
+                if remain == 0 {
+                    break;
+                }
+                if last_report.map(|t| t.elapsed() >= PROGRESS_INTERVAL).unwrap_or(true) {
+                    last_report = Some(Instant::now());
+                    handle.progress(state.received, writer.position + remain);
+                    report(state);
+                }
+                continue;
+            }
+            //  未收到剩余为0的分片就结束，视为中断
+            Ok(None) if state.total.is_some() && state.remain > 0 => String::from("Snapshot stream closed unexpectedly"),
+            Ok(None) => break,
+            Err(e) => e.to_string(),
+        };
+
+        loop {
+            if state.resumes >= MAX_RESUMES {
+                return Err(LogicError::MsgError(interrupted));
+            }
+            state.resumes += 1;
+            warn!(
+                "Snapshot of session {} interrupted at {} bytes: {}, resume {}/{}",
+                session, state.received, interrupted, state.resumes, MAX_RESUMES
+            );
+            handle.message(format!("Resuming ({}/{}): {}", state.resumes, MAX_RESUMES, interrupted));
+            report(state);
+            select! {
+                _ = tokio::time::sleep(Duration::from_secs(state.resumes as u64)) => {},
+                _ = handle.cancelled() => return Ok(false),
+            }
+            let result = {
+                let mut connector = get_connector(&session)?;
+                connector.maintenance_snapshot_stream().await
+            };
+            match result {
+                Ok(new_stream) => {
+                    stream = new_stream;
+                    writer.restart();
+                    break;
+                }
+                Err(e) => warn!("Failed to request snapshot again: {}", e),
+            }
+        }
+    }
+
+    Ok(true)
+}
@@ -13,6 +13,18 @@ mod test_connect {
             user: None,
             tls: None,
             ssh: None,
+            idle_timeout_minutes: None,
+            read_only: false,
+            encrypted_prefixes: vec![],
+            max_txn_ops: None,
+            max_request_bytes: None,
+            max_recv_message_bytes: None,
+            max_send_message_bytes: None,
+            sync_endpoints: false,
+            extends: None,
+            key_tree: None,
+            prompt_credentials: vec![],
+            content_hints: None,
         };
         EtcdConnector::new(connection).await
     }
@@ -51,6 +63,49 @@ mod test_connect {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn auth_bootstrap() -> Result<(), LogicError> {
+        use crate::transport::user::{AuthBootstrapConfig, AuthBootstrapRole, AuthBootstrapUser, SerializablePermission};
+
+        let config = || AuthBootstrapConfig {
+            root_password: String::from("root"),
+            roles: vec![
+                AuthBootstrapRole {
+                    role: String::from("root"),
+                    permissions: vec![],
+                },
+                AuthBootstrapRole {
+                    role: String::from("bootstrap_reader"),
+                    permissions: vec![SerializablePermission {
+                        key: String::from("/bootstrap/"),
+                        perm_type: 0,
+                        prefix: true,
+                        all_keys: false,
+                    }],
+                },
+            ],
+            users: vec![AuthBootstrapUser {
+                user: String::from("bootstrap_user"),
+                password: String::from("bootstrap"),
+                roles: vec![String::from("bootstrap_reader")],
+            }],
+            enable_auth: false,
+        };
+        let mut connector = get_connector().await?;
+        let steps = connector.auth_bootstrap(config()).await?;
+        assert!(steps.iter().any(|s| s.action == "addRole" && s.target == "root" && s.skipped));
+        assert!(steps.iter().any(|s| s.action == "addUser" && s.target == "bootstrap_user"));
+
+        //  再次执行时所有资源都已存在
+        let steps = connector.auth_bootstrap(config()).await?;
+        println!("{:?}", steps);
+        assert!(steps.iter().all(|s| s.skipped));
+
+        connector.user_delete(String::from("bootstrap_user")).await?;
+        connector.role_delete(String::from("bootstrap_reader")).await?;
+        Ok(())
+    }
+
     #[tokio::test]
     async fn get_cluster_info() -> Result<(), LogicError> {
         let mut connector = get_connector().await?;
@@ -107,4 +162,1113 @@ mod test_connect {
         println!("finished");
         Ok(())
     }
-}
\ No newline at end of file
+}
+mod test_key_index {
+    use crate::etcd::key_index::{KeyIndex, KeyMeta};
+
+    fn meta(revision: i64) -> KeyMeta {
+        KeyMeta {
+            create_revision: revision,
+            mod_revision: revision,
+            version: 1,
+            lease: 0,
+        }
+    }
+
+    fn keys(list: Vec<(Vec<u8>, KeyMeta)>) -> Vec<String> {
+        list.into_iter().map(|(k, _)| String::from_utf8(k).unwrap()).collect()
+    }
+
+    #[test]
+    fn insert_remove_and_query() {
+        let mut index = KeyIndex::new();
+        for (i, key) in ["/app/b", "/app/a", "/app", "/app/a/c", "/db/x"].iter().enumerate() {
+            assert!(index.insert(key.as_bytes(), meta(i as i64)));
+        }
+        assert!(!index.insert(b"/app/a", meta(9)));
+        assert_eq!(index.len(), 5);
+        assert_eq!(index.get(b"/app/a"), Some(meta(9)));
+        assert_eq!(index.get(b"/ap"), None);
+
+        assert_eq!(keys(index.keys()), vec!["/app", "/app/a", "/app/a/c", "/app/b", "/db/x"]);
+        assert_eq!(index.count_prefix(b"/app/"), 3);
+        assert_eq!(keys(index.keys_with_prefix(b"/app/a", 10)), vec!["/app/a", "/app/a/c"]);
+        assert_eq!(keys(index.keys_after(b"/app/a", 2)), vec!["/app/a/c", "/app/b"]);
+        assert_eq!(keys(index.keys_after(b"", 1)), vec!["/app"]);
+
+        assert!(index.remove(b"/app/a"));
+        assert!(!index.remove(b"/app/a"));
+        assert!(index.remove(b"/app"));
+        assert_eq!(index.len(), 3);
+        assert_eq!(keys(index.keys()), vec!["/app/a/c", "/app/b", "/db/x"]);
+        assert_eq!(index.count_prefix(b"/app"), 2);
+    }
+
+    #[test]
+    fn next_segments() {
+        let mut index = KeyIndex::new();
+        let list = ["/app/a/1", "/app/a/2", "/app/b", "/app/c/1", "/db/x"];
+        for key in list.iter() {
+            index.insert(key.as_bytes(), meta(1));
+        }
+
+        let segments = index.next_segments(b"/app/", b"/", 10);
+        assert_eq!(segments, vec![
+            (b"/app/a/".to_vec(), 2),
+            (b"/app/b".to_vec(), 1),
+            (b"/app/c/".to_vec(), 1),
+        ]);
+        assert_eq!(index.next_segments(b"/", b"/", 1), vec![(b"/app/".to_vec(), 4)]);
+
+        let keys: Vec<Vec<u8>> = list.iter().map(|k| k.as_bytes().to_vec()).collect();
+        assert_eq!(crate::etcd::key_index::next_segments_of_keys(&keys, b"/app/", b"/", 10), segments);
+    }
+}
+
+mod test_console {
+    use crate::etcd::console::{parse, ConsoleCommand};
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(parse("get --prefix /a --limit=10").unwrap(), ConsoleCommand::Get {
+            key: String::from("/a"),
+            prefix: true,
+            keys_only: false,
+            limit: 10,
+            revision: None,
+        });
+        assert_eq!(parse(r#"put /k "hello world""#).unwrap(), ConsoleCommand::Put {
+            key: String::from("/k"),
+            value: String::from("hello world"),
+            lease: None,
+        });
+        assert_eq!(parse("del /x --prefix").unwrap(), ConsoleCommand::Del {
+            key: String::from("/x"),
+            prefix: true,
+        });
+        assert!(parse("del '' --prefix").is_err());
+        assert!(parse("put /k").is_err());
+        assert!(parse("get /k --unknown").is_err());
+        assert!(parse("compact 100").is_err());
+    }
+}
+
+mod test_notification_rule {
+    use crate::transport::connection::{NotificationRule, ValuePredicate, WatchEventType};
+    use crate::utils::fuzzy::glob_match;
+
+    #[test]
+    fn glob() {
+        assert!(glob_match("/app/*/config", "/app/order/config"));
+        assert!(glob_match("/app/*", "/app/order/config"));
+        assert!(glob_match("/app/?", "/app/a"));
+        assert!(!glob_match("/app/?", "/app/ab"));
+        assert!(!glob_match("/app/*/config", "/app/order/status"));
+    }
+
+    #[test]
+    fn matches() {
+        let mut rule = NotificationRule {
+            id: String::from("1"),
+            name: String::new(),
+            key_pattern: String::from("/service/*/replicas"),
+            event_types: vec![WatchEventType::Modify, WatchEventType::Delete],
+            value_predicate: Some(ValuePredicate::LessThan(2.0)),
+            muted: false,
+            snooze_until: None,
+        };
+        assert!(rule.matches("/service/api/replicas", WatchEventType::Modify, Some(b"1")));
+        assert!(!rule.matches("/service/api/replicas", WatchEventType::Modify, Some(b"3")));
+        assert!(!rule.matches("/service/api/replicas", WatchEventType::Create, Some(b"1")));
+        assert!(!rule.matches("/service/api/replicas", WatchEventType::Delete, None));
+
+        rule.value_predicate = None;
+        assert!(rule.matches("/service/api/replicas", WatchEventType::Delete, None));
+
+        rule.snooze_until = Some(1000);
+        assert!(!rule.is_active(999));
+        assert!(rule.is_active(1000));
+        rule.muted = true;
+        assert!(!rule.is_active(1000));
+    }
+}
+
+mod test_auth_simulation {
+    use crate::transport::user::{AuthSimulation, SerializablePermission};
+
+    fn permission(key: &str, perm_type: i32, prefix: bool) -> SerializablePermission {
+        SerializablePermission {
+            key: String::from(key),
+            perm_type,
+            prefix,
+            all_keys: false,
+        }
+    }
+
+    #[test]
+    fn resolve() {
+        let roles = vec![
+            (String::from("reader"), vec![permission("/app/", 0, true)]),
+            (String::from("writer"), vec![permission("/app/config", 1, false)]),
+        ];
+        let result = AuthSimulation::resolve("alice", "/app/config", false, roles.clone());
+        assert!(result.read && result.write);
+        assert_eq!(result.read_grants[0].role, "reader");
+        assert_eq!(result.write_grants[0].role, "writer");
+
+        //  单个key的权限不覆盖前缀范围
+        let result = AuthSimulation::resolve("alice", "/app/config", true, roles.clone());
+        assert!(result.read && !result.write);
+
+        let result = AuthSimulation::resolve("alice", "/other", false, roles);
+        assert!(!result.read && !result.write);
+
+        let result = AuthSimulation::resolve("bob", "/other", true, vec![(String::from("root"), vec![])]);
+        assert!(result.root && result.read && result.write);
+    }
+}
+
+mod test_txn_batch {
+    use crate::etcd::txn_batch::TxnLimits;
+
+    #[test]
+    fn split() {
+        let kvs: Vec<(Vec<u8>, Vec<u8>)> = (0..10)
+            .map(|i| (format!("/k{}", i).into_bytes(), vec![0u8; 1000]))
+            .collect();
+
+        let limits = TxnLimits { max_ops: 4, max_request_bytes: 1024 * 1024 };
+        assert_eq!(limits.split(&kvs).unwrap(), vec![0..4, 4..8, 8..10]);
+
+        //  每批最多容纳3个键值对
+        let limits = TxnLimits { max_ops: 128, max_request_bytes: 4 * 1024 + 3200 };
+        let batches = limits.split(&kvs).unwrap();
+        assert!(batches.iter().all(|r| r.len() <= 3));
+        assert_eq!(batches.iter().map(|r| r.len()).sum::<usize>(), 10);
+
+        let limits = TxnLimits { max_ops: 128, max_request_bytes: 4 * 1024 + 100 };
+        assert_eq!(limits.split(&kvs).unwrap_err(), b"/k0".to_vec());
+    }
+}
+
+mod test_hot_keys {
+    use crate::etcd::hot_keys::key_prefix;
+
+    #[test]
+    fn prefix() {
+        assert_eq!(key_prefix("/app/config/db", "/", 1), Some("/app/"));
+        assert_eq!(key_prefix("/app/config/db", "/", 2), Some("/app/config/"));
+        assert_eq!(key_prefix("/app/config/db", "/", 3), None);
+        assert_eq!(key_prefix("app.config.db", ".", 1), Some("app."));
+        assert_eq!(key_prefix("/app", "/", 0), None);
+    }
+}
+
+mod test_garbage {
+    use crate::etcd::garbage::{is_orphan_lock, min_unmodified_millis};
+    use crate::transport::kv::RevisionTimeSample;
+
+    #[test]
+    fn orphan_lock() {
+        assert!(is_orphan_lock("/lock/job/694d7a5c7e9b2c04", 0));
+        assert!(is_orphan_lock("/lock/job/694d7a5c7e9b2c04", 1));
+        assert!(!is_orphan_lock("/lock/job/694d7a5c7e9b2c04", 0x694d7a5c7e9b2c04));
+        assert!(!is_orphan_lock("/app/config/1", 0));
+        assert!(!is_orphan_lock("/app/config/database", 0));
+        assert!(is_orphan_lock("/services/election/694d7a5c7e9b2c04", 0));
+        //  不在锁或选举前缀下的十六进制key不视为锁
+        assert!(!is_orphan_lock("/app/cache/694d7a5c7e9b2c04", 0));
+        assert!(!is_orphan_lock("/app/locker/694d7a5c7e9b2c04", 0));
+    }
+
+    #[test]
+    fn unmodified_time() {
+        let timeline = vec![
+            RevisionTimeSample { time: 1000, revision: 10 },
+            RevisionTimeSample { time: 2000, revision: 20 },
+        ];
+        assert_eq!(min_unmodified_millis(5, &timeline, 5000), Some(4000));
+        assert_eq!(min_unmodified_millis(15, &timeline, 5000), Some(3000));
+        assert_eq!(min_unmodified_millis(21, &timeline, 5000), None);
+    }
+}
+
+mod test_dir_sync {
+    use crate::etcd::dir_sync::{is_safe_relative_path, key_to_path, path_to_key};
+
+    #[test]
+    fn key_path_mapping() {
+        assert_eq!(key_to_path("config/db", false), "config/db");
+        assert_eq!(key_to_path("config", true), "config/%self");
+        assert_eq!(key_to_path("a:b/", false), "a%3Ab/%");
+        assert_eq!(key_to_path("../x", false), "%2E%2E/x");
+
+        for (key, is_parent) in [("config/db", false), ("config", true), ("a:b/", false), ("../x", false), ("100%", false)] {
+            assert_eq!(path_to_key(&key_to_path(key, is_parent)), key);
+            assert!(is_safe_relative_path(&key_to_path(key, is_parent)));
+        }
+    }
+
+    #[test]
+    fn unsafe_path() {
+        assert!(!is_safe_relative_path("../x"));
+        assert!(!is_safe_relative_path("/etc/passwd"));
+        assert!(!is_safe_relative_path("a/./b"));
+        assert!(!is_safe_relative_path("C:/x"));
+        assert!(!is_safe_relative_path("a\\..\\b"));
+    }
+}
+
+mod test_connection_inherit {
+    use crate::transport::connection::{Connection, ConnectionSsh, ConnectionUser, CredentialKind};
+
+    fn connection(host: &str, extends: Option<&str>) -> Connection {
+        Connection {
+            host: String::from(host),
+            port: 0,
+            namespace: None,
+            user: None,
+            tls: None,
+            ssh: None,
+            idle_timeout_minutes: None,
+            read_only: false,
+            encrypted_prefixes: vec![],
+            max_txn_ops: None,
+            max_request_bytes: None,
+            max_recv_message_bytes: None,
+            max_send_message_bytes: None,
+            sync_endpoints: false,
+            extends: extends.map(String::from),
+            key_tree: None,
+            prompt_credentials: vec![],
+            content_hints: None,
+        }
+    }
+
+    #[test]
+    fn inherit() {
+        let mut base = connection("10.0.0.1", Some("root"));
+        base.port = 2379;
+        base.read_only = true;
+        base.ssh = Some(ConnectionSsh {
+            host: String::from("bastion"),
+            port: 22,
+            user: String::from("ops"),
+            identity: None,
+            compression: false,
+            ciphers: vec![],
+            kex: vec![],
+            macs: vec![],
+            use_config: false,
+        });
+
+        let mut derived = connection("10.0.0.2", Some("base"));
+        derived.inherit(&base);
+        assert_eq!(derived.host, "10.0.0.2");
+        assert_eq!(derived.port, 2379);
+        assert!(derived.read_only);
+        assert_eq!(derived.ssh.unwrap().host, "bastion");
+        assert_eq!(derived.extends.as_deref(), Some("root"));
+    }
+
+    #[test]
+    fn strip_prompted_credentials() {
+        let mut base = connection("10.0.0.1", None);
+        base.user = Some(ConnectionUser {
+            username: String::from("root"),
+            password: String::from("secret"),
+        });
+        base.prompt_credentials = vec![CredentialKind::EtcdPassword];
+
+        let mut derived = connection("10.0.0.2", None);
+        derived.inherit(&base);
+        assert_eq!(derived.prompt_credentials, vec![CredentialKind::EtcdPassword]);
+
+        base.strip_prompted_credentials();
+        assert_eq!(base.user.unwrap().password, "");
+        //  未配置提示输入时不清除
+        derived.prompt_credentials.clear();
+        derived.strip_prompted_credentials();
+        assert_eq!(derived.user.unwrap().password, "secret");
+    }
+}
+
+mod test_prefetch {
+    use crate::etcd::key_index;
+    use crate::etcd::prefetcher::PrefetchIndex;
+
+    #[test]
+    fn store_and_invalidate() {
+        let kvs = key_index::to_serializable_kvs(vec![
+            (b"/app/a".to_vec(), key_index::KeyMeta { create_revision: 1, mod_revision: 1, version: 1, lease: 0 }),
+            (b"/app/b".to_vec(), key_index::KeyMeta { create_revision: 2, mod_revision: 2, version: 1, lease: 0 }),
+        ]);
+        let mut prefetch = PrefetchIndex::default();
+        prefetch.store(b"/app/", &kvs);
+        assert!(prefetch.is_covered(b"/app/"));
+        assert!(prefetch.is_covered(b"/app/a"));
+        assert!(!prefetch.is_covered(b"/other/"));
+        assert_eq!(prefetch.keys_with_prefix(b"/app/").len(), 2);
+
+        prefetch.store(b"/app/", &kvs[..1]);
+        assert_eq!(prefetch.keys_with_prefix(b"/app/").len(), 1);
+
+        prefetch.invalidate(b"/app/c");
+        assert!(!prefetch.is_covered(b"/app/"));
+
+        //  删除前缀时，包含该前缀和位于其下的前缀都失效
+        prefetch.store(b"/app/", &kvs);
+        prefetch.store(b"/app/sub/", &[]);
+        prefetch.store(b"/other/", &[]);
+        prefetch.invalidate_prefix(b"/app/s");
+        assert!(!prefetch.is_covered(b"/app/"));
+        assert!(!prefetch.is_covered(b"/app/sub/"));
+        assert!(prefetch.is_covered(b"/other/"));
+    }
+}
+
+mod test_key_separator {
+    use crate::etcd::key_separator;
+
+    #[test]
+    fn detect_best_separator() {
+        let keys: Vec<String> = ["app.svc.a", "app.svc.b", "app.db.url", "app.db.user", "cfg.timeout"]
+            .iter()
+            .map(|k| k.to_string())
+            .collect();
+        let scores = key_separator::detect(&keys, &[]);
+        assert_eq!(scores[0].separator, ".");
+        assert_eq!(scores[0].coverage, 1.0);
+        assert_eq!(scores[0].max_depth, 3);
+
+        let slash = scores.iter().find(|s| s.separator == "/").unwrap();
+        assert_eq!(slash.score, 0.0);
+
+        let scores = key_separator::detect(&keys, &["::".to_string()]);
+        assert_eq!(scores.len(), 1);
+        assert_eq!(scores[0].score, 0.0);
+    }
+}
+
+mod test_auth_token {
+    use crate::etcd::etcd_connector::parse_auth_token;
+    use crate::transport::user::AuthTokenType;
+
+    #[test]
+    fn parse_token_type() {
+        assert_eq!(parse_auth_token("hBnuVxXlDTWUsWiq.15"), (AuthTokenType::Simple, None));
+
+        //  {"alg":"RS256"} . {"exp":1700000000,"username":"root"} . signature
+        let jwt = "eyJhbGciOiJSUzI1NiJ9.eyJleHAiOjE3MDAwMDAwMDAsInVzZXJuYW1lIjoicm9vdCJ9.c2ln";
+        assert_eq!(parse_auth_token(jwt), (AuthTokenType::Jwt, Some(1_700_000_000_000)));
+    }
+}
+
+mod test_call_deadline {
+    use std::time::Duration;
+
+    use crate::etcd;
+
+    #[test]
+    fn validate_timeout() {
+        assert_eq!(etcd::call_deadline(None).unwrap(), None);
+        assert_eq!(etcd::call_deadline(Some(600)).unwrap(), Some(Duration::from_secs(600)));
+        assert!(etcd::call_deadline(Some(0)).is_err());
+        assert!(etcd::call_deadline(Some(100_000)).is_err());
+    }
+}
+
+mod test_shared_annotation {
+    use crate::etcd::shared_annotation::{annotation_key, root};
+    use crate::transport::settings::SettingConfig;
+
+    #[test]
+    fn storage_key() {
+        let mut settings = SettingConfig::default();
+        assert!(root(&settings).is_err());
+
+        settings.shared_annotations = true;
+        settings.shared_annotation_prefix = String::from("/__workbench");
+        let root = root(&settings).unwrap();
+        assert_eq!(root, "/__workbench/");
+        assert_eq!(annotation_key(&root, "/a", false), "/__workbench/annotations/L2E");
+        assert_eq!(annotation_key(&root, "/a", true), "/__workbench/annotations/L2E.prefix");
+    }
+}
+
+mod test_report_scheduler {
+    use chrono::{Local, TimeZone};
+
+    use crate::etcd::report_scheduler::is_due;
+
+    #[test]
+    fn due_once_a_day() {
+        let now = Local.with_ymd_and_hms(2024, 5, 1, 3, 0, 0).unwrap();
+        let yesterday = Local.with_ymd_and_hms(2024, 4, 30, 2, 0, 30).unwrap().timestamp_millis() as u64;
+        let today = Local.with_ymd_and_hms(2024, 5, 1, 2, 0, 30).unwrap().timestamp_millis() as u64;
+
+        assert!(is_due("02:00", yesterday, now));
+        assert!(is_due("02:00", 0, now));
+        assert!(!is_due("02:00", today, now));
+        assert!(!is_due("04:00", yesterday, now));
+        assert!(!is_due("25:00", 0, now));
+    }
+}
+
+mod test_audit_stream {
+    use std::io::Read;
+
+    use flate2::read::MultiGzDecoder;
+
+    use crate::etcd::audit_stream::{AuditRecord, AuditWriter};
+
+    #[test]
+    fn append_gzip_members() {
+        let dir = std::env::temp_dir().join(format!("audit-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let writer = AuditWriter::new(&dir, "127.0.0.1:2379/ns");
+        writer.write(&[AuditRecord::Start { time: 1, revision: 10 }]).unwrap();
+        writer
+            .write(&[AuditRecord::Gap {
+                time: 2,
+                from_revision: 10,
+                to_revision: 19,
+                reason: String::from("compacted"),
+            }])
+            .unwrap();
+
+        let path = writer.file_path(&chrono::Local::now().format("%Y-%m-%d").to_string());
+        assert!(path.file_name().unwrap().to_string_lossy().starts_with("audit-127_0_0_1_2379_ns-"));
+        let mut content = String::new();
+        MultiGzDecoder::new(std::fs::File::open(&path).unwrap())
+            .read_to_string(&mut content)
+            .unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains(r#""type":"start""#));
+        assert!(lines[1].contains(r#""fromRevision":10"#));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+mod test_clock_drift {
+    use crate::etcd::clock_drift::assess;
+    use crate::transport::maintenance::{DriftRisk, MemberClock};
+
+    fn member(offset: Option<i64>, term: u64) -> MemberClock {
+        MemberClock {
+            id: String::new(),
+            name: String::new(),
+            endpoint: None,
+            offset_millis: offset,
+            uncertainty_millis: None,
+            raft_term: Some(term),
+            leader: None,
+            error_msg: None,
+        }
+    }
+
+    #[test]
+    fn assess_risk() {
+        let (drift, risk, warnings) = assess(&[member(Some(100), 5), member(Some(-200), 5)], 0, 0);
+        assert_eq!(drift, Some(300));
+        assert_eq!(risk, DriftRisk::Low);
+        assert!(warnings.is_empty());
+
+        let (_, risk, warnings) = assess(&[member(Some(3000), 5), member(Some(0), 6)], 0, 0);
+        assert_eq!(risk, DriftRisk::Medium);
+        assert_eq!(warnings.len(), 2);
+
+        let (drift, risk, _) = assess(&[member(None, 5)], 4, 3_600_000);
+        assert_eq!(drift, None);
+        assert_eq!(risk, DriftRisk::High);
+
+        //  误差范围内的偏差不告警
+        let mut a = member(Some(1500), 5);
+        a.uncertainty_millis = Some(600);
+        let mut b = member(Some(0), 5);
+        b.uncertainty_millis = Some(600);
+        let (drift, risk, warnings) = assess(&[a.clone(), b.clone()], 0, 0);
+        assert_eq!(drift, Some(1500));
+        assert_eq!(risk, DriftRisk::Low);
+        assert!(warnings.is_empty());
+
+        a.offset_millis = Some(2500);
+        let (_, risk, warnings) = assess(&[a, b], 0, 0);
+        assert_eq!(risk, DriftRisk::Medium);
+        assert_eq!(warnings, vec![String::from("Clock drift between members is at least 1300 ms")]);
+    }
+}
+
+mod test_raft_lag {
+    use std::collections::HashMap;
+
+    use crate::etcd::raft_lag::summarize;
+    use crate::transport::maintenance::RaftLagSample;
+
+    fn sample(time: u64, lags: &[(&str, u64)]) -> RaftLagSample {
+        RaftLagSample {
+            time,
+            leader: String::from("1"),
+            leader_applied_index: 10000,
+            lags: lags.iter().map(|(id, lag)| (id.to_string(), *lag)).collect::<HashMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn slow_follower() {
+        let samples: Vec<RaftLagSample> = (0..10)
+            .map(|i| sample(i, &[("2", 3), ("3", if i == 0 { 10 } else { 2000 })]))
+            .collect();
+        let (avg, max, slow) = summarize(&samples, "2");
+        assert_eq!(avg, Some(3.0));
+        assert_eq!(max, 3);
+        assert!(!slow);
+
+        let (_, max, slow) = summarize(&samples, "3");
+        assert_eq!(max, 2000);
+        assert!(slow);
+
+        //  采样不足时不判断
+        let (_, _, slow) = summarize(&samples[1..3], "3");
+        assert!(!slow);
+        assert_eq!(summarize(&samples, "4"), (None, 0, false));
+    }
+}
+
+mod test_key_tail {
+    use crate::etcd::key_tail::{TailRecord, TailWriter};
+    use crate::transport::kv::KeyTailFormat;
+
+    fn record(revision: i64, value: Option<&str>) -> TailRecord {
+        TailRecord {
+            time: 1,
+            revision,
+            op: if value.is_some() { "put" } else { "delete" },
+            key: String::from("/config/app"),
+            version: revision,
+            lease: 0,
+            value: value.map(String::from),
+            raw_value: None,
+        }
+    }
+
+    #[test]
+    fn append_and_rotate() {
+        let dir = std::env::temp_dir().join(format!("tail-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        let writer = TailWriter::new(&path, KeyTailFormat::Value, 12, 2);
+        writer.write(&record(1, Some("aaaa"))).unwrap();
+        //  值格式下删除事件不写入
+        writer.write(&record(2, None)).unwrap();
+        writer.write(&record(3, Some("bbbb"))).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "aaaa\nbbbb\n");
+
+        //  超过12字节时轮转，最多保留两个历史文件
+        for (revision, value) in [(4, "cccc"), (5, "dddd"), (6, "eeee"), (7, "ffff"), (8, "gggg")] {
+            writer.write(&record(revision, Some(value))).unwrap();
+        }
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "gggg\n");
+        assert_eq!(std::fs::read_to_string(writer.rotated_path(1)).unwrap(), "eeee\nffff\n");
+        assert_eq!(std::fs::read_to_string(writer.rotated_path(2)).unwrap(), "cccc\ndddd\n");
+        assert!(!writer.rotated_path(3).exists());
+
+        let json = TailWriter::new(&dir.join("app.json"), KeyTailFormat::Json, 0, 0);
+        json.write(&record(9, None)).unwrap();
+        let content = std::fs::read_to_string(dir.join("app.json")).unwrap();
+        assert!(content.contains(r#""op":"delete""#));
+        assert!(!content.contains("value"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+mod test_delete_preview {
+    use crate::etcd::delete_preview::summarize;
+
+    #[test]
+    fn summarize_leases() {
+        let kvs = vec![
+            (String::from("/a/1"), 10, 0),
+            (String::from("/a/2"), 6, 7),
+            (String::from("/a/3"), 0, 9),
+            (String::from("/a/4"), 4, 9),
+        ];
+        let (total_bytes, leases, sample_keys) = summarize(&kvs);
+        assert_eq!(total_bytes, 16 + 20);
+        assert_eq!(leases.len(), 2);
+        assert_eq!((leases[0].lease.as_str(), leases[0].keys), ("9", 2));
+        assert_eq!((leases[1].lease.as_str(), leases[1].keys), ("7", 1));
+        assert_eq!(sample_keys.len(), 4);
+    }
+}
+
+mod test_auth_export {
+    use std::collections::HashMap;
+
+    use crate::transport::user::{AuthExport, PASSWORD_PLACEHOLDER};
+
+    #[test]
+    fn parse_and_passwords() {
+        let content = r#"
+roles:
+  - role: reader
+    permissions:
+      - key: /app/
+        permType: 0
+        prefix: true
+        allKeys: false
+users:
+  - user: alice
+    password: "<password>"
+    roles: [reader]
+  - user: bob
+    password: secret
+    roles: []
+"#;
+        let export = AuthExport::parse(content).unwrap();
+        assert_eq!(export.users[0].password, PASSWORD_PLACEHOLDER);
+        let mut passwords = HashMap::new();
+        assert_eq!(export.password_of("alice", &passwords), None);
+        assert_eq!(export.password_of("bob", &passwords).as_deref(), Some("secret"));
+        passwords.insert(String::from("alice"), String::from("pwd"));
+        assert_eq!(export.password_of("alice", &passwords).as_deref(), Some("pwd"));
+
+        let definition = export.to_definition();
+        assert!(!definition.prune);
+        assert_eq!(definition.roles[0].permissions.len(), 1);
+        assert_eq!(definition.users[0].roles, vec![String::from("reader")]);
+
+        let yaml = serde_yaml::to_string(&export).unwrap();
+        assert_eq!(AuthExport::parse(&yaml).unwrap().users.len(), 2);
+    }
+}
+
+mod test_dry_run {
+    use crate::etcd::dry_run::{permission_target, plan};
+    use crate::transport::dry_run::{Mutation, PlannedOp};
+    use crate::transport::user::SerializablePermission;
+
+    #[test]
+    fn serialize_mutation() {
+        let done: Mutation<usize> = Mutation::Done(3);
+        assert_eq!(serde_json::to_string(&done).unwrap(), "3");
+        let done: Mutation<()> = Mutation::Done(());
+        assert_eq!(serde_json::to_string(&done).unwrap(), "null");
+
+        let planned: Mutation<()> = Mutation::Plan(plan("user_add", vec![PlannedOp::new("userAdd", "alice")]));
+        let json = serde_json::to_value(&planned).unwrap();
+        assert_eq!(json["command"], "user_add");
+        assert_eq!(json["ops"][0]["target"], "alice");
+        assert_eq!(json["txn"], false);
+    }
+
+    #[test]
+    fn target_of_permission() {
+        let mut permission = SerializablePermission {
+            key: String::from("/app/"),
+            perm_type: 0,
+            prefix: true,
+            all_keys: false,
+        };
+        assert_eq!(permission_target("reader", &permission), "reader:/app/*");
+        permission.prefix = false;
+        assert_eq!(permission_target("reader", &permission), "reader:/app/");
+        permission.all_keys = true;
+        assert_eq!(permission_target("reader", &permission), "reader:*");
+    }
+}
+
+mod test_kv_macro {
+    use std::collections::BTreeMap;
+
+    use crate::etcd::kv_macro::{op_value, put_op};
+    use crate::transport::kv::{KvMacro, MacroOp};
+
+    #[test]
+    fn values_and_serialization() {
+        let text = put_op(String::from("/app/v"), b"v=${VERSION}".to_vec(), None, true);
+        let binary = put_op(String::from("/app/bin"), vec![0xff, 0x00], Some(60), false);
+        let MacroOp::Put { value, raw_value, render_variables, .. } = &text else {
+            panic!("put expected");
+        };
+        let mut variables = BTreeMap::new();
+        assert!(op_value(value, raw_value, *render_variables, &variables).is_err());
+        variables.insert(String::from("VERSION"), String::from("1.2"));
+        assert_eq!(op_value(value, raw_value, *render_variables, &variables).unwrap(), b"v=1.2");
+        let MacroOp::Put { value, raw_value, .. } = &binary else {
+            panic!("put expected");
+        };
+        assert!(value.is_none());
+        assert_eq!(op_value(value, raw_value, false, &variables).unwrap(), vec![0xff, 0x00]);
+
+        let kv_macro = KvMacro {
+            name: String::from("release"),
+            ops: vec![
+                text,
+                binary,
+                MacroOp::Delete { keys: vec![String::from("/app/old")] },
+                MacroOp::Rename { from: String::from("/app/a"), to: String::from("/app/b") },
+            ],
+            source: None,
+            created_at: 0,
+        };
+        let json = serde_json::to_value(&kv_macro).unwrap();
+        assert_eq!(json["ops"][0]["op"], "put");
+        assert_eq!(json["ops"][0]["renderVariables"], true);
+        assert!(json["ops"][1].get("renderVariables").is_none());
+        assert_eq!(json["ops"][2]["op"], "delete");
+        assert_eq!(json["ops"][3]["to"], "/app/b");
+        let parsed: KvMacro = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.ops, kv_macro.ops);
+    }
+}
+
+mod test_message_size {
+    use crate::error::remediation::{remediation_for, RemediationAction};
+
+    #[test]
+    fn remediation_of_message_size() {
+        let response = remediation_for(
+            11,
+            "Error, decoded message length too large: found 5242880 bytes, the limit is: 4194304 bytes",
+        )
+        .unwrap();
+        assert_eq!(response.code, "RESPONSE_TOO_LARGE");
+        assert_eq!(response.actions, vec![RemediationAction::EditConnection]);
+        let request = remediation_for(11, "Error, encoded message length too large: found 10 bytes, the limit is: 4 bytes").unwrap();
+        assert_eq!(request.code, "REQUEST_TOO_LARGE");
+        let server = remediation_for(3, "etcdserver: request is too large").unwrap();
+        assert_eq!(server.code, "SERVER_REQUEST_TOO_LARGE");
+    }
+}
+
+mod test_error_category {
+    use crate::error::category::{categorize, ErrorCategory};
+
+    #[test]
+    fn categorize_grpc_status() {
+        assert_eq!(categorize(16, "etcdserver: invalid auth token"), Some(ErrorCategory::AuthFailed));
+        assert_eq!(
+            categorize(3, "etcdserver: authentication failed, invalid user ID or password"),
+            Some(ErrorCategory::AuthFailed)
+        );
+        assert_eq!(categorize(7, "etcdserver: permission denied"), Some(ErrorCategory::PermissionDenied));
+        assert_eq!(categorize(14, "etcdserver: no leader"), Some(ErrorCategory::NotLeader));
+        assert_eq!(categorize(14, "etcdserver: leader changed"), Some(ErrorCategory::NotLeader));
+        assert_eq!(categorize(14, "etcdserver: request timed out"), Some(ErrorCategory::Unavailable));
+        assert_eq!(
+            categorize(11, "etcdserver: mvcc: required revision has been compacted"),
+            Some(ErrorCategory::Compacted)
+        );
+        assert_eq!(categorize(3, "etcdserver: request is too large"), Some(ErrorCategory::TooLarge));
+        assert_eq!(categorize(8, "etcdserver: mvcc: database space exceeded"), Some(ErrorCategory::QuotaExceeded));
+        assert_eq!(categorize(0, "transport error"), Some(ErrorCategory::Unavailable));
+        //  未来版本与压缩同为 OutOfRange，但不属于任何分类
+        assert_eq!(categorize(11, "etcdserver: mvcc: required revision is a future revision"), None);
+        //  分类以状态码为准，错误信息中的关键字不影响其他状态码
+        assert_eq!(categorize(8, "etcdserver: too many requests"), None);
+        assert_eq!(categorize(5, "etcdserver: permission denied"), None);
+    }
+
+    #[test]
+    fn remediation_follows_category() {
+        use crate::error::remediation::remediation_for;
+
+        assert_eq!(remediation_for(8, "etcdserver: mvcc: database space exceeded").unwrap().code, "NOSPACE");
+        assert_eq!(remediation_for(16, "etcdserver: invalid auth token").unwrap().code, "UNAUTHENTICATED");
+        assert_eq!(remediation_for(5, "etcdserver: requested lease not found").unwrap().code, "LEASE_NOT_FOUND");
+        assert_eq!(remediation_for(14, "etcdserver: no leader"), None);
+    }
+}
+
+mod test_member_endpoints {
+    use crate::etcd::etcd_connector::member_endpoint_addresses;
+
+    #[test]
+    fn addresses_from_member_urls() {
+        let urls = [
+            "https://10.0.0.1:2379",
+            "http://10.0.0.2:2379",
+            "http://10.0.0.2:2379/",
+            "https://[fd00::3]:2379",
+            "unix:///run/etcd.sock",
+        ];
+        let addresses: Vec<String> = member_endpoint_addresses(&urls, "10.0.0.1:2379")
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        assert_eq!(addresses, vec!["10.0.0.2:2379", "[fd00::3]:2379"]);
+    }
+}
+
+mod test_watch_throttle {
+    use std::time::Duration;
+
+    use crate::etcd::watch_throttle::WatchThrottle;
+    use crate::transport::kv::WatchKeyEvent;
+
+    fn event(key: &str, mod_revision: i64) -> WatchKeyEvent {
+        WatchKeyEvent {
+            key: String::from(key),
+            op: String::from("put"),
+            mod_revision,
+            version: 1,
+            lease: 0,
+            value: Some(mod_revision.to_string()),
+            raw_value: None,
+        }
+    }
+
+    #[test]
+    fn coalesce_and_limit() {
+        //  每批最多 10 * 200 / 1000 = 2 个事件
+        let mut throttle = WatchThrottle::new(Duration::from_millis(200), 10);
+        assert_eq!(throttle.batch_limit(), 2);
+        assert!(throttle.flush(1, "/app").is_none());
+
+        throttle.push(event("/app/a", 1));
+        throttle.push(event("/app/a", 2));
+        throttle.push(event("/app/b", 3));
+        let batch = throttle.flush(1, "/app").unwrap();
+        assert_eq!(batch.merged, 1);
+        assert_eq!(batch.dropped, 0);
+        assert_eq!(batch.events.len(), 2);
+        assert_eq!(batch.events[0].value.as_deref(), Some("2"));
+
+        throttle.push(event("/app/c", 6));
+        throttle.push(event("/app/d", 4));
+        throttle.push(event("/app/e", 5));
+        let batch = throttle.flush(1, "/app").unwrap();
+        assert_eq!(batch.dropped, 1);
+        let keys: Vec<&str> = batch.events.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, vec!["/app/e", "/app/c"]);
+        assert_eq!(batch.total_merged, 1);
+        assert_eq!(batch.total_dropped, 1);
+    }
+}
+
+mod test_poll_scheduler {
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    use crate::etcd::poll_scheduler::resolve_interval;
+    use crate::transport::settings::{PollTask, SettingConfig};
+
+    #[test]
+    fn resolve_poll_interval() {
+        let mut settings = SettingConfig::default();
+        settings.health_check_interval_seconds = 30;
+        assert_eq!(resolve_interval(PollTask::HealthCheck, &settings, None, true), Duration::from_secs(30));
+        assert_eq!(resolve_interval(PollTask::LatencySample, &settings, None, true), Duration::from_secs(5));
+
+        settings.poll_interval_seconds.insert(PollTask::LatencySample, 10);
+        let overrides = BTreeMap::from([(PollTask::LatencySample, 20), (PollTask::HealthCheck, 1)]);
+        assert_eq!(resolve_interval(PollTask::LatencySample, &settings, None, true), Duration::from_secs(10));
+        assert_eq!(resolve_interval(PollTask::LatencySample, &settings, Some(&overrides), true), Duration::from_secs(20));
+        //  不低于任务的最小间隔
+        assert_eq!(resolve_interval(PollTask::HealthCheck, &settings, Some(&overrides), true), Duration::from_secs(5));
+
+        settings.unfocused_poll_factor = 3;
+        assert_eq!(resolve_interval(PollTask::LatencySample, &settings, None, false), Duration::from_secs(30));
+        settings.adaptive_polling = false;
+        assert_eq!(resolve_interval(PollTask::LatencySample, &settings, None, false), Duration::from_secs(10));
+    }
+}
+
+mod test_snapshot_transfer {
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use crate::error::LogicError;
+    use crate::etcd::snapshot_transfer::{eta_seconds, part_path, SnapshotWriter, ThroughputMeter};
+
+    #[test]
+    fn snapshot_throughput() {
+        let mut meter = ThroughputMeter::default();
+        meter.record(Duration::from_secs(1), 1000);
+        assert_eq!(meter.instant(Duration::from_secs(1)), 1000.0);
+
+        meter.record(Duration::from_secs(2), 2000);
+        meter.record(Duration::from_secs(5), 3000);
+        //  窗口内只剩后两个分片
+        assert_eq!(meter.instant(Duration::from_secs(5)), 5000.0 / 3.0);
+        assert_eq!(meter.average(Duration::from_secs(5)), 1200.0);
+        assert_eq!(meter.average(Duration::ZERO), 0.0);
+
+        assert_eq!(eta_seconds(1000, 300.0), Some(4));
+        assert_eq!(eta_seconds(1000, 0.0), None);
+    }
+
+    /// 依次写入两个快照流，返回最终的文件内容
+    async fn write_streams(name: &str, first: &[&str], second: &[&str]) -> Result<String, LogicError> {
+        let path = std::env::temp_dir().join(format!("etcd-workbench-test-{}.db", name));
+        let mut writer = SnapshotWriter::create(&path).await?;
+        for chunk in first {
+            writer.write(chunk.as_bytes()).await?;
+        }
+        writer.restart();
+        for chunk in second {
+            writer.write(chunk.as_bytes()).await?;
+        }
+        writer.finish().await?;
+        assert_eq!(writer.position() as usize, second.concat().len());
+        drop(writer);
+
+        let content = std::fs::read_to_string(&path)?;
+        std::fs::remove_file(&path)?;
+        Ok(content)
+    }
+
+    #[tokio::test]
+    async fn snapshot_writer_resume() -> Result<(), LogicError> {
+        //  新的快照与已写入的内容一致，分片大小不同也能比对
+        let content = write_streams("same", &["hello ", "wor"], &["hel", "lo world"]).await?;
+        assert_eq!(content, "hello world");
+
+        //  在分片中间出现不一致，从不一致的分片开始覆盖
+        let content = write_streams("diverge", &["hello ", "world"], &["hello th", "ere"]).await?;
+        assert_eq!(content, "hello there");
+
+        //  新的快照更短时截掉多余的旧内容
+        let content = write_streams("shorter", &["hello world"], &["hello"]).await?;
+        assert_eq!(content, "hello");
+        Ok(())
+    }
+
+    #[test]
+    fn snapshot_part_path() {
+        let path = PathBuf::from("/tmp/backup/etcd.db");
+        assert_eq!(part_path(&path), PathBuf::from("/tmp/backup/etcd.db.part"));
+    }
+}
+
+mod test_session_lock {
+    use crate::etcd::session_lock::{hash_master_password, verify_master_password};
+
+    #[test]
+    fn master_password_hash() {
+        let hash = hash_master_password("correct horse");
+        assert!(hash.starts_with("pbkdf2-sha256$"));
+        assert!(verify_master_password("correct horse", &hash));
+        assert!(!verify_master_password("wrong horse", &hash));
+        assert!(!verify_master_password("correct horse", "plain"));
+        //  每次生成不同的盐
+        assert_ne!(hash, hash_master_password("correct horse"));
+    }
+}
+
+mod test_operation_queue {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use crate::etcd::operation_queue::{acquire, list, remove_session};
+    use crate::transport::maintenance::MaintenanceOperation;
+
+    #[tokio::test]
+    async fn serialize_operations() {
+        let session = -197;
+        let first = acquire(session, MaintenanceOperation::Defragment, |_| {}).await.unwrap();
+
+        let positions = Arc::new(Mutex::new(Vec::new()));
+        let reported = positions.clone();
+        let second = tokio::spawn(async move {
+            acquire(session, MaintenanceOperation::Snapshot, |ahead| reported.lock().unwrap().push(ahead)).await
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(list(session).len(), 2);
+        assert_eq!(*positions.lock().unwrap(), vec![1]);
+
+        drop(first);
+        let second = second.await.unwrap().unwrap();
+        assert_eq!(list(session)[0].operation, MaintenanceOperation::Snapshot);
+        drop(second);
+        assert!(list(session).is_empty());
+
+        //  会话断开时等待中的操作返回错误
+        let _running = acquire(session, MaintenanceOperation::Compact, |_| {}).await.unwrap();
+        let waiting = tokio::spawn(async move { acquire(session, MaintenanceOperation::Defragment, |_| {}).await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        remove_session(session);
+        assert!(waiting.await.unwrap().is_err());
+    }
+}
+
+mod test_content_hint {
+    use crate::etcd::content_hint::{hint_key, is_hint_key, is_hint_key_raw};
+    use crate::transport::connection::{ContentHintConfig, ContentHintStorage};
+
+    #[test]
+    fn storage_key() {
+        let sibling = ContentHintConfig {
+            storage: ContentHintStorage::Sibling,
+            prefix: None,
+        };
+        assert_eq!(hint_key(&sibling, "/app/config"), "/app/config.__meta");
+        assert!(is_hint_key(&sibling, "/app/config.__meta"));
+        assert!(!is_hint_key(&sibling, "/app/config"));
+        assert!(is_hint_key_raw(&sibling, b"/app/config.__meta"));
+        assert!(!is_hint_key_raw(&sibling, &[0xff, b'.']));
+
+        let prefix = ContentHintConfig {
+            storage: ContentHintStorage::Prefix,
+            prefix: Some(String::from("/meta")),
+        };
+        assert_eq!(hint_key(&prefix, "/a"), "/meta/L2E");
+        assert!(is_hint_key(&prefix, "/meta/L2E"));
+        assert!(!is_hint_key(&prefix, "/a"));
+
+        let default_prefix = ContentHintConfig {
+            storage: ContentHintStorage::Prefix,
+            prefix: None,
+        };
+        assert!(hint_key(&default_prefix, "/a").starts_with("/.etcd-workbench/content-hints/"));
+    }
+}
+
+mod test_sandbox {
+    use crate::etcd::sandbox::{find_checksum, validate_version};
+
+    #[test]
+    fn version() {
+        assert_eq!(validate_version("v3.5.17").unwrap(), "3.5.17");
+        assert_eq!(validate_version("3.6.0-rc.1").unwrap(), "3.6.0-rc.1");
+        assert!(validate_version("../../3.5.17").is_err());
+        assert!(validate_version("3.5").is_err());
+        assert!(validate_version("3.5.17/../x").is_err());
+        assert!(validate_version("3.5.17-").is_err());
+    }
+
+    #[test]
+    fn checksum() {
+        let sums = "AB12  etcd-v3.5.17-linux-amd64.tar.gz\ncd34 *etcd-v3.5.17-darwin-arm64.zip\n";
+        assert_eq!(find_checksum(sums, "etcd-v3.5.17-linux-amd64.tar.gz").as_deref(), Some("ab12"));
+        assert_eq!(find_checksum(sums, "etcd-v3.5.17-darwin-arm64.zip").as_deref(), Some("cd34"));
+        assert_eq!(find_checksum(sums, "etcd-v3.5.17-windows-amd64.zip"), None);
+    }
+}
+
+mod test_prefix_counter {
+    use etcd_client::EventType;
+
+    use crate::etcd::prefix_counter::count_delta;
+
+    #[test]
+    fn count_events() {
+        //  新建、修改、删除
+        assert_eq!(count_delta([(EventType::Put, 1)].into_iter()), 1);
+        assert_eq!(count_delta([(EventType::Put, 3)].into_iter()), 0);
+        assert_eq!(count_delta([(EventType::Delete, 0)].into_iter()), -1);
+
+        //  同一响应中删除后重新创建的key版本重新从1开始
+        let events = [
+            (EventType::Put, 1),
+            (EventType::Put, 1),
+            (EventType::Put, 2),
+            (EventType::Delete, 0),
+            (EventType::Put, 1),
+        ];
+        assert_eq!(count_delta(events.into_iter()), 2);
+        assert_eq!(count_delta(std::iter::empty()), 0);
+    }
+}
@@ -0,0 +1,88 @@
+use std::ops::Range;
+
+use crate::transport::connection::Connection;
+
+/// etcd `--max-txn-ops` 的默认值
+pub const DEFAULT_MAX_TXN_OPS: usize = 128;
+/// etcd `--max-request-bytes` 的默认值
+pub const DEFAULT_MAX_REQUEST_BYTES: usize = 1536 * 1024;
+/// 每个操作在请求中除key和value外的额外开销估算
+const OP_OVERHEAD_BYTES: usize = 32;
+/// 为请求头等预留的空间
+const REQUEST_RESERVED_BYTES: usize = 4 * 1024;
+
+/// 服务端对单个事务的限制，etcd 不提供查询接口，优先使用连接中的配置，否则使用默认值
+#[derive(Debug, Clone, Copy)]
+pub struct TxnLimits {
+    pub max_ops: usize,
+    pub max_request_bytes: usize,
+}
+
+impl Default for TxnLimits {
+    fn default() -> Self {
+        TxnLimits {
+            max_ops: DEFAULT_MAX_TXN_OPS,
+            max_request_bytes: DEFAULT_MAX_REQUEST_BYTES,
+        }
+    }
+}
+
+impl TxnLimits {
+    pub fn from_connection(connection: &Connection) -> Self {
+        TxnLimits {
+            max_ops: connection.max_txn_ops.filter(|v| *v > 0).unwrap_or(DEFAULT_MAX_TXN_OPS),
+            max_request_bytes: connection
+                .max_request_bytes
+                .filter(|v| *v > 0)
+                .unwrap_or(DEFAULT_MAX_REQUEST_BYTES),
+        }
+    }
+
+    fn op_bytes(key: &[u8], value: &[u8]) -> usize {
+        key.len() + value.len() + OP_OVERHEAD_BYTES
+    }
+
+    /// 单个请求中可用于操作的字节数
+    fn payload_bytes(&self) -> usize {
+        self.max_request_bytes.saturating_sub(REQUEST_RESERVED_BYTES).max(1)
+    }
+
+    /// 将键值对按顺序切分为多个批次，每批的操作数和请求大小都不超过限制。
+    /// 单个键值对就超过请求大小限制时返回该key
+    pub fn split(&self, kvs: &[(Vec<u8>, Vec<u8>)]) -> Result<Vec<Range<usize>>, Vec<u8>> {
+        let max_ops = self.max_ops.max(1);
+        let payload = self.payload_bytes();
+        let mut batches = Vec::new();
+        let mut start = 0;
+        let mut bytes = 0;
+        for (i, (key, value)) in kvs.iter().enumerate() {
+            let size = Self::op_bytes(key, value);
+            if size > payload {
+                return Err(key.clone());
+            }
+            if i > start && (i - start >= max_ops || bytes + size > payload) {
+                batches.push(start..i);
+                start = i;
+                bytes = 0;
+            }
+            bytes += size;
+        }
+        if start < kvs.len() {
+            batches.push(start..kvs.len());
+        }
+        Ok(batches)
+    }
+}
+
+/// 判断是否为超出事务限制导致的错误：操作数过多或请求过大
+pub fn is_limit_exceeded(e: &etcd_client::Error) -> bool {
+    match e {
+        etcd_client::Error::GRpcStatus(status) => {
+            //  8: ResourceExhausted
+            status.code() as i32 == 8
+                || status.message().contains("too many operations in txn request")
+                || status.message().contains("request is too large")
+        }
+        _ => false,
+    }
+}
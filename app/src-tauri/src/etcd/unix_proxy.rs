@@ -0,0 +1,70 @@
+use log::{debug, warn};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+use crate::error::LogicError;
+
+/// 将本地随机端口转发到unix socket，etcd客户端只能连接TCP地址
+pub struct UnixSocketProxy {
+    proxy_port: u16,
+    accept_task: JoinHandle<()>,
+}
+
+impl UnixSocketProxy {
+    #[cfg(unix)]
+    pub async fn new(path: &str) -> Result<Self, LogicError> {
+        use tokio::net::UnixStream;
+
+        //  先连接一次，尽早发现路径或权限错误
+        UnixStream::connect(path)
+            .await
+            .map_err(|e| LogicError::IllegalArgument(format!("Cannot connect to unix socket {}: {}", path, e)))?;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let proxy_port = listener.local_addr()?.port();
+        let path = path.to_string();
+        debug!("Unix socket proxy of {} listens on local port {}", path, proxy_port);
+        let accept_task = tokio::spawn(async move {
+            loop {
+                let mut stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        warn!("Unix socket proxy listener error: {e}");
+                        break;
+                    }
+                };
+                let path = path.clone();
+                tokio::spawn(async move {
+                    match UnixStream::connect(&path).await {
+                        Ok(mut socket) => {
+                            let _ = tokio::io::copy_bidirectional(&mut stream, &mut socket).await;
+                        }
+                        Err(e) => warn!("Failed to connect to unix socket {}: {}", path, e),
+                    }
+                });
+            }
+        });
+        Ok(UnixSocketProxy {
+            proxy_port,
+            accept_task,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub async fn new(path: &str) -> Result<Self, LogicError> {
+        Err(LogicError::IllegalArgument(format!(
+            "Unix socket {} is not supported on this platform",
+            path
+        )))
+    }
+
+    pub fn get_proxy_port(&self) -> u16 {
+        self.proxy_port
+    }
+}
+
+impl Drop for UnixSocketProxy {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+    }
+}
@@ -0,0 +1,141 @@
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use etcd_client::{WatchStream, Watcher};
+use log::{debug, info, warn};
+use tokio::select;
+use tokio::sync::oneshot;
+
+use crate::transport::kv::{SerializableKeyValue, ValueCacheStats};
+
+/// 缓存的最大key数量，超出后清空重新缓存
+const MAX_CACHE_ENTRIES: usize = 2000;
+
+#[derive(Default)]
+struct CacheState {
+    /// 以带命名空间的完整key为索引，同时记录值的修改版本
+    entries: DashMap<Vec<u8>, (i64, SerializableKeyValue)>,
+    /// watch 已处理到的版本
+    watched_revision: AtomicI64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    /// watch 已断开，缓存不再可信
+    closed: AtomicBool,
+}
+
+/// 读穿透的键值缓存，重复读取同一个key时直接从内存返回，
+/// 后台监听整个键空间，key发生变化时失效对应的缓存
+pub struct ValueCache {
+    state: Arc<CacheState>,
+    stop_notifier: Option<oneshot::Sender<()>>,
+}
+
+impl ValueCache {
+    pub fn start(mut watcher: Watcher, mut stream: WatchStream) -> Self {
+        let (stop_sender, mut stop_receiver) = oneshot::channel::<()>();
+        let state = Arc::new(CacheState::default());
+        let task_state = Arc::clone(&state);
+
+        tokio::spawn(async move {
+            info!("Value cache watcher started");
+            loop {
+                let message = select! {
+                    message = stream.message() => message,
+                    _ = &mut stop_receiver => break,
+                };
+
+                let response = match message {
+                    Ok(Some(response)) => response,
+                    Ok(None) => break,
+                    Err(e) => {
+                        warn!("Value cache watch error: {e}");
+                        break;
+                    }
+                };
+
+                //  先推进已处理的版本再失效缓存，读取中的旧值无法在失效后重新写入
+                if let Some(header) = response.header() {
+                    task_state.watched_revision.fetch_max(header.revision(), Ordering::SeqCst);
+                }
+                for event in response.events() {
+                    if let Some(kv) = event.kv() {
+                        task_state.watched_revision.fetch_max(kv.mod_revision(), Ordering::SeqCst);
+                        task_state
+                            .entries
+                            .remove_if(kv.key(), |_, (revision, _)| *revision < kv.mod_revision());
+                    }
+                }
+            }
+            //  无法再收到变更通知，清空缓存并停止使用
+            task_state.closed.store(true, Ordering::SeqCst);
+            task_state.entries.clear();
+            let _ = watcher.cancel().await;
+            debug!("Value cache watcher stopped");
+        });
+
+        ValueCache {
+            state,
+            stop_notifier: Some(stop_sender),
+        }
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<SerializableKeyValue> {
+        if self.state.closed.load(Ordering::SeqCst) {
+            return None;
+        }
+        match self.state.entries.get(key) {
+            Some(kv) => {
+                self.state.hits.fetch_add(1, Ordering::Relaxed);
+                Some(kv.value().1.clone())
+            }
+            None => {
+                self.state.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// 读取到的值的版本为 `revision`，若 watch 已经处理过更新的版本，则期间可能有变更事件已被错过，不缓存
+    pub fn put(&self, key: Vec<u8>, revision: i64, kv: SerializableKeyValue) {
+        if self.state.closed.load(Ordering::SeqCst)
+            || revision < self.state.watched_revision.load(Ordering::SeqCst)
+        {
+            return;
+        }
+        if self.state.entries.len() >= MAX_CACHE_ENTRIES {
+            self.state.entries.clear();
+        }
+        let mod_revision = kv.mod_revision;
+        self.state.entries.insert(key.clone(), (mod_revision, kv));
+        //  写入期间 watch 推进了版本，对应的失效事件可能已处理，放弃本次缓存
+        if revision < self.state.watched_revision.load(Ordering::SeqCst) {
+            self.state.entries.remove_if(&key, |_, (r, _)| *r == mod_revision);
+        }
+    }
+
+    pub fn invalidate(&self, key: &[u8]) {
+        self.state.entries.remove(key);
+    }
+
+    pub fn stats(&self) -> ValueCacheStats {
+        ValueCacheStats {
+            enabled: !self.state.closed.load(Ordering::SeqCst),
+            entries: self.state.entries.len(),
+            hits: self.state.hits.load(Ordering::Relaxed),
+            misses: self.state.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(sender) = self.stop_notifier.take() {
+            let _ = sender.send(());
+        }
+    }
+}
+
+impl Drop for ValueCache {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
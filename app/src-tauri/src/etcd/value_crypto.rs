@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use log::{info, warn};
+
+use crate::utils::aes_util;
+
+/// 加密值的标记前缀，后面跟随base64编码的 [nonce, 密文]
+pub const ENCRYPTED_VALUE_MARKER: &[u8] = b"$ewenc1$";
+/// 系统钥匙串中的服务名
+const KEYRING_SERVICE: &str = "Etcd Workbench";
+
+/// 客户端按前缀加密键值。每个前缀使用独立的 AES-256-GCM 密钥，密钥保存在系统钥匙串中，
+/// 写入时透明加密，读取时透明解密
+pub struct ValueCrypto {
+    /// 钥匙串账户名前缀，区分不同的集群和命名空间
+    account: String,
+    prefixes: Vec<String>,
+    keys: HashMap<String, Vec<u8>>,
+}
+
+impl ValueCrypto {
+    /// 未配置加密前缀时返回空
+    pub fn new(host: &str, port: u16, namespace: &Option<String>, prefixes: &[String]) -> Option<Self> {
+        let prefixes: Vec<String> = prefixes.iter().filter(|p| !p.is_empty()).cloned().collect();
+        if prefixes.is_empty() {
+            return None;
+        }
+        let namespace = namespace.as_deref().unwrap_or("");
+        Some(ValueCrypto {
+            account: format!("kv-encryption:{}:{}:{}", host, port, namespace),
+            prefixes,
+            keys: HashMap::new(),
+        })
+    }
+
+    pub fn is_encrypted(value: &[u8]) -> bool {
+        value.starts_with(ENCRYPTED_VALUE_MARKER)
+    }
+
+    /// 匹配key所属的加密前缀，多个前缀匹配时取最长的
+    fn match_prefix(&self, key: &[u8]) -> Option<String> {
+        self.prefixes
+            .iter()
+            .filter(|p| key.starts_with(p.as_bytes()))
+            .max_by_key(|p| p.len())
+            .cloned()
+    }
+
+    fn check_prefix(&self, prefix: &str) -> Result<(), String> {
+        if self.prefixes.iter().any(|p| p == prefix) {
+            Ok(())
+        } else {
+            Err(format!("Prefix is not configured for encryption: {}", prefix))
+        }
+    }
+
+    fn entry(&self, prefix: &str) -> Result<keyring::Entry, String> {
+        keyring::Entry::new(KEYRING_SERVICE, &format!("{}:{}", self.account, prefix))
+            .map_err(|e| format!("Failed to open keyring: {e}"))
+    }
+
+    /// 从钥匙串读取前缀的密钥，`create` 为true时不存在则生成新密钥
+    fn load_key(&mut self, prefix: &str, create: bool) -> Result<Option<Vec<u8>>, String> {
+        if let Some(key) = self.keys.get(prefix) {
+            return Ok(Some(key.clone()));
+        }
+
+        let entry = self.entry(prefix)?;
+        let key = match entry.get_password() {
+            Ok(encoded) => BASE64_STANDARD
+                .decode(encoded)
+                .map_err(|e| format!("Invalid encryption key in keyring: {e}"))?,
+            Err(keyring::Error::NoEntry) if create => {
+                let key = aes_util::generate_key_256();
+                entry
+                    .set_password(&BASE64_STANDARD.encode(&key))
+                    .map_err(|e| format!("Failed to save encryption key: {e}"))?;
+                info!("Generated encryption key for prefix: {}", prefix);
+                key
+            }
+            Err(keyring::Error::NoEntry) => return Ok(None),
+            Err(e) => return Err(format!("Failed to read encryption key: {e}")),
+        };
+        self.keys.insert(String::from(prefix), key.clone());
+        Ok(Some(key))
+    }
+
+    /// 清除内存中缓存的密钥，之后使用时重新从钥匙串读取
+    pub fn clear_keys(&mut self) {
+        self.keys.clear();
+    }
+
+    /// 如果key属于加密前缀，返回加密后的值，否则原样返回
+    pub fn encrypt(&mut self, key: &[u8], value: Vec<u8>) -> Result<Vec<u8>, String> {
+        let Some(prefix) = self.match_prefix(key) else {
+            return Ok(value);
+        };
+        let secret = self.load_key(&prefix, true)?.unwrap();
+        let encrypted = aes_util::encrypt_gcm_256(&secret, &value).map_err(|e| e.to_string())?;
+
+        let mut result = Vec::from(ENCRYPTED_VALUE_MARKER);
+        result.extend(BASE64_STANDARD.encode(encrypted).into_bytes());
+        Ok(result)
+    }
+
+    /// 解密带有加密标记的值，未加密的值返回空
+    pub fn decrypt(&mut self, key: &[u8], value: &[u8]) -> Result<Option<Vec<u8>>, String> {
+        if !Self::is_encrypted(value) {
+            return Ok(None);
+        }
+        let prefix = self
+            .match_prefix(key)
+            .ok_or_else(|| String::from("The key is not under any encrypted prefix"))?;
+        let secret = self
+            .load_key(&prefix, false)?
+            .ok_or_else(|| format!("No encryption key found for prefix: {}", prefix))?;
+
+        let encrypted = BASE64_STANDARD
+            .decode(&value[ENCRYPTED_VALUE_MARKER.len()..])
+            .map_err(|e| format!("Invalid encrypted value: {e}"))?;
+        let decrypted = aes_util::decrypt_gcm_256(&secret, &encrypted).map_err(|e| {
+            warn!("Failed to decrypt value of prefix {}: {}", prefix, e);
+            String::from("Failed to decrypt value, the encryption key may be wrong")
+        })?;
+        Ok(Some(decrypted))
+    }
+
+    /// 导出前缀的密钥（base64），用于分享给其他成员
+    pub fn export_key(&mut self, prefix: &str) -> Result<String, String> {
+        self.check_prefix(prefix)?;
+        let key = self.load_key(prefix, true)?.unwrap();
+        Ok(BASE64_STANDARD.encode(key))
+    }
+
+    /// 导入前缀的密钥（base64）并保存到钥匙串
+    pub fn import_key(&mut self, prefix: &str, encoded: &str) -> Result<(), String> {
+        self.check_prefix(prefix)?;
+        let key = BASE64_STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| format!("Invalid encryption key: {e}"))?;
+        if key.len() != aes_util::LENGTH_32 {
+            return Err(format!("Invalid encryption key length: {}, expect: {}", key.len(), aes_util::LENGTH_32));
+        }
+        self.entry(prefix)?
+            .set_password(&BASE64_STANDARD.encode(&key))
+            .map_err(|e| format!("Failed to save encryption key: {e}"))?;
+        self.keys.insert(String::from(prefix), key);
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use log::{debug, info, warn};
+use tauri::{AppHandle, Manager};
+use tokio::time::{interval, MissedTickBehavior};
+
+use crate::api::event_bus::{self, EventStream};
+use crate::transport::connection::SessionReconnected;
+
+use super::{list_session_windows, reconnect_session};
+
+/// 检查间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// 两次检查之间多出的时间超过该值时认为系统经历了休眠
+const SLEEP_THRESHOLD: Duration = Duration::from_secs(30);
+
+/// 检测系统休眠唤醒，唤醒后重建所有连接的SSH隧道、etcd客户端和监听。
+///
+/// 部分平台的单调时钟在休眠时停止计时，因此同时比较墙上时间和单调时钟的间隔
+pub fn start(app: AppHandle) {
+    tokio::spawn(async move {
+        let mut timer = interval(CHECK_INTERVAL);
+        timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut last_wall = SystemTime::now();
+        let mut last_instant = Instant::now();
+
+        loop {
+            timer.tick().await;
+            let wall_elapsed = SystemTime::now().duration_since(last_wall).unwrap_or_default();
+            let instant_elapsed = last_instant.elapsed();
+            last_wall = SystemTime::now();
+            last_instant = Instant::now();
+
+            let elapsed = wall_elapsed.max(instant_elapsed);
+            if elapsed < CHECK_INTERVAL + SLEEP_THRESHOLD {
+                continue;
+            }
+            info!("System resumed after about {} seconds, reconnecting sessions", elapsed.as_secs());
+            reconnect_all(&app).await;
+        }
+    });
+}
+
+async fn reconnect_all(app: &AppHandle) {
+    for (session, label) in list_session_windows() {
+        let Some(window) = app.get_window(&label) else {
+            continue;
+        };
+        let error_msg = match reconnect_session(session, window).await {
+            Ok(_) => {
+                debug!("Session reconnected after wake: {}", session);
+                None
+            }
+            Err(e) => {
+                warn!("Failed to reconnect session after wake: {}, {:?}", session, e);
+                Some(format!("{:?}", e))
+            }
+        };
+        let event = SessionReconnected { session, error_msg };
+        event_bus::publish_to(app, &label, EventStream::Watch, "session_reconnected", event);
+    }
+}
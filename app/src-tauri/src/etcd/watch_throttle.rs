@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use etcd_client::{Event, EventType, WatchStream, Watcher};
+use log::{debug, info, warn};
+use tauri::Window;
+use tokio::select;
+use tokio::sync::oneshot;
+use tokio::time::{interval, MissedTickBehavior};
+
+use crate::api::event_bus::{self, EventStream};
+use crate::transport::kv::{WatchEventBatch, WatchKeyEvent};
+
+/// 合并和限流监听事件，每个刷新周期内同一key只保留最新的事件，
+/// 每批最多推送 `每秒上限 * 刷新间隔` 个事件，超出的事件丢弃并计数
+pub struct WatchThrottle {
+    pending: HashMap<String, WatchKeyEvent>,
+    flush_interval: Duration,
+    batch_limit: usize,
+    merged: u64,
+    total_merged: u64,
+    total_dropped: u64,
+}
+
+impl WatchThrottle {
+    pub fn new(flush_interval: Duration, max_events_per_second: u64) -> Self {
+        let batch_limit = (max_events_per_second as u128 * flush_interval.as_millis() / 1000).max(1) as usize;
+        WatchThrottle {
+            pending: HashMap::new(),
+            flush_interval,
+            batch_limit,
+            merged: 0,
+            total_merged: 0,
+            total_dropped: 0,
+        }
+    }
+
+    pub fn flush_interval(&self) -> Duration {
+        self.flush_interval
+    }
+
+    pub fn batch_limit(&self) -> usize {
+        self.batch_limit
+    }
+
+    /// 加入一个事件，覆盖同一key尚未推送的事件
+    pub fn push(&mut self, event: WatchKeyEvent) {
+        if self.pending.insert(event.key.clone(), event).is_some() {
+            self.merged += 1;
+        }
+    }
+
+    /// 取出本周期的事件批次，没有事件时返回空。超出批次上限时保留修改版本最新的事件
+    pub fn flush(&mut self, session: i32, prefix: &str) -> Option<WatchEventBatch> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let mut events: Vec<WatchKeyEvent> = self.pending.drain().map(|(_, event)| event).collect();
+        events.sort_by_key(|event| event.mod_revision);
+        let dropped = events.len().saturating_sub(self.batch_limit);
+        events.drain(..dropped);
+
+        let merged = std::mem::take(&mut self.merged);
+        self.total_merged += merged;
+        self.total_dropped += dropped as u64;
+        Some(WatchEventBatch {
+            session,
+            prefix: String::from(prefix),
+            events,
+            merged,
+            dropped: dropped as u64,
+            total_merged: self.total_merged,
+            total_dropped: self.total_dropped,
+        })
+    }
+}
+
+/// 将etcd事件转为推送给前端的事件，key去掉命名空间
+pub fn to_key_event(event: &Event, namespace: Option<&str>) -> Option<WatchKeyEvent> {
+    let kv = event.kv()?;
+    let mut key = String::from_utf8_lossy(kv.key()).to_string();
+    if let Some(k) = namespace.and_then(|namespace| key.strip_prefix(namespace)) {
+        key = String::from(k);
+    }
+    let deleted = event.event_type() == EventType::Delete;
+    let (value, raw_value) = if deleted {
+        (None, None)
+    } else {
+        match std::str::from_utf8(kv.value()) {
+            Ok(value) => (Some(value.to_string()), None),
+            Err(_) => (None, Some(BASE64_STANDARD.encode(kv.value()))),
+        }
+    };
+    Some(WatchKeyEvent {
+        key,
+        op: String::from(if deleted { "delete" } else { "put" }),
+        mod_revision: kv.mod_revision(),
+        version: kv.version(),
+        lease: kv.lease(),
+        value,
+        raw_value,
+    })
+}
+
+/// 前缀事件订阅，监听到的事件经过合并和限流后通过 `watch_events` 事件批量推送给窗口
+pub struct PrefixEventSubscription {
+    session_id: i32,
+    prefix: String,
+    stop_notifier: Option<oneshot::Sender<()>>,
+}
+
+impl PrefixEventSubscription {
+    pub fn start(
+        session_id: i32,
+        prefix: String,
+        namespace: Option<String>,
+        mut watcher: Watcher,
+        mut stream: WatchStream,
+        window: Window,
+        mut throttle: WatchThrottle,
+    ) -> Self {
+        let (stop_sender, mut stop_receiver) = oneshot::channel::<()>();
+
+        let subscription_prefix = prefix.clone();
+        tokio::spawn(async move {
+            info!(
+                "Prefix event subscription started: {}, {}, batch limit {}",
+                session_id,
+                prefix,
+                throttle.batch_limit()
+            );
+            let mut timer = interval(throttle.flush_interval());
+            timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                let message = select! {
+                    message = stream.message() => message,
+                    _ = timer.tick() => {
+                        if let Some(batch) = throttle.flush(session_id, &prefix) {
+                            event_bus::publish(&window, EventStream::Watch, "watch_events", batch);
+                        }
+                        continue;
+                    },
+                    _ = &mut stop_receiver => break,
+                };
+
+                let response = match message {
+                    Ok(Some(response)) => response,
+                    Ok(None) => break,
+                    Err(e) => {
+                        warn!("Prefix event watch error: {e}");
+                        break;
+                    }
+                };
+
+                for event in response.events() {
+                    if let Some(event) = to_key_event(event, namespace.as_deref()) {
+                        throttle.push(event);
+                    }
+                }
+            }
+            //  推送停止前已收到的事件
+            if let Some(batch) = throttle.flush(session_id, &prefix) {
+                event_bus::publish(&window, EventStream::Watch, "watch_events", batch);
+            }
+            let _ = watcher.cancel().await;
+            debug!("Prefix event subscription stopped: {}, {}", session_id, prefix);
+        });
+
+        PrefixEventSubscription {
+            session_id,
+            prefix: subscription_prefix,
+            stop_notifier: Some(stop_sender),
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(sender) = self.stop_notifier.take() {
+            let _ = sender.send(());
+        }
+        debug!("Stop prefix event subscription: {}, {}", self.session_id, self.prefix);
+    }
+}
+
+impl Drop for PrefixEventSubscription {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
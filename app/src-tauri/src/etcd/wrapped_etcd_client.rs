@@ -1,51 +1,203 @@
 use etcd_client::{
-    AlarmAction, AlarmOptions, AlarmResponse, AlarmType, AuthDisableResponse, AuthEnableResponse, DefragmentResponse, DeleteOptions, DeleteResponse, GetOptions, GetResponse, LeaseGrantOptions, LeaseGrantResponse, LeaseLeasesResponse, LeaseRevokeResponse, LeaseTimeToLiveOptions, LeaseTimeToLiveResponse, MemberAddOptions, MemberAddResponse, MemberListResponse, MemberRemoveResponse, MemberUpdateResponse, Permission, PutOptions, PutResponse, RoleAddResponse, RoleDeleteResponse, RoleGetResponse, RoleGrantPermissionResponse, RoleListResponse, RoleRevokePermissionOptions, RoleRevokePermissionResponse, SnapshotStreaming, StatusResponse, UserAddOptions, UserAddResponse, UserChangePasswordResponse, UserDeleteResponse, UserGetResponse, UserGrantRoleResponse, UserListResponse, UserRevokeRoleResponse
+    AlarmAction, AlarmOptions, AlarmResponse, AlarmType, AuthDisableResponse, AuthEnableResponse, CompactionOptions, CompactionResponse, DefragmentResponse, DeleteOptions, DeleteResponse, GetOptions, GetResponse, LeaseGrantOptions, LeaseGrantResponse, LeaseKeepAliveStream, LeaseKeeper, LeaseLeasesResponse, LeaseRevokeResponse, LeaseTimeToLiveOptions, LeaseTimeToLiveResponse, MemberAddOptions, MemberAddResponse, MemberListResponse, MemberPromoteResponse, MemberRemoveResponse, MemberUpdateResponse, Permission, PutOptions, PutResponse, RoleAddResponse, RoleDeleteResponse, RoleGetResponse, RoleGrantPermissionResponse, RoleListResponse, RoleRevokePermissionOptions, RoleRevokePermissionResponse, SnapshotStreaming, StatusResponse, Txn, TxnResponse, UserAddOptions, UserAddResponse, UserChangePasswordResponse, UserDeleteResponse, UserGetResponse, UserGrantRoleResponse, UserListResponse, UserRevokeRoleResponse, WatchOptions, WatchStream, Watcher, KvClient, WatchClient
 };
 
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::debug;
+use tonic::Status;
+
+use crate::etcd::retry::RetryPolicy;
 use crate::transport::connection::ConnectionUser;
 
+/// 执行幂等请求，失败时按 `self.retry` 策略等待后重试
+macro_rules! retry_idempotent {
+    ($self:ident, $call:expr) => {{
+        let mut attempt = 0u32;
+        loop {
+            let result = $call;
+            match &result {
+                Err(e) => match $self.retry.next_delay(e, attempt) {
+                    Some(delay) => {
+                        debug!("Transient etcd error, retry after {:?}: {}", delay, e);
+                        tokio::time::sleep(delay).await;
+                        attempt += 1;
+                    }
+                    None => break result,
+                },
+                Ok(_) => break result,
+            }
+        }
+    }};
+}
+
+/// 未设置请求超时时间时使用的默认值
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 在 `timeout` 内等待请求完成，超时返回 DeadlineExceeded，与服务端超时的处理方式一致
+pub async fn deadline<T>(
+    timeout: Duration,
+    request: impl Future<Output = Result<T, etcd_client::Error>>,
+) -> Result<T, etcd_client::Error> {
+    match tokio::time::timeout(timeout, request).await {
+        Ok(result) => result,
+        Err(_) => Err(etcd_client::Error::GRpcStatus(Status::deadline_exceeded(format!(
+            "Request timed out after {} seconds",
+            timeout.as_secs()
+        )))),
+    }
+}
+
+/// token失效后重新认证时的回调，参数为认证失败的错误信息
+pub type ReauthListener = Arc<dyn Fn(Option<String>) + Send + Sync>;
+
 #[derive(Clone)]
 pub struct WrappedEtcdClient {
     inner: etcd_client::Client,
+    /// 键值和监听请求使用单独的客户端，以便设置消息大小限制
+    kv: KvClient,
+    watch_client: WatchClient,
     auth: Option<ConnectionUser>,
+    retry: RetryPolicy,
+    reauth_listener: Option<ReauthListener>,
+    /// 连接默认的请求超时时间
+    timeout: Duration,
+    /// 单次操作指定的超时时间，设置后覆盖 `timeout`
+    call_deadline: Option<Duration>,
+    /// 用于查看会话身份的token，重新认证后失效
+    identity_token: Option<String>,
 }
 
 impl WrappedEtcdClient {
     pub fn new(client: etcd_client::Client, auth: Option<ConnectionUser>) -> Self {
         WrappedEtcdClient {
+            kv: client.kv_client(),
+            watch_client: client.watch_client(),
             inner: client,
             auth,
+            retry: RetryPolicy::default(),
+            reauth_listener: None,
+            timeout: DEFAULT_TIMEOUT,
+            call_deadline: None,
+            identity_token: None,
         }
     }
 
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// 设置键值和监听请求的gRPC消息大小限制，为空时使用默认值
+    pub fn with_message_limits(mut self, max_recv_bytes: Option<usize>, max_send_bytes: Option<usize>) -> Self {
+        if let Some(limit) = max_recv_bytes {
+            self.kv = self.kv.max_decoding_message_size(limit);
+            self.watch_client = self.watch_client.max_decoding_message_size(limit);
+        }
+        if let Some(limit) = max_send_bytes {
+            self.kv = self.kv.max_encoding_message_size(limit);
+        }
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 设置之后请求的超时时间，为 None 时恢复使用连接默认的超时时间
+    pub fn set_call_deadline(&mut self, deadline: Option<Duration>) {
+        self.call_deadline = deadline;
+    }
+
+    /// 当前请求使用的超时时间
+    pub fn timeout(&self) -> Duration {
+        self.call_deadline.unwrap_or(self.timeout)
+    }
+
+    pub fn set_reauth_listener(&mut self, listener: ReauthListener) {
+        self.reauth_listener = Some(listener);
+    }
+
     pub async fn authenticate(&mut self) -> Result<(), etcd_client::Error> {
-        let auth = self.auth.clone();
-        if let Some(user) = auth {
-            self.inner
-                .set_client_auth(user.username, user.password)
-                .await
+        self.identity_token = None;
+        if let Some(user) = &self.auth {
+            let result = deadline(
+                self.timeout(),
+                self.inner.set_client_auth(user.username.clone(), user.password.clone()),
+            )
+            .await;
+            if let Some(listener) = &self.reauth_listener {
+                listener(result.as_ref().err().map(|e| e.to_string()));
+            }
+            result
         } else {
             Ok(())
         }
     }
 
+    /// 使用连接的用户申请一个新token，不影响当前客户端使用的token，未配置用户时返回 None
+    pub async fn issue_token(&self) -> Result<Option<String>, etcd_client::Error> {
+        match &self.auth {
+            Some(user) => {
+                let mut auth_client = self.inner.auth_client();
+                let response = deadline(
+                    self.timeout(),
+                    auth_client.authenticate(user.username.clone(), user.password.clone()),
+                )
+                .await?;
+                Ok(Some(String::from(response.token())))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// 当前会话的token，未配置用户时返回 None。
+    /// etcd-client 不提供读取客户端内部token的接口，这里只在首次查询或重新认证后申请一次并缓存，
+    /// 避免每次查询都在服务端产生新的token
+    pub async fn session_token(&mut self) -> Result<Option<String>, etcd_client::Error> {
+        if self.identity_token.is_none() {
+            self.identity_token = self.issue_token().await?;
+        }
+        Ok(self.identity_token.clone())
+    }
+
+    /// 清除认证用户和当前token，共享token的键值、监听客户端同样失效
+    pub fn clear_credentials(&mut self) {
+        self.identity_token = None;
+        self.auth = None;
+        self.inner.remove_client_auth();
+    }
+
+    /// 恢复认证用户并重新申请token
+    pub async fn restore_credentials(&mut self, auth: Option<ConnectionUser>) -> Result<(), etcd_client::Error> {
+        self.auth = auth;
+        self.authenticate().await
+    }
+
+    pub fn get_user(&self) -> Option<&ConnectionUser> {
+        self.auth.as_ref()
+    }
+
     pub fn get_inner(&self) -> &etcd_client::Client {
         &self.inner
     }
 
-    pub async fn kv_get_request(
+    async fn kv_get_request_once(
         &mut self,
         key: Vec<u8>,
         option: Option<GetOptions>,
     ) -> Result<GetResponse, etcd_client::Error> {
-        let result = self.inner.get(key.clone(), option.clone()).await;
+        let result = deadline(self.timeout(), self.kv.get(key.clone(), option.clone())).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.get(key, option).await;
+                    return deadline(self.timeout(), self.kv.get(key, option)).await;
                 }
             }
         }
@@ -59,7 +211,7 @@ impl WrappedEtcdClient {
         option: Option<PutOptions>,
     ) -> Result<PutResponse, etcd_client::Error> {
         let result = self
-            .inner
+            .kv
             .put(key.clone(), value.clone(), option.clone())
             .await;
 
@@ -68,7 +220,7 @@ impl WrappedEtcdClient {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.put(key, value, option).await;
+                    return deadline(self.timeout(), self.kv.put(key, value, option)).await;
                 }
             }
         }
@@ -80,29 +232,63 @@ impl WrappedEtcdClient {
         key: Vec<u8>,
         option: Option<DeleteOptions>,
     ) -> Result<DeleteResponse, etcd_client::Error> {
-        let result = self.inner.delete(key.clone(), option.clone()).await;
+        let result = deadline(self.timeout(), self.kv.delete(key.clone(), option.clone())).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.delete(key, option).await;
+                    return deadline(self.timeout(), self.kv.delete(key, option)).await;
                 }
             }
         }
         result
     }
 
-    pub async fn leases(&mut self) -> Result<LeaseLeasesResponse, etcd_client::Error> {
-        let result = self.inner.leases().await;
+    pub async fn txn(&mut self, txn: Txn) -> Result<TxnResponse, etcd_client::Error> {
+        let result = deadline(self.timeout(), self.kv.txn(txn.clone())).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.leases().await;
+                    return deadline(self.timeout(), self.kv.txn(txn)).await;
+                }
+            }
+        }
+        result
+    }
+
+    pub async fn watch(
+        &mut self,
+        key: Vec<u8>,
+        option: Option<WatchOptions>,
+    ) -> Result<(Watcher, WatchStream), etcd_client::Error> {
+        let result = deadline(self.timeout(), self.watch_client.watch(key.clone(), option.clone())).await;
+
+        if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
+            if s.code() as i32 == 16 {
+                let self_auth = self.auth.clone();
+                if let Some(auth) = self_auth {
+                    self.authenticate().await?;
+                    return deadline(self.timeout(), self.watch_client.watch(key, option)).await;
+                }
+            }
+        }
+        result
+    }
+
+    async fn leases_once(&mut self) -> Result<LeaseLeasesResponse, etcd_client::Error> {
+        let result = deadline(self.timeout(), self.inner.leases()).await;
+
+        if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
+            if s.code() as i32 == 16 {
+                let self_auth = self.auth.clone();
+                if let Some(auth) = self_auth {
+                    self.authenticate().await?;
+                    return deadline(self.timeout(), self.inner.leases()).await;
                 }
             }
         }
@@ -114,14 +300,14 @@ impl WrappedEtcdClient {
         ttl: i64,
         option: Option<LeaseGrantOptions>,
     ) -> Result<LeaseGrantResponse, etcd_client::Error> {
-        let result = self.inner.lease_grant(ttl.clone(), option.clone()).await;
+        let result = deadline(self.timeout(), self.inner.lease_grant(ttl.clone(), option.clone())).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.lease_grant(ttl, option).await;
+                    return deadline(self.timeout(), self.inner.lease_grant(ttl, option)).await;
                 }
             }
         }
@@ -132,48 +318,66 @@ impl WrappedEtcdClient {
         &mut self,
         id: i64,
     ) -> Result<LeaseRevokeResponse, etcd_client::Error> {
-        let result = self.inner.lease_revoke(id).await;
+        let result = deadline(self.timeout(), self.inner.lease_revoke(id)).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.lease_revoke(id).await;
+                    return deadline(self.timeout(), self.inner.lease_revoke(id)).await;
                 }
             }
         }
         result
     }
 
-    pub async fn lease_time_to_live(
+    pub async fn lease_keep_alive(
+        &mut self,
+        id: i64,
+    ) -> Result<(LeaseKeeper, LeaseKeepAliveStream), etcd_client::Error> {
+        let result = deadline(self.timeout(), self.inner.lease_keep_alive(id)).await;
+
+        if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
+            if s.code() as i32 == 16 {
+                let self_auth = self.auth.clone();
+                if let Some(auth) = self_auth {
+                    self.authenticate().await?;
+                    return deadline(self.timeout(), self.inner.lease_keep_alive(id)).await;
+                }
+            }
+        }
+        result
+    }
+
+    async fn lease_time_to_live_once(
         &mut self,
         id: i64,
         option: Option<LeaseTimeToLiveOptions>,
     ) -> Result<LeaseTimeToLiveResponse, etcd_client::Error> {
-        let result = self.inner.lease_time_to_live(id, option.clone()).await;
+        let result = deadline(self.timeout(), self.inner.lease_time_to_live(id, option.clone())).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.lease_time_to_live(id, option).await;
+                    return deadline(self.timeout(), self.inner.lease_time_to_live(id, option)).await;
                 }
             }
         }
         result
     }
 
-    pub async fn user_list(&mut self) -> Result<UserListResponse, etcd_client::Error> {
-        let result = self.inner.user_list().await;
+    async fn user_list_once(&mut self) -> Result<UserListResponse, etcd_client::Error> {
+        let result = deadline(self.timeout(), self.inner.user_list()).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.user_list().await;
+                    return deadline(self.timeout(), self.inner.user_list()).await;
                 }
             }
         }
@@ -196,7 +400,7 @@ impl WrappedEtcdClient {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.user_add(name, password, options).await;
+                    return deadline(self.timeout(), self.inner.user_add(name, password, options)).await;
                 }
             }
         }
@@ -207,14 +411,14 @@ impl WrappedEtcdClient {
         &mut self,
         user: String,
     ) -> Result<UserDeleteResponse, etcd_client::Error> {
-        let result = self.inner.user_delete(user.clone()).await;
+        let result = deadline(self.timeout(), self.inner.user_delete(user.clone())).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.user_delete(user).await;
+                    return deadline(self.timeout(), self.inner.user_delete(user)).await;
                 }
             }
         }
@@ -236,7 +440,7 @@ impl WrappedEtcdClient {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.user_change_password(user, password).await;
+                    return deadline(self.timeout(), self.inner.user_change_password(user, password)).await;
                 }
             }
         }
@@ -248,14 +452,14 @@ impl WrappedEtcdClient {
         user: String,
         role: String,
     ) -> Result<UserGrantRoleResponse, etcd_client::Error> {
-        let result = self.inner.user_grant_role(user.clone(), role.clone()).await;
+        let result = deadline(self.timeout(), self.inner.user_grant_role(user.clone(), role.clone())).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.user_grant_role(user, role).await;
+                    return deadline(self.timeout(), self.inner.user_grant_role(user, role)).await;
                 }
             }
         }
@@ -277,22 +481,22 @@ impl WrappedEtcdClient {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.user_revoke_role(user, role).await;
+                    return deadline(self.timeout(), self.inner.user_revoke_role(user, role)).await;
                 }
             }
         }
         result
     }
 
-    pub async fn user_get(&mut self, user: &String) -> Result<UserGetResponse, etcd_client::Error> {
-        let result = self.inner.user_get(user.clone()).await;
+    async fn user_get_once(&mut self, user: &String) -> Result<UserGetResponse, etcd_client::Error> {
+        let result = deadline(self.timeout(), self.inner.user_get(user.clone())).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.user_get(user).await;
+                    return deadline(self.timeout(), self.inner.user_get(user)).await;
                 }
             }
         }
@@ -300,14 +504,14 @@ impl WrappedEtcdClient {
     }
 
     pub async fn auth_enable(&mut self) -> Result<AuthEnableResponse, etcd_client::Error> {
-        let result = self.inner.auth_enable().await;
+        let result = deadline(self.timeout(), self.inner.auth_enable()).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.auth_enable().await;
+                    return deadline(self.timeout(), self.inner.auth_enable()).await;
                 }
             }
         }
@@ -315,44 +519,44 @@ impl WrappedEtcdClient {
     }
 
     pub async fn auth_disable(&mut self) -> Result<AuthDisableResponse, etcd_client::Error> {
-        let result = self.inner.auth_disable().await;
+        let result = deadline(self.timeout(), self.inner.auth_disable()).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.auth_disable().await;
+                    return deadline(self.timeout(), self.inner.auth_disable()).await;
                 }
             }
         }
         result
     }
 
-    pub async fn role_list(&mut self) -> Result<RoleListResponse, etcd_client::Error> {
-        let result = self.inner.role_list().await;
+    async fn role_list_once(&mut self) -> Result<RoleListResponse, etcd_client::Error> {
+        let result = deadline(self.timeout(), self.inner.role_list()).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.role_list().await;
+                    return deadline(self.timeout(), self.inner.role_list()).await;
                 }
             }
         }
         result
     }
 
-    pub async fn role_get(&mut self, role: String) -> Result<RoleGetResponse, etcd_client::Error> {
-        let result = self.inner.role_get(role.clone()).await;
+    async fn role_get_once(&mut self, role: String) -> Result<RoleGetResponse, etcd_client::Error> {
+        let result = deadline(self.timeout(), self.inner.role_get(role.clone())).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.role_get(role).await;
+                    return deadline(self.timeout(), self.inner.role_get(role)).await;
                 }
             }
         }
@@ -360,14 +564,14 @@ impl WrappedEtcdClient {
     }
 
     pub async fn role_add(&mut self, role: String) -> Result<RoleAddResponse, etcd_client::Error> {
-        let result = self.inner.role_add(role.clone()).await;
+        let result = deadline(self.timeout(), self.inner.role_add(role.clone())).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.role_add(role).await;
+                    return deadline(self.timeout(), self.inner.role_add(role)).await;
                 }
             }
         }
@@ -378,14 +582,14 @@ impl WrappedEtcdClient {
         &mut self,
         role: String,
     ) -> Result<RoleDeleteResponse, etcd_client::Error> {
-        let result = self.inner.role_delete(role.clone()).await;
+        let result = deadline(self.timeout(), self.inner.role_delete(role.clone())).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.role_delete(role).await;
+                    return deadline(self.timeout(), self.inner.role_delete(role)).await;
                 }
             }
         }
@@ -407,7 +611,7 @@ impl WrappedEtcdClient {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.role_grant_permission(role, permission).await;
+                    return deadline(self.timeout(), self.inner.role_grant_permission(role, permission)).await;
                 }
             }
         }
@@ -430,37 +634,37 @@ impl WrappedEtcdClient {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.role_revoke_permission(role, key, option).await;
+                    return deadline(self.timeout(), self.inner.role_revoke_permission(role, key, option)).await;
                 }
             }
         }
         result
     }
 
-    pub async fn member_list(&mut self) -> Result<MemberListResponse, etcd_client::Error> {
-        let result = self.inner.member_list().await;
+    async fn member_list_once(&mut self) -> Result<MemberListResponse, etcd_client::Error> {
+        let result = deadline(self.timeout(), self.inner.member_list()).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.member_list().await;
+                    return deadline(self.timeout(), self.inner.member_list()).await;
                 }
             }
         }
         result
     }
 
-    pub async fn status(&mut self) -> Result<StatusResponse, etcd_client::Error> {
-        let result = self.inner.status().await;
+    async fn status_once(&mut self) -> Result<StatusResponse, etcd_client::Error> {
+        let result = deadline(self.timeout(), self.inner.status()).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.status().await;
+                    return deadline(self.timeout(), self.inner.status()).await;
                 }
             }
         }
@@ -483,7 +687,7 @@ impl WrappedEtcdClient {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.alarm(alarm_action, alarm_type, option).await;
+                    return deadline(self.timeout(), self.inner.alarm(alarm_action, alarm_type, option)).await;
                 }
             }
         }
@@ -495,14 +699,14 @@ impl WrappedEtcdClient {
         urls: Vec<String>,
         option: Option<MemberAddOptions>,
     ) -> Result<MemberAddResponse, etcd_client::Error> {
-        let result = self.inner.member_add(urls.clone(), option.clone()).await;
+        let result = deadline(self.timeout(), self.inner.member_add(urls.clone(), option.clone())).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.member_add(urls, option).await;
+                    return deadline(self.timeout(), self.inner.member_add(urls, option)).await;
                 }
             }
         }
@@ -513,14 +717,32 @@ impl WrappedEtcdClient {
         &mut self,
         id: u64
     ) -> Result<MemberRemoveResponse, etcd_client::Error> {
-        let result = self.inner.member_remove(id).await;
+        let result = deadline(self.timeout(), self.inner.member_remove(id)).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.member_remove(id).await;
+                    return deadline(self.timeout(), self.inner.member_remove(id)).await;
+                }
+            }
+        }
+        result
+    }
+
+    pub async fn member_promote(
+        &mut self,
+        id: u64
+    ) -> Result<MemberPromoteResponse, etcd_client::Error> {
+        let result = deadline(self.timeout(), self.inner.member_promote(id)).await;
+
+        if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
+            if s.code() as i32 == 16 {
+                let self_auth = self.auth.clone();
+                if let Some(auth) = self_auth {
+                    self.authenticate().await?;
+                    return deadline(self.timeout(), self.inner.member_promote(id)).await;
                 }
             }
         }
@@ -532,14 +754,14 @@ impl WrappedEtcdClient {
         id: u64,
         url: Vec<String>
     ) -> Result<MemberUpdateResponse, etcd_client::Error> {
-        let result = self.inner.member_update(id, url.clone()).await;
+        let result = deadline(self.timeout(), self.inner.member_update(id, url.clone())).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.member_update(id, url).await;
+                    return deadline(self.timeout(), self.inner.member_update(id, url)).await;
                 }
             }
         }
@@ -547,14 +769,33 @@ impl WrappedEtcdClient {
     }
     
     pub async fn defragment(&mut self) -> Result<DefragmentResponse, etcd_client::Error> {
-        let result = self.inner.defragment().await;
+        let result = deadline(self.timeout(), self.inner.defragment()).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.defragment().await;
+                    return deadline(self.timeout(), self.inner.defragment()).await;
+                }
+            }
+        }
+        result
+    }
+
+    pub async fn compact(
+        &mut self,
+        revision: i64,
+        options: Option<CompactionOptions>,
+    ) -> Result<CompactionResponse, etcd_client::Error> {
+        let result = deadline(self.timeout(), self.kv.compact(revision, options.clone())).await;
+
+        if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
+            if s.code() as i32 == 16 {
+                let self_auth = self.auth.clone();
+                if let Some(auth) = self_auth {
+                    self.authenticate().await?;
+                    return deadline(self.timeout(), self.kv.compact(revision, options)).await;
                 }
             }
         }
@@ -562,17 +803,60 @@ impl WrappedEtcdClient {
     }
 
     pub async fn snapshot(&mut self) -> Result<SnapshotStreaming, etcd_client::Error> {
-        let result = self.inner.snapshot().await;
+        let result = deadline(self.timeout(), self.inner.snapshot()).await;
 
         if let Err(etcd_client::Error::GRpcStatus(s)) = &result {
             if s.code() as i32 == 16 {
                 let self_auth = self.auth.clone();
                 if let Some(auth) = self_auth {
                     self.authenticate().await?;
-                    return self.inner.snapshot().await;
+                    return deadline(self.timeout(), self.inner.snapshot()).await;
                 }
             }
         }
         result
     }
 }
+
+/// 幂等的读取请求，短暂故障时按重试策略重试
+impl WrappedEtcdClient {
+    pub async fn kv_get_request(&mut self, key: Vec<u8>, option: Option<GetOptions>) -> Result<GetResponse, etcd_client::Error> {
+        retry_idempotent!(self, self.kv_get_request_once(key.clone(), option.clone()).await)
+    }
+
+    pub async fn leases(&mut self) -> Result<LeaseLeasesResponse, etcd_client::Error> {
+        retry_idempotent!(self, self.leases_once().await)
+    }
+
+    pub async fn user_list(&mut self) -> Result<UserListResponse, etcd_client::Error> {
+        retry_idempotent!(self, self.user_list_once().await)
+    }
+
+    pub async fn user_get(&mut self, user: &String) -> Result<UserGetResponse, etcd_client::Error> {
+        retry_idempotent!(self, self.user_get_once(user).await)
+    }
+
+    pub async fn role_list(&mut self) -> Result<RoleListResponse, etcd_client::Error> {
+        retry_idempotent!(self, self.role_list_once().await)
+    }
+
+    pub async fn role_get(&mut self, role: String) -> Result<RoleGetResponse, etcd_client::Error> {
+        retry_idempotent!(self, self.role_get_once(role.clone()).await)
+    }
+
+    pub async fn member_list(&mut self) -> Result<MemberListResponse, etcd_client::Error> {
+        retry_idempotent!(self, self.member_list_once().await)
+    }
+
+    pub async fn lease_time_to_live(
+        &mut self,
+        id: i64,
+        option: Option<LeaseTimeToLiveOptions>,
+    ) -> Result<LeaseTimeToLiveResponse, etcd_client::Error> {
+        retry_idempotent!(self, self.lease_time_to_live_once(id, option.clone()).await)
+    }
+
+    pub async fn status(&mut self) -> Result<StatusResponse, etcd_client::Error> {
+        retry_idempotent!(self, self.status_once().await)
+    }
+}
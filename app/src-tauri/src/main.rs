@@ -5,7 +5,8 @@ use api::settings::get_global_store_from_file;
 use log::{debug, info, LevelFilter};
 use tauri::{Manager, PhysicalSize, RunEvent, Size, WindowEvent};
 
-// use crate::api::windows::tray_menu_handle;
+use crate::api::windows::tray_menu_handle;
+use crate::utils::deep_link::DEEP_LINK_SCHEME;
 use crate::utils::file_util;
 
 mod api;
@@ -34,7 +35,12 @@ async fn main() {
     file_util::init().unwrap();
     info!("file util initialized");
 
+    //  必须在创建应用之前调用，已有实例运行时会将链接转发给该实例并退出
+    tauri_plugin_deep_link::prepare("com.beifengtz.etcdworkbench");
+
     tauri::Builder::default()
+        .system_tray(api::windows::create_system_tray())
+        .on_system_tray_event(|app, event| tray_menu_handle(app, event))
         .setup(|app| {
             debug!("loading window size from user setting file");
             let store = get_global_store_from_file().unwrap();
@@ -70,51 +76,225 @@ async fn main() {
                 }
             }
 
+            api::task_center::init(app.handle());
+            utils::value_plugin::load_all();
+            api::updater::start_update_checker(app.handle());
+            etcd::wake_monitor::start(app.handle());
+            etcd::session_lock::start(app.handle());
+            etcd::report_scheduler::start();
+            etcd::maintenance_scheduler::start();
+            utils::usage_stats::start_flusher();
+            api::windows::init_tray(app.handle());
+
+            let handle = app.handle();
+            tauri_plugin_deep_link::register(DEEP_LINK_SCHEME, move |url| {
+                api::deep_link::handle_deep_link(handle.clone(), url);
+            }).unwrap();
+            //  Windows 和 Linux 通过链接冷启动时，链接以启动参数传入
+            #[cfg(not(target_os = "macos"))]
+            if let Some(url) = std::env::args().nth(1) {
+                if url.starts_with(DEEP_LINK_SCHEME) {
+                    api::deep_link::handle_deep_link(app.handle(), url);
+                }
+            }
+
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
+        .register_uri_scheme_protocol(utils::value_transfer::PROTOCOL, utils::value_transfer::handle_request)
+        .invoke_system(String::from(utils::usage_stats::INVOKE_INITIALIZATION_SCRIPT), utils::usage_stats::respond)
+        .invoke_handler(utils::usage_stats::track(etcd::idle_monitor::track_activity(tauri::generate_handler![
             api::windows::client_error,
             api::windows::open_main_window,
             api::windows::open_setting_window,
             api::windows::exit_app,
             api::windows::open_folder,
             api::windows::get_download_path,
+            api::windows::open_connection_window,
+            api::windows::take_window_connection,
+            api::deep_link::take_deep_link,
+            api::deep_link::resolve_deep_link,
+            api::deep_link::generate_key_link,
+            api::quick_open::quick_open_search,
+            api::quick_open::get_recent_keys,
             api::connection::connect_test,
             api::connection::connect,
             api::connection::disconnect,
+            api::connection::respond_credential_request,
+            api::connection::sync_session_endpoints,
+            api::connection::get_ssh_algorithms,
+            api::connection::list_ssh_config_hosts,
             api::connection::save_connection,
             api::connection::remove_connection,
             api::connection::get_connection_list,
             api::connection::export_connection,
             api::connection::import_connection,
+            api::connection::preview_external_connections,
+            api::connection::import_external_connections,
+            api::connection::encode_connection_share,
+            api::connection::decode_connection_share,
             api::connection::update_key_collection,
+            api::connection::list_key_annotations,
+            api::connection::set_key_annotation,
+            api::connection::remove_key_annotation,
+            api::connection::search_key_annotations,
+            api::connection::export_key_bookmarks,
+            api::connection::import_key_bookmarks,
+            api::connection::get_connection_variables,
+            api::connection::set_connection_variables,
+            api::connection::get_poll_intervals,
+            api::connection::set_connection_poll_intervals,
+            api::connection::get_cached_state,
+            api::connection::clear_cached_state,
+            api::connection::list_shared_annotations,
+            api::connection::set_shared_annotation,
+            api::connection::remove_shared_annotation,
+            api::connection::update_shared_bookmarks,
             api::connection::set_key_monitor,
             api::connection::remove_key_monitor,
+            api::connection::list_notification_rules,
+            api::connection::set_notification_rule,
+            api::connection::remove_notification_rule,
+            api::connection::mute_notification_rule,
+            api::connection::snooze_notification_rule,
+            api::connection::get_connection_tls_info,
+            api::connection::get_tunnel_diagnostics,
+            api::connection::fetch_server_certificate,
+            api::connection::trust_server_certificate,
             api::settings::get_settings,
             api::settings::get_global_store,
             api::settings::save_settings,
+            api::settings::set_setting,
+            api::settings::reset_settings,
+            api::settings::set_master_password,
+            api::settings::lock_session,
+            api::settings::unlock_session,
+            api::settings::is_session_locked,
+            api::settings::touch_session_activity,
+            api::settings::export_workspace,
+            api::settings::import_workspace,
+            api::settings::get_usage_stats,
+            api::settings::reset_usage_stats,
+            api::settings::test_alert_webhook,
             api::settings::save_global_store,
             api::settings::get_app_version,
             api::settings::is_debug_model,
+            api::updater::check_update,
+            api::updater::install_update,
             api::kv::kv_get_all_keys,
             api::kv::kv_get_all_keys_paging,
+            api::kv::kv_get_all_keys_paging_at,
+            api::kv::kv_stream_all_keys,
             api::kv::kv_get,
             api::kv::kv_get_by_version,
             api::kv::kv_get_history_versions,
             api::kv::kv_get_with_prefix,
+            api::kv::get_keyspace_bounds,
+            api::kv::kv_autocomplete,
+            api::kv::kv_list_prefix_keys,
+            api::kv::kv_prefetch_children,
+            api::kv::kv_resolve_separator,
+            api::kv::subscribe_prefix_changes,
+            api::kv::unsubscribe_prefix_changes,
+            api::kv::mark_prefix_viewed,
+            api::kv::get_prefix_changes,
+            api::kv::track_prefix_count,
+            api::kv::untrack_prefix_count,
+            api::kv::get_prefix_counts,
+            api::kv::get_content_hint,
+            api::kv::set_content_hint,
+            api::kv::convert_value_format,
+            api::kv::subscribe_prefix_events,
+            api::kv::unsubscribe_prefix_events,
+            api::kv::set_read_revision,
+            api::kv::get_read_revision,
+            api::kv::list_revisions_near_time,
+            api::kv::kv_console,
+            api::kv::kv_encryption_export_key,
+            api::kv::kv_encryption_import_key,
+            api::kv::kv_put_from_file,
+            api::kv::kv_save_to_file,
+            api::kv::kv_export_history,
+            api::kv::kv_diff_file,
+            api::kv::kv_apply_file,
+            api::kv::kv_sync_pull,
+            api::kv::kv_sync_push,
+            api::kv::kv_set_value_cache,
+            api::kv::kv_get_value_cache_stats,
+            api::kv::kv_acquire_edit_lock,
+            api::kv::kv_release_edit_lock,
+            api::kv::kv_put_batch,
+            api::kv::kv_import_json,
+            api::kv::kv_import_external,
+            api::kv::kv_transfer,
+            api::kv::kv_start_hot_key_sampling,
+            api::kv::kv_stop_hot_key_sampling,
+            api::kv::kv_get_hot_key_report,
+            api::kv::mirror_start,
+            api::kv::mirror_pause,
+            api::kv::mirror_resume,
+            api::kv::mirror_stop,
+            api::kv::mirror_list,
+            api::kv::kv_start_audit_stream,
+            api::kv::kv_start_key_tail,
             api::kv::kv_put,
             api::kv::kv_put_with_lease,
+            api::kv::kv_render_template,
+            api::kv::kv_templatize_value,
             api::kv::kv_delete,
+            api::kv::kv_rename,
+            api::kv::macro_start_recording,
+            api::kv::macro_stop_recording,
+            api::kv::macro_is_recording,
+            api::kv::macro_list,
+            api::kv::macro_save,
+            api::kv::macro_delete,
+            api::kv::run_macro,
+            api::kv::kv_delete_prefix_preview,
+            api::kv::kv_delete_prefix,
+            api::kv::kv_delete_if,
+            api::kv::kv_list_trash,
+            api::kv::kv_restore_trash,
+            api::kv::kv_purge_trash,
+            api::kv::kv_attach_prefix_lease,
+            api::kv::kv_analyze_garbage,
+            api::kv::kv_cleanup_garbage,
             api::maintenance::get_cluster,
+            api::maintenance::get_health_state,
+            api::maintenance::get_endpoint_capabilities,
+            api::event_bus::replay_events,
+            api::task_center::list_tasks,
+            api::task_center::cancel_task,
+            api::task_center::remove_task,
+            api::task_center::clear_finished_tasks,
+            api::maintenance::cluster_add_member,
+            api::maintenance::cluster_promote_member,
+            api::maintenance::start_latency_sampler,
+            api::maintenance::stop_latency_sampler,
+            api::maintenance::get_latency_samples,
             api::maintenance::maintenance_defragment,
+            api::maintenance::list_maintenance_queue,
             api::maintenance::maintenance_create_snapshot_task,
             api::maintenance::maintenance_stop_snapshot_task,
             api::maintenance::maintenance_remove_snapshot_task,
             api::maintenance::maintenance_list_snapshot_task,
+            api::maintenance::generate_cluster_report,
+            api::maintenance::get_report_schedule,
+            api::maintenance::set_report_schedule,
+            api::maintenance::send_cluster_report,
+            api::maintenance::list_maintenance_schedules,
+            api::maintenance::set_maintenance_schedule,
+            api::maintenance::remove_maintenance_schedule,
+            api::maintenance::run_maintenance_schedule,
+            api::maintenance::list_maintenance_runs,
+            api::maintenance::diagnose_clock_drift,
+            api::maintenance::get_raft_lag,
             api::lease::leases,
             api::lease::lease_get,
             api::lease::lease_grant,
             api::lease::lease_revoke,
+            api::lease::lease_keep_alive_start,
+            api::lease::lease_keep_alive_stop,
+            api::lease::lease_keep_alive_list,
             api::user::user_list,
             api::user::user_add,
             api::user::user_delete,
@@ -123,18 +303,39 @@ async fn main() {
             api::user::user_revoke_role,
             api::user::auth_enable,
             api::user::auth_disable,
+            api::user::auth_bootstrap,
+            api::user::get_session_identity,
             api::role::role_list,
             api::role::role_add,
             api::role::role_delete,
             api::role::role_get_permissions,
             api::role::role_grant_permission,
             api::role::role_revoke_permission,
-        ])
+            api::role::auth_plan,
+            api::role::auth_apply,
+            api::role::auth_export,
+            api::role::auth_import,
+            api::role::auth_simulate,
+            api::role::precheck_operation,
+            api::sandbox::sandbox_start,
+            api::sandbox::sandbox_stop,
+            api::sandbox::sandbox_status,
+            api::sandbox::sandbox_reset,
+            api::plugin::plugin_list,
+            api::plugin::plugin_reload,
+            api::plugin::plugin_install,
+            api::plugin::plugin_remove,
+            api::plugin::plugin_set_enabled,
+            api::plugin::plugin_run_command,
+            api::actions::list_actions,
+        ])))
         .build(tauri::generate_context!())
         .expect("error while running tauri application")
         .run(|app, event| {
             match event {
-                RunEvent::Exit => {}
+                RunEvent::Exit => {
+                    api::shutdown::shutdown_blocking();
+                }
                 RunEvent::ExitRequested { .. } => {}
                 RunEvent::WindowEvent {
                     label,
@@ -150,14 +351,20 @@ async fn main() {
                                 api.prevent_close();
                             } else if label.eq("splashscreen") {
 
+                            } else if label.starts_with(api::windows::CONNECTION_WINDOW_LABEL_PREFIX) {
+                                //  独立连接窗口直接关闭，销毁后断开其中的连接
                             } else {
                                 let win = app.get_window(label.as_str()).unwrap();
                                 win.hide().unwrap();
                                 api.prevent_close();
                             }
                         }
-                        WindowEvent::Destroyed => {}
-                        WindowEvent::Focused(_) => {}
+                        WindowEvent::Destroyed => {
+                            api::windows::on_window_destroyed(&label);
+                        }
+                        WindowEvent::Focused(focused) => {
+                            etcd::poll_scheduler::set_window_focused(&label, focused);
+                        }
                         WindowEvent::ScaleFactorChanged { .. } => {}
                         WindowEvent::FileDrop(_) => {}
                         WindowEvent::ThemeChanged(_) => {},
@@ -0,0 +1,196 @@
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use log::warn;
+use russh::ChannelId;
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::sync::{mpsc, Semaphore};
+use tokio::time::timeout;
+
+/// 通道登记前收到的数据最多等待的时间，超时后认为该通道不会被读取
+const UNREGISTERED_TIMEOUT: Duration = Duration::from_secs(30);
+
+struct Inbox {
+    sender: mpsc::UnboundedSender<Vec<u8>>,
+    /// 通道登记前收到的数据同样放入队列，登记时取出接收端
+    receiver: Option<mpsc::UnboundedReceiver<Vec<u8>>>,
+    /// 队列剩余可容纳的字节数
+    space: Arc<Semaphore>,
+    created: Instant,
+}
+
+/// SSH会话的接收流控。
+///
+/// 每个转发通道的队列按字节数限制，积压达到窗口大小时会话的 `data` 回调等待本地读取，
+/// 暂停读取该会话的数据，由TCP将压力传导到对端，不丢弃数据也不断开通道。
+/// 收到数据但一直没有登记的通道，超时后丢弃其队列
+pub struct FlowControl {
+    window: usize,
+    inboxes: DashMap<ChannelId, Inbox>,
+}
+
+impl FlowControl {
+    pub fn new(window: usize) -> Self {
+        FlowControl {
+            window: window.max(1),
+            inboxes: DashMap::new(),
+        }
+    }
+
+    /// 通道的初始窗口，与积压上限相同
+    pub fn window(&self) -> u32 {
+        self.window.min(u32::MAX as usize) as u32
+    }
+
+    /// 数据块占用的队列空间，超过窗口的数据块按窗口计算，避免永远无法放入
+    fn cost(&self, len: usize) -> u32 {
+        len.min(self.window).min(u32::MAX as usize) as u32
+    }
+
+    fn new_inbox(&self) -> Inbox {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Inbox {
+            sender,
+            receiver: Some(receiver),
+            space: Arc::new(Semaphore::new(self.window)),
+            created: Instant::now(),
+        }
+    }
+
+    /// 开始接收转发通道的数据，返回读取端
+    pub fn register(self: &Arc<Self>, channel: ChannelId) -> ChannelInbox {
+        let mut inbox = self.inboxes.entry(channel).or_insert_with(|| self.new_inbox());
+        let receiver = match inbox.receiver.take() {
+            Some(receiver) => receiver,
+            //  通道已登记过，重新建立队列
+            None => {
+                inbox.space.close();
+                *inbox = self.new_inbox();
+                inbox.receiver.take().unwrap()
+            }
+        };
+        ChannelInbox {
+            receiver,
+            space: Arc::clone(&inbox.space),
+            chunk: Vec::new(),
+            pos: 0,
+            flow: Arc::clone(self),
+            channel,
+        }
+    }
+
+    pub fn unregister(&self, channel: ChannelId) {
+        if let Some((_, inbox)) = self.inboxes.remove(&channel) {
+            inbox.space.close();
+        }
+    }
+
+    /// 移除超时未登记的通道
+    fn purge_unregistered(&self) {
+        self.inboxes.retain(|channel, inbox| {
+            let keep = inbox.receiver.is_none() || inbox.created.elapsed() < UNREGISTERED_TIMEOUT;
+            if !keep {
+                warn!("ssh channel {:?} was never read, discard its data", channel);
+                inbox.space.close();
+            }
+            keep
+        });
+    }
+
+    /// 会话收到通道数据时调用，队列已满时等待读取端取出数据。
+    /// 未登记的通道最多等待 `UNREGISTERED_TIMEOUT`，之后丢弃该通道的数据
+    pub async fn received(&self, channel: ChannelId, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        if !self.inboxes.contains_key(&channel) {
+            self.purge_unregistered();
+        }
+        let (sender, space, registered) = {
+            let inbox = self.inboxes.entry(channel).or_insert_with(|| self.new_inbox());
+            (inbox.sender.clone(), Arc::clone(&inbox.space), inbox.receiver.is_none())
+        };
+        let cost = self.cost(data.len());
+        let acquire = Arc::clone(&space).acquire_many_owned(cost);
+        let permit = if registered {
+            acquire.await
+        } else {
+            match timeout(UNREGISTERED_TIMEOUT, acquire).await {
+                Ok(permit) => permit,
+                Err(_) => {
+                    self.purge_unregistered();
+                    return;
+                }
+            }
+        };
+        //  队列已关闭，读取端已结束，丢弃数据
+        let Ok(permit) = permit else {
+            return;
+        };
+        permit.forget();
+        if sender.send(data.to_vec()).is_err() {
+            space.add_permits(cost as usize);
+        }
+    }
+
+    /// 对端关闭或结束发送，读取完队列中的数据后结束
+    pub fn finished(&self, channel: ChannelId) {
+        if let Some(mut inbox) = self.inboxes.get_mut(&channel) {
+            inbox.close();
+        }
+    }
+}
+
+impl Inbox {
+    /// 替换为已关闭的发送端，读取端取完队列中的数据后结束，之后收到的数据直接丢弃
+    fn close(&mut self) {
+        let (sender, _) = mpsc::unbounded_channel();
+        self.sender = sender;
+    }
+}
+
+/// 转发通道的读取端
+pub struct ChannelInbox {
+    receiver: mpsc::UnboundedReceiver<Vec<u8>>,
+    space: Arc<Semaphore>,
+    chunk: Vec<u8>,
+    pos: usize,
+    flow: Arc<FlowControl>,
+    channel: ChannelId,
+}
+
+impl AsyncRead for ChannelInbox {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        if self.pos >= self.chunk.len() {
+            //  当前数据块已读完，归还占用的队列空间
+            if !self.chunk.is_empty() {
+                let cost = self.flow.cost(self.chunk.len());
+                self.space.add_permits(cost as usize);
+                self.chunk = Vec::new();
+                self.pos = 0;
+            }
+            match ready!(self.receiver.poll_recv(cx)) {
+                Some(chunk) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                }
+                None => return Poll::Ready(Ok(())),
+            }
+        }
+        let n = buf.remaining().min(self.chunk.len() - self.pos);
+        let pos = self.pos;
+        buf.put_slice(&self.chunk[pos..pos + n]);
+        self.pos += n;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for ChannelInbox {
+    fn drop(&mut self) {
+        self.flow.unregister(self.channel);
+    }
+}
@@ -1,2 +1,7 @@
 pub mod ssh_tunnel;
-pub mod ssh_client;
\ No newline at end of file
+pub mod ssh_client;
+pub mod ssh_algorithm;
+pub mod ssh_config;
+pub mod session_pool;
+pub mod tunnel_stats;
+pub mod flow_control;
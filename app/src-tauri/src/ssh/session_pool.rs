@@ -0,0 +1,173 @@
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::{debug, info};
+use russh::client::Handle;
+use russh::Disconnect;
+use sha2::{Digest, Sha256};
+
+use crate::ssh::flow_control::FlowControl;
+use crate::ssh::ssh_client::SshClient;
+use crate::ssh::ssh_config::SshHop;
+use crate::utils::aes_util;
+
+/// 依次经过所有跳板机后建立的已认证会话
+pub struct SshSessionChain {
+    /// 最后一台服务器的会话，用于转发
+    pub target: Handle<SshClient>,
+    /// 经过的跳板机会话，使用期间需要保持连接
+    jumps: Vec<Handle<SshClient>>,
+    /// 目标会话的接收流控，转发通道需要登记
+    pub flow: Arc<FlowControl>,
+}
+
+impl SshSessionChain {
+    pub fn new(target: Handle<SshClient>, jumps: Vec<Handle<SshClient>>, flow: Arc<FlowControl>) -> Self {
+        SshSessionChain { target, jumps, flow }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.target.is_closed() || self.jumps.iter().any(|jump| jump.is_closed())
+    }
+
+    /// 先断开目标服务器，再由近及远断开跳板机
+    pub async fn disconnect(&self) {
+        for handle in std::iter::once(&self.target).chain(self.jumps.iter().rev()) {
+            if handle.is_closed() {
+                continue;
+            }
+            if let Err(e) = handle.disconnect(Disconnect::ByApplication, "", "English").await {
+                debug!("Failed to disconnect ssh session: {}", e);
+            }
+        }
+    }
+}
+
+struct PooledChain {
+    chain: Arc<SshSessionChain>,
+    /// 没有隧道使用后在此时间关闭
+    expire_at: Instant,
+}
+
+lazy_static! {
+    static ref SESSION_POOL: DashMap<String, PooledChain> = DashMap::new();
+    /// 所有建立过的会话，退出时主动断开仍存活的会话
+    static ref LIVE_CHAINS: Mutex<Vec<Weak<SshSessionChain>>> = Mutex::new(Vec::new());
+    /// 计算凭据摘要的随机盐，只在本次运行中有效，日志中的复用标识无法用于推测凭据
+    static ref IDENTITY_SALT: Vec<u8> = aes_util::generate_key_256();
+}
+
+/// 记录新建立的会话
+pub fn track(chain: &Arc<SshSessionChain>) {
+    let mut chains = LIVE_CHAINS.lock().unwrap();
+    chains.retain(|chain| chain.strong_count() > 0);
+    chains.push(Arc::downgrade(chain));
+}
+
+/// 断开所有仍存活的会话，包括复用池中的会话
+pub async fn disconnect_all() {
+    SESSION_POOL.clear();
+    let chains: Vec<Arc<SshSessionChain>> = LIVE_CHAINS
+        .lock()
+        .unwrap()
+        .drain(..)
+        .filter_map(|chain| chain.upgrade())
+        .collect();
+    if !chains.is_empty() {
+        info!("Disconnect {} ssh sessions", chains.len());
+    }
+    for chain in chains {
+        chain.disconnect().await;
+    }
+}
+
+/// 会话的复用标识，由每一跳的 `user@host:port` 及其凭据摘要按跳板顺序组成，
+/// 经过相同的跳板机、每一跳使用相同的凭据和连接选项时才复用
+pub fn chain_key(hops: &[SshHop]) -> String {
+    hops.iter()
+        .map(|hop| format!("{}@{}:{}#{}", hop.ssh.user, hop.ssh.host, hop.ssh.port, identity_digest(hop)))
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+/// 一跳的密码、私钥、私钥文件及算法等连接选项的加盐摘要
+fn identity_digest(hop: &SshHop) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(IDENTITY_SALT.as_slice());
+    //  每个字段带上标记和长度，避免不同字段拼接后相同
+    let mut field = |tag: u8, value: &[u8]| {
+        hasher.update([tag]);
+        hasher.update((value.len() as u64).to_le_bytes());
+        hasher.update(value);
+    };
+    if let Some(identity) = &hop.ssh.identity {
+        if let Some(password) = &identity.password {
+            field(b'p', password.as_bytes());
+        }
+        if let Some(key) = &identity.key {
+            field(b'k', &key.key);
+            if let Some(passphrase) = &key.passphrase {
+                field(b'P', passphrase.as_bytes());
+            }
+        }
+    }
+    for path in &hop.identity_files {
+        field(b'f', path.to_string_lossy().as_bytes());
+    }
+    field(b'z', &[hop.ssh.compression as u8]);
+    for (tag, algorithms) in [(b'c', &hop.ssh.ciphers), (b'x', &hop.ssh.kex), (b'm', &hop.ssh.macs)] {
+        field(tag, algorithms.join(",").as_bytes());
+    }
+    hasher.finalize()[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 获取可复用的已认证会话
+pub fn acquire(key: &str) -> Option<Arc<SshSessionChain>> {
+    purge();
+    let chain = SESSION_POOL.get(key)?.chain.clone();
+    info!("Reuse authenticated ssh session {}", key);
+    Some(chain)
+}
+
+/// 新建的会话加入复用池，在隧道使用期间及关闭后 `ttl` 时间内可被复用
+pub fn register(key: String, chain: Arc<SshSessionChain>, ttl: Duration) {
+    SESSION_POOL.insert(
+        key,
+        PooledChain {
+            chain,
+            expire_at: Instant::now() + ttl,
+        },
+    );
+}
+
+/// 隧道关闭时调用，会话再保留 `ttl` 时间，到期后没有隧道使用时关闭
+pub fn release(key: &str, ttl: Duration) {
+    match SESSION_POOL.get_mut(key) {
+        Some(mut pooled) => pooled.expire_at = Instant::now() + ttl,
+        None => return,
+    }
+    //  可能在运行时之外的线程中释放，使用tauri的运行时
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(ttl).await;
+        purge();
+    });
+}
+
+/// 不再复用任何会话，如锁定会话时。使用中的隧道不受影响，关闭时会话随之关闭
+pub fn clear() {
+    SESSION_POOL.clear();
+}
+
+fn purge() {
+    let now = Instant::now();
+    SESSION_POOL.retain(|key, pooled| {
+        let in_use = Arc::strong_count(&pooled.chain) > 1;
+        let keep = !pooled.chain.is_closed() && (in_use || now < pooled.expire_at);
+        if !keep {
+            debug!("Close pooled ssh session {}", key);
+        }
+        keep
+    });
+}
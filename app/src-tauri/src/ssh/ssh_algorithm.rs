@@ -0,0 +1,91 @@
+use std::borrow::Cow;
+
+use russh::{cipher, compression, kex, mac, Preferred};
+
+use crate::error::LogicError;
+use crate::transport::connection::{ConnectionSsh, SshAlgorithms};
+
+/// 未指定密钥交换算法时使用，兼容只支持旧算法的服务器
+const DEFAULT_KEX: &[kex::Name] = &[
+    kex::CURVE25519,
+    kex::CURVE25519_PRE_RFC_8731,
+    kex::DH_G1_SHA1,
+    kex::DH_G14_SHA1,
+    kex::DH_G16_SHA512,
+    kex::DH_G14_SHA256,
+    kex::ECDH_SHA2_NISTP256,
+    kex::ECDH_SHA2_NISTP384,
+    kex::ECDH_SHA2_NISTP521,
+    kex::EXTENSION_SUPPORT_AS_CLIENT,
+    kex::EXTENSION_SUPPORT_AS_SERVER,
+    kex::EXTENSION_OPENSSH_STRICT_KEX_AS_CLIENT,
+    kex::EXTENSION_OPENSSH_STRICT_KEX_AS_SERVER,
+];
+
+/// 协议扩展使用的伪算法，指定密钥交换算法时追加在末尾
+const KEX_EXTENSIONS: &[kex::Name] = &[
+    kex::EXTENSION_SUPPORT_AS_CLIENT,
+    kex::EXTENSION_SUPPORT_AS_SERVER,
+    kex::EXTENSION_OPENSSH_STRICT_KEX_AS_CLIENT,
+    kex::EXTENSION_OPENSSH_STRICT_KEX_AS_SERVER,
+];
+
+/// OpenSSH 默认只在认证后启用 `zlib@openssh.com`，服务端不支持压缩时回退到不压缩
+const COMPRESSED: &[compression::Name] = &[compression::ZLIB_LEGACY, compression::ZLIB, compression::NONE];
+
+const UNCOMPRESSED: &[compression::Name] = &[compression::NONE];
+
+/// 按配置的顺序解析算法名，不支持的算法返回错误。不允许不加密或不校验的算法
+fn parse_names<N>(names: &[String], kind: &str) -> Result<Vec<N>, LogicError>
+where
+    N: for<'a> TryFrom<&'a str, Error = ()> + PartialEq,
+{
+    let mut result = Vec::with_capacity(names.len());
+    for name in names.iter().map(|name| name.trim()).filter(|name| !name.is_empty()) {
+        if name == "none" || name == "clear" {
+            return Err(LogicError::IllegalArgument(format!("Unsupported SSH {} algorithm: {}", kind, name)));
+        }
+        let parsed = N::try_from(name)
+            .map_err(|_| LogicError::IllegalArgument(format!("Unsupported SSH {} algorithm: {}", kind, name)))?;
+        if !result.contains(&parsed) {
+            result.push(parsed);
+        }
+    }
+    Ok(result)
+}
+
+/// 根据SSH配置生成协商算法的优先顺序，未指定的算法使用默认顺序
+pub fn preferred(ssh: &ConnectionSsh) -> Result<Preferred, LogicError> {
+    let mut kex: Vec<kex::Name> = parse_names(&ssh.kex, "key exchange")?;
+    let kex = if kex.is_empty() {
+        Cow::Borrowed(DEFAULT_KEX)
+    } else {
+        kex.extend_from_slice(KEX_EXTENSIONS);
+        Cow::Owned(kex)
+    };
+    let cipher: Vec<cipher::Name> = parse_names(&ssh.ciphers, "cipher")?;
+    let mac: Vec<mac::Name> = parse_names(&ssh.macs, "MAC")?;
+    Ok(Preferred {
+        kex,
+        cipher: if cipher.is_empty() { Preferred::DEFAULT.cipher } else { Cow::Owned(cipher) },
+        mac: if mac.is_empty() { Preferred::DEFAULT.mac } else { Cow::Owned(mac) },
+        compression: Cow::Borrowed(if ssh.compression { COMPRESSED } else { UNCOMPRESSED }),
+        ..<_>::default()
+    })
+}
+
+/// 可以配置的算法
+pub fn supported() -> SshAlgorithms {
+    let names = |names: Vec<&str>| {
+        names
+            .into_iter()
+            .filter(|name| *name != "none" && *name != "clear")
+            .map(String::from)
+            .collect()
+    };
+    SshAlgorithms {
+        kex: names(kex::ALL_KEX_ALGORITHMS.iter().map(|n| n.as_ref()).collect()),
+        ciphers: names(cipher::ALL_CIPHERS.iter().map(|n| n.as_ref()).collect()),
+        macs: names(mac::ALL_MAC_ALGORITHMS.iter().map(|n| n.as_ref()).collect()),
+    }
+}
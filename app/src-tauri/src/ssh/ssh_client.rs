@@ -1,17 +1,24 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use log::{info};
-use russh::{client};
+use russh::{client, ChannelId};
 use russh::client::{DisconnectReason};
 use russh::keys::ssh_key;
 
+use crate::ssh::flow_control::FlowControl;
+
 pub struct SshClient {
     ssh_simple_info: String,
+    /// 转发通道的接收流控，跳板机会话为空
+    flow: Option<Arc<FlowControl>>,
 }
 
 impl SshClient {
-    pub fn new(ssh_simple_info: String) -> Self {
+    pub fn new(ssh_simple_info: String, flow: Option<Arc<FlowControl>>) -> Self {
         SshClient {
-            ssh_simple_info
+            ssh_simple_info,
+            flow,
         }
     }
 }
@@ -27,6 +34,40 @@ impl client::Handler for SshClient {
         Ok(true)
     }
 
+    async fn data(
+        &mut self,
+        channel: ChannelId,
+        data: &[u8],
+        _session: &mut client::Session,
+    ) -> Result<(), Self::Error> {
+        if let Some(flow) = &self.flow {
+            flow.received(channel, data).await;
+        }
+        Ok(())
+    }
+
+    async fn channel_eof(
+        &mut self,
+        channel: ChannelId,
+        _session: &mut client::Session,
+    ) -> Result<(), Self::Error> {
+        if let Some(flow) = &self.flow {
+            flow.finished(channel);
+        }
+        Ok(())
+    }
+
+    async fn channel_close(
+        &mut self,
+        channel: ChannelId,
+        _session: &mut client::Session,
+    ) -> Result<(), Self::Error> {
+        if let Some(flow) = &self.flow {
+            flow.finished(channel);
+        }
+        Ok(())
+    }
+
     async fn disconnected(
         &mut self,
         reason: DisconnectReason<Self::Error>,
@@ -0,0 +1,282 @@
+use std::fs;
+use std::path::PathBuf;
+
+use log::{debug, warn};
+use tauri::api::path::home_dir;
+
+use crate::error::LogicError;
+use crate::transport::connection::ConnectionSsh;
+
+/// 未配置 IdentityFile 时依次尝试的私钥，与 OpenSSH 一致
+const DEFAULT_IDENTITY_FILES: &[&str] = &["~/.ssh/id_ed25519", "~/.ssh/id_ecdsa", "~/.ssh/id_rsa"];
+
+/// 单个主机在配置文件中解析出的选项
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SshHostConfig {
+    pub host_name: Option<String>,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    pub identity_files: Vec<String>,
+    pub proxy_jump: Option<String>,
+}
+
+/// `Host` 块，`Match` 块不支持，其中的选项不会生效
+#[derive(Debug)]
+struct HostBlock {
+    patterns: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+/// OpenSSH 客户端配置文件 `~/.ssh/config`
+#[derive(Debug, Default)]
+pub struct SshConfigFile {
+    blocks: Vec<HostBlock>,
+}
+
+/// 连接路径上的一台SSH服务器，`identity_files` 为配置文件中指定的私钥
+#[derive(Debug, Clone)]
+pub struct SshHop {
+    pub ssh: ConnectionSsh,
+    pub identity_files: Vec<PathBuf>,
+}
+
+/// 按空白切分参数，支持双引号
+fn split_args(s: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in s.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+    args
+}
+
+/// 匹配 `*` 和 `?` 通配符
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match (pattern.first(), text.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => glob_match(&pattern[1..], text) || (!text.is_empty() && glob_match(pattern, &text[1..])),
+        (Some(b'?'), Some(_)) => glob_match(&pattern[1..], &text[1..]),
+        (Some(p), Some(t)) if p.eq_ignore_ascii_case(t) => glob_match(&pattern[1..], &text[1..]),
+        _ => false,
+    }
+}
+
+impl HostBlock {
+    /// 任一取反的模式匹配时不匹配，否则任一模式匹配即匹配
+    fn matches(&self, host: &str) -> bool {
+        let mut matched = false;
+        for pattern in &self.patterns {
+            if let Some(negated) = pattern.strip_prefix('!') {
+                if glob_match(negated.as_bytes(), host.as_bytes()) {
+                    return false;
+                }
+            } else if glob_match(pattern.as_bytes(), host.as_bytes()) {
+                matched = true;
+            }
+        }
+        matched
+    }
+}
+
+impl SshConfigFile {
+    pub fn parse(content: &str) -> Self {
+        //  第一个 Host 之前的选项对所有主机生效
+        let mut blocks = vec![HostBlock {
+            patterns: vec![String::from("*")],
+            options: vec![],
+        }];
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (keyword, rest) = match line.find(|c: char| c.is_whitespace() || c == '=') {
+                Some(idx) => (&line[..idx], line[idx..].trim_start()),
+                None => (line, ""),
+            };
+            let rest = rest.strip_prefix('=').unwrap_or(rest).trim();
+            let keyword = keyword.to_ascii_lowercase();
+            match keyword.as_str() {
+                "host" => blocks.push(HostBlock {
+                    patterns: split_args(rest),
+                    options: vec![],
+                }),
+                "match" => {
+                    debug!("Match blocks of ssh config are not supported: {}", rest);
+                    blocks.push(HostBlock {
+                        patterns: vec![],
+                        options: vec![],
+                    })
+                }
+                _ => {
+                    if let Some(block) = blocks.last_mut() {
+                        block.options.push((keyword, rest.to_string()));
+                    }
+                }
+            }
+        }
+        SshConfigFile { blocks }
+    }
+
+    /// 读取 `~/.ssh/config`，文件不存在时返回空配置
+    pub fn load() -> Result<Self, LogicError> {
+        let Some(path) = home_dir().map(|home| home.join(".ssh").join("config")) else {
+            return Ok(Self::default());
+        };
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// 解析主机别名的选项，与 OpenSSH 相同，每个选项以第一次出现的值为准，IdentityFile 累加
+    pub fn resolve(&self, host: &str) -> SshHostConfig {
+        let mut config = SshHostConfig::default();
+        for block in self.blocks.iter().filter(|b| b.matches(host)) {
+            for (keyword, value) in &block.options {
+                let value = split_args(value).into_iter().next().unwrap_or_default();
+                match keyword.as_str() {
+                    "hostname" if config.host_name.is_none() => config.host_name = Some(value),
+                    "port" if config.port.is_none() => match value.parse() {
+                        Ok(port) => config.port = Some(port),
+                        Err(_) => warn!("Invalid port in ssh config of {}: {}", host, value),
+                    },
+                    "user" if config.user.is_none() => config.user = Some(value),
+                    "identityfile" => config.identity_files.push(value),
+                    "proxyjump" if config.proxy_jump.is_none() => config.proxy_jump = Some(value),
+                    _ => {}
+                }
+            }
+        }
+        config
+    }
+
+    /// 配置文件中明确列出的主机别名，不包含通配符
+    pub fn hosts(&self) -> Vec<String> {
+        let mut hosts: Vec<String> = self
+            .blocks
+            .iter()
+            .flat_map(|b| b.patterns.iter())
+            .filter(|p| !p.contains(['*', '?', '!']))
+            .cloned()
+            .collect();
+        hosts.sort();
+        hosts.dedup();
+        hosts
+    }
+}
+
+/// 解析跳板机 `[user@]host[:port]`，也支持 `ssh://` 前缀及 `[ipv6]:port`
+pub fn parse_jump(jump: &str) -> Result<(Option<String>, String, Option<u16>), LogicError> {
+    let jump = jump.trim();
+    let jump = jump.strip_prefix("ssh://").unwrap_or(jump);
+    let (user, address) = match jump.rsplit_once('@') {
+        Some((user, address)) => (Some(user.to_string()), address),
+        None => (None, jump),
+    };
+    let invalid = || LogicError::IllegalArgument(format!("Invalid ProxyJump host: {}", jump));
+    let (host, port) = if let Some(rest) = address.strip_prefix('[') {
+        let (host, port) = rest.split_once(']').ok_or_else(invalid)?;
+        (host, port.strip_prefix(':'))
+    } else {
+        match address.split_once(':') {
+            Some((host, port)) if !port.contains(':') => (host, Some(port)),
+            _ => (address, None),
+        }
+    };
+    if host.is_empty() {
+        return Err(invalid());
+    }
+    let port = match port {
+        Some(port) => Some(port.parse().map_err(|_| invalid())?),
+        None => None,
+    };
+    Ok((user, host.to_string(), port))
+}
+
+fn expand_path(path: &str, host: &str, user: &str) -> PathBuf {
+    let home = home_dir().unwrap_or_default();
+    let path = path
+        .replace("%d", &home.to_string_lossy())
+        .replace("%h", host)
+        .replace("%r", user)
+        .replace("%%", "%");
+    match path.strip_prefix("~/") {
+        Some(rest) => home.join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+/// 用配置文件中的选项补全连接，配置文件中的 HostName、Port、User 优先
+fn apply(mut ssh: ConnectionSsh, config: SshHostConfig) -> SshHop {
+    if let Some(host_name) = config.host_name {
+        ssh.host = host_name.replace("%h", &ssh.host);
+    }
+    if let Some(port) = config.port {
+        ssh.port = port;
+    }
+    if let Some(user) = config.user {
+        ssh.user = user;
+    }
+    let files: Vec<&str> = if config.identity_files.is_empty() {
+        DEFAULT_IDENTITY_FILES.to_vec()
+    } else {
+        config.identity_files.iter().map(|f| f.as_str()).collect()
+    };
+    let identity_files = files
+        .into_iter()
+        .filter(|f| !f.eq_ignore_ascii_case("none"))
+        .map(|f| expand_path(f, &ssh.host, &ssh.user))
+        .collect();
+    ssh.use_config = false;
+    SshHop { ssh, identity_files }
+}
+
+/// 解析连接需要经过的SSH服务器，跳板机在前，目标服务器在最后。
+/// 未启用配置文件时只有连接本身
+pub fn resolve_hops(ssh: &ConnectionSsh, config: &SshConfigFile) -> Result<Vec<SshHop>, LogicError> {
+    if !ssh.use_config {
+        return Ok(vec![SshHop {
+            ssh: ssh.clone(),
+            identity_files: vec![],
+        }]);
+    }
+    let target = config.resolve(&ssh.host);
+    let mut hops = Vec::new();
+    if let Some(jumps) = target.proxy_jump.as_deref().filter(|j| !j.eq_ignore_ascii_case("none")) {
+        for jump in jumps.split(',') {
+            let (user, host, port) = parse_jump(jump)?;
+            //  跳板机本身的 ProxyJump 不再展开
+            let mut jump_config = config.resolve(&host);
+            jump_config.proxy_jump = None;
+            if user.is_some() {
+                jump_config.user = user;
+            }
+            if port.is_some() {
+                jump_config.port = port;
+            }
+            let jump_ssh = ConnectionSsh {
+                host,
+                port: 22,
+                user: ssh.user.clone(),
+                identity: None,
+                ..ssh.clone()
+            };
+            hops.push(apply(jump_ssh, jump_config));
+        }
+    }
+    hops.push(apply(ssh.clone(), target));
+    Ok(hops)
+}
@@ -1,96 +1,210 @@
-use std::borrow::Cow;
 use std::io::{Error, ErrorKind};
 use std::sync::Arc;
 use std::time::Duration;
 
 use log::{debug, error, info, warn};
-use russh::{client, kex, Preferred};
+use russh::client;
 use russh::client::Handle;
 use russh::keys::key::PrivateKeyWithHashAlg;
 use russh::keys::{decode_secret_key, HashAlg};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::{oneshot, watch};
 use tokio::time::timeout;
 use tokio::{io, select};
+use zeroize::Zeroizing;
 
 use crate::api::settings::get_settings;
 use crate::error::LogicError;
+use crate::ssh::flow_control::FlowControl;
+use crate::ssh::session_pool::{self, SshSessionChain};
+use crate::ssh::ssh_algorithm;
 use crate::ssh::ssh_client::SshClient;
-use crate::transport::connection::ConnectionSsh;
+use crate::ssh::ssh_config::{resolve_hops, SshConfigFile, SshHop};
+use crate::ssh::tunnel_stats::{copy_counted, TunnelStats};
+use crate::transport::connection::{ConnectionSsh, EndpointAddress, TunnelDiagnostics};
 
 pub struct SshTunnel {
     proxy_port: u16,
     send_abort: watch::Sender<()>,
+    ssh_simple_info: String,
+    /// 隧道使用的会话，关闭前需要保持连接
+    _chain: Arc<SshSessionChain>,
+    /// 开启会话复用时的复用标识及关闭后的保留时间
+    reuse: Option<(String, Duration)>,
+    stats: Arc<TunnelStats>,
 }
 
 impl SshTunnel {
     pub async fn new(
         ssh_config: ConnectionSsh,
-        forward_host: &'static str,
-        forward_port: u16,
+        forward: EndpointAddress,
     ) -> Result<Self, LogicError> {
-        let config = client::Config {
-            inactivity_timeout: Some(Duration::from_secs(10)),
-            keepalive_interval: Some(Duration::from_secs(5)),
-            keepalive_max: 6,
-            preferred: Preferred {
-                kex: Cow::Borrowed(&[
-                    kex::CURVE25519,
-                    kex::CURVE25519_PRE_RFC_8731,
-                    kex::DH_G1_SHA1,
-                    kex::DH_G14_SHA1,
-                    kex::DH_G16_SHA512,
-                    kex::DH_G14_SHA256,
-                    kex::ECDH_SHA2_NISTP256,
-                    kex::ECDH_SHA2_NISTP384,
-                    kex::ECDH_SHA2_NISTP521,
-                    kex::EXTENSION_SUPPORT_AS_CLIENT,
-                    kex::EXTENSION_SUPPORT_AS_SERVER,
-                    kex::EXTENSION_OPENSSH_STRICT_KEX_AS_CLIENT,
-                    kex::EXTENSION_OPENSSH_STRICT_KEX_AS_SERVER,
-                ]),
-                ..<_>::default()
-            },
-            ..<_>::default()
+        let config_file = if ssh_config.use_config {
+            SshConfigFile::load()?
+        } else {
+            SshConfigFile::default()
+        };
+        let hops = resolve_hops(&ssh_config, &config_file)?;
+        let Some(target) = hops.last().map(|hop| &hop.ssh) else {
+            return Err(LogicError::IllegalArgument(String::from("No SSH server to connect")));
         };
-        let config = Arc::new(config);
+        let ssh_simple_info = format!("{}@{}:{}", target.user, target.host, target.port);
+        let settings = get_settings().await?;
 
-        let ssh_simple_info = format!(
-            "{}@{}:{}",
-            ssh_config.user, ssh_config.host, ssh_config.port
-        );
-        let client = SshClient::new(ssh_simple_info.clone());
-        let addr = format!("{}:{}", ssh_config.host, ssh_config.port);
+        let reuse_ttl = Duration::from_secs(settings.ssh_session_reuse_seconds);
+        let reuse = (!reuse_ttl.is_zero()).then(|| (session_pool::chain_key(&hops), reuse_ttl));
+        let reused = reuse.as_ref().and_then(|(key, _)| session_pool::acquire(key));
+        let chain = match reused {
+            Some(chain) => chain,
+            None => {
+                let connect_timeout = Duration::from_secs(settings.ssh_connect_timeout_seconds);
+                let flow = Arc::new(FlowControl::new(settings.ssh_channel_window_kb as usize * 1024));
+                let chain = Arc::new(Self::connect_chain(&hops, connect_timeout, flow).await?);
+                session_pool::track(&chain);
+                if let Some((key, ttl)) = &reuse {
+                    session_pool::register(key.clone(), Arc::clone(&chain), *ttl);
+                }
+                chain
+            }
+        };
 
-        let settings = get_settings().await?;
+        Self::open(ssh_simple_info, chain, forward, reuse, settings.ssh_proxy_buffer_kb as usize * 1024).await
+    }
 
-        let stream = timeout(
-            Duration::from_secs(settings.ssh_connect_timeout_seconds),
-            TcpStream::connect(addr),
+    /// 在同一个SSH会话上建立到另一个地址的转发，用于连接集群的其他成员
+    pub async fn forward(&self, forward: EndpointAddress) -> Result<Self, LogicError> {
+        let settings = get_settings().await?;
+        Self::open(
+            self.ssh_simple_info.clone(),
+            Arc::clone(&self._chain),
+            forward,
+            None,
+            settings.ssh_proxy_buffer_kb as usize * 1024,
         )
         .await
-        .map_err(|_| io::Error::new(ErrorKind::ConnectionAborted, "ssh connection timeout"))??;
+    }
+
+    async fn open(
+        ssh_simple_info: String,
+        chain: Arc<SshSessionChain>,
+        forward: EndpointAddress,
+        reuse: Option<(String, Duration)>,
+        buffer_size: usize,
+    ) -> Result<Self, LogicError> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let proxy_port = listener.local_addr()?.port();
+
+        let (send_abort, rcv_abort) = watch::channel(());
+        let stats = Arc::new(TunnelStats::new(ssh_simple_info.clone(), forward.to_string()));
+
+        info!(
+            "{} create ssh forward accept handler, local port is {}",
+            ssh_simple_info, proxy_port
+        );
+
+        Self::handle_tcp_proxy(
+            ssh_simple_info.clone(),
+            listener,
+            Arc::clone(&chain),
+            Arc::new(forward),
+            Arc::clone(&stats),
+            buffer_size,
+            rcv_abort,
+        )
+        .await?;
+
+        Ok(SshTunnel {
+            proxy_port,
+            send_abort,
+            ssh_simple_info,
+            _chain: chain,
+            reuse,
+            stats,
+        })
+    }
+
+    /// 依次连接并认证每一台服务器，后一台通过前一台跳板机转发。流控只用于最后一台服务器上的转发通道
+    async fn connect_chain(
+        hops: &[SshHop],
+        connect_timeout: Duration,
+        flow: Arc<FlowControl>,
+    ) -> Result<SshSessionChain, LogicError> {
+        let mut jump_sessions = Vec::new();
+        let mut session: Option<Handle<SshClient>> = None;
+        for (i, hop) in hops.iter().enumerate() {
+            let hop_flow = (i + 1 == hops.len()).then(|| Arc::clone(&flow));
+            let next = match session.take() {
+                None => {
+                    let addr = match EndpointAddress::parse(&hop.ssh.host, hop.ssh.port) {
+                        Ok(addr @ EndpointAddress::Tcp { .. }) => addr.to_string(),
+                        Ok(EndpointAddress::Unix(_)) => {
+                            return Err(LogicError::IllegalArgument(String::from(
+                                "The SSH server must be a TCP address",
+                            )))
+                        }
+                        Err(e) => return Err(LogicError::IllegalArgument(format!("Invalid SSH server: {}", e))),
+                    };
+                    let stream = timeout(connect_timeout, TcpStream::connect(addr))
+                        .await
+                        .map_err(|_| io::Error::new(ErrorKind::ConnectionAborted, "ssh connection timeout"))??;
+                    Self::connect_hop(stream, hop, hop_flow).await?
+                }
+                //  通过上一台跳板机转发到下一台服务器
+                Some(jump) => {
+                    let channel = jump
+                        .channel_open_direct_tcpip(hop.ssh.host.as_str(), hop.ssh.port as u32, "127.0.0.1", 22)
+                        .await?;
+                    let next = Self::connect_hop(channel.into_stream(), hop, hop_flow).await?;
+                    jump_sessions.push(jump);
+                    next
+                }
+            };
+            session = Some(next);
+        }
+        let session = session.ok_or_else(|| LogicError::IllegalArgument(String::from("No SSH server to connect")))?;
+        Ok(SshSessionChain::new(session, jump_sessions, flow))
+    }
 
-        let mut session = client::connect_stream(config, stream, client).await?;
+    /// 在已建立的连接上完成SSH握手及认证
+    async fn connect_hop<S>(stream: S, hop: &SshHop, flow: Option<Arc<FlowControl>>) -> Result<Handle<SshClient>, LogicError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let default_config = client::Config::default();
+        let config = client::Config {
+            //  转发通道的窗口与流控的积压上限一致
+            window_size: flow.as_ref().map(|f| f.window()).unwrap_or(default_config.window_size),
+            inactivity_timeout: Some(Duration::from_secs(10)),
+            keepalive_interval: Some(Duration::from_secs(5)),
+            keepalive_max: 6,
+            preferred: ssh_algorithm::preferred(&hop.ssh)?,
+            ..default_config
+        };
+        let ssh_simple_info = format!("{}@{}:{}", hop.ssh.user, hop.ssh.host, hop.ssh.port);
+        let client = SshClient::new(ssh_simple_info, flow);
+        let mut session = client::connect_stream(Arc::new(config), stream, client).await?;
+        Self::authenticate(&mut session, hop).await?;
+        Ok(session)
+    }
 
-        if let Some(identity) = ssh_config.identity {
-            if let Some(key) = identity.key {
-                let passphrase = if let Some(ref p) = key.passphrase {
-                    Some(p.as_str())
-                } else {
-                    None
-                };
+    /// 优先使用连接配置的密码或私钥，未配置时依次尝试配置文件中的私钥
+    async fn authenticate(session: &mut Handle<SshClient>, hop: &SshHop) -> Result<(), LogicError> {
+        let user = hop.ssh.user.as_str();
+        if let Some(identity) = &hop.ssh.identity {
+            if let Some(key) = &identity.key {
+                let passphrase = key.passphrase.as_deref();
                 let hash_alg = key
                     .hash_algorithm
                     .clone()
                     .map(|s| HashAlg::new(s.as_str()).unwrap());
 
-                match decode_secret_key(String::from_utf8(key.key)?.as_str(), passphrase) {
+                //  私钥内容的临时副本使用后清零
+                let pem = Zeroizing::new(String::from_utf8(key.key.clone())?);
+                match decode_secret_key(pem.as_str(), passphrase) {
                     Ok(key_pair) => {
                         let private_key = PrivateKeyWithHashAlg::new(Arc::new(key_pair), hash_alg)?;
-                        let res = session
-                            .authenticate_publickey(ssh_config.user, private_key)
-                            .await?;
+                        let res = session.authenticate_publickey(user, private_key).await?;
                         if !res {
                             return Err(LogicError::IoError(Error::new(
                                 ErrorKind::ConnectionAborted,
@@ -106,10 +220,8 @@ impl SshTunnel {
                         )));
                     }
                 }
-            } else if let Some(password) = identity.password {
-                let res = session
-                    .authenticate_password(ssh_config.user, password)
-                    .await?;
+            } else if let Some(password) = &identity.password {
+                let res = session.authenticate_password(user, password).await?;
                 if !res {
                     return Err(LogicError::IoError(Error::new(
                         ErrorKind::ConnectionAborted,
@@ -117,44 +229,54 @@ impl SshTunnel {
                     )));
                 }
             }
+            return Ok(());
         }
 
-        let listener = TcpListener::bind("127.0.0.1:0").await?;
-        let proxy_port = listener.local_addr()?.port();
-
-        let (send_abort, rcv_abort) = watch::channel(());
-
-        info!(
-            "{} create ssh forward accept handler, local port is {}",
-            ssh_simple_info, proxy_port
-        );
-
-        Self::handle_tcp_proxy(
-            ssh_simple_info,
-            listener,
-            Arc::new(session),
-            forward_host,
-            forward_port,
-            rcv_abort,
-        )
-        .await?;
-
-        Ok(SshTunnel {
-            proxy_port,
-            send_abort,
-        })
+        let mut tried = false;
+        for path in &hop.identity_files {
+            let Ok(content) = tokio::fs::read_to_string(path).await.map(Zeroizing::new) else {
+                continue;
+            };
+            //  有密码保护的私钥无法在此处使用
+            let key_pair = match decode_secret_key(content.as_str(), None) {
+                Ok(key_pair) => key_pair,
+                Err(e) => {
+                    debug!("Skip ssh identity file {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            tried = true;
+            let hash_alg = key_pair.algorithm().is_rsa().then_some(HashAlg::Sha256);
+            let private_key = PrivateKeyWithHashAlg::new(Arc::new(key_pair), hash_alg)?;
+            if session.authenticate_publickey(user, private_key).await? {
+                debug!("Ssh authenticated with identity file {}", path.display());
+                return Ok(());
+            }
+        }
+        if tried {
+            return Err(LogicError::IoError(Error::new(
+                ErrorKind::ConnectionAborted,
+                "Ssh authentication failed",
+            )));
+        }
+        Ok(())
     }
 
     pub fn get_proxy_port(&self) -> u16 {
         self.proxy_port
     }
 
+    pub fn diagnostics(&self) -> TunnelDiagnostics {
+        self.stats.diagnostics()
+    }
+
     async fn handle_tcp_proxy(
         ssh_simple_info: String,
         listener: TcpListener,
-        ssh_session: Arc<Handle<SshClient>>,
-        forward_host: &'static str,
-        forward_port: u16,
+        ssh_session: Arc<SshSessionChain>,
+        forward: Arc<EndpointAddress>,
+        stats: Arc<TunnelStats>,
+        buffer_size: usize,
         rcv_abort: watch::Receiver<()>,
     ) -> Result<(), LogicError> {
         let (sender, receiver) = oneshot::channel();
@@ -178,32 +300,42 @@ impl SshTunnel {
                             let mut rcv_abort3 = rcv_abort2.clone();
                             let ssh_session = Arc::clone(&ssh_session);
                             let ssh_simple_info3 = Arc::clone(&ssh_simple_info2);
+                            let stats = Arc::clone(&stats);
 
-                            debug!("ssh proxy stream task started, chain: local({}) -> local(127.0.0.1:{}) -> ssh({}) -> remote({}:{})",
-                                addr, local_port, ssh_simple_info2,  forward_host, forward_port);
+                            debug!("ssh proxy stream task started, chain: local({}) -> local(127.0.0.1:{}) -> ssh({}) -> remote({})",
+                                addr, local_port, ssh_simple_info2, forward);
 
-                            let direct_channel_result = ssh_session
-                                .channel_open_direct_tcpip(
-                                    forward_host,
-                                    forward_port as u32,
-                                    "127.0.0.1",
-                                    22,
-                                )
-                                .await;
+                            let direct_channel_result = match forward.as_ref() {
+                                EndpointAddress::Tcp { host, port } => {
+                                    ssh_session
+                                        .target
+                                        .channel_open_direct_tcpip(host.as_str(), *port as u32, "127.0.0.1", 22)
+                                        .await
+                                }
+                                //  转发到远程主机上的unix socket
+                                EndpointAddress::Unix(path) => {
+                                    ssh_session.target.channel_open_direct_streamlocal(path.as_str()).await
+                                }
+                            };
 
                             match direct_channel_result {
-                                Ok(mut channel) => {
+                                Ok(channel) => {
+                                    stats.add_stream();
+                                    //  通道数据由会话回调写入按字节限制的队列，不使用通道自身的无界队列，取得写入端后即释放
+                                    let mut channel_reader = ssh_session.flow.register(channel.id());
+                                    let mut channel_writer = channel.make_writer();
+                                    drop(channel);
                                     tokio::spawn(async move {
-                                        let mut channel_writer = channel.make_writer();
-                                        let mut channel_reader = channel.make_reader();
-
                                         let (mut socket_reader, mut socket_writer) = stream.split();
 
+                                        //  任一方向结束或出错时关闭本地连接
                                         let proxy_task = async {
-                                            loop {
-                                                select! {
-                                                    _ = io::copy(&mut socket_reader, &mut channel_writer) => {},
-                                                    _ = io::copy(&mut channel_reader, &mut socket_writer) => {}
+                                            select! {
+                                                _ = copy_counted(&mut socket_reader, &mut channel_writer, &stats.upload, None, buffer_size) => {},
+                                                result = copy_counted(&mut channel_reader, &mut socket_writer, &stats.download, Some(&stats.upload), buffer_size) => {
+                                                    if let Err(e) = result {
+                                                        warn!("{} ssh proxy stream closed: {}", ssh_simple_info3, e);
+                                                    }
                                                 }
                                             }
                                         };
@@ -263,6 +395,9 @@ impl Drop for SshTunnel {
                 warn!("ssh send abort error: {e}")
             }
         }
+        if let Some((key, ttl)) = &self.reuse {
+            session_pool::release(key, *ttl);
+        }
         debug!("drop ssh tunnel");
     }
 }
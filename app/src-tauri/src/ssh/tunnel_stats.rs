@@ -0,0 +1,245 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tokio::io::{self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::select;
+
+use crate::transport::connection::{TunnelDiagnostics, TunnelDirectionStats, TunnelHint};
+
+/// IPv4的最小MSS，路径MTU异常时数据会被切成小于此大小的碎片
+pub const SMALL_CHUNK_BYTES: usize = 536;
+/// 吞吐量的统计窗口
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(1);
+/// 超过此间隔没有数据视为空闲，重新开始统计窗口
+const IDLE_GAP: Duration = Duration::from_secs(5);
+/// 请求发出后超过此时间没有任何返回视为读取停滞
+pub const STALL_AFTER: Duration = Duration::from_secs(5);
+/// 峰值吞吐量达到此值后才判断吞吐量骤降，避免少量数据误判
+pub const MIN_PEAK_BPS: u64 = 256 * 1024;
+/// 小于此大小的上行数据块视为HTTP/2控制帧（WINDOW_UPDATE、PING、SETTINGS确认等），
+/// 这些帧不一定有响应，不作为判断读取停滞的请求
+pub const MIN_REQUEST_BYTES: usize = 64;
+/// 转发的数据达到此大小后才判断碎片化
+const MIN_FRAGMENTED_BYTES: u64 = 1024 * 1024;
+
+const PATH_ISSUE: &str = "possible MTU/path issue through the bastion";
+
+struct DirectionState {
+    stats: TunnelDirectionStats,
+    window_start: Option<Instant>,
+    window_bytes: u64,
+    last_at: Option<Instant>,
+    /// 最近一次达到请求大小的数据块的时间
+    last_request_at: Option<Instant>,
+}
+
+/// 单个方向的统计，同一隧道的所有连接共用
+pub struct DirectionCounter {
+    state: Mutex<DirectionState>,
+}
+
+impl DirectionCounter {
+    fn new() -> Self {
+        DirectionCounter {
+            state: Mutex::new(DirectionState {
+                stats: TunnelDirectionStats::default(),
+                window_start: None,
+                window_bytes: 0,
+                last_at: None,
+                last_request_at: None,
+            }),
+        }
+    }
+
+    /// 记录转发的数据块，每满一个窗口计算一次吞吐量。
+    /// 上一个窗口接近峰值而当前窗口不足峰值的5%时，视为传输中吞吐量骤降（通常是大量重传）
+    pub fn record(&self, n: usize) {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        state.stats.bytes += n as u64;
+        state.stats.chunks += 1;
+        if n < SMALL_CHUNK_BYTES {
+            state.stats.small_chunks += 1;
+        }
+
+        if state.last_at.is_some_and(|last| now.duration_since(last) > IDLE_GAP) {
+            state.window_start = None;
+            state.stats.last_bps = 0;
+        }
+        state.last_at = Some(now);
+        if n >= MIN_REQUEST_BYTES {
+            state.last_request_at = Some(now);
+        }
+
+        let Some(start) = state.window_start else {
+            state.window_start = Some(now);
+            state.window_bytes = n as u64;
+            return;
+        };
+        state.window_bytes += n as u64;
+        let age = now.duration_since(start);
+        if age < THROUGHPUT_WINDOW {
+            return;
+        }
+        let bps = (state.window_bytes as f64 / age.as_secs_f64()) as u64;
+        let stats = &mut state.stats;
+        if stats.peak_bps >= MIN_PEAK_BPS && stats.last_bps * 2 >= stats.peak_bps && bps * 20 < stats.peak_bps {
+            stats.collapses += 1;
+        }
+        stats.last_bps = bps;
+        stats.peak_bps = stats.peak_bps.max(bps);
+        state.window_start = Some(now);
+        state.window_bytes = 0;
+    }
+
+    fn last_at(&self) -> Option<Instant> {
+        self.state.lock().unwrap().last_at
+    }
+
+    fn last_request_at(&self) -> Option<Instant> {
+        self.state.lock().unwrap().last_request_at
+    }
+
+    /// 对端最后一次发送请求大小的数据后超过 [`STALL_AFTER`] 仍没有读到任何数据，
+    /// 只有控制帧的上行不计入
+    fn stalled_behind(&self, request: &DirectionCounter) -> bool {
+        let Some(requested_at) = request.last_request_at() else {
+            return false;
+        };
+        let answered = self.last_at().is_some_and(|last| last >= requested_at);
+        !answered && requested_at.elapsed() >= STALL_AFTER
+    }
+
+    fn record_stall(&self) {
+        self.state.lock().unwrap().stats.stalls += 1;
+    }
+
+    fn snapshot(&self) -> TunnelDirectionStats {
+        self.state.lock().unwrap().stats.clone()
+    }
+}
+
+/// 隧道的传输统计，用于诊断经过跳板机时的MTU或路径问题
+pub struct TunnelStats {
+    server: String,
+    forward: String,
+    streams: AtomicU64,
+    pub upload: DirectionCounter,
+    pub download: DirectionCounter,
+}
+
+impl TunnelStats {
+    pub fn new(server: String, forward: String) -> Self {
+        TunnelStats {
+            server,
+            forward,
+            streams: AtomicU64::new(0),
+            upload: DirectionCounter::new(),
+            download: DirectionCounter::new(),
+        }
+    }
+
+    pub fn add_stream(&self) {
+        self.streams.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn diagnostics(&self) -> TunnelDiagnostics {
+        let upload = self.upload.snapshot();
+        let download = self.download.snapshot();
+        let hints = diagnose(&upload, &download);
+        TunnelDiagnostics {
+            server: self.server.clone(),
+            forward: self.forward.clone(),
+            streams: self.streams.load(Ordering::Relaxed),
+            upload,
+            download,
+            hints,
+        }
+    }
+}
+
+/// 根据统计推断隧道异常：
+/// - 大量数据几乎都以小于最小MSS的碎片到达，且吞吐量一直很低。
+///   吞吐量正常时大量小数据块通常只是很多小请求，不视为异常
+/// - 请求发出后多次长时间没有任何返回
+/// - 传输过程中吞吐量多次骤降，通常是丢包后大量重传
+pub fn diagnose(upload: &TunnelDirectionStats, download: &TunnelDirectionStats) -> Vec<TunnelHint> {
+    let mut hints = vec![];
+    let fragmented = [upload, download]
+        .iter()
+        .any(|stats| {
+            stats.bytes >= MIN_FRAGMENTED_BYTES
+                && stats.small_chunks * 10 >= stats.chunks * 9
+                && stats.peak_bps < MIN_PEAK_BPS
+        });
+    if fragmented {
+        hints.push(TunnelHint {
+            code: String::from("TINY_WRITES"),
+            hint: format!("Most data passes through the tunnel in tiny fragments, {}", PATH_ISSUE),
+        });
+    }
+    if download.stalls >= 3 {
+        hints.push(TunnelHint {
+            code: String::from("STALLED_READS"),
+            hint: format!(
+                "Responses stalled {} times while requests were pending, {}",
+                download.stalls, PATH_ISSUE
+            ),
+        });
+    }
+    let collapses = upload.collapses + download.collapses;
+    if collapses >= 2 {
+        hints.push(TunnelHint {
+            code: String::from("THROUGHPUT_COLLAPSE"),
+            hint: format!(
+                "Throughput collapsed {} times during transfers, packets are likely being retransmitted, {}",
+                collapses, PATH_ISSUE
+            ),
+        });
+    }
+    hints
+}
+
+/// 与 `io::copy` 相同，同时记录每次转发的数据块。写入完成后才读取下一块，写入端慢时不会继续读取。
+/// 指定 `request` 时，对端有未响应的请求且长时间读不到数据则记录一次停滞
+pub async fn copy_counted<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    counter: &DirectionCounter,
+    request: Option<&DirectionCounter>,
+    buffer_size: usize,
+) -> io::Result<u64>
+where
+    R: AsyncRead + Unpin + ?Sized,
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    let mut buf = vec![0u8; buffer_size];
+    let mut total = 0;
+    loop {
+        let n = {
+            let read = reader.read(&mut buf);
+            tokio::pin!(read);
+            let mut stalled = false;
+            loop {
+                select! {
+                    n = &mut read => break n?,
+                    _ = tokio::time::sleep(STALL_AFTER), if request.is_some() => {
+                        //  每次等待只记录一次
+                        if !stalled && request.is_some_and(|request| counter.stalled_behind(request)) {
+                            counter.record_stall();
+                            stalled = true;
+                        }
+                    }
+                }
+            }
+        };
+        if n == 0 {
+            writer.flush().await?;
+            return Ok(total);
+        }
+        writer.write_all(&buf[..n]).await?;
+        counter.record(n);
+        total += n as u64;
+    }
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+/// 执行操作所需的能力
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all="camelCase")]
+pub enum ActionCapability {
+    /// 需要打开的连接
+    Session,
+    /// 需要连接可写
+    Write,
+    /// 需要root用户
+    Root,
+}
+
+/// 操作参数的类型，界面据此选择输入控件
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all="camelCase")]
+pub enum ActionParamType {
+    String,
+    Number,
+    Boolean,
+    /// etcd中的key，可使用key补全
+    Key,
+    /// 本地文件路径
+    File,
+    /// 本地目录路径
+    Dir,
+    Json,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct ActionParam {
+    /// 与命令的参数名一致
+    pub name: String,
+    pub param_type: ActionParamType,
+    pub required: bool,
+}
+
+/// 可调用的操作，通过 `command` 对应的命令执行，`args` 为固定传入的参数
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct ActionDef {
+    pub id: String,
+    pub title: String,
+    pub category: String,
+    pub command: String,
+    pub params: Vec<ActionParam>,
+    pub capabilities: Vec<ActionCapability>,
+    #[serde(default)]
+    pub args: Option<serde_json::Value>,
+}
+
+/// 操作及其在当前连接下是否可用
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct ActionInfo {
+    #[serde(flatten)]
+    pub action: ActionDef,
+    pub available: bool,
+    pub unavailable_reason: Option<String>,
+}
@@ -1,17 +1,36 @@
+use std::collections::BTreeMap;
+
+use crate::error::LogicError;
+use crate::utils::fuzzy::glob_match;
+use crate::transport::kv::SerializableKeyValue;
+use crate::transport::maintenance::{HealthState, MaintenanceSchedule, SerializableCluster};
+use crate::transport::report::ReportSchedule;
+use crate::transport::settings::PollTask;
 use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// 释放时清零密码，私钥和SSH凭据同样如此
+#[derive(Debug, Serialize, Deserialize, Clone, Zeroize, ZeroizeOnDrop)]
 pub struct ConnectionUser {
+    #[zeroize(skip)]
     pub username: String,
     pub password: String,
 }
 
 pub type TlsCertificate = Vec<u8>;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Zeroize, ZeroizeOnDrop)]
 pub struct TlsIdentity {
+    #[zeroize(skip)]
     pub cert: TlsCertificate,
+    /// 私钥，支持 PKCS#1、SEC1、PKCS#8 以及加密的 PKCS#8 格式
     pub key: Vec<u8>,
+    /// 私钥或 PKCS#12 证书包的密码
+    #[serde(default)]
+    pub passphrase: Option<String>,
+    /// PKCS#12 证书包（.p12/.pfx），配置后将忽略 cert 和 key
+    #[serde(default)]
+    pub pkcs12: Option<Vec<u8>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -19,19 +38,23 @@ pub struct ConnectionTls {
     pub domain: Option<String>,
     pub cert: Vec<TlsCertificate>,
     pub identity: Option<TlsIdentity>,
+    /// 首次信任时固定的服务端证书SHA-256指纹，指纹变化后将拒绝连接
+    #[serde(default, rename = "pinnedFingerprint")]
+    pub pinned_fingerprint: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Zeroize, ZeroizeOnDrop)]
 #[serde(rename_all="camelCase")]
 pub struct SshPrivateKey {
     pub key: Vec<u8>,
     pub passphrase: Option<String>,
     /// ssh_key::algorithm::HashAlg
     #[serde(default = "default_private_key_hash_alg")]
+    #[zeroize(skip)]
     pub hash_algorithm: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Zeroize, ZeroizeOnDrop)]
 pub struct SshIdentity {
     pub password: Option<String>,
     pub key: Option<SshPrivateKey>,
@@ -43,6 +66,109 @@ pub struct ConnectionSsh {
     pub port: u16,
     pub user: String,
     pub identity: Option<SshIdentity>,
+    /// 启用压缩，可加快慢速链路上快照等大量数据的传输
+    #[serde(default)]
+    pub compression: bool,
+    /// 优先使用的加密算法，为空时使用默认顺序
+    #[serde(default)]
+    pub ciphers: Vec<String>,
+    /// 优先使用的密钥交换算法，为空时使用默认顺序
+    #[serde(default)]
+    pub kex: Vec<String>,
+    /// 优先使用的MAC算法，为空时使用默认顺序
+    #[serde(default)]
+    pub macs: Vec<String>,
+    /// 从 `~/.ssh/config` 解析 `host` 别名的 HostName、Port、User、IdentityFile 及 ProxyJump
+    #[serde(default, rename = "useConfig")]
+    pub use_config: bool,
+}
+
+/// 支持配置的SSH算法
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SshAlgorithms {
+    pub kex: Vec<String>,
+    pub ciphers: Vec<String>,
+    pub macs: Vec<String>,
+}
+
+/// SSH隧道单个方向的传输统计
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct TunnelDirectionStats {
+    pub bytes: u64,
+    /// 转发的数据块数量
+    pub chunks: u64,
+    /// 小于最小MSS（536字节）的数据块数量
+    pub small_chunks: u64,
+    /// 最近一个统计窗口的吞吐量（字节/秒）
+    pub last_bps: u64,
+    pub peak_bps: u64,
+    /// 传输过程中吞吐量骤降的次数
+    pub collapses: u64,
+    /// 有未响应的请求时长时间读不到数据的次数
+    pub stalls: u64,
+}
+
+/// 隧道异常的诊断提示，`code` 供前端识别
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all="camelCase")]
+pub struct TunnelHint {
+    pub code: String,
+    pub hint: String,
+}
+
+/// SSH隧道的传输统计及诊断提示
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct TunnelDiagnostics {
+    /// 最后一台SSH服务器
+    pub server: String,
+    /// 转发的目标地址
+    pub forward: String,
+    /// 经过隧道的连接数
+    pub streams: u64,
+    /// 发往etcd的数据
+    pub upload: TunnelDirectionStats,
+    /// etcd返回的数据
+    pub download: TunnelDirectionStats,
+    pub hints: Vec<TunnelHint>,
+}
+
+/// 证书的解析信息
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub serial: String,
+    /// Subject Alternative Names
+    pub sans: Vec<String>,
+    /// 生效时间（毫秒时间戳）
+    pub not_before: i64,
+    /// 过期时间（毫秒时间戳）
+    pub not_after: i64,
+    pub fingerprint_sha256: String,
+    pub expired: bool,
+    /// 剩余有效天数，已过期为负数
+    pub days_remaining: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct ConnectionTlsInfo {
+    /// CA证书
+    pub ca: Vec<CertificateInfo>,
+    /// 客户端证书
+    pub identity: Vec<CertificateInfo>,
+}
+
+/// 从服务端获取到的证书，用于首次连接时由用户确认是否信任
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct ServerCertificate {
+    pub info: CertificateInfo,
+    pub pem: String,
 }
 
 /// 连接必要数据
@@ -54,6 +180,311 @@ pub struct Connection {
     pub user: Option<ConnectionUser>,
     pub tls: Option<ConnectionTls>,
     pub ssh: Option<ConnectionSsh>,
+    /// 空闲超时时间（分钟），超过该时间没有任何操作将自动断开连接，为空或0时不自动断开
+    #[serde(default, rename = "idleTimeoutMinutes")]
+    pub idle_timeout_minutes: Option<u64>,
+    /// 只读连接，禁止所有写操作
+    #[serde(default, rename = "readOnly")]
+    pub read_only: bool,
+    /// 客户端加密的key前缀，这些前缀下的值在写入前加密、读取后解密
+    #[serde(default, rename = "encryptedPrefixes")]
+    pub encrypted_prefixes: Vec<String>,
+    /// 服务端的 `--max-txn-ops` 配置，批量写入时据此拆分事务，为空时使用etcd默认值
+    #[serde(default, rename = "maxTxnOps")]
+    pub max_txn_ops: Option<usize>,
+    /// 服务端的 `--max-request-bytes` 配置，为空时使用etcd默认值
+    #[serde(default, rename = "maxRequestBytes")]
+    pub max_request_bytes: Option<usize>,
+    /// 客户端接收gRPC消息的最大字节数，为空时使用默认的4MB，读取大量或较大的值时需要调大
+    #[serde(default, rename = "maxRecvMessageBytes")]
+    pub max_recv_message_bytes: Option<usize>,
+    /// 客户端发送gRPC消息的最大字节数，为空时不限制
+    #[serde(default, rename = "maxSendMessageBytes")]
+    pub max_send_message_bytes: Option<usize>,
+    /// 连接后根据成员列表添加其他成员的地址，在所有成员间负载均衡
+    #[serde(default, rename = "syncEndpoints")]
+    pub sync_endpoints: bool,
+    /// 继承的基础连接配置名，未配置的项使用基础配置中的值，打开连接时解析
+    #[serde(default)]
+    pub extends: Option<String>,
+    /// key树的分隔方式，为空时使用设置中的分隔符
+    #[serde(default, rename = "keyTree")]
+    pub key_tree: Option<KeyTreeConfig>,
+    /// 连接时提示输入的凭据，这些凭据不会保存
+    #[serde(default, rename = "promptCredentials")]
+    pub prompt_credentials: Vec<CredentialKind>,
+    /// 在etcd中记录用户为key选择的内容类型，为空时不读写
+    #[serde(default, rename = "contentHints")]
+    pub content_hints: Option<ContentHintConfig>,
+}
+
+/// 内容类型提示的存储位置
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all="camelCase")]
+pub enum ContentHintStorage {
+    /// 与key同级的 `<key>.__meta`
+    Sibling,
+    /// 统一保存在 `prefix` 下
+    Prefix,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct ContentHintConfig {
+    pub storage: ContentHintStorage,
+    /// `Prefix` 模式的存储前缀，为空时使用 `/.etcd-workbench/content-hints/`
+    #[serde(default)]
+    pub prefix: Option<String>,
+}
+
+/// 可以在连接时输入而不保存的凭据
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all="camelCase")]
+pub enum CredentialKind {
+    /// etcd用户的密码
+    EtcdPassword,
+    /// SSH用户的密码
+    SshPassword,
+    /// SSH私钥的密码
+    SshPassphrase,
+}
+
+/// 连接时请求用户输入凭据，前端通过 `respond_credential_request` 回复
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct CredentialRequest {
+    pub id: String,
+    pub connection: String,
+    pub kind: CredentialKind,
+    /// 凭据所属的用户，如 `root` 或 `ops@bastion:22`
+    pub target: String,
+}
+
+/// key树的构建模式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all="camelCase")]
+pub enum KeyTreeMode {
+    /// 使用指定的分隔符，可以是多个字符
+    Separator,
+    /// 不分层，所有key平铺展示
+    Flat,
+    /// 从多个候选分隔符中选择层级结构最好的一个
+    Mixed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct KeyTreeConfig {
+    pub mode: KeyTreeMode,
+    /// `Separator` 模式使用的分隔符
+    #[serde(default)]
+    pub separator: Option<String>,
+    /// `Mixed` 模式的候选分隔符，为空时使用默认候选
+    #[serde(default)]
+    pub candidates: Vec<String>,
+}
+
+/// 分隔符产生的层级结构评估
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SeparatorScore {
+    pub separator: String,
+    /// 包含该分隔符的key比例
+    pub coverage: f64,
+    /// 平均层数
+    pub avg_depth: f64,
+    pub max_depth: usize,
+    /// 不同目录的数量
+    pub dirs: usize,
+    pub score: f64,
+}
+
+/// 连接最终使用的分隔符，`Mixed` 模式下附带各候选分隔符的评估结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct KeySeparatorInfo {
+    pub mode: KeyTreeMode,
+    /// 为空表示平铺
+    pub separator: String,
+    pub scores: Vec<SeparatorScore>,
+}
+
+impl Connection {
+    /// 用基础配置补全当前配置中未设置的项，`extends` 替换为基础配置的继承关系以便继续向上解析
+    pub fn inherit(&mut self, base: &Connection) {
+        if self.host.is_empty() {
+            self.host = base.host.clone();
+        }
+        if self.port == 0 {
+            self.port = base.port;
+        }
+        if self.namespace.is_none() {
+            self.namespace = base.namespace.clone();
+        }
+        if self.user.is_none() {
+            self.user = base.user.clone();
+        }
+        if self.tls.is_none() {
+            self.tls = base.tls.clone();
+        }
+        if self.ssh.is_none() {
+            self.ssh = base.ssh.clone();
+        }
+        if self.idle_timeout_minutes.is_none() {
+            self.idle_timeout_minutes = base.idle_timeout_minutes;
+        }
+        self.read_only = self.read_only || base.read_only;
+        self.sync_endpoints = self.sync_endpoints || base.sync_endpoints;
+        if self.encrypted_prefixes.is_empty() {
+            self.encrypted_prefixes = base.encrypted_prefixes.clone();
+        }
+        if self.max_txn_ops.is_none() {
+            self.max_txn_ops = base.max_txn_ops;
+        }
+        if self.max_request_bytes.is_none() {
+            self.max_request_bytes = base.max_request_bytes;
+        }
+        if self.max_recv_message_bytes.is_none() {
+            self.max_recv_message_bytes = base.max_recv_message_bytes;
+        }
+        if self.max_send_message_bytes.is_none() {
+            self.max_send_message_bytes = base.max_send_message_bytes;
+        }
+        if self.key_tree.is_none() {
+            self.key_tree = base.key_tree.clone();
+        }
+        if self.prompt_credentials.is_empty() {
+            self.prompt_credentials = base.prompt_credentials.clone();
+        }
+        if self.content_hints.is_none() {
+            self.content_hints = base.content_hints.clone();
+        }
+        self.extends = base.extends.clone();
+    }
+
+    /// 清除连接时输入的凭据，保存配置前调用
+    pub fn strip_prompted_credentials(&mut self) {
+        for kind in &self.prompt_credentials {
+            match kind {
+                CredentialKind::EtcdPassword => {
+                    if let Some(user) = self.user.as_mut() {
+                        user.password.zeroize();
+                    }
+                }
+                CredentialKind::SshPassword => {
+                    if let Some(identity) = self.ssh.as_mut().and_then(|ssh| ssh.identity.as_mut()) {
+                        identity.password.zeroize();
+                    }
+                }
+                CredentialKind::SshPassphrase => {
+                    if let Some(key) = self.ssh.as_mut().and_then(|ssh| ssh.identity.as_mut()).and_then(|i| i.key.as_mut()) {
+                        key.passphrase.zeroize();
+                    }
+                }
+            }
+        }
+    }
+
+    /// 清除所有密码和私钥，只保留连接地址等非敏感配置
+    pub fn clear_secrets(&mut self) {
+        if let Some(user) = self.user.as_mut() {
+            user.password.zeroize();
+        }
+        if let Some(identity) = self.tls.as_mut().and_then(|tls| tls.identity.as_mut()) {
+            identity.key.zeroize();
+            identity.passphrase.zeroize();
+            identity.pkcs12.zeroize();
+        }
+        if let Some(identity) = self.ssh.as_mut().and_then(|ssh| ssh.identity.as_mut()) {
+            identity.password.zeroize();
+            if let Some(key) = identity.key.as_mut() {
+                key.key.zeroize();
+                key.passphrase.zeroize();
+            }
+        }
+    }
+
+    /// 解析并校验连接的etcd地址
+    pub fn endpoint(&self) -> Result<EndpointAddress, LogicError> {
+        EndpointAddress::parse(&self.host, self.port).map_err(LogicError::IllegalArgument)
+    }
+}
+
+/// unix socket地址的前缀
+pub const UNIX_SOCKET_SCHEME: &str = "unix://";
+
+/// 解析后的连接地址
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EndpointAddress {
+    /// 主机名、IPv4或IPv6地址（不含方括号）
+    Tcp { host: String, port: u16 },
+    /// unix socket文件的绝对路径
+    Unix(String),
+}
+
+impl EndpointAddress {
+    /// 解析 `host` 和 `port`，`host` 可以是主机名、IPv4、IPv6（可带方括号）或 `unix:///path/to/socket`，
+    /// unix socket地址忽略端口。格式错误时返回可直接展示的错误描述
+    pub fn parse(host: &str, port: u16) -> Result<Self, String> {
+        let host = host.trim();
+        if host.is_empty() {
+            return Err(String::from("Host is required"));
+        }
+        if let Some(path) = host.strip_prefix(UNIX_SOCKET_SCHEME) {
+            if !path.starts_with('/') || path.len() < 2 {
+                return Err(format!(
+                    "Invalid unix socket address {}, expected an absolute path like unix:///run/etcd.sock",
+                    host
+                ));
+            }
+            return Ok(EndpointAddress::Unix(String::from(path)));
+        }
+
+        let host = if let Some(inner) = host.strip_prefix('[') {
+            let inner = inner
+                .strip_suffix(']')
+                .ok_or_else(|| format!("Invalid IPv6 address {}, the closing bracket is missing", host))?;
+            inner
+                .parse::<std::net::Ipv6Addr>()
+                .map_err(|_| format!("Invalid IPv6 address {}", host))?;
+            inner
+        } else if host.contains(':') {
+            if host.parse::<std::net::Ipv6Addr>().is_err() {
+                return Err(if host.matches(':').count() == 1 {
+                    format!("Host {} should not contain a port, set the port separately", host)
+                } else {
+                    format!("Invalid IPv6 address {}", host)
+                });
+            }
+            host
+        } else {
+            let valid = host
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_'));
+            if !valid || host.starts_with('.') || host.starts_with('-') {
+                return Err(format!("Invalid host {}", host));
+            }
+            host
+        };
+        if port == 0 {
+            return Err(String::from("Port must be between 1 and 65535"));
+        }
+        Ok(EndpointAddress::Tcp {
+            host: String::from(host),
+            port,
+        })
+    }
+}
+
+/// 连接使用的地址，IPv6地址带方括号，如 `[::1]:2379`、`unix:///run/etcd.sock`
+impl std::fmt::Display for EndpointAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EndpointAddress::Tcp { host, port } if host.contains(':') => write!(f, "[{}]:{}", host, port),
+            EndpointAddress::Tcp { host, port } => write!(f, "{}:{}", host, port),
+            EndpointAddress::Unix(path) => write!(f, "{}{}", UNIX_SOCKET_SCHEME, path),
+        }
+    }
 }
 
 /// 连接信息
@@ -68,7 +499,90 @@ pub struct ConnectionInfo {
     pub key_collection: Vec<String>,
     //  key监控列表
     #[serde(default = "default_key_monitor_list")]
-    pub key_monitor_list: Vec<KeyMonitorConfig>
+    pub key_monitor_list: Vec<KeyMonitorConfig>,
+    //  监听事件的通知规则
+    #[serde(default)]
+    pub notification_rules: Vec<NotificationRule>,
+    //  是否为模板，模板只用于被其他连接继承
+    #[serde(default)]
+    pub template: bool,
+    //  key或前缀的本地注释，不写入etcd
+    #[serde(default)]
+    pub annotations: Vec<KeyAnnotation>,
+    //  定时报告配置
+    #[serde(default)]
+    pub report_schedule: Option<ReportSchedule>,
+    //  定时压缩、碎片整理计划
+    #[serde(default)]
+    pub maintenance_schedules: Vec<MaintenanceSchedule>,
+    //  值模板中 `${NAME}` 引用的变量
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+    //  覆盖设置中的定时任务间隔秒数
+    #[serde(default)]
+    pub poll_interval_seconds: BTreeMap<PollTask, u64>,
+    //  实时统计key数量的前缀，连接时自动开始统计
+    #[serde(default)]
+    pub tracked_prefixes: Vec<String>,
+}
+
+/// 附加在key或前缀上的本地注释
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct KeyAnnotation {
+    /// key或前缀（全路径）
+    pub key: String,
+    /// 为true时注释作用于以 `key` 开头的所有key
+    #[serde(default)]
+    pub prefix: bool,
+    #[serde(default)]
+    pub note: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 颜色标签，如 `#ff5252`
+    pub color: Option<String>,
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+/// 保存在etcd中的共享注释
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SharedAnnotation {
+    #[serde(flatten)]
+    pub annotation: KeyAnnotation,
+    /// 最后修改者，格式为 `user@host`
+    pub author: String,
+    /// 注释在etcd中的修改版本，更新和删除时用于检测冲突
+    pub mod_revision: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct SharedAnnotations {
+    pub annotations: Vec<SharedAnnotation>,
+    pub key_collection: Vec<String>,
+    /// 由更新版本的workbench写入、当前版本无法读取的记录数
+    pub unsupported: usize,
+}
+
+/// 写入共享注释的结果，发生冲突时 `current` 为etcd中的最新内容
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SharedAnnotationWriteResult {
+    pub success: bool,
+    pub mod_revision: i64,
+    pub current: Option<SharedAnnotation>,
+}
+
+/// 收藏夹及注释，用于在连接之间导出导入
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct KeyBookmarks {
+    #[serde(default)]
+    pub key_collection: Vec<String>,
+    #[serde(default)]
+    pub annotations: Vec<KeyAnnotation>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -83,6 +597,44 @@ pub struct SessionData {
     pub key_monitor_list: Option<Vec<KeyMonitorConfig>>
 }
 
+/// 连接最近一次读取到的集群状态，集群不可达时用于离线查看
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct CachedState {
+    pub connection: String,
+    /// 数据来源集群，格式为 `host:port{namespace}`
+    pub scope: String,
+    /// 最近一次读取的所有key，不含值
+    #[serde(default)]
+    pub keys: Option<Vec<SerializableKeyValue>>,
+    #[serde(default)]
+    pub keys_cached_at: Option<u64>,
+    /// key数量超过缓存上限，`keys` 只包含部分key
+    #[serde(default)]
+    pub keys_truncated: bool,
+    #[serde(default)]
+    pub cluster: Option<SerializableCluster>,
+    #[serde(default)]
+    pub cluster_cached_at: Option<u64>,
+    /// 最近一次健康检查正常时的结果
+    #[serde(default)]
+    pub health: Option<HealthState>,
+    /// 最近查看过的key的值，按查看时间从旧到新排列
+    #[serde(default)]
+    pub values: Vec<CachedValue>,
+    pub updated_at: u64,
+    /// 连接当前没有可用的会话，数据可能已过期
+    #[serde(default)]
+    pub stale: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct CachedValue {
+    pub kv: SerializableKeyValue,
+    pub cached_at: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all="camelCase")]
 pub struct KeyMonitorConfig {
@@ -115,4 +667,177 @@ fn default_key_collection() -> Vec<String> {
 
 fn default_key_monitor_list() -> Vec<KeyMonitorConfig> {
     vec![]
-}
\ No newline at end of file
+}
+/// 连接因空闲超时被自动断开时推送的事件数据
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SessionIdleClosed {
+    pub session: i32,
+    /// 最后一次操作的时间（毫秒时间戳）
+    pub last_active_time: u64,
+    pub idle_timeout_minutes: u64,
+}
+
+/// 系统唤醒后重建连接时推送的事件数据
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SessionReconnected {
+    pub session: i32,
+    /// 重建失败的原因，成功时为空
+    pub error_msg: Option<String>,
+}
+
+/// 可导入连接配置的其他etcd客户端
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all="camelCase")]
+pub enum ExternalConnectionSource {
+    EtcdManager,
+    Etcdkeeper,
+    Kstone,
+}
+
+/// 从其他客户端配置中解析出的连接
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct ExternalConnection {
+    pub name: String,
+    pub connection: Connection,
+    /// 无法完整转换的配置项，如读取失败的证书文件
+    pub warnings: Vec<String>,
+}
+
+/// 导入其他客户端连接配置的结果
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct ExternalImportResult {
+    pub imported: Vec<String>,
+    /// 已存在同名连接而跳过的连接
+    pub skipped: Vec<String>,
+}
+
+/// 深度链接的处理结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all="camelCase")]
+pub struct DeepLinkEvent {
+    pub connection: String,
+    /// 需要定位的key
+    pub key: Option<String>,
+    /// 需要查看的历史版本，为空时查看最新值
+    pub revision: Option<i64>,
+    /// 连接的会话，复用已打开的会话时 `session_data` 为空
+    pub session: Option<i32>,
+    pub session_data: Option<SessionData>,
+    /// 自动连接失败的原因，如需要输入密码，前端需提示用户后手动连接
+    pub error: Option<LogicError>,
+}
+
+/// 最近访问的key
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct RecentKey {
+    pub connection: String,
+    pub key: String,
+    /// 访问时间（毫秒时间戳）
+    pub time: u64,
+}
+
+/// 快速打开的搜索结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct QuickOpenItem {
+    /// connection, bookmark, recentKey
+    pub kind: String,
+    pub connection: String,
+    pub key: Option<String>,
+    pub score: i64,
+    /// 匹配到的字符下标，基于 `key`，没有key时基于 `connection`
+    pub matched: Vec<usize>,
+}
+
+/// 监听事件类型
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all="camelCase")]
+pub enum WatchEventType {
+    Create,
+    Modify,
+    Delete,
+}
+
+/// 对key的当前值进行判断的条件
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "op", content = "value", rename_all="camelCase")]
+pub enum ValuePredicate {
+    Contains(String),
+    Equals(String),
+    NotEquals(String),
+    /// 值为数字且大于给定值
+    GreaterThan(f64),
+    /// 值为数字且小于给定值
+    LessThan(f64),
+}
+
+impl ValuePredicate {
+    pub fn test(&self, value: &[u8]) -> bool {
+        let value = String::from_utf8_lossy(value);
+        let number = || value.trim().parse::<f64>().ok();
+        match self {
+            ValuePredicate::Contains(s) => value.contains(s.as_str()),
+            ValuePredicate::Equals(s) => value == s.as_str(),
+            ValuePredicate::NotEquals(s) => value != s.as_str(),
+            ValuePredicate::GreaterThan(n) => number().map(|v| v > *n).unwrap_or(false),
+            ValuePredicate::LessThan(n) => number().map(|v| v < *n).unwrap_or(false),
+        }
+    }
+}
+
+/// 监听事件的桌面通知规则
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct NotificationRule {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    /// key匹配模式，支持 `*` 和 `?` 通配符
+    pub key_pattern: String,
+    pub event_types: Vec<WatchEventType>,
+    #[serde(default)]
+    pub value_predicate: Option<ValuePredicate>,
+    #[serde(default)]
+    pub muted: bool,
+    /// 暂停通知的截止时间（毫秒时间戳）
+    #[serde(default)]
+    pub snooze_until: Option<u64>,
+}
+
+impl NotificationRule {
+    /// 规则是否处于生效状态，未静音且不在暂停期内
+    pub fn is_active(&self, now: u64) -> bool {
+        !self.muted && self.snooze_until.map(|t| now >= t).unwrap_or(true)
+    }
+
+    /// 判断事件是否匹配规则，删除事件没有值，配置了值条件时不会匹配
+    pub fn matches(&self, key: &str, event_type: WatchEventType, value: Option<&[u8]>) -> bool {
+        if !self.event_types.contains(&event_type) || !glob_match(&self.key_pattern, key) {
+            return false;
+        }
+        match (&self.value_predicate, value) {
+            (None, _) => true,
+            (Some(predicate), Some(value)) => predicate.test(value),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// 通知规则被触发，通过 `notification_rule_triggered` 事件推送给窗口
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct NotificationRuleTriggered {
+    pub session: i32,
+    pub rule_id: String,
+    pub rule_name: String,
+    pub key: String,
+    pub event_type: WatchEventType,
+    pub time: u64,
+}
+
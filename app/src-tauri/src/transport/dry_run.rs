@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+/// 修改类命令的返回值，试运行时返回变更计划，否则返回命令原本的结果。
+///
+/// 不带标签序列化，未开启试运行时与原来的返回值完全一致
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Mutation<T> {
+    Done(T),
+    Plan(MutationPlan),
+}
+
+/// 试运行生成的变更计划，不执行任何修改
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct MutationPlan {
+    /// 试运行的命令名
+    pub command: String,
+    /// 所有操作是否在同一个事务中执行
+    pub txn: bool,
+    /// 事务的比较条件，如 `mod_revision("/a") == 10`
+    pub compares: Vec<String>,
+    pub ops: Vec<PlannedOp>,
+    /// 读取当前状态时的集群版本，未读取时为0
+    pub revision: i64,
+}
+
+/// 变更计划中的一个操作
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct PlannedOp {
+    /// put, delete, leaseGrant, leaseRevoke, attachLease, userAdd, roleGrantPermission 等
+    pub op: String,
+    /// 操作的key、lease、用户或角色
+    pub target: String,
+    /// key当前是否存在，非key操作为空
+    pub exists: Option<bool>,
+    /// key当前的修改版本
+    pub mod_revision: Option<i64>,
+    /// 写入值的字节数
+    pub value_size: Option<usize>,
+    pub lease: Option<String>,
+    /// 受影响的key数量，如删除lease时一并删除的key
+    pub affected: Option<usize>,
+}
+
+impl PlannedOp {
+    pub fn new(op: &str, target: impl Into<String>) -> Self {
+        PlannedOp {
+            op: String::from(op),
+            target: target.into(),
+            ..Default::default()
+        }
+    }
+}
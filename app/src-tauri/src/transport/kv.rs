@@ -1,17 +1,23 @@
 use etcd_client::KeyValue;
 use serde::{Deserialize, Serialize};
 
+use crate::utils::text_diff::DiffLine;
+
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all="camelCase")]
 pub enum FormatLanguage {
-    Json
+    Json,
+    Yaml,
+    Text,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all="camelCase")]
 pub enum FormatSource {
-    Kubernetes
+    Kubernetes,
+    /// 值解码插件
+    Plugin,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -22,6 +28,9 @@ pub struct FormattedValue {
     pub language: FormatLanguage,
     //  格式化内容
     pub value: String,
+    /// 解码该值的插件名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -34,28 +43,48 @@ pub struct SerializableKeyValue {
     pub value: Vec<u8>,
     pub lease: String,
     pub lease_info: Option<SerializableLeaseSimpleInfo>,
-    pub formatted_value: Option<FormattedValue>
+    pub formatted_value: Option<FormattedValue>,
+    /// 值在etcd中是否为客户端加密存储
+    #[serde(default)]
+    pub encrypted: bool,
+    /// 解密失败的原因，此时 `value` 为加密后的原始值
+    #[serde(default)]
+    pub decrypt_error: Option<String>,
+    /// 值过大时不随结果返回，`value` 为空，需要通过该句柄读取
+    #[serde(default)]
+    pub value_handle: Option<ValueHandle>,
+}
+
+/// 大值的读取句柄，通过 `url` 以二进制读取一次后失效
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct ValueHandle {
+    pub token: String,
+    pub url: String,
+    pub size: usize,
 }
 
 impl From<KeyValue> for SerializableKeyValue {
     fn from(kv: KeyValue) -> Self {
-        unsafe {
-            let key = String::from(kv.key_str_unchecked());
-            let value = Vec::from(kv.value());
-            let create_revision = kv.create_revision();
-            let mod_revision = kv.mod_revision();
-            let version = kv.version();
-            let lease = kv.lease().to_string();
-            SerializableKeyValue {
-                key,
-                value,
-                create_revision,
-                mod_revision,
-                version,
-                lease,
-                lease_info: None,
-                formatted_value: None
-            }
+        //  非UTF-8的key仅做有损转换用于展示
+        let key = String::from_utf8_lossy(kv.key()).to_string();
+        let value = Vec::from(kv.value());
+        let create_revision = kv.create_revision();
+        let mod_revision = kv.mod_revision();
+        let version = kv.version();
+        let lease = kv.lease().to_string();
+        SerializableKeyValue {
+            key,
+            value,
+            create_revision,
+            mod_revision,
+            version,
+            lease,
+            lease_info: None,
+            formatted_value: None,
+            encrypted: false,
+            decrypt_error: None,
+            value_handle: None,
         }
     }
 }
@@ -82,9 +111,631 @@ pub struct SerializableLeaseSimpleInfo {
     pub granted_ttl: i64
 }
 
+/// workbench 代为续约的lease状态
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct LeaseKeepAliveState {
+    pub session: i32,
+    pub lease: String,
+    /// 最近一次续约后的剩余时间（秒）
+    pub ttl: i64,
+    /// 最近一次续约的时间（毫秒时间戳）
+    pub keep_alive_time: u64,
+    /// 下一次续约的时间（毫秒时间戳）
+    pub next_keep_alive_time: u64,
+    /// 续约是否已停止，lease过期或出错时也会停止
+    pub stopped: bool,
+    pub error_msg: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all="camelCase")]
 pub struct SearchResult {
     pub count: usize,
-    pub results: Vec<SerializableKeyValue>
-}
\ No newline at end of file
+    pub results: Vec<SerializableKeyValue>,
+    /// 搜索时读取的数据版本
+    pub revision: i64,
+    /// 因key不是UTF-8而未放入结果的数量
+    #[serde(default)]
+    pub binary_skipped: usize,
+}
+
+/// 键空间的版本范围
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct KeyspaceBounds {
+    /// 当前版本
+    pub revision: i64,
+    /// 压缩版本，未压缩过时为0
+    pub compact_revision: i64,
+    /// 最早可读取的版本
+    pub oldest_revision: i64,
+}
+
+/// key路径自动补全项
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct KeyCompletion {
+    /// 补全后的路径，包含输入的前缀
+    pub path: String,
+    /// 该路径下key的数量
+    pub key_count: usize,
+    /// 是否为目录（以路径分隔符结尾）
+    pub is_dir: bool,
+}
+
+/// 订阅前缀自上次查看以来的变化统计
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct PrefixChangeCounter {
+    pub session: i32,
+    pub prefix: String,
+    pub added: u64,
+    pub modified: u64,
+    pub deleted: u64,
+    /// 开始统计的时间（毫秒时间戳）
+    pub since: u64,
+}
+
+/// 用户为key选择的内容类型，保存在etcd中，再次打开时使用对应的编辑器语言
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct ContentHint {
+    /// 编辑器语言，如 `json`、`yaml`、`blob`
+    pub content_type: String,
+    /// 值的编码，如 `base64`、`gzip`
+    #[serde(default)]
+    pub encoding: Option<String>,
+}
+
+/// 前缀下key的实时数量，先按前缀统计一次，之后根据监听事件增减
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct PrefixKeyCount {
+    pub session: i32,
+    pub prefix: String,
+    pub count: i64,
+    /// 统计结果对应的版本
+    pub revision: i64,
+    /// 最后更新时间（毫秒时间戳），尚未完成统计时为0
+    pub updated_at: u64,
+    /// 监听中断时为false，此时数量可能已过时，恢复后会重新统计
+    pub live: bool,
+}
+
+/// 前缀监听中单个key合并后的最新事件
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct WatchKeyEvent {
+    pub key: String,
+    /// put 或 delete
+    pub op: String,
+    pub mod_revision: i64,
+    pub version: i64,
+    pub lease: i64,
+    /// UTF-8的值，删除时为空
+    pub value: Option<String>,
+    /// 值不是UTF-8时的原始内容（base64）
+    pub raw_value: Option<String>,
+}
+
+/// 一个刷新周期内推送给前端的事件批次
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct WatchEventBatch {
+    pub session: i32,
+    pub prefix: String,
+    /// 按修改版本排序，同一个key只保留最新的事件
+    pub events: Vec<WatchKeyEvent>,
+    /// 本批次中被同一key的新事件覆盖的事件数
+    pub merged: u64,
+    /// 本批次中超出速率限制而丢弃的事件数，大于0时前端需要重新加载前缀
+    pub dropped: u64,
+    /// 订阅以来累计合并的事件数
+    pub total_merged: u64,
+    /// 订阅以来累计丢弃的事件数
+    pub total_dropped: u64,
+}
+
+/// 某一时间观察到的集群版本
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all="camelCase")]
+pub struct RevisionTimeSample {
+    /// 观察时间（毫秒时间戳）
+    pub time: u64,
+    pub revision: i64,
+}
+
+/// 控制台命令的执行结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct ConsoleResult {
+    pub output: String,
+    /// 写操作需要用户确认，为 true 时命令未执行，`output` 为将要进行的操作描述
+    pub confirm_required: bool,
+}
+
+/// 分页读取结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct KeyValuePage {
+    /// 本页读取的数据版本，传入后续分页请求可固定读取版本
+    pub revision: i64,
+    /// 是否还有更多数据
+    pub more: bool,
+    pub kvs: Vec<SerializableKeyValue>,
+    /// 本页读取到的最后一个key，下一页从该key之后读取。列表隐藏了部分key时可能不在 `kvs` 中
+    pub cursor: Option<String>,
+}
+/// 键值缓存的统计信息
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct ValueCacheStats {
+    /// 缓存是否可用，watch 断开后缓存会自动失效
+    pub enabled: bool,
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// 编辑锁的持有者信息
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct EditLockInfo {
+    pub key: String,
+    /// 编辑者身份，格式为 `user@host`
+    pub owner: String,
+    /// 编辑者使用的etcd用户
+    pub etcd_user: Option<String>,
+    pub session: i32,
+    /// 加锁时间（毫秒时间戳）
+    pub since: u64,
+    /// 是否为其他workbench实例通过etcd持有的锁
+    #[serde(default)]
+    pub remote: bool,
+}
+
+/// 获取编辑锁的结果，未获取到时 `holder` 为当前持有者
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct EditLockResult {
+    pub acquired: bool,
+    pub holder: Option<EditLockInfo>,
+}
+
+/// 批量写入的键值对
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct KeyValuePair {
+    pub key: String,
+    pub value: Vec<u8>,
+}
+
+/// 批量写入结果，失败时已写入的批次不会回滚
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct BatchPutResult {
+    pub total: usize,
+    pub written: usize,
+    /// 实际提交的事务数
+    pub batches: usize,
+    pub error_msg: Option<String>,
+}
+
+/// 将前缀下的key绑定到新lease的结果
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct LeaseAttachResult {
+    /// 没有任何key绑定成功时lease已被撤销，为0
+    pub lease: i64,
+    pub ttl: i64,
+    pub total: usize,
+    pub attached: usize,
+    /// 实际提交的事务数
+    pub batches: usize,
+    /// 读取后被修改而未绑定的key
+    pub conflicts: Vec<String>,
+    pub error_msg: Option<String>,
+}
+
+/// 可能已废弃的key的类型
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all="camelCase")]
+pub enum GarbageKind {
+    /// 长时间未修改
+    Stale,
+    EmptyValue,
+    /// 遗留的锁或选举key
+    OrphanLock,
+}
+
+/// 废弃数据分析的参数，未设置的条件不参与判断
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct GarbageOptions {
+    pub prefix: String,
+    /// 超过该天数未修改视为废弃，依赖连接期间观察到的版本时间记录
+    pub stale_days: Option<u64>,
+    /// 修改版本落后当前版本超过该数量视为废弃
+    pub stale_revisions: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct GarbageItem {
+    pub key: String,
+    pub kind: GarbageKind,
+    pub mod_revision: i64,
+    pub lease: i64,
+    /// 至少已多久未修改（毫秒），无法估算时为空
+    pub unmodified_millis: Option<u64>,
+}
+
+/// 废弃数据分析报告
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct GarbageReport {
+    pub prefix: String,
+    pub revision: i64,
+    pub scanned: usize,
+    /// 最早的版本时间记录（毫秒时间戳），早于该时间的修改无法估算时间
+    pub timeline_start: Option<u64>,
+    pub items: Vec<GarbageItem>,
+}
+
+/// 清理废弃数据的结果
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct GarbageCleanupResult {
+    pub deleted: Vec<String>,
+    /// 分析后被修改而未删除的key
+    pub conflicts: Vec<String>,
+}
+
+/// 导入时的key前缀映射，将 `source` 前缀替换为 `target`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct PrefixMapping {
+    pub source: String,
+    pub target: String,
+}
+
+/// 镜像时目标中已存在key的处理方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all="camelCase")]
+pub enum MirrorConflictPolicy {
+    /// 源数据覆盖目标，源中删除的key在目标中同样删除
+    Overwrite,
+    /// 目标优先，不覆盖目标中已存在或在目标连接被修改过的key，只同步镜像写入后未被修改的key的更新和删除
+    DestinationWins,
+}
+
+/// 镜像配置，将源连接中 `prefix` 下的数据单向同步到目标连接
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct MirrorConfig {
+    pub source_session: i32,
+    pub target_session: i32,
+    pub prefix: String,
+    /// 目标中的前缀，为空时与源前缀相同
+    pub target_prefix: Option<String>,
+    pub conflict_policy: MirrorConflictPolicy,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all="camelCase")]
+pub enum MirrorState {
+    /// 正在进行首次全量同步
+    Syncing,
+    Running,
+    Paused,
+    Stopped,
+    Error,
+}
+
+/// 镜像的运行状态
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct MirrorStatus {
+    pub id: i32,
+    pub config: MirrorConfig,
+    pub state: MirrorState,
+    /// 首次全量同步的key数量
+    pub synced_keys: usize,
+    /// 已应用的变更事件数
+    pub applied_events: u64,
+    /// 观察到的源集群最新版本
+    pub source_revision: i64,
+    /// 已应用到目标的源版本
+    pub applied_revision: i64,
+    /// 落后的版本数
+    pub lag_revisions: i64,
+    /// 最近一批事件从本地收到到应用到目标完成的耗时，不包含源集群提交到推送的延迟
+    pub apply_millis: u64,
+    pub error_msg: Option<String>,
+}
+
+/// 热点key统计中的一项，可以是单个key或前缀
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct HotKeyStat {
+    pub key: String,
+    pub puts: u64,
+    pub deletes: u64,
+    /// 每秒修改次数
+    pub rate: f64,
+}
+
+/// 热点key分析报告
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct HotKeyReport {
+    pub session: i32,
+    /// 开始分析的时间（毫秒时间戳）
+    pub since: u64,
+    pub duration_seconds: u64,
+    pub elapsed_millis: u64,
+    pub total_events: u64,
+    pub finished: bool,
+    pub keys: Vec<HotKeyStat>,
+    pub prefixes: Vec<HotKeyStat>,
+}
+
+/// 历史归档中的一条修改记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct HistoryRecord {
+    pub key: String,
+    /// put 或 delete
+    pub event_type: String,
+    pub mod_revision: i64,
+    pub create_revision: i64,
+    pub version: i64,
+    pub lease: i64,
+    /// base64编码的原始值，删除记录为空
+    pub value: String,
+}
+
+/// 前缀的历史归档，包含可读取范围内的所有修改记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct HistoryArchive {
+    pub prefix: String,
+    /// 导出时间（毫秒时间戳）
+    pub exported_at: u64,
+    /// 压缩版本，早于此版本的历史已无法读取
+    pub compact_revision: i64,
+    pub start_revision: i64,
+    pub end_revision: i64,
+    /// 重放未追上导出时的版本，只包含到 `end_revision` 为止的记录
+    #[serde(default)]
+    pub truncated: bool,
+    pub records: Vec<HistoryRecord>,
+}
+
+/// 历史归档导出结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct HistoryExportResult {
+    pub records: usize,
+    pub keys: usize,
+    pub start_revision: i64,
+    pub end_revision: i64,
+    pub truncated: bool,
+    pub bytes: u64,
+}
+
+/// key的值与本地文件的对比结果，`old` 为key的值，`new` 为文件内容
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct FileDiffResult {
+    pub key_exists: bool,
+    /// key当前的修改版本，写回文件时用于CAS校验，key不存在时为0
+    pub mod_revision: i64,
+    pub identical: bool,
+    /// 任意一方为二进制内容时只比较是否相同，不提供逐行差异，非UTF-8的文本仍逐行对比
+    pub binary: bool,
+    pub added: usize,
+    pub removed: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+/// 前缀与本地目录同步的结果
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct DirSyncResult {
+    /// 写入的文件（拉取）或key（推送）
+    pub written: Vec<String>,
+    pub deleted: Vec<String>,
+    pub unchanged: usize,
+    /// 双方都有修改或CAS校验失败而跳过的key
+    pub conflicts: Vec<String>,
+}
+
+/// 展开树节点时预取下一层目录的结果
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct PrefetchResult {
+    /// 连接已建立完整的key索引，无需预取
+    pub from_index: bool,
+    /// 本次预取的前缀
+    pub prefixes: Vec<String>,
+    /// 预取失败或key数量过多未缓存的前缀
+    pub skipped: Vec<String>,
+}
+
+/// 前缀下的key列表（不含value）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct PrefixKeys {
+    pub keys: Vec<SerializableKeyValue>,
+    /// 是否为前缀下的全部key，为false时结果被截断
+    pub complete: bool,
+    /// 是否从内存索引返回
+    pub cached: bool,
+}
+
+/// 流式读取key时推送的一批数据，同一流的批次按 `seq` 顺序推送
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct KeyStreamBatch {
+    pub stream_id: String,
+    pub seq: u32,
+    /// 所有批次固定读取的版本
+    pub revision: i64,
+    pub keys: Vec<SerializableKeyValue>,
+    /// 是否为最后一批，出错时也会以最后一批结束
+    pub done: bool,
+    /// 截至本批已推送的key总数
+    pub total: usize,
+    pub error_msg: Option<String>,
+}
+
+/// 回收站中已删除的key，列表中不包含值
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct TrashEntry {
+    pub id: String,
+    /// 删除时所在的集群地址和命名空间，只能恢复到相同的集群
+    pub scope: String,
+    pub connection: Option<String>,
+    pub key: String,
+    /// key和值的字节数
+    pub size: usize,
+    /// 删除时绑定的lease，恢复时不再绑定
+    pub lease: i64,
+    pub deleted_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct TrashRestoreResult {
+    /// 已恢复的回收站记录ID
+    pub restored: Vec<String>,
+    /// key已存在、不属于当前集群或值无法解密而未恢复的key
+    pub skipped: Vec<String>,
+}
+
+/// 审计任务配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct AuditStreamConfig {
+    pub session: i32,
+    /// 审计文件的输出目录
+    pub dir: String,
+    /// 是否记录put事件的值，值可能包含敏感信息，默认不记录
+    #[serde(default)]
+    pub include_values: bool,
+}
+
+/// 追加到文件的内容格式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all="camelCase")]
+pub enum KeyTailFormat {
+    /// 每次修改追加一行值，删除时不追加
+    #[default]
+    Value,
+    /// 每次变更追加一行JSON，包含操作类型、版本及值
+    Json,
+}
+
+/// 持续将单个key的变更追加到本地文件的任务配置，类似对etcd中的配置执行 `tail -f`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct KeyTailConfig {
+    pub session: i32,
+    pub key: String,
+    /// 输出文件，已存在时追加
+    pub path: String,
+    #[serde(default)]
+    pub format: KeyTailFormat,
+    /// 文件超过该大小（MB）时轮转，为空使用默认值，为0时不轮转
+    pub max_file_size_mb: Option<u64>,
+    /// 轮转时保留的历史文件数，为空使用默认值
+    pub max_files: Option<usize>,
+}
+
+/// 前缀下的key绑定的lease及数量
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct DeletePreviewLease {
+    pub lease: String,
+    pub keys: usize,
+}
+
+/// 删除前缀前的预览，确认删除时通过 `token` 引用，删除仅在数据未发生变化时执行
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct DeletePreview {
+    pub token: String,
+    pub prefix: String,
+    /// 预览读取的版本
+    pub revision: i64,
+    pub count: usize,
+    /// key数量超过预览读取的上限，`total_bytes` 和 `leases` 只统计了读取到的部分
+    pub truncated: bool,
+    /// key和value的总字节数
+    pub total_bytes: u64,
+    /// 按绑定的key数量降序
+    pub leases: Vec<DeletePreviewLease>,
+    /// 部分将被删除的key
+    pub sample_keys: Vec<String>,
+    /// token过期时间（毫秒时间戳）
+    pub expire_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct DeletePrefixResult {
+    pub deleted: usize,
+    pub revision: i64,
+}
+
+/// 宏中的一个操作。值为UTF-8文本时保存在 `value`，否则以base64保存在 `rawValue`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum MacroOp {
+    #[serde(rename_all = "camelCase")]
+    Put {
+        key: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        value: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        raw_value: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ttl: Option<i64>,
+        /// 执行前使用目标连接的变量渲染值
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        render_variables: bool,
+    },
+    Delete {
+        keys: Vec<String>,
+    },
+    Rename {
+        from: String,
+        to: String,
+    },
+}
+
+/// 录制的操作序列，可在任意连接上重放
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct KvMacro {
+    pub name: String,
+    pub ops: Vec<MacroOp>,
+    /// 录制时使用的连接名
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub created_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct MacroRunResult {
+    /// 成功执行的操作数
+    pub executed: usize,
+    /// 执行失败的操作序号，之后的操作不再执行
+    pub failed_index: Option<usize>,
+    pub error: Option<String>,
+}
@@ -1,26 +1,40 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::transport::connection::CertificateInfo;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all="camelCase")]
 pub struct SerializableCluster {
     pub id: String,
     pub member_id: String,
     pub revision: i64,
     pub members: Vec<SerializableClusterMember>,
-    pub status: SerializableClusterStatus
+    /// 当前连接的端点状态，读取失败时为空，原因见 `status_error`
+    pub status: Option<SerializableClusterStatus>,
+    pub status_error: Option<String>,
+    /// 读取报警失败的原因，此时各成员的报警类型无效
+    pub alarm_error: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all="camelCase")]
 pub struct SerializableClusterMember {
     pub id: String,
     pub name: String,
     pub peer_uri: Vec<String>,
     pub client_uri: Vec<String>,
-    pub alarm_type: i32
+    pub alarm_type: i32,
+    /// 分别连接各成员读取的状态，未读取或读取失败时为空
+    #[serde(default)]
+    pub status: Option<SerializableClusterStatus>,
+    /// 读取成员状态失败的原因，成员宕机时不影响其他成员的结果
+    #[serde(default)]
+    pub error_msg: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all="camelCase")]
 pub struct SerializableClusterStatus {
     pub version: String,
@@ -38,7 +52,22 @@ pub struct SerializableClusterStatus {
 pub struct SnapshotState {
     pub received: u64,
     pub remain: u64,
-    pub error_msg: Option<String>
+    pub error_msg: Option<String>,
+    /// 快照总字节数，收到首个分片前未知
+    #[serde(default)]
+    pub total: Option<u64>,
+    /// 最近几秒的下载速率（字节/秒）
+    #[serde(default)]
+    pub throughput: f64,
+    /// 开始下载以来的平均速率（字节/秒）
+    #[serde(default)]
+    pub average_throughput: f64,
+    /// 按当前速率预计的剩余秒数
+    #[serde(default)]
+    pub eta_seconds: Option<u64>,
+    /// 传输中断后重新请求快照的次数
+    #[serde(default)]
+    pub resumes: u32,
 }
 
 impl Clone for SnapshotState {
@@ -46,7 +75,12 @@ impl Clone for SnapshotState {
         Self {
             received: self.received,
             remain: self.remain,
-            error_msg: self.error_msg.clone()
+            error_msg: self.error_msg.clone(),
+            total: self.total,
+            throughput: self.throughput,
+            average_throughput: self.average_throughput,
+            eta_seconds: self.eta_seconds,
+            resumes: self.resumes,
         }
     }
 }
@@ -65,4 +99,341 @@ pub struct SnapshotInfo {
 pub struct SnapshotStateEvent {
     pub id: i32,
     pub state: SnapshotState
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SerializableAlarm {
+    pub member_id: String,
+    pub alarm_type: i32
+}
+
+/// 连接的健康状态，由健康检查定时任务更新
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct HealthState {
+    pub session: i32,
+    pub healthy: bool,
+    /// 检查时间（毫秒时间戳）
+    pub check_time: u64,
+    pub error_msg: Option<String>,
+    pub alarms: Vec<SerializableAlarm>,
+    /// 即将过期或已过期的证书
+    pub cert_warnings: Vec<CertificateInfo>,
+}
+
+impl HealthState {
+    /// 根据健康状态生成告警：无法连接、存储空间不足、数据损坏以及证书即将过期
+    pub fn alerts(&self, connection: &str) -> Vec<Alert> {
+        let alert = |alert_type, subject: String, message: String| Alert {
+            alert_type,
+            session: self.session,
+            connection: String::from(connection),
+            subject,
+            message,
+            time: self.check_time,
+        };
+
+        let mut alerts = Vec::new();
+        if !self.healthy {
+            let reason = self.error_msg.clone().unwrap_or_default();
+            alerts.push(alert(AlertType::Unreachable, String::new(), format!("Member is unreachable: {}", reason)));
+        }
+        for alarm in &self.alarms {
+            //  etcdserverpb.AlarmType: 1 NOSPACE, 2 CORRUPT
+            match alarm.alarm_type {
+                1 => alerts.push(alert(AlertType::NoSpace, alarm.member_id.to_string(), format!("NOSPACE alarm on member {}", alarm.member_id))),
+                2 => alerts.push(alert(AlertType::Corrupt, alarm.member_id.to_string(), format!("CORRUPT alarm on member {}", alarm.member_id))),
+                _ => {}
+            }
+        }
+        for cert in &self.cert_warnings {
+            let message = if cert.expired {
+                format!("Certificate {} has expired", cert.subject)
+            } else {
+                format!("Certificate {} expires in {} days", cert.subject, cert.days_remaining)
+            };
+            alerts.push(alert(AlertType::CertExpiring, cert.fingerprint_sha256.clone(), message));
+        }
+        alerts
+    }
+}
+
+/// 连接端点支持的功能，通过 gRPC proxy 连接时部分运维操作不可用
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct EndpointCapabilities {
+    /// 连接的端点是否为 etcd gRPC proxy
+    pub grpc_proxy: bool,
+    /// 判断为 gRPC proxy 的依据
+    pub proxy_reason: Option<String>,
+    /// 碎片整理、快照等针对单个成员的运维操作
+    pub maintenance: bool,
+    /// 成员列表是否为真实的集群成员，proxy 配置了 `--resolver-prefix` 时返回的是 proxy 列表
+    pub member_management: bool,
+    /// 连接时获取到的服务端版本，获取失败时为空
+    pub server_version: String,
+    pub learner: bool,
+}
+
+impl EndpointCapabilities {
+    pub fn with_server_version(mut self, version: &str) -> Self {
+        self.server_version = String::from(version);
+        self.learner = ServerFeature::Learner.supported_by(version);
+        self
+    }
+}
+
+impl Default for EndpointCapabilities {
+    fn default() -> Self {
+        EndpointCapabilities {
+            grpc_proxy: false,
+            proxy_reason: None,
+            maintenance: true,
+            member_management: true,
+            server_version: String::new(),
+            learner: true,
+        }
+    }
+}
+
+/// 依赖服务端版本的功能
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all="camelCase")]
+pub enum ServerFeature {
+    /// learner 成员，3.4 引入
+    Learner,
+}
+
+impl ServerFeature {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ServerFeature::Learner => "Learner member",
+        }
+    }
+
+    pub fn min_version(&self) -> &'static str {
+        match self {
+            ServerFeature::Learner => "3.4.0",
+        }
+    }
+
+    /// 版本无法解析时视为支持，由服务端决定是否可用
+    pub fn supported_by(&self, version: &str) -> bool {
+        match (parse_version(version), parse_version(self.min_version())) {
+            (Some(current), Some(min)) => current >= min,
+            _ => true,
+        }
+    }
+}
+
+/// 解析 `major.minor.patch` 格式的版本号，忽略前缀 `v` 以及预发布后缀
+pub fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let version = version.trim().trim_start_matches('v');
+    let version = version.split(['-', '+']).next()?;
+    let mut parts = version.split('.').map(|p| p.parse::<u64>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+    let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+    Some((major, minor, patch))
+}
+
+/// 告警类型
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all="camelCase")]
+pub enum AlertType {
+    Unreachable,
+    NoSpace,
+    Corrupt,
+    CertExpiring,
+    MaintenanceFailed,
+}
+
+/// 由健康检查产生的告警，可发送到webhook
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct Alert {
+    pub alert_type: AlertType,
+    pub session: i32,
+    pub connection: String,
+    /// 告警对象，如成员ID、证书指纹、计划ID，与类型、会话一起用于告警去重
+    pub subject: String,
+    pub message: String,
+    /// 告警时间（毫秒时间戳）
+    pub time: u64,
+}
+
+/// 一次延迟采样结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct LatencySample {
+    /// 采样时间（毫秒时间戳）
+    pub time: u64,
+    /// 往返耗时（毫秒），请求失败时为空
+    pub rtt_ms: Option<f64>,
+    pub error_msg: Option<String>,
+}
+
+/// 某一节点的延迟采样记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct EndpointLatency {
+    /// 成员ID，为空时表示会话本身的连接（包含SSH隧道）
+    pub member_id: Option<String>,
+    pub name: String,
+    pub endpoint: String,
+    pub samples: Vec<LatencySample>,
+}
+
+/// 定时运维操作
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum MaintenanceAction {
+    /// 压缩历史版本，保留最近的 `retain_revisions` 个版本或 `retain_hours` 小时内的版本，同时指定时保留更多的一方
+    #[serde(rename_all = "camelCase")]
+    Compact {
+        retain_revisions: Option<i64>,
+        retain_hours: Option<u64>,
+    },
+    /// 依次整理各follower的碎片，一个完成后再整理下一个，`include_leader` 为true时最后整理leader
+    #[serde(rename_all = "camelCase")]
+    Defragment {
+        #[serde(default)]
+        include_leader: bool,
+    },
+}
+
+/// 连接的定时运维计划
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct MaintenanceSchedule {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    /// 五段式cron表达式，按本地时间执行
+    pub cron: String,
+    pub action: MaintenanceAction,
+    #[serde(default)]
+    pub enabled: bool,
+    /// 下次执行时间（毫秒时间戳），只用于展示
+    #[serde(default, skip_deserializing)]
+    pub next_run: Option<u64>,
+}
+
+/// 一次运维计划的执行记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct MaintenanceRun {
+    pub schedule_id: String,
+    pub name: String,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub success: bool,
+    pub message: String,
+}
+
+/// 单个成员的时钟及raft状态
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct MemberClock {
+    pub id: String,
+    pub name: String,
+    pub endpoint: Option<String>,
+    /// 成员时钟相对本机的偏差（毫秒），正数表示成员时钟较快
+    pub offset_millis: Option<i64>,
+    /// 偏差的误差范围（毫秒），由请求往返时间和HTTP Date头的秒级精度决定
+    pub uncertainty_millis: Option<u64>,
+    pub raft_term: Option<u64>,
+    pub leader: Option<String>,
+    pub error_msg: Option<String>,
+}
+
+/// 时钟漂移和leader切换风险
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all="camelCase")]
+pub enum DriftRisk {
+    Low,
+    Medium,
+    High,
+}
+
+/// 成员间时钟漂移诊断结果，`warnings` 不为空时界面展示警告
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct ClockDriftReport {
+    pub check_time: u64,
+    pub members: Vec<MemberClock>,
+    /// 各成员时钟之间的最大偏差（毫秒），可测量的成员少于两个时为空
+    pub max_drift_millis: Option<i64>,
+    /// 本会话期间观察到的raft任期变化次数及观察时长
+    pub term_changes: u64,
+    pub observed_millis: u64,
+    pub risk: DriftRisk,
+    pub warnings: Vec<String>,
+}
+
+/// 某一时刻各follower落后leader的已应用日志条数
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct RaftLagSample {
+    pub time: u64,
+    pub leader: String,
+    pub leader_applied_index: u64,
+    /// 成员ID -> 落后条数，读取状态失败的成员不在其中
+    pub lags: HashMap<String, u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct FollowerLag {
+    pub id: String,
+    pub name: String,
+    pub applied_index: Option<u64>,
+    /// 本次采样落后的条数
+    pub lag: Option<u64>,
+    /// 历史采样中的平均及最大落后条数
+    pub avg_lag: Option<f64>,
+    pub max_lag: u64,
+    /// 大部分采样都明显落后，可能是磁盘或网络较慢
+    pub slow: bool,
+    pub error_msg: Option<String>,
+}
+
+/// follower的raft进度落后情况，`samples` 为本会话期间的历史采样
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct RaftLagReport {
+    pub check_time: u64,
+    pub leader: Option<String>,
+    pub leader_applied_index: Option<u64>,
+    pub followers: Vec<FollowerLag>,
+    pub samples: Vec<RaftLagSample>,
+    pub warnings: Vec<String>,
+}
+
+/// 互斥的运维操作，同一连接同时只执行一个
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum MaintenanceOperation {
+    Defragment,
+    Snapshot,
+    Compact,
+}
+
+/// 运维队列中的操作，队首为正在执行的操作
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedOperation {
+    pub id: u64,
+    pub operation: MaintenanceOperation,
+    pub enqueued_at: u64,
+}
+
+/// 操作排队等待时推送，`ahead` 为排在前面的操作数量
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceQueueEvent {
+    pub session: i32,
+    pub operation: MaintenanceOperation,
+    pub ahead: usize,
+}
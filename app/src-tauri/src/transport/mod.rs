@@ -2,4 +2,10 @@ pub mod connection;
 pub mod kv;
 pub mod user;
 pub mod maintenance;
-pub mod settings;
\ No newline at end of file
+pub mod settings;
+pub mod updater;
+pub mod report;
+pub mod sandbox;
+pub mod plugin;
+pub mod action;
+pub mod dry_run;
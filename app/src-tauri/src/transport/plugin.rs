@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+use crate::transport::kv::FormatLanguage;
+
+/// 插件目录中的 `plugin.json`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct PluginManifest {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// 插件实现的接口版本，需与应用支持的版本一致
+    pub abi_version: u32,
+    /// WASM文件名，相对于插件目录
+    #[serde(default = "default_entry")]
+    pub entry: String,
+    /// 只对这些前缀下的key生效，为空时对所有key生效
+    #[serde(default)]
+    pub key_prefixes: Vec<String>,
+    /// 解码结果的格式
+    #[serde(default = "default_language")]
+    pub language: FormatLanguage,
+    /// 插件提供的自定义命令
+    #[serde(default)]
+    pub commands: Vec<PluginCommandDef>,
+    /// 执行命令时申请访问的key前缀，需用户在安装时授予后才能访问
+    #[serde(default)]
+    pub granted_prefixes: Vec<String>,
+    /// 是否申请在授权的前缀下写入，需用户在安装时授予
+    #[serde(default)]
+    pub allow_write: bool,
+}
+
+/// 用户安装插件时授予的权限，只能是插件申请的权限的子集
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct PluginGrant {
+    #[serde(default)]
+    pub prefixes: Vec<String>,
+    #[serde(default)]
+    pub allow_write: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct PluginCommandDef {
+    pub name: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+fn default_entry() -> String {
+    String::from("plugin.wasm")
+}
+
+fn default_language() -> FormatLanguage {
+    FormatLanguage::Json
+}
+
+/// 插件状态
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct PluginInfo {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub abi_version: u32,
+    pub key_prefixes: Vec<String>,
+    pub language: FormatLanguage,
+    pub path: String,
+    pub enabled: bool,
+    /// 是否已通过校验并加载
+    pub loaded: bool,
+    pub error_msg: Option<String>,
+    pub commands: Vec<PluginCommandDef>,
+    /// 插件申请的权限
+    pub requested_prefixes: Vec<String>,
+    pub requested_write: bool,
+    /// 用户授予的权限
+    pub granted_prefixes: Vec<String>,
+    pub allow_write: bool,
+}
+
+/// 插件命令输出的面板，`content` 的格式由 `kind` 决定：text、markdown 为字符串，json 为任意值，
+/// table 为 `{"columns": [...], "rows": [[...]]}`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct PluginPanel {
+    pub title: String,
+    pub kind: String,
+    pub content: serde_json::Value,
+}
+
+/// 插件命令的执行结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct PluginCommandResult {
+    pub success: bool,
+    pub panels: Vec<PluginPanel>,
+    pub error_msg: Option<String>,
+}
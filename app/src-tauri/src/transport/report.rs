@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+
+use crate::transport::maintenance::{SerializableAlarm, SerializableCluster, SerializableClusterStatus};
+use crate::transport::user::SerializableUser;
+
+/// 集群报告，用于变更评审和审计
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct ClusterReport {
+    /// 生成时间（毫秒时间戳）
+    pub generate_time: u64,
+    pub cluster: SerializableCluster,
+    pub member_status: Vec<MemberStatusReport>,
+    pub alarms: Vec<SerializableAlarm>,
+    pub keyspace: KeyspaceReport,
+    pub auth: AuthReport,
+}
+
+/// 单个成员节点的状态
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct MemberStatusReport {
+    pub id: String,
+    pub name: String,
+    pub endpoint: Option<String>,
+    pub status: Option<SerializableClusterStatus>,
+    pub error_msg: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct KeyspaceReport {
+    pub revision: i64,
+    pub compact_revision: i64,
+    pub total_keys: i64,
+    /// 按key数量排序的一级路径
+    pub top_prefixes: Vec<PrefixCount>,
+    /// 一级路径统计是否只基于部分key
+    pub sampled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct PrefixCount {
+    pub prefix: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct AuthReport {
+    pub users: Vec<SerializableUser>,
+    pub roles: Vec<String>,
+    /// 无权限读取认证信息时的错误
+    pub error_msg: Option<String>,
+}
+
+/// 定时生成集群报告并推送的配置，每个连接单独配置，仅在连接打开期间执行
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct ReportSchedule {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 每天生成报告的本地时间，格式为 `HH:MM`
+    #[serde(default = "default_report_time")]
+    pub time: String,
+    /// 报告格式：html、markdown
+    #[serde(default = "default_report_format")]
+    pub format: String,
+    pub webhook: Option<ReportWebhook>,
+    pub smtp: Option<SmtpConfig>,
+    /// 上次生成报告的时间（毫秒时间戳）
+    #[serde(default)]
+    pub last_run: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct ReportWebhook {
+    pub url: String,
+    /// 签名密钥，签名方式与告警webhook相同
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+/// 发送报告邮件的SMTP配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    /// 加密方式：starttls、tls、none
+    #[serde(default = "default_smtp_security")]
+    pub security: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+/// 推送报告的结果
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct ReportDeliveryResult {
+    pub generate_time: u64,
+    /// 推送成功的渠道：webhook、smtp
+    pub delivered: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+fn default_report_time() -> String {
+    String::from("02:00")
+}
+
+fn default_report_format() -> String {
+    String::from("html")
+}
+
+fn default_smtp_security() -> String {
+    String::from("starttls")
+}
+
+impl ReportSchedule {
+    /// 校验配置，不合法时返回错误描述
+    pub fn validate(&self) -> Result<(), String> {
+        if chrono::NaiveTime::parse_from_str(&self.time, "%H:%M").is_err() {
+            return Err(format!("Invalid report time: {}, expected HH:MM", self.time));
+        }
+        if !["html", "markdown"].contains(&self.format.as_str()) {
+            return Err(format!("Unsupported report format: {}", self.format));
+        }
+        if self.enabled && self.webhook.is_none() && self.smtp.is_none() {
+            return Err(String::from("At least one of webhook and SMTP is required"));
+        }
+        if let Some(webhook) = &self.webhook {
+            if !webhook.url.starts_with("http://") && !webhook.url.starts_with("https://") {
+                return Err(format!("Invalid webhook url: {}", webhook.url));
+            }
+        }
+        if let Some(smtp) = &self.smtp {
+            if smtp.host.is_empty() || smtp.from.is_empty() || smtp.to.is_empty() {
+                return Err(String::from("SMTP host, sender and recipients are required"));
+            }
+            if !["starttls", "tls", "none"].contains(&smtp.security.as_str()) {
+                return Err(format!("Unsupported SMTP security: {}", smtp.security));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+/// 本地沙箱集群的状态
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SandboxStatus {
+    pub running: bool,
+    pub pid: Option<u32>,
+    /// 客户端连接地址，如 `127.0.0.1:23790`
+    pub endpoint: String,
+    pub version: Option<String>,
+    pub binary_path: Option<String>,
+    pub data_dir: String,
+    pub log_path: String,
+}
+
+/// 启动沙箱集群的参数
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct SandboxOptions {
+    /// 用户指定的etcd程序路径，为空时使用已下载的程序，没有则自动下载
+    #[serde(default)]
+    pub binary_path: Option<String>,
+    /// 自动下载时使用的版本，如 `3.5.17`
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub client_port: Option<u16>,
+    #[serde(default)]
+    pub peer_port: Option<u16>,
+}
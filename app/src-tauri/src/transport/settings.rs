@@ -1,13 +1,20 @@
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::Path;
 
 use serde::{Deserialize, Serialize};
 
+use crate::transport::connection::ConnectionInfo;
+use crate::transport::maintenance::AlertType;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all="camelCase")]
 pub struct SettingConfig {
-    /// 应用主题
+    /// 应用主题：auto、light、dark
     #[serde(default = "default_theme")]
     pub theme: String,
+    /// 界面语言，auto 表示跟随系统
+    #[serde(default = "default_language")]
+    pub language: String,
     /// 编辑器黑色主题
     #[serde(default = "default_editor_dark_theme")]
     pub editor_dark_theme: String,
@@ -27,10 +34,19 @@ pub struct SettingConfig {
     /// KV保存之前是否检查格式
     #[serde(default = "default_kv_check_format_before_save")]
     pub kv_check_format_before_save: bool,
+    /// 是否为大数据量的连接建立内存key索引
+    #[serde(default = "default_kv_index_enabled")]
+    pub kv_index_enabled: bool,
+    /// key数量达到此值时才建立内存索引
+    #[serde(default = "default_kv_index_min_keys")]
+    pub kv_index_min_keys: i64,
 
     /// 自动更新
     #[serde(default = "default_auto_update")]
     pub auto_update: bool,
+    /// 更新渠道：stable、beta
+    #[serde(default = "default_update_channel")]
+    pub update_channel: String,
 
     /// 使用 ctrl + w 关闭连接tab
     #[serde(default)]
@@ -41,18 +57,173 @@ pub struct SettingConfig {
     /// 请求超时秒数
     #[serde(default = "default_request_timeout_seconds")]
     pub request_timeout_seconds: u64,
+    /// 幂等请求遇到短暂故障时的最大重试次数，为0时不重试
+    #[serde(default = "default_retry_max_attempts")]
+    pub retry_max_attempts: u32,
+    /// 首次重试的基础等待毫秒数，之后指数增长并加入随机抖动
+    #[serde(default = "default_retry_base_delay_millis")]
+    pub retry_base_delay_millis: u64,
     /// SSH连接超时秒数
     #[serde(default = "default_ssh_connect_timeout_seconds")]
     pub ssh_connect_timeout_seconds: u64,
+    /// 关闭连接后保留已认证SSH会话的秒数，期间连接同一服务器时直接复用，避免重复进行多因素认证。为0时不保留
+    #[serde(default)]
+    pub ssh_session_reuse_seconds: u64,
+    /// SSH转发每次读写的缓冲区大小，单位KB
+    #[serde(default = "default_ssh_proxy_buffer_kb")]
+    pub ssh_proxy_buffer_kb: u32,
+    /// SSH通道的流控窗口，单位KB。本地读取慢时通道最多积压此大小的数据，超过后断开该转发连接，不影响同一会话的其他连接
+    #[serde(default = "default_ssh_channel_window_kb")]
+    pub ssh_channel_window_kb: u32,
     /// 连接存储加密密钥，bytes字符长度必须为16位
     #[serde(default = "default_connection_conf_encrypt_key")]
     pub connection_conf_encrypt_key: String,
+    /// 健康检查间隔秒数
+    #[serde(default = "default_health_check_interval_seconds")]
+    pub health_check_interval_seconds: u64,
+    /// TLS证书剩余有效天数小于此值时发出警告
+    #[serde(default = "default_tls_cert_expire_warn_days")]
+    pub tls_cert_expire_warn_days: i64,
+    /// 是否允许记录匿名使用统计，默认关闭，需要用户主动开启
+    #[serde(default = "default_telemetry_enabled")]
+    pub telemetry_enabled: bool,
+    /// 文件下载目录，为空时使用系统下载目录
+    #[serde(default)]
+    pub download_dir: Option<String>,
+    /// 告警webhook
+    #[serde(default)]
+    pub alert_webhooks: Vec<AlertWebhook>,
+    /// 编辑key时在etcd中写入共享编辑锁，使其他workbench用户也能看到
+    #[serde(default)]
+    pub shared_edit_lock: bool,
+    /// 将key注释和收藏夹保存到etcd中，使团队成员都能看到
+    #[serde(default)]
+    pub shared_annotations: bool,
+    /// 共享注释在etcd中的前缀，位于连接的命名空间内
+    #[serde(default = "default_shared_annotation_prefix")]
+    pub shared_annotation_prefix: String,
+    /// 回收站保留已删除key的天数，为0时删除key不放入回收站
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: u32,
+    /// 回收站的最大容量（MB），超出时移除最早删除的key
+    #[serde(default = "default_trash_max_size_mb")]
+    pub trash_max_size_mb: u64,
+    /// 监听事件推送给前端的刷新间隔毫秒数，周期内同一key的事件只保留最新的
+    #[serde(default = "default_watch_flush_interval_millis")]
+    pub watch_flush_interval_millis: u64,
+    /// 每个监听每秒最多推送给前端的事件数，超出的事件被丢弃并计数
+    #[serde(default = "default_watch_max_events_per_second")]
+    pub watch_max_events_per_second: u64,
+    /// 后台定时任务的间隔秒数，未配置的任务使用默认间隔，可被连接的配置覆盖
+    #[serde(default)]
+    pub poll_interval_seconds: BTreeMap<PollTask, u64>,
+    /// 窗口失去焦点时按 `unfocused_poll_factor` 倍数放慢定时任务
+    #[serde(default = "default_adaptive_polling")]
+    pub adaptive_polling: bool,
+    #[serde(default = "default_unfocused_poll_factor")]
+    pub unfocused_poll_factor: u32,
+    /// 在本地保存最近读取的key列表、集群状态和选中的值，集群不可达时可查看
+    #[serde(default = "default_offline_cache")]
+    pub offline_cache: bool,
+    /// 超过此分钟数未操作时锁定会话，需要输入主密码解锁，为0时不锁定
+    #[serde(default)]
+    pub lock_idle_minutes: u32,
+    /// 主密码的加盐哈希，只能通过 `set_master_password` 修改
+    #[serde(default)]
+    pub master_password_hash: Option<String>,
+}
+
+/// 定时向前端推送数据的后台任务
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all="camelCase")]
+pub enum PollTask {
+    /// 集群健康状态检查
+    HealthCheck,
+    /// 成员延迟采样
+    LatencySample,
+    /// 热点key统计报告
+    HotKeyReport,
+}
+
+impl PollTask {
+    pub const ALL: [PollTask; 3] = [PollTask::HealthCheck, PollTask::LatencySample, PollTask::HotKeyReport];
+
+    /// 未配置间隔时使用的默认秒数
+    pub fn default_seconds(&self, settings: &SettingConfig) -> u64 {
+        match self {
+            PollTask::HealthCheck => settings.health_check_interval_seconds,
+            PollTask::LatencySample => 5,
+            PollTask::HotKeyReport => 2,
+        }
+    }
+
+    pub fn min_seconds(&self) -> u64 {
+        match self {
+            PollTask::HealthCheck => 5,
+            PollTask::LatencySample | PollTask::HotKeyReport => 1,
+        }
+    }
+}
+
+/// 校验定时任务间隔的配置
+pub fn validate_poll_intervals(intervals: &BTreeMap<PollTask, u64>) -> Result<(), String> {
+    for (task, seconds) in intervals {
+        if *seconds < task.min_seconds() || *seconds > 3600 {
+            return Err(format!(
+                "Interval of {:?} must be between {} and 3600 seconds",
+                task,
+                task.min_seconds()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 校验下载目录是否存在。目录可能位于暂时未挂载的磁盘上，只在修改时校验
+pub fn validate_download_dir(download_dir: &Option<String>) -> Result<(), String> {
+    if let Some(dir) = download_dir {
+        if !dir.is_empty() && !Path::new(dir).is_dir() {
+            return Err(format!("Download directory does not exist: {}", dir));
+        }
+    }
+    Ok(())
+}
+
+/// 告警推送的webhook配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct AlertWebhook {
+    pub name: String,
+    pub url: String,
+    /// 消息格式：generic、slack、teams
+    #[serde(default = "default_webhook_kind")]
+    pub kind: String,
+    /// 签名密钥，配置后请求头中会携带 HMAC-SHA256 签名
+    #[serde(default)]
+    pub secret: Option<String>,
+    #[serde(default = "default_webhook_enabled")]
+    pub enabled: bool,
+    /// 需要推送的告警类型，为空时推送所有类型
+    #[serde(default)]
+    pub alert_types: Vec<AlertType>,
+}
+
+fn default_webhook_kind() -> String {
+    String::from("generic")
+}
+
+fn default_webhook_enabled() -> bool {
+    true
 }
 
 fn default_theme() -> String {
     String::from("auto")
 }
 
+fn default_language() -> String {
+    String::from("auto")
+}
+
 fn default_editor_dark_theme() -> String {
     String::from("barf")
 }
@@ -73,6 +244,14 @@ fn default_kv_check_format_before_save() -> bool {
     true
 }
 
+fn default_kv_index_enabled() -> bool {
+    true
+}
+
+fn default_kv_index_min_keys() -> i64 {
+    50000
+}
+
 fn default_connect_timeout_seconds() -> u64 {
     5
 }
@@ -81,35 +260,217 @@ fn default_request_timeout_seconds() -> u64 {
     15
 }
 
+fn default_retry_max_attempts() -> u32 {
+    3
+}
+
+fn default_retry_base_delay_millis() -> u64 {
+    200
+}
+
 fn default_ssh_connect_timeout_seconds() -> u64 {
     10
 }
 
+fn default_ssh_proxy_buffer_kb() -> u32 {
+    8
+}
+
+fn default_ssh_channel_window_kb() -> u32 {
+    2048
+}
+
 fn default_auto_update() -> bool {
     true
 }
 
+fn default_update_channel() -> String {
+    String::from("stable")
+}
+
 fn default_connection_conf_encrypt_key() -> String {
     String::from("workbench*#)&%.$")
 }
 
+fn default_health_check_interval_seconds() -> u64 {
+    60
+}
+
+fn default_tls_cert_expire_warn_days() -> i64 {
+    30
+}
+
+fn default_telemetry_enabled() -> bool {
+    false
+}
+
+fn default_shared_annotation_prefix() -> String {
+    String::from("/__workbench/")
+}
+
+fn default_trash_retention_days() -> u32 {
+    7
+}
+
+fn default_trash_max_size_mb() -> u64 {
+    64
+}
+
+fn default_watch_flush_interval_millis() -> u64 {
+    250
+}
+
+fn default_watch_max_events_per_second() -> u64 {
+    1000
+}
+
+fn default_adaptive_polling() -> bool {
+    true
+}
+
+fn default_unfocused_poll_factor() -> u32 {
+    4
+}
+
+fn default_offline_cache() -> bool {
+    true
+}
+
 impl Default for SettingConfig {
     fn default() -> Self {
         SettingConfig {
             theme: default_theme(),
+            language: default_language(),
             editor_dark_theme: default_editor_dark_theme(),
             editor_light_theme: default_editor_light_theme(),
             kv_path_splitter: default_kv_path_splitter(),
             kv_pagination_query: true,
             kv_limit_per_page: default_kv_limit_per_page(),
             kv_check_format_before_save: true,
+            kv_index_enabled: default_kv_index_enabled(),
+            kv_index_min_keys: default_kv_index_min_keys(),
             auto_update: true,
+            update_channel: default_update_channel(),
             close_tab_use_ctrl_w: true,
             connect_timeout_seconds: default_connect_timeout_seconds(),
             request_timeout_seconds: default_request_timeout_seconds(),
+            retry_max_attempts: default_retry_max_attempts(),
+            retry_base_delay_millis: default_retry_base_delay_millis(),
             ssh_connect_timeout_seconds: default_ssh_connect_timeout_seconds(),
+            ssh_session_reuse_seconds: 0,
+            ssh_proxy_buffer_kb: default_ssh_proxy_buffer_kb(),
+            ssh_channel_window_kb: default_ssh_channel_window_kb(),
             connection_conf_encrypt_key: default_connection_conf_encrypt_key(),
+            health_check_interval_seconds: default_health_check_interval_seconds(),
+            tls_cert_expire_warn_days: default_tls_cert_expire_warn_days(),
+            telemetry_enabled: default_telemetry_enabled(),
+            download_dir: None,
+            alert_webhooks: vec![],
+            shared_edit_lock: false,
+            shared_annotations: false,
+            shared_annotation_prefix: default_shared_annotation_prefix(),
+            trash_retention_days: default_trash_retention_days(),
+            trash_max_size_mb: default_trash_max_size_mb(),
+            watch_flush_interval_millis: default_watch_flush_interval_millis(),
+            watch_max_events_per_second: default_watch_max_events_per_second(),
+            poll_interval_seconds: BTreeMap::new(),
+            adaptive_polling: default_adaptive_polling(),
+            unfocused_poll_factor: default_unfocused_poll_factor(),
+            offline_cache: default_offline_cache(),
+            lock_idle_minutes: 0,
+            master_password_hash: None,
+        }
+    }
+}
+
+impl SettingConfig {
+    /// 校验设置项的值，不合法时返回错误描述
+    pub fn validate(&self) -> Result<(), String> {
+        if !["auto", "light", "dark"].contains(&self.theme.as_str()) {
+            return Err(format!("Unsupported theme: {}", self.theme));
+        }
+        if self.language.is_empty() {
+            return Err(String::from("Language can not be empty"));
+        }
+        if self.kv_path_splitter.is_empty() {
+            return Err(String::from("KV path splitter can not be empty"));
+        }
+        if self.kv_limit_per_page == 0 || self.kv_limit_per_page > 100_000 {
+            return Err(String::from("KV limit per page must be between 1 and 100000"));
+        }
+        if self.kv_index_min_keys < 0 {
+            return Err(String::from("KV index min keys can not be negative"));
+        }
+        if self.connect_timeout_seconds == 0 || self.connect_timeout_seconds > 300 {
+            return Err(String::from("Connect timeout must be between 1 and 300 seconds"));
+        }
+        if self.request_timeout_seconds == 0 || self.request_timeout_seconds > 3600 {
+            return Err(String::from("Request timeout must be between 1 and 3600 seconds"));
+        }
+        if self.retry_max_attempts > 10 {
+            return Err(String::from("Retry attempts must be between 0 and 10"));
+        }
+        if self.retry_base_delay_millis < 10 || self.retry_base_delay_millis > 10000 {
+            return Err(String::from("Retry base delay must be between 10 and 10000 milliseconds"));
+        }
+        if self.ssh_connect_timeout_seconds == 0 || self.ssh_connect_timeout_seconds > 300 {
+            return Err(String::from("SSH connect timeout must be between 1 and 300 seconds"));
+        }
+        if self.ssh_session_reuse_seconds > 3600 {
+            return Err(String::from("SSH session reuse time must be between 0 and 3600 seconds"));
+        }
+        if self.ssh_proxy_buffer_kb == 0 || self.ssh_proxy_buffer_kb > 1024 {
+            return Err(String::from("SSH proxy buffer size must be between 1 and 1024 KB"));
+        }
+        if self.ssh_channel_window_kb < 64 || self.ssh_channel_window_kb > 65536 {
+            return Err(String::from("SSH channel window must be between 64 and 65536 KB"));
+        }
+        if self.connection_conf_encrypt_key.as_bytes().len() != 16 {
+            return Err(String::from("Encrypt key must be 16 bytes"));
+        }
+        if !["stable", "beta"].contains(&self.update_channel.as_str()) {
+            return Err(format!("Unsupported update channel: {}", self.update_channel));
         }
+        if self.health_check_interval_seconds < 5 {
+            return Err(String::from("Health check interval must be at least 5 seconds"));
+        }
+        if self.tls_cert_expire_warn_days < 0 {
+            return Err(String::from("Certificate expire warning days can not be negative"));
+        }
+        if self.shared_annotations && self.shared_annotation_prefix.is_empty() {
+            return Err(String::from("Shared annotation prefix can not be empty"));
+        }
+        if self.trash_retention_days > 365 {
+            return Err(String::from("Trash retention days must be between 0 and 365"));
+        }
+        if self.trash_max_size_mb == 0 || self.trash_max_size_mb > 4096 {
+            return Err(String::from("Trash max size must be between 1 and 4096 MB"));
+        }
+        if self.watch_flush_interval_millis < 50 || self.watch_flush_interval_millis > 10000 {
+            return Err(String::from("Watch flush interval must be between 50 and 10000 milliseconds"));
+        }
+        if self.watch_max_events_per_second == 0 {
+            return Err(String::from("Watch max events per second must be greater than 0"));
+        }
+        if self.unfocused_poll_factor == 0 || self.unfocused_poll_factor > 60 {
+            return Err(String::from("Unfocused poll factor must be between 1 and 60"));
+        }
+        validate_poll_intervals(&self.poll_interval_seconds)?;
+        if self.lock_idle_minutes > 1440 {
+            return Err(String::from("Session lock idle minutes must be between 0 and 1440"));
+        }
+        if self.lock_idle_minutes > 0 && self.master_password_hash.is_none() {
+            return Err(String::from("Set a master password before enabling session lock"));
+        }
+        for webhook in &self.alert_webhooks {
+            if !webhook.url.starts_with("http://") && !webhook.url.starts_with("https://") {
+                return Err(format!("Invalid webhook url: {}", webhook.url));
+            }
+            if !["generic", "slack", "teams"].contains(&webhook.kind.as_str()) {
+                return Err(format!("Unsupported webhook kind: {}", webhook.kind));
+            }
+        }
+        Ok(())
     }
 }
 
@@ -141,4 +502,64 @@ pub struct FileFormat {
     /// key全路径，包含namespace前缀
     pub key: String,
     pub format: String
-}
\ No newline at end of file
+}
+/// 工作区导出文件，包含所有连接（含收藏夹和模板）与设置，用于团队共享
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct WorkspaceBundle {
+    pub version: u32,
+    /// 导出时间（毫秒时间戳）
+    pub exported_at: i64,
+    /// 已清除密码、私钥等敏感信息的连接配置
+    pub connections: Vec<ConnectionInfo>,
+    /// 已清除webhook签名密钥和连接存储加密密钥的设置
+    pub settings: SettingConfig,
+    /// 使用共享密码加密的敏感信息，导出时未设置共享密码则为空
+    #[serde(default)]
+    pub secrets: Option<WorkspaceSecrets>,
+}
+
+/// 共享密码加密的敏感信息，均为base64编码
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct WorkspaceSecrets {
+    pub salt: String,
+    pub data: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct WorkspaceImportResult {
+    pub imported: Vec<String>,
+    /// 已存在而跳过的连接
+    pub skipped: Vec<String>,
+    pub settings_imported: bool,
+    /// 是否使用共享密码恢复了敏感信息
+    pub secrets_restored: bool,
+}
+
+/// 本地使用统计，只保存在本机，不会发送到任何地方
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct UsageStats {
+    /// 开始统计的时间（毫秒时间戳）
+    pub since: u64,
+    pub total_commands: u64,
+    pub total_errors: u64,
+    /// 错误数占调用数的比例
+    #[serde(default)]
+    pub error_rate: f64,
+    /// 各命令（功能）的调用次数
+    pub commands: BTreeMap<String, u64>,
+    /// 各错误类型（`errType`）的次数
+    pub errors: BTreeMap<String, u64>,
+    /// 最近每天的调用和错误次数，key为 `yyyy-MM-dd`
+    pub daily: BTreeMap<String, DailyUsage>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct DailyUsage {
+    pub commands: u64,
+    pub errors: u64,
+}
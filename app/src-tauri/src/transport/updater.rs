@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// 更新检查结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct UpdateCheckResult {
+    pub available: bool,
+    /// 更新渠道：stable、beta
+    pub channel: String,
+    pub current_version: String,
+    pub latest_version: String,
+    /// 发布说明
+    pub notes: Option<String>,
+    /// 发布时间（毫秒时间戳）
+    pub date: Option<i64>,
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use etcd_client::{Permission, PermissionType};
 use log::warn;
 use serde::{Deserialize, Serialize};
@@ -8,7 +10,7 @@ pub struct SerializableUser {
     pub roles: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all="camelCase")]
 pub struct SerializablePermission {
     pub key: String,
@@ -31,6 +33,37 @@ impl Into<Permission> for SerializablePermission {
 }
 
 impl SerializablePermission {
+    /// 判断两个权限是否作用于同一范围且类型相同
+    pub fn same_as(&self, other: &SerializablePermission) -> bool {
+        if self.perm_type != other.perm_type {
+            return false;
+        }
+        if self.all_keys || other.all_keys {
+            return self.all_keys == other.all_keys;
+        }
+        self.key == other.key && self.prefix == other.prefix
+    }
+
+    /// 判断权限是否覆盖目标key，`prefix` 为true时需要覆盖以 `key` 开头的整个范围
+    pub fn covers(&self, key: &str, prefix: bool) -> bool {
+        if self.all_keys {
+            true
+        } else if self.prefix {
+            key.starts_with(self.key.as_str())
+        } else {
+            !prefix && key == self.key
+        }
+    }
+
+    /// permission::Type: 0 READ, 1 WRITE, 2 READWRITE
+    pub fn can_read(&self) -> bool {
+        self.perm_type == 0 || self.perm_type == 2
+    }
+
+    pub fn can_write(&self) -> bool {
+        self.perm_type == 1 || self.perm_type == 2
+    }
+
     pub fn parse_range_end(&self) -> Vec<u8> {
         if self.all_keys {
             vec![b'\0']
@@ -51,4 +84,394 @@ impl SerializablePermission {
             vec![]
         }
     }
-}
\ No newline at end of file
+}
+/// 权限初始化配置，按顺序创建角色、用户，最后开启权限验证
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct AuthBootstrapConfig {
+    /// root 用户密码
+    pub root_password: String,
+    #[serde(default)]
+    pub roles: Vec<AuthBootstrapRole>,
+    #[serde(default)]
+    pub users: Vec<AuthBootstrapUser>,
+    /// 是否在最后开启权限验证
+    #[serde(default = "default_enable_auth")]
+    pub enable_auth: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct AuthBootstrapRole {
+    pub role: String,
+    #[serde(default)]
+    pub permissions: Vec<SerializablePermission>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct AuthBootstrapUser {
+    pub user: String,
+    pub password: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+/// 权限初始化执行的步骤，已存在的资源会被跳过
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct AuthBootstrapStep {
+    pub action: String,
+    pub target: String,
+    pub skipped: bool,
+}
+
+impl AuthBootstrapStep {
+    pub fn new(action: &str, target: impl Into<String>, skipped: bool) -> Self {
+        AuthBootstrapStep {
+            action: String::from(action),
+            target: target.into(),
+            skipped,
+        }
+    }
+}
+
+fn default_enable_auth() -> bool {
+    true
+}
+
+/// 声明式权限定义，描述期望的角色权限以及用户的角色授权
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct AuthDefinition {
+    #[serde(default)]
+    pub roles: Vec<AuthBootstrapRole>,
+    #[serde(default)]
+    pub users: Vec<AuthDefinitionUser>,
+    /// 是否删除定义中不存在的角色
+    #[serde(default)]
+    pub prune: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct AuthDefinitionUser {
+    pub user: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+impl AuthDefinition {
+    /// 解析 JSON 或 YAML 格式的权限定义
+    pub fn parse(content: &str) -> Result<Self, String> {
+        if content.trim_start().starts_with('{') {
+            serde_json::from_str(content).map_err(|e| format!("Invalid json definition: {e}"))
+        } else {
+            serde_yaml::from_str(content).map_err(|e| format!("Invalid yaml definition: {e}"))
+        }
+    }
+}
+
+/// 导出文件中的密码占位符，导入时需要为新建的用户提供密码
+pub const PASSWORD_PLACEHOLDER: &str = "<password>";
+
+/// 导出的用户、角色及权限配置，可导入到其他集群
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct AuthExport {
+    #[serde(default)]
+    pub roles: Vec<AuthBootstrapRole>,
+    /// 导出时密码为占位符，已存在的用户导入时不修改密码
+    #[serde(default)]
+    pub users: Vec<AuthBootstrapUser>,
+}
+
+impl AuthExport {
+    /// 解析 JSON 或 YAML 格式的导出文件
+    pub fn parse(content: &str) -> Result<Self, String> {
+        if content.trim_start().starts_with('{') {
+            serde_json::from_str(content).map_err(|e| format!("Invalid json auth file: {e}"))
+        } else {
+            serde_yaml::from_str(content).map_err(|e| format!("Invalid yaml auth file: {e}"))
+        }
+    }
+
+    /// 新建用户的密码，优先使用导入时传入的密码，其次是文件中不是占位符的密码
+    pub fn password_of(&self, user: &str, passwords: &HashMap<String, String>) -> Option<String> {
+        if let Some(password) = passwords.get(user).filter(|p| !p.is_empty()) {
+            return Some(password.clone());
+        }
+        self.users
+            .iter()
+            .find(|u| u.user == user)
+            .map(|u| u.password.clone())
+            .filter(|p| !p.is_empty() && p != PASSWORD_PLACEHOLDER)
+    }
+
+    /// 转换为不删除多余角色的权限定义
+    pub fn to_definition(&self) -> AuthDefinition {
+        AuthDefinition {
+            roles: self
+                .roles
+                .iter()
+                .map(|r| AuthBootstrapRole {
+                    role: r.role.clone(),
+                    permissions: r.permissions.clone(),
+                })
+                .collect(),
+            users: self
+                .users
+                .iter()
+                .map(|u| AuthDefinitionUser {
+                    user: u.user.clone(),
+                    roles: u.roles.clone(),
+                })
+                .collect(),
+            prune: false,
+        }
+    }
+}
+
+/// 导入权限配置的结果，权限变更失败回滚时新建的用户也会被删除
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct AuthImportResult {
+    pub created_users: Vec<String>,
+    pub apply: AuthApplyResult,
+}
+
+/// 权限变更计划中的一个步骤
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct AuthPlanStep {
+    /// addRole, deleteRole, grantPermission, revokePermission, grantRole, revokeRole
+    pub action: String,
+    pub role: String,
+    pub user: Option<String>,
+    pub permission: Option<SerializablePermission>,
+}
+
+impl AuthPlanStep {
+    pub fn role(action: &str, role: impl Into<String>) -> Self {
+        AuthPlanStep {
+            action: String::from(action),
+            role: role.into(),
+            user: None,
+            permission: None,
+        }
+    }
+
+    pub fn permission(action: &str, role: impl Into<String>, permission: SerializablePermission) -> Self {
+        AuthPlanStep {
+            permission: Some(permission),
+            ..Self::role(action, role)
+        }
+    }
+
+    pub fn user(action: &str, user: impl Into<String>, role: impl Into<String>) -> Self {
+        AuthPlanStep {
+            user: Some(user.into()),
+            ..Self::role(action, role)
+        }
+    }
+
+    /// 是否为相同的步骤，权限按 `SerializablePermission::same_as` 比较
+    pub fn same_as(&self, other: &AuthPlanStep) -> bool {
+        self.action == other.action
+            && self.role == other.role
+            && self.user == other.user
+            && match (&self.permission, &other.permission) {
+                (Some(a), Some(b)) => a.same_as(b),
+                (None, None) => true,
+                _ => false,
+            }
+    }
+
+    /// 生成用于回滚的反向步骤
+    pub fn inverse(&self) -> Self {
+        let action = match self.action.as_str() {
+            "addRole" => "deleteRole",
+            "deleteRole" => "addRole",
+            "grantPermission" => "revokePermission",
+            "revokePermission" => "grantPermission",
+            "grantRole" => "revokeRole",
+            "revokeRole" => "grantRole",
+            other => other,
+        };
+        AuthPlanStep {
+            action: String::from(action),
+            ..self.clone()
+        }
+    }
+}
+
+/// 权限变更计划的执行结果，执行失败时已执行的步骤会按相反顺序回滚
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all="camelCase")]
+pub struct AuthApplyResult {
+    pub steps: Vec<AuthPlanStep>,
+    /// 成功执行的步骤数
+    pub applied: usize,
+    pub rolled_back: bool,
+    pub error_msg: Option<String>,
+}
+
+/// 授予权限的角色及对应的权限
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct PermissionGrant {
+    pub role: String,
+    pub permission: SerializablePermission,
+}
+
+/// 模拟用户对key或前缀的访问权限
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct AuthSimulation {
+    pub user: String,
+    pub key: String,
+    pub prefix: bool,
+    /// 用户拥有的角色
+    pub roles: Vec<String>,
+    pub root: bool,
+    pub read: bool,
+    pub write: bool,
+    /// 允许读取的角色及权限
+    pub read_grants: Vec<PermissionGrant>,
+    /// 允许写入的角色及权限
+    pub write_grants: Vec<PermissionGrant>,
+}
+
+impl AuthSimulation {
+    /// 根据用户的角色及各角色的权限计算是否可以读写
+    pub fn resolve(
+        user: &str,
+        key: &str,
+        prefix: bool,
+        roles: Vec<(String, Vec<SerializablePermission>)>,
+    ) -> Self {
+        let root = user == "root" || roles.iter().any(|(role, _)| role == "root");
+        let mut read_grants = Vec::new();
+        let mut write_grants = Vec::new();
+        for (role, permissions) in &roles {
+            for permission in permissions.iter().filter(|p| p.covers(key, prefix)) {
+                let grant = PermissionGrant {
+                    role: role.clone(),
+                    permission: permission.clone(),
+                };
+                if permission.can_read() {
+                    read_grants.push(grant.clone());
+                }
+                if permission.can_write() {
+                    write_grants.push(grant);
+                }
+            }
+        }
+        AuthSimulation {
+            user: String::from(user),
+            key: String::from(key),
+            prefix,
+            roles: roles.into_iter().map(|(role, _)| role).collect(),
+            root,
+            read: root || !read_grants.is_empty(),
+            write: root || !write_grants.is_empty(),
+            read_grants,
+            write_grants,
+        }
+    }
+}
+
+/// 需要预检权限的操作
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all="camelCase")]
+pub enum PrecheckOperation {
+    Read,
+    Write,
+    Delete,
+    /// 快照、碎片整理、告警等维护操作
+    Maintenance,
+    /// 用户、角色及权限管理
+    AuthManage,
+}
+
+impl PrecheckOperation {
+    pub fn is_write(&self) -> bool {
+        !matches!(self, PrecheckOperation::Read)
+    }
+
+    /// 只有root用户可以执行的操作
+    pub fn root_only(&self) -> bool {
+        matches!(self, PrecheckOperation::Maintenance | PrecheckOperation::AuthManage)
+    }
+}
+
+/// 操作的权限预检结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct OperationPrecheck {
+    pub operation: PrecheckOperation,
+    pub key: String,
+    pub prefix: bool,
+    pub allowed: bool,
+    /// 判断依据：readOnly, noAuth, root, probe
+    pub method: String,
+    /// 不允许时的原因
+    pub reason: Option<String>,
+}
+
+impl OperationPrecheck {
+    pub fn allow(operation: PrecheckOperation, key: String, prefix: bool, method: &str) -> Self {
+        OperationPrecheck {
+            operation,
+            key,
+            prefix,
+            allowed: true,
+            method: String::from(method),
+            reason: None,
+        }
+    }
+
+    pub fn deny(operation: PrecheckOperation, key: String, prefix: bool, method: &str, reason: &str) -> Self {
+        OperationPrecheck {
+            allowed: false,
+            reason: Some(String::from(reason)),
+            ..Self::allow(operation, key, prefix, method)
+        }
+    }
+}
+
+/// 认证token的类型，由etcd的 `--auth-token` 参数决定
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all="camelCase")]
+pub enum AuthTokenType {
+    Simple,
+    Jwt,
+}
+
+/// 当前连接使用的身份
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SessionIdentity {
+    /// 未配置用户时为空
+    pub user: Option<String>,
+    pub roles: Vec<String>,
+    pub root: bool,
+    pub auth_enabled: bool,
+    pub token_type: Option<AuthTokenType>,
+    /// JWT token的过期时间（毫秒时间戳），simple token由服务端按TTL过期，无法获取
+    pub token_expires_at: Option<u64>,
+    /// 连接期间token失效后重新认证的次数
+    pub reauth_count: u64,
+    pub last_reauth_time: Option<u64>,
+}
+
+/// token失效后重新认证的事件
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct ReauthEvent {
+    pub session: i32,
+    pub user: Option<String>,
+    pub success: bool,
+    pub time: u64,
+    pub error_msg: Option<String>,
+}
@@ -3,16 +3,26 @@ use aes::cipher::consts::U16;
 use aes::cipher::generic_array::GenericArray;
 use aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit};
 use aes::Aes128;
+use aes_gcm::aead::{Aead, AeadCore, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use sha2::Sha256;
 use std::cmp::min;
 use std::fmt::Display;
 
 pub const LENGTH_16: usize = 16;
+pub const LENGTH_32: usize = 32;
+/// AES-GCM 随机数长度
+const GCM_NONCE_LENGTH: usize = 12;
+/// 由密码派生密钥时的迭代次数
+const PBKDF2_ROUNDS: u32 = 210_000;
 
 #[derive(Debug)]
 pub enum AesError {
     InvalidKeyLength(String),
     InvalidBlockLength,
     TryFromSliceError,
+    /// 加密失败或密文校验失败
+    GcmError,
 }
 
 impl Display for AesError {
@@ -27,6 +37,9 @@ impl Display for AesError {
             AesError::TryFromSliceError => {
                 write!(f, "aes crypt error: TryFromSliceError")
             }
+            AesError::GcmError => {
+                write!(f, "aes crypt error: GcmError")
+            }
         }
     }
 }
@@ -90,6 +103,53 @@ pub fn decrypt_128(key: &[u8], content: impl Into<Vec<u8>>) -> Result<Vec<u8>, A
     decode_aes_block_content_16(&blocks)
 }
 
+/// 生成随机的 AES-256 密钥
+pub fn generate_key_256() -> Vec<u8> {
+    Aes256Gcm::generate_key(OsRng).to_vec()
+}
+
+/// 使用 PBKDF2-HMAC-SHA256 由密码和盐派生 AES-256 密钥
+pub fn derive_key_256(password: &str, salt: &[u8]) -> Vec<u8> {
+    let mut key = vec![0u8; LENGTH_32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, PBKDF2_ROUNDS, &mut key);
+    key
+}
+
+/// 使用 AES-256-GCM 加密，结果格式为 [nonce(12), 密文]
+pub fn encrypt_gcm_256(key: &[u8], content: &[u8]) -> Result<Vec<u8>, AesError> {
+    let cipher = new_gcm_256(key)?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let encrypted = cipher.encrypt(&nonce, content).map_err(|_| AesError::GcmError)?;
+
+    let mut result = Vec::with_capacity(GCM_NONCE_LENGTH + encrypted.len());
+    result.extend_from_slice(&nonce);
+    result.extend(encrypted);
+    Ok(result)
+}
+
+/// 解密 [`encrypt_gcm_256`] 的结果
+pub fn decrypt_gcm_256(key: &[u8], content: &[u8]) -> Result<Vec<u8>, AesError> {
+    let cipher = new_gcm_256(key)?;
+    if content.len() < GCM_NONCE_LENGTH {
+        return Err(AesError::InvalidBlockLength);
+    }
+    let (nonce, encrypted) = content.split_at(GCM_NONCE_LENGTH);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), encrypted)
+        .map_err(|_| AesError::GcmError)
+}
+
+fn new_gcm_256(key: &[u8]) -> Result<Aes256Gcm, AesError> {
+    let key_len = key.len();
+    if key_len != LENGTH_32 {
+        return Err(AesError::InvalidKeyLength(format!(
+            "Invalid aes key length: {}, expect: {}",
+            key_len, LENGTH_32
+        )));
+    }
+    Aes256Gcm::new_from_slice(key).map_err(|_| AesError::TryFromSliceError)
+}
+
 fn encode_aes_block_content_16(
     content: impl Into<Vec<u8>>,
 ) -> Result<Vec<GenericArray<u8, U16>>, AesError> {
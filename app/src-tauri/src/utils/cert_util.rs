@@ -0,0 +1,348 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use log::{debug, warn};
+use pkcs8::der::Decode;
+use pkcs8::EncryptedPrivateKeyInfo;
+use sha2::{Digest, Sha256};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_rustls::rustls::client::danger::{
+    HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+};
+use tokio_rustls::rustls::crypto::{
+    ring, verify_tls12_signature, verify_tls13_signature, WebPkiSupportedAlgorithms,
+};
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+use tokio_rustls::TlsConnector;
+use x509_parser::extensions::GeneralName;
+use x509_parser::pem::Pem;
+
+use crate::error::LogicError;
+use crate::etcd::now_timestamp;
+use crate::transport::connection::{CertificateInfo, ConnectionTls, ConnectionTlsInfo, TlsIdentity};
+
+const MILLIS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
+
+/// 不校验证书链的验证器，仅记录服务端证书，用于获取自签名证书的指纹。
+/// 握手签名仍然正常校验。
+#[derive(Debug)]
+struct CapturingCertVerifier {
+    algorithms: WebPkiSupportedAlgorithms,
+    captured: Mutex<Option<Vec<u8>>>,
+}
+
+impl ServerCertVerifier for CapturingCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, tokio_rustls::rustls::Error> {
+        *self.captured.lock().unwrap() = Some(end_entity.to_vec());
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+/// 与服务端进行TLS握手并获取其证书（DER格式），不校验证书是否可信。
+///
+/// - `host`、`port`: 实际连接的地址，使用SSH隧道时为本地代理地址
+/// - `server_name`: SNI，一般为配置的域名或原始主机地址
+pub async fn fetch_server_certificate(
+    host: &str,
+    port: u16,
+    server_name: String,
+    connect_timeout: Duration,
+) -> Result<Vec<u8>, LogicError> {
+    let provider = Arc::new(ring::default_provider());
+    let verifier = Arc::new(CapturingCertVerifier {
+        algorithms: provider.signature_verification_algorithms,
+        captured: Mutex::new(None),
+    });
+
+    let config = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| LogicError::MsgError(e.to_string()))?
+        .dangerous()
+        .with_custom_certificate_verifier(verifier.clone())
+        .with_no_client_auth();
+
+    let server_name = ServerName::try_from(server_name).map_err(|e| {
+        warn!("Invalid tls server name: {e}");
+        LogicError::ArgumentError
+    })?;
+
+    //  建立TCP连接和TLS握手整体受连接超时限制，避免服务端不响应握手时一直等待
+    let handshake = async {
+        let stream = TcpStream::connect((host, port)).await?;
+        //  服务端开启了客户端证书校验时握手可能失败，但此时服务端证书已经被记录
+        let result = TlsConnector::from(Arc::new(config))
+            .connect(server_name, stream)
+            .await;
+        if let Err(e) = result {
+            debug!("tls handshake for fetching certificate finished with error: {e}");
+        }
+        Ok::<(), std::io::Error>(())
+    };
+    timeout(connect_timeout, handshake)
+        .await
+        .map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::TimedOut, "tls handshake timeout")
+        })??;
+
+    let captured = verifier.captured.lock().unwrap().take();
+    captured.ok_or(LogicError::MsgError(String::from(
+        "The server did not provide a certificate",
+    )))
+}
+
+/// 将DER格式证书编码为PEM格式
+pub fn der_to_pem(der: &[u8]) -> String {
+    encode_pem("CERTIFICATE", der)
+}
+
+fn encode_pem(label: &str, der: &[u8]) -> String {
+    let encoded = BASE64_STANDARD.encode(der);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(String::from_utf8_lossy(line).as_ref());
+        pem.push('\n');
+    }
+    pem.push_str(format!("-----END {}-----\n", label).as_str());
+    pem
+}
+
+/// 将客户端身份配置转换为gRPC连接所需的PEM格式证书和私钥。
+///
+/// - PKCS#12 证书包：解密后提取证书链和 PKCS#8 私钥
+/// - 加密的 PKCS#8 私钥（PEM或DER）：使用密码解密
+/// - 其他未加密的PEM私钥原样返回
+///
+/// 内容已加密但未提供密码时返回 [`LogicError::PassphraseRequired`]
+pub fn resolve_identity(identity: &TlsIdentity) -> Result<(Vec<u8>, Vec<u8>), LogicError> {
+    if let Some(pkcs12) = &identity.pkcs12 {
+        return resolve_pkcs12(pkcs12, identity.passphrase.as_deref());
+    }
+
+    let key = &identity.key;
+    let key_der = if key.starts_with(b"-----BEGIN") {
+        let pem = Pem::iter_from_buffer(key)
+            .next()
+            .ok_or(LogicError::MsgError(String::from("Empty private key")))?
+            .map_err(|e| {
+                warn!("Failed to read private key pem: {e}");
+                LogicError::MsgError(String::from("Failed to read private key"))
+            })?;
+        if pem.label != "ENCRYPTED PRIVATE KEY" {
+            if String::from_utf8_lossy(key).contains("Proc-Type: 4,ENCRYPTED") {
+                return Err(LogicError::MsgError(String::from(
+                    "Legacy encrypted private key is not supported, please convert it to PKCS#8",
+                )));
+            }
+            return Ok((identity.cert.clone(), key.clone()));
+        }
+        pem.contents
+    } else {
+        key.clone()
+    };
+
+    let key_der = match EncryptedPrivateKeyInfo::from_der(&key_der) {
+        Ok(encrypted) => {
+            let passphrase = identity
+                .passphrase
+                .as_ref()
+                .ok_or(LogicError::PassphraseRequired)?;
+            let document = encrypted.decrypt(passphrase).map_err(|e| {
+                warn!("Failed to decrypt private key: {e}");
+                LogicError::MsgError(String::from(
+                    "Failed to decrypt private key, please check the passphrase",
+                ))
+            })?;
+            document.as_bytes().to_vec()
+        }
+        //  未加密的DER格式私钥
+        Err(_) => key_der,
+    };
+
+    Ok((
+        identity.cert.clone(),
+        encode_pem("PRIVATE KEY", &key_der).into_bytes(),
+    ))
+}
+
+fn resolve_pkcs12(data: &[u8], passphrase: Option<&str>) -> Result<(Vec<u8>, Vec<u8>), LogicError> {
+    let pfx = p12::PFX::parse(data).map_err(|e| {
+        warn!("Failed to parse pkcs12: {:?}", e);
+        LogicError::MsgError(String::from("Failed to parse PKCS#12 file"))
+    })?;
+
+    let password = passphrase.unwrap_or("");
+    if !pfx.verify_mac(password) {
+        return if passphrase.is_none() {
+            Err(LogicError::PassphraseRequired)
+        } else {
+            Err(LogicError::MsgError(String::from(
+                "Failed to decrypt PKCS#12 file, please check the passphrase",
+            )))
+        };
+    }
+
+    let decrypt_err = |e| {
+        warn!("Failed to decrypt pkcs12 bags: {:?}", e);
+        LogicError::MsgError(String::from("Failed to decrypt PKCS#12 file"))
+    };
+    let keys = pfx.key_bags(password).map_err(decrypt_err)?;
+    let certs = pfx.cert_x509_bags(password).map_err(decrypt_err)?;
+
+    let key = keys
+        .first()
+        .ok_or(LogicError::MsgError(String::from("No private key found in PKCS#12 file")))?;
+    if certs.is_empty() {
+        return Err(LogicError::MsgError(String::from(
+            "No certificate found in PKCS#12 file",
+        )));
+    }
+
+    let mut cert_pem = String::new();
+    for cert in certs.iter() {
+        cert_pem.push_str(der_to_pem(cert).as_str());
+    }
+
+    Ok((
+        cert_pem.into_bytes(),
+        encode_pem("PRIVATE KEY", key).into_bytes(),
+    ))
+}
+
+/// 解析PEM格式的证书内容，一个文件中可能包含多个证书（证书链）
+pub fn parse_pem_certificates(data: &[u8]) -> Result<Vec<CertificateInfo>, LogicError> {
+    let mut result = Vec::new();
+    for pem in Pem::iter_from_buffer(data) {
+        let pem = pem.map_err(|e| {
+            warn!("Failed to read pem: {e}");
+            LogicError::MsgError(String::from("Failed to read PEM certificate"))
+        })?;
+        if pem.label != "CERTIFICATE" {
+            continue;
+        }
+        result.push(parse_der_certificate(&pem.contents)?);
+    }
+    Ok(result)
+}
+
+/// 解析DER格式的证书
+pub fn parse_der_certificate(der: &[u8]) -> Result<CertificateInfo, LogicError> {
+    let (_, cert) = x509_parser::parse_x509_certificate(der).map_err(|e| {
+        warn!("Failed to parse x509 certificate: {e}");
+        LogicError::MsgError(String::from("Failed to parse x509 certificate"))
+    })?;
+
+    let mut sans = Vec::new();
+    if let Ok(Some(ext)) = cert.subject_alternative_name() {
+        for name in ext.value.general_names.iter() {
+            match name {
+                GeneralName::DNSName(s) => sans.push(format!("DNS:{}", s)),
+                GeneralName::URI(s) => sans.push(format!("URI:{}", s)),
+                GeneralName::RFC822Name(s) => sans.push(format!("email:{}", s)),
+                GeneralName::IPAddress(ip) => sans.push(format!("IP:{}", format_ip(ip))),
+                _ => {}
+            }
+        }
+    }
+
+    let validity = cert.validity();
+    let not_before = validity.not_before.timestamp() * 1000;
+    let not_after = validity.not_after.timestamp() * 1000;
+    let now = now_timestamp() as i64;
+
+    Ok(CertificateInfo {
+        subject: cert.subject().to_string(),
+        issuer: cert.issuer().to_string(),
+        serial: cert.raw_serial_as_string(),
+        sans,
+        not_before,
+        not_after,
+        fingerprint_sha256: fingerprint_sha256(der),
+        expired: now > not_after,
+        days_remaining: (not_after - now) / MILLIS_PER_DAY,
+    })
+}
+
+/// 解析连接中配置的CA证书以及客户端证书
+pub fn parse_tls_info(tls: &ConnectionTls) -> Result<ConnectionTlsInfo, LogicError> {
+    let mut ca = Vec::new();
+    for cert in &tls.cert {
+        ca.append(&mut parse_pem_certificates(cert)?);
+    }
+
+    let identity = if let Some(identity) = &tls.identity {
+        let (cert, _) = resolve_identity(identity)?;
+        parse_pem_certificates(&cert)?
+    } else {
+        vec![]
+    };
+
+    Ok(ConnectionTlsInfo { ca, identity })
+}
+
+/// 获取剩余有效天数小于等于 `warn_days` 的证书，无法解析的证书将被忽略
+pub fn find_expiring_certificates(tls: &ConnectionTls, warn_days: i64) -> Vec<CertificateInfo> {
+    match parse_tls_info(tls) {
+        Ok(info) => info
+            .ca
+            .into_iter()
+            .chain(info.identity)
+            .filter(|c| c.days_remaining <= warn_days)
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// 计算证书的SHA-256指纹，格式如 `AB:CD:...`
+pub fn fingerprint_sha256(der: &[u8]) -> String {
+    let digest = Sha256::digest(der);
+    digest
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+fn format_ip(ip: &[u8]) -> String {
+    match ip.len() {
+        4 => std::net::Ipv4Addr::new(ip[0], ip[1], ip[2], ip[3]).to_string(),
+        16 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(ip);
+            std::net::Ipv6Addr::from(octets).to_string()
+        }
+        _ => format!("{:?}", ip),
+    }
+}
@@ -0,0 +1,307 @@
+use std::fs;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::transport::connection::{
+    Connection, ConnectionTls, ConnectionUser, ExternalConnection, ExternalConnectionSource, TlsIdentity, UNIX_SOCKET_SCHEME,
+};
+
+const DEFAULT_PORT: u16 = 2379;
+
+/// 解析后的etcd地址
+#[derive(Debug, PartialEq)]
+pub struct Endpoint {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+}
+
+/// 解析 `https://host:port`、`host:port`、`host`、`unix:///path` 格式的地址，未指定端口时使用2379
+pub fn parse_endpoint(endpoint: &str) -> Option<Endpoint> {
+    let endpoint = endpoint.trim();
+    if endpoint.starts_with(UNIX_SOCKET_SCHEME) {
+        return Some(Endpoint {
+            host: String::from(endpoint),
+            port: DEFAULT_PORT,
+            tls: false,
+        });
+    }
+    let (tls, rest) = if let Some(rest) = endpoint.strip_prefix("https://") {
+        (true, rest)
+    } else {
+        (false, endpoint.strip_prefix("http://").unwrap_or(endpoint))
+    };
+    let rest = rest.trim_end_matches('/');
+    if rest.is_empty() {
+        return None;
+    }
+    let (host, port) = match rest.rsplit_once(':') {
+        //  IPv6 地址需要用方括号包裹端口
+        Some((host, port)) if !host.contains(':') || host.ends_with(']') => (host, port.parse().ok()?),
+        _ => (rest, DEFAULT_PORT),
+    };
+    Some(Endpoint {
+        host: String::from(host.trim_start_matches('[').trim_end_matches(']')),
+        port,
+        tls,
+    })
+}
+
+fn new_connection(endpoint: &Endpoint) -> Connection {
+    Connection {
+        host: endpoint.host.clone(),
+        port: endpoint.port,
+        namespace: None,
+        user: None,
+        tls: None,
+        ssh: None,
+        idle_timeout_minutes: None,
+        read_only: false,
+        encrypted_prefixes: vec![],
+        max_txn_ops: None,
+        max_request_bytes: None,
+        max_recv_message_bytes: None,
+        max_send_message_bytes: None,
+        sync_endpoints: false,
+        extends: None,
+        key_tree: None,
+        prompt_credentials: vec![],
+        content_hints: None,
+    }
+}
+
+fn read_file(path: &str, warnings: &mut Vec<String>) -> Option<Vec<u8>> {
+    if path.is_empty() {
+        return None;
+    }
+    match fs::read(path) {
+        Ok(content) => Some(content),
+        Err(e) => {
+            warnings.push(format!("Failed to read {path}: {e}"));
+            None
+        }
+    }
+}
+
+/// 根据证书文件路径配置TLS，地址为https或配置了证书时开启
+fn apply_tls(
+    connection: &mut Connection,
+    tls: bool,
+    ca_file: &str,
+    cert_file: &str,
+    key_file: &str,
+    warnings: &mut Vec<String>,
+) {
+    let ca = read_file(ca_file, warnings);
+    let cert = read_file(cert_file, warnings);
+    let key = read_file(key_file, warnings);
+    if !tls && ca.is_none() && cert.is_none() {
+        return;
+    }
+    let identity = match (cert, key) {
+        (Some(cert), Some(key)) => Some(TlsIdentity {
+            cert,
+            key,
+            passphrase: None,
+            pkcs12: None,
+        }),
+        _ => None,
+    };
+    connection.tls = Some(ConnectionTls {
+        domain: None,
+        cert: ca.into_iter().collect(),
+        identity,
+        pinned_fingerprint: None,
+    });
+}
+
+fn apply_user(connection: &mut Connection, username: &str, password: &str) {
+    if !username.is_empty() {
+        connection.user = Some(ConnectionUser {
+            username: String::from(username),
+            password: String::from(password),
+        });
+    }
+}
+
+fn str_field<'a>(value: &'a Value, names: &[&str]) -> &'a str {
+    names
+        .iter()
+        .find_map(|name| value.get(*name).and_then(Value::as_str))
+        .unwrap_or_default()
+}
+
+/// 解析 etcd-manager 的配置文件，支持单个配置、配置数组以及包含 `etcdConfig`、`connections`、`profiles` 的对象
+pub fn parse_etcd_manager(content: &str) -> Result<Vec<ExternalConnection>, String> {
+    let root: Value = serde_json::from_str(content).map_err(|e| format!("Invalid etcd-manager config: {e}"))?;
+    let configs: Vec<&Value> = match &root {
+        Value::Array(list) => list.iter().collect(),
+        Value::Object(map) => {
+            match ["connections", "profiles"].iter().find_map(|k| map.get(*k).and_then(Value::as_array)) {
+                Some(list) => list.iter().collect(),
+                None => vec![map.get("etcdConfig").unwrap_or(&root)],
+            }
+        }
+        _ => vec![],
+    };
+
+    let mut result = Vec::new();
+    for (i, config) in configs.into_iter().enumerate() {
+        let mut warnings = Vec::new();
+        let Some(mut endpoint) = parse_endpoint(str_field(config, &["endpoint", "host"])) else {
+            continue;
+        };
+        if let Some(port) = config.get("port").and_then(Value::as_u64) {
+            endpoint.port = port as u16;
+        }
+        let mut connection = new_connection(&endpoint);
+        apply_user(
+            &mut connection,
+            str_field(config, &["username", "user"]),
+            str_field(config, &["password"]),
+        );
+        apply_tls(
+            &mut connection,
+            endpoint.tls,
+            str_field(config, &["caFile", "ca"]),
+            str_field(config, &["certFile", "cert"]),
+            str_field(config, &["keyFile", "key"]),
+            &mut warnings,
+        );
+        let name = match str_field(config, &["name", "title"]) {
+            "" => format!("etcd-manager-{}", i + 1),
+            name => String::from(name),
+        };
+        result.push(ExternalConnection {
+            name,
+            connection,
+            warnings,
+        });
+    }
+    Ok(result)
+}
+
+/// 解析 etcdkeeper 的连接信息。etcdkeeper 在浏览器中保存etcd地址，证书和认证通过启动参数配置，
+/// 因此内容为每行一个地址，以 `-` 开头的行为启动参数（`-usetls`、`-cacert`、`-cert`、`-key`），作用于所有地址
+pub fn parse_etcdkeeper(content: &str) -> Result<Vec<ExternalConnection>, String> {
+    let mut endpoints = Vec::new();
+    let mut use_tls = false;
+    let (mut ca_file, mut cert_file, mut key_file) = (String::new(), String::new(), String::new());
+    for line in content.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        if !line.starts_with('-') {
+            endpoints.extend(line.split(',').filter_map(parse_endpoint));
+            continue;
+        }
+        let mut args = line.split_whitespace().peekable();
+        while let Some(arg) = args.next() {
+            let arg = arg.trim_start_matches('-');
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) => (flag, Some(value)),
+                None => (arg, None),
+            };
+            let mut value = || {
+                inline
+                    .map(String::from)
+                    .or_else(|| args.next_if(|v| !v.starts_with('-')).map(String::from))
+                    .unwrap_or_default()
+            };
+            match flag {
+                "usetls" => use_tls = inline.map(|v| v != "false").unwrap_or(true),
+                "cacert" => ca_file = value(),
+                "cert" => cert_file = value(),
+                "key" => key_file = value(),
+                _ => {}
+            }
+        }
+    }
+    if endpoints.is_empty() {
+        return Err(String::from("No etcd address found"));
+    }
+
+    let mut result = Vec::new();
+    for endpoint in endpoints {
+        let mut warnings = Vec::new();
+        let mut connection = new_connection(&endpoint);
+        apply_tls(
+            &mut connection,
+            endpoint.tls || use_tls,
+            &ca_file,
+            &cert_file,
+            &key_file,
+            &mut warnings,
+        );
+        result.push(ExternalConnection {
+            name: format!("etcdkeeper-{}:{}", endpoint.host, endpoint.port),
+            connection,
+            warnings,
+        });
+    }
+    Ok(result)
+}
+
+#[derive(Deserialize)]
+struct KstoneMetadata {
+    name: String,
+    #[serde(default)]
+    annotations: std::collections::HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct KstoneCluster {
+    #[serde(default)]
+    kind: String,
+    metadata: KstoneMetadata,
+}
+
+/// 解析 Kstone 的 EtcdCluster 资源（YAML，可包含多个文档或List），
+/// 地址取自 `importedAddr` 或 `extClientURL` 注解，证书保存在集群的Secret中，需要导入后手动配置
+pub fn parse_kstone(content: &str) -> Result<Vec<ExternalConnection>, String> {
+    let mut clusters = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(content) {
+        let value = serde_yaml::Value::deserialize(document).map_err(|e| format!("Invalid kstone config: {e}"))?;
+        match value.get("items").and_then(|v| v.as_sequence()) {
+            Some(items) => clusters.extend(items.iter().cloned()),
+            None => clusters.push(value),
+        }
+    }
+
+    let mut result = Vec::new();
+    for value in clusters {
+        let Ok(cluster) = serde_yaml::from_value::<KstoneCluster>(value) else {
+            continue;
+        };
+        if !cluster.kind.is_empty() && cluster.kind != "EtcdCluster" {
+            continue;
+        }
+        let annotations = &cluster.metadata.annotations;
+        let address = annotations
+            .get("importedAddr")
+            .or_else(|| annotations.get("extClientURL"))
+            .map(String::as_str)
+            .unwrap_or_default();
+        let Some(endpoint) = address.split(',').find_map(parse_endpoint) else {
+            continue;
+        };
+        let mut warnings = Vec::new();
+        let mut connection = new_connection(&endpoint);
+        if endpoint.tls {
+            apply_tls(&mut connection, true, "", "", "", &mut warnings);
+            warnings.push(String::from("TLS certificates are stored in the cluster secret, configure them manually"));
+        }
+        result.push(ExternalConnection {
+            name: cluster.metadata.name,
+            connection,
+            warnings,
+        });
+    }
+    Ok(result)
+}
+
+pub fn parse(source: ExternalConnectionSource, content: &str) -> Result<Vec<ExternalConnection>, String> {
+    match source {
+        ExternalConnectionSource::EtcdManager => parse_etcd_manager(content),
+        ExternalConnectionSource::Etcdkeeper => parse_etcdkeeper(content),
+        ExternalConnectionSource::Kstone => parse_kstone(content),
+    }
+}
@@ -0,0 +1,186 @@
+use std::io::{Read, Write};
+
+use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+use base64::Engine;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+
+use crate::transport::connection::{Connection, ConnectionSsh, ConnectionTls, ConnectionUser, ExternalConnection};
+
+/// 分享字符串的前缀，包含格式版本
+pub const SHARE_PREFIX: &str = "ewb1:";
+/// 解压后内容的最大字节数，正常的分享内容远小于此值
+const MAX_DECODED_BYTES: u64 = 64 * 1024;
+
+/// 分享内容，字段名尽量短以便生成二维码。不包含密码、私钥及证书内容
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+struct SharedProfile {
+    n: String,
+    h: String,
+    p: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ns: Option<String>,
+    /// 用户名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    u: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tls: Option<SharedTls>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ssh: Option<SharedSsh>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    ro: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+struct SharedTls {
+    /// 校验证书使用的域名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    d: Option<String>,
+    /// 是否需要CA证书
+    #[serde(default)]
+    ca: bool,
+    /// 是否需要客户端证书
+    #[serde(default)]
+    id: bool,
+    /// 固定的服务端证书指纹
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fp: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+struct SharedSsh {
+    h: String,
+    p: u16,
+    u: String,
+    /// 是否启用压缩
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    z: bool,
+    /// 加密、密钥交换、MAC算法
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    c: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    k: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    m: Vec<String>,
+    /// 是否从 `~/.ssh/config` 解析主机别名
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    cfg: bool,
+}
+
+/// 将连接配置编码为可分享的字符串，去除所有密码、私钥及证书内容
+pub fn encode(name: &str, connection: &Connection) -> Result<String, String> {
+    let profile = SharedProfile {
+        n: name.to_string(),
+        h: connection.host.clone(),
+        p: connection.port,
+        ns: connection.namespace.clone().filter(|ns| !ns.is_empty()),
+        u: connection.user.as_ref().map(|u| u.username.clone()),
+        tls: connection.tls.as_ref().map(|tls| SharedTls {
+            d: tls.domain.clone(),
+            ca: !tls.cert.is_empty(),
+            id: tls.identity.is_some(),
+            fp: tls.pinned_fingerprint.clone(),
+        }),
+        ssh: connection.ssh.as_ref().map(|ssh| SharedSsh {
+            h: ssh.host.clone(),
+            p: ssh.port,
+            u: ssh.user.clone(),
+            z: ssh.compression,
+            c: ssh.ciphers.clone(),
+            k: ssh.kex.clone(),
+            m: ssh.macs.clone(),
+            cfg: ssh.use_config,
+        }),
+        ro: connection.read_only,
+    };
+    let json = serde_json::to_vec(&profile).map_err(|e| e.to_string())?;
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&json).map_err(|e| e.to_string())?;
+    let data = encoder.finish().map_err(|e| e.to_string())?;
+    Ok(format!("{}{}", SHARE_PREFIX, BASE64_URL_SAFE_NO_PAD.encode(data)))
+}
+
+/// 解析分享字符串，返回的连接不含任何凭据，`warnings` 列出导入后需要补充的内容
+pub fn decode(content: &str) -> Result<ExternalConnection, String> {
+    let encoded = content
+        .trim()
+        .strip_prefix(SHARE_PREFIX)
+        .ok_or_else(|| String::from("Unsupported connection share string"))?;
+    let data = BASE64_URL_SAFE_NO_PAD
+        .decode(encoded)
+        .map_err(|e| format!("Invalid connection share string: {e}"))?;
+    let mut json = Vec::new();
+    DeflateDecoder::new(data.as_slice())
+        .take(MAX_DECODED_BYTES + 1)
+        .read_to_end(&mut json)
+        .map_err(|e| format!("Invalid connection share string: {e}"))?;
+    if json.len() as u64 > MAX_DECODED_BYTES {
+        return Err(String::from("Connection share string is too large"));
+    }
+    let profile: SharedProfile =
+        serde_json::from_slice(&json).map_err(|e| format!("Invalid connection share string: {e}"))?;
+
+    let mut warnings = Vec::new();
+    let user = profile.u.map(|username| {
+        warnings.push(format!("Password of user {} is required", username));
+        ConnectionUser {
+            username,
+            password: String::new(),
+        }
+    });
+    let tls = profile.tls.map(|tls| {
+        if tls.ca {
+            warnings.push(String::from("CA certificate is required"));
+        }
+        if tls.id {
+            warnings.push(String::from("Client certificate and key are required"));
+        }
+        ConnectionTls {
+            domain: tls.d,
+            cert: vec![],
+            identity: None,
+            pinned_fingerprint: tls.fp,
+        }
+    });
+    let ssh = profile.ssh.map(|ssh| {
+        warnings.push(format!("SSH password or private key of {}@{} is required", ssh.u, ssh.h));
+        ConnectionSsh {
+            host: ssh.h,
+            port: ssh.p,
+            user: ssh.u,
+            identity: None,
+            compression: ssh.z,
+            ciphers: ssh.c,
+            kex: ssh.k,
+            macs: ssh.m,
+            use_config: ssh.cfg,
+        }
+    });
+
+    Ok(ExternalConnection {
+        name: profile.n,
+        connection: Connection {
+            host: profile.h,
+            port: profile.p,
+            namespace: profile.ns,
+            user,
+            tls,
+            ssh,
+            idle_timeout_minutes: None,
+            read_only: profile.ro,
+            encrypted_prefixes: vec![],
+            max_txn_ops: None,
+            max_request_bytes: None,
+            max_recv_message_bytes: None,
+            max_send_message_bytes: None,
+            sync_endpoints: false,
+            extends: None,
+            key_tree: None,
+            prompt_credentials: vec![],
+            content_hints: None,
+        },
+        warnings,
+    })
+}
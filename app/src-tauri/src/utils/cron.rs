@@ -0,0 +1,108 @@
+use chrono::{DateTime, Datelike, Duration, Local, Timelike};
+
+/// 向后查找下次执行时间的最大范围（分钟），约一年
+const MAX_LOOKAHEAD_MINUTES: i64 = 366 * 24 * 60;
+
+/// 五段式cron表达式：分 时 日 月 周。
+///
+/// 每段支持 `*`、数字、范围 `a-b`、步长 `*/n` 或 `a-b/n` 以及以逗号分隔的列表，周日可写作0或7。
+/// 与标准cron一致，日和周同时指定时满足其一即可
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("Invalid step: {}", item))?;
+                if step == 0 {
+                    return Err(format!("Invalid step: {}", item));
+                }
+                (range, step)
+            }
+            None => (item, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            let start = start.parse().map_err(|_| format!("Invalid range: {}", item))?;
+            let end = end.parse().map_err(|_| format!("Invalid range: {}", item))?;
+            (start, end)
+        } else {
+            let value: u32 = range.parse().map_err(|_| format!("Invalid value: {}", item))?;
+            //  `5/10` 表示从5开始每10个单位
+            if item.contains('/') {
+                (value, max)
+            } else {
+                (value, value)
+            }
+        };
+        if start < min || end > max || start > end {
+            return Err(format!("Value out of range {}-{}: {}", min, max, item));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+impl CronSchedule {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!("Cron expression must have 5 fields: {}", expr));
+        }
+        let mut weekdays = parse_field(fields[4], 0, 7)?;
+        //  7 和 0 都表示周日
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(CronSchedule {
+            minutes: parse_field(fields[0], 0, 59)?,
+            hours: parse_field(fields[1], 0, 23)?,
+            days: parse_field(fields[2], 1, 31)?,
+            months: parse_field(fields[3], 1, 12)?,
+            weekdays,
+            any_day: fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        })
+    }
+
+    /// 时间所在的分钟是否满足表达式
+    pub fn matches(&self, time: &DateTime<Local>) -> bool {
+        let bit = |mask: u64, value: u32| mask & (1 << value) != 0;
+        if !bit(self.minutes, time.minute()) || !bit(self.hours, time.hour()) || !bit(self.months, time.month()) {
+            return false;
+        }
+        let day = bit(self.days, time.day());
+        let weekday = bit(self.weekdays, time.weekday().num_days_from_sunday());
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        }
+    }
+
+    /// 查找 `time` 之后的下一次执行时间，一年内没有匹配时返回空
+    pub fn next_after(&self, time: &DateTime<Local>) -> Option<DateTime<Local>> {
+        let mut next = time.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        for _ in 0..MAX_LOOKAHEAD_MINUTES {
+            if self.matches(&next) {
+                return Some(next);
+            }
+            next += Duration::minutes(1);
+        }
+        None
+    }
+}
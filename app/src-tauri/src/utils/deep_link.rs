@@ -0,0 +1,109 @@
+/// 自定义协议名
+pub const DEEP_LINK_SCHEME: &str = "etcd-workbench";
+
+/// 解析后的深度链接，格式为 `etcd-workbench://connection/<name>[/key/<path>][?revision=<rev>]`，
+/// 连接名和key路径需经过URL编码
+#[derive(Debug, PartialEq, Eq)]
+pub struct DeepLink {
+    pub connection: String,
+    pub key: Option<String>,
+    /// 固定查看key在该版本时的值
+    pub revision: Option<i64>,
+}
+
+impl DeepLink {
+    pub fn parse(url: &str) -> Result<Self, String> {
+        let path = url
+            .trim()
+            .strip_prefix(DEEP_LINK_SCHEME)
+            .and_then(|s| s.strip_prefix("://"))
+            .ok_or_else(|| format!("Unsupported link: {}", url))?;
+        //  忽略锚点和无法识别的查询参数
+        let path = path.split('#').next().unwrap_or("");
+        let (path, query) = path.split_once('?').unwrap_or((path, ""));
+
+        let rest = path
+            .strip_prefix("connection/")
+            .ok_or_else(|| format!("Unsupported link: {}", url))?;
+        let (name, rest) = match rest.split_once('/') {
+            Some((name, rest)) => (name, Some(rest)),
+            None => (rest, None),
+        };
+        let connection = percent_decode(name)?;
+        if connection.is_empty() {
+            return Err(String::from("Missing connection name"));
+        }
+
+        let key = match rest {
+            None | Some("") => None,
+            Some(rest) => {
+                let key = rest
+                    .strip_prefix("key/")
+                    .ok_or_else(|| format!("Unsupported link: {}", url))?;
+                Some(percent_decode(key)?)
+            }
+        };
+        let mut revision = None;
+        for (name, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+            if name == "revision" {
+                let rev = value
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|rev| *rev > 0)
+                    .ok_or_else(|| format!("Invalid revision: {}", value))?;
+                revision = Some(rev);
+            }
+        }
+        if revision.is_some() && key.is_none() {
+            return Err(String::from("Revision requires a key"));
+        }
+        Ok(DeepLink { connection, key, revision })
+    }
+
+    /// 生成规范的链接，key中的 `/` 保留，其他保留字符均编码
+    pub fn to_url(&self) -> String {
+        let mut url = format!("{}://connection/{}", DEEP_LINK_SCHEME, percent_encode(&self.connection, false));
+        if let Some(key) = &self.key {
+            url.push_str("/key/");
+            url.push_str(&percent_encode(key, true));
+            if let Some(revision) = self.revision {
+                url.push_str(&format!("?revision={}", revision));
+            }
+        }
+        url
+    }
+}
+
+/// URL百分号编码，只保留非保留字符
+fn percent_encode(s: &str, keep_slash: bool) -> String {
+    let mut result = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) || (keep_slash && b == b'/') {
+            result.push(b as char);
+        } else {
+            result.push_str(&format!("%{:02X}", b));
+        }
+    }
+    result
+}
+
+/// URL百分号解码
+fn percent_decode(s: &str) -> Result<String, String> {
+    let bytes = s.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s
+                .get(i + 1..i + 3)
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+                .ok_or_else(|| format!("Invalid percent encoding: {}", s))?;
+            result.push(hex);
+            i += 3;
+        } else {
+            result.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(result).map_err(|_| format!("Invalid utf-8 in link: {}", s))
+}
@@ -2,10 +2,14 @@
 use std::{fs, io};
 use std::env::temp_dir;
 use std::fs::File;
-use std::io::Write;
-use std::path::{PathBuf};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use log::info;
 use tauri::api::path::{BaseDirectory, local_data_dir};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use uuid::Uuid;
 
 static BASE_DIR: &'static str = "Etcd Workbench";
@@ -14,6 +18,15 @@ pub static DATA_DIR: &'static str = "data";
 pub static SETTINGS_FILE: &'static str = "settings";
 pub static GLOBAL_STORE_FILE: &'static str = "store";
 pub static META_FILE: &'static str = "meta";
+pub static RECENT_KEYS_FILE: &'static str = "recent_keys";
+pub static SANDBOX_DIR: &'static str = "sandbox";
+pub static PLUGIN_DIR: &'static str = "plugins";
+pub static USAGE_STATS_FILE: &'static str = "usage_stats";
+pub static TRASH_FILE: &'static str = "trash";
+pub static MACROS_FILE: &'static str = "macros";
+pub static STATE_CACHE_DIR: &'static str = "state_cache";
+/// 文件分块读写的大小
+const CHUNK_SIZE: usize = 64 * 1024;
 
 /// 创建一个临时文件，并返回该文件的全路径
 pub fn create_temp_file(data: &[u8]) -> io::Result<String> {
@@ -26,6 +39,80 @@ pub fn create_temp_file(data: &[u8]) -> io::Result<String> {
     Ok(file_full_name)
 }
 
+/// 分块读取文件内容，`compress` 为true时边读边进行gzip压缩。
+/// 内容超过 `max_size` 时停止读取并返回空
+pub async fn read_file_chunked(path: &Path, compress: bool, max_size: usize) -> io::Result<Option<Vec<u8>>> {
+    let mut file = tokio::fs::File::open(path).await?;
+    if !compress && file.metadata().await?.len() > max_size as u64 {
+        return Ok(None);
+    }
+
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let mut content = Vec::new();
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        let size = if compress {
+            encoder.write_all(&buf[..n])?;
+            encoder.get_ref().len()
+        } else {
+            content.extend_from_slice(&buf[..n]);
+            content.len()
+        };
+        if size > max_size {
+            return Ok(None);
+        }
+    }
+
+    if compress {
+        content = encoder.finish()?;
+        if content.len() > max_size {
+            return Ok(None);
+        }
+    }
+    Ok(Some(content))
+}
+
+/// 分块写入文件，`decompress` 为true且内容为gzip格式时边解压边写入，返回写入的字节数
+pub async fn write_file_chunked(path: &Path, content: &[u8], decompress: bool) -> io::Result<u64> {
+    let mut file = tokio::fs::File::create(path).await?;
+    let mut written = 0u64;
+    if decompress && is_gzip(content) {
+        let mut decoder = GzDecoder::new(content);
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        loop {
+            let n = decoder.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            file.write_all(&buf[..n]).await?;
+            written += n as u64;
+        }
+    } else {
+        for chunk in content.chunks(CHUNK_SIZE) {
+            file.write_all(chunk).await?;
+            written += chunk.len() as u64;
+        }
+    }
+    file.flush().await?;
+    Ok(written)
+}
+
+/// 使用gzip压缩内容
+pub fn gzip(content: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(content)?;
+    encoder.finish()
+}
+
+/// 判断内容是否为gzip格式
+pub fn is_gzip(content: &[u8]) -> bool {
+    content.starts_with(&[0x1f, 0x8b])
+}
+
 pub fn init() -> io::Result<()> {
     let path = get_storage_root_path();
     info!("initialized local path: {}", path.to_str().unwrap_or(""));
@@ -62,6 +149,55 @@ pub fn get_global_store_file_path() -> PathBuf {
     path
 }
 
+/// 获取最近访问key记录的文件路径
+pub fn get_recent_keys_file_path() -> PathBuf {
+    let mut path = get_data_path();
+    path.push(RECENT_KEYS_FILE);
+    path
+}
+
+/// 获取本地使用统计的文件路径
+pub fn get_usage_stats_file_path() -> PathBuf {
+    let mut path = get_data_path();
+    path.push(USAGE_STATS_FILE);
+    path
+}
+
+/// 获取已删除key回收站的文件路径
+pub fn get_trash_file_path() -> PathBuf {
+    let mut path = get_data_path();
+    path.push(TRASH_FILE);
+    path
+}
+
+/// 获取录制的操作宏的文件路径
+pub fn get_macros_file_path() -> PathBuf {
+    let mut path = get_data_path();
+    path.push(MACROS_FILE);
+    path
+}
+
+/// 获取离线状态缓存的目录，每个连接一个文件
+pub fn get_state_cache_dir_path() -> PathBuf {
+    let mut path = get_data_path();
+    path.push(STATE_CACHE_DIR);
+    path
+}
+
+/// 获取本地沙箱集群的目录，存放etcd程序、数据和日志
+pub fn get_sandbox_dir_path() -> PathBuf {
+    let mut path = get_storage_root_path();
+    path.push(SANDBOX_DIR);
+    path
+}
+
+/// 获取值解码插件的目录，每个插件一个子目录
+pub fn get_plugin_dir_path() -> PathBuf {
+    let mut path = get_storage_root_path();
+    path.push(PLUGIN_DIR);
+    path
+}
+
 /// 存储数据的目录，存放配置、元数据、设置等
 pub fn get_data_path() -> PathBuf {
     let mut path = get_storage_root_path();
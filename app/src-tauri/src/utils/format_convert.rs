@@ -0,0 +1,213 @@
+use serde_yaml::Value;
+
+use crate::transport::kv::FormatLanguage;
+
+/// 在JSON和YAML之间转换值，转换后重新解析并与原内容比较，不一致或原内容包含
+/// 无法在目标格式中表示的信息（注释、锚点、标签等）时返回错误，避免保存时丢失数据
+pub fn convert(content: &str, from: &FormatLanguage, to: &FormatLanguage) -> Result<String, String> {
+    match (from, to) {
+        (FormatLanguage::Json, FormatLanguage::Yaml) => json_to_yaml(content),
+        (FormatLanguage::Yaml, FormatLanguage::Json) => yaml_to_json(content),
+        (FormatLanguage::Json, FormatLanguage::Json) | (FormatLanguage::Yaml, FormatLanguage::Yaml) => {
+            Ok(String::from(content))
+        }
+        _ => Err(format!("Unsupported conversion: {:?} to {:?}", from, to)),
+    }
+}
+
+fn json_to_yaml(content: &str) -> Result<String, String> {
+    //  使用YAML的Mapping保留key的顺序
+    let value: Value = serde_json::from_str(content).map_err(|e| format!("Invalid json: {e}"))?;
+    //  解析后再比较无法发现解析时已丢失的精度，需要检查原文中的数字
+    if let Some(number) = find_lossy_number(content) {
+        return Err(format!("The number {} can not be kept exactly in yaml", number));
+    }
+    let yaml = serde_yaml::to_string(&value).map_err(|e| format!("Failed to write yaml: {e}"))?;
+    let back: Value = serde_yaml::from_str(&yaml).map_err(|e| format!("Converted yaml is invalid: {e}"))?;
+    if back != value {
+        return Err(String::from("The value changes after converting to yaml"));
+    }
+    Ok(yaml)
+}
+
+/// 查找解析后会改变的数字字面量。数字按i64、u64或f64保存，超出范围或精度的数字会被改变。
+///
+/// `content` 必须是合法的JSON，跳过字符串中的内容
+pub fn find_lossy_number(content: &str) -> Option<&str> {
+    let bytes = content.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i += 1;
+            }
+            b'-' | b'0'..=b'9' => {
+                let start = i;
+                while i < bytes.len() && matches!(bytes[i], b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E') {
+                    i += 1;
+                }
+                let literal = &content[start..i];
+                if !number_round_trips(literal) {
+                    return Some(literal);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+fn number_round_trips(literal: &str) -> bool {
+    if literal.parse::<i64>().is_ok() || literal.parse::<u64>().is_ok() {
+        return true;
+    }
+    match literal.parse::<f64>() {
+        //  `{:e}` 输出能还原为同一个f64的最短十进制表示，与原文表示的数值相同时写出后不变
+        Ok(f) if f.is_finite() => match normalize_decimal(literal) {
+            Some(original) => normalize_decimal(&format!("{:e}", f)) == Some(original),
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// 将十进制数字拆分为 (是否为负, 去除首尾0的有效数字, 指数)
+fn normalize_decimal(literal: &str) -> Option<(bool, String, i64)> {
+    let (negative, literal) = match literal.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, literal),
+    };
+    let (mantissa, exponent) = match literal.find(['e', 'E']) {
+        Some(pos) => (&literal[..pos], literal[pos + 1..].trim_start_matches('+').parse::<i64>().ok()?),
+        None => (literal, 0),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{}{}", integer, fraction);
+    let digits = digits.trim_start_matches('0');
+    let trimmed = digits.trim_end_matches('0');
+    let exponent = exponent - fraction.len() as i64 + (digits.len() - trimmed.len()) as i64;
+    if trimmed.is_empty() {
+        return Some((negative, String::new(), 0));
+    }
+    Some((negative, String::from(trimmed), exponent))
+}
+
+fn yaml_to_json(content: &str) -> Result<String, String> {
+    if let Some(feature) = find_lossy_syntax(content) {
+        return Err(format!("The yaml contains {} which can not be kept in json", feature));
+    }
+    let value: Value = serde_yaml::from_str(content).map_err(|e| format!("Invalid yaml: {e}"))?;
+    if contains_tag(&value) {
+        return Err(String::from("The yaml contains tags which can not be kept in json"));
+    }
+    let json = serde_json::to_string_pretty(&value).map_err(|e| format!("Failed to write json: {e}"))?;
+    let back: Value = serde_json::from_str(&json).map_err(|e| format!("Converted json is invalid: {e}"))?;
+    //  如非字符串的key、NaN等无法用json表示的值
+    if back != value {
+        return Err(String::from("The value changes after converting to json"));
+    }
+    Ok(json)
+}
+
+fn contains_tag(value: &Value) -> bool {
+    match value {
+        Value::Tagged(_) => true,
+        Value::Sequence(seq) => seq.iter().any(contains_tag),
+        Value::Mapping(map) => map.iter().any(|(k, v)| contains_tag(k) || contains_tag(v)),
+        _ => false,
+    }
+}
+
+/// 查找解析后会丢失的YAML语法，返回其描述。
+///
+/// 只做词法层面的检查：跳过引号内的内容和块标量（`|`、`>`）的正文
+pub fn find_lossy_syntax(content: &str) -> Option<&'static str> {
+    let mut quote: Option<char> = None;
+    //  块标量所属行的缩进，之后缩进更大的行都是正文
+    let mut block_indent: Option<usize> = None;
+    let mut has_content = false;
+
+    for line in content.lines() {
+        let indent = line.len() - line.trim_start().len();
+        if let Some(parent) = block_indent {
+            if line.trim().is_empty() || indent > parent {
+                continue;
+            }
+            block_indent = None;
+        }
+        if quote.is_none() {
+            let trimmed = line.trim_end();
+            if trimmed == "---" || trimmed.starts_with("--- ") || trimmed == "..." {
+                if has_content {
+                    return Some("multiple documents");
+                }
+                continue;
+            }
+        }
+
+        let mut prev: Option<char> = None;
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match quote {
+                Some('\'') => {
+                    if c == '\'' {
+                        //  单引号内用两个单引号转义
+                        if chars.peek() == Some(&'\'') {
+                            chars.next();
+                        } else {
+                            quote = None;
+                        }
+                    }
+                }
+                Some(_) => {
+                    if c == '\\' {
+                        chars.next();
+                    } else if c == '"' {
+                        quote = None;
+                    }
+                }
+                None => {
+                    let token_start = prev.map(|p| p.is_whitespace() || "[{,".contains(p)).unwrap_or(true);
+                    if token_start {
+                        match c {
+                            '\'' | '"' => quote = Some(c),
+                            '#' => return Some("comments"),
+                            '&' | '*' if chars.peek().is_some_and(|n| !n.is_whitespace()) => {
+                                return Some("anchors or aliases");
+                            }
+                            '!' => return Some("tags"),
+                            _ => {}
+                        }
+                    }
+                    if !c.is_whitespace() {
+                        has_content = true;
+                    }
+                }
+            }
+            prev = Some(c);
+        }
+
+        if quote.is_none() && is_block_scalar_header(line) {
+            block_indent = Some(indent);
+        }
+    }
+    None
+}
+
+/// 行尾为块标量标识，如 `key: |`、`- >-`、`key: |2+`
+fn is_block_scalar_header(line: &str) -> bool {
+    let trimmed = line.trim_end();
+    let Some(pos) = trimmed.rfind(['|', '>']) else {
+        return false;
+    };
+    let (head, indicator) = trimmed.split_at(pos);
+    if !indicator[1..].chars().all(|c| c == '+' || c == '-' || c.is_ascii_digit()) {
+        return false;
+    }
+    let head = head.trim_end();
+    head.is_empty() || head.ends_with(':') || head.ends_with('-')
+}
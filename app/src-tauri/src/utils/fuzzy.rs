@@ -0,0 +1,80 @@
+/// 连续匹配的加分
+const CONSECUTIVE_BONUS: i64 = 15;
+/// 匹配到单词开头（首字符或分隔符之后）的加分
+const BOUNDARY_BONUS: i64 = 10;
+/// 每个字符的基础分
+const MATCH_SCORE: i64 = 10;
+
+fn is_separator(c: char) -> bool {
+    matches!(c, '/' | '-' | '_' | '.' | ' ' | ':')
+}
+
+/// 不区分大小写的模糊匹配，`pattern` 的字符需按顺序出现在 `text` 中。
+///
+/// 返回匹配分数以及匹配到的字符下标，分数越高越相关；不匹配时返回空
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let pattern: Vec<char> = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if pattern.is_empty() {
+        return Some((0, vec![]));
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut positions = Vec::with_capacity(pattern.len());
+    let mut score = 0;
+    let mut pi = 0;
+    for (i, c) in chars.iter().enumerate() {
+        if pi == pattern.len() {
+            break;
+        }
+        if !c.to_lowercase().eq(std::iter::once(pattern[pi])) {
+            continue;
+        }
+        score += MATCH_SCORE;
+        if i == 0 || is_separator(chars[i - 1]) {
+            score += BOUNDARY_BONUS;
+        }
+        if positions.last().map(|last| last + 1 == i).unwrap_or(false) {
+            score += CONSECUTIVE_BONUS;
+        }
+        positions.push(i);
+        pi += 1;
+    }
+    if pi < pattern.len() {
+        return None;
+    }
+
+    //  越短的文本、越靠前的匹配越相关
+    score -= chars.len() as i64 / 4;
+    score -= positions[0] as i64;
+    Some((score, positions))
+}
+
+/// 通配符匹配，`*` 匹配任意长度的字符，`?` 匹配单个字符
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    //  最近一个 `*` 的位置以及当时匹配到的文本位置，用于回溯
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((sp, st)) = star {
+            p = sp + 1;
+            t = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
@@ -80,6 +80,7 @@ where
                 source: FormatSource::Kubernetes,
                 language: FormatLanguage::Json,
                 value: s,
+                plugin: None,
             });
         }
         Err(e) => {
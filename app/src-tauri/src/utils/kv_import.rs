@@ -0,0 +1,90 @@
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use serde::Deserialize;
+
+use crate::transport::kv::PrefixMapping;
+
+/// `consul kv export` 导出的单条记录，value为base64编码
+#[derive(Deserialize)]
+struct ConsulEntry {
+    key: String,
+    #[serde(default)]
+    value: Option<String>,
+}
+
+/// 解析 `consul kv export` 导出的JSON，Consul的key不以 `/` 开头，导入时补全
+pub fn parse_consul_export(content: &str) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let entries: Vec<ConsulEntry> =
+        serde_json::from_str(content).map_err(|e| format!("Invalid consul export: {e}"))?;
+    let mut kvs = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let value = match entry.value {
+            Some(v) if !v.is_empty() => BASE64_STANDARD
+                .decode(v)
+                .map_err(|e| format!("Invalid base64 value of key {}: {e}", entry.key))?,
+            _ => vec![],
+        };
+        let key = if entry.key.starts_with('/') {
+            entry.key
+        } else {
+            format!("/{}", entry.key)
+        };
+        kvs.push((key, value));
+    }
+    Ok(kvs)
+}
+
+/// 解析 ZooKeeper 导出数据，支持两种格式：
+/// - JSON 对象，`{"/path": "value"}`
+/// - 文本，每行一个节点 `/path=value`，值中的 `\n` 转义为换行，`#` 开头的行为注释
+///
+/// 只导入有数据的节点，仅作为路径的空节点会被忽略
+pub fn parse_zookeeper_dump(content: &str) -> Result<Vec<(String, Vec<u8>)>, String> {
+    if content.trim_start().starts_with('{') {
+        let map: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(content).map_err(|e| format!("Invalid zookeeper dump: {e}"))?;
+        return Ok(map
+            .into_iter()
+            .filter_map(|(path, value)| {
+                let value = match value {
+                    serde_json::Value::Null => return None,
+                    serde_json::Value::String(s) => s.into_bytes(),
+                    other => other.to_string().into_bytes(),
+                };
+                Some((path, value))
+            })
+            .filter(|(_, value)| !value.is_empty())
+            .collect());
+    }
+
+    let mut kvs = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (path, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid zookeeper dump at line {}: {}", i + 1, line))?;
+        if !path.starts_with('/') {
+            return Err(format!("Invalid zookeeper path at line {}: {}", i + 1, path));
+        }
+        if value.is_empty() {
+            continue;
+        }
+        kvs.push((String::from(path), value.replace("\\n", "\n").into_bytes()));
+    }
+    Ok(kvs)
+}
+
+/// 按前缀映射转换key，多个映射匹配时使用最长的源前缀，没有匹配时保持不变
+pub fn map_key(key: &str, mappings: &[PrefixMapping]) -> String {
+    match mappings
+        .iter()
+        .filter(|m| key.starts_with(m.source.as_str()))
+        .max_by_key(|m| m.source.len())
+    {
+        Some(m) => format!("{}{}", m.target, &key[m.source.len()..]),
+        None => String::from(key),
+    }
+}
@@ -1,6 +1,24 @@
 pub mod file_util;
 pub mod aes_util;
 pub mod k8s_formatter;
+pub mod cert_util;
+pub mod report_util;
+pub mod deep_link;
+pub mod fuzzy;
+pub mod kv_import;
+pub mod conn_import;
+pub mod workspace;
+pub mod usage_stats;
+pub mod value_transfer;
+pub mod text_diff;
+pub mod value_plugin;
+pub mod plugin_host;
+pub mod trash;
+pub mod cron;
+pub mod template;
+pub mod conn_share;
+pub mod state_cache;
+pub mod format_convert;
 mod test;
 
 
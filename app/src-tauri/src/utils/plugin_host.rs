@@ -0,0 +1,302 @@
+use log::{debug, info};
+use tokio::runtime::Handle;
+use wasmi::{Caller, Engine, Extern, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::error::LogicError;
+use crate::etcd;
+use crate::transport::plugin::{PluginCommandResult, PluginGrant, PluginPanel};
+use crate::utils::value_plugin;
+
+/// 宿主函数所在的导入模块名
+pub const HOST_MODULE: &'static str = "workbench";
+/// 插件可以导入的宿主函数：
+///
+/// - `read_key(key_ptr, key_len) -> i64`: 读取key的值，返回 `(地址 << 32) | 长度`
+/// - `list_keys(prefix_ptr, prefix_len) -> i64`: 列出前缀下的key，结果为JSON数组，返回值同上
+/// - `put_key(key_ptr, key_len, value_ptr, value_len) -> i32`: 写入key，需要用户授予写权限，成功返回0
+/// - `emit_panel(ptr, len) -> i32`: 输出一个JSON格式的面板用于界面展示，成功返回0
+///
+/// 返回负数表示失败：-1 无权访问，-2 执行出错
+pub const HOST_FUNCTIONS: [&'static str; 4] = ["read_key", "list_keys", "put_key", "emit_panel"];
+/// 单次执行可使用的内存上限
+const MAX_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+/// 插件单次传给宿主函数的数据长度上限
+const MAX_INPUT_BYTES: usize = 8 * 1024 * 1024;
+/// 单次执行最多输出的面板数
+const MAX_PANELS: usize = 50;
+const DENIED: i32 = -1;
+const FAILED: i32 = -2;
+
+/// 插件实例的运行状态，宿主函数据此限制插件可以访问的范围
+pub struct HostState {
+    limits: StoreLimits,
+    /// 执行命令时的连接，解码值时为空，此时所有读写都被拒绝
+    session: Option<i32>,
+    granted_prefixes: Vec<String>,
+    allow_write: bool,
+    handle: Option<Handle>,
+    panels: Vec<PluginPanel>,
+}
+
+impl HostState {
+    fn new(session: Option<i32>, grant: &PluginGrant, handle: Option<Handle>) -> Self {
+        HostState {
+            limits: StoreLimitsBuilder::new().memory_size(MAX_MEMORY_BYTES).build(),
+            session,
+            granted_prefixes: grant.prefixes.clone(),
+            allow_write: grant.allow_write,
+            handle,
+            panels: vec![],
+        }
+    }
+
+    fn is_granted(&self, key: &str) -> bool {
+        self.session.is_some() && self.granted_prefixes.iter().any(|p| key.starts_with(p.as_str()))
+    }
+}
+
+/// 创建插件实例使用的Store，限制内存和可执行的指令数
+pub fn new_store(
+    engine: &Engine,
+    session: Option<i32>,
+    grant: &PluginGrant,
+    handle: Option<Handle>,
+    fuel: u64,
+) -> Result<Store<HostState>, String> {
+    let mut store = Store::new(engine, HostState::new(session, grant, handle));
+    store.limiter(|state| &mut state.limits);
+    store.set_fuel(fuel).map_err(|e| e.to_string())?;
+    Ok(store)
+}
+
+fn memory(caller: &Caller<'_, HostState>) -> Option<Memory> {
+    caller.get_export("memory").and_then(Extern::into_memory)
+}
+
+/// 读取插件内存，先校验范围再分配缓冲区
+fn read_bytes(caller: &Caller<'_, HostState>, ptr: i32, len: i32) -> Option<Vec<u8>> {
+    let memory = memory(caller)?;
+    let (ptr, len) = (usize::try_from(ptr).ok()?, usize::try_from(len).ok()?);
+    if len > MAX_INPUT_BYTES || ptr.checked_add(len)? > memory.data_size(caller) {
+        return None;
+    }
+    let mut buf = vec![0u8; len];
+    memory.read(caller, ptr, &mut buf).ok()?;
+    Some(buf)
+}
+
+fn read_string(caller: &Caller<'_, HostState>, ptr: i32, len: i32) -> Option<String> {
+    read_bytes(caller, ptr, len).and_then(|b| String::from_utf8(b).ok())
+}
+
+/// 通过插件的 `alloc` 分配内存并写入数据，返回 `(地址 << 32) | 长度`
+fn write_result(caller: &mut Caller<'_, HostState>, bytes: &[u8]) -> i64 {
+    let alloc = match caller
+        .get_export("alloc")
+        .and_then(Extern::into_func)
+        .and_then(|f| f.typed::<i32, i32>(&*caller).ok())
+    {
+        Some(alloc) => alloc,
+        None => return FAILED as i64,
+    };
+    let Ok(ptr) = alloc.call(&mut *caller, bytes.len() as i32) else {
+        return FAILED as i64;
+    };
+    match memory(caller) {
+        Some(memory) if memory.write(&mut *caller, ptr as usize, bytes).is_ok() => {
+            ((ptr as u32 as i64) << 32) | bytes.len() as i64
+        }
+        _ => FAILED as i64,
+    }
+}
+
+/// 在执行命令的线程中同步调用连接
+fn block_on<T>(state: &HostState, f: impl std::future::Future<Output = Result<T, LogicError>>) -> Option<T> {
+    let handle = state.handle.as_ref()?;
+    match handle.block_on(f) {
+        Ok(v) => Some(v),
+        Err(e) => {
+            debug!("Plugin host call failed: {:?}", e);
+            None
+        }
+    }
+}
+
+/// 创建注册了宿主函数的Linker
+pub fn linker(engine: &Engine) -> Result<Linker<HostState>, String> {
+    let mut linker = <Linker<HostState>>::new(engine);
+    linker
+        .func_wrap(HOST_MODULE, "read_key", |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> i64 {
+            let Some(key) = read_string(&caller, ptr, len) else {
+                return FAILED as i64;
+            };
+            if !caller.data().is_granted(&key) {
+                return DENIED as i64;
+            }
+            let session = caller.data().session.unwrap();
+            let value = block_on(caller.data(), async move {
+                let mut connector = etcd::get_connector(&session)?;
+                connector.kv_get(key).await.map(|kv| kv.value)
+            });
+            match value {
+                Some(value) => write_result(&mut caller, &value),
+                None => FAILED as i64,
+            }
+        })
+        .map_err(|e| e.to_string())?;
+    linker
+        .func_wrap(HOST_MODULE, "list_keys", |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> i64 {
+            let Some(prefix) = read_string(&caller, ptr, len) else {
+                return FAILED as i64;
+            };
+            if !caller.data().is_granted(&prefix) {
+                return DENIED as i64;
+            }
+            let session = caller.data().session.unwrap();
+            let keys = block_on(caller.data(), async move {
+                let mut connector = etcd::get_connector(&session)?;
+                let (_, kvs) = connector.kv_get_prefix_values(prefix).await?;
+                Ok(kvs.into_iter().map(|kv| kv.key).collect::<Vec<String>>())
+            });
+            match keys.and_then(|keys| serde_json::to_vec(&keys).ok()) {
+                Some(json) => write_result(&mut caller, &json),
+                None => FAILED as i64,
+            }
+        })
+        .map_err(|e| e.to_string())?;
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "put_key",
+            |caller: Caller<'_, HostState>, key_ptr: i32, key_len: i32, value_ptr: i32, value_len: i32| -> i32 {
+                let (Some(key), Some(value)) = (
+                    read_string(&caller, key_ptr, key_len),
+                    read_bytes(&caller, value_ptr, value_len),
+                ) else {
+                    return FAILED;
+                };
+                let state = caller.data();
+                if !state.allow_write || !state.is_granted(&key) {
+                    return DENIED;
+                }
+                let session = state.session.unwrap();
+                if etcd::check_writable(&session).is_err() {
+                    return DENIED;
+                }
+                let result = block_on(state, async move {
+                    let mut connector = etcd::get_connector(&session)?;
+                    connector.kv_put(key, value, None).await?;
+                    Ok(())
+                });
+                if result.is_some() {
+                    0
+                } else {
+                    FAILED
+                }
+            },
+        )
+        .map_err(|e| e.to_string())?;
+    linker
+        .func_wrap(HOST_MODULE, "emit_panel", |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> i32 {
+            let panel = read_bytes(&caller, ptr, len).and_then(|b| serde_json::from_slice::<PluginPanel>(&b).ok());
+            let state = caller.data_mut();
+            match panel {
+                Some(_) if state.panels.len() >= MAX_PANELS => DENIED,
+                Some(panel) => {
+                    state.panels.push(panel);
+                    0
+                }
+                None => FAILED,
+            }
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(linker)
+}
+
+/// 在独立的实例中执行插件命令，插件需导出 `run(name_ptr, name_len, args_ptr, args_len) -> i32`，返回0表示成功
+fn run_command(
+    engine: &Engine,
+    module: &Module,
+    grant: &PluginGrant,
+    session: i32,
+    command: &str,
+    args: &[u8],
+    handle: Handle,
+    fuel: u64,
+) -> Result<Vec<PluginPanel>, String> {
+    let mut store = new_store(engine, Some(session), grant, Some(handle), fuel)?;
+    let instance = linker(engine)?
+        .instantiate(&mut store, module)
+        .and_then(|pre| pre.start(&mut store))
+        .map_err(|e| e.to_string())?;
+    let memory = instance.get_memory(&store, "memory").ok_or("Missing export: memory")?;
+    let alloc = instance
+        .get_typed_func::<i32, i32>(&store, "alloc")
+        .map_err(|e| e.to_string())?;
+    let run = instance
+        .get_typed_func::<(i32, i32, i32, i32), i32>(&store, "run")
+        .map_err(|_| String::from("The plugin does not provide commands"))?;
+
+    let mut write = |bytes: &[u8]| -> Result<i32, String> {
+        let ptr = alloc.call(&mut store, bytes.len() as i32).map_err(|e| e.to_string())?;
+        memory.write(&mut store, ptr as usize, bytes).map_err(|e| e.to_string())?;
+        Ok(ptr)
+    };
+    let name_ptr = write(command.as_bytes())?;
+    let args_ptr = write(args)?;
+
+    let code = run
+        .call(&mut store, (name_ptr, command.len() as i32, args_ptr, args.len() as i32))
+        .map_err(|e| e.to_string())?;
+    let panels = std::mem::take(&mut store.data_mut().panels);
+    if code != 0 {
+        return Err(format!("The command exited with code {code}"));
+    }
+    Ok(panels)
+}
+
+/// 执行插件提供的命令，插件只能访问用户授予的前缀下的key
+pub async fn execute(
+    session: i32,
+    plugin: String,
+    command: String,
+    args: serde_json::Value,
+) -> Result<PluginCommandResult, LogicError> {
+    etcd::get_connector(&session)?;
+    let (module, manifest, grant) = value_plugin::get_loaded(&plugin)
+        .ok_or(LogicError::ResourceNotExist("The plugin does not exist or is not loaded"))?;
+    if !manifest.commands.iter().any(|c| c.name == command) {
+        return Err(LogicError::IllegalArgument(format!("Unknown command: {command}")));
+    }
+    let args = serde_json::to_vec(&args)?;
+
+    info!("Run plugin command: {} {}", plugin, command);
+    let handle = Handle::current();
+    let result = tokio::task::spawn_blocking(move || {
+        run_command(
+            value_plugin::engine(),
+            &module,
+            &grant,
+            session,
+            &command,
+            &args,
+            handle,
+            value_plugin::FUEL_LIMIT,
+        )
+    })
+    .await
+    .map_err(|e| LogicError::MsgError(e.to_string()))?;
+
+    Ok(match result {
+        Ok(panels) => PluginCommandResult {
+            success: true,
+            panels,
+            error_msg: None,
+        },
+        Err(e) => PluginCommandResult {
+            success: false,
+            panels: vec![],
+            error_msg: Some(e),
+        },
+    })
+}
@@ -0,0 +1,207 @@
+use std::fmt::Write;
+
+use chrono::{Local, TimeZone};
+
+use crate::transport::report::ClusterReport;
+
+/// 报告格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Html,
+    Markdown,
+}
+
+impl ReportFormat {
+    /// 根据格式名或文件后缀解析，无法识别时使用 HTML
+    pub fn parse(format: Option<&str>, filepath: &str) -> Self {
+        let name = format
+            .map(|f| f.to_lowercase())
+            .unwrap_or_else(|| filepath.rsplit('.').next().unwrap_or("").to_lowercase());
+        match name.as_str() {
+            "md" | "markdown" => ReportFormat::Markdown,
+            _ => ReportFormat::Html,
+        }
+    }
+}
+
+pub fn render(report: &ClusterReport, format: ReportFormat) -> String {
+    match format {
+        ReportFormat::Html => render_html(report),
+        ReportFormat::Markdown => render_markdown(report),
+    }
+}
+
+fn format_time(millis: u64) -> String {
+    Local
+        .timestamp_millis_opt(millis as i64)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| millis.to_string())
+}
+
+fn format_size(bytes: i64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.2} {}", size, UNITS[unit])
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_md(s: &str) -> String {
+    s.replace('|', "\\|")
+}
+
+/// 报告的表格数据：标题、表头、行
+fn tables(report: &ClusterReport) -> Vec<(&'static str, Vec<&'static str>, Vec<Vec<String>>)> {
+    let cluster = &report.cluster;
+
+    let mut overview = vec![
+        vec![String::from("Cluster ID"), cluster.id.clone()],
+        vec![String::from("Revision"), cluster.revision.to_string()],
+    ];
+    match &cluster.status {
+        Some(status) => overview.extend([
+            vec![String::from("Version"), status.version.clone()],
+            vec![String::from("Leader"), status.leader.clone()],
+            vec![String::from("Raft Term"), status.raft_term.clone()],
+            vec![String::from("Raft Index"), status.raft_index.clone()],
+            vec![String::from("Errors"), status.errors.join(", ")],
+        ]),
+        None => overview.push(vec![
+            String::from("Status Error"),
+            cluster.status_error.clone().unwrap_or_default(),
+        ]),
+    }
+    if let Some(e) = &cluster.alarm_error {
+        overview.push(vec![String::from("Alarm Error"), e.clone()]);
+    }
+
+    let members = report
+        .member_status
+        .iter()
+        .map(|m| {
+            let (version, allocated, used) = match &m.status {
+                Some(s) => (
+                    s.version.clone(),
+                    format_size(s.db_size_allocated),
+                    format_size(s.db_size_used),
+                ),
+                None => (String::new(), String::new(), String::new()),
+            };
+            vec![
+                m.id.clone(),
+                m.name.clone(),
+                m.endpoint.clone().unwrap_or_default(),
+                version,
+                allocated,
+                used,
+                m.error_msg.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+
+    let alarms = report
+        .alarms
+        .iter()
+        .map(|a| {
+            let alarm = match a.alarm_type {
+                1 => "NOSPACE",
+                2 => "CORRUPT",
+                _ => "NONE",
+            };
+            vec![a.member_id.clone(), String::from(alarm)]
+        })
+        .collect();
+
+    let keyspace = &report.keyspace;
+    let mut keyspace_rows = vec![
+        vec![String::from("Total Keys"), keyspace.total_keys.to_string()],
+        vec![String::from("Compact Revision"), keyspace.compact_revision.to_string()],
+    ];
+    for p in &keyspace.top_prefixes {
+        keyspace_rows.push(vec![p.prefix.clone(), p.count.to_string()]);
+    }
+    if keyspace.sampled {
+        keyspace_rows.push(vec![String::from("Note"), String::from("Prefix counts are based on a sample of keys")]);
+    }
+
+    let auth = &report.auth;
+    let mut auth_rows: Vec<Vec<String>> = auth
+        .users
+        .iter()
+        .map(|u| vec![u.user.clone(), u.roles.join(", ")])
+        .collect();
+    auth_rows.push(vec![String::from("Roles"), auth.roles.join(", ")]);
+    if let Some(e) = &auth.error_msg {
+        auth_rows.push(vec![String::from("Error"), e.clone()]);
+    }
+
+    vec![
+        ("Overview", vec!["Item", "Value"], overview),
+        ("Members", vec!["ID", "Name", "Endpoint", "Version", "DB Size", "DB Size In Use", "Error"], members),
+        ("Alarms", vec!["Member ID", "Alarm"], alarms),
+        ("Keyspace", vec!["Item", "Keys"], keyspace_rows),
+        ("Auth", vec!["User", "Roles"], auth_rows),
+    ]
+}
+
+pub fn render_html(report: &ClusterReport) -> String {
+    let mut html = String::new();
+    let title = format!("Etcd Cluster Report - {}", report.cluster.id);
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+        <style>body{{font-family:sans-serif;margin:24px}}table{{border-collapse:collapse;margin-bottom:24px}}\
+        th,td{{border:1px solid #ccc;padding:4px 8px;text-align:left}}th{{background:#f3f3f3}}</style>\n\
+        </head>\n<body>\n<h1>{}</h1>\n<p>Generated at {}</p>\n",
+        escape_html(&title),
+        escape_html(&title),
+        format_time(report.generate_time)
+    );
+    for (name, headers, rows) in tables(report) {
+        let _ = write!(html, "<h2>{}</h2>\n<table>\n<tr>", name);
+        for h in headers {
+            let _ = write!(html, "<th>{}</th>", h);
+        }
+        html.push_str("</tr>\n");
+        for row in rows {
+            html.push_str("<tr>");
+            for cell in row {
+                let _ = write!(html, "<td>{}</td>", escape_html(&cell));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+pub fn render_markdown(report: &ClusterReport) -> String {
+    let mut md = String::new();
+    let _ = write!(
+        md,
+        "# Etcd Cluster Report - {}\n\nGenerated at {}\n\n",
+        report.cluster.id,
+        format_time(report.generate_time)
+    );
+    for (name, headers, rows) in tables(report) {
+        let _ = write!(md, "## {}\n\n| {} |\n|{}\n", name, headers.join(" | "), "---|".repeat(headers.len()));
+        for row in rows {
+            let cells: Vec<String> = row.iter().map(|c| escape_md(c)).collect();
+            let _ = writeln!(md, "| {} |", cells.join(" | "));
+        }
+        md.push('\n');
+    }
+    md
+}
@@ -0,0 +1,212 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::warn;
+
+use crate::api::settings::get_settings;
+use crate::error::LogicError;
+use crate::etcd;
+use crate::transport::connection::{CachedState, CachedValue};
+use crate::transport::kv::SerializableKeyValue;
+use crate::transport::maintenance::{HealthState, SerializableCluster};
+use crate::utils::{aes_util, file_util, md5};
+
+/// 最多缓存的key数量，超出的部分不保存
+const MAX_CACHED_KEYS: usize = 100_000;
+/// 每个连接最多缓存的值数量，超出时移除最早查看的
+pub const MAX_CACHED_VALUES: usize = 100;
+/// 超过此大小的值不缓存
+pub const MAX_CACHED_VALUE_SIZE: usize = 64 * 1024;
+/// 缓存更新后延迟写入文件，期间的多次更新合并为一次写入
+const WRITE_DEBOUNCE: Duration = Duration::from_secs(2);
+
+lazy_static! {
+    /// 以连接名为索引，已从文件加载的缓存
+    static ref STATES: DashMap<String, CachedState> = DashMap::new();
+    /// 等待写入文件的连接及写入时使用的密钥
+    static ref PENDING_WRITES: DashMap<String, String> = DashMap::new();
+}
+
+fn cache_path(connection: &str) -> PathBuf {
+    let mut path = file_util::get_state_cache_dir_path();
+    path.push(md5(connection));
+    path
+}
+
+/// 缓存文件与连接配置使用同一个密钥加密，密钥变更后旧缓存无法读取，视为没有缓存
+fn load(connection: &str, key: &str) -> Option<CachedState> {
+    let content = fs::read(cache_path(connection)).ok()?;
+    let data = match aes_util::decrypt_128(key.as_bytes(), content) {
+        Ok(data) => data,
+        Err(e) => {
+            warn!("Failed to decrypt cached state of {}: {}", connection, e);
+            return None;
+        }
+    };
+    serde_json::from_slice(&data)
+        .map_err(|e| warn!("Failed to read cached state of {}: {}", connection, e))
+        .ok()
+}
+
+fn store(state: &CachedState, key: &str) -> Result<(), LogicError> {
+    fs::create_dir_all(file_util::get_state_cache_dir_path())?;
+    let data = aes_util::encrypt_128(key.as_bytes(), serde_json::to_vec(state)?)?;
+    fs::write(cache_path(&state.connection), data)?;
+    Ok(())
+}
+
+/// 更新会话所属连接的缓存并写入文件，未保存的连接或关闭了离线缓存时不处理。
+/// 连接的地址或命名空间变化后丢弃旧的缓存
+async fn update(session: i32, f: impl FnOnce(&mut CachedState)) {
+    if etcd::get_connection_info_optional(&session).is_none() {
+        return;
+    }
+    let Some(connection) = etcd::get_connection_name(&session) else {
+        return;
+    };
+    let settings = match get_settings().await {
+        Ok(settings) if settings.offline_cache => settings,
+        _ => return,
+    };
+    let Ok(scope) = etcd::cluster_scope(&session) else {
+        return;
+    };
+    let key = settings.connection_conf_encrypt_key;
+    let mut state = STATES
+        .entry(connection.clone())
+        .or_insert_with(|| load(&connection, &key).unwrap_or_default());
+    if state.scope != scope || state.connection != connection {
+        *state = CachedState {
+            connection,
+            scope,
+            ..Default::default()
+        };
+    }
+    f(&mut state);
+    state.updated_at = etcd::now_timestamp() as u64;
+    let connection = state.connection.clone();
+    drop(state);
+    schedule_write(connection, key);
+}
+
+/// 延迟写入缓存文件，已有等待中的写入时只更新密钥。
+/// 缓存可能包含大量key，序列化、加密和写文件在阻塞线程中执行
+fn schedule_write(connection: String, key: String) {
+    if PENDING_WRITES.insert(connection.clone(), key).is_some() {
+        return;
+    }
+    tokio::spawn(async move {
+        tokio::time::sleep(WRITE_DEBOUNCE).await;
+        let result = tokio::task::spawn_blocking(move || {
+            //  等待期间缓存被清除时不再写入
+            let Some((_, key)) = PENDING_WRITES.remove(&connection) else {
+                return Ok(());
+            };
+            let Some(state) = STATES.get(&connection).map(|state| state.clone()) else {
+                return Ok(());
+            };
+            store(&state, &key).map_err(|e| format!("{}: {:?}", connection, e))
+        })
+        .await;
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("Failed to write cached state of {}", e),
+            Err(e) => warn!("Failed to write cached state: {}", e),
+        }
+    });
+}
+
+pub async fn cache_keys(session: i32, keys: &[SerializableKeyValue]) {
+    update(session, |state| {
+        state.keys = Some(keys.iter().take(MAX_CACHED_KEYS).cloned().collect());
+        state.keys_truncated = keys.len() > MAX_CACHED_KEYS;
+        state.keys_cached_at = Some(etcd::now_timestamp() as u64);
+    })
+    .await
+}
+
+pub async fn cache_cluster(session: i32, cluster: &SerializableCluster) {
+    update(session, |state| {
+        state.cluster = Some(cluster.clone());
+        state.cluster_cached_at = Some(etcd::now_timestamp() as u64);
+    })
+    .await
+}
+
+/// 只缓存正常的检查结果，作为最后一次已知的可用状态
+pub async fn cache_health(session: i32, health: &HealthState) {
+    if !health.healthy {
+        return;
+    }
+    update(session, |state| state.health = Some(health.clone())).await
+}
+
+/// 缓存查看的值，加密解密失败、过大或未随结果返回的值不缓存
+pub async fn cache_value(session: i32, kv: &SerializableKeyValue) {
+    if kv.decrypt_error.is_some() || kv.value_handle.is_some() || kv.value.len() > MAX_CACHED_VALUE_SIZE {
+        return;
+    }
+    let value = CachedValue {
+        kv: kv.clone(),
+        cached_at: etcd::now_timestamp() as u64,
+    };
+    update(session, |state| push_value(state, value)).await
+}
+
+/// 同一个key只保留最新的值，数量超出上限时移除最早查看的值
+pub fn push_value(state: &mut CachedState, value: CachedValue) {
+    state.values.retain(|v| v.kv.key != value.kv.key);
+    state.values.push(value);
+    if state.values.len() > MAX_CACHED_VALUES {
+        let overflow = state.values.len() - MAX_CACHED_VALUES;
+        state.values.drain(..overflow);
+    }
+}
+
+/// 读取连接的缓存，连接当前没有健康的会话时标记为过期
+pub async fn get(connection: &str) -> Result<Option<CachedState>, LogicError> {
+    let key = get_settings().await?.connection_conf_encrypt_key;
+    let state = match STATES.get(connection) {
+        Some(state) => Some(state.clone()),
+        None => load(connection, &key),
+    };
+    Ok(state.map(|mut state| {
+        state.stale = !etcd::list_session_health()
+            .iter()
+            .any(|(_, name, healthy)| name == connection && *healthy == Some(true));
+        state
+    }))
+}
+
+/// 删除连接的缓存，未指定连接时删除所有缓存，返回删除的缓存文件数
+pub fn clear(connection: Option<&str>) -> Result<usize, LogicError> {
+    match connection {
+        Some(connection) => {
+            PENDING_WRITES.remove(connection);
+            STATES.remove(connection);
+            let path = cache_path(connection);
+            if path.exists() {
+                fs::remove_file(path)?;
+                return Ok(1);
+            }
+            Ok(0)
+        }
+        None => {
+            PENDING_WRITES.clear();
+            STATES.clear();
+            let dir = file_util::get_state_cache_dir_path();
+            if !dir.exists() {
+                return Ok(0);
+            }
+            let mut removed = 0;
+            for entry in fs::read_dir(dir)? {
+                fs::remove_file(entry?.path())?;
+                removed += 1;
+            }
+            Ok(removed)
+        }
+    }
+}
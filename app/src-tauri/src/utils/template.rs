@@ -0,0 +1,89 @@
+use std::collections::BTreeMap;
+
+/// 变量名只能包含字母、数字、`_`、`.` 和 `-`，且不能以数字开头
+pub fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+/// 将 `${NAME}` 替换为变量值。紧挨 `{` 的连续 `$` 中每两个 `$` 表示一个 `$`，
+/// 例如 `$${` 原样输出 `${`，`$$${NAME}` 输出 `$` 及变量值；其他位置的 `$` 原样保留。
+///
+/// 存在未定义的变量时返回这些变量名，不做部分替换
+pub fn render(text: &str, variables: &BTreeMap<String, String>) -> Result<String, Vec<String>> {
+    let mut result = String::with_capacity(text.len());
+    let mut missing: Vec<String> = Vec::new();
+    let mut rest = text;
+    while let Some(pos) = rest.find("${") {
+        let dollars = rest[..=pos].len() - rest[..=pos].trim_end_matches('$').len();
+        result.push_str(&rest[..pos + 1 - dollars]);
+        result.push_str(&"$".repeat(dollars / 2));
+        if dollars % 2 == 0 {
+            result.push('{');
+            rest = &rest[pos + 2..];
+            continue;
+        }
+        let after = &rest[pos + 2..];
+        match after.find('}') {
+            Some(end) if is_valid_name(&after[..end]) => {
+                let name = &after[..end];
+                match variables.get(name) {
+                    Some(value) => result.push_str(value),
+                    None if !missing.iter().any(|m| m == name) => missing.push(name.to_string()),
+                    None => {}
+                }
+                rest = &after[end + 1..];
+            }
+            //  不是合法的变量引用，原样保留
+            _ => {
+                result.push_str("${");
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+    if missing.is_empty() {
+        Ok(result)
+    } else {
+        Err(missing)
+    }
+}
+
+/// 将文本中出现的变量值替换回 `${NAME}`，便于比较不同环境中的同一份配置。
+///
+/// 较长的值优先替换，空值不参与替换；紧挨 `{` 或变量引用的 `$` 会转义为 `$$`，使结果可以再次渲染
+pub fn templatize(text: &str, variables: &BTreeMap<String, String>) -> String {
+    let mut candidates: Vec<(&String, &String)> = variables.iter().filter(|(_, v)| !v.is_empty()).collect();
+    candidates.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(b.0)));
+
+    let mut result = String::with_capacity(text.len());
+    let mut i = 0;
+    'outer: while i < text.len() {
+        let rest = &text[i..];
+        for (name, value) in &candidates {
+            if rest.starts_with(value.as_str()) {
+                result.push_str("${");
+                result.push_str(name);
+                result.push('}');
+                i += value.len();
+                continue 'outer;
+            }
+        }
+        if rest.starts_with('$') {
+            let after = rest.trim_start_matches('$');
+            let dollars = rest.len() - after.len();
+            let escape = after.starts_with('{') || candidates.iter().any(|(_, value)| after.starts_with(value.as_str()));
+            result.push_str(&"$".repeat(if escape { dollars * 2 } else { dollars }));
+            i += dollars;
+            continue;
+        }
+        let c = rest.chars().next().unwrap();
+        result.push(c);
+        i += c.len_utf8();
+    }
+    result
+}
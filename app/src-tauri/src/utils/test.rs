@@ -1,5 +1,8 @@
 #![cfg(test)]
-use super::aes_util;
+use super::{aes_util, cert_util};
+use super::deep_link::DeepLink;
+use super::fuzzy::fuzzy_match;
+use super::template;
 
 const KEY: &'static str = "1234567890123!@#";
 
@@ -46,4 +49,709 @@ fn test_aes_json_content() {
     let decrypted = aes_util::decrypt_128(KEY.as_bytes(), encrypted).unwrap();
     let res = String::from_utf8(decrypted).unwrap();
     assert_eq!(content, res);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_aes_gcm() {
+    let key = aes_util::generate_key_256();
+    let content = "etcd-workbench secret";
+    let encrypted = aes_util::encrypt_gcm_256(&key, content.as_bytes()).unwrap();
+    let decrypted = aes_util::decrypt_gcm_256(&key, &encrypted).unwrap();
+    assert_eq!(content.as_bytes(), decrypted.as_slice());
+
+    let other_key = aes_util::generate_key_256();
+    assert!(aes_util::decrypt_gcm_256(&other_key, &encrypted).is_err());
+}
+
+const TEST_CERT: &'static str = "-----BEGIN CERTIFICATE-----
+MIIBnDCCAUGgAwIBAgIUOUL35kb4QF1zxBGSXlT4wdNfn8kwCgYIKoZIzj0EAwIw
+FDESMBAGA1UEAwwJZXRjZC10ZXN0MCAXDTI2MTAxNTA4MjU1NVoYDzIxMjYwOTIx
+MDgyNTU1WjAUMRIwEAYDVQQDDAlldGNkLXRlc3QwWTATBgcqhkjOPQIBBggqhkjO
+PQMBBwNCAAQzBA1PPv+CPtNhtP0pp/FpsLKRDWUS3NNVwO/Qjkey7fiQX6ZBEcaS
+VSroTPuKoXIYugpW1eQ19o6rKYqKzvZro28wbTAdBgNVHQ4EFgQU4qzvtcKUEXYs
+taxNLUZq3dvVtsAwHwYDVR0jBBgwFoAU4qzvtcKUEXYstaxNLUZq3dvVtsAwDwYD
+VR0TAQH/BAUwAwEB/zAaBgNVHREEEzARgglsb2NhbGhvc3SHBH8AAAEwCgYIKoZI
+zj0EAwIDSQAwRgIhAKG5EfGTJlZbx9ceKMRzKvpZPY10/BuGh5dPfPLCHGTOAiEA
+rL51fES8XpIMxnJhr3DncIeGA6QfPClmotWuvsVdYA0=
+-----END CERTIFICATE-----
+";
+
+#[test]
+fn test_parse_certificate() {
+    let certs = cert_util::parse_pem_certificates(TEST_CERT.as_bytes()).unwrap();
+    assert_eq!(certs.len(), 1);
+
+    let cert = &certs[0];
+    assert_eq!(cert.subject, "CN=etcd-test");
+    assert_eq!(cert.sans, vec!["DNS:localhost", "IP:127.0.0.1"]);
+    assert_eq!(cert.fingerprint_sha256, "CD:FF:24:8D:B1:6C:DA:6A:BB:D6:9B:6D:52:84:BF:FF:E7:4E:B3:7F:F9:48:7B:65:02:DB:C7:18:2B:DF:D4:5C");
+    assert!(!cert.expired);
+}
+#[test]
+fn test_parse_deep_link() {
+    let link = DeepLink::parse("etcd-workbench://connection/my%20cluster/key//app/config%2Fdb").unwrap();
+    assert_eq!(link.connection, "my cluster");
+    assert_eq!(link.key, Some(String::from("/app/config/db")));
+
+    let link = DeepLink::parse("etcd-workbench://connection/local").unwrap();
+    assert_eq!(link.connection, "local");
+    assert_eq!(link.key, None);
+
+    assert!(DeepLink::parse("etcd-workbench://connection/").is_err());
+    assert!(DeepLink::parse("etcd-workbench://foo/local").is_err());
+    assert!(DeepLink::parse("https://connection/local").is_err());
+}
+
+#[test]
+fn test_deep_link_revision() {
+    let link = DeepLink::parse("etcd-workbench://connection/prod/key//app/db?revision=42#top").unwrap();
+    assert_eq!(link.key, Some(String::from("/app/db")));
+    assert_eq!(link.revision, Some(42));
+
+    assert!(DeepLink::parse("etcd-workbench://connection/prod/key//app/db?revision=abc").is_err());
+    assert!(DeepLink::parse("etcd-workbench://connection/prod?revision=42").is_err());
+
+    let link = DeepLink {
+        connection: String::from("my cluster/1"),
+        key: Some(String::from("/app/config db")),
+        revision: Some(7),
+    };
+    let url = link.to_url();
+    assert_eq!(url, "etcd-workbench://connection/my%20cluster%2F1/key//app/config%20db?revision=7");
+    assert_eq!(DeepLink::parse(&url).unwrap(), link);
+}
+
+#[test]
+fn test_fuzzy_match() {
+    let (score, matched) = fuzzy_match("apcfg", "/app/config").unwrap();
+    assert_eq!(matched, vec![1, 2, 5, 8, 10]);
+    assert!(score > 0);
+
+    assert!(fuzzy_match("cfgx", "/app/config").is_none());
+    assert_eq!(fuzzy_match("", "/app/config"), Some((0, vec![])));
+
+    //  连续匹配、单词开头匹配的分数更高
+    let (better, _) = fuzzy_match("conf", "/app/config").unwrap();
+    let (worse, _) = fuzzy_match("conf", "/cxoxnxf").unwrap();
+    assert!(better > worse);
+}
+
+#[test]
+fn test_webhook_sign() {
+    use crate::etcd::alert_dispatcher::sign;
+    let signature = sign("secret", "1700000000", "{}");
+    assert_eq!(signature, "b8569b78799ff9e3cbff0fc2d63a33a2b57f3282abd07c37ae5e8e7d79a5f163");
+    assert_ne!(signature, sign("secret", "1700000001", "{}"));
+}
+
+#[test]
+fn test_server_feature_version() {
+    use crate::transport::maintenance::{parse_version, ServerFeature};
+    assert_eq!(parse_version("3.5.12"), Some((3, 5, 12)));
+    assert_eq!(parse_version("v3.4.0-rc.1"), Some((3, 4, 0)));
+    assert_eq!(parse_version("3.6"), Some((3, 6, 0)));
+    assert_eq!(parse_version("unknown"), None);
+
+    assert!(ServerFeature::Learner.supported_by("3.4.27"));
+    assert!(!ServerFeature::Learner.supported_by("3.3.25"));
+    assert!(ServerFeature::Learner.supported_by("3.5.0"));
+    assert!(ServerFeature::Learner.supported_by(""));
+}
+
+#[test]
+fn test_kv_import() {
+    use crate::transport::kv::PrefixMapping;
+    use crate::utils::kv_import::{map_key, parse_consul_export, parse_zookeeper_dump};
+
+    let consul = r#"[{"key":"app/db/url","flags":0,"value":"bXlzcWw6Ly9sb2NhbGhvc3Q="},{"key":"app/empty","flags":0,"value":""}]"#;
+    let kvs = parse_consul_export(consul).unwrap();
+    assert_eq!(kvs[0], (String::from("/app/db/url"), b"mysql://localhost".to_vec()));
+    assert_eq!(kvs[1].1, Vec::<u8>::new());
+
+    let zk = "# dump\n/app=\n/app/name=demo\n/app/desc=a\\nb\n";
+    let kvs = parse_zookeeper_dump(zk).unwrap();
+    assert_eq!(kvs.len(), 2);
+    assert_eq!(kvs[1], (String::from("/app/desc"), b"a\nb".to_vec()));
+    assert!(parse_zookeeper_dump("app=1").is_err());
+
+    let mappings = vec![
+        PrefixMapping { source: String::from("/app/"), target: String::from("/services/app/") },
+        PrefixMapping { source: String::from("/app/db/"), target: String::from("/db/") },
+    ];
+    assert_eq!(map_key("/app/db/url", &mappings), "/db/url");
+    assert_eq!(map_key("/app/name", &mappings), "/services/app/name");
+    assert_eq!(map_key("/other", &mappings), "/other");
+}
+
+#[test]
+fn test_diff_lines() {
+    use super::text_diff::{diff_lines, DiffKind};
+
+    let diff = diff_lines("a\nb\nc", "a\nc\nd");
+    let kinds: Vec<DiffKind> = diff.iter().map(|l| l.kind).collect();
+    assert_eq!(kinds, vec![DiffKind::Equal, DiffKind::Delete, DiffKind::Equal, DiffKind::Insert]);
+    assert_eq!(diff[1].text, "b");
+    assert_eq!(diff[1].old_line, Some(2));
+    assert_eq!(diff[3].new_line, Some(3));
+
+    assert!(diff_lines("x\ny", "x\ny").iter().all(|l| l.kind == DiffKind::Equal));
+}
+
+#[test]
+fn test_diff_bytes() {
+    use super::text_diff::{diff_bytes, is_binary, DiffKind};
+
+    let diff = diff_bytes(b"a\r\n\xff\xfe\nc\n", b"a\n\xff\xfe\nd");
+    let kinds: Vec<DiffKind> = diff.iter().map(|l| l.kind).collect();
+    assert_eq!(kinds, vec![DiffKind::Equal, DiffKind::Equal, DiffKind::Delete, DiffKind::Insert]);
+    assert_eq!(diff[1].text, "\u{FFFD}\u{FFFD}");
+
+    assert!(is_binary(b"abc\0def"));
+    assert!(!is_binary(b"\xff\xfe"));
+}
+
+#[test]
+fn test_conn_import() {
+    use super::conn_import::{parse_endpoint, parse_etcd_manager, parse_etcdkeeper, parse_kstone, Endpoint};
+
+    assert_eq!(
+        parse_endpoint("https://10.0.0.1:2380"),
+        Some(Endpoint { host: String::from("10.0.0.1"), port: 2380, tls: true })
+    );
+    assert_eq!(
+        parse_endpoint("127.0.0.1"),
+        Some(Endpoint { host: String::from("127.0.0.1"), port: 2379, tls: false })
+    );
+    assert_eq!(parse_endpoint("[::1]:2379").map(|e| e.host), Some(String::from("::1")));
+    assert_eq!(parse_endpoint("unix:///run/etcd.sock").map(|e| e.host), Some(String::from("unix:///run/etcd.sock")));
+
+    let list = parse_etcd_manager(r#"{"etcdConfig": {"endpoint": "http://127.0.0.1", "port": 12379, "username": "root", "password": "pwd"}}"#).unwrap();
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].connection.port, 12379);
+    assert_eq!(list[0].connection.user.as_ref().map(|u| u.username.as_str()), Some("root"));
+
+    let list = parse_etcdkeeper("127.0.0.1:2379,10.0.0.2:2379\n-usetls=true").unwrap();
+    assert_eq!(list.len(), 2);
+
+    let list = parse_kstone(
+        "apiVersion: kstone.tkestack.io/v1alpha2\nkind: EtcdCluster\nmetadata:\n  name: prod\n  annotations:\n    importedAddr: http://1.2.3.4:2379\n",
+    )
+    .unwrap();
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].name, "prod");
+    assert_eq!(list[0].connection.host, "1.2.3.4");
+}
+
+#[test]
+fn test_workspace_secrets() {
+    use super::conn_import::parse_etcd_manager;
+    use super::workspace::{restore, strip};
+    use crate::transport::connection::ConnectionInfo;
+    use crate::transport::settings::SettingConfig;
+
+    let external = parse_etcd_manager(r#"{"endpoint": "127.0.0.1", "username": "root", "password": "pwd"}"#).unwrap();
+    let mut connections: Vec<ConnectionInfo> = external
+        .into_iter()
+        .map(|e| ConnectionInfo {
+            name: e.name,
+            connection: e.connection,
+            key_collection: vec![String::from("/a")],
+            key_monitor_list: vec![],
+            notification_rules: vec![],
+            template: false,
+            annotations: vec![],
+            report_schedule: None,
+            maintenance_schedules: vec![],
+            variables: Default::default(),
+            poll_interval_seconds: Default::default(),
+            tracked_prefixes: vec![],
+        })
+        .collect();
+    let mut settings = SettingConfig::default();
+
+    let secrets = strip(&mut connections, &mut settings, Some("share")).unwrap().unwrap();
+    assert_eq!(connections[0].connection.user.as_ref().unwrap().password, "");
+    assert_eq!(connections[0].key_collection, vec![String::from("/a")]);
+    assert!(settings.connection_conf_encrypt_key.is_empty());
+
+    assert!(restore(&mut connections, &mut settings, &secrets, "wrong").is_err());
+    restore(&mut connections, &mut settings, &secrets, "share").unwrap();
+    assert_eq!(connections[0].connection.user.as_ref().unwrap().password, "pwd");
+
+    assert!(strip(&mut connections, &mut settings, None).unwrap().is_none());
+}
+
+#[test]
+fn test_cron_schedule() {
+    use chrono::{Local, TimeZone};
+    use super::cron::CronSchedule;
+
+    //  2024-05-05 是周日
+    let sunday = Local.with_ymd_and_hms(2024, 5, 5, 3, 30, 0).unwrap();
+    let cron = CronSchedule::parse("30 3 * * 0").unwrap();
+    assert!(cron.matches(&sunday));
+    assert_eq!(CronSchedule::parse("30 3 * * 7").unwrap(), cron);
+    assert!(!cron.matches(&Local.with_ymd_and_hms(2024, 5, 6, 3, 30, 0).unwrap()));
+
+    let every_15 = CronSchedule::parse("*/15 1-2 * * *").unwrap();
+    assert!(every_15.matches(&Local.with_ymd_and_hms(2024, 5, 5, 2, 45, 0).unwrap()));
+    assert!(!every_15.matches(&Local.with_ymd_and_hms(2024, 5, 5, 3, 0, 0).unwrap()));
+
+    //  日和周同时指定时满足其一即可
+    let either = CronSchedule::parse("0 0 1 * 1").unwrap();
+    assert!(either.matches(&Local.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap()));
+    assert!(either.matches(&Local.with_ymd_and_hms(2024, 5, 6, 0, 0, 0).unwrap()));
+
+    assert_eq!(
+        cron.next_after(&Local.with_ymd_and_hms(2024, 5, 5, 3, 30, 10).unwrap()),
+        Some(Local.with_ymd_and_hms(2024, 5, 12, 3, 30, 0).unwrap())
+    );
+    assert!(CronSchedule::parse("60 * * * *").is_err());
+    assert!(CronSchedule::parse("* * *").is_err());
+    assert!(CronSchedule::parse("*/0 * * * *").is_err());
+}
+
+#[test]
+fn test_template() {
+    let variables: std::collections::BTreeMap<String, String> = [("ENV", "prod"), ("HOST", "db.prod.local")]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    let text = "host=${HOST} env=${ENV} raw=$${ENV} bad=${1x}";
+    assert_eq!(
+        template::render(text, &variables).unwrap(),
+        "host=db.prod.local env=prod raw=${ENV} bad=${1x}"
+    );
+    assert_eq!(
+        template::render("${ENV}-${REGION}-${REGION}", &variables),
+        Err(vec![String::from("REGION")])
+    );
+
+    //  较长的值优先替换，原有的 `${` 转义后可再次渲染
+    let concrete = "host=db.prod.local env=prod raw=${ENV}";
+    let templated = template::templatize(concrete, &variables);
+    assert_eq!(templated, "host=${HOST} env=${ENV} raw=$${ENV}");
+    assert_eq!(template::render(&templated, &variables).unwrap(), concrete);
+
+    //  变量值前的 `$` 同样需要转义，其他位置的 `$` 原样保留
+    for concrete in ["$prod", "$${prod}", "cost=$5 $$", "$$${ENV}"] {
+        let templated = template::templatize(concrete, &variables);
+        assert_eq!(template::render(&templated, &variables).unwrap(), concrete);
+    }
+    assert_eq!(template::templatize("$prod", &variables), "$$${ENV}");
+    assert_eq!(template::render("$$${ENV} $${ENV} a$$b", &variables).unwrap(), "$prod ${ENV} a$$b");
+}
+
+#[test]
+fn test_conn_share() {
+    use super::conn_share::{decode, encode, SHARE_PREFIX};
+    use crate::transport::connection::{ConnectionSsh, ConnectionTls, ConnectionUser, SshIdentity};
+
+    let mut connection = super::conn_import::parse_etcdkeeper("10.0.0.1:2379").unwrap().remove(0).connection;
+    connection.namespace = Some(String::from("/app"));
+    connection.user = Some(ConnectionUser {
+        username: String::from("root"),
+        password: String::from("secret"),
+    });
+    connection.tls = Some(ConnectionTls {
+        domain: Some(String::from("etcd.local")),
+        cert: vec![b"CA".to_vec()],
+        identity: None,
+        pinned_fingerprint: None,
+    });
+    connection.ssh = Some(ConnectionSsh {
+        host: String::from("bastion"),
+        port: 22,
+        user: String::from("ops"),
+        identity: Some(SshIdentity {
+            password: Some(String::from("ssh-secret")),
+            key: None,
+        }),
+        compression: true,
+        ciphers: vec![String::from("aes256-gcm@openssh.com")],
+        kex: vec![],
+        macs: vec![],
+        use_config: false,
+    });
+
+    let shared = encode("prod", &connection).unwrap();
+    assert!(shared.starts_with(SHARE_PREFIX));
+    let decoded = decode(&shared).unwrap();
+    assert_eq!(decoded.name, "prod");
+    assert_eq!(decoded.connection.host, "10.0.0.1");
+    assert_eq!(decoded.connection.namespace.as_deref(), Some("/app"));
+    assert_eq!(decoded.connection.user.as_ref().unwrap().password, "");
+    assert!(decoded.connection.tls.as_ref().unwrap().cert.is_empty());
+    assert!(decoded.connection.ssh.as_ref().unwrap().identity.is_none());
+    assert!(decoded.connection.ssh.as_ref().unwrap().compression);
+    assert_eq!(decoded.connection.ssh.as_ref().unwrap().ciphers, vec!["aes256-gcm@openssh.com"]);
+    assert_eq!(decoded.warnings.len(), 3);
+    assert!(decode("ewb1:%%").is_err());
+
+    //  解压后过大的内容直接拒绝
+    use std::io::Write;
+    use base64::Engine;
+    let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder.write_all(&vec![b' '; 1024 * 1024]).unwrap();
+    let bomb = format!("{}{}", SHARE_PREFIX, base64::prelude::BASE64_URL_SAFE_NO_PAD.encode(encoder.finish().unwrap()));
+    assert_eq!(decode(&bomb).unwrap_err(), "Connection share string is too large");
+}
+
+#[test]
+fn test_endpoint_address() {
+    use crate::transport::connection::EndpointAddress;
+
+    let tcp = |host: &str, port: u16| EndpointAddress::Tcp { host: String::from(host), port };
+    assert_eq!(EndpointAddress::parse("127.0.0.1", 2379), Ok(tcp("127.0.0.1", 2379)));
+    assert_eq!(EndpointAddress::parse(" etcd-0.local ", 2379), Ok(tcp("etcd-0.local", 2379)));
+    assert_eq!(EndpointAddress::parse("[::1]", 2379), Ok(tcp("::1", 2379)));
+    assert_eq!(EndpointAddress::parse("fe80::1", 2379).unwrap().to_string(), "[fe80::1]:2379");
+    assert_eq!(
+        EndpointAddress::parse("unix:///run/etcd.sock", 0),
+        Ok(EndpointAddress::Unix(String::from("/run/etcd.sock")))
+    );
+    assert_eq!(
+        EndpointAddress::parse("unix:///run/etcd.sock", 0).unwrap().to_string(),
+        "unix:///run/etcd.sock"
+    );
+
+    assert!(EndpointAddress::parse("", 2379).is_err());
+    assert!(EndpointAddress::parse("unix://run/etcd.sock", 0).is_err());
+    assert!(EndpointAddress::parse("[::1", 2379).is_err());
+    assert!(EndpointAddress::parse("[::g]", 2379).is_err());
+    assert!(EndpointAddress::parse("127.0.0.1:2379", 2379).unwrap_err().contains("port"));
+    assert!(EndpointAddress::parse("etcd host", 2379).is_err());
+    assert!(EndpointAddress::parse("127.0.0.1", 0).is_err());
+}
+
+#[test]
+fn test_state_cache_values() {
+    use super::state_cache::{push_value, MAX_CACHED_VALUES};
+    use crate::transport::connection::{CachedState, CachedValue};
+    use crate::transport::kv::SerializableKeyValue;
+
+    let value = |key: &str, cached_at: u64| CachedValue {
+        kv: SerializableKeyValue {
+            key: String::from(key),
+            create_revision: 1,
+            mod_revision: 1,
+            version: 1,
+            value: cached_at.to_string().into_bytes(),
+            lease: String::from("0"),
+            lease_info: None,
+            formatted_value: None,
+            encrypted: false,
+            decrypt_error: None,
+            value_handle: None,
+        },
+        cached_at,
+    };
+
+    let mut state = CachedState::default();
+    push_value(&mut state, value("/a", 1));
+    push_value(&mut state, value("/b", 2));
+    push_value(&mut state, value("/a", 3));
+    let keys: Vec<&str> = state.values.iter().map(|v| v.kv.key.as_str()).collect();
+    assert_eq!(keys, vec!["/b", "/a"]);
+    assert_eq!(state.values[1].kv.value, b"3");
+
+    for i in 0..MAX_CACHED_VALUES {
+        push_value(&mut state, value(&format!("/k{}", i), 10 + i as u64));
+    }
+    assert_eq!(state.values.len(), MAX_CACHED_VALUES);
+    assert_eq!(state.values[0].kv.key, "/k0");
+
+    let json = serde_json::to_value(&state).unwrap();
+    assert_eq!(json["stale"], false);
+    assert!(json.get("keysCachedAt").is_some());
+}
+
+#[test]
+fn test_ssh_algorithm() {
+    use crate::ssh::ssh_algorithm::{preferred, supported};
+    use crate::transport::connection::ConnectionSsh;
+
+    let mut ssh = ConnectionSsh {
+        host: String::from("bastion"),
+        port: 22,
+        user: String::from("ops"),
+        identity: None,
+        compression: false,
+        ciphers: vec![],
+        kex: vec![],
+        macs: vec![],
+        use_config: false,
+    };
+    let default = preferred(&ssh).unwrap();
+    assert_eq!(default.compression.len(), 1);
+    assert_eq!(default.compression[0].as_ref(), "none");
+    assert!(default.kex.iter().any(|k| k.as_ref() == "diffie-hellman-group14-sha1"));
+
+    ssh.compression = true;
+    ssh.ciphers = vec![String::from("aes128-ctr"), String::from(" aes128-ctr ")];
+    ssh.kex = vec![String::from("ecdh-sha2-nistp256")];
+    let custom = preferred(&ssh).unwrap();
+    assert_eq!(custom.compression[0].as_ref(), "zlib@openssh.com");
+    assert_eq!(custom.cipher.iter().map(|c| c.as_ref()).collect::<Vec<_>>(), vec!["aes128-ctr"]);
+    assert_eq!(custom.kex[0].as_ref(), "ecdh-sha2-nistp256");
+    assert!(custom.kex.iter().any(|k| k.as_ref() == "kex-strict-c-v00@openssh.com"));
+
+    ssh.macs = vec![String::from("none")];
+    assert!(preferred(&ssh).is_err());
+    ssh.macs = vec![String::from("hmac-md5")];
+    assert!(preferred(&ssh).is_err());
+
+    let algorithms = supported();
+    assert!(algorithms.ciphers.contains(&String::from("chacha20-poly1305@openssh.com")));
+    assert!(!algorithms.ciphers.contains(&String::from("none")));
+}
+
+#[test]
+fn test_ssh_config() {
+    use crate::ssh::ssh_config::{parse_jump, resolve_hops, SshConfigFile};
+    use crate::transport::connection::ConnectionSsh;
+
+    let config = SshConfigFile::parse(
+        r#"
+User default
+# comment
+Host etcd-prod
+    HostName 10.0.1.5
+    Port 2222
+    IdentityFile ~/.ssh/prod_key
+    ProxyJump ops@bastion:2200
+Host bastion
+    HostName bastion.example.com
+    User ignored
+Host *.internal !secret.internal
+    Port=22022
+Host *
+    IdentityFile "~/.ssh/common key"
+"#,
+    );
+    assert_eq!(config.hosts(), vec!["bastion", "etcd-prod"]);
+
+    let prod = config.resolve("etcd-prod");
+    assert_eq!(prod.host_name.as_deref(), Some("10.0.1.5"));
+    assert_eq!(prod.port, Some(2222));
+    assert_eq!(prod.user.as_deref(), Some("default"));
+    assert_eq!(prod.identity_files, vec!["~/.ssh/prod_key", "~/.ssh/common key"]);
+    assert_eq!(config.resolve("a.internal").port, Some(22022));
+    assert_eq!(config.resolve("secret.internal").port, None);
+
+    assert_eq!(parse_jump("ops@bastion:2200").unwrap(), (Some(String::from("ops")), String::from("bastion"), Some(2200)));
+    assert_eq!(parse_jump("[::1]:22").unwrap(), (None, String::from("::1"), Some(22)));
+    assert!(parse_jump("bastion:ssh").is_err());
+
+    let ssh = ConnectionSsh {
+        host: String::from("etcd-prod"),
+        port: 22,
+        user: String::from("me"),
+        identity: None,
+        compression: false,
+        ciphers: vec![],
+        kex: vec![],
+        macs: vec![],
+        use_config: true,
+    };
+    let hops = resolve_hops(&ssh, &config).unwrap();
+    assert_eq!(hops.len(), 2);
+    assert_eq!(hops[0].ssh.host, "bastion.example.com");
+    assert_eq!(hops[0].ssh.port, 2200);
+    assert_eq!(hops[0].ssh.user, "ops");
+    assert_eq!(hops[1].ssh.host, "10.0.1.5");
+    assert_eq!(hops[1].ssh.port, 2222);
+    assert_eq!(hops[1].identity_files.len(), 2);
+
+    let hops = resolve_hops(&ConnectionSsh { use_config: false, ..ssh }, &config).unwrap();
+    assert_eq!(hops.len(), 1);
+    assert_eq!(hops[0].ssh.host, "etcd-prod");
+}
+
+#[test]
+fn test_format_convert() {
+    use crate::transport::kv::FormatLanguage;
+    use super::format_convert::{convert, find_lossy_number, find_lossy_syntax};
+
+    let json = r#"{"name":"web","replicas":3,"ports":[80,443],"enabled":true}"#;
+    let yaml = convert(json, &FormatLanguage::Json, &FormatLanguage::Yaml).unwrap();
+    assert_eq!(yaml, "name: web\nreplicas: 3\nports:\n- 80\n- 443\nenabled: true\n");
+    let back = convert(&yaml, &FormatLanguage::Yaml, &FormatLanguage::Json).unwrap();
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&back).unwrap(), serde_json::from_str::<serde_json::Value>(json).unwrap());
+    //  保持key的顺序
+    assert!(back.find("name").unwrap() < back.find("enabled").unwrap());
+
+    assert_eq!(find_lossy_syntax("a: 1 # note"), Some("comments"));
+    assert_eq!(find_lossy_syntax("base: &b\n  x: 1\nother: *b"), Some("anchors or aliases"));
+    assert_eq!(find_lossy_syntax("a: !!str 1"), Some("tags"));
+    assert_eq!(find_lossy_syntax("a: 1\n---\nb: 2"), Some("multiple documents"));
+    assert_eq!(find_lossy_syntax("---\na: 'x # y'\nb: \"it's #1\"\nc: a#b"), None);
+    assert_eq!(find_lossy_syntax("script: |\n  echo # not a comment\n  ls *\nnext: 1"), None);
+
+    assert!(convert("a: 1 # note", &FormatLanguage::Yaml, &FormatLanguage::Json).is_err());
+    //  json的key只能是字符串
+    assert!(convert("1: a", &FormatLanguage::Yaml, &FormatLanguage::Json).is_err());
+    assert!(convert("a: .nan", &FormatLanguage::Yaml, &FormatLanguage::Json).is_err());
+    assert!(convert(r#"{"a":1,"a":2}"#, &FormatLanguage::Json, &FormatLanguage::Yaml).is_err());
+    //  超出i64、u64或f64精度的数字
+    assert_eq!(find_lossy_number(r#"{"id":12345678901234567890123,"s":"99999999999999999999"}"#), Some("12345678901234567890123"));
+    assert_eq!(find_lossy_number(r#"[0.10000000000000001, 1]"#), Some("0.10000000000000001"));
+    assert_eq!(find_lossy_number(r#"[1.0, 1.50, -0.25, 1e2, 18446744073709551615, -9223372036854775808, "\"1e999"]"#), None);
+    assert!(convert(r#"{"id":12345678901234567890123}"#, &FormatLanguage::Json, &FormatLanguage::Yaml).is_err());
+    assert!(convert("x", &FormatLanguage::Text, &FormatLanguage::Json).is_err());
+}
+
+#[test]
+fn test_tunnel_diagnose() {
+    use crate::ssh::tunnel_stats::diagnose;
+    use crate::transport::connection::TunnelDirectionStats;
+
+    let healthy = TunnelDirectionStats {
+        bytes: 4 * 1024 * 1024,
+        chunks: 600,
+        small_chunks: 80,
+        peak_bps: 2 * 1024 * 1024,
+        ..Default::default()
+    };
+    assert!(diagnose(&TunnelDirectionStats::default(), &healthy).is_empty());
+
+    let fragmented = TunnelDirectionStats {
+        bytes: 1024 * 1024,
+        chunks: 5000,
+        small_chunks: 4800,
+        ..Default::default()
+    };
+    let hints = diagnose(&TunnelDirectionStats::default(), &fragmented);
+    assert_eq!(hints.len(), 1);
+    assert_eq!(hints[0].code, "TINY_WRITES");
+    assert!(hints[0].hint.contains("MTU"));
+    //  吞吐量正常时只是大量小请求
+    let busy = TunnelDirectionStats {
+        peak_bps: 2 * 1024 * 1024,
+        ..fragmented.clone()
+    };
+    assert!(diagnose(&TunnelDirectionStats::default(), &busy).is_empty());
+
+    let stalled = TunnelDirectionStats {
+        stalls: 3,
+        collapses: 1,
+        ..healthy.clone()
+    };
+    let upload = TunnelDirectionStats {
+        collapses: 1,
+        ..Default::default()
+    };
+    let codes: Vec<String> = diagnose(&upload, &stalled).into_iter().map(|h| h.code).collect();
+    assert_eq!(codes, vec!["STALLED_READS", "THROUGHPUT_COLLAPSE"]);
+}
+
+#[test]
+fn test_ssh_chain_key() {
+    use crate::ssh::session_pool::chain_key;
+    use crate::ssh::ssh_config::SshHop;
+    use crate::transport::connection::{ConnectionSsh, SshIdentity};
+
+    let hop = |host: &str, password: &str| SshHop {
+        ssh: ConnectionSsh {
+            host: String::from(host),
+            port: 22,
+            user: String::from("ops"),
+            identity: Some(SshIdentity {
+                password: Some(String::from(password)),
+                key: None,
+            }),
+            compression: false,
+            ciphers: vec![],
+            kex: vec![],
+            macs: vec![],
+            use_config: false,
+        },
+        identity_files: vec![],
+    };
+    let key = chain_key(&[hop("bastion", "a"), hop("target", "b")]);
+    assert_eq!(key, chain_key(&[hop("bastion", "a"), hop("target", "b")]));
+    assert_ne!(key, chain_key(&[hop("bastion", "a"), hop("target", "c")]));
+    assert_ne!(key, chain_key(&[hop("bastion", "c"), hop("target", "b")]));
+    assert_ne!(key, chain_key(&[hop("target", "b")]));
+}
+
+#[test]
+fn test_trash_store() {
+    use base64::prelude::BASE64_STANDARD;
+    use base64::Engine;
+    use crate::transport::kv::TrashEntry;
+    use crate::transport::settings::SettingConfig;
+    use crate::utils::aes_util;
+    use crate::utils::trash::{compact, enforce_limits, load, StoredEntry};
+
+    let dir = std::env::temp_dir().join("etcd-workbench-test-trash");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("trash.jsonl");
+
+    let stored = |id: &str, size: usize, deleted_at: u64| StoredEntry {
+        entry: TrashEntry {
+            id: String::from(id),
+            scope: String::from("127.0.0.1:2379"),
+            connection: None,
+            key: String::from(id),
+            size,
+            lease: 0,
+            deleted_at,
+        },
+        raw_key: BASE64_STANDARD.encode(id),
+        value: String::new(),
+        encrypted: false,
+    };
+    let add = |entry: &StoredEntry| {
+        let mut record = serde_json::to_value(entry).unwrap();
+        record["op"] = serde_json::Value::from("add");
+        record.to_string()
+    };
+    let remove = |id: &str| serde_json::json!({"op": "remove", "id": id}).to_string();
+
+    //  删除不存在的记录和损坏的行只计一条失效记录
+    let lines = [add(&stored("a", 1, 0)), add(&stored("b", 1, 0)), remove("a"), remove("missing"), String::from("{broken")];
+    std::fs::write(&path, lines.join("\n")).unwrap();
+    let mut state = load(path.clone());
+    assert_eq!(state.entries.len(), 1);
+    assert_eq!(state.entries[0].entry.id, "b");
+    assert_eq!(state.dead, 4);
+
+    //  失效记录达到阈值后只保留有效记录
+    state.dead = 100;
+    compact(&mut state);
+    assert_eq!(state.dead, 0);
+    assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+    let reloaded = load(path.clone());
+    assert_eq!(reloaded.entries.len(), 1);
+    assert_eq!(reloaded.dead, 0);
+
+    //  先移除过期记录，总大小超出时从最旧的开始移除
+    let now = crate::etcd::now_timestamp() as u64;
+    let day = 24 * 60 * 60 * 1000;
+    state.entries = vec![
+        stored("expired", 10, now - 3 * day),
+        stored("big1", 600 * 1024, now - 1000),
+        stored("big2", 600 * 1024, now),
+    ];
+    let mut settings = SettingConfig::default();
+    settings.trash_retention_days = 1;
+    settings.trash_max_size_mb = 1;
+    enforce_limits(&mut state, &settings);
+    let ids: Vec<&str> = state.entries.iter().map(|e| e.entry.id.as_str()).collect();
+    assert_eq!(ids, vec!["big2"]);
+    assert_eq!(state.dead, 4);
+
+    //  值使用连接配置的密钥加密，旧版本的记录未加密
+    let key = "0123456789abcdef";
+    let mut encrypted = stored("encrypted", 5, now);
+    encrypted.value = BASE64_STANDARD.encode(aes_util::encrypt_128(key.as_bytes(), "hello").unwrap());
+    encrypted.encrypted = true;
+    assert_eq!(encrypted.value(key).unwrap(), b"hello");
+    assert!(encrypted.value("fedcba9876543210").map(|v| v != b"hello").unwrap_or(true));
+    let mut plain = stored("plain", 5, now);
+    plain.value = BASE64_STANDARD.encode("hello");
+    assert_eq!(plain.value(key).unwrap(), b"hello");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+
+/// 超过此行数乘积时不计算逐行差异，直接视为整体替换
+const MAX_DIFF_CELLS: usize = 25_000_000;
+/// 判断是否为二进制内容时检查的字节数
+const BINARY_CHECK_BYTES: usize = 8000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all="camelCase")]
+pub enum DiffKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+/// 一行差异，行号从1开始，插入行没有旧行号，删除行没有新行号
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all="camelCase")]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub text: String,
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+}
+
+/// 开头部分包含 `\0` 时视为二进制内容，与git的判断方式一致
+pub fn is_binary(data: &[u8]) -> bool {
+    data[..data.len().min(BINARY_CHECK_BYTES)].contains(&0)
+}
+
+/// 基于最长公共子序列的逐行对比
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    diff_bytes(old.as_bytes(), new.as_bytes())
+}
+
+/// 按原始字节逐行对比，不要求内容为UTF-8，仅在输出行文本时做有损转换
+pub fn diff_bytes(old: &[u8], new: &[u8]) -> Vec<DiffLine> {
+    let a = split_lines(old);
+    let b = split_lines(new);
+    let (n, m) = (a.len(), b.len());
+
+    let mut result = Vec::with_capacity(n.max(m));
+    if n.saturating_mul(m) > MAX_DIFF_CELLS {
+        result.extend(a.iter().enumerate().map(|(i, line)| line_of(DiffKind::Delete, line, Some(i + 1), None)));
+        result.extend(b.iter().enumerate().map(|(j, line)| line_of(DiffKind::Insert, line, None, Some(j + 1))));
+        return result;
+    }
+
+    //  lcs[i][j] 为 a[i..] 与 b[j..] 的最长公共子序列长度
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && a[i] == b[j] {
+            result.push(line_of(DiffKind::Equal, a[i], Some(i + 1), Some(j + 1)));
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            result.push(line_of(DiffKind::Insert, b[j], None, Some(j + 1)));
+            j += 1;
+        } else {
+            result.push(line_of(DiffKind::Delete, a[i], Some(i + 1), None));
+            i += 1;
+        }
+    }
+    result
+}
+
+/// 以 `\n` 分行并去掉行尾的 `\r`，与 `str::lines` 的行为一致
+fn split_lines(data: &[u8]) -> Vec<&[u8]> {
+    if data.is_empty() {
+        return vec![];
+    }
+    let data = data.strip_suffix(b"\n").unwrap_or(data);
+    data.split(|b| *b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .collect()
+}
+
+fn line_of(kind: DiffKind, text: &[u8], old_line: Option<usize>, new_line: Option<usize>) -> DiffLine {
+    DiffLine {
+        kind,
+        text: String::from_utf8_lossy(text).to_string(),
+        old_line,
+        new_line,
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use lazy_static::lazy_static;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::LogicError;
+use crate::etcd;
+use crate::transport::kv::TrashEntry;
+use crate::transport::settings::SettingConfig;
+use crate::utils::{aes_util, file_util};
+
+/// 失效记录数达到此值且不少于有效记录数时压缩文件
+const COMPACT_MIN_DEAD: usize = 100;
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
+/// 回收站中的键值对，key和值以base64保存原始字节，值使用连接配置的加密密钥加密
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredEntry {
+    #[serde(flatten)]
+    pub entry: TrashEntry,
+    pub raw_key: String,
+    pub value: String,
+    /// 旧版本写入的记录没有加密
+    #[serde(default)]
+    pub encrypted: bool,
+}
+
+impl StoredEntry {
+    pub fn raw_key(&self) -> Vec<u8> {
+        BASE64_STANDARD.decode(&self.raw_key).unwrap_or_default()
+    }
+
+    /// 解密后的值，`key` 为连接配置的加密密钥
+    pub fn value(&self, key: &str) -> Result<Vec<u8>, LogicError> {
+        let data = BASE64_STANDARD.decode(&self.value).unwrap_or_default();
+        if !self.encrypted {
+            return Ok(data);
+        }
+        aes_util::decrypt_128(key.as_bytes(), data).map_err(|e| {
+            warn!("Failed to decrypt trash entry {}: {}", self.entry.id, e);
+            LogicError::MsgError(format!("Failed to decrypt the deleted value of {}", self.entry.key))
+        })
+    }
+}
+
+/// 回收站文件中的一行，文件只追加写入，删除记录在压缩时才会从文件中移除
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+enum TrashRecord {
+    Add(StoredEntry),
+    Remove { id: String },
+}
+
+pub struct TrashState {
+    path: PathBuf,
+    /// 按删除时间从旧到新排列
+    pub entries: Vec<StoredEntry>,
+    /// 文件中已失效的记录数
+    pub dead: usize,
+}
+
+lazy_static! {
+    static ref TRASH: Mutex<Option<TrashState>> = Mutex::new(None);
+}
+
+/// 读取回收站文件，重放添加和删除记录
+pub fn load(path: PathBuf) -> TrashState {
+    let mut state = TrashState {
+        path,
+        entries: Vec::new(),
+        dead: 0,
+    };
+    let Ok(content) = fs::read_to_string(&state.path) else {
+        return state;
+    };
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        match serde_json::from_str::<TrashRecord>(line) {
+            Ok(TrashRecord::Add(entry)) => state.entries.push(entry),
+            Ok(TrashRecord::Remove { id }) => {
+                let before = state.entries.len();
+                state.entries.retain(|e| e.entry.id != id);
+                //  删除记录本身，以及存在时被删除的添加记录
+                state.dead += 1 + (before - state.entries.len());
+            }
+            Err(e) => {
+                warn!("Skipped broken trash record: {e}");
+                state.dead += 1;
+            }
+        }
+    }
+    compact(&mut state);
+    state
+}
+
+fn append(state: &TrashState, records: &[TrashRecord]) {
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&state.path)
+        .and_then(|mut file| {
+            let mut content = Vec::new();
+            for record in records {
+                serde_json::to_writer(&mut content, record)?;
+                content.push(b'\n');
+            }
+            file.write_all(&content)
+        });
+    if let Err(e) = result {
+        warn!("Failed to write trash file: {e}");
+    }
+}
+
+/// 失效记录过多时只保留有效记录重写文件
+pub fn compact(state: &mut TrashState) {
+    if state.dead < COMPACT_MIN_DEAD || state.dead < state.entries.len() {
+        return;
+    }
+    rewrite(state);
+}
+
+fn rewrite(state: &mut TrashState) {
+    let tmp = state.path.with_extension("tmp");
+    let mut content = Vec::new();
+    for entry in &state.entries {
+        if serde_json::to_writer(&mut content, &TrashRecord::Add(entry.clone())).is_ok() {
+            content.push(b'\n');
+        }
+    }
+    match fs::write(&tmp, content).and_then(|_| fs::rename(&tmp, &state.path)) {
+        Ok(_) => state.dead = 0,
+        Err(e) => warn!("Failed to compact trash file: {e}"),
+    }
+}
+
+/// 移除过期记录，总大小超过上限时从最旧的记录开始移除
+pub fn enforce_limits(state: &mut TrashState, settings: &SettingConfig) {
+    let expire_before = (etcd::now_timestamp() as u64).saturating_sub(settings.trash_retention_days as u64 * DAY_MILLIS);
+    let max_size = settings.trash_max_size_mb as usize * 1024 * 1024;
+    let mut total: usize = state.entries.iter().map(|e| e.entry.size).sum();
+    let mut removed = Vec::new();
+    state.entries.retain(|e| {
+        if e.entry.deleted_at < expire_before || total > max_size {
+            total -= e.entry.size;
+            removed.push(TrashRecord::Remove { id: e.entry.id.clone() });
+            false
+        } else {
+            true
+        }
+    });
+    remove_records(state, removed);
+}
+
+fn remove_records(state: &mut TrashState, records: Vec<TrashRecord>) {
+    if records.is_empty() {
+        return;
+    }
+    append(state, &records);
+    state.dead += records.len() * 2;
+    compact(state);
+}
+
+fn with_state<T>(f: impl FnOnce(&mut TrashState) -> T) -> T {
+    let mut lock = TRASH.lock().unwrap();
+    f(lock.get_or_insert_with(|| load(file_util::get_trash_file_path())))
+}
+
+/// 将删除的键值对放入回收站，保留天数为0时不记录
+pub fn add(settings: &SettingConfig, scope: &str, connection: Option<String>, kvs: Vec<(Vec<u8>, Vec<u8>, i64)>) {
+    if settings.trash_retention_days == 0 || kvs.is_empty() {
+        return;
+    }
+    let deleted_at = etcd::now_timestamp() as u64;
+    let encrypt_key = settings.connection_conf_encrypt_key.as_bytes();
+    let entries: Vec<StoredEntry> = kvs
+        .into_iter()
+        .filter_map(|(key, value, lease)| {
+            let size = key.len() + value.len();
+            let value = match aes_util::encrypt_128(encrypt_key, value) {
+                Ok(value) => value,
+                Err(e) => {
+                    warn!("Failed to encrypt deleted value, skipped: {}", e);
+                    return None;
+                }
+            };
+            Some(StoredEntry {
+                entry: TrashEntry {
+                    id: Uuid::new_v4().simple().to_string(),
+                    scope: String::from(scope),
+                    connection: connection.clone(),
+                    key: String::from_utf8_lossy(&key).to_string(),
+                    size,
+                    lease,
+                    deleted_at,
+                },
+                raw_key: BASE64_STANDARD.encode(&key),
+                value: BASE64_STANDARD.encode(&value),
+                encrypted: true,
+            })
+        })
+        .collect();
+    with_state(|state| {
+        append(state, &entries.iter().cloned().map(TrashRecord::Add).collect::<Vec<_>>());
+        state.entries.extend(entries);
+        enforce_limits(state, settings);
+    });
+}
+
+/// 列出回收站中的记录，最近删除的在前，`scope` 为空时列出所有集群的记录
+pub fn list(settings: &SettingConfig, scope: Option<&str>) -> Vec<TrashEntry> {
+    with_state(|state| {
+        enforce_limits(state, settings);
+        state
+            .entries
+            .iter()
+            .rev()
+            .filter(|e| scope.map_or(true, |s| e.entry.scope == s))
+            .map(|e| e.entry.clone())
+            .collect()
+    })
+}
+
+pub fn get(ids: &[String]) -> Vec<StoredEntry> {
+    let ids: HashSet<&String> = ids.iter().collect();
+    with_state(|state| {
+        state
+            .entries
+            .iter()
+            .filter(|e| ids.contains(&e.entry.id))
+            .cloned()
+            .collect()
+    })
+}
+
+/// 从回收站移除记录，返回移除的数量
+pub fn remove(ids: &[String]) -> usize {
+    let ids: HashSet<&String> = ids.iter().collect();
+    with_state(|state| {
+        let mut removed = Vec::new();
+        state.entries.retain(|e| {
+            if ids.contains(&e.entry.id) {
+                removed.push(TrashRecord::Remove { id: e.entry.id.clone() });
+                false
+            } else {
+                true
+            }
+        });
+        let count = removed.len();
+        remove_records(state, removed);
+        count
+    })
+}
+
+/// 永久删除指定记录，`ids` 为空时清空回收站
+pub fn purge(ids: Option<&[String]>) -> usize {
+    match ids {
+        Some(ids) => remove(ids),
+        None => with_state(|state| {
+            let count = state.entries.len();
+            state.entries.clear();
+            state.dead = 0;
+            if let Err(e) = fs::write(&state.path, "") {
+                warn!("Failed to clear trash file: {e}");
+            }
+            count
+        }),
+    }
+}
+
+/// 连接配置的加密密钥变更后重新加密回收站中的值并重写文件，无法解密的记录被移除
+pub fn reencrypt(old_key: &[u8], new_key: &[u8]) {
+    with_state(|state| {
+        if state.entries.iter().all(|e| !e.encrypted) {
+            return;
+        }
+        state.entries.retain_mut(|stored| {
+            if !stored.encrypted {
+                return true;
+            }
+            let data = BASE64_STANDARD.decode(&stored.value).unwrap_or_default();
+            match aes_util::reencrypt_128(data, old_key, new_key) {
+                Ok(data) => {
+                    stored.value = BASE64_STANDARD.encode(data);
+                    true
+                }
+                Err(e) => {
+                    warn!("Failed to reencrypt trash entry {}, removed: {}", stored.entry.id, e);
+                    false
+                }
+            }
+        });
+        rewrite(state);
+    })
+}
@@ -0,0 +1,179 @@
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::Local;
+use lazy_static::lazy_static;
+use log::warn;
+use tauri::api::ipc::{format_callback, format_callback_result, CallbackFn};
+use tauri::{Invoke, InvokeResponse, Runtime, Window};
+
+use crate::api::settings::get_settings;
+use crate::etcd;
+use crate::transport::settings::UsageStats;
+use crate::utils::file_util;
+
+/// 保留每日统计的天数
+const MAX_DAILY_RECORDS: usize = 30;
+/// 统计数据写入文件的间隔，应用退出时也会写入
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 与tauri默认相同的IPC初始化脚本，自定义响应函数时必须一并指定
+pub const INVOKE_INITIALIZATION_SCRIPT: &str = "Object.defineProperty(window, '__TAURI_POST_MESSAGE__', { value: (message) => window.ipc.postMessage((function (message) { return JSON.stringify(message, (_k, val) => { if (val instanceof Map) { let o = {}; val.forEach((v, k) => o[k] = v); return o; } else { return val; } }) })(message)) })";
+
+/// 与设置中的 `telemetry_enabled` 同步，未开启时不记录也不写入统计数据
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+struct StatsState {
+    stats: UsageStats,
+    dirty: bool,
+}
+
+lazy_static! {
+    static ref USAGE_STATS: Mutex<Option<StatsState>> = Mutex::new(None);
+}
+
+fn load() -> UsageStats {
+    fs::read(file_util::get_usage_stats_file_path())
+        .ok()
+        .and_then(|content| serde_json::from_slice(&content).ok())
+        .unwrap_or_else(|| UsageStats {
+            since: etcd::now_timestamp() as u64,
+            ..UsageStats::default()
+        })
+}
+
+/// 设置加载或修改后调用
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+fn update(f: impl FnOnce(&mut UsageStats)) {
+    if !ENABLED.load(Ordering::SeqCst) {
+        return;
+    }
+    let mut lock = USAGE_STATS.lock().unwrap();
+    let state = lock.get_or_insert_with(|| StatsState {
+        stats: load(),
+        dirty: false,
+    });
+    f(&mut state.stats);
+    state.dirty = true;
+}
+
+fn today() -> String {
+    Local::now().format("%Y-%m-%d").to_string()
+}
+
+/// 只保留最近的每日统计
+fn trim_daily(stats: &mut UsageStats) {
+    while stats.daily.len() > MAX_DAILY_RECORDS {
+        stats.daily.pop_first();
+    }
+}
+
+/// 记录一次命令调用
+pub fn record_command(command: &str) {
+    update(|stats| {
+        stats.total_commands += 1;
+        *stats.commands.entry(String::from(command)).or_default() += 1;
+        stats.daily.entry(today()).or_default().commands += 1;
+        trim_daily(stats);
+    });
+}
+
+/// 记录一次返回给界面的错误，`kind` 为错误的 `errType`
+pub fn record_error(kind: &str) {
+    update(|stats| {
+        stats.total_errors += 1;
+        *stats.errors.entry(String::from(kind)).or_default() += 1;
+        stats.daily.entry(today()).or_default().errors += 1;
+        trim_daily(stats);
+    });
+}
+
+pub fn get() -> UsageStats {
+    let mut lock = USAGE_STATS.lock().unwrap();
+    let state = lock.get_or_insert_with(|| StatsState {
+        stats: load(),
+        dirty: false,
+    });
+    let mut stats = state.stats.clone();
+    if stats.total_commands > 0 {
+        stats.error_rate = stats.total_errors as f64 / stats.total_commands as f64;
+    }
+    stats
+}
+
+/// 清空统计数据，并重新开始计时
+pub fn reset() {
+    let mut lock = USAGE_STATS.lock().unwrap();
+    *lock = Some(StatsState {
+        stats: UsageStats {
+            since: etcd::now_timestamp() as u64,
+            ..UsageStats::default()
+        },
+        dirty: true,
+    });
+}
+
+/// 将有变化的统计数据写入文件
+pub fn flush() {
+    if !ENABLED.load(Ordering::SeqCst) {
+        return;
+    }
+    let content = {
+        let mut lock = USAGE_STATS.lock().unwrap();
+        match lock.as_mut() {
+            Some(state) if state.dirty => {
+                state.dirty = false;
+                serde_json::to_vec(&state.stats)
+            }
+            _ => return,
+        }
+    };
+    let result = content
+        .map_err(|e| e.to_string())
+        .and_then(|content| fs::write(file_util::get_usage_stats_file_path(), content).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        warn!("Failed to save usage stats: {e}");
+    }
+}
+
+/// 定时将统计数据写入文件
+pub fn start_flusher() {
+    tokio::spawn(async move {
+        match get_settings().await {
+            Ok(settings) => set_enabled(settings.telemetry_enabled),
+            Err(e) => warn!("Failed to read usage stats setting: {:?}", e),
+        }
+        loop {
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+            tokio::task::spawn_blocking(flush).await.ok();
+        }
+    });
+}
+
+/// 包装命令处理函数，在调用前记录命令名
+pub fn track<R: Runtime>(
+    handler: impl Fn(Invoke<R>) + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) + Send + Sync + 'static {
+    move |invoke| {
+        record_command(invoke.message.command());
+        handler(invoke)
+    }
+}
+
+/// 命令结果返回给界面时统计错误次数，其余与tauri默认的响应方式相同
+pub fn respond<R: Runtime>(window: Window<R>, response: InvokeResponse, success: CallbackFn, error: CallbackFn) {
+    let result = response.into_result();
+    if let Err(e) = &result {
+        record_error(e.get("errType").and_then(|t| t.as_str()).unwrap_or("Other"));
+    }
+    let callback = match format_callback_result(result, success, error) {
+        Ok(callback) => callback,
+        Err(e) => format_callback(error, &e.to_string()).expect("unable to serialize response string to json"),
+    };
+    let _ = window.eval(&callback);
+}
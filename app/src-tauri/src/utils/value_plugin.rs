@@ -0,0 +1,364 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use wasmi::{Config, Engine, Module};
+
+use crate::error::LogicError;
+use crate::transport::kv::{FormatSource, FormattedValue};
+use crate::transport::plugin::{PluginGrant, PluginInfo, PluginManifest};
+use crate::utils::{file_util, plugin_host};
+
+/// 应用支持的插件接口版本。插件以WASM实现，需要导出以下内容，只能导入 [`plugin_host::HOST_FUNCTIONS`] 中的宿主函数：
+///
+/// - `memory`: 线性内存
+/// - `alloc(len: i32) -> i32`: 分配指定长度的内存，返回地址
+/// - `decode(key_ptr: i32, key_len: i32, value_ptr: i32, value_len: i32) -> i64`: 可选，值解码。
+///   解码成功时返回 `(结果地址 << 32) | 结果长度`，结果为UTF-8文本；无法解码时返回0
+/// - `run(name_ptr: i32, name_len: i32, args_ptr: i32, args_len: i32) -> i32`: 可选，执行自定义命令
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+const MANIFEST_FILE: &'static str = "plugin.json";
+/// 记录被禁用插件的文件，位于插件目录下
+const DISABLED_FILE: &'static str = "disabled.json";
+/// 记录用户授予各插件权限的文件，位于插件目录下
+const GRANTS_FILE: &'static str = "grants.json";
+/// 单次解码可执行的指令数上限，防止插件死循环
+pub const FUEL_LIMIT: u64 = 200_000_000;
+/// 单次解码的最长等待时间，超时后放弃结果
+const DECODE_TIMEOUT: Duration = Duration::from_secs(2);
+/// 解码结果的长度上限
+const MAX_OUTPUT_BYTES: usize = 8 * 1024 * 1024;
+const REQUIRED_EXPORTS: [&'static str; 2] = ["memory", "alloc"];
+
+struct ValuePlugin {
+    manifest: PluginManifest,
+    grant: PluginGrant,
+    dir: PathBuf,
+    enabled: bool,
+    module: Option<Module>,
+    error_msg: Option<String>,
+}
+
+impl ValuePlugin {
+    fn matches(&self, key: &str) -> bool {
+        self.manifest.key_prefixes.is_empty() || self.manifest.key_prefixes.iter().any(|p| key.starts_with(p.as_str()))
+    }
+
+    fn to_info(&self) -> PluginInfo {
+        PluginInfo {
+            name: self.manifest.name.clone(),
+            version: self.manifest.version.clone(),
+            description: self.manifest.description.clone(),
+            abi_version: self.manifest.abi_version,
+            key_prefixes: self.manifest.key_prefixes.clone(),
+            language: self.manifest.language.clone(),
+            path: self.dir.display().to_string(),
+            enabled: self.enabled,
+            loaded: self.module.is_some(),
+            error_msg: self.error_msg.clone(),
+            commands: self.manifest.commands.clone(),
+            requested_prefixes: self.manifest.granted_prefixes.clone(),
+            requested_write: self.manifest.allow_write,
+            granted_prefixes: self.grant.prefixes.clone(),
+            allow_write: self.grant.allow_write,
+        }
+    }
+}
+
+lazy_static! {
+    static ref ENGINE: Engine = {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        Engine::new(&config)
+    };
+    static ref PLUGINS: RwLock<Vec<ValuePlugin>> = RwLock::new(vec![]);
+}
+
+fn read_disabled() -> HashSet<String> {
+    let path = file_util::get_plugin_dir_path().join(DISABLED_FILE);
+    fs::read(path)
+        .ok()
+        .and_then(|content| serde_json::from_slice(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_disabled(disabled: &HashSet<String>) -> Result<(), LogicError> {
+    let dir = file_util::get_plugin_dir_path();
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(DISABLED_FILE), serde_json::to_vec(disabled)?)?;
+    Ok(())
+}
+
+fn read_grants() -> HashMap<String, PluginGrant> {
+    let path = file_util::get_plugin_dir_path().join(GRANTS_FILE);
+    fs::read(path)
+        .ok()
+        .and_then(|content| serde_json::from_slice(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_grants(grants: &HashMap<String, PluginGrant>) -> Result<(), LogicError> {
+    let dir = file_util::get_plugin_dir_path();
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(GRANTS_FILE), serde_json::to_vec(grants)?)?;
+    Ok(())
+}
+
+/// 只保留插件申请过的权限，授予的前缀必须位于申请的前缀之下
+fn restrict_grant(manifest: &PluginManifest, grant: PluginGrant) -> PluginGrant {
+    PluginGrant {
+        prefixes: grant
+            .prefixes
+            .into_iter()
+            .filter(|p| manifest.granted_prefixes.iter().any(|r| p.starts_with(r.as_str())))
+            .collect(),
+        allow_write: grant.allow_write && manifest.allow_write,
+    }
+}
+
+fn read_manifest(dir: &Path) -> Result<PluginManifest, String> {
+    let content = fs::read(dir.join(MANIFEST_FILE)).map_err(|e| format!("Failed to read {MANIFEST_FILE}: {e}"))?;
+    serde_json::from_slice(&content).map_err(|e| format!("Invalid {MANIFEST_FILE}: {e}"))
+}
+
+/// 入口文件只能是插件目录下的文件名，不能包含路径
+fn is_valid_entry(entry: &str) -> bool {
+    !entry.is_empty() && entry != "." && !entry.contains("..") && !entry.contains(['/', '\\', ':'])
+}
+
+/// 编译插件并校验接口版本和导出项
+fn compile(manifest: &PluginManifest, dir: &Path) -> Result<Module, String> {
+    if !is_valid_entry(&manifest.entry) {
+        return Err(format!("Invalid plugin entry: {}", manifest.entry));
+    }
+    if manifest.abi_version != PLUGIN_ABI_VERSION {
+        return Err(format!(
+            "Unsupported plugin abi version {}, expected {}",
+            manifest.abi_version, PLUGIN_ABI_VERSION
+        ));
+    }
+    let wasm = fs::read(dir.join(&manifest.entry)).map_err(|e| format!("Failed to read {}: {e}", manifest.entry))?;
+    let module = Module::new(&ENGINE, &wasm[..]).map_err(|e| format!("Invalid wasm module: {e}"))?;
+    for import in module.imports() {
+        if import.module() != plugin_host::HOST_MODULE || !plugin_host::HOST_FUNCTIONS.contains(&import.name()) {
+            return Err(format!("Unsupported import: {}::{}", import.module(), import.name()));
+        }
+    }
+    for name in REQUIRED_EXPORTS {
+        if module.get_export(name).is_none() {
+            return Err(format!("Missing export: {name}"));
+        }
+    }
+    if module.get_export("decode").is_none() && module.get_export("run").is_none() {
+        return Err(String::from("The plugin exports neither decode nor run"));
+    }
+    Ok(module)
+}
+
+fn load(dir: PathBuf, disabled: &HashSet<String>, grants: &HashMap<String, PluginGrant>) -> Option<ValuePlugin> {
+    let manifest = match read_manifest(&dir) {
+        Ok(m) => m,
+        Err(e) => {
+            warn!("Skip plugin {}: {}", dir.display(), e);
+            return None;
+        }
+    };
+    let enabled = !disabled.contains(&manifest.name);
+    let grant = restrict_grant(&manifest, grants.get(&manifest.name).cloned().unwrap_or_default());
+    let (module, error_msg) = if enabled {
+        match compile(&manifest, &dir) {
+            Ok(module) => (Some(module), None),
+            Err(e) => {
+                warn!("Failed to load plugin {}: {}", manifest.name, e);
+                (None, Some(e))
+            }
+        }
+    } else {
+        (None, None)
+    };
+    Some(ValuePlugin {
+        manifest,
+        grant,
+        dir,
+        enabled,
+        module,
+        error_msg,
+    })
+}
+
+/// 重新扫描插件目录并加载所有插件
+pub fn load_all() -> Vec<PluginInfo> {
+    let disabled = read_disabled();
+    let grants = read_grants();
+    let mut plugins = Vec::new();
+    if let Ok(entries) = fs::read_dir(file_util::get_plugin_dir_path()) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                if let Some(plugin) = load(path, &disabled, &grants) {
+                    plugins.push(plugin);
+                }
+            }
+        }
+    }
+    plugins.sort_by(|a, b| a.manifest.name.cmp(&b.manifest.name));
+    info!("Loaded {} value plugins", plugins.len());
+
+    let infos = plugins.iter().map(ValuePlugin::to_info).collect();
+    *PLUGINS.write().unwrap() = plugins;
+    infos
+}
+
+pub fn list() -> Vec<PluginInfo> {
+    PLUGINS.read().unwrap().iter().map(ValuePlugin::to_info).collect()
+}
+
+/// 从目录安装插件，复制到插件目录后重新加载，同名插件会被覆盖。
+/// 插件只能获得 `grant` 中用户授予的key访问权限
+pub fn install(source: &Path, grant: PluginGrant) -> Result<PluginInfo, LogicError> {
+    let manifest = read_manifest(source).map_err(LogicError::IllegalArgument)?;
+    compile(&manifest, source).map_err(LogicError::IllegalArgument)?;
+    if manifest.name.is_empty() || manifest.name.contains(['/', '\\', '.']) {
+        return Err(LogicError::IllegalArgument(String::from("Invalid plugin name")));
+    }
+
+    let target = file_util::get_plugin_dir_path().join(&manifest.name);
+    if target.exists() {
+        fs::remove_dir_all(&target)?;
+    }
+    fs::create_dir_all(&target)?;
+    fs::copy(source.join(MANIFEST_FILE), target.join(MANIFEST_FILE))?;
+    fs::copy(source.join(&manifest.entry), target.join(&manifest.entry))?;
+
+    let mut grants = read_grants();
+    grants.insert(manifest.name.clone(), restrict_grant(&manifest, grant));
+    write_grants(&grants)?;
+
+    load_all()
+        .into_iter()
+        .find(|p| p.name == manifest.name)
+        .ok_or(LogicError::ResourceNotExist("The plugin was not loaded"))
+}
+
+pub fn remove(name: &str) -> Result<(), LogicError> {
+    let dir = PLUGINS
+        .read()
+        .unwrap()
+        .iter()
+        .find(|p| p.manifest.name == name)
+        .map(|p| p.dir.clone())
+        .ok_or(LogicError::ResourceNotExist("The plugin does not exist"))?;
+    fs::remove_dir_all(dir)?;
+    let mut grants = read_grants();
+    if grants.remove(name).is_some() {
+        write_grants(&grants)?;
+    }
+    load_all();
+    Ok(())
+}
+
+pub fn set_enabled(name: &str, enabled: bool) -> Result<Vec<PluginInfo>, LogicError> {
+    let mut disabled = read_disabled();
+    if enabled {
+        disabled.remove(name);
+    } else {
+        disabled.insert(String::from(name));
+    }
+    write_disabled(&disabled)?;
+    Ok(load_all())
+}
+
+pub fn engine() -> &'static Engine {
+    &ENGINE
+}
+
+/// 获取已加载的插件及用户授予的权限
+pub fn get_loaded(name: &str) -> Option<(Module, PluginManifest, PluginGrant)> {
+    PLUGINS
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|p| p.enabled && p.manifest.name == name)
+        .find_map(|p| p.module.clone().map(|m| (m, p.manifest.clone(), p.grant.clone())))
+}
+
+/// 在独立的实例中执行插件解码，每次调用互不影响。解码时插件不能访问任何key
+fn run(module: &Module, key: &[u8], value: &[u8]) -> Result<Option<String>, String> {
+    let mut store = plugin_host::new_store(&ENGINE, None, &PluginGrant::default(), None, FUEL_LIMIT)?;
+    let instance = plugin_host::linker(&ENGINE)?
+        .instantiate(&mut store, module)
+        .and_then(|pre| pre.start(&mut store))
+        .map_err(|e| e.to_string())?;
+    let memory = instance.get_memory(&store, "memory").ok_or("Missing export: memory")?;
+    let alloc = instance
+        .get_typed_func::<i32, i32>(&store, "alloc")
+        .map_err(|e| e.to_string())?;
+    let decode = instance
+        .get_typed_func::<(i32, i32, i32, i32), i64>(&store, "decode")
+        .map_err(|e| e.to_string())?;
+
+    let mut write = |bytes: &[u8]| -> Result<i32, String> {
+        let ptr = alloc.call(&mut store, bytes.len() as i32).map_err(|e| e.to_string())?;
+        memory.write(&mut store, ptr as usize, bytes).map_err(|e| e.to_string())?;
+        Ok(ptr)
+    };
+    let key_ptr = write(key)?;
+    let value_ptr = write(value)?;
+
+    let packed = decode
+        .call(&mut store, (key_ptr, key.len() as i32, value_ptr, value.len() as i32))
+        .map_err(|e| e.to_string())? as u64;
+    if packed == 0 {
+        return Ok(None);
+    }
+    let ptr = (packed >> 32) as usize;
+    let len = (packed & 0xFFFF_FFFF) as usize;
+    if len > MAX_OUTPUT_BYTES {
+        return Err(format!("The output exceeds {MAX_OUTPUT_BYTES} bytes"));
+    }
+    let mut output = vec![0u8; len];
+    memory.read(&store, ptr, &mut output).map_err(|e| e.to_string())?;
+    String::from_utf8(output).map(Some).map_err(|e| e.to_string())
+}
+
+/// 依次尝试匹配key的插件，返回第一个解码成功的结果。插件在阻塞线程中执行，受指令数和时间限制
+pub async fn try_format(key: String, value: Vec<u8>) -> Option<FormattedValue> {
+    let task = tokio::task::spawn_blocking(move || try_format_blocking(&key, &value));
+    match tokio::time::timeout(DECODE_TIMEOUT, task).await {
+        Ok(Ok(formatted)) => formatted,
+        Ok(Err(e)) => {
+            warn!("Value plugin task failed: {e}");
+            None
+        }
+        Err(_) => {
+            warn!("Value plugin decode timed out after {:?}", DECODE_TIMEOUT);
+            None
+        }
+    }
+}
+
+fn try_format_blocking(key: &str, value: &[u8]) -> Option<FormattedValue> {
+    let plugins = PLUGINS.read().unwrap();
+    for plugin in plugins.iter().filter(|p| p.enabled && p.matches(key)) {
+        let Some(module) = plugin.module.as_ref().filter(|m| m.get_export("decode").is_some()) else {
+            continue;
+        };
+        match run(module, key.as_bytes(), value) {
+            Ok(Some(output)) => {
+                return Some(FormattedValue {
+                    source: FormatSource::Plugin,
+                    language: plugin.manifest.language.clone(),
+                    value: output,
+                    plugin: Some(plugin.manifest.name.clone()),
+                });
+            }
+            Ok(None) => {}
+            Err(e) => debug!("Plugin {} failed to decode {}: {}", plugin.manifest.name, key, e),
+        }
+    }
+    None
+}
@@ -0,0 +1,76 @@
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::debug;
+use tauri::http::{Request, Response, ResponseBuilder};
+use tauri::{AppHandle, Runtime};
+use uuid::Uuid;
+
+use crate::transport::kv::{SerializableKeyValue, ValueHandle};
+
+/// 传输大值使用的自定义协议
+pub const PROTOCOL: &'static str = "etcdvalue";
+/// 值的长度达到此值时不通过命令返回，改为由界面通过自定义协议以二进制读取
+pub const LARGE_VALUE_THRESHOLD: usize = 1024 * 1024;
+/// 暂存的值在此时间内未被读取将被丢弃
+const HANDLE_TTL: Duration = Duration::from_secs(60);
+
+lazy_static! {
+    static ref PENDING_VALUES: DashMap<String, (Instant, Vec<u8>)> = DashMap::new();
+}
+
+/// 值超过阈值时移入暂存区，`value` 置空并设置 `value_handle`
+pub fn offload(kv: &mut SerializableKeyValue) {
+    if kv.value.len() < LARGE_VALUE_THRESHOLD {
+        return;
+    }
+    PENDING_VALUES.retain(|_, (time, _)| time.elapsed() < HANDLE_TTL);
+
+    let token = Uuid::new_v4().simple().to_string();
+    let size = kv.value.len();
+    PENDING_VALUES.insert(token.clone(), (Instant::now(), std::mem::take(&mut kv.value)));
+    kv.value_handle = Some(ValueHandle {
+        url: url(&token),
+        token,
+        size,
+    });
+}
+
+/// 取出暂存的值，每个值只能读取一次
+pub fn take(token: &str) -> Option<Vec<u8>> {
+    PENDING_VALUES
+        .remove(token)
+        .filter(|(_, (time, _))| time.elapsed() < HANDLE_TTL)
+        .map(|(_, (_, value))| value)
+}
+
+/// Windows 下自定义协议需通过 `https://<协议>.localhost/` 访问
+fn url(token: &str) -> String {
+    if cfg!(windows) {
+        format!("https://{PROTOCOL}.localhost/{token}")
+    } else {
+        format!("{PROTOCOL}://localhost/{token}")
+    }
+}
+
+/// 自定义协议的处理函数，路径为值的token，返回原始二进制数据
+pub fn handle_request<R: Runtime>(_app: &AppHandle<R>, request: &Request) -> Result<Response, Box<dyn Error>> {
+    let token = request
+        .uri()
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .rsplit('/')
+        .next()
+        .unwrap_or_default();
+    let builder = ResponseBuilder::new().header("Access-Control-Allow-Origin", "*");
+    match take(token) {
+        Some(value) => {
+            debug!("Transfer large value: {} bytes", value.len());
+            builder.mimetype("application/octet-stream").status(200).body(value)
+        }
+        None => builder.status(404).body(Vec::new()),
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::transport::connection::{Connection, ConnectionInfo, SshPrivateKey};
+use crate::transport::settings::{SettingConfig, WorkspaceSecrets};
+use crate::utils::aes_util;
+
+/// 工作区导出文件的格式版本
+pub const WORKSPACE_VERSION: u32 = 1;
+const SALT_LENGTH: usize = 16;
+
+/// 单个连接中的敏感信息
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all="camelCase")]
+pub struct ConnectionSecrets {
+    pub user_password: Option<String>,
+    pub tls_key: Option<Vec<u8>>,
+    pub tls_passphrase: Option<String>,
+    pub tls_pkcs12: Option<Vec<u8>>,
+    pub ssh_password: Option<String>,
+    pub ssh_key: Option<SshPrivateKey>,
+}
+
+impl ConnectionSecrets {
+    fn is_empty(&self) -> bool {
+        self.user_password.is_none()
+            && self.tls_key.is_none()
+            && self.tls_passphrase.is_none()
+            && self.tls_pkcs12.is_none()
+            && self.ssh_password.is_none()
+            && self.ssh_key.is_none()
+    }
+}
+
+/// 共享密码加密的内容，连接按连接名、webhook按名称索引
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct SecretPayload {
+    connections: HashMap<String, ConnectionSecrets>,
+    webhooks: HashMap<String, String>,
+}
+
+/// 从连接配置中取出敏感信息，原配置中对应的项被清空。客户端证书保留，私钥需要重新配置或通过共享密码恢复
+pub fn take_secrets(connection: &mut Connection) -> ConnectionSecrets {
+    let mut secrets = ConnectionSecrets::default();
+    if let Some(user) = &mut connection.user {
+        secrets.user_password = Some(std::mem::take(&mut user.password)).filter(|p| !p.is_empty());
+    }
+    if let Some(identity) = connection.tls.as_mut().and_then(|tls| tls.identity.as_mut()) {
+        secrets.tls_key = Some(std::mem::take(&mut identity.key)).filter(|k| !k.is_empty());
+        secrets.tls_passphrase = identity.passphrase.take();
+        secrets.tls_pkcs12 = identity.pkcs12.take();
+    }
+    if let Some(identity) = connection.ssh.as_mut().and_then(|ssh| ssh.identity.as_mut()) {
+        secrets.ssh_password = identity.password.take();
+        secrets.ssh_key = identity.key.take();
+    }
+    secrets
+}
+
+/// 将 [`take_secrets`] 取出的敏感信息写回连接配置
+pub fn restore_secrets(connection: &mut Connection, secrets: ConnectionSecrets) {
+    if let (Some(user), Some(password)) = (&mut connection.user, secrets.user_password) {
+        user.password = password;
+    }
+    if let Some(identity) = connection.tls.as_mut().and_then(|tls| tls.identity.as_mut()) {
+        if let Some(key) = secrets.tls_key {
+            identity.key = key;
+        }
+        identity.passphrase = secrets.tls_passphrase.or(identity.passphrase.take());
+        identity.pkcs12 = secrets.tls_pkcs12.or(identity.pkcs12.take());
+    }
+    if let Some(identity) = connection.ssh.as_mut().and_then(|ssh| ssh.identity.as_mut()) {
+        identity.password = secrets.ssh_password.or(identity.password.take());
+        identity.key = secrets.ssh_key.or(identity.key.take());
+    }
+}
+
+/// 清除连接和设置中的敏感信息。设置了共享密码时，敏感信息使用由该密码派生的密钥加密后返回
+pub fn strip(
+    connections: &mut [ConnectionInfo],
+    settings: &mut SettingConfig,
+    share_password: Option<&str>,
+) -> Result<Option<WorkspaceSecrets>, String> {
+    let mut payload = SecretPayload::default();
+    for info in connections.iter_mut() {
+        let secrets = take_secrets(&mut info.connection);
+        if !secrets.is_empty() {
+            payload.connections.insert(info.name.clone(), secrets);
+        }
+    }
+    for webhook in settings.alert_webhooks.iter_mut() {
+        if let Some(secret) = webhook.secret.take() {
+            payload.webhooks.insert(webhook.name.clone(), secret);
+        }
+    }
+    //  连接存储加密密钥和主密码只用于本机，导入时使用目标机器的配置，锁定时间同样保留本机的设置
+    settings.connection_conf_encrypt_key.clear();
+    settings.master_password_hash = None;
+
+    let Some(password) = share_password.filter(|p| !p.is_empty()) else {
+        return Ok(None);
+    };
+    let salt: [u8; SALT_LENGTH] = rand::random();
+    let key = aes_util::derive_key_256(password, &salt);
+    let json = serde_json::to_vec(&payload).map_err(|e| e.to_string())?;
+    let data = aes_util::encrypt_gcm_256(&key, &json).map_err(|e| e.to_string())?;
+    Ok(Some(WorkspaceSecrets {
+        salt: BASE64_STANDARD.encode(salt),
+        data: BASE64_STANDARD.encode(data),
+    }))
+}
+
+/// 使用共享密码解密敏感信息并写回连接和设置，密码错误时返回错误
+pub fn restore(
+    connections: &mut [ConnectionInfo],
+    settings: &mut SettingConfig,
+    secrets: &WorkspaceSecrets,
+    share_password: &str,
+) -> Result<(), String> {
+    let salt = BASE64_STANDARD.decode(&secrets.salt).map_err(|e| e.to_string())?;
+    let data = BASE64_STANDARD.decode(&secrets.data).map_err(|e| e.to_string())?;
+    let key = aes_util::derive_key_256(share_password, &salt);
+    let json = aes_util::decrypt_gcm_256(&key, &data).map_err(|_| String::from("Incorrect share password"))?;
+    let mut payload = serde_json::from_slice::<SecretPayload>(&json).map_err(|e| e.to_string())?;
+
+    for info in connections.iter_mut() {
+        if let Some(secrets) = payload.connections.remove(&info.name) {
+            restore_secrets(&mut info.connection, secrets);
+        }
+    }
+    for webhook in settings.alert_webhooks.iter_mut() {
+        if let Some(secret) = payload.webhooks.remove(&webhook.name) {
+            webhook.secret = Some(secret);
+        }
+    }
+    Ok(())
+}
@@ -25,7 +25,9 @@ export enum EventName {
     EDIT_KEY_MONITOR = 'editKeyMonitor',
     KEY_MONITOR_CONFIG_CHANGE = 'keyMonitorChange',
     KEY_MONITOR_EVENT = 'key_monitor',
-    SET_SETTING_ANCHOR = 'setSettingAnchor'
+    SET_SETTING_ANCHOR = 'setSettingAnchor',
+    DEEP_LINK = 'deep_link',
+    OPEN_KEY = 'openKey'
 }
 
 export type KeyMonitorEventType = "Remove" | "Create" | "LeaseChange" | "ValueChange"
@@ -1,10 +1,69 @@
 import {invoke} from "@tauri-apps/api";
-import {Connection, ConnectionInfo, KeyMonitorConfig, SessionData} from "~/common/transport/connection.ts";
-import {Cluster, SnapshotInfo} from "~/common/transport/maintenance.ts";
-import {KeyValue, LeaseInfo, SearchResult} from "~/common/transport/kv.ts";
+import {appWindow} from "@tauri-apps/api/window";
+import {
+    CachedState,
+    Connection,
+    ConnectionInfo,
+    DeepLinkEvent,
+    ErrorCategory,
+    ExternalConnection,
+    KeyAnnotation,
+    KeyBookmarks,
+    KeyMonitorConfig,
+    KeySeparatorInfo,
+    SessionData,
+    SharedAnnotations,
+    SharedAnnotationWriteResult,
+    SshAlgorithms,
+    TunnelDiagnostics
+} from "~/common/transport/connection.ts";
+import {
+    ClockDriftReport,
+    Cluster,
+    MaintenanceRun,
+    MaintenanceSchedule,
+    QueuedOperation,
+    RaftLagReport,
+    ReportDeliveryResult,
+    ReportSchedule,
+    SnapshotInfo
+} from "~/common/transport/maintenance.ts";
+import {
+    AuditStreamConfig,
+    ContentHint,
+    DeletePrefixResult,
+    DeletePreview,
+    KeyStreamBatch,
+    KeyTailConfig,
+    KeyValue,
+    KeyValuePage,
+    KvMacro,
+    LeaseInfo,
+    MacroOp,
+    MacroRunResult,
+    PrefetchResult,
+    PrefixKeyCount,
+    PrefixKeys,
+    SearchResult,
+    TrashEntry,
+    TrashRestoreResult
+} from "~/common/transport/kv.ts";
 import {_emitLocal, _tipError, EventName} from "~/common/events.ts";
 import {LogicErrorInfo} from "~/common/types.ts";
-import {RolePermission, User} from "~/common/transport/user.ts";
+import {AuthImportResult, RolePermission, SessionIdentity, User} from "~/common/transport/user.ts";
+import {PollTask, UsageStats, WorkspaceImportResult} from "~/common/transport/setting.ts";
+import {ActionInfo} from "~/common/transport/action.ts";
+import {MutationPlan} from "~/common/transport/dry_run.ts";
+
+const ERROR_CATEGORY_TITLES: Record<ErrorCategory, string> = {
+    AUTH_FAILED: 'Authentication failed',
+    PERMISSION_DENIED: 'Permission denied',
+    NOT_LEADER: 'No available leader',
+    UNAVAILABLE: 'Cluster unavailable',
+    COMPACTED: 'Revision compacted',
+    TOO_LARGE: 'Message too large',
+    QUOTA_EXCEEDED: 'Database quota exceeded',
+}
 
 export function _handleError(info: LogicErrorInfo) {
     let error = info.e
@@ -13,7 +72,8 @@ export function _handleError(info: LogicErrorInfo) {
     if (typeof error === 'string') {
         _tipError((info.prefix ? info.prefix : "") + info.e)
     } else {
-        _tipError((info.prefix ? info.prefix : "") + error.errMsg)
+        let title = error.category ? `${ERROR_CATEGORY_TITLES[error.category]}: ` : ""
+        _tipError((info.prefix ? info.prefix : "") + title + error.errMsg)
         if (error.errType == "Unauthenticated" && info.session) {
             _emitLocal(EventName.CLOSE_TAB, info.session.id)
         }
@@ -32,6 +92,33 @@ export function _connectTest(connection: Connection): Promise<undefined> {
     return invoke('connect_test', {connection})
 }
 
+export function _respondCredentialRequest(id: string, value?: string): Promise<undefined> {
+    return invoke('respond_credential_request', {id, value})
+}
+
+/**
+ * 设置、修改或清除主密码，已设置时需要提供当前密码
+ */
+export function _setMasterPassword(currentPassword?: string, newPassword?: string): Promise<undefined> {
+    return invoke('set_master_password', {currentPassword, newPassword})
+}
+
+export function _lockSession(): Promise<undefined> {
+    return invoke('lock_session')
+}
+
+export function _unlockSession(password: string): Promise<undefined> {
+    return invoke('unlock_session', {password})
+}
+
+export function _isSessionLocked(): Promise<boolean> {
+    return invoke('is_session_locked')
+}
+
+export function _touchSessionActivity(): Promise<undefined> {
+    return invoke('touch_session_activity')
+}
+
 export function _connect(name: string, connection: Connection): Promise<SessionData> {
     return invoke('connect', {
         name,
@@ -43,6 +130,28 @@ export function _disconnect(sessionId: number): Promise<undefined> {
     return invoke('disconnect', {session: sessionId})
 }
 
+/**
+ * 根据成员列表重新同步连接使用的地址，返回额外使用的成员地址
+ */
+export function _syncSessionEndpoints(sessionId: number): Promise<string[]> {
+    return invoke('sync_session_endpoints', {session: sessionId})
+}
+
+/**
+ * 会话使用的SSH隧道的传输统计及诊断提示，未使用SSH时为空
+ */
+export function _getTunnelDiagnostics(sessionId: number): Promise<TunnelDiagnostics[]> {
+    return invoke('get_tunnel_diagnostics', {session: sessionId})
+}
+
+export function _getSshAlgorithms(): Promise<SshAlgorithms> {
+    return invoke('get_ssh_algorithms')
+}
+
+export function _listSshConfigHosts(): Promise<string[]> {
+    return invoke('list_ssh_config_hosts')
+}
+
 export function _getConnectionList(): Promise<ConnectionInfo[]> {
     return invoke('get_connection_list')
 }
@@ -66,54 +175,322 @@ export function _importConnection(filepath: string): Promise<undefined> {
     return invoke('import_connection', {filepath: filepath})
 }
 
+/**
+ * 将连接编码为分享字符串，不包含密码、私钥及证书内容
+ */
+export function _encodeConnectionShare(name: string): Promise<string> {
+    return invoke('encode_connection_share', {name})
+}
+
+export function _decodeConnectionShare(content: string): Promise<ExternalConnection> {
+    return invoke('decode_connection_share', {content})
+}
+
+export function _exportWorkspace(filepath: string, sharePassword?: string): Promise<undefined> {
+    return invoke('export_workspace', {filepath, sharePassword})
+}
+
+export function _importWorkspace(filepath: string,
+                                 sharePassword: string | undefined,
+                                 overwrite: boolean,
+                                 importSettings: boolean): Promise<WorkspaceImportResult> {
+    return invoke('import_workspace', {filepath, sharePassword, overwrite, importSettings})
+}
+
+export function _getUsageStats(): Promise<UsageStats> {
+    return invoke('get_usage_stats')
+}
+
+export function _resetUsageStats(): Promise<undefined> {
+    return invoke('reset_usage_stats')
+}
+
+export function _listPrefixKeys(sessionId: number, prefix: string): Promise<PrefixKeys> {
+    return invoke('kv_list_prefix_keys', {session: sessionId, prefix})
+}
+
+export function _prefetchChildren(sessionId: number, prefix: string): Promise<PrefetchResult> {
+    return invoke('kv_prefetch_children', {session: sessionId, prefix})
+}
+
+export function _resolveKeySeparator(sessionId: number, refresh?: boolean): Promise<KeySeparatorInfo> {
+    return invoke('kv_resolve_separator', {session: sessionId, refresh})
+}
+
+export function _listActions(sessionId?: number): Promise<ActionInfo[]> {
+    return invoke('list_actions', {session: sessionId})
+}
+
+export function _runAction(action: ActionInfo, params: Record<string, any>, sessionId?: number): Promise<any> {
+    return invoke(action.command, {...params, ...action.args, session: sessionId})
+}
+
 export function _getCluster(sessionId: number): Promise<Cluster> {
     return invoke('get_cluster', {session: sessionId})
 }
 
-export function _defragment(sessionId: number): Promise<undefined> {
-    return invoke('maintenance_defragment', {session: sessionId})
+export function _getReportSchedule(sessionId: number): Promise<ReportSchedule | undefined> {
+    return invoke('get_report_schedule', {session: sessionId})
+}
+
+export function _setReportSchedule(sessionId: number, schedule?: ReportSchedule): Promise<undefined> {
+    return invoke('set_report_schedule', {
+        session: sessionId,
+        schedule
+    })
+}
+
+/**
+ * 立即生成报告并推送到定时报告配置的渠道
+ */
+export function _sendClusterReport(sessionId: number): Promise<ReportDeliveryResult> {
+    return invoke('send_cluster_report', {session: sessionId})
+}
+
+export function _listMaintenanceSchedules(sessionId: number): Promise<MaintenanceSchedule[]> {
+    return invoke('list_maintenance_schedules', {session: sessionId})
+}
+
+export function _setMaintenanceSchedule(sessionId: number, schedule: MaintenanceSchedule): Promise<MaintenanceSchedule> {
+    return invoke('set_maintenance_schedule', {
+        session: sessionId,
+        schedule
+    })
+}
+
+export function _removeMaintenanceSchedule(sessionId: number, id: string): Promise<undefined> {
+    return invoke('remove_maintenance_schedule', {
+        session: sessionId,
+        id
+    })
+}
+
+export function _runMaintenanceSchedule(sessionId: number, id: string): Promise<MaintenanceRun> {
+    return invoke('run_maintenance_schedule', {
+        session: sessionId,
+        id
+    })
+}
+
+export function _listMaintenanceRuns(sessionId: number): Promise<MaintenanceRun[]> {
+    return invoke('list_maintenance_runs', {session: sessionId})
+}
+
+export function _diagnoseClockDrift(sessionId: number): Promise<ClockDriftReport> {
+    return invoke('diagnose_clock_drift', {session: sessionId})
 }
 
-export function _getAllKeys(sessionId: number): Promise<KeyValue[]> {
-    return invoke('kv_get_all_keys', {session: sessionId})
+export function _getRaftLag(sessionId: number): Promise<RaftLagReport> {
+    return invoke('get_raft_lag', {session: sessionId})
 }
 
-export function _getAllKeysPaging(sessionId: number, cursorKey: string, limit: number): Promise<KeyValue[]> {
+/**
+ * timeoutSeconds 为本次操作指定超时时间，不传时使用设置中的请求超时时间
+ */
+export function _defragment(sessionId: number, timeoutSeconds?: number): Promise<undefined> {
+    return invoke('maintenance_defragment', {session: sessionId, timeoutSeconds})
+}
+
+/**
+ * 连接的运维操作队列，第一个为正在执行的操作
+ */
+export function _listMaintenanceQueue(sessionId: number): Promise<QueuedOperation[]> {
+    return invoke('list_maintenance_queue', {session: sessionId})
+}
+
+export function _getAllKeys(sessionId: number, timeoutSeconds?: number): Promise<KeyValue[]> {
+    return invoke('kv_get_all_keys', {session: sessionId, timeoutSeconds})
+}
+
+/**
+ * 分批读取所有key，每批到达时回调 onBatch，全部读取完成后 resolve
+ */
+export async function _streamAllKeys(sessionId: number,
+                                     onBatch: (keys: KeyValue[], batch: KeyStreamBatch) => void,
+                                     batchSize?: number): Promise<number> {
+    const streamId = `${sessionId}-${Date.now()}-${Math.random().toString(36).substring(2)}`
+    let unlisten: (() => void) | undefined
+    return new Promise<number>(async (resolve, reject) => {
+        unlisten = await appWindow.listen<KeyStreamBatch>('kv_keys_batch', e => {
+            const batch = e.payload
+            if (batch.streamId !== streamId) {
+                return
+            }
+            if (batch.keys.length > 0) {
+                onBatch(batch.keys, batch)
+            }
+            if (batch.done) {
+                unlisten?.()
+                batch.errorMsg ? reject(batch.errorMsg) : resolve(batch.total)
+            }
+        })
+        invoke('kv_stream_all_keys', {session: sessionId, streamId, batchSize}).catch(e => {
+            unlisten?.()
+            reject(e)
+        })
+    })
+}
+
+export function _getAllKeysPaging(sessionId: number, cursorKey: string, limit: number, timeoutSeconds?: number): Promise<KeyValue[]> {
     return invoke('kv_get_all_keys_paging', {
         session: sessionId,
         cursorKey,
-        limit
+        limit,
+        timeoutSeconds
     })
 }
 
+/**
+ * 分页获取所有key，首页revision传空，后续分页传入首页返回的revision，使所有分页读取同一版本
+ */
+export function _getAllKeysPagingAt(sessionId: number, cursorKey: string, limit: number, revision?: number): Promise<KeyValuePage> {
+    return invoke('kv_get_all_keys_paging_at', {
+        session: sessionId,
+        cursorKey,
+        limit,
+        revision
+    })
+}
+
+/**
+ * 大值通过自定义协议以二进制读取，避免IPC序列化的开销
+ */
+async function loadLargeValue(kv: KeyValue): Promise<KeyValue> {
+    if (kv.valueHandle) {
+        const response = await fetch(kv.valueHandle.url)
+        if (!response.ok) {
+            throw new Error(`Failed to load value: ${response.status}`)
+        }
+        kv.value = Array.from(new Uint8Array(await response.arrayBuffer()))
+        kv.valueHandle = undefined
+    }
+    return kv
+}
+
 export function _getKV(sessionId: number, key: string): Promise<KeyValue> {
-    return invoke('kv_get', {
+    return invoke<KeyValue>('kv_get', {
         session: sessionId,
         key
+    }).then(loadLargeValue)
+}
+
+/**
+ * 在JSON和YAML之间转换值，转换会丢失信息（注释、锚点等）时返回错误
+ */
+export function _convertValueFormat(content: string, from: 'json' | 'yaml', to: 'json' | 'yaml'): Promise<string> {
+    return invoke('convert_value_format', {
+        content,
+        from,
+        to
     })
 }
 
+/**
+ * 连接未开启内容类型提示时返回空
+ */
+export function _getContentHint(sessionId: number, key: string): Promise<ContentHint | undefined> {
+    return invoke('get_content_hint', {
+        session: sessionId,
+        key
+    })
+}
+
+/**
+ * hint为空时删除记录，连接未开启内容类型提示时返回false
+ */
+export function _setContentHint(sessionId: number, key: string, hint?: ContentHint): Promise<boolean> {
+    return invoke('set_content_hint', {
+        session: sessionId,
+        key,
+        hint
+    })
+}
+
+/**
+ * 生成指向key在指定版本的链接，revision为空时使用key当前的修改版本
+ */
+export function _generateKeyLink(sessionId: number, key: string, revision?: number): Promise<string> {
+    return invoke('generate_key_link', {
+        session: sessionId,
+        key,
+        revision
+    })
+}
+
+/**
+ * 在应用内打开 etcd-workbench:// 链接
+ */
+export function _resolveDeepLink(url: string): Promise<undefined> {
+    return invoke('resolve_deep_link', {url})
+}
+
+/**
+ * 获取尚未处理的深度链接结果，只能获取一次
+ */
+export function _takeDeepLink(): Promise<DeepLinkEvent | null> {
+    return invoke('take_deep_link')
+}
+
 export function _getKVByVersion(sessionId: number, key: string, version: number): Promise<KeyValue> {
-    return invoke('kv_get_by_version', {
+    return invoke<KeyValue>('kv_get_by_version', {
         session: sessionId,
         key,
         version
-    })
+    }).then(loadLargeValue)
 }
 
-export function _searchByPrefix(sessionId: number, prefix: string): Promise<SearchResult> {
+/**
+ * revision不为空时在该版本搜索，与分页加载的key树保持一致
+ */
+export function _searchByPrefix(sessionId: number, prefix: string, revision?: number): Promise<SearchResult> {
     return invoke('kv_get_with_prefix', {
         session: sessionId,
-        prefix
+        prefix,
+        revision
     })
 }
 
-export function _putKV(sessionId: number, key: string, value: number[], ttl?: number): Promise<undefined> {
+/**
+ * renderVariables 为true时先将值中的 ${NAME} 替换为连接变量再写入
+ */
+export function _putKV(sessionId: number, key: string, value: number[], ttl?: number, renderVariables?: boolean): Promise<undefined> {
     return invoke('kv_put', {
         session: sessionId,
         key,
         value,
-        ttl
+        ttl,
+        renderVariables
+    })
+}
+
+export function _renderTemplate(sessionId: number, value: string): Promise<string> {
+    return invoke('kv_render_template', {
+        session: sessionId,
+        value
+    })
+}
+
+export function _templatizeValue(sessionId: number, value: string): Promise<string> {
+    return invoke('kv_templatize_value', {
+        session: sessionId,
+        value
+    })
+}
+
+/**
+ * 预览删除前缀的影响，返回的 token 用于 _deletePrefix 确认删除
+ */
+export function _previewDeletePrefix(sessionId: number, prefix: string): Promise<DeletePreview> {
+    return invoke('kv_delete_prefix_preview', {
+        session: sessionId,
+        prefix
+    })
+}
+
+export function _deletePrefix(sessionId: number, token: string): Promise<DeletePrefixResult> {
+    return invoke('kv_delete_prefix', {
+        session: sessionId,
+        token
     })
 }
 
@@ -133,6 +510,144 @@ export function _deleteKV(sessionId: number, keys: string[]): Promise<number> {
     })
 }
 
+/**
+ * 以试运行方式调用修改类命令，只返回变更计划不执行，args 与直接调用该命令时相同
+ */
+export function _dryRun(command: string, args: Record<string, any>): Promise<MutationPlan> {
+    return invoke(command, {
+        ...args,
+        dryRun: true
+    })
+}
+
+export function _listTrash(sessionId?: number): Promise<TrashEntry[]> {
+    return invoke('kv_list_trash', {
+        session: sessionId
+    })
+}
+
+export function _restoreTrash(sessionId: number, ids: string[], overwrite: boolean): Promise<TrashRestoreResult> {
+    return invoke('kv_restore_trash', {
+        session: sessionId,
+        ids,
+        overwrite
+    })
+}
+
+export function _purgeTrash(ids?: string[]): Promise<number> {
+    return invoke('kv_purge_trash', {
+        ids
+    })
+}
+
+/**
+ * 启动审计任务，返回任务ID，通过 cancel_task 停止
+ */
+export function _startAuditStream(config: AuditStreamConfig): Promise<number> {
+    return invoke('kv_start_audit_stream', {
+        config
+    })
+}
+
+/**
+ * 持续将key的变更追加到本地文件，返回任务ID，通过 cancel_task 停止
+ */
+export function _startKeyTail(config: KeyTailConfig): Promise<number> {
+    return invoke('kv_start_key_tail', {
+        config
+    })
+}
+
+export function _renameKV(sessionId: number, from: string, to: string): Promise<boolean> {
+    return invoke('kv_rename', {
+        session: sessionId,
+        from,
+        to
+    })
+}
+
+export function _startMacroRecording(sessionId: number): Promise<undefined> {
+    return invoke('macro_start_recording', {
+        session: sessionId
+    })
+}
+
+/**
+ * 停止录制并返回录制的操作，通过 _saveMacro 保存
+ */
+export function _stopMacroRecording(sessionId: number): Promise<MacroOp[]> {
+    return invoke('macro_stop_recording', {
+        session: sessionId
+    })
+}
+
+export function _isMacroRecording(sessionId: number): Promise<boolean> {
+    return invoke('macro_is_recording', {
+        session: sessionId
+    })
+}
+
+export function _listMacros(): Promise<KvMacro[]> {
+    return invoke('macro_list')
+}
+
+export function _saveMacro(definition: KvMacro): Promise<undefined> {
+    return invoke('macro_save', {
+        definition
+    })
+}
+
+export function _deleteMacro(name: string): Promise<boolean> {
+    return invoke('macro_delete', {
+        name
+    })
+}
+
+/**
+ * 在目标连接上执行宏，预览变更计划使用 _dryRun('run_macro', {session, name})
+ */
+export function _runMacro(sessionId: number, name: string): Promise<MacroRunResult> {
+    return invoke('run_macro', {
+        session: sessionId,
+        name
+    })
+}
+
+export function _subscribePrefixEvents(sessionId: number, prefix: string): Promise<undefined> {
+    return invoke('subscribe_prefix_events', {
+        session: sessionId,
+        prefix
+    })
+}
+
+export function _unsubscribePrefixEvents(sessionId: number, prefix: string): Promise<undefined> {
+    return invoke('unsubscribe_prefix_events', {
+        session: sessionId,
+        prefix
+    })
+}
+
+/**
+ * 开始统计前缀下的key数量，之后由监听事件实时更新
+ */
+export function _trackPrefixCount(sessionId: number, prefix: string): Promise<undefined> {
+    return invoke('track_prefix_count', {
+        session: sessionId,
+        prefix
+    })
+}
+
+export function _untrackPrefixCount(sessionId: number, prefix: string): Promise<undefined> {
+    return invoke('untrack_prefix_count', {
+        session: sessionId,
+        prefix
+    })
+}
+
+export function _getPrefixCounts(sessionId: number): Promise<PrefixKeyCount[]> {
+    return invoke('get_prefix_counts', {session: sessionId})
+}
+
 export function _getKVHistoryVersions(sessionId: number, key: string, start: number, end: number): Promise<number[]> {
     return invoke('kv_get_history_versions', {
         session: sessionId,
@@ -170,6 +685,12 @@ export function _grantLease(sessionId: number, ttl: number, lease?: string): Pro
     })
 }
 
+export function _getSessionIdentity(sessionId: number): Promise<SessionIdentity> {
+    return invoke('get_session_identity', {
+        session: sessionId,
+    })
+}
+
 export function _getAllUsers(sessionId: number): Promise<User[]> {
     return invoke('user_list', {
         session: sessionId,
@@ -270,6 +791,27 @@ export function _revokeRolePermissions(sessionId: number, role: string, permissi
     })
 }
 
+/**
+ * 导出用户、角色及权限到YAML文件，密码以占位符代替
+ */
+export function _exportAuth(sessionId: number, filepath: string): Promise<undefined> {
+    return invoke('auth_export', {
+        session: sessionId,
+        filepath
+    })
+}
+
+/**
+ * passwords 为新建用户的密码，文件中为占位符的新用户必须提供
+ */
+export function _importAuth(sessionId: number, filepath: string, passwords: Record<string, string>): Promise<AuthImportResult> {
+    return invoke('auth_import', {
+        session: sessionId,
+        filepath,
+        passwords
+    })
+}
+
 export function _maintenanceCreateSnapshotTask(sessionId: number, filepath: string):Promise<SnapshotInfo> {
     return invoke('maintenance_create_snapshot_task', {
         session: sessionId,
@@ -300,6 +842,117 @@ export function _updateKeyCollection(session: number, keyCollection: string[]):
     })
 }
 
+export function _listKeyAnnotations(session: number): Promise<KeyAnnotation[]> {
+    return invoke('list_key_annotations', {
+        session
+    })
+}
+
+export function _setKeyAnnotation(session: number, annotation: KeyAnnotation): Promise<undefined> {
+    return invoke('set_key_annotation', {
+        session,
+        annotation
+    })
+}
+
+export function _removeKeyAnnotation(session: number, key: string, prefix: boolean): Promise<undefined> {
+    return invoke('remove_key_annotation', {
+        session,
+        key,
+        prefix
+    })
+}
+
+export function _searchKeyAnnotations(session: number, query: string): Promise<KeyAnnotation[]> {
+    return invoke('search_key_annotations', {
+        session,
+        query
+    })
+}
+
+export function _exportKeyBookmarks(session: number): Promise<KeyBookmarks> {
+    return invoke('export_key_bookmarks', {
+        session
+    })
+}
+
+export function _importKeyBookmarks(session: number, bookmarks: KeyBookmarks, overwrite: boolean): Promise<KeyBookmarks> {
+    return invoke('import_key_bookmarks', {
+        session,
+        bookmarks,
+        overwrite
+    })
+}
+
+export function _getCachedState(name: string): Promise<CachedState | undefined> {
+    return invoke('get_cached_state', {
+        name
+    })
+}
+
+export function _clearCachedState(name?: string): Promise<number> {
+    return invoke('clear_cached_state', {
+        name
+    })
+}
+
+export function _getConnectionVariables(session: number): Promise<Record<string, string>> {
+    return invoke('get_connection_variables', {
+        session
+    })
+}
+
+export function _setConnectionVariables(session: number, variables: Record<string, string>): Promise<undefined> {
+    return invoke('set_connection_variables', {
+        session,
+        variables
+    })
+}
+
+export function _getPollIntervals(session: number): Promise<Record<PollTask, number>> {
+    return invoke('get_poll_intervals', {
+        session
+    })
+}
+
+export function _setConnectionPollIntervals(session: number, intervals: Partial<Record<PollTask, number>>): Promise<undefined> {
+    return invoke('set_connection_poll_intervals', {
+        session,
+        intervals
+    })
+}
+
+export function _listSharedAnnotations(session: number): Promise<SharedAnnotations> {
+    return invoke('list_shared_annotations', {
+        session
+    })
+}
+
+export function _setSharedAnnotation(session: number, annotation: KeyAnnotation, expectedModRevision: number): Promise<SharedAnnotationWriteResult> {
+    return invoke('set_shared_annotation', {
+        session,
+        annotation,
+        expectedModRevision
+    })
+}
+
+export function _removeSharedAnnotation(session: number, key: string, prefix: boolean, expectedModRevision: number): Promise<boolean> {
+    return invoke('remove_shared_annotation', {
+        session,
+        key,
+        prefix,
+        expectedModRevision
+    })
+}
+
+export function _updateSharedBookmarks(session: number, add: string[], remove: string[]): Promise<string[]> {
+    return invoke('update_shared_bookmarks', {
+        session,
+        add,
+        remove
+    })
+}
+
 export function _setKeyMonitor(session: number, keyMonitor: KeyMonitorConfig): Promise<undefined> {
     return invoke('set_key_monitor', {
         session,
@@ -0,0 +1,23 @@
+export type ActionCapability = 'session' | 'write' | 'root'
+
+export type ActionParamType = 'string' | 'number' | 'boolean' | 'key' | 'file' | 'dir' | 'json'
+
+export interface ActionParam {
+    name: string,
+    paramType: ActionParamType,
+    required: boolean,
+}
+
+export interface ActionInfo {
+    id: string,
+    title: string,
+    category: string,
+    //  执行操作调用的命令
+    command: string,
+    params: ActionParam[],
+    capabilities: ActionCapability[],
+    //  调用命令时固定传入的参数
+    args?: Record<string, any>,
+    available: boolean,
+    unavailableReason?: string,
+}
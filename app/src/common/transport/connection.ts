@@ -1,3 +1,7 @@
+import {PollTask} from "~/common/transport/setting.ts";
+import {KeyValue} from "~/common/transport/kv.ts";
+import {Cluster} from "~/common/transport/maintenance.ts";
+
 export interface ConnectionUser {
     username: string,
     password: string
@@ -31,7 +35,50 @@ export interface ConnectionSsh {
     host: string,
     port: number,
     user: string,
-    identity?: SshIdentity
+    identity?: SshIdentity,
+    //  启用压缩，可加快慢速链路上的传输
+    compression?: boolean,
+    //  优先使用的算法，为空时使用默认顺序
+    ciphers?: string[],
+    kex?: string[],
+    macs?: string[],
+    //  从 ~/.ssh/config 解析主机别名
+    useConfig?: boolean
+}
+
+export interface SshAlgorithms {
+    kex: string[],
+    ciphers: string[],
+    macs: string[]
+}
+
+//  SSH隧道单个方向的传输统计
+export interface TunnelDirectionStats {
+    bytes: number,
+    chunks: number,
+    //  小于最小MSS（536字节）的数据块数量
+    smallChunks: number,
+    //  吞吐量，字节/秒
+    lastBps: number,
+    peakBps: number,
+    //  吞吐量骤降次数
+    collapses: number,
+    //  读取停滞次数
+    stalls: number,
+}
+
+export interface TunnelHint {
+    code: string,
+    hint: string,
+}
+
+export interface TunnelDiagnostics {
+    server: string,
+    forward: string,
+    streams: number,
+    upload: TunnelDirectionStats,
+    download: TunnelDirectionStats,
+    hints: TunnelHint[],
 }
 
 export interface Connection {
@@ -40,7 +87,59 @@ export interface Connection {
     namespace?: string,
     user?: ConnectionUser,
     tls?: ConnectionTls,
-    ssh?: ConnectionSsh
+    ssh?: ConnectionSsh,
+    keyTree?: KeyTreeConfig,
+    //  gRPC消息大小限制（字节），为空时使用默认值
+    maxRecvMessageBytes?: number,
+    maxSendMessageBytes?: number,
+    //  连接后根据成员列表在所有成员间负载均衡
+    syncEndpoints?: boolean,
+    //  连接时提示输入、不保存的凭据
+    promptCredentials?: CredentialKind[],
+    //  在etcd中记录用户为key选择的内容类型，为空时不记录
+    contentHints?: ContentHintConfig,
+}
+
+export type ContentHintStorage = 'sibling' | 'prefix'
+
+export interface ContentHintConfig {
+    storage: ContentHintStorage,
+    //  prefix 模式的存储前缀，为空时使用默认前缀
+    prefix?: string,
+}
+
+export type CredentialKind = 'etcdPassword' | 'sshPassword' | 'sshPassphrase'
+
+export interface CredentialRequest {
+    id: string,
+    connection: string,
+    kind: CredentialKind,
+    //  凭据所属的用户
+    target: string
+}
+
+export type KeyTreeMode = 'separator' | 'flat' | 'mixed'
+
+export interface KeyTreeConfig {
+    mode: KeyTreeMode,
+    separator?: string,
+    candidates: string[]
+}
+
+export interface SeparatorScore {
+    separator: string,
+    coverage: number,
+    avgDepth: number,
+    maxDepth: number,
+    dirs: number,
+    score: number
+}
+
+export interface KeySeparatorInfo {
+    mode: KeyTreeMode,
+    //  为空表示平铺
+    separator: string,
+    scores: SeparatorScore[]
 }
 
 export interface ConnectionInfo {
@@ -48,9 +147,87 @@ export interface ConnectionInfo {
     connection: Connection,
     keyCollection: string[],
     keyMonitorList: KeyMonitorConfig[],
+    annotations?: KeyAnnotation[],
+    //  值模板中 ${NAME} 引用的变量
+    variables?: Record<string, string>,
+    //  覆盖设置中的定时任务间隔秒数
+    pollIntervalSeconds?: Partial<Record<PollTask, number>>,
+    //  实时统计key数量的前缀
+    trackedPrefixes?: string[],
     default?: boolean
 }
 
+//  连接最近一次读取到的集群状态，集群不可达时离线查看
+export interface CachedState {
+    connection: string,
+    //  数据来源集群：host:port{namespace}
+    scope: string,
+    keys?: KeyValue[],
+    keysCachedAt?: number,
+    //  key数量超过缓存上限，只包含部分key
+    keysTruncated: boolean,
+    cluster?: Cluster,
+    clusterCachedAt?: number,
+    //  最近一次正常的健康检查结果
+    health?: {
+        checkTime: number,
+        alarms: { memberId: string, alarmType: number }[],
+    },
+    //  最近查看过的值，按查看时间从旧到新排列
+    values: {
+        kv: KeyValue,
+        cachedAt: number,
+    }[],
+    updatedAt: number,
+    //  连接当前不可用，数据可能已过期
+    stale: boolean,
+}
+
+//  从其他客户端配置或分享字符串解析出的连接
+export interface ExternalConnection {
+    name: string,
+    connection: Connection,
+    //  需要用户补充的内容，如密码、证书
+    warnings: string[],
+}
+
+export interface KeyAnnotation {
+    //  key或前缀（全路径）
+    key: string,
+    //  为true时作用于以key开头的所有key
+    prefix: boolean,
+    note: string,
+    tags: string[],
+    color?: string,
+    updatedAt?: number,
+}
+
+export interface SharedAnnotation extends KeyAnnotation {
+    //  最后修改者，格式为 user@host
+    author: string,
+    //  更新和删除时用于检测冲突
+    modRevision: number,
+}
+
+export interface SharedAnnotations {
+    annotations: SharedAnnotation[],
+    keyCollection: string[],
+    //  由更新版本写入、无法读取的记录数
+    unsupported: number,
+}
+
+export interface SharedAnnotationWriteResult {
+    success: boolean,
+    modRevision: number,
+    //  冲突时为etcd中的最新内容
+    current?: SharedAnnotation,
+}
+
+export interface KeyBookmarks {
+    keyCollection: string[],
+    annotations: KeyAnnotation[],
+}
+
 export const DEFAULT_CONNECTION: ConnectionInfo = {
     name: '',
     connection: {
@@ -75,9 +252,37 @@ export interface SessionData {
     keyMonitorMap?: Record<string, KeyMonitorConfig>
 }
 
+export type RemediationAction = 'compact' | 'defragment' | 'disarmAlarm' | 'useLatestRevision'
+    | 'createLease' | 'enableAuth' | 'managePermission' | 'reconnect' | 'editConnection'
+
+export interface Remediation {
+    code: string,
+    hint: string,
+    actions: RemediationAction[],
+}
+
+//  etcd错误面向用户的分类，值保持稳定
+export type ErrorCategory = 'AUTH_FAILED' | 'PERMISSION_DENIED' | 'NOT_LEADER' | 'UNAVAILABLE'
+    | 'COMPACTED' | 'TOO_LARGE' | 'QUOTA_EXCEEDED'
+
+/**
+ * 深度链接的处理结果，session为空时表示自动连接失败
+ */
+export interface DeepLinkEvent {
+    connection: string,
+    key?: string,
+    revision?: number,
+    session?: number,
+    sessionData?: SessionData,
+    error?: ErrorPayload,
+}
+
 export interface ErrorPayload {
     errType: string,
-    errMsg: string
+    errMsg: string,
+    category?: ErrorCategory,
+    //  常见etcd错误的修复建议
+    remediation?: Remediation,
 }
 
 export interface KeyMonitorConfig {
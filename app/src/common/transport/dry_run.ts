@@ -0,0 +1,22 @@
+export interface PlannedOp {
+    //  put, delete, leaseGrant, leaseRevoke, attachLease, userAdd, roleGrantPermission 等
+    op: string,
+    //  操作的key、lease、用户或角色
+    target: string,
+    //  key当前是否存在，非key操作为空
+    exists?: boolean,
+    modRevision?: number,
+    valueSize?: number,
+    lease?: string,
+    //  受影响的key数量
+    affected?: number,
+}
+
+//  试运行生成的变更计划
+export interface MutationPlan {
+    command: string,
+    txn: boolean,
+    compares: string[],
+    ops: PlannedOp[],
+    revision: number,
+}
@@ -1,15 +1,20 @@
 export enum FormatSource {
     Kubernetes = "kubernetes",
+    Plugin = "plugin",
 }
 
 export enum FormatLanguage {
     Json = "json",
+    Yaml = "yaml",
+    Text = "text",
 }
 
 export interface FormattedValue {
     source: FormatSource,
     language: FormatLanguage,
-    value: string
+    value: string,
+    //  解码该值的插件名
+    plugin?: string,
 }
 
 export interface KeyValue {
@@ -20,7 +25,15 @@ export interface KeyValue {
     value: number[],
     lease: string,
     leaseInfo?: LeaseSimpleInfo,
-    formattedValue?: FormattedValue
+    formattedValue?: FormattedValue,
+    //  值过大时不随结果返回，需要通过该句柄读取
+    valueHandle?: ValueHandle
+}
+
+export interface ValueHandle {
+    token: string,
+    url: string,
+    size: number,
 }
 
 export interface LeaseInfo {
@@ -35,7 +48,180 @@ export interface LeaseSimpleInfo {
     grantedTtl: number,
 }
 
+export interface KeyValuePage {
+    //  本页读取的数据版本，传入后续分页请求可固定读取版本
+    revision: number,
+    //  是否还有更多数据
+    more: boolean,
+    kvs: KeyValue[],
+    //  本页读取到的最后一个key，下一页从该key之后读取，隐藏了部分key时可能不在 kvs 中
+    cursor?: string,
+}
+
 export interface SearchResult {
     count: number,
-    results: KeyValue[]
-}
\ No newline at end of file
+    results: KeyValue[],
+    //  因key不是UTF-8而未放入结果的数量
+    binarySkipped?: number
+}
+
+export interface PrefetchResult {
+    //  连接已建立完整的key索引，无需预取
+    fromIndex: boolean,
+    prefixes: string[],
+    skipped: string[],
+}
+
+export interface ContentHint {
+    //  编辑器语言
+    contentType: string,
+    encoding?: string,
+}
+
+export interface PrefixKeyCount {
+    session: number,
+    prefix: string,
+    count: number,
+    revision: number,
+    //  尚未完成统计时为0
+    updatedAt: number,
+    //  为false时监听中断，数量可能已过时
+    live: boolean,
+}
+
+export interface PrefixKeys {
+    keys: KeyValue[],
+    //  为false时结果被截断
+    complete: boolean,
+    cached: boolean,
+}
+
+export interface KeyStreamBatch {
+    streamId: string,
+    seq: number,
+    revision: number,
+    keys: KeyValue[],
+    //  是否为最后一批，出错时也会以最后一批结束
+    done: boolean,
+    total: number,
+    errorMsg?: string,
+}
+
+export interface TrashEntry {
+    id: string,
+    //  删除时所在的集群地址和命名空间
+    scope: string,
+    connection?: string,
+    key: string,
+    size: number,
+    lease: number,
+    deletedAt: number,
+}
+
+export interface TrashRestoreResult {
+    restored: string[],
+    //  key已存在或不属于当前集群而未恢复的key
+    skipped: string[],
+}
+
+export interface AuditStreamConfig {
+    session: number,
+    //  审计文件的输出目录
+    dir: string,
+    //  是否记录put事件的值
+    includeValues?: boolean,
+}
+
+export interface KeyTailConfig {
+    session: number,
+    key: string,
+    //  输出文件，已存在时追加
+    path: string,
+    //  value：每行一个值；json：每行一个包含版本等信息的JSON
+    format?: 'value' | 'json',
+    //  超过该大小（MB）时轮转，为0时不轮转
+    maxFileSizeMb?: number,
+    maxFiles?: number,
+}
+
+export interface DeletePreviewLease {
+    lease: string,
+    keys: number,
+}
+
+export interface DeletePreview {
+    //  确认删除时使用
+    token: string,
+    prefix: string,
+    revision: number,
+    count: number,
+    //  key数量超过预览读取的上限，totalBytes 和 leases 只统计了读取到的部分，展示时应为 ≥
+    truncated: boolean,
+    //  key和value的总字节数
+    totalBytes: number,
+    leases: DeletePreviewLease[],
+    sampleKeys: string[],
+    expireAt: number,
+}
+
+export interface DeletePrefixResult {
+    deleted: number,
+    revision: number,
+}
+
+//  宏中的一个操作，值为UTF-8文本时保存在 value，否则以base64保存在 rawValue
+export type MacroOp = {
+    op: 'put',
+    key: string,
+    value?: string,
+    rawValue?: string,
+    ttl?: number,
+    //  执行前使用目标连接的变量渲染值
+    renderVariables?: boolean,
+} | {
+    op: 'delete',
+    keys: string[],
+} | {
+    op: 'rename',
+    from: string,
+    to: string,
+}
+
+export interface KvMacro {
+    name: string,
+    ops: MacroOp[],
+    //  录制时使用的连接名
+    source?: string,
+    createdAt?: number,
+}
+
+export interface MacroRunResult {
+    executed: number,
+    //  执行失败的操作序号，之后的操作不再执行
+    failedIndex?: number,
+    error?: string,
+}
+
+export interface WatchKeyEvent {
+    key: string,
+    op: 'put' | 'delete',
+    modRevision: number,
+    version: number,
+    lease: number,
+    value?: string,
+    //  值不是UTF-8时的原始内容（base64）
+    rawValue?: string,
+}
+
+//  watch_events 事件的内容，一个刷新周期内同一key只保留最新的事件
+export interface WatchEventBatch {
+    session: number,
+    prefix: string,
+    events: WatchKeyEvent[],
+    //  被同一key的新事件覆盖的事件数
+    merged: number,
+    //  超出速率限制丢弃的事件数，大于0时需要重新加载前缀
+    dropped: number,
+    totalMerged: number,
+    totalDropped: number,
+}
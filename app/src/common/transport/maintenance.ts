@@ -9,7 +9,10 @@ export interface Cluster {
     memberId: string,
     revision: number,
     members: ClusterMember[],
-    status: ClusterStatus
+    //  当前连接的端点状态，读取失败时为空
+    status?: ClusterStatus,
+    statusError?: string,
+    alarmError?: string
 }
 
 export interface ClusterMember {
@@ -17,7 +20,10 @@ export interface ClusterMember {
     name: string,
     peerUri: string[],
     clientUri: string[],
-    alarmType: number
+    alarmType: number,
+    //  分别连接各成员读取的状态，成员不可用时为空并附带错误信息
+    status?: ClusterStatus,
+    errorMsg?: string
 }
 
 export interface ClusterStatus {
@@ -35,7 +41,16 @@ export interface SnapshotState {
     received: number,
     remain: number,
     errorMsg?: string,
-    finished: boolean
+    finished: boolean,
+    //  快照总字节数，收到首个分片前未知
+    total?: number,
+    //  最近几秒的下载速率（字节/秒）
+    throughput: number,
+    averageThroughput: number,
+    //  按当前速率预计的剩余秒数
+    etaSeconds?: number,
+    //  传输中断后重新请求快照的次数
+    resumes: number
 }
 
 export interface SnapshotInfo {
@@ -48,4 +63,134 @@ export interface SnapshotInfo {
 export interface SnapshotStateEvent {
     id: number,
     state: SnapshotState
-}
\ No newline at end of file
+}
+export interface ReportWebhook {
+    url: string,
+    //  签名密钥，签名方式与告警webhook相同
+    secret?: string,
+}
+
+export interface SmtpConfig {
+    host: string,
+    port: number,
+    security: 'starttls' | 'tls' | 'none',
+    username?: string,
+    password?: string,
+    from: string,
+    to: string[],
+}
+
+export interface ReportSchedule {
+    enabled: boolean,
+    //  每天生成报告的本地时间，格式为 HH:MM
+    time: string,
+    format: 'html' | 'markdown',
+    webhook?: ReportWebhook,
+    smtp?: SmtpConfig,
+    lastRun?: number,
+}
+
+export interface ReportDeliveryResult {
+    generateTime: number,
+    //  推送成功的渠道：webhook、smtp
+    delivered: string[],
+    errors: string[],
+}
+
+export type MaintenanceAction = {
+    type: 'compact',
+    //  保留最近的版本数，与保留小时数同时指定时保留更多的一方
+    retainRevisions?: number,
+    retainHours?: number,
+} | {
+    type: 'defragment',
+    //  是否在所有follower之后整理leader
+    includeLeader: boolean,
+}
+
+export interface MaintenanceSchedule {
+    id: string,
+    name: string,
+    //  五段式cron表达式，按本地时间执行
+    cron: string,
+    action: MaintenanceAction,
+    enabled: boolean,
+    nextRun?: number,
+}
+
+export interface MaintenanceRun {
+    scheduleId: string,
+    name: string,
+    startTime: number,
+    endTime: number,
+    success: boolean,
+    message: string,
+}
+
+export interface MemberClock {
+    id: string,
+    name: string,
+    endpoint?: string,
+    //  成员时钟相对本机的偏差（毫秒），正数表示成员时钟较快
+    offsetMillis?: number,
+    uncertaintyMillis?: number,
+    raftTerm?: number,
+    leader?: string,
+    errorMsg?: string,
+}
+
+export interface ClockDriftReport {
+    checkTime: number,
+    members: MemberClock[],
+    maxDriftMillis?: number,
+    //  本会话期间观察到的任期变化次数及观察时长
+    termChanges: number,
+    observedMillis: number,
+    risk: 'low' | 'medium' | 'high',
+    //  不为空时展示警告
+    warnings: string[],
+}
+
+export interface RaftLagSample {
+    time: number,
+    leader: string,
+    leaderAppliedIndex: number,
+    //  成员ID -> 落后的已应用日志条数
+    lags: Record<string, number>,
+}
+
+export interface FollowerLag {
+    id: string,
+    name: string,
+    appliedIndex?: number,
+    lag?: number,
+    avgLag?: number,
+    maxLag: number,
+    //  持续明显落后
+    slow: boolean,
+    errorMsg?: string,
+}
+
+export interface RaftLagReport {
+    checkTime: number,
+    leader?: string,
+    leaderAppliedIndex?: number,
+    followers: FollowerLag[],
+    samples: RaftLagSample[],
+    warnings: string[],
+}
+
+export type MaintenanceOperation = 'defragment' | 'snapshot' | 'compact'
+
+export interface QueuedOperation {
+    id: number,
+    operation: MaintenanceOperation,
+    enqueuedAt: number,
+}
+
+export interface MaintenanceQueueEvent {
+    session: number,
+    operation: MaintenanceOperation,
+    //  排在前面的操作数量
+    ahead: number,
+}
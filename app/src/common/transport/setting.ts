@@ -23,6 +23,8 @@ export interface SettingConfig {
 
     //  自动下载更新
     autoUpdate: boolean,
+    //  允许记录匿名使用统计，默认关闭
+    telemetryEnabled: boolean,
 
     //  使用 ctrl + w 关闭连接tab
     closeTabUseCtrlW: boolean,
@@ -32,10 +34,39 @@ export interface SettingConfig {
     requestTimeoutSeconds: number | string,
     //  SSH连接超时秒数
     sshConnectTimeoutSeconds: number | string,
+    //  SSH会话复用秒数，连接关闭后已认证的会话保留该时间供新连接复用，为0时不复用
+    sshSessionReuseSeconds: number | string,
+    //  SSH转发的缓冲区大小，单位KB
+    sshProxyBufferKb: number | string,
+    //  SSH通道的流控窗口，单位KB，本地读取慢时最多积压此大小的数据，超过后断开该转发连接
+    sshChannelWindowKb: number | string,
     //  连接存储加密密钥，bytes字符长度必须为16位
     connectionConfEncryptKey: string,
+    //  回收站保留天数，为0时不记录删除的key
+    trashRetentionDays: number | string,
+    //  回收站最大占用空间，单位MB
+    trashMaxSizeMb: number | string,
+    //  监听事件推送的刷新间隔毫秒数，周期内同一key只保留最新的事件
+    watchFlushIntervalMillis: number | string,
+    //  每个监听每秒最多推送的事件数
+    watchMaxEventsPerSecond: number | string,
+    //  后台定时任务的间隔秒数，未配置的任务使用默认间隔
+    pollIntervalSeconds?: Partial<Record<PollTask, number>>,
+    //  窗口失去焦点时放慢定时任务
+    adaptivePolling: boolean,
+    //  失去焦点时间隔放大的倍数
+    unfocusedPollFactor: number | string,
+    //  本地保存最近读取的集群状态，集群不可达时离线查看
+    offlineCache: boolean,
+    //  超过此分钟数未操作时锁定会话，为0时不锁定
+    lockIdleMinutes: number | string,
+    //  主密码的哈希，只能通过 set_master_password 修改
+    masterPasswordHash?: string,
 }
 
+//  定时向前端推送数据的后台任务
+export type PollTask = 'healthCheck' | 'latencySample' | 'hotKeyReport'
+
 export interface SettingWindowState {
     mainWindowWidth: number,
     mainWindowHeight: number,
@@ -53,10 +84,22 @@ export const DEFAULT_SETTING_CONFIG: SettingConfig = {
     kvCheckFormatBeforeSave: true,
     closeTabUseCtrlW: true,
     autoUpdate: true,
+    telemetryEnabled: false,
     connectTimeoutSeconds: 5,
     requestTimeoutSeconds: 15,
     sshConnectTimeoutSeconds: 10,
-    connectionConfEncryptKey: 'workbench*#)&%.$'
+    sshSessionReuseSeconds: 0,
+    sshProxyBufferKb: 8,
+    sshChannelWindowKb: 2048,
+    connectionConfEncryptKey: 'workbench*#)&%.$',
+    trashRetentionDays: 7,
+    trashMaxSizeMb: 64,
+    watchFlushIntervalMillis: 250,
+    watchMaxEventsPerSecond: 1000,
+    adaptivePolling: true,
+    unfocusedPollFactor: 4,
+    offlineCache: true,
+    lockIdleMinutes: 0,
 }
 
 export interface UpdateInfo {
@@ -80,4 +123,28 @@ export interface GlobalStoreConfig {
 export const DEFAULT_GLOBAL_STORE: GlobalStoreConfig = {
     fileFormatLog: [],
     fileFormatLogMap: {}
-}
\ No newline at end of file
+}
+
+export interface WorkspaceImportResult {
+    imported: string[],
+    skipped: string[],
+    settingsImported: boolean,
+    secretsRestored: boolean,
+}
+
+export interface DailyUsage {
+    commands: number,
+    errors: number,
+}
+
+export interface UsageStats {
+    //  开始统计的时间（毫秒时间戳）
+    since: number,
+    totalCommands: number,
+    totalErrors: number,
+    errorRate: number,
+    commands: Record<string, number>,
+    errors: Record<string, number>,
+    //  key为 yyyy-MM-dd
+    daily: Record<string, DailyUsage>,
+}
@@ -15,4 +15,44 @@ export interface RolePermission {
     permType: RolePermType,
     prefix: boolean,
     allKeys: boolean
-}
\ No newline at end of file
+}
+export type AuthTokenType = 'simple' | 'jwt'
+
+export interface SessionIdentity {
+    user?: string,
+    roles: string[],
+    root: boolean,
+    authEnabled: boolean,
+    tokenType?: AuthTokenType,
+    //  JWT token的过期时间（毫秒时间戳）
+    tokenExpiresAt?: number,
+    reauthCount: number,
+    lastReauthTime?: number
+}
+
+export interface ReauthEvent {
+    session: number,
+    user?: string,
+    success: boolean,
+    time: number,
+    errorMsg?: string
+}
+
+export interface AuthPlanStep {
+    action: 'addRole' | 'deleteRole' | 'grantPermission' | 'revokePermission' | 'grantRole' | 'revokeRole',
+    role: string,
+    user?: string,
+    permission?: RolePermission
+}
+
+export interface AuthApplyResult {
+    steps: AuthPlanStep[],
+    applied: number,
+    rolledBack: boolean,
+    errorMsg?: string
+}
+
+export interface AuthImportResult {
+    createdUsers: string[],
+    apply: AuthApplyResult
+}
@@ -1,4 +1,4 @@
-import {ErrorPayload, HashAlgorithm, SessionData} from "~/common/transport/connection.ts";
+import {ContentHintStorage, CredentialKind, ErrorPayload, HashAlgorithm, SessionData} from "~/common/transport/connection.ts";
 
 export type EditorHighlightLanguage = EditorSupportedHighlightLanguage | EditorNotSupportedHighlightLanguage
 
@@ -64,7 +64,12 @@ export type ConnectionForm = {
     namespace: string,
     user: ConnectionUserForm,
     tls: ConnectionTlsForm,
-    ssh: ConnectionSshForm
+    ssh: ConnectionSshForm,
+    //  连接时提示输入、不保存的凭据
+    promptCredentials: CredentialKind[],
+    //  内容类型提示的存储位置，off为不记录
+    contentHints: 'off' | ContentHintStorage,
+    contentHintPrefix: string
 }
 
 export type ConnectionUserForm = {
@@ -104,6 +109,11 @@ export type ConnectionSshForm = {
     port: string,
     user: string,
     identity: ConnectionSshIdentity,
+    compression: boolean,
+    ciphers: string[],
+    kex: string[],
+    macs: string[],
+    useConfig: boolean,
 }
 
 export const DefaultConnection: ConnectionForm = {
@@ -151,8 +161,16 @@ export const DefaultConnection: ConnectionForm = {
                 passphrase: '',
                 hashAlgorithm: ''
             }
-        }
-    }
+        },
+        compression: false,
+        ciphers: [],
+        kex: [],
+        macs: [],
+        useConfig: false
+    },
+    promptCredentials: [],
+    contentHints: 'off',
+    contentHintPrefix: ''
 }
 
 export type EditorConfig = {
@@ -2,7 +2,7 @@ import {open} from '@tauri-apps/api/shell'
 import {_alertError} from "~/common/events.ts";
 import {EditorHighlightLanguage} from "~/common/types.ts";
 import {_useGlobalStore} from "~/common/store.ts";
-import {FormattedValue} from "~/common/transport/kv.ts";
+import {FormatSource, FormattedValue} from "~/common/transport/kv.ts";
 
 const TEXT_DECODER = new TextDecoder();
 const TEXT_ENCODER = new TextEncoder();
@@ -195,6 +195,9 @@ export function _tryParseEditorLanguage(
     namespace?: string
 ): EditorHighlightLanguage {
     if (formattedValue) {
+        if (formattedValue.source == FormatSource.Plugin) {
+            return formattedValue.language as EditorHighlightLanguage
+        }
         return formattedValue.source as EditorHighlightLanguage
     }
     //  先从记录中读取用户选择的格式